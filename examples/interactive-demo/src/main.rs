@@ -87,6 +87,8 @@ pub fn read_char() -> std::io::Result<char> {
             kind: KeyEventKind::Press,
             modifiers: _,
             state: _,
+            extended_modifiers: _,
+            physical_key: _,
         })) = event::read()
         {
             return Ok(c);
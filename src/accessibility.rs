@@ -0,0 +1,163 @@
+//! # Accessibility
+//!
+//! Screen readers read the terminal line by line and announce what changes. Colors don't
+//! reach their users, and blinking cursors, animations and frequently repainted screens make
+//! them announce the same content over and over.
+//!
+//! The screen reader mode is a single switch, which applications can expose as an option or
+//! an environment variable. While it's enabled:
+//!
+//! - Colors aren't written, the color commands like
+//!   [`SetForegroundColor`](../style/struct.SetForegroundColor.html) do nothing.
+//! - The cursor and text don't blink: [`EnableBlinking`](../cursor/struct.EnableBlinking.html)
+//!   and the blinking attributes do nothing, and the blinking
+//!   [`SetCursorStyle`](../cursor/enum.SetCursorStyle.html)s set their steady counterparts.
+//! - Intervals added with [`event::add_interval`](../event/fn.add_interval.html) tick at most
+//!   once every [`MIN_REPAINT_INTERVAL`](constant.MIN_REPAINT_INTERVAL.html), to slow down
+//!   animations.
+//!
+//! [`Announce`](struct.Announce.html) writes a plain line to tell something to the user,
+//! regardless of the mode.
+//!
+//! ```no_run
+//! use std::io;
+//! use crossterm::{accessibility::{self, Announce}, execute};
+//!
+//! fn main() -> io::Result<()> {
+//!     accessibility::set_screen_reader_mode(std::env::var_os("MY_APP_SCREEN_READER").is_some());
+//!
+//!     execute!(io::stdout(), Announce("3 files copied"))
+//! }
+//! ```
+
+use std::{
+    fmt::{self, Display},
+    sync::atomic::{AtomicBool, Ordering},
+    time::Duration,
+};
+
+use crate::{style::NoStyle, Command};
+
+/// The shortest interval of [`event::add_interval`](../event/fn.add_interval.html) in the
+/// screen reader mode.
+pub const MIN_REPAINT_INTERVAL: Duration = Duration::from_secs(1);
+
+static SCREEN_READER_MODE: AtomicBool = AtomicBool::new(false);
+
+#[cfg(test)]
+thread_local! {
+    // Overrides `SCREEN_READER_MODE` on this thread, so tests don't affect each other.
+    static SCREEN_READER_OVERRIDE: std::cell::Cell<Option<bool>> = std::cell::Cell::new(None);
+}
+
+/// Enables or disables the screen reader mode, see the [module documentation](index.html).
+pub fn set_screen_reader_mode(enabled: bool) {
+    SCREEN_READER_MODE.store(enabled, Ordering::Relaxed);
+}
+
+/// Returns whether the screen reader mode is enabled.
+pub fn screen_reader_mode() -> bool {
+    #[cfg(test)]
+    if let Some(enabled) = SCREEN_READER_OVERRIDE.with(|cell| cell.get()) {
+        return enabled;
+    }
+    SCREEN_READER_MODE.load(Ordering::Relaxed)
+}
+
+/// Runs `f` with the screen reader mode enabled or disabled on this thread.
+#[cfg(test)]
+pub(crate) fn with_screen_reader_mode<T>(enabled: bool, f: impl FnOnce() -> T) -> T {
+    struct Restore(Option<bool>);
+
+    impl Drop for Restore {
+        fn drop(&mut self) {
+            SCREEN_READER_OVERRIDE.with(|cell| cell.set(self.0));
+        }
+    }
+
+    let _restore = Restore(SCREEN_READER_OVERRIDE.with(|cell| cell.replace(Some(enabled))));
+    f()
+}
+
+/// A command that writes `text` as a plain line for screen readers.
+///
+/// Escape sequences in the text, e.g. from [`StyledContent`](../style/struct.StyledContent.html),
+/// are dropped, and the line ends with `\r\n`, so it also starts the next line in raw mode.
+///
+/// # Notes
+///
+/// Commands must be executed/queued for execution otherwise they do nothing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Announce<T: Display>(pub T);
+
+impl<T: Display> Command for Announce<T> {
+    fn write_ansi(&self, f: &mut impl fmt::Write) -> fmt::Result {
+        write!(f, "{}\r\n", NoStyle(&self.0))
+    }
+
+    #[cfg(windows)]
+    fn execute_winapi(&self) -> std::io::Result<()> {
+        panic!("tried to execute Announce command using WinAPI, use ANSI instead");
+    }
+
+    #[cfg(windows)]
+    fn is_ansi_code_supported(&self) -> bool {
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        cursor::{EnableBlinking, SetCursorStyle},
+        style::{
+            set_color_override, Attribute, Color, SetAttribute, SetBackgroundColor,
+            SetForegroundColor, Stylize,
+        },
+        Command,
+    };
+
+    use super::{with_screen_reader_mode, Announce};
+
+    #[test]
+    fn test_announce_writes_plain_lines() {
+        assert_eq!(
+            Announce(format!("{} files copied", "3".bold().red())).ansi_string(),
+            "3 files copied\r\n"
+        );
+    }
+
+    #[test]
+    fn test_screen_reader_mode_suppresses_colors() {
+        let previous = set_color_override(Some(true));
+        with_screen_reader_mode(true, || {
+            assert_eq!(SetForegroundColor(Color::Red).ansi_string(), "");
+            assert_eq!(SetBackgroundColor(Color::Blue).ansi_string(), "");
+        });
+        with_screen_reader_mode(false, || {
+            assert_ne!(SetForegroundColor(Color::Red).ansi_string(), "");
+        });
+        set_color_override(previous);
+    }
+
+    #[test]
+    fn test_screen_reader_mode_suppresses_blinking() {
+        with_screen_reader_mode(true, || {
+            assert_eq!(EnableBlinking.ansi_string(), "");
+            assert_eq!(SetAttribute(Attribute::SlowBlink).ansi_string(), "");
+            assert_eq!(SetAttribute(Attribute::RapidBlink).ansi_string(), "");
+            assert_eq!(
+                SetCursorStyle::BlinkingBar.ansi_string(),
+                SetCursorStyle::SteadyBar.ansi_string()
+            );
+            assert_eq!(
+                SetCursorStyle::BlinkingBlock.ansi_string(),
+                SetCursorStyle::SteadyBlock.ansi_string()
+            );
+        });
+        with_screen_reader_mode(false, || {
+            assert!(EnableBlinking.ansi_string().starts_with("\x1b[?12h"));
+            assert_eq!(SetCursorStyle::BlinkingBar.ansi_string(), "\x1b[5 q");
+        });
+    }
+}
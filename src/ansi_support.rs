@@ -1,8 +1,60 @@
-use std::sync::atomic::{AtomicBool, Ordering};
+use std::{
+    os::raw::c_int,
+    sync::atomic::{AtomicBool, AtomicU8, Ordering},
+};
 
 use crossterm_winapi::{ConsoleMode, Handle};
 use parking_lot::Once;
-use winapi::um::wincon::ENABLE_VIRTUAL_TERMINAL_PROCESSING;
+use winapi::{
+    shared::{minwindef::DWORD, windef::HWND},
+    um::{
+        fileapi::{GetFileType, FILE_NAME_INFO},
+        minwinbase::FileNameInfo,
+        winbase::{GetFileInformationByHandleEx, FILE_TYPE_PIPE},
+        wincon::{GetConsoleWindow, ENABLE_VIRTUAL_TERMINAL_PROCESSING},
+        winnt::HANDLE,
+        winuser::GetClassNameW,
+    },
+};
+
+/// The kind of console host the current process is attached to.
+///
+/// The console host decides whether output should be written as VT sequences or through the
+/// Console API. See [`console_kind`](fn.console_kind.html).
+#[derive(Debug, PartialOrd, PartialEq, Eq, Clone, Copy, Hash)]
+pub enum ConsoleKind {
+    /// A classic console window (conhost). Depending on the Windows version, it may or may not
+    /// understand VT sequences.
+    Classic,
+    /// A pseudo console (ConPTY), as used by Windows Terminal, VS Code and most modern
+    /// terminal emulators. ConPTY always understands VT sequences.
+    ConPty,
+    /// A MSYS2 or Cygwin pty, as used by mintty (Git Bash). The standard handles are named pipes,
+    /// so the Console API can't be used and VT sequences must be written instead.
+    MsysPty,
+    /// The process isn't attached to a console, e.g. the output is redirected to a file.
+    Unknown,
+}
+
+impl ConsoleKind {
+    fn from_u8(value: u8) -> ConsoleKind {
+        match value {
+            1 => ConsoleKind::Classic,
+            2 => ConsoleKind::ConPty,
+            3 => ConsoleKind::MsysPty,
+            _ => ConsoleKind::Unknown,
+        }
+    }
+
+    fn as_u8(self) -> u8 {
+        match self {
+            ConsoleKind::Classic => 1,
+            ConsoleKind::ConPty => 2,
+            ConsoleKind::MsysPty => 3,
+            ConsoleKind::Unknown => 4,
+        }
+    }
+}
 
 /// Enable virtual terminal processing.
 ///
@@ -26,21 +78,171 @@ fn enable_vt_processing() -> std::io::Result<()> {
     Ok(())
 }
 
+/// Checks if the standard output or input handle is one end of a MSYS2/Cygwin pty, the other
+/// one may be redirected.
+fn is_msys_pty() -> bool {
+    match Handle::output_handle() {
+        Ok(handle) if is_msys_pty_handle(*handle) => true,
+        _ => is_msys_pty_input(),
+    }
+}
+
+/// Checks if the standard input handle is one end of a MSYS2/Cygwin pty, which the input is read
+/// from and the terminal modes are set on.
+pub(crate) fn is_msys_pty_input() -> bool {
+    match Handle::input_handle() {
+        Ok(handle) => is_msys_pty_handle(*handle),
+        Err(_) => false,
+    }
+}
+
+/// Checks if `handle` is one end of a MSYS2/Cygwin pty.
+///
+/// These ptys are implemented with named pipes called
+/// `\{msys,cygwin}-XXXXXXXXXXXXXXXX-ptyN-{from,to}-master`.
+pub(crate) fn is_msys_pty_handle(handle: HANDLE) -> bool {
+    if unsafe { GetFileType(handle) } != FILE_TYPE_PIPE {
+        return false;
+    }
+
+    // FILE_NAME_INFO is a DWORD length followed by the UTF-16 name, use u32 storage to keep the
+    // buffer aligned.
+    let mut buffer = [0u32; 256];
+    let size = (buffer.len() * std::mem::size_of::<u32>()) as DWORD;
+    if unsafe {
+        GetFileInformationByHandleEx(handle, FileNameInfo, buffer.as_mut_ptr().cast(), size)
+    } == 0
+    {
+        return false;
+    }
+
+    let info = buffer.as_ptr() as *const FILE_NAME_INFO;
+    let name = unsafe {
+        let len = ((*info).FileNameLength as usize / 2)
+            .min((size as usize - std::mem::size_of::<DWORD>()) / 2);
+        std::slice::from_raw_parts((*info).FileName.as_ptr(), len)
+    };
+    let name = String::from_utf16_lossy(name);
+
+    (name.contains("msys-") || name.contains("cygwin-"))
+        && name.contains("-pty")
+        && (name.ends_with("-from-master") || name.ends_with("-to-master"))
+}
+
+fn detect_console_kind() -> ConsoleKind {
+    if is_msys_pty() {
+        return ConsoleKind::MsysPty;
+    }
+
+    let attached = Handle::current_out_handle()
+        .and_then(|handle| ConsoleMode::from(handle).mode())
+        .is_ok();
+    if !attached {
+        return ConsoleKind::Unknown;
+    }
+
+    // A pseudo console has a hidden window of its own class, a classic console a visible one or,
+    // when it was started without a window, none. Environment variables like `WT_SESSION` are
+    // inherited by processes started in a new console, so they aren't trusted.
+    let window = unsafe { GetConsoleWindow() };
+    if !window.is_null() && window_class(window).as_deref() == Some(PSEUDO_CONSOLE_WINDOW_CLASS) {
+        ConsoleKind::ConPty
+    } else {
+        ConsoleKind::Classic
+    }
+}
+
+/// The class of the window ConPTY creates for the pseudo console.
+const PSEUDO_CONSOLE_WINDOW_CLASS: &str = "PseudoConsoleWindow";
+
+/// Returns the class name of `window`.
+fn window_class(window: HWND) -> Option<String> {
+    let mut name = [0u16; 64];
+    let len = unsafe { GetClassNameW(window, name.as_mut_ptr(), name.len() as c_int) };
+    if len <= 0 {
+        return None;
+    }
+    Some(String::from_utf16_lossy(&name[..len as usize]))
+}
+
+static CONSOLE_KIND: AtomicU8 = AtomicU8::new(0);
+static CONSOLE_KIND_INITIALIZER: Once = Once::new();
+
+/// Returns the kind of console host the current process is attached to.
+///
+/// The detection is done once and cached for the lifetime of the process.
+pub fn console_kind() -> ConsoleKind {
+    CONSOLE_KIND_INITIALIZER.call_once(|| {
+        CONSOLE_KIND.store(detect_console_kind().as_u8(), Ordering::SeqCst);
+    });
+
+    ConsoleKind::from_u8(CONSOLE_KIND.load(Ordering::SeqCst))
+}
+
+const ANSI_NOT_FORCED: u8 = 0;
+const ANSI_FORCED_ON: u8 = 1;
+const ANSI_FORCED_OFF: u8 = 2;
+
+static ANSI_OVERRIDE: AtomicU8 = AtomicU8::new(ANSI_NOT_FORCED);
 static SUPPORTS_ANSI_ESCAPE_CODES: AtomicBool = AtomicBool::new(false);
 static INITIALIZER: Once = Once::new();
 
 /// Checks if the current terminal supports ANSI escape sequences
+///
+/// The answer depends on the [`ConsoleKind`](enum.ConsoleKind.html), unless it was overridden
+/// with [`force_ansi`](fn.force_ansi.html).
 pub fn supports_ansi() -> bool {
+    match ANSI_OVERRIDE.load(Ordering::SeqCst) {
+        ANSI_FORCED_ON => return true,
+        ANSI_FORCED_OFF => return false,
+        _ => {}
+    }
+
     INITIALIZER.call_once(|| {
-        // Some terminals on Windows like GitBash can't use WinAPI calls directly
-        // so when we try to enable the ANSI-flag for Windows this won't work.
-        // Because of that we should check first if the TERM-variable is set
-        // and see if the current terminal is a terminal who does support ANSI.
-        let supported = enable_vt_processing().is_ok()
-            || std::env::var("TERM").map_or(false, |term| term != "dumb");
+        let supported = match console_kind() {
+            // ConPTY parses VT sequences regardless, if the flag can't be enabled the console
+            // isn't a pseudo console after all.
+            ConsoleKind::ConPty => enable_vt_processing().is_ok(),
+            // The Console API calls fail on a pty, the terminal emulator on the other side
+            // is the one interpreting the output.
+            ConsoleKind::MsysPty => true,
+            // Some terminals on Windows like GitBash can't use WinAPI calls directly
+            // so when we try to enable the ANSI-flag for Windows this won't work.
+            // Because of that we should check first if the TERM-variable is set
+            // and see if the current terminal is a terminal who does support ANSI.
+            #[cfg(not(feature = "legacy-windows"))]
+            ConsoleKind::Classic | ConsoleKind::Unknown => {
+                enable_vt_processing().is_ok()
+                    || std::env::var("TERM").map_or(false, |term| term != "dumb")
+            }
+            // Don't touch the VT processing flag at all, use the Console API only.
+            #[cfg(feature = "legacy-windows")]
+            ConsoleKind::Classic | ConsoleKind::Unknown => false,
+        };
 
         SUPPORTS_ANSI_ESCAPE_CODES.store(supported, Ordering::SeqCst);
     });
 
     SUPPORTS_ANSI_ESCAPE_CODES.load(Ordering::SeqCst)
 }
+
+/// Overrides the automatic choice between ANSI escape sequences and the Console API.
+///
+/// Passing `true` makes every command write ANSI escape sequences, passing `false` makes them
+/// use the Console API where one is available. This is useful for applications that know more
+/// about their environment than crossterm's detection does.
+///
+/// The detection recognizes a pseudo console (ConPTY) by the class of its hidden console window,
+/// and uses ANSI escape sequences there and in MSYS2/Cygwin ptys. In a classic console, ANSI
+/// escape sequences are used if the VT processing flag can be enabled, or `TERM` names a
+/// terminal, and the Console API otherwise. With the `legacy-windows` feature, classic consoles
+/// always use the Console API.
+pub fn force_ansi(enabled: bool) {
+    if enabled {
+        // Best effort, the console may not need or support the flag.
+        let _ = enable_vt_processing();
+        ANSI_OVERRIDE.store(ANSI_FORCED_ON, Ordering::SeqCst);
+    } else {
+        ANSI_OVERRIDE.store(ANSI_FORCED_OFF, Ordering::SeqCst);
+    }
+}
@@ -0,0 +1,513 @@
+//! # Buffer
+//!
+//! The `buffer` module provides a grid of styled cells which is drawn to the terminal by only
+//! printing the cells that changed since the last time, without adopting a full TUI framework.
+//!
+//! A [`Screen`](struct.Screen.html) keeps the [`Buffer`](struct.Buffer.html) drawn last and the
+//! one being drawn. The application prints into the current buffer and calls
+//! [`Screen::flush`](struct.Screen.html#method.flush), which compares both buffers and emits the
+//! cursor movements and styles needed for the changed cells only:
+//!
+//! ```no_run
+//! use std::io;
+//! use crossterm::{buffer::Screen, style::{ContentStyle, Stylize}, terminal};
+//!
+//! fn main() -> io::Result<()> {
+//!     let (width, height) = terminal::size()?;
+//!     let mut screen = Screen::new(width, height);
+//!
+//!     for i in 0..10 {
+//!         let buffer = screen.buffer_mut();
+//!         buffer.clear();
+//!         buffer.print(0, 0, "Counting:", ContentStyle::new());
+//!         buffer.print(10, 0, &i.to_string(), ContentStyle::new().bold());
+//!
+//!         // only the number is printed again
+//!         screen.flush(&mut io::stdout())?;
+//!     }
+//!     Ok(())
+//! }
+//! ```
+//!
+//! The buffer keeps track of the cells which were written since the last flush, its damage, so
+//! that only those are compared with the buffer on the screen. Runs of changed cells with the
+//! same style are printed at once. Rows are compared by their hashes first, so unchanged rows
+//! are skipped quickly on large screens. If rows moved up or down, e.g. when a log grows, the
+//! screen scrolls them with a scroll region instead of printing them again.
+//!
+//! Popups, toasts and the like can be drawn on [layers](struct.Layer.html) on top of the buffer,
+//! which are composed before the comparison, so closing a popup just draws the cells below it
+//! again.
+//!
+//! A [`Frame`](struct.Frame.html) schedules the flushes, it coalesces the changes of several
+//! events into one frame, caps the frame rate and wraps every frame in a synchronized update.
+//!
+//! A [`Snapshot`](struct.Snapshot.html) of the screen can be saved as bytes and restored later,
+//! e.g. to draw the same user interface again after running a shell. A buffer can also be
+//! exported as [plain text](struct.Buffer.html#method.to_text) or
+//! [HTML](struct.Buffer.html#method.to_html), e.g. for bug reports.
+//!
+//! Every `char` takes one cell, a symbol made of several chars, e.g. with combining marks, can be
+//! put into a cell with [`Buffer::set`](struct.Buffer.html#method.set).
+
+use std::{
+    collections::hash_map::DefaultHasher,
+    fmt::Display,
+    hash::{Hash, Hasher},
+    ops::Range,
+};
+
+use crate::style::{ContentStyle, StyledContent};
+
+pub use self::{
+    diff::{flush, Diff},
+    frame::Frame,
+    layer::{Layer, LayerId},
+    screen::Screen,
+    snapshot::Snapshot,
+};
+
+mod diff;
+mod export;
+mod frame;
+mod layer;
+mod screen;
+mod scroll;
+mod snapshot;
+
+/// A cell of a [`Buffer`], a symbol and its style.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Cell {
+    /// The symbol printed in the cell.
+    pub symbol: String,
+    /// The colors and attributes of the symbol.
+    pub style: ContentStyle,
+}
+
+impl Cell {
+    /// Creates a cell with `symbol` printed in `style`.
+    pub fn new(symbol: impl Into<String>, style: ContentStyle) -> Cell {
+        Cell {
+            symbol: symbol.into(),
+            style,
+        }
+    }
+
+    fn set_char(&mut self, symbol: char, style: ContentStyle) {
+        self.symbol.clear();
+        self.symbol.push(symbol);
+        self.style = style;
+    }
+}
+
+impl Default for Cell {
+    /// An unstyled space.
+    fn default() -> Self {
+        Cell::new(" ", ContentStyle::default())
+    }
+}
+
+/// A grid of [`Cell`]s, row by row.
+///
+/// Positions are (column, row), starting at `0`, like the cursor positions of the
+/// [`cursor`](../cursor/index.html) module. Writes outside of the buffer are ignored.
+///
+/// Every write marks the cells as damaged until [`Buffer::reset_damage`] is called, a new buffer is
+/// damaged everywhere. Two buffers are equal if they have the same cells, regardless of the damage.
+#[derive(Debug, Clone, Default)]
+pub struct Buffer {
+    width: u16,
+    height: u16,
+    cells: Vec<Cell>,
+    // the damaged columns of every row
+    damage: Vec<Option<(u16, u16)>>,
+    // the hash of every row, if it's up to date
+    hashes: Vec<Option<u64>>,
+}
+
+impl Buffer {
+    /// Creates a buffer of `width` x `height` empty cells.
+    pub fn new(width: u16, height: u16) -> Buffer {
+        Buffer {
+            width,
+            height,
+            cells: vec![Cell::default(); width as usize * height as usize],
+            damage: vec![Some((0, width)); height as usize],
+            hashes: vec![None; height as usize],
+        }
+    }
+
+    /// Returns the width in cells.
+    pub fn width(&self) -> u16 {
+        self.width
+    }
+
+    /// Returns the height in cells.
+    pub fn height(&self) -> u16 {
+        self.height
+    }
+
+    /// Returns the size (columns, rows).
+    pub fn size(&self) -> (u16, u16) {
+        (self.width, self.height)
+    }
+
+    /// Returns all cells, row by row.
+    pub fn cells(&self) -> &[Cell] {
+        &self.cells
+    }
+
+    /// Returns the cells of row `y`, which is empty if it's outside of the buffer.
+    pub fn line(&self, y: u16) -> &[Cell] {
+        if y >= self.height {
+            return &[];
+        }
+        let start = y as usize * self.width as usize;
+        &self.cells[start..start + self.width as usize]
+    }
+
+    /// Returns the cell at (`x`, `y`).
+    pub fn get(&self, x: u16, y: u16) -> Option<&Cell> {
+        self.index(x, y).map(|index| &self.cells[index])
+    }
+
+    /// Returns the cell at (`x`, `y`) to change it.
+    ///
+    /// The cell is marked as damaged, even if it isn't changed.
+    pub fn get_mut(&mut self, x: u16, y: u16) -> Option<&mut Cell> {
+        let index = self.index(x, y)?;
+        self.damage_line(y, x..x + 1);
+        Some(&mut self.cells[index])
+    }
+
+    /// Replaces the cell at (`x`, `y`).
+    pub fn set(&mut self, x: u16, y: u16, cell: Cell) {
+        if let Some(current) = self.get_mut(x, y) {
+            *current = cell;
+        }
+    }
+
+    /// Prints `text` in `style` starting at (`x`, `y`), one char per cell, and returns the
+    /// number of cells written.
+    ///
+    /// The text doesn't wrap, it's cut off at the end of the row.
+    pub fn print(&mut self, x: u16, y: u16, text: &str, style: ContentStyle) -> u16 {
+        let start = match self.index(x, y) {
+            Some(start) => start,
+            None => return 0,
+        };
+        let cells = &mut self.cells[start..start + (self.width - x) as usize];
+
+        let mut written = 0;
+        for (cell, symbol) in cells.iter_mut().zip(text.chars()) {
+            cell.set_char(symbol, style);
+            written += 1;
+        }
+        self.damage_line(y, x..x + written);
+        written
+    }
+
+    /// Prints styled content starting at (`x`, `y`), see [`Buffer::print`].
+    pub fn print_styled<D: Display>(&mut self, x: u16, y: u16, content: &StyledContent<D>) -> u16 {
+        self.print(x, y, &content.content().to_string(), *content.style())
+    }
+
+    /// Sets every cell to a copy of `cell`.
+    pub fn fill(&mut self, cell: &Cell) {
+        for current in &mut self.cells {
+            current.clone_from(cell);
+        }
+        self.damage_all();
+    }
+
+    /// Sets every cell to an unstyled space.
+    pub fn clear(&mut self) {
+        for cell in &mut self.cells {
+            cell.set_char(' ', ContentStyle::default());
+        }
+        self.damage_all();
+    }
+
+    /// Changes the size to `width` x `height`, keeping the cells which are still inside.
+    pub fn resize(&mut self, width: u16, height: u16) {
+        if (width, height) == self.size() {
+            return;
+        }
+
+        let mut resized = Buffer::new(width, height);
+        for y in 0..self.height.min(height) {
+            let columns = self.width.min(width) as usize;
+            let start = resized.index(0, y).unwrap_or_default();
+            resized.cells[start..start + columns].clone_from_slice(&self.line(y)[..columns]);
+        }
+        *self = resized;
+    }
+
+    /// Moves the rows in `rows` up by `lines`, like
+    /// [`ScrollUp`](../terminal/struct.ScrollUp.html) in a scroll region, and clears the rows at
+    /// the bottom.
+    pub fn scroll_up(&mut self, rows: Range<u16>, lines: u16) {
+        if let Some((cells, hashes, lines)) = self.scroll_region(rows, lines) {
+            let width = cells.len() / hashes.len();
+            cells.rotate_left(lines * width);
+            hashes.rotate_left(lines);
+
+            let (cells_len, hashes_len) = (cells.len(), hashes.len());
+            for cell in &mut cells[cells_len - lines * width..] {
+                cell.set_char(' ', ContentStyle::default());
+            }
+            for hash in &mut hashes[hashes_len - lines..] {
+                *hash = None;
+            }
+        }
+    }
+
+    /// Moves the rows in `rows` down by `lines`, like
+    /// [`ScrollDown`](../terminal/struct.ScrollDown.html) in a scroll region, and clears the rows at
+    /// the top.
+    pub fn scroll_down(&mut self, rows: Range<u16>, lines: u16) {
+        if let Some((cells, hashes, lines)) = self.scroll_region(rows, lines) {
+            let width = cells.len() / hashes.len();
+            cells.rotate_right(lines * width);
+            hashes.rotate_right(lines);
+
+            for cell in &mut cells[..lines * width] {
+                cell.set_char(' ', ContentStyle::default());
+            }
+            for hash in &mut hashes[..lines] {
+                *hash = None;
+            }
+        }
+    }
+
+    // damages `rows` and returns their cells, their hashes and the number of rows to scroll
+    #[allow(clippy::type_complexity)]
+    fn scroll_region(
+        &mut self,
+        rows: Range<u16>,
+        lines: u16,
+    ) -> Option<(&mut [Cell], &mut [Option<u64>], usize)> {
+        let end = rows.end.min(self.height);
+        if rows.start >= end || lines == 0 || self.width == 0 {
+            return None;
+        }
+        // the rows move with their hashes
+        for damage in &mut self.damage[rows.start as usize..end as usize] {
+            *damage = Some((0, self.width));
+        }
+
+        let width = self.width as usize;
+        let (start, end) = (rows.start as usize, end as usize);
+        Some((
+            &mut self.cells[start * width..end * width],
+            &mut self.hashes[start..end],
+            (lines as usize).min(end - start),
+        ))
+    }
+
+    /// Returns the cells which differ from `previous`, which is the buffer on the screen.
+    ///
+    /// If the sizes differ, every cell is part of the difference.
+    pub fn diff<'a>(&'a self, previous: &Buffer) -> Diff<'a> {
+        Diff::new(self, previous, false)
+    }
+
+    /// Like [`Buffer::diff`], but only compares the damaged cells, so `previous` must be a copy
+    /// of this buffer as of the last [`Buffer::reset_damage`].
+    pub fn diff_damage<'a>(&'a self, previous: &Buffer) -> Diff<'a> {
+        Diff::new(self, previous, true)
+    }
+
+    /// Returns the damaged columns of every row which has any.
+    pub fn damage(&self) -> impl Iterator<Item = (u16, Range<u16>)> + '_ {
+        self.damage
+            .iter()
+            .enumerate()
+            .filter_map(|(y, damage)| damage.map(|(start, end)| (y as u16, start..end)))
+    }
+
+    /// Marks the `columns` of row `y` as damaged, e.g. to print them again.
+    pub fn damage_line(&mut self, y: u16, columns: Range<u16>) {
+        let end = columns.end.min(self.width);
+        if columns.start >= end {
+            return;
+        }
+        if let Some(damage) = self.damage.get_mut(y as usize) {
+            *damage = Some(match *damage {
+                Some((start, current_end)) => (start.min(columns.start), current_end.max(end)),
+                None => (columns.start, end),
+            });
+            self.hashes[y as usize] = None;
+        }
+    }
+
+    /// Marks every cell as damaged.
+    pub fn damage_all(&mut self) {
+        for damage in &mut self.damage {
+            *damage = Some((0, self.width));
+        }
+        for hash in &mut self.hashes {
+            *hash = None;
+        }
+    }
+
+    /// Marks every cell as undamaged, usually after the buffer has been drawn.
+    pub fn reset_damage(&mut self) {
+        for damage in &mut self.damage {
+            *damage = None;
+        }
+    }
+
+    // copies the damaged cells and their hashes to `previous`, which has the same size
+    fn copy_damage_to(&self, previous: &mut Buffer) {
+        for (y, columns) in self.damage() {
+            let start = y as usize * self.width as usize;
+            let range = start + columns.start as usize..start + columns.end as usize;
+            previous.cells[range.clone()].clone_from_slice(&self.cells[range]);
+            previous.hashes[y as usize] = self.hashes[y as usize];
+        }
+    }
+
+    // computes the hashes which aren't up to date
+    fn hash_lines(&mut self) {
+        for y in 0..self.height {
+            if self.hashes[y as usize].is_none() {
+                self.hashes[y as usize] = Some(hash_line(self.line(y)));
+            }
+        }
+    }
+
+    // whether row `y` equals row `other_y` of `other`, by their hashes if both are up to date, so
+    // unchanged rows are skipped without comparing their cells
+    fn line_eq(&self, y: u16, other: &Buffer, other_y: u16) -> bool {
+        match (
+            self.hashes.get(y as usize),
+            other.hashes.get(other_y as usize),
+        ) {
+            (Some(Some(hash)), Some(Some(other_hash))) => hash == other_hash,
+            _ => self.line(y) == other.line(other_y),
+        }
+    }
+
+    fn index(&self, x: u16, y: u16) -> Option<usize> {
+        if x < self.width && y < self.height {
+            Some(y as usize * self.width as usize + x as usize)
+        } else {
+            None
+        }
+    }
+}
+
+fn hash_line(cells: &[Cell]) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    cells.hash(&mut hasher);
+    hasher.finish()
+}
+
+impl PartialEq for Buffer {
+    fn eq(&self, other: &Buffer) -> bool {
+        self.size() == other.size() && self.cells == other.cells
+    }
+}
+
+impl Eq for Buffer {}
+
+#[cfg(test)]
+mod tests {
+    use crate::style::{ContentStyle, Stylize};
+
+    use super::{hash_line, Buffer, Cell};
+
+    #[test]
+    fn test_buffer_print() {
+        let mut buffer = Buffer::new(4, 2);
+        assert_eq!(buffer.print(1, 0, "abcdef", ContentStyle::new()), 3);
+        assert_eq!(buffer.print(0, 2, "a", ContentStyle::new()), 0);
+        assert_eq!(buffer.print_styled(0, 1, &"xä".red()), 2);
+
+        let symbols = |buffer: &Buffer, y| {
+            buffer
+                .line(y)
+                .iter()
+                .map(|cell| cell.symbol.as_str())
+                .collect::<String>()
+        };
+        assert_eq!(symbols(&buffer, 0), " abc");
+        assert_eq!(symbols(&buffer, 1), "xä  ");
+        assert_eq!(
+            buffer.get(1, 1),
+            Some(&Cell::new("ä", ContentStyle::new().red()))
+        );
+        assert_eq!(buffer.get(4, 0), None);
+        assert!(buffer.line(2).is_empty());
+
+        buffer.resize(2, 3);
+        assert_eq!(symbols(&buffer, 0), " a");
+        assert_eq!(symbols(&buffer, 1), "xä");
+        assert_eq!(symbols(&buffer, 2), "  ");
+
+        buffer.clear();
+        assert_eq!(buffer, Buffer::new(2, 3));
+    }
+
+    #[test]
+    fn test_buffer_damage() {
+        let mut buffer = Buffer::new(8, 3);
+        assert_eq!(buffer.damage().count(), 3);
+
+        buffer.reset_damage();
+        buffer.print(2, 0, "ab", ContentStyle::new());
+        buffer.print(5, 0, "c", ContentStyle::new());
+        buffer.get_mut(7, 2).unwrap().symbol.push('\u{301}');
+        buffer.damage_line(1, 6..20);
+        assert_eq!(
+            buffer.damage().collect::<Vec<_>>(),
+            [(0, 2..6), (1, 6..8), (2, 7..8)]
+        );
+
+        let mut previous = Buffer::new(8, 3);
+        assert_eq!(buffer.diff_damage(&previous).len(), 4);
+        buffer.copy_damage_to(&mut previous);
+        assert_eq!(previous, buffer);
+
+        buffer.reset_damage();
+        assert_eq!(buffer.damage().count(), 0);
+
+        // the hashes are used to compare whole lines
+        buffer.hash_lines();
+        previous.hash_lines();
+        assert!(buffer.line_eq(0, &previous, 0));
+        assert!(!buffer.line_eq(0, &previous, 1));
+        buffer.print(0, 0, "x", ContentStyle::new());
+        assert_eq!(buffer.hashes[0], None);
+        assert!(!buffer.line_eq(0, &previous, 0));
+        buffer.clear();
+        assert_eq!(buffer.damage().count(), 3);
+    }
+
+    #[test]
+    fn test_buffer_scroll() {
+        let mut buffer = Buffer::new(1, 4);
+        for (y, symbol) in ["a", "b", "c", "d"].iter().enumerate() {
+            buffer.print(0, y as u16, symbol, ContentStyle::new());
+        }
+        let symbols = |buffer: &Buffer| {
+            buffer
+                .cells()
+                .iter()
+                .map(|cell| cell.symbol.as_str())
+                .collect::<String>()
+        };
+
+        buffer.hash_lines();
+        buffer.scroll_up(1..3, 1);
+        assert_eq!(symbols(&buffer), "ac d");
+        // the hashes move with the rows
+        assert_eq!(buffer.hashes[1], Some(hash_line(buffer.line(1))));
+        assert_eq!(buffer.hashes[2], None);
+        buffer.scroll_down(0..10, 2);
+        assert_eq!(symbols(&buffer), "  ac");
+        buffer.scroll_up(0..4, 5);
+        assert_eq!(symbols(&buffer), "    ");
+    }
+}
@@ -0,0 +1,314 @@
+use std::{io, ops::Range};
+
+use crate::{
+    cursor::{MoveRight, MoveTo, MoveToColumn, MoveToNextLine},
+    queue,
+    style::{
+        Attribute, ContentStyle, Print, SetAttribute, SetAttributes, SetBackgroundColor,
+        SetForegroundColor, SetStyle, SetUnderlineColor, SetUnderlineStyle,
+    },
+};
+
+use super::{Buffer, Cell};
+
+/// Unchanged cells between two changed ones are printed again if there are at most this many,
+/// which is shorter than moving the cursor over them.
+const MAX_GAP: u16 = 3;
+
+/// The cells of a [`Buffer`] which changed, see [`Buffer::diff`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diff<'a> {
+    buffer: &'a Buffer,
+    // runs of changed cells as (row, first column, end column), row by row
+    runs: Vec<(u16, u16, u16)>,
+}
+
+impl<'a> Diff<'a> {
+    pub(super) fn new(current: &'a Buffer, previous: &Buffer, damage_only: bool) -> Diff<'a> {
+        let mut diff = Diff {
+            buffer: current,
+            runs: Vec::new(),
+        };
+
+        if current.size() != previous.size() {
+            for y in 0..current.height {
+                diff.runs.push((y, 0, current.width));
+            }
+        } else if damage_only {
+            for (y, columns) in current.damage() {
+                diff.push_changes(previous, y, columns);
+            }
+        } else {
+            for y in 0..current.height {
+                diff.push_changes(previous, y, 0..current.width);
+            }
+        }
+        diff
+    }
+
+    fn push_changes(&mut self, previous: &Buffer, y: u16, columns: Range<u16>) {
+        if self.buffer.line_eq(y, previous, y) {
+            return;
+        }
+
+        let (current, previous) = (self.buffer.line(y), previous.line(y));
+        let mut run: Option<u16> = None;
+
+        for x in columns.clone() {
+            let changed = current[x as usize] != previous[x as usize];
+            match run {
+                None if changed => run = Some(x),
+                Some(start) if !changed => {
+                    self.runs.push((y, start, x));
+                    run = None;
+                }
+                _ => {}
+            }
+        }
+        if let Some(start) = run {
+            self.runs.push((y, start, columns.end));
+        }
+    }
+
+    /// Returns the changed cells and their positions (column, row), row by row.
+    pub fn iter(&self) -> impl Iterator<Item = (u16, u16, &'a Cell)> + '_ {
+        let buffer = self.buffer;
+        self.runs.iter().flat_map(move |&(y, start, end)| {
+            let line = buffer.line(y);
+            (start..end).map(move |x| (x, y, &line[x as usize]))
+        })
+    }
+
+    /// Returns the number of changed cells.
+    pub fn len(&self) -> usize {
+        self.runs
+            .iter()
+            .map(|&(_, start, end)| (end - start) as usize)
+            .sum()
+    }
+
+    /// Returns whether no cell changed.
+    pub fn is_empty(&self) -> bool {
+        self.runs.is_empty()
+    }
+}
+
+/// Prints the changed cells of `diff` and flushes the `writer`.
+///
+/// The cursor is only moved where a changed cell doesn't follow the previous one, and only the
+/// colors and attributes which differ from the previous cell are set. Cells with the same style
+/// are printed at once, and a few unchanged cells between changed ones are printed again if it
+/// takes less than moving the cursor. The style is reset at the end and the cursor is left behind
+/// the last printed cell.
+pub fn flush(writer: &mut impl io::Write, diff: &Diff<'_>) -> io::Result<()> {
+    write_diff(writer, diff)?;
+    writer.flush()
+}
+
+pub(super) fn write_diff(writer: &mut impl io::Write, diff: &Diff<'_>) -> io::Result<()> {
+    let mut cursor = None;
+    let mut style = None;
+    let mut text = String::new();
+
+    let mut runs = diff.runs.iter().copied().peekable();
+    while let Some((y, start, mut end)) = runs.next() {
+        let line = diff.buffer.line(y);
+        while let Some(&(next_y, next_start, next_end)) = runs.peek() {
+            if next_y != y || next_start - end > MAX_GAP || !can_merge(line, end, next_start) {
+                break;
+            }
+            end = next_end;
+            runs.next();
+        }
+
+        write_move(writer, cursor, (start, y))?;
+        for cell in &line[start as usize..end as usize] {
+            if style != Some(cell.style) {
+                if !text.is_empty() {
+                    queue!(writer, Print(&text))?;
+                    text.clear();
+                }
+                write_style(writer, style, &cell.style)?;
+                style = Some(cell.style);
+            }
+            text.push_str(&cell.symbol);
+        }
+        queue!(writer, Print(&text))?;
+        text.clear();
+
+        // the cursor doesn't move behind the last column until the next char is printed
+        cursor = if end < diff.buffer.width {
+            Some((end, y))
+        } else {
+            None
+        };
+    }
+
+    if style.map_or(false, |style| style != ContentStyle::default()) {
+        queue!(writer, SetAttribute(Attribute::Reset))?;
+    }
+    Ok(())
+}
+
+// whether the unchanged cells from `end` to `next_start` can be printed without changing the style
+// more often, because they have the style of the cell before or after them
+fn can_merge(line: &[Cell], end: u16, next_start: u16) -> bool {
+    let gap = &line[end as usize..next_start as usize];
+    let (before, after) = (
+        &line[end as usize - 1].style,
+        &line[next_start as usize].style,
+    );
+    gap.iter().all(|cell| cell.style == *before) || gap.iter().all(|cell| cell.style == *after)
+}
+
+fn write_move(
+    writer: &mut impl io::Write,
+    cursor: Option<(u16, u16)>,
+    (x, y): (u16, u16),
+) -> io::Result<()> {
+    match cursor {
+        Some(cursor) if cursor == (x, y) => Ok(()),
+        Some((column, row)) if row == y && column < x => queue!(writer, MoveRight(x - column)),
+        Some((_, row)) if row == y => queue!(writer, MoveToColumn(x)),
+        Some((_, row)) if row + 1 == y && x == 0 => queue!(writer, MoveToNextLine(1)),
+        _ => queue!(writer, MoveTo(x, y)),
+    }
+}
+
+fn write_style(
+    writer: &mut impl io::Write,
+    current: Option<ContentStyle>,
+    style: &ContentStyle,
+) -> io::Result<()> {
+    let current = match current {
+        Some(current) if current == *style => return Ok(()),
+        Some(current) => current,
+        None => ContentStyle::default(),
+    };
+
+    // colors and attributes can't be removed one by one, so start over
+    let removed = (current.attributes ^ style.attributes) & current.attributes;
+    let colors = [
+        (current.foreground_color, style.foreground_color),
+        (current.background_color, style.background_color),
+        (current.underline_color, style.underline_color),
+    ];
+    if !removed.is_empty()
+        || (current.underline.is_some() && style.underline.is_none())
+        || colors
+            .iter()
+            .any(|(current, color)| current.is_some() && color.is_none())
+    {
+        return queue!(writer, SetAttribute(Attribute::Reset), SetStyle(*style));
+    }
+
+    if current.foreground_color != style.foreground_color {
+        if let Some(color) = style.foreground_color {
+            queue!(writer, SetForegroundColor(color))?;
+        }
+    }
+    if current.background_color != style.background_color {
+        if let Some(color) = style.background_color {
+            queue!(writer, SetBackgroundColor(color))?;
+        }
+    }
+    if current.underline_color != style.underline_color {
+        if let Some(color) = style.underline_color {
+            queue!(writer, SetUnderlineColor(color))?;
+        }
+    }
+    let added = (current.attributes ^ style.attributes) & style.attributes;
+    if !added.is_empty() {
+        queue!(writer, SetAttributes(added))?;
+    }
+    if current.underline != style.underline {
+        if let Some(underline) = style.underline {
+            queue!(writer, SetUnderlineStyle(underline))?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        buffer::Buffer,
+        style::{ContentStyle, Stylize, UnderlineStyle},
+    };
+
+    use super::flush;
+
+    fn render(current: &Buffer, previous: &Buffer) -> String {
+        let mut output = Vec::new();
+        flush(&mut output, &current.diff(previous)).unwrap();
+        String::from_utf8(output).unwrap()
+    }
+
+    #[test]
+    fn test_flush_moves_the_cursor_to_changed_cells() {
+        let previous = Buffer::new(10, 3);
+        let mut current = previous.clone();
+        assert!(current.diff(&previous).is_empty());
+        assert_eq!(render(&current, &previous), "");
+
+        current.print(1, 0, "ab", ContentStyle::new());
+        current.print(7, 0, "c", ContentStyle::new());
+        current.print(2, 0, "x", ContentStyle::new());
+        current.print(0, 1, "d", ContentStyle::new());
+        current.print(9, 1, "e", ContentStyle::new());
+        current.print(3, 2, "f", ContentStyle::new());
+        current.print(6, 2, "g", ContentStyle::new());
+        assert_eq!(current.diff(&previous).len(), 7);
+        // the unchanged cells between f and g are printed again
+        assert_eq!(
+            render(&current, &previous),
+            "\x1B[1;2Hax\x1B[4Cc\x1B[1Ed\x1B[8Ce\x1B[3;4Hf  g"
+        );
+
+        // everything is printed if the size changed
+        let diff = current.diff(&Buffer::new(0, 0));
+        assert_eq!(diff.len(), 30);
+        assert_eq!(diff.iter().nth(11), Some((1, 1, &current.cells[11])));
+    }
+
+    #[test]
+    fn test_flush_only_changes_the_style_where_needed() {
+        let previous = Buffer::new(8, 1);
+        let mut current = previous.clone();
+        current.print(0, 0, "ab", ContentStyle::new().red());
+        current.print(2, 0, "c", ContentStyle::new().red().bold());
+        current.print(3, 0, "d", ContentStyle::new().blue().bold());
+        current.print(4, 0, "e", ContentStyle::new().blue());
+        current.print(6, 0, "f", ContentStyle::new());
+
+        assert_eq!(
+            render(&current, &previous),
+            "\x1B[1;1H\x1B[38;5;9mab\x1B[1mc\x1B[38;5;12md\
+             \x1B[0m\x1B[38;5;12me\x1B[0m f"
+        );
+    }
+
+    #[test]
+    fn test_flush_replaces_the_underline_style() {
+        let previous = Buffer::new(3, 1);
+        let mut current = previous.clone();
+        current.print(
+            0,
+            0,
+            "a",
+            ContentStyle::new().underline_style(UnderlineStyle::Curly),
+        );
+        current.print(
+            1,
+            0,
+            "b",
+            ContentStyle::new().underline_style(UnderlineStyle::Double),
+        );
+        current.print(2, 0, "c", ContentStyle::new());
+
+        assert_eq!(
+            render(&current, &previous),
+            "\x1B[1;1H\x1B[4:3ma\x1B[4:2mb\x1B[0mc"
+        );
+    }
+}
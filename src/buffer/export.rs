@@ -0,0 +1,231 @@
+use std::fmt::Write;
+
+use crate::style::{Attribute, Color, ContentStyle};
+
+use super::{Buffer, Cell};
+
+/// The colors of xterm for the 16 named colors.
+const PALETTE: [(u8, u8, u8); 16] = [
+    (0x00, 0x00, 0x00),
+    (0xCD, 0x00, 0x00),
+    (0x00, 0xCD, 0x00),
+    (0xCD, 0xCD, 0x00),
+    (0x00, 0x00, 0xEE),
+    (0xCD, 0x00, 0xCD),
+    (0x00, 0xCD, 0xCD),
+    (0xE5, 0xE5, 0xE5),
+    (0x7F, 0x7F, 0x7F),
+    (0xFF, 0x00, 0x00),
+    (0x00, 0xFF, 0x00),
+    (0xFF, 0xFF, 0x00),
+    (0x5C, 0x5C, 0xFF),
+    (0xFF, 0x00, 0xFF),
+    (0x00, 0xFF, 0xFF),
+    (0xFF, 0xFF, 0xFF),
+];
+
+impl Buffer {
+    /// Returns the symbols without styles, a line per row without trailing spaces.
+    pub fn to_text(&self) -> String {
+        let mut text = String::new();
+        for y in 0..self.height {
+            if y > 0 {
+                text.push('\n');
+            }
+            for cell in self.line(y) {
+                text.push_str(&cell.symbol);
+            }
+            text.truncate(text.trim_end_matches(' ').len());
+        }
+        text
+    }
+
+    /// Returns the cells as HTML, a `<pre>` element with a `<span>` for every run of styled cells.
+    ///
+    /// The colors are the ones of xterm, unset colors are left to the page, and reversed cells
+    /// without colors are black on white. Blinking, framed and encircled cells aren't rendered.
+    pub fn to_html(&self) -> String {
+        let mut html = String::from("<pre>");
+        for y in 0..self.height {
+            if y > 0 {
+                html.push('\n');
+            }
+
+            let line = self.line(y);
+            let end = line
+                .iter()
+                .rposition(|cell| *cell != Cell::default())
+                .map_or(0, |end| end + 1);
+            let mut cells = line[..end].iter().peekable();
+            while let Some(cell) = cells.next() {
+                let style = css(&cell.style);
+                if !style.is_empty() {
+                    write!(html, "<span style=\"{}\">", style).unwrap();
+                }
+                push_escaped(&mut html, &cell.symbol);
+                while let Some(next) = cells.next_if(|next| next.style == cell.style) {
+                    push_escaped(&mut html, &next.symbol);
+                }
+                if !style.is_empty() {
+                    html.push_str("</span>");
+                }
+            }
+        }
+        html.push_str("</pre>");
+        html
+    }
+}
+
+fn css(style: &ContentStyle) -> String {
+    let attributes = style.attributes;
+    let (mut foreground, mut background) = (style.foreground_color, style.background_color);
+    if attributes.has(Attribute::Reverse) {
+        foreground = Some(background.unwrap_or(Color::Black));
+        background = Some(style.foreground_color.unwrap_or(Color::White));
+    }
+
+    let mut css = String::new();
+    if let Some(color) = foreground.and_then(rgb) {
+        write!(css, "color:{};", color).unwrap();
+    }
+    if let Some(color) = background.and_then(rgb) {
+        write!(css, "background-color:{};", color).unwrap();
+    }
+    if attributes.has(Attribute::Bold) {
+        css.push_str("font-weight:bold;");
+    }
+    if attributes.has(Attribute::Dim) {
+        css.push_str("opacity:0.5;");
+    }
+    if attributes.has(Attribute::Italic) {
+        css.push_str("font-style:italic;");
+    }
+    if attributes.has(Attribute::Hidden) {
+        css.push_str("visibility:hidden;");
+    }
+
+    let underline = [
+        (Attribute::Underlined, "underline"),
+        (Attribute::DoubleUnderlined, "underline double"),
+        (Attribute::Undercurled, "underline wavy"),
+        (Attribute::Underdotted, "underline dotted"),
+        (Attribute::Underdashed, "underline dashed"),
+    ]
+    .iter()
+    .find(|(attribute, _)| match style.underline {
+        Some(underline) => underline.attribute() == *attribute,
+        None => attributes.has(*attribute),
+    })
+    .map(|(_, line)| *line);
+    let lines = [
+        underline,
+        Some("line-through").filter(|_| attributes.has(Attribute::CrossedOut)),
+        Some("overline").filter(|_| attributes.has(Attribute::OverLined)),
+    ];
+    let lines = lines.iter().flatten().copied().collect::<Vec<_>>();
+    if !lines.is_empty() {
+        write!(css, "text-decoration:{};", lines.join(" ")).unwrap();
+        if let Some(color) = style.underline_color.and_then(rgb) {
+            write!(css, "text-decoration-color:{};", color).unwrap();
+        }
+    }
+
+    css.pop();
+    css
+}
+
+// the color as `#rrggbb`, or `None` for the default color
+fn rgb(color: Color) -> Option<String> {
+    let index = match color {
+        Color::Reset => return None,
+        Color::Rgb { r, g, b } => return Some(format!("#{:02x}{:02x}{:02x}", r, g, b)),
+        Color::AnsiValue(value) => value,
+        Color::Black => 0,
+        Color::DarkRed => 1,
+        Color::DarkGreen => 2,
+        Color::DarkYellow => 3,
+        Color::DarkBlue => 4,
+        Color::DarkMagenta => 5,
+        Color::DarkCyan => 6,
+        Color::Grey => 7,
+        Color::DarkGrey => 8,
+        Color::Red => 9,
+        Color::Green => 10,
+        Color::Yellow => 11,
+        Color::Blue => 12,
+        Color::Magenta => 13,
+        Color::Cyan => 14,
+        Color::White => 15,
+    };
+
+    let (r, g, b) = match index {
+        0..=15 => PALETTE[index as usize],
+        // the 6x6x6 color cube
+        16..=231 => {
+            let level = |value: u8| if value == 0 { 0 } else { value * 40 + 55 };
+            let index = index - 16;
+            (level(index / 36), level(index / 6 % 6), level(index % 6))
+        }
+        // the grayscale ramp
+        _ => {
+            let level = (index - 232) * 10 + 8;
+            (level, level, level)
+        }
+    };
+    Some(format!("#{:02x}{:02x}{:02x}", r, g, b))
+}
+
+fn push_escaped(html: &mut String, text: &str) {
+    for c in text.chars() {
+        match c {
+            '&' => html.push_str("&amp;"),
+            '<' => html.push_str("&lt;"),
+            '>' => html.push_str("&gt;"),
+            '"' => html.push_str("&quot;"),
+            c => html.push(c),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        buffer::Buffer,
+        style::{Color, ContentStyle, Stylize},
+    };
+
+    use super::rgb;
+
+    #[test]
+    fn test_export_text() {
+        let mut buffer = Buffer::new(5, 3);
+        buffer.print(0, 0, "a b", ContentStyle::new().red());
+        buffer.print(1, 2, "ä", ContentStyle::new());
+        assert_eq!(buffer.to_text(), "a b\n\n ä");
+    }
+
+    #[test]
+    fn test_export_html() {
+        let mut buffer = Buffer::new(6, 2);
+        buffer.print(0, 0, "<a>", ContentStyle::new().red().bold());
+        buffer.print(3, 0, "&", ContentStyle::new());
+        buffer.print(0, 1, "x", ContentStyle::new().reverse());
+        buffer.print(1, 1, "y", ContentStyle::new().crossed_out().underlined());
+        assert_eq!(
+            buffer.to_html(),
+            "<pre><span style=\"color:#ff0000;font-weight:bold\">&lt;a&gt;</span>&amp;\n\
+             <span style=\"color:#000000;background-color:#ffffff\">x</span>\
+             <span style=\"text-decoration:underline line-through\">y</span></pre>"
+        );
+    }
+
+    #[test]
+    fn test_rgb() {
+        assert_eq!(rgb(Color::Reset), None);
+        assert_eq!(rgb(Color::DarkBlue).unwrap(), "#0000ee");
+        assert_eq!(rgb(Color::Rgb { r: 1, g: 2, b: 255 }).unwrap(), "#0102ff");
+        assert_eq!(rgb(Color::AnsiValue(9)).unwrap(), "#ff0000");
+        assert_eq!(rgb(Color::AnsiValue(67)).unwrap(), "#5f87af");
+        assert_eq!(rgb(Color::AnsiValue(255)).unwrap(), "#eeeeee");
+    }
+}
@@ -0,0 +1,187 @@
+use std::{
+    io,
+    time::{Duration, Instant},
+};
+
+use crate::SynchronizedUpdate;
+
+/// Schedules the rendering of frames, e.g. of a [`Screen`](struct.Screen.html).
+///
+/// Instead of rendering after every event, the application [requests](Frame::request) a frame and
+/// calls [`Frame::render`] whenever it's idle. Requests are coalesced into a single frame, which is
+/// rendered within a synchronized update, so the terminal never shows half of it:
+///
+/// - the frame is rendered when the `budget` after the first request has passed, so that more
+///   changes can be made in the meantime,
+/// - and at most `max_fps` frames are rendered per second.
+///
+/// [`Frame::timeout`] returns how long to wait for events until the frame is due.
+///
+/// # Examples
+///
+/// ```no_run
+/// use std::{io, time::Duration};
+/// use crossterm::{buffer::{Frame, Screen}, event, style::ContentStyle};
+///
+/// fn main() -> io::Result<()> {
+///     let mut screen = Screen::new(80, 24);
+///     let mut frame = Frame::new().max_fps(60);
+///     let mut stdout = io::stdout();
+///
+///     loop {
+///         if event::poll(frame.timeout().unwrap_or(Duration::from_secs(1)))? {
+///             let event = event::read()?;
+///             screen.buffer_mut().print(0, 0, &format!("{:?}", event), ContentStyle::new());
+///             frame.request();
+///         }
+///
+///         frame.render(&mut stdout, |stdout| screen.flush(stdout))?;
+///     }
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct Frame {
+    budget: Duration,
+    min_interval: Duration,
+    // when the first request since the last frame was made
+    requested: Option<Instant>,
+    last_frame: Option<Instant>,
+}
+
+impl Frame {
+    /// Creates a scheduler which renders requested frames right away.
+    pub fn new() -> Frame {
+        Frame {
+            budget: Duration::ZERO,
+            min_interval: Duration::ZERO,
+            requested: None,
+            last_frame: None,
+        }
+    }
+
+    /// Waits for `budget` after the first request before rendering the frame.
+    pub fn budget(mut self, budget: Duration) -> Frame {
+        self.budget = budget;
+        self
+    }
+
+    /// Renders at most `fps` frames per second, `0` doesn't limit them.
+    pub fn max_fps(mut self, fps: u32) -> Frame {
+        self.min_interval = match fps {
+            0 => Duration::ZERO,
+            fps => Duration::from_secs(1) / fps,
+        };
+        self
+    }
+
+    /// Requests a frame, multiple requests before it's rendered result in a single frame.
+    pub fn request(&mut self) {
+        self.request_at(Instant::now());
+    }
+
+    /// Returns whether a frame was requested and not rendered yet.
+    pub fn is_requested(&self) -> bool {
+        self.requested.is_some()
+    }
+
+    /// Returns how long it takes until the requested frame is due, `None` if there is no request.
+    pub fn timeout(&self) -> Option<Duration> {
+        self.due()
+            .map(|due| due.saturating_duration_since(Instant::now()))
+    }
+
+    /// Renders the requested frame with `draw` inside a synchronized update, if it's due.
+    ///
+    /// Returns `None` without calling `draw` if no frame is due.
+    pub fn render<W, T>(
+        &mut self,
+        writer: &mut W,
+        draw: impl FnOnce(&mut W) -> io::Result<T>,
+    ) -> io::Result<Option<T>>
+    where
+        W: io::Write + ?Sized,
+    {
+        self.render_at(Instant::now(), writer, draw)
+    }
+
+    fn request_at(&mut self, now: Instant) {
+        self.requested.get_or_insert(now);
+    }
+
+    fn due(&self) -> Option<Instant> {
+        let requested = self.requested?;
+        let due = requested + self.budget;
+        Some(match self.last_frame {
+            Some(last_frame) => due.max(last_frame + self.min_interval),
+            None => due,
+        })
+    }
+
+    fn render_at<W, T>(
+        &mut self,
+        now: Instant,
+        writer: &mut W,
+        draw: impl FnOnce(&mut W) -> io::Result<T>,
+    ) -> io::Result<Option<T>>
+    where
+        W: io::Write + ?Sized,
+    {
+        match self.due() {
+            Some(due) if due <= now => {}
+            _ => return Ok(None),
+        }
+
+        let result = writer.sync_update(draw)??;
+        self.requested = None;
+        self.last_frame = Some(now);
+        Ok(Some(result))
+    }
+}
+
+impl Default for Frame {
+    fn default() -> Self {
+        Frame::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        io::{self, Write},
+        time::{Duration, Instant},
+    };
+
+    use super::Frame;
+
+    fn draw(output: &mut Vec<u8>) -> io::Result<()> {
+        output.write_all(b"frame")
+    }
+
+    #[test]
+    fn test_frame_coalesces_requests() {
+        let start = Instant::now();
+        let at = |millis| start + Duration::from_millis(millis);
+        let mut frame = Frame::new().budget(Duration::from_millis(5)).max_fps(50);
+        let mut output = Vec::new();
+
+        assert_eq!(frame.render_at(at(0), &mut output, draw).unwrap(), None);
+        frame.request_at(at(0));
+        frame.request_at(at(3));
+        assert_eq!(frame.due(), Some(at(5)));
+        assert_eq!(frame.render_at(at(4), &mut output, draw).unwrap(), None);
+        assert!(output.is_empty());
+
+        assert_eq!(frame.render_at(at(5), &mut output, draw).unwrap(), Some(()));
+        assert_eq!(output, b"\x1B[?2026hframe\x1B[?2026l");
+        assert!(!frame.is_requested());
+
+        // at most 50 frames per second
+        frame.request_at(at(6));
+        assert_eq!(frame.due(), Some(at(25)));
+
+        let mut frame = Frame::new();
+        frame.request_at(at(0));
+        assert_eq!(frame.due(), Some(at(0)));
+        assert_eq!(Frame::new().timeout(), None);
+    }
+}
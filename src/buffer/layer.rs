@@ -0,0 +1,83 @@
+use super::Buffer;
+
+/// The area (column, row, width, height) and priority of a visible layer.
+pub(super) type LayerState = ((u16, u16, u16, u16), i32);
+
+/// A layer added to a [`Screen`](struct.Screen.html).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct LayerId(pub(super) u32);
+
+/// A buffer drawn on top of the buffer of a [`Screen`](struct.Screen.html), e.g. a popup or a
+/// toast, see [`Screen::add_layer`](struct.Screen.html#method.add_layer).
+///
+/// The layer covers the cells below it completely, including its spaces. Layers with a higher
+/// priority are drawn on top of the ones with a lower priority, layers with the same priority
+/// in the order they were added.
+#[derive(Debug, Clone)]
+pub struct Layer {
+    buffer: Buffer,
+    position: (u16, u16),
+    priority: i32,
+    visible: bool,
+    // the state of the layer at the last flush
+    pub(super) drawn: Option<LayerState>,
+}
+
+impl Layer {
+    pub(super) fn new(buffer: Buffer, position: (u16, u16), priority: i32) -> Layer {
+        Layer {
+            buffer,
+            position,
+            priority,
+            visible: true,
+            drawn: None,
+        }
+    }
+
+    /// Returns the content of the layer.
+    pub fn buffer(&self) -> &Buffer {
+        &self.buffer
+    }
+
+    /// Returns the content of the layer to change it.
+    pub fn buffer_mut(&mut self) -> &mut Buffer {
+        &mut self.buffer
+    }
+
+    /// Returns the position (column, row) of the top left corner on the screen.
+    pub fn position(&self) -> (u16, u16) {
+        self.position
+    }
+
+    /// Moves the top left corner to `position` (column, row) on the screen.
+    pub fn set_position(&mut self, position: (u16, u16)) {
+        self.position = position;
+    }
+
+    /// Returns the priority.
+    pub fn priority(&self) -> i32 {
+        self.priority
+    }
+
+    /// Changes the priority, layers with a higher priority are drawn on top.
+    pub fn set_priority(&mut self, priority: i32) {
+        self.priority = priority;
+    }
+
+    /// Returns whether the layer is drawn.
+    pub fn is_visible(&self) -> bool {
+        self.visible
+    }
+
+    /// Shows or hides the layer, the cells below a hidden layer are drawn again.
+    pub fn set_visible(&mut self, visible: bool) {
+        self.visible = visible;
+    }
+
+    // the area and priority the layer is drawn with now
+    pub(super) fn state(&self) -> Option<LayerState> {
+        let (x, y) = self.position;
+        let (width, height) = self.buffer.size();
+        Some(((x, y, width, height), self.priority)).filter(|_| self.visible)
+    }
+}
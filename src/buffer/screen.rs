@@ -0,0 +1,389 @@
+use std::io;
+
+use crate::{cursor::MoveTo, queue};
+
+use super::{
+    diff::write_diff,
+    layer::{Layer, LayerId, LayerState},
+    scroll::Scroll,
+    Buffer, Snapshot,
+};
+
+/// The buffer on the screen and the one being drawn, see the [module](index.html) docs.
+///
+/// Popups and the like can be drawn on [layers](struct.Layer.html) on top of the buffer, so
+/// the cells below don't have to be saved and drawn again when they're closed.
+#[derive(Debug, Clone)]
+pub struct Screen {
+    current: Buffer,
+    previous: Buffer,
+    cursor: Option<(u16, u16)>,
+    layers: Vec<(LayerId, Layer)>,
+    next_layer_id: u32,
+    // the buffer with the layers drawn on top, only used if there are any
+    composed: Buffer,
+}
+
+impl Screen {
+    /// Creates a screen of `width` x `height` cells.
+    ///
+    /// The first [`Screen::flush`] prints every cell, because the content of the terminal isn't
+    /// known.
+    pub fn new(width: u16, height: u16) -> Screen {
+        Screen {
+            current: Buffer::new(width, height),
+            previous: Buffer::default(),
+            cursor: None,
+            layers: Vec::new(),
+            next_layer_id: 0,
+            composed: Buffer::default(),
+        }
+    }
+
+    /// Returns the buffer being drawn.
+    pub fn buffer(&self) -> &Buffer {
+        &self.current
+    }
+
+    /// Returns the buffer being drawn to change it.
+    pub fn buffer_mut(&mut self) -> &mut Buffer {
+        &mut self.current
+    }
+
+    /// Returns the buffer on the screen, as of the last [`Screen::flush`], including the layers.
+    pub fn previous(&self) -> &Buffer {
+        &self.previous
+    }
+
+    /// Returns where the cursor is moved after a flush.
+    pub fn cursor(&self) -> Option<(u16, u16)> {
+        self.cursor
+    }
+
+    /// Moves the cursor to `position` (column, row) after every flush, or leaves it behind the
+    /// last printed cell if it's `None`.
+    pub fn set_cursor(&mut self, position: Option<(u16, u16)>) {
+        self.cursor = position;
+    }
+
+    /// Changes the size, e.g. after a [resize event](../event/enum.Event.html#variant.Resize).
+    ///
+    /// The next flush prints every cell, the terminal should be cleared before.
+    pub fn resize(&mut self, width: u16, height: u16) {
+        self.current.resize(width, height);
+        self.invalidate();
+    }
+
+    /// Forgets the buffer on the screen so that the next flush prints every cell, e.g. after
+    /// something else printed to the terminal.
+    pub fn invalidate(&mut self) {
+        self.previous = Buffer::default();
+    }
+
+    /// Adds a layer of `width` x `height` empty cells with its top left corner at `position`
+    /// (column, row), which is drawn on top of the layers with a lower `priority`.
+    pub fn add_layer(
+        &mut self,
+        position: (u16, u16),
+        (width, height): (u16, u16),
+        priority: i32,
+    ) -> LayerId {
+        let id = LayerId(self.next_layer_id);
+        self.next_layer_id = self.next_layer_id.wrapping_add(1);
+        let layer = Layer::new(Buffer::new(width, height), position, priority);
+        self.layers.push((id, layer));
+        id
+    }
+
+    /// Returns a layer.
+    pub fn layer(&self, id: LayerId) -> Option<&Layer> {
+        self.layers
+            .iter()
+            .find(|(layer_id, _)| *layer_id == id)
+            .map(|(_, layer)| layer)
+    }
+
+    /// Returns a layer to change it.
+    pub fn layer_mut(&mut self, id: LayerId) -> Option<&mut Layer> {
+        self.layers
+            .iter_mut()
+            .find(|(layer_id, _)| *layer_id == id)
+            .map(|(_, layer)| layer)
+    }
+
+    /// Removes a layer, the cells below it are drawn again with the next flush.
+    pub fn remove_layer(&mut self, id: LayerId) -> Option<Layer> {
+        let index = self
+            .layers
+            .iter()
+            .position(|(layer_id, _)| *layer_id == id)?;
+        let (_, layer) = self.layers.remove(index);
+        damage_area(&mut self.current, layer.drawn);
+        Some(layer)
+    }
+
+    /// Returns a snapshot of the buffer being drawn and the cursor position, without the layers.
+    pub fn snapshot(&self) -> Snapshot {
+        Snapshot::new(self.current.clone(), self.cursor)
+    }
+
+    /// Replaces the buffer being drawn and the cursor position with the ones of `snapshot`.
+    ///
+    /// The next flush prints every cell, so the terminal looks exactly like when the snapshot
+    /// was taken, no matter what was printed in between.
+    pub fn restore(&mut self, snapshot: Snapshot) {
+        let (buffer, cursor) = snapshot.into_parts();
+        self.current = buffer;
+        self.current.damage_all();
+        self.cursor = cursor;
+        self.invalidate();
+    }
+
+    /// Prints the cells which changed since the last flush to `writer`, see [`flush`](fn.flush.html).
+    ///
+    /// Only the [damaged](Buffer::damage) cells are compared with the buffer on the screen.
+    pub fn flush(&mut self, writer: &mut impl io::Write) -> io::Result<()> {
+        if self.layers.is_empty() {
+            self.composed = Buffer::default();
+            draw(writer, &mut self.current, &mut self.previous, self.cursor)?;
+        } else {
+            self.compose();
+            draw(writer, &mut self.composed, &mut self.previous, self.cursor)?;
+            for (_, layer) in &mut self.layers {
+                layer.buffer_mut().reset_damage();
+            }
+        }
+        self.current.reset_damage();
+        Ok(())
+    }
+
+    // draws the damaged cells of the buffer and the layers into the composed buffer
+    fn compose(&mut self) {
+        if self.composed.size() != self.current.size() {
+            self.composed = Buffer::new(self.current.width, self.current.height);
+            self.current.damage_all();
+        }
+
+        // everything which changed is marked as damage of the buffer below the layers
+        for (_, layer) in &mut self.layers {
+            let state = layer.state();
+            if state != layer.drawn {
+                damage_area(&mut self.current, layer.drawn);
+                damage_area(&mut self.current, state);
+                layer.drawn = state;
+            }
+            if let Some(((x, y, _, _), _)) = state {
+                for (row, columns) in layer.buffer().damage() {
+                    let start = columns.start.saturating_add(x);
+                    self.current
+                        .damage_line(row.saturating_add(y), start..columns.end.saturating_add(x));
+                }
+            }
+        }
+
+        let mut layers = self
+            .layers
+            .iter()
+            .filter_map(|(_, layer)| layer.drawn.map(|(_, priority)| (priority, layer)))
+            .collect::<Vec<_>>();
+        layers.sort_by_key(|&(priority, _)| priority);
+
+        let width = self.current.width as usize;
+        for (y, columns) in self.current.damage() {
+            let start = y as usize * width;
+            let range = start + columns.start as usize..start + columns.end as usize;
+            self.composed.cells[range.clone()].clone_from_slice(&self.current.cells[range]);
+
+            for &(_, layer) in &layers {
+                let (x, top) = layer.position();
+                let row = match y.checked_sub(top) {
+                    Some(row) if row < layer.buffer().height() => layer.buffer().line(row),
+                    _ => continue,
+                };
+                let first = columns.start.max(x);
+                let end = columns.end.min(x.saturating_add(row.len() as u16));
+                for column in first..end {
+                    self.composed.cells[start + column as usize]
+                        .clone_from(&row[(column - x) as usize]);
+                }
+            }
+            self.composed.damage_line(y, columns);
+        }
+    }
+}
+
+// marks the area of a layer as damaged
+fn damage_area(buffer: &mut Buffer, state: Option<LayerState>) {
+    if let Some(((x, y, width, height), _)) = state {
+        for row in y..y.saturating_add(height) {
+            buffer.damage_line(row, x..x.saturating_add(width));
+        }
+    }
+}
+
+// prints the damaged cells of `current` which differ from `previous` and updates `previous`
+fn draw(
+    writer: &mut impl io::Write,
+    current: &mut Buffer,
+    previous: &mut Buffer,
+    cursor: Option<(u16, u16)>,
+) -> io::Result<()> {
+    current.hash_lines();
+    if let Some(scroll) = Scroll::detect(current, previous) {
+        if supports_scroll_regions() {
+            scroll.write(writer)?;
+            scroll.apply(previous);
+            previous.hash_lines();
+            // the scrolled rows have to be compared again
+            for y in scroll.rows() {
+                current.damage_line(y, 0..current.width());
+            }
+            current.hash_lines();
+        }
+    }
+
+    write_diff(writer, &current.diff_damage(previous))?;
+    if let Some((x, y)) = cursor {
+        queue!(writer, MoveTo(x, y))?;
+    }
+    writer.flush()?;
+
+    if previous.size() == current.size() {
+        current.copy_damage_to(previous);
+    } else {
+        previous.clone_from(current);
+    }
+    current.reset_damage();
+    Ok(())
+}
+
+// the legacy Windows API can't scroll a part of the screen
+fn supports_scroll_regions() -> bool {
+    #[cfg(windows)]
+    return crate::ansi_support::supports_ansi();
+    #[cfg(not(windows))]
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::style::ContentStyle;
+
+    use super::Screen;
+
+    #[test]
+    fn test_screen_flush() {
+        let mut screen = Screen::new(3, 1);
+        let mut output = Vec::new();
+        screen.flush(&mut output).unwrap();
+        assert_eq!(output, b"\x1B[1;1H   ");
+
+        output.clear();
+        screen.buffer_mut().print(1, 0, "a", ContentStyle::new());
+        screen.set_cursor(Some((0, 0)));
+        screen.flush(&mut output).unwrap();
+        assert_eq!(output, b"\x1B[1;2Ha\x1B[1;1H");
+        assert_eq!(screen.previous(), screen.buffer());
+
+        output.clear();
+        screen.set_cursor(None);
+        screen.flush(&mut output).unwrap();
+        assert!(output.is_empty());
+
+        // a damaged cell which didn't change isn't printed
+        screen.buffer_mut().print(1, 0, "a", ContentStyle::new());
+        assert_eq!(screen.buffer().damage().count(), 1);
+        screen.flush(&mut output).unwrap();
+        assert!(output.is_empty());
+        assert_eq!(screen.buffer().damage().count(), 0);
+
+        screen.resize(2, 1);
+        screen.flush(&mut output).unwrap();
+        assert_eq!(output, b"\x1B[1;1H a");
+    }
+
+    #[test]
+    fn test_screen_scrolls() {
+        let mut screen = Screen::new(1, 4);
+        let mut output = Vec::new();
+        for (y, symbol) in ["a", "b", "c", "d"].iter().enumerate() {
+            screen
+                .buffer_mut()
+                .print(0, y as u16, symbol, ContentStyle::new());
+        }
+        screen.flush(&mut output).unwrap();
+
+        output.clear();
+        screen.buffer_mut().scroll_up(0..4, 1);
+        screen.buffer_mut().print(0, 3, "e", ContentStyle::new());
+        screen.flush(&mut output).unwrap();
+        assert_eq!(output, b"\x1B[1;4r\x1B[1S\x1B[r\x1B[4;1He");
+        assert_eq!(screen.previous(), screen.buffer());
+    }
+
+    #[test]
+    fn test_screen_restore() {
+        let mut screen = Screen::new(2, 1);
+        screen.buffer_mut().print(0, 0, "ab", ContentStyle::new());
+        screen.set_cursor(Some((1, 0)));
+        let snapshot = screen.snapshot();
+        screen.flush(&mut Vec::new()).unwrap();
+
+        screen.buffer_mut().clear();
+        screen.set_cursor(None);
+        screen.restore(snapshot);
+
+        let mut output = Vec::new();
+        screen.flush(&mut output).unwrap();
+        assert_eq!(output, b"\x1B[1;1Hab\x1B[1;2H");
+    }
+
+    #[test]
+    fn test_screen_layers() {
+        let mut screen = Screen::new(4, 2);
+        screen.buffer_mut().print(0, 0, "abcd", ContentStyle::new());
+        screen.buffer_mut().print(0, 1, "efgh", ContentStyle::new());
+        screen.flush(&mut Vec::new()).unwrap();
+
+        let popup = screen.add_layer((1, 0), (2, 1), 1);
+        screen
+            .layer_mut(popup)
+            .unwrap()
+            .buffer_mut()
+            .print(0, 0, "xy", ContentStyle::new());
+        let toast = screen.add_layer((2, 0), (1, 2), 0);
+        screen
+            .layer_mut(toast)
+            .unwrap()
+            .buffer_mut()
+            .print(0, 1, "z", ContentStyle::new());
+
+        let mut output = Vec::new();
+        screen.flush(&mut output).unwrap();
+        // the popup is on top of the toast
+        assert_eq!(output, b"\x1B[1;2Hxy\x1B[2;3Hz");
+        assert_eq!(screen.previous().to_text(), "axyd\nefzh");
+        assert_eq!(screen.buffer().to_text(), "abcd\nefgh");
+
+        output.clear();
+        let layer = screen.layer_mut(popup).unwrap();
+        layer.set_position((2, 1));
+        layer.buffer_mut().print(0, 0, "X", ContentStyle::new());
+        screen.flush(&mut output).unwrap();
+        assert_eq!(screen.previous().to_text(), "ab d\nefXy");
+        assert_eq!(output, b"\x1B[1;2Hb \x1B[2;3HXy");
+
+        output.clear();
+        screen.layer_mut(toast).unwrap().set_visible(false);
+        screen.remove_layer(popup).unwrap();
+        assert!(screen.layer(popup).is_none());
+        screen.flush(&mut output).unwrap();
+        assert_eq!(screen.previous().to_text(), "abcd\nefgh");
+
+        output.clear();
+        screen.remove_layer(toast).unwrap();
+        screen.buffer_mut().print(3, 1, "!", ContentStyle::new());
+        screen.flush(&mut output).unwrap();
+        assert_eq!(output, b"\x1B[2;4H!");
+    }
+}
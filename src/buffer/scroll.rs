@@ -0,0 +1,156 @@
+use std::{io, ops::Range};
+
+use crate::{
+    queue,
+    terminal::{ResetScrollRegion, ScrollDown, ScrollUp, SetScrollRegion},
+};
+
+use super::Buffer;
+
+/// Scrolling has to save printing at least this many rows to be worth the escape sequences.
+const MIN_SAVED_ROWS: usize = 2;
+
+/// Rows of the previous buffer which moved up or down in the current one.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(super) struct Scroll {
+    rows: Range<u16>,
+    // positive if the rows moved up
+    lines: i32,
+}
+
+impl Scroll {
+    /// Finds the scroll within the damaged rows of `current` which leaves the fewest rows to
+    /// print, if any saves enough of them.
+    pub(super) fn detect(current: &Buffer, previous: &Buffer) -> Option<Scroll> {
+        if current.size() != previous.size() {
+            return None;
+        }
+
+        let changed = current
+            .damage()
+            .map(|(y, _)| y)
+            .filter(|&y| !current.line_eq(y, previous, y))
+            .collect::<Vec<_>>();
+        let (top, bottom) = match (changed.first(), changed.last()) {
+            (Some(&top), Some(&bottom)) => (top, bottom + 1),
+            _ => return None,
+        };
+        if changed.len() <= MIN_SAVED_ROWS {
+            return None;
+        }
+
+        let mut blank = Buffer::new(current.width, 1);
+        blank.hash_lines();
+
+        let height = (bottom - top) as i32;
+        let mut best: Option<(usize, i32)> = None;
+        for lines in (1..height).flat_map(|lines| [lines, -lines]) {
+            // the rows which still have to be printed after scrolling
+            let mut cost = 0;
+            for y in top..bottom {
+                let source = y as i32 + lines;
+                let matches = if (top as i32..bottom as i32).contains(&source) {
+                    current.line_eq(y, previous, source as u16)
+                } else {
+                    current.line_eq(y, &blank, 0)
+                };
+                if !matches {
+                    cost += 1;
+                    if best.map_or(false, |(best, _)| cost >= best) {
+                        break;
+                    }
+                }
+            }
+            if best.map_or(true, |(best, _)| cost < best) {
+                best = Some((cost, lines));
+            }
+        }
+
+        match best {
+            Some((cost, lines)) if cost + MIN_SAVED_ROWS <= changed.len() => Some(Scroll {
+                rows: top..bottom,
+                lines,
+            }),
+            _ => None,
+        }
+    }
+
+    /// Scrolls the terminal, the cursor is moved to the top left corner.
+    pub(super) fn write(&self, writer: &mut impl io::Write) -> io::Result<()> {
+        queue!(writer, SetScrollRegion(self.rows.start, self.rows.end - 1))?;
+        if self.lines > 0 {
+            queue!(writer, ScrollUp(self.lines as u16))?;
+        } else {
+            queue!(writer, ScrollDown(-self.lines as u16))?;
+        }
+        queue!(writer, ResetScrollRegion)
+    }
+
+    /// Scrolls `buffer` like the terminal.
+    pub(super) fn apply(&self, buffer: &mut Buffer) {
+        if self.lines > 0 {
+            buffer.scroll_up(self.rows.clone(), self.lines as u16);
+        } else {
+            buffer.scroll_down(self.rows.clone(), -self.lines as u16);
+        }
+    }
+
+    /// Returns the rows which were scrolled.
+    pub(super) fn rows(&self) -> Range<u16> {
+        self.rows.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{buffer::Buffer, style::ContentStyle};
+
+    use super::Scroll;
+
+    fn buffer(lines: &[&str]) -> Buffer {
+        let mut buffer = Buffer::new(3, lines.len() as u16);
+        for (y, line) in lines.iter().enumerate() {
+            buffer.print(0, y as u16, line, ContentStyle::new());
+        }
+        buffer
+    }
+
+    #[test]
+    fn test_detect_scroll() {
+        let previous = buffer(&["top", "a", "b", "c", "d", "bar"]);
+
+        let current = buffer(&["top", "b", "c", "d", "e", "bar"]);
+        let scroll = Scroll::detect(&current, &previous).unwrap();
+        assert_eq!(
+            scroll,
+            Scroll {
+                rows: 1..5,
+                lines: 1
+            }
+        );
+
+        let mut scrolled = previous.clone();
+        scroll.apply(&mut scrolled);
+        assert_eq!(scrolled, buffer(&["top", "b", "c", "d", "", "bar"]));
+
+        let mut output = Vec::new();
+        scroll.write(&mut output).unwrap();
+        assert_eq!(output, b"\x1B[2;5r\x1B[1S\x1B[r");
+
+        let current = buffer(&["", "", "top", "a", "b", "c"]);
+        let scroll = Scroll::detect(&current, &previous).unwrap();
+        assert_eq!(
+            scroll,
+            Scroll {
+                rows: 0..6,
+                lines: -2
+            }
+        );
+
+        // not worth it for less than two rows
+        let current = buffer(&["top", "b", "x", "y", "z", "bar"]);
+        assert_eq!(Scroll::detect(&current, &previous), None);
+        let current = buffer(&["top", "a", "c", "d", "d", "bar"]);
+        assert_eq!(Scroll::detect(&current, &previous), None);
+    }
+}
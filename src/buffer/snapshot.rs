@@ -0,0 +1,324 @@
+use std::io;
+
+use crate::style::{Attribute, Attributes, Color, ContentStyle, UnderlineStyle};
+
+use super::{Buffer, Cell};
+
+const MAGIC: &[u8] = b"CTSB\x02";
+
+/// The named colors in the order of their tags, `Rgb` and `AnsiValue` follow.
+const NAMED_COLORS: [Color; 17] = [
+    Color::Reset,
+    Color::Black,
+    Color::DarkGrey,
+    Color::Red,
+    Color::DarkRed,
+    Color::Green,
+    Color::DarkGreen,
+    Color::Yellow,
+    Color::DarkYellow,
+    Color::Blue,
+    Color::DarkBlue,
+    Color::Magenta,
+    Color::DarkMagenta,
+    Color::Cyan,
+    Color::DarkCyan,
+    Color::White,
+    Color::Grey,
+];
+
+/// The content of a [`Screen`](struct.Screen.html) and its cursor, which can be saved as bytes
+/// and restored later.
+///
+/// # Examples
+///
+/// ```no_run
+/// use std::io;
+/// use crossterm::buffer::{Screen, Snapshot};
+///
+/// fn main() -> io::Result<()> {
+///     let mut screen = Screen::new(80, 24);
+///     // ... draw the user interface
+///
+///     let bytes = screen.snapshot().to_bytes();
+///     // ... temporarily drop to a shell, or write `bytes` to a file
+///
+///     screen.restore(Snapshot::from_bytes(&bytes)?);
+///     screen.flush(&mut io::stdout())
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Snapshot {
+    buffer: Buffer,
+    cursor: Option<(u16, u16)>,
+}
+
+impl Snapshot {
+    /// Creates a snapshot of `buffer` with the cursor at `cursor`.
+    pub fn new(buffer: Buffer, cursor: Option<(u16, u16)>) -> Snapshot {
+        Snapshot { buffer, cursor }
+    }
+
+    /// Returns the saved buffer.
+    pub fn buffer(&self) -> &Buffer {
+        &self.buffer
+    }
+
+    /// Returns the saved cursor position (column, row).
+    pub fn cursor(&self) -> Option<(u16, u16)> {
+        self.cursor
+    }
+
+    /// Returns the saved buffer and cursor position.
+    pub fn into_parts(self) -> (Buffer, Option<(u16, u16)>) {
+        (self.buffer, self.cursor)
+    }
+
+    /// Encodes the snapshot as bytes, in a format only meant to be read by
+    /// [`Snapshot::from_bytes`].
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = MAGIC.to_vec();
+        bytes.extend_from_slice(&self.buffer.width.to_le_bytes());
+        bytes.extend_from_slice(&self.buffer.height.to_le_bytes());
+        match self.cursor {
+            Some((x, y)) => {
+                bytes.push(1);
+                bytes.extend_from_slice(&x.to_le_bytes());
+                bytes.extend_from_slice(&y.to_le_bytes());
+            }
+            None => bytes.push(0),
+        }
+
+        // the style is only written if it differs from the previous cell
+        let mut style = ContentStyle::default();
+        for cell in &self.buffer.cells {
+            let new_style = cell.style != style;
+            write_varint(&mut bytes, cell.symbol.len() << 1 | new_style as usize);
+            bytes.extend_from_slice(cell.symbol.as_bytes());
+            if new_style {
+                style = cell.style;
+                write_style(&mut bytes, &style);
+            }
+        }
+        bytes
+    }
+
+    /// Decodes a snapshot encoded by [`Snapshot::to_bytes`].
+    pub fn from_bytes(bytes: &[u8]) -> io::Result<Snapshot> {
+        let mut reader = Reader(bytes);
+        if reader.take(MAGIC.len())? != MAGIC {
+            return Err(invalid());
+        }
+
+        let width = reader.u16()?;
+        let height = reader.u16()?;
+        let cursor = match reader.u8()? {
+            0 => None,
+            1 => Some((reader.u16()?, reader.u16()?)),
+            _ => return Err(invalid()),
+        };
+
+        // every cell takes at least a byte, so a forged size can't allocate more than that
+        if width as usize * height as usize > reader.0.len() {
+            return Err(invalid());
+        }
+
+        let mut buffer = Buffer::new(width, height);
+        let mut style = ContentStyle::default();
+        for cell in &mut buffer.cells {
+            let header = reader.varint()?;
+            let symbol = std::str::from_utf8(reader.take(header >> 1)?).map_err(|_| invalid())?;
+            if header & 1 != 0 {
+                style = reader.style()?;
+            }
+            *cell = Cell::new(symbol, style);
+        }
+        if !reader.0.is_empty() {
+            return Err(invalid());
+        }
+
+        Ok(Snapshot { buffer, cursor })
+    }
+}
+
+fn write_varint(bytes: &mut Vec<u8>, mut value: usize) {
+    while value >= 0x80 {
+        bytes.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    bytes.push(value as u8);
+}
+
+fn write_style(bytes: &mut Vec<u8>, style: &ContentStyle) {
+    for color in [
+        style.foreground_color,
+        style.background_color,
+        style.underline_color,
+    ] {
+        match color {
+            None => bytes.push(0),
+            Some(Color::Rgb { r, g, b }) => bytes.extend_from_slice(&[18, r, g, b]),
+            Some(Color::AnsiValue(value)) => bytes.extend_from_slice(&[19, value]),
+            Some(color) => {
+                let tag = NAMED_COLORS.iter().position(|named| *named == color);
+                bytes.push(tag.map_or(0, |tag| tag as u8 + 1));
+            }
+        }
+    }
+
+    // the attributes by their position, which doesn't depend on the SGR codes
+    let attributes = Attribute::iterator()
+        .enumerate()
+        .filter(|&(_, attribute)| style.attributes.has(attribute))
+        .fold(0u32, |bits, (index, _)| bits | 1 << index);
+    bytes.extend_from_slice(&attributes.to_le_bytes());
+
+    let underline = UnderlineStyle::ALL
+        .iter()
+        .position(|&underline| style.underline == Some(underline));
+    bytes.push(underline.map_or(0, |index| index as u8 + 1));
+}
+
+struct Reader<'a>(&'a [u8]);
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> io::Result<&'a [u8]> {
+        if self.0.len() < len {
+            return Err(invalid());
+        }
+        let (taken, rest) = self.0.split_at(len);
+        self.0 = rest;
+        Ok(taken)
+    }
+
+    fn u8(&mut self) -> io::Result<u8> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u16(&mut self) -> io::Result<u16> {
+        let bytes = self.take(2)?;
+        Ok(u16::from_le_bytes([bytes[0], bytes[1]]))
+    }
+
+    fn varint(&mut self) -> io::Result<usize> {
+        let mut value = 0usize;
+        for shift in (0..usize::BITS).step_by(7) {
+            let byte = self.u8()?;
+            value |= ((byte & 0x7F) as usize) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        Err(invalid())
+    }
+
+    fn color(&mut self) -> io::Result<Option<Color>> {
+        Ok(match self.u8()? {
+            0 => None,
+            18 => {
+                let rgb = self.take(3)?;
+                Some(Color::Rgb {
+                    r: rgb[0],
+                    g: rgb[1],
+                    b: rgb[2],
+                })
+            }
+            19 => Some(Color::AnsiValue(self.u8()?)),
+            tag => Some(*NAMED_COLORS.get(tag as usize - 1).ok_or_else(invalid)?),
+        })
+    }
+
+    fn style(&mut self) -> io::Result<ContentStyle> {
+        let foreground_color = self.color()?;
+        let background_color = self.color()?;
+        let underline_color = self.color()?;
+
+        let bytes = self.take(4)?;
+        let bits = u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+        let mut attributes = Attributes::default();
+        for (index, attribute) in Attribute::iterator().enumerate() {
+            if bits & 1 << index != 0 {
+                attributes.set(attribute);
+            }
+        }
+
+        let underline = match self.u8()? {
+            0 => None,
+            tag => Some(
+                *UnderlineStyle::ALL
+                    .get(tag as usize - 1)
+                    .ok_or_else(invalid)?,
+            ),
+        };
+
+        Ok(ContentStyle {
+            foreground_color,
+            background_color,
+            underline_color,
+            attributes,
+            underline,
+        })
+    }
+}
+
+fn invalid() -> io::Error {
+    crate::Error::ParseError("The snapshot is invalid or was saved by an incompatible version.")
+        .into()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io;
+
+    use crate::{
+        buffer::Buffer,
+        style::{Color, ContentStyle, Stylize, UnderlineStyle},
+    };
+
+    use super::Snapshot;
+
+    #[test]
+    fn test_snapshot_round_trip() {
+        let mut buffer = Buffer::new(4, 2);
+        buffer.print(0, 0, "ab", ContentStyle::new().red().on_dark_blue().bold());
+        buffer.print(
+            2,
+            0,
+            "ä",
+            ContentStyle::new().with(Color::Rgb { r: 1, g: 2, b: 3 }),
+        );
+        buffer.print(0, 1, "x", ContentStyle::new().underlined().italic());
+        buffer.print(
+            1,
+            1,
+            "y",
+            ContentStyle::new().underline_style(UnderlineStyle::Curly),
+        );
+        buffer.get_mut(3, 1).unwrap().symbol = "e\u{301}".repeat(40);
+
+        let snapshot = Snapshot::new(buffer, Some((3, 1)));
+        let bytes = snapshot.to_bytes();
+        assert!(bytes.starts_with(b"CTSB\x02\x04\0\x02\0\x01\x03\0\x01\0"));
+        assert_eq!(Snapshot::from_bytes(&bytes).unwrap(), snapshot);
+
+        let snapshot = Snapshot::new(Buffer::new(3, 0), None);
+        assert_eq!(snapshot.to_bytes(), b"CTSB\x02\x03\0\0\0\0");
+        assert_eq!(
+            Snapshot::from_bytes(&snapshot.to_bytes()).unwrap(),
+            snapshot
+        );
+    }
+
+    #[test]
+    fn test_snapshot_rejects_invalid_bytes() {
+        let bytes = Snapshot::new(Buffer::new(2, 2), None).to_bytes();
+        assert!(Snapshot::from_bytes(&bytes[..bytes.len() - 1]).is_err());
+        assert!(Snapshot::from_bytes(&[&bytes[..], b"\0"].concat()).is_err());
+        assert!(Snapshot::from_bytes(b"CTSB\x02").is_err());
+        assert!(Snapshot::from_bytes(b"").is_err());
+
+        // the size of far more cells than there are bytes
+        let e = Snapshot::from_bytes(b"CTSB\x02\xFF\xFF\xFF\xFF\x00\x00").unwrap_err();
+        assert_eq!(e.kind(), io::ErrorKind::InvalidData);
+    }
+}
@@ -0,0 +1,418 @@
+//! # Capabilities
+//!
+//! Terminals differ in which escape sequences they understand. The `capabilities` module
+//! describes the optional features of a terminal with [`Capabilities`](struct.Capabilities.html).
+//! Commands which write sequences that not every terminal supports consult the installed
+//! capabilities and pick another encoding, degrade or silently do nothing.
+//!
+//! By default crossterm assumes a modern, xterm compatible terminal which supports everything.
+//! Applications that know better can install their own capabilities with
+//! [`set`](fn.set.html).
+//!
+//! ```no_run
+//! use crossterm::capabilities::{self, Capabilities};
+//!
+//! capabilities::set(Capabilities {
+//!     underline_color: false,
+//!     ..Capabilities::default()
+//! });
+//! ```
+//!
+//! [`from_env`](fn.from_env.html) applies the usual heuristics on `TERM`, `COLORTERM` and
+//! friends, e.g. to detect truecolor support, multiplexers or dumb terminals.
+//!
+//! Serial consoles and hardware terminals are better served by the
+//! [`conservative`](struct.Capabilities.html#method.conservative) profile, which `from_env`
+//! picks for a dumb or serial `TERM`, and which can be installed explicitly:
+//!
+//! ```no_run
+//! use crossterm::capabilities::{self, Capabilities};
+//!
+//! capabilities::set(Capabilities::conservative());
+//! ```
+//!
+//! Likewise, [`screen`](struct.Capabilities.html#method.screen) is a profile for GNU screen,
+//! which `from_env` picks inside of it.
+//!
+//! The quirks of individual terminals, e.g. which graphics protocols they support, are hints
+//! by the name of the terminal, which [`apply_terminal_hints`](fn.apply_terminal_hints.html)
+//! applies. Applications can add their own with
+//! [`add_terminal_hint`](fn.add_terminal_hint.html).
+
+use std::{cell::RefCell, io, time::Duration};
+
+use parking_lot::RwLock;
+
+use crate::style::{Attribute, Attributes};
+
+pub use self::env::{from_env, Environment, Multiplexer};
+pub use self::hints::{
+    add_terminal_hint, apply_terminal_hints, detect_terminal, outer_terminal, remove_terminal_hint,
+};
+
+mod env;
+mod hints;
+
+/// The optional features a terminal supports.
+///
+/// The [`Default`](#impl-Default) implementation assumes a modern terminal which supports
+/// everything, except for the graphics protocols.
+#[derive(Debug, PartialEq, Eq, Clone, Hash)]
+pub struct Capabilities {
+    /// The terminal can report mouse events with the SGR (`?1006`) encoding.
+    ///
+    /// If this and [`urxvt_mouse`](#structfield.urxvt_mouse) are both set, both encodings are
+    /// enabled and the terminal picks the last one it understands, which is SGR.
+    pub sgr_mouse: bool,
+    /// The terminal can report mouse events with the urxvt (`?1015`) encoding.
+    pub urxvt_mouse: bool,
+    /// The terminal understands `SGR 58`/`SGR 59` to set and reset the underline color.
+    ///
+    /// If not set, [`SetUnderlineColor`](../style/struct.SetUnderlineColor.html) does nothing.
+    pub underline_color: bool,
+    /// The terminal can display sixel graphics.
+    ///
+    /// Few terminals support them, so this isn't set by default. It can be detected with
+    /// [`terminal::supports_sixel`](../terminal/fn.supports_sixel.html). If not set,
+    /// [`PrintSixel`](../graphics/struct.PrintSixel.html) does nothing.
+    pub sixel: bool,
+    /// The terminal understands the kitty graphics protocol, e.g. kitty, WezTerm or Ghostty.
+    ///
+    /// Only consulted by [`graphics::best_protocol`](../graphics/fn.best_protocol.html), the
+    /// kitty graphics commands are always written.
+    pub kitty_graphics: bool,
+    /// The terminal understands the inline image protocol of iTerm2.
+    ///
+    /// Only consulted by [`graphics::best_protocol`](../graphics/fn.best_protocol.html).
+    pub inline_images: bool,
+    /// The terminal shows 24 bit colors.
+    ///
+    /// If not set, [`Color::Rgb`](../style/enum.Color.html#variant.Rgb) colors are replaced
+    /// with the closest of the 256 colors. It can be detected with
+    /// [`detect_truecolor`](fn.detect_truecolor.html).
+    pub truecolor: bool,
+    /// The terminal shows the 256 colors of
+    /// [`Color::AnsiValue`](../style/enum.Color.html#variant.AnsiValue).
+    ///
+    /// If not set, foreground and background colors are replaced with the closest of the 16 base
+    /// colors and written with the original `SGR 30`-`37`, `40`-`47` and, with
+    /// [`bright_colors`](#structfield.bright_colors), their bright variants.
+    pub ansi256: bool,
+    /// The terminal shows the bright base colors of `SGR 90`-`97` and `100`-`107`.
+    ///
+    /// Only consulted without [`ansi256`](#structfield.ansi256). If not set, the bright colors
+    /// are written as their dark variants, as hardware terminals like the VT100 don't know these
+    /// parameters.
+    pub bright_colors: bool,
+    /// The terminal understands `OSC 8` hyperlinks.
+    ///
+    /// If not set, [`PrintHyperlink`](../style/struct.PrintHyperlink.html) prints the text as
+    /// configured with [`set_hyperlink_fallback`](../style/fn.set_hyperlink_fallback.html).
+    pub hyperlinks: bool,
+    /// The text attributes the terminal renders.
+    ///
+    /// Only the attributes which turn something on are consulted. Instead of the others,
+    /// [`SetAttribute`](../style/struct.SetAttribute.html) writes their fallback from the
+    /// [`AttributeFallbacks`](../style/struct.AttributeFallbacks.html), or nothing.
+    pub attributes: Attributes,
+    /// The terminal takes `SGR 21` for turning bold off.
+    ///
+    /// Most terminals follow ECMA-48 and draw a double underline instead, so this isn't set by
+    /// default, and [`Attribute::NoBold`](../style/enum.Attribute.html#variant.NoBold) is written
+    /// as [`Attribute::NormalIntensity`](../style/enum.Attribute.html#variant.NormalIntensity)
+    /// (`SGR 22`), which turns dim text off as well. The hints of
+    /// [`apply_terminal_hints`](fn.apply_terminal_hints.html) set it for rxvt-unicode.
+    pub sgr21_no_bold: bool,
+    /// The terminal has an alternate screen.
+    ///
+    /// If not set, [`EnterAlternateScreen`](../terminal/struct.EnterAlternateScreen.html) and
+    /// [`LeaveAlternateScreen`](../terminal/struct.LeaveAlternateScreen.html) do nothing, so
+    /// the application draws on the main screen.
+    pub alternate_screen: bool,
+    /// The terminal understands synchronized output (DEC private mode 2026).
+    ///
+    /// Terminals which don't support it ignore the mode, so it's set by default. It can be
+    /// detected with
+    /// [`supports_synchronized_output`](../terminal/fn.supports_synchronized_output.html). If
+    /// not set, [`SynchronizedWriter`](../struct.SynchronizedWriter.html) writes the frames as
+    /// they are.
+    pub synchronized_output: bool,
+    /// The multiplexer the application runs in, see
+    /// [`detect_multiplexer`](fn.detect_multiplexer.html).
+    ///
+    /// Multiplexers handle or drop some sequences meant for the terminal they run in, what the
+    /// commands which write them do instead is up to the `multiplexer_policy`.
+    pub multiplexer: Option<Multiplexer>,
+    /// What the commands which write the clipboard, the title or graphics do inside the
+    /// `multiplexer`.
+    pub multiplexer_policy: MultiplexerPolicy,
+    /// How long to wait for the reply to a query, e.g. the primary device attributes (DA1) or
+    /// the cursor position (CPR).
+    ///
+    /// `None` if the terminal isn't queried at all: functions which need a reply fail right away
+    /// with [`Error::UnsupportedCapability`](../enum.Error.html#variant.UnsupportedCapability),
+    /// instead of writing a query nobody answers and waiting for the timeout.
+    pub query_timeout: Option<Duration>,
+}
+
+impl Default for Capabilities {
+    fn default() -> Self {
+        Capabilities {
+            sgr_mouse: true,
+            urxvt_mouse: true,
+            underline_color: true,
+            sixel: false,
+            kitty_graphics: false,
+            inline_images: false,
+            truecolor: true,
+            ansi256: true,
+            bright_colors: true,
+            hyperlinks: true,
+            attributes: Attributes::all(),
+            sgr21_no_bold: false,
+            alternate_screen: true,
+            synchronized_output: true,
+            multiplexer: None,
+            multiplexer_policy: MultiplexerPolicy::default(),
+            query_timeout: Some(DEFAULT_QUERY_TIMEOUT),
+        }
+    }
+}
+
+impl Capabilities {
+    /// Returns a conservative profile for serial consoles and hardware terminals, e.g. a VT100
+    /// on the other end of a slow serial line.
+    ///
+    /// Queries get a short timeout of 200 milliseconds, there is no alternate screen, mouse,
+    /// hyperlinks or graphics, colors are restricted to the 8 base colors, and only bold,
+    /// underlined, blinking and reversed text is rendered.
+    /// [`from_env`](fn.from_env.html) suggests it for serial `TERM`s like `vt100` or `vt220`, and
+    /// a variant without any queries or attributes for dumb terminals.
+    pub fn conservative() -> Capabilities {
+        Capabilities {
+            sgr_mouse: false,
+            urxvt_mouse: false,
+            underline_color: false,
+            sixel: false,
+            kitty_graphics: false,
+            inline_images: false,
+            truecolor: false,
+            ansi256: false,
+            bright_colors: false,
+            hyperlinks: false,
+            attributes: Attributes::from(
+                &[
+                    Attribute::Bold,
+                    Attribute::Underlined,
+                    Attribute::SlowBlink,
+                    Attribute::Reverse,
+                ][..],
+            ),
+            sgr21_no_bold: false,
+            alternate_screen: false,
+            synchronized_output: false,
+            multiplexer: None,
+            multiplexer_policy: MultiplexerPolicy::default(),
+            query_timeout: Some(CONSERVATIVE_QUERY_TIMEOUT),
+        }
+    }
+
+    /// Returns a profile for applications inside GNU screen.
+    ///
+    /// Screen renders the 256 colors, but no 24 bit colors, underline colors, styled underlines
+    /// or hyperlinks, and reports the mouse only in the original X10 encoding, which is limited
+    /// to 223 columns and rows. The graphics protocols don't make it through, and the clipboard
+    /// and title sequences are wrapped in its passthrough sequence, see
+    /// [`ScreenPassthrough`](../struct.ScreenPassthrough.html).
+    /// [`from_env`](fn.from_env.html) suggests it when `STY` is set or `TERM` starts with
+    /// `screen` outside of tmux.
+    pub fn screen() -> Capabilities {
+        Capabilities {
+            sgr_mouse: false,
+            urxvt_mouse: false,
+            underline_color: false,
+            truecolor: false,
+            hyperlinks: false,
+            attributes: Attributes::all()
+                .without(Attribute::DoubleUnderlined)
+                .without(Attribute::Undercurled)
+                .without(Attribute::Underdotted)
+                .without(Attribute::Underdashed),
+            synchronized_output: false,
+            multiplexer: Some(Multiplexer::Screen),
+            ..Capabilities::default()
+        }
+    }
+}
+
+/// What the commands which write sequences a multiplexer would handle or drop itself, the
+/// clipboard, the title and graphics, do inside it, see
+/// [`Capabilities::multiplexer`](struct.Capabilities.html#structfield.multiplexer).
+///
+/// How often commands were wrapped or suppressed is reported by
+/// [`metrics`](../fn.metrics.html).
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub enum MultiplexerPolicy {
+    /// Wraps the sequences in the passthrough sequence of the multiplexer, so they reach the
+    /// terminal it runs in, like [`tmux_passthrough`](../fn.tmux_passthrough.html) and
+    /// [`screen_passthrough`](../fn.screen_passthrough.html). They're written as they are if
+    /// the multiplexer has none.
+    Passthrough,
+    /// Doesn't write the sequences.
+    Suppress,
+    /// Writes the sequences as they are, for the multiplexer to handle or drop.
+    Direct,
+}
+
+impl Default for MultiplexerPolicy {
+    fn default() -> Self {
+        MultiplexerPolicy::Passthrough
+    }
+}
+
+/// How long queries wait for a reply by default.
+const DEFAULT_QUERY_TIMEOUT: Duration = Duration::from_millis(2000);
+/// How long queries wait for a reply in the conservative profile.
+const CONSERVATIVE_QUERY_TIMEOUT: Duration = Duration::from_millis(200);
+
+static CAPABILITIES: RwLock<Option<Capabilities>> = parking_lot::const_rwlock(None);
+
+thread_local! {
+    /// The capabilities of the terminal other than the one of the process the commands are
+    /// written for, see [`for_other_terminal`].
+    static OTHER_TERMINAL: RefCell<Option<Capabilities>> = RefCell::new(None);
+}
+
+/// Returns the capabilities commands currently consult.
+///
+/// These are the ones installed with [`set`](fn.set.html), or the
+/// [default](struct.Capabilities.html#impl-Default) ones if none were installed.
+pub fn current() -> Capabilities {
+    query(Capabilities::clone)
+}
+
+/// Installs the capabilities commands consult from now on.
+pub fn set(capabilities: Capabilities) {
+    *CAPABILITIES.write() = Some(capabilities);
+}
+
+/// Detects whether the terminal shows 24 bit colors.
+///
+/// Behind multiplexers and SSH, `COLORTERM` often doesn't make it to the application or
+/// describes another terminal, so with the `events` feature the terminal is asked on Unix: a 24
+/// bit color is set and read back with a DECRQSS request, and the colors and attributes reported
+/// before are set again. If the terminal doesn't answer, this falls back to `COLORTERM` and
+/// friends (see [`Environment::truecolor`](struct.Environment.html#structfield.truecolor)) and,
+/// with the `terminfo` feature, the `RGB` and `Tc` flags of the terminfo entry.
+///
+/// # Examples
+///
+/// ```no_run
+/// use crossterm::capabilities;
+///
+/// let mut capabilities = capabilities::from_env().capabilities();
+/// capabilities.truecolor = capabilities::detect_truecolor();
+/// capabilities::set(capabilities);
+/// ```
+pub fn detect_truecolor() -> bool {
+    #[cfg(all(unix, feature = "events"))]
+    if let Ok(Some(truecolor)) = crate::terminal::sys::query_truecolor() {
+        return truecolor;
+    }
+
+    #[cfg(feature = "terminfo")]
+    if let Some(info) = crate::terminfo::current() {
+        if info.flag("RGB") || info.flag("Tc") {
+            return true;
+        }
+    }
+
+    from_env().truecolor
+}
+
+/// Detects the multiplexer the application runs in.
+///
+/// The environment variables are inherited by processes which run elsewhere, e.g. after `ssh`
+/// from inside tmux, so with the `events` feature the terminal is asked on Unix for its
+/// secondary device attributes (DA2), which tmux and GNU screen answer with their own
+/// identifiers. Otherwise or if it doesn't answer, this falls back to
+/// [`Environment::multiplexer`](struct.Environment.html#structfield.multiplexer).
+///
+/// # Examples
+///
+/// ```no_run
+/// use crossterm::capabilities;
+///
+/// let mut capabilities = capabilities::from_env().capabilities();
+/// capabilities.multiplexer = capabilities::detect_multiplexer();
+/// capabilities::set(capabilities);
+/// ```
+pub fn detect_multiplexer() -> Option<Multiplexer> {
+    let env = from_env().multiplexer;
+
+    #[cfg(all(unix, feature = "events"))]
+    if let Ok(Some(attributes)) = crate::terminal::sys::query_secondary_device_attributes() {
+        return match attributes.first() {
+            Some(84) => Some(Multiplexer::Tmux),
+            Some(83) => Some(Multiplexer::Screen),
+            // Zellij answers like the terminal it runs in
+            _ => env.filter(|multiplexer| *multiplexer == Multiplexer::Zellij),
+        };
+    }
+
+    env
+}
+
+/// Runs `f` with the commands it writes rendered for another terminal, e.g. a
+/// [`RemoteTerminal`](../terminal/struct.RemoteTerminal.html), which has `capabilities`.
+///
+/// The commands consult these capabilities instead of the installed ones, and neither the
+/// terminfo entry nor the state crossterm tracks about the terminal of the process. The previous
+/// setting is restored afterwards, also when `f` panics.
+pub(crate) fn for_other_terminal<T>(capabilities: &Capabilities, f: impl FnOnce() -> T) -> T {
+    struct Restore(Option<Capabilities>);
+
+    impl Drop for Restore {
+        fn drop(&mut self) {
+            let previous = self.0.take();
+            OTHER_TERMINAL.with(|other| *other.borrow_mut() = previous);
+        }
+    }
+
+    let previous = OTHER_TERMINAL.with(|other| other.replace(Some(capabilities.clone())));
+    let _restore = Restore(previous);
+    f()
+}
+
+/// Returns whether the commands are rendered for another terminal, see [`for_other_terminal`].
+pub(crate) fn is_other_terminal() -> bool {
+    OTHER_TERMINAL.with(|other| other.borrow().is_some())
+}
+
+/// Returns what `f` returns for the installed capabilities, `None` if none were installed.
+pub(crate) fn query_installed<T>(f: impl FnOnce(&Capabilities) -> T) -> Option<T> {
+    if is_other_terminal() {
+        return Some(query(f));
+    }
+    CAPABILITIES.read().as_ref().map(f)
+}
+
+/// Runs `f` with the current capabilities without cloning them.
+pub(crate) fn query<T>(f: impl FnOnce(&Capabilities) -> T) -> T {
+    OTHER_TERMINAL.with(|other| match &*other.borrow() {
+        Some(capabilities) => f(capabilities),
+        None => match &*CAPABILITIES.read() {
+            Some(capabilities) => f(capabilities),
+            None => f(&Capabilities::default()),
+        },
+    })
+}
+
+/// Returns how long to wait for the reply to a query, an error if the terminal isn't queried.
+#[cfg_attr(not(all(unix, feature = "events")), allow(dead_code))]
+pub(crate) fn query_timeout() -> io::Result<Duration> {
+    query(|c| c.query_timeout).ok_or_else(|| {
+        crate::Error::UnsupportedCapability("The terminal is not queried, see `query_timeout`.")
+            .into()
+    })
+}
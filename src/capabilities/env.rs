@@ -0,0 +1,305 @@
+use std::env;
+
+use super::{apply_terminal_hints, Capabilities};
+use crate::style::{Attribute, Attributes};
+
+/// A terminal multiplexer, which sits between the application and the terminal emulator.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub enum Multiplexer {
+    /// tmux, detected by `TMUX`.
+    Tmux,
+    /// GNU screen, detected by `STY` or a `screen` `TERM` outside of tmux.
+    Screen,
+    /// Zellij, detected by `ZELLIJ`.
+    Zellij,
+}
+
+/// What the environment variables tell about the terminal, see [`from_env`](fn.from_env.html).
+///
+/// These are heuristics: the variables describe the terminal the session was started in, which
+/// isn't necessarily the one the application talks to, e.g. after `ssh` or inside a multiplexer.
+#[derive(Debug, PartialEq, Eq, Clone, Hash, Default)]
+pub struct Environment {
+    /// The value of `TERM`.
+    pub term: Option<String>,
+    /// The value of `TERM_PROGRAM`, which some terminal emulators set to their name.
+    pub term_program: Option<String>,
+    /// The terminal supports 24 bit colors.
+    ///
+    /// `COLORTERM` is `truecolor` or `24bit`, `TERM` ends with `-direct`, or `TERM_PROGRAM`
+    /// names a terminal known to support them.
+    pub truecolor: bool,
+    /// The terminal supports 256 colors, `TERM` contains `256color` or `truecolor` is set.
+    pub ansi256: bool,
+    /// The multiplexer the application runs in.
+    pub multiplexer: Option<Multiplexer>,
+    /// The terminal understands no escape sequences, `TERM` is `dumb` or, on Unix, not set.
+    pub dumb: bool,
+    /// The terminal is a serial console or hardware terminal, `TERM` names a DEC VT terminal
+    /// like `vt100` or `vt220`.
+    pub serial: bool,
+    /// The user asked for no colors with `NO_COLOR`.
+    pub no_color: bool,
+    /// The user asked for colors even if the output isn't a terminal, with `CLICOLOR_FORCE` or
+    /// `FORCE_COLOR`.
+    pub force_color: bool,
+    /// The session runs over SSH, `SSH_CONNECTION` or `SSH_TTY` is set.
+    pub ssh: bool,
+}
+
+impl Environment {
+    /// Applies the heuristics to the variables returned by `var`.
+    ///
+    /// Variables which are set to an empty string count as not set.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossterm::capabilities::{Environment, Multiplexer};
+    ///
+    /// let env = Environment::from_vars(|name| match name {
+    ///     "TERM" => Some("screen-256color".to_string()),
+    ///     "TMUX" => Some("/tmp/tmux-1000/default,1234,0".to_string()),
+    ///     _ => None,
+    /// });
+    ///
+    /// assert_eq!(env.multiplexer, Some(Multiplexer::Tmux));
+    /// assert!(env.ansi256);
+    /// assert!(!env.truecolor);
+    /// ```
+    pub fn from_vars(var: impl Fn(&str) -> Option<String>) -> Environment {
+        let var = |name: &str| var(name).filter(|value| !value.is_empty());
+
+        let term = var("TERM");
+        let term_program = var("TERM_PROGRAM");
+        let term_name = term.as_deref().unwrap_or_default();
+
+        let truecolor = matches!(
+            var("COLORTERM").as_deref(),
+            Some("truecolor") | Some("24bit")
+        ) || term_name.ends_with("-direct")
+            || matches!(
+                term_program.as_deref(),
+                Some("iTerm.app") | Some("WezTerm") | Some("vscode") | Some("ghostty")
+            );
+
+        let multiplexer = if var("TMUX").is_some() {
+            Some(Multiplexer::Tmux)
+        } else if var("STY").is_some() || term_name.starts_with("screen") {
+            Some(Multiplexer::Screen)
+        } else if var("ZELLIJ").is_some() {
+            Some(Multiplexer::Zellij)
+        } else {
+            None
+        };
+
+        Environment {
+            truecolor,
+            ansi256: truecolor || term_name.contains("256color"),
+            multiplexer,
+            dumb: term_name == "dumb" || (cfg!(unix) && term.is_none()),
+            serial: is_serial(term_name),
+            no_color: var("NO_COLOR").is_some(),
+            force_color: var("CLICOLOR_FORCE").map_or(false, |value| value != "0")
+                || var("FORCE_COLOR").map_or(false, |value| value != "0" && value != "false"),
+            ssh: var("SSH_CONNECTION").is_some() || var("SSH_TTY").is_some(),
+            term,
+            term_program,
+        }
+    }
+
+    /// Returns the [`Capabilities`](struct.Capabilities.html) these hints suggest.
+    ///
+    /// A dumb terminal supports none of them and isn't queried, serial terminals get the
+    /// [`conservative`](struct.Capabilities.html#method.conservative) profile and GNU screen
+    /// the [`screen`](struct.Capabilities.html#method.screen) one. The Linux console doesn't
+    /// support underline colors, styled underlines or hyperlinks, and renders only a few
+    /// attributes and no 24 bit colors. Outside of multiplexers, the
+    /// [hints](fn.apply_terminal_hints.html) for `TERM_PROGRAM` are applied, e.g. to enable the
+    /// graphics protocols of the terminals known to support them.
+    pub fn capabilities(&self) -> Capabilities {
+        if self.dumb {
+            return Capabilities {
+                attributes: Attributes::none(),
+                query_timeout: None,
+                ..Capabilities::conservative()
+            };
+        }
+        if self.serial {
+            return Capabilities::conservative();
+        }
+        if self.multiplexer == Some(Multiplexer::Screen) {
+            return Capabilities::screen();
+        }
+
+        let attributes = if self.term.as_deref() == Some("linux") {
+            Attributes::from(
+                &[
+                    Attribute::Bold,
+                    Attribute::Dim,
+                    Attribute::Underlined,
+                    Attribute::SlowBlink,
+                    Attribute::Reverse,
+                ][..],
+            )
+        } else {
+            Attributes::all()
+        };
+
+        let mut capabilities = Capabilities {
+            underline_color: self.term.as_deref() != Some("linux"),
+            kitty_graphics: self.multiplexer.is_none()
+                && self.term.as_deref() == Some("xterm-kitty"),
+            // the variables don't reliably tell that colors are missing, see `detect_truecolor`
+            truecolor: self.truecolor || self.term.as_deref() != Some("linux"),
+            hyperlinks: self.term.as_deref() != Some("linux"),
+            attributes,
+            multiplexer: self.multiplexer,
+            ..Capabilities::default()
+        };
+        // multiplexers don't pass the graphics protocols through
+        if let Some(term_program) = self
+            .term_program
+            .as_deref()
+            .filter(|_| self.multiplexer.is_none())
+        {
+            apply_terminal_hints(term_program, &mut capabilities);
+        }
+        capabilities
+    }
+}
+
+/// Returns whether `term` names a DEC VT terminal, e.g. `vt100`, `vt220-am` or `vt52`.
+fn is_serial(term: &str) -> bool {
+    let name = term.split('-').next().unwrap_or_default();
+    name.strip_prefix("vt").map_or(false, |model| {
+        !model.is_empty() && model.bytes().all(|b| b.is_ascii_digit())
+    })
+}
+
+/// Applies the heuristics crossterm uses to the environment variables of the current process.
+///
+/// See [`Environment`](struct.Environment.html) for which variables are consulted. The result
+/// can be turned into capabilities to install:
+///
+/// ```no_run
+/// use crossterm::capabilities;
+///
+/// capabilities::set(capabilities::from_env().capabilities());
+/// ```
+pub fn from_env() -> Environment {
+    Environment::from_vars(|name| env::var(name).ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Attribute, Capabilities, Environment, Multiplexer};
+
+    fn from_vars(vars: &[(&str, &str)]) -> Environment {
+        Environment::from_vars(|name| {
+            vars.iter()
+                .find(|(key, _)| *key == name)
+                .map(|(_, value)| value.to_string())
+        })
+    }
+
+    #[test]
+    fn test_colors() {
+        let env = from_vars(&[("TERM", "xterm-256color"), ("COLORTERM", "truecolor")]);
+        assert!(env.truecolor && env.ansi256);
+
+        let env = from_vars(&[("TERM", "xterm-256color")]);
+        assert!(!env.truecolor && env.ansi256);
+
+        let env = from_vars(&[("TERM", "xterm-direct")]);
+        assert!(env.truecolor && env.ansi256);
+
+        let env = from_vars(&[("TERM", "xterm"), ("TERM_PROGRAM", "iTerm.app")]);
+        assert!(env.truecolor);
+
+        let env = from_vars(&[("TERM", "xterm"), ("NO_COLOR", "1")]);
+        assert!(!env.ansi256 && env.no_color);
+
+        assert!(!from_vars(&[("NO_COLOR", "")]).no_color);
+        assert!(from_vars(&[("CLICOLOR_FORCE", "1")]).force_color);
+        assert!(!from_vars(&[("FORCE_COLOR", "0")]).force_color);
+    }
+
+    #[test]
+    fn test_multiplexer() {
+        let env = from_vars(&[("TERM", "screen-256color"), ("TMUX", "/tmp/tmux")]);
+        assert_eq!(env.multiplexer, Some(Multiplexer::Tmux));
+
+        let env = from_vars(&[("TERM", "screen.xterm-256color")]);
+        assert_eq!(env.multiplexer, Some(Multiplexer::Screen));
+        assert!(!env.capabilities().underline_color);
+        assert!(!env.capabilities().attributes.has(Attribute::Undercurled));
+        assert!(!env.capabilities().hyperlinks);
+        assert_eq!(env.capabilities(), Capabilities::screen());
+        assert_eq!(
+            from_vars(&[("TERM", "xterm"), ("STY", "1234.pts-0.host")]).capabilities(),
+            Capabilities::screen()
+        );
+
+        let env = from_vars(&[("TERM", "xterm"), ("ZELLIJ", "0")]);
+        assert_eq!(env.multiplexer, Some(Multiplexer::Zellij));
+
+        assert_eq!(from_vars(&[("TERM", "xterm")]).multiplexer, None);
+    }
+
+    #[test]
+    fn test_graphics() {
+        let capabilities = from_vars(&[("TERM", "xterm-kitty")]).capabilities();
+        assert!(capabilities.kitty_graphics && !capabilities.inline_images);
+
+        let capabilities =
+            from_vars(&[("TERM", "xterm"), ("TERM_PROGRAM", "iTerm.app")]).capabilities();
+        assert!(!capabilities.kitty_graphics && capabilities.inline_images);
+
+        let env = from_vars(&[
+            ("TERM", "xterm-kitty"),
+            ("TERM_PROGRAM", "WezTerm"),
+            ("TMUX", "/tmp/tmux"),
+        ]);
+        assert!(!env.capabilities().kitty_graphics && !env.capabilities().inline_images);
+
+        let capabilities =
+            from_vars(&[("TERM", "xterm"), ("TERM_PROGRAM", "Apple_Terminal")]).capabilities();
+        assert!(!capabilities.truecolor && !capabilities.hyperlinks);
+    }
+
+    #[test]
+    fn test_dumb() {
+        let env = from_vars(&[("TERM", "dumb")]);
+        assert!(env.dumb);
+        assert!(!env.capabilities().sgr_mouse);
+        assert!(!from_vars(&[("TERM", "linux")]).capabilities().truecolor);
+
+        assert_eq!(from_vars(&[]).dumb, cfg!(unix));
+        assert!(!from_vars(&[("TERM", "xterm")]).dumb);
+        assert_eq!(
+            from_vars(&[("TERM", "xterm")]).capabilities(),
+            Default::default()
+        );
+        assert_eq!(
+            from_vars(&[("TERM", "dumb")]).capabilities().query_timeout,
+            None
+        );
+    }
+
+    #[test]
+    fn test_serial() {
+        for term in ["vt100", "vt220", "vt52", "vt102-nam"] {
+            let env = from_vars(&[("TERM", term)]);
+            assert!(env.serial && !env.dumb, "{}", term);
+            assert_eq!(env.capabilities(), Capabilities::conservative());
+        }
+        for term in ["vte-256color", "vt", "xterm", "linux"] {
+            assert!(!from_vars(&[("TERM", term)]).serial, "{}", term);
+        }
+
+        let capabilities = Capabilities::conservative();
+        assert!(!capabilities.alternate_screen && !capabilities.ansi256);
+        assert!(capabilities.query_timeout < Capabilities::default().query_timeout);
+    }
+}
@@ -0,0 +1,287 @@
+#[cfg(all(unix, feature = "events"))]
+use std::io;
+use std::sync::Arc;
+
+use parking_lot::RwLock;
+
+use super::Capabilities;
+
+type Hint = Arc<dyn Fn(&mut Capabilities) + Send + Sync>;
+type BuiltinHint = (&'static str, fn(&mut Capabilities));
+
+/// The quirks of the terminals crossterm knows, by their name.
+const BUILTIN_HINTS: &[BuiltinHint] = &[
+    ("WezTerm", |capabilities| {
+        capabilities.kitty_graphics = true;
+        capabilities.inline_images = true;
+    }),
+    ("ghostty", |capabilities| capabilities.kitty_graphics = true),
+    ("kitty", |capabilities| capabilities.kitty_graphics = true),
+    ("iTerm.app", |capabilities| {
+        capabilities.inline_images = true
+    }),
+    ("iTerm2", |capabilities| capabilities.inline_images = true),
+    ("foot", |capabilities| capabilities.sixel = true),
+    ("Apple_Terminal", |capabilities| {
+        capabilities.truecolor = false;
+        capabilities.hyperlinks = false;
+    }),
+    // SGR 21 resets the intensity like SGR 22
+    ("rxvt-unicode", |capabilities| {
+        capabilities.sgr21_no_bold = true
+    }),
+];
+
+/// The most nested tmuxes [`outer_terminal`](fn.outer_terminal.html) looks through.
+#[cfg(all(unix, feature = "events"))]
+const MAX_TMUX_NESTING: usize = 4;
+
+/// The hints added with [`add_terminal_hint`](fn.add_terminal_hint.html).
+static HINTS: RwLock<Vec<(String, Hint)>> = parking_lot::const_rwlock(Vec::new());
+
+/// Returns the name of the terminal in `terminal`, without the version the XTVERSION response
+/// has, e.g. `kitty` for `kitty(0.31.0)` or `WezTerm` for `WezTerm 20240203-110809-5046fc22`.
+fn terminal_name(terminal: &str) -> &str {
+    terminal.split([' ', '(']).next().unwrap_or_default()
+}
+
+/// Adds a hint about the quirks of a terminal, which changes the capabilities for it, e.g.
+/// because it supports a graphics protocol.
+///
+/// `terminal` is the name the terminal reports, without the version, or its `TERM_PROGRAM`, and
+/// is compared case-insensitively, see [`detect_terminal`](fn.detect_terminal.html). The hints
+/// are applied after the built-in ones, and replace the hint which was added for the name
+/// before.
+///
+/// # Examples
+///
+/// ```no_run
+/// use crossterm::capabilities;
+///
+/// capabilities::add_terminal_hint("contour", |capabilities| capabilities.sixel = true);
+/// ```
+pub fn add_terminal_hint(
+    terminal: impl Into<String>,
+    hint: impl Fn(&mut Capabilities) + Send + Sync + 'static,
+) {
+    let terminal = terminal.into();
+    let mut hints = HINTS.write();
+    hints.retain(|(added, _)| !added.eq_ignore_ascii_case(&terminal));
+    hints.push((terminal, Arc::new(hint)));
+}
+
+/// Removes the hint added with [`add_terminal_hint`](fn.add_terminal_hint.html), returns
+/// whether there was one. The built-in hints are kept.
+pub fn remove_terminal_hint(terminal: &str) -> bool {
+    let mut hints = HINTS.write();
+    let len = hints.len();
+    hints.retain(|(added, _)| !added.eq_ignore_ascii_case(terminal));
+    hints.len() != len
+}
+
+/// Changes `capabilities` as the hints for `terminal` say, the built-in ones and those added
+/// with [`add_terminal_hint`](fn.add_terminal_hint.html).
+///
+/// `terminal` can be the response to XTVERSION, including the version, or a `TERM_PROGRAM`.
+/// [`Environment::capabilities`](struct.Environment.html#method.capabilities) applies the hints
+/// for `TERM_PROGRAM` outside of multiplexers.
+///
+/// # Examples
+///
+/// ```
+/// use crossterm::capabilities::{self, Capabilities};
+///
+/// let mut capabilities = Capabilities::default();
+/// capabilities::apply_terminal_hints("WezTerm 20240203-110809-5046fc22", &mut capabilities);
+/// assert!(capabilities.kitty_graphics);
+/// ```
+pub fn apply_terminal_hints(terminal: &str, capabilities: &mut Capabilities) {
+    let name = terminal_name(terminal);
+    for (_, hint) in BUILTIN_HINTS
+        .iter()
+        .filter(|(builtin, _)| builtin.eq_ignore_ascii_case(name))
+    {
+        hint(capabilities);
+    }
+
+    let hints: Vec<Hint> = HINTS
+        .read()
+        .iter()
+        .filter(|(added, _)| added.eq_ignore_ascii_case(name))
+        .map(|(_, hint)| hint.clone())
+        .collect();
+    // called without the lock, so the hint can add and remove hints
+    for hint in hints {
+        hint(capabilities);
+    }
+}
+
+/// Identifies the terminal for [`apply_terminal_hints`](fn.apply_terminal_hints.html).
+///
+/// With the `events` feature, the terminal is asked on Unix for its name and version with
+/// XTVERSION, which tells the terminal the application actually talks to, e.g. the multiplexer.
+/// Otherwise or if it doesn't answer, this falls back to
+/// [`Environment::term_program`](struct.Environment.html#structfield.term_program).
+///
+/// # Examples
+///
+/// ```no_run
+/// use crossterm::capabilities;
+///
+/// let mut capabilities = capabilities::from_env().capabilities();
+/// if let Some(terminal) = capabilities::detect_terminal() {
+///     capabilities::apply_terminal_hints(&terminal, &mut capabilities);
+/// }
+/// capabilities::set(capabilities);
+/// ```
+pub fn detect_terminal() -> Option<String> {
+    #[cfg(all(unix, feature = "events"))]
+    if let Ok(Some(version)) = crate::terminal::sys::query_terminal_version() {
+        return Some(version);
+    }
+
+    super::from_env().term_program
+}
+
+/// Identifies the outermost terminal emulator, the one which draws the application, for
+/// [`apply_terminal_hints`](fn.apply_terminal_hints.html).
+///
+/// Inside a multiplexer, the terminal the application talks to is the multiplexer, but whether
+/// the graphics protocols or the clipboard work, with the passthrough sequence, depends on the
+/// terminal it runs in. With the `events` feature, on Unix, the terminal tmux runs in is asked
+/// for its name and version with XTVERSION in the passthrough sequence of tmux, see
+/// [`tmux_passthrough`](../fn.tmux_passthrough.html). If that's another tmux, the query is
+/// wrapped once more for the terminal the next one runs in, up to four nested tmuxes. A tmux
+/// which doesn't pass the query on, e.g. without `set -g allow-passthrough on`, doesn't answer
+/// at all, so this waits for
+/// [`query_timeout`](struct.Capabilities.html#structfield.query_timeout) then. Other
+/// multiplexers aren't asked. Outside of multiplexers, the terminal itself is asked.
+///
+/// Otherwise or if it doesn't answer, this falls back to
+/// [`Environment::term_program`](struct.Environment.html#structfield.term_program), unless tmux
+/// set it to its own name, and then to a `TERM` which names a terminal emulator, e.g.
+/// `xterm-kitty` or `alacritty`.
+///
+/// # Examples
+///
+/// ```no_run
+/// use crossterm::capabilities;
+///
+/// let mut capabilities = capabilities::from_env().capabilities();
+/// if let Some(terminal) = capabilities::outer_terminal() {
+///     capabilities::apply_terminal_hints(&terminal, &mut capabilities);
+/// }
+/// capabilities::set(capabilities);
+/// ```
+pub fn outer_terminal() -> Option<String> {
+    let env = super::from_env();
+
+    #[cfg(all(unix, feature = "events"))]
+    {
+        let version = match env.multiplexer {
+            Some(super::Multiplexer::Tmux) => outer_terminal_version(),
+            Some(_) => Ok(None),
+            None => crate::terminal::sys::query_terminal_version(),
+        };
+        if let Ok(Some(version)) = version {
+            return Some(version);
+        }
+    }
+
+    env.term_program
+        .filter(|term_program| term_program != "tmux")
+        .or_else(|| {
+            env.term
+                .as_deref()
+                .and_then(terminal_from_term)
+                .map(str::to_string)
+        })
+}
+
+/// Asks the terminal the nested tmuxes run in for its name and version, through one tmux after
+/// the other.
+#[cfg(all(unix, feature = "events"))]
+fn outer_terminal_version() -> io::Result<Option<String>> {
+    for depth in 1..=MAX_TMUX_NESTING {
+        match crate::terminal::sys::query_outer_terminal_version(depth)? {
+            Some(version) if version.starts_with("tmux") => {}
+            version => return Ok(version),
+        }
+    }
+    Ok(None)
+}
+
+/// Returns the name of the terminal emulator `term` names, e.g. `kitty` for `xterm-kitty`,
+/// `None` for the generic ones like `xterm-256color`.
+fn terminal_from_term(term: &str) -> Option<&'static str> {
+    let name = term.strip_prefix("xterm-").unwrap_or(term);
+    match name.split('-').next().unwrap_or_default() {
+        "kitty" => Some("kitty"),
+        "ghostty" => Some("ghostty"),
+        "wezterm" => Some("WezTerm"),
+        "alacritty" => Some("alacritty"),
+        "foot" => Some("foot"),
+        "contour" => Some("contour"),
+        "rxvt" if name.starts_with("rxvt-unicode") => Some("rxvt-unicode"),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        add_terminal_hint, apply_terminal_hints, remove_terminal_hint, terminal_from_term,
+        terminal_name, Capabilities,
+    };
+
+    #[test]
+    fn test_terminal_name() {
+        assert_eq!(terminal_name("kitty(0.31.0)"), "kitty");
+        assert_eq!(terminal_name("WezTerm 20240203-110809-5046fc22"), "WezTerm");
+        assert_eq!(terminal_name("Apple_Terminal"), "Apple_Terminal");
+    }
+
+    #[test]
+    fn test_terminal_from_term() {
+        assert_eq!(terminal_from_term("xterm-kitty"), Some("kitty"));
+        assert_eq!(terminal_from_term("foot-extra"), Some("foot"));
+        assert_eq!(terminal_from_term("wezterm"), Some("WezTerm"));
+        assert_eq!(
+            terminal_from_term("rxvt-unicode-256color"),
+            Some("rxvt-unicode")
+        );
+        assert_eq!(terminal_from_term("rxvt"), None);
+        assert_eq!(terminal_from_term("xterm-256color"), None);
+        assert_eq!(terminal_from_term("tmux-256color"), None);
+    }
+
+    #[test]
+    fn test_terminal_hints() {
+        let mut capabilities = Capabilities::default();
+        apply_terminal_hints("Apple_Terminal", &mut capabilities);
+        assert!(!capabilities.truecolor && !capabilities.hyperlinks);
+        assert!(!capabilities.sgr21_no_bold);
+
+        let mut capabilities = Capabilities::default();
+        apply_terminal_hints("rxvt-unicode", &mut capabilities);
+        assert!(capabilities.sgr21_no_bold);
+
+        add_terminal_hint("Contour", |capabilities| capabilities.sixel = true);
+        let mut capabilities = Capabilities::default();
+        apply_terminal_hints("contour 0.4.3", &mut capabilities);
+        assert!(capabilities.sixel);
+
+        // the added hints are applied after the built-in ones
+        add_terminal_hint("foot", |capabilities| capabilities.sixel = false);
+        let mut capabilities = Capabilities::default();
+        apply_terminal_hints("foot(1.16.2)", &mut capabilities);
+        assert!(!capabilities.sixel);
+
+        assert!(remove_terminal_hint("contour"));
+        assert!(remove_terminal_hint("foot"));
+        assert!(!remove_terminal_hint("foot"));
+        let mut capabilities = Capabilities::default();
+        apply_terminal_hints("foot(1.16.2)", &mut capabilities);
+        assert!(capabilities.sixel);
+    }
+}
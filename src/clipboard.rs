@@ -0,0 +1,181 @@
+//! # Clipboard
+//!
+//! The `clipboard` module provides commands to copy text to the clipboard of the terminal's
+//! system with `OSC 52`, which also works over SSH.
+//!
+//! X11 has several selections, besides the clipboard there's the primary selection, which is
+//! pasted with the middle mouse button, and the cut buffers. The text can be copied to any
+//! combination of them:
+//!
+//! ```no_run
+//! use std::io::{self, Write};
+//! use crossterm::{
+//!     clipboard::{ClipboardSelection, ClipboardType, CopyToClipboard},
+//!     execute,
+//! };
+//!
+//! fn main() -> io::Result<()> {
+//!     execute!(io::stdout(), CopyToClipboard::to_clipboard_from("foo"))?;
+//!
+//!     let both = ClipboardSelection(vec![ClipboardType::Clipboard, ClipboardType::Primary]);
+//!     execute!(io::stdout(), CopyToClipboard::new("bar", both))
+//! }
+//! ```
+//!
+//! Many terminals only support the clipboard, some need the feature to be enabled in their
+//! settings, and some limit the length of the text.
+
+use std::fmt;
+
+use crate::{base64, command::write_multiplexed, Command};
+
+/// A selection the text is copied to, see [`ClipboardSelection`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ClipboardType {
+    /// The clipboard, which is pasted with Ctrl+V or the like.
+    Clipboard,
+    /// The primary selection of X11, which is pasted with the middle mouse button.
+    Primary,
+    /// The secondary selection of X11.
+    Secondary,
+    /// The selection the terminal is configured to use for selecting text, usually the primary
+    /// selection.
+    Select,
+    /// One of the X11 cut buffers `0` to `7`, other numbers are ignored.
+    CutBuffer(u8),
+}
+
+impl ClipboardType {
+    fn code(self) -> Option<char> {
+        match self {
+            ClipboardType::Clipboard => Some('c'),
+            ClipboardType::Primary => Some('p'),
+            ClipboardType::Secondary => Some('q'),
+            ClipboardType::Select => Some('s'),
+            ClipboardType::CutBuffer(buffer @ 0..=7) => Some((b'0' + buffer) as char),
+            ClipboardType::CutBuffer(_) => None,
+        }
+    }
+}
+
+/// The selections the text is copied to.
+///
+/// If empty, the terminal picks its default, which is usually the selection and the first cut
+/// buffer.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+pub struct ClipboardSelection(pub Vec<ClipboardType>);
+
+impl ClipboardSelection {
+    /// Only the clipboard.
+    pub fn clipboard() -> ClipboardSelection {
+        ClipboardSelection(vec![ClipboardType::Clipboard])
+    }
+
+    /// Only the primary selection.
+    pub fn primary() -> ClipboardSelection {
+        ClipboardSelection(vec![ClipboardType::Primary])
+    }
+}
+
+impl fmt::Display for ClipboardSelection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for code in self.0.iter().filter_map(|target| target.code()) {
+            fmt::Write::write_char(f, code)?;
+        }
+        Ok(())
+    }
+}
+
+/// A command that copies `content` to the selections of `destination` with `OSC 52`.
+///
+/// # Notes
+///
+/// Commands must be executed/queued for execution otherwise they do nothing.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct CopyToClipboard<T> {
+    /// The text to copy.
+    pub content: T,
+    /// Where to copy it to.
+    pub destination: ClipboardSelection,
+}
+
+impl<T: AsRef<[u8]>> CopyToClipboard<T> {
+    /// Creates a command which copies `content` to `destination`.
+    pub fn new(content: T, destination: ClipboardSelection) -> CopyToClipboard<T> {
+        CopyToClipboard {
+            content,
+            destination,
+        }
+    }
+
+    /// Creates a command which copies `content` to the clipboard.
+    pub fn to_clipboard_from(content: T) -> CopyToClipboard<T> {
+        CopyToClipboard::new(content, ClipboardSelection::clipboard())
+    }
+
+    /// Creates a command which copies `content` to the primary selection.
+    pub fn to_primary_from(content: T) -> CopyToClipboard<T> {
+        CopyToClipboard::new(content, ClipboardSelection::primary())
+    }
+}
+
+impl<T: AsRef<[u8]>> Command for CopyToClipboard<T> {
+    fn write_ansi(&self, f: &mut impl fmt::Write) -> fmt::Result {
+        use fmt::Write;
+
+        write_multiplexed(f, |f| {
+            write!(
+                f,
+                "\x1B]52;{};{}\x1B\\",
+                self.destination,
+                base64::encode(self.content.as_ref())
+            )
+        })
+    }
+
+    fn size_hint(&self) -> usize {
+        (self.content.as_ref().len() + 2) / 3 * 4 + 16
+    }
+
+    #[cfg(windows)]
+    fn execute_winapi(&self) -> std::io::Result<()> {
+        Err(crate::Error::UnsupportedCapability(
+            "Copying to the clipboard is not supported by the legacy Windows API.",
+        )
+        .into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Command;
+
+    use super::{ClipboardSelection, ClipboardType, CopyToClipboard};
+
+    #[test]
+    fn test_copy_to_clipboard() {
+        assert_eq!(
+            CopyToClipboard::to_clipboard_from("foo").ansi_string(),
+            "\x1B]52;c;Zm9v\x1B\\"
+        );
+        assert_eq!(
+            CopyToClipboard::to_primary_from(b"foo").ansi_string(),
+            "\x1B]52;p;Zm9v\x1B\\"
+        );
+
+        let selection = ClipboardSelection(vec![
+            ClipboardType::Clipboard,
+            ClipboardType::Primary,
+            ClipboardType::CutBuffer(1),
+            ClipboardType::CutBuffer(8),
+        ]);
+        assert_eq!(
+            CopyToClipboard::new(String::from("foobar"), selection).ansi_string(),
+            "\x1B]52;cp1;Zm9vYmFy\x1B\\"
+        );
+        assert_eq!(
+            CopyToClipboard::new("", ClipboardSelection::default()).ansi_string(),
+            "\x1B]52;;\x1B\\"
+        );
+    }
+}
@@ -3,6 +3,46 @@ use std::io::{self, Write};
 
 use crate::terminal::{BeginSynchronizedUpdate, EndSynchronizedUpdate};
 
+pub use self::{
+    batch::{batch, QueuedWriter},
+    boxed::{BoxedCommand, DynCommand},
+    buffer::CommandBuffer,
+    buffering::BufferingWriter,
+    nonblocking::NonBlockingWriter,
+    passthrough::{screen_passthrough, tmux_passthrough, ScreenPassthrough, TmuxPassthrough},
+    rate_limit::RateLimitedWriter,
+    synchronized::SynchronizedWriter,
+    tee::{remove_tee, set_tee},
+    write::{retry_policy, set_retry_policy, write_large, RetryPolicy},
+};
+
+#[doc(hidden)]
+pub use self::lock::{LockColored, LockStdout, Locker, PassThrough};
+pub(crate) use self::passthrough::{write_multiplexed, write_multiplexed_with, PassthroughWriter};
+pub(crate) use self::tee::tee;
+#[cfg(feature = "events")]
+pub(crate) use self::tracking::is_tracking;
+pub(crate) use self::tracking::tracked;
+#[cfg(test)]
+pub(crate) use self::tracking::with_tracking;
+pub use self::tracking::ProcessTerminal;
+#[doc(hidden)]
+pub use self::tracking::{Probe, ProbeOtherWriter, ProbeProcessTerminal};
+pub(crate) use self::write::{write_all, writing_large, CHUNK_SIZE};
+
+mod batch;
+mod boxed;
+mod buffer;
+mod buffering;
+mod lock;
+mod nonblocking;
+mod passthrough;
+mod rate_limit;
+mod synchronized;
+mod tee;
+mod tracking;
+mod write;
+
 /// An interface for a command that performs an action on the terminal.
 ///
 /// Crossterm provides a set of commands,
@@ -34,6 +74,71 @@ pub trait Command {
     fn is_ansi_code_supported(&self) -> bool {
         super::ansi_support::supports_ansi()
     }
+
+    /// Returns an estimate of the number of bytes [`write_ansi`](#tymethod.write_ansi) writes.
+    ///
+    /// Writers like [`CommandBuffer`](struct.CommandBuffer.html) use it to reserve capacity up
+    /// front. It is only a hint, `write_ansi` may write more or less. The default implementation
+    /// returns `0`, which means unknown.
+    fn size_hint(&self) -> usize {
+        0
+    }
+
+    /// Returns the ANSI representation of this command as a string.
+    ///
+    /// This writes the ANSI codes on every platform, the command is never executed with WinAPI
+    /// calls. See also [`render!`](macro.render.html).
+    ///
+    /// # Panics
+    ///
+    /// Panics if [`write_ansi`](#tymethod.write_ansi) errors, which only happens if a `Display`
+    /// implementation the command uses returns an error.
+    fn ansi_string(&self) -> String {
+        let mut ansi = String::with_capacity(self.size_hint());
+        if self.write_ansi(&mut ansi).is_err() {
+            panic!(
+                "<{}>::write_ansi incorrectly errored",
+                std::any::type_name::<Self>()
+            );
+        }
+        ansi
+    }
+
+    /// Combines this command with `next` into a single command which executes both, in order.
+    ///
+    /// Tuples, arrays, slices and `Vec`s of commands are commands too, this is a shorthand for
+    /// creating a tuple.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use std::io;
+    /// use crossterm::{cursor, style::Print, Command, QueueableCommand};
+    ///
+    /// fn main() -> io::Result<()> {
+    ///     let greeting = cursor::MoveTo(0, 0).then(Print("Hello"));
+    ///     let lines: Vec<_> = (1..4).map(|row| (cursor::MoveTo(0, row), Print("-"))).collect();
+    ///
+    ///     io::stdout().queue(greeting)?.queue(lines)?;
+    ///     Ok(())
+    /// }
+    /// ```
+    fn then<C: Command>(self, next: C) -> (Self, C)
+    where
+        Self: Sized,
+    {
+        (self, next)
+    }
+
+    /// Boxes this command, so it can be stored together with commands of other types.
+    ///
+    /// See [`DynCommand`](trait.DynCommand.html) for more info.
+    fn boxed(self) -> BoxedCommand
+    where
+        Self: Sized + Send + 'static,
+    {
+        Box::new(self)
+    }
 }
 
 impl<T: Command + ?Sized> Command for &T {
@@ -52,6 +157,135 @@ impl<T: Command + ?Sized> Command for &T {
     fn is_ansi_code_supported(&self) -> bool {
         T::is_ansi_code_supported(self)
     }
+
+    #[inline]
+    fn size_hint(&self) -> usize {
+        T::size_hint(self)
+    }
+}
+
+/// Executes one part of a composite command with WinAPI calls.
+///
+/// Parts that are written as ANSI codes can't be written to the writer the composite command is
+/// queued on at this point, so they are written to stdout.
+#[cfg(windows)]
+fn execute_part_winapi(command: &impl Command) -> io::Result<()> {
+    if command.is_ansi_code_supported() {
+        let mut stdout = io::stdout();
+        write_command_ansi(&mut stdout, command)?;
+        stdout.flush()
+    } else {
+        command.execute_winapi()
+    }
+}
+
+macro_rules! impl_command_for_tuple {
+    ($($name:ident . $index:tt),+) => {
+        /// Executes all commands of the tuple, in order.
+        impl<$($name: Command),+> Command for ($($name,)+) {
+            fn write_ansi(&self, f: &mut impl fmt::Write) -> fmt::Result {
+                $(self.$index.write_ansi(f)?;)+
+                Ok(())
+            }
+
+            #[cfg(windows)]
+            fn execute_winapi(&self) -> io::Result<()> {
+                $(execute_part_winapi(&self.$index)?;)+
+                Ok(())
+            }
+
+            #[cfg(windows)]
+            fn is_ansi_code_supported(&self) -> bool {
+                $(self.$index.is_ansi_code_supported())&&+
+            }
+
+            fn size_hint(&self) -> usize {
+                0 $(+ self.$index.size_hint())+
+            }
+        }
+    };
+}
+
+impl_command_for_tuple!(A.0);
+impl_command_for_tuple!(A.0, B.1);
+impl_command_for_tuple!(A.0, B.1, C.2);
+impl_command_for_tuple!(A.0, B.1, C.2, D.3);
+impl_command_for_tuple!(A.0, B.1, C.2, D.3, E.4);
+impl_command_for_tuple!(A.0, B.1, C.2, D.3, E.4, F.5);
+impl_command_for_tuple!(A.0, B.1, C.2, D.3, E.4, F.5, G.6);
+impl_command_for_tuple!(A.0, B.1, C.2, D.3, E.4, F.5, G.6, H.7);
+impl_command_for_tuple!(A.0, B.1, C.2, D.3, E.4, F.5, G.6, H.7, I.8);
+impl_command_for_tuple!(A.0, B.1, C.2, D.3, E.4, F.5, G.6, H.7, I.8, J.9);
+impl_command_for_tuple!(A.0, B.1, C.2, D.3, E.4, F.5, G.6, H.7, I.8, J.9, K.10);
+impl_command_for_tuple!(A.0, B.1, C.2, D.3, E.4, F.5, G.6, H.7, I.8, J.9, K.10, L.11);
+
+/// Executes all commands of the slice, in order.
+impl<T: Command> Command for [T] {
+    fn write_ansi(&self, f: &mut impl fmt::Write) -> fmt::Result {
+        for command in self {
+            command.write_ansi(f)?;
+        }
+        Ok(())
+    }
+
+    #[cfg(windows)]
+    fn execute_winapi(&self) -> io::Result<()> {
+        for command in self {
+            execute_part_winapi(command)?;
+        }
+        Ok(())
+    }
+
+    #[cfg(windows)]
+    fn is_ansi_code_supported(&self) -> bool {
+        self.iter().all(|command| command.is_ansi_code_supported())
+    }
+
+    fn size_hint(&self) -> usize {
+        self.iter().map(Command::size_hint).sum()
+    }
+}
+
+/// Executes all commands of the array, in order.
+impl<T: Command, const N: usize> Command for [T; N] {
+    fn write_ansi(&self, f: &mut impl fmt::Write) -> fmt::Result {
+        self[..].write_ansi(f)
+    }
+
+    #[cfg(windows)]
+    fn execute_winapi(&self) -> io::Result<()> {
+        self[..].execute_winapi()
+    }
+
+    #[cfg(windows)]
+    fn is_ansi_code_supported(&self) -> bool {
+        self[..].is_ansi_code_supported()
+    }
+
+    fn size_hint(&self) -> usize {
+        self[..].size_hint()
+    }
+}
+
+/// Executes all commands of the vector, in order.
+impl<T: Command> Command for Vec<T> {
+    fn write_ansi(&self, f: &mut impl fmt::Write) -> fmt::Result {
+        self[..].write_ansi(f)
+    }
+
+    #[cfg(windows)]
+    fn execute_winapi(&self) -> io::Result<()> {
+        self[..].execute_winapi()
+    }
+
+    #[cfg(windows)]
+    fn is_ansi_code_supported(&self) -> bool {
+        self[..].is_ansi_code_supported()
+    }
+
+    fn size_hint(&self) -> usize {
+        self[..].size_hint()
+    }
 }
 
 /// An interface for types that can queue commands for further execution.
@@ -118,20 +352,41 @@ impl<T: Write + ?Sized> QueueableCommand for T {
     ///     and can therefore not be written to the given `writer`.
     ///     Therefore, there is no difference between [execute](./trait.ExecutableCommand.html)
     ///     and [queue](./trait.QueueableCommand.html) for those old Windows versions.
+    /// * Commands queued with this method don't update the state crossterm tracks about the
+    ///   terminal, e.g. [`is_alternate_screen_active`](terminal/fn.is_alternate_screen_active.html),
+    ///   use [`queue!`](macro.queue.html) on a [`ProcessTerminal`](trait.ProcessTerminal.html)
+    ///   for that.
     fn queue(&mut self, command: impl Command) -> io::Result<&mut Self> {
-        #[cfg(windows)]
-        if !command.is_ansi_code_supported() {
-            // There may be queued commands in this writer, but `execute_winapi` will execute the
-            // command immediately. To prevent commands being executed out of order we flush the
-            // writer now.
-            self.flush()?;
-            command.execute_winapi()?;
-            return Ok(self);
-        }
+        queue_tracked(self, command, false)
+    }
+}
 
-        write_command_ansi(self, command)?;
-        Ok(self)
+/// Queues `command` on `writer`, which updates the tracked state of the terminal if `tracking`
+/// is set, i.e. [`queue!`](macro.queue.html) found that `writer` is a
+/// [`ProcessTerminal`](trait.ProcessTerminal.html).
+#[doc(hidden)]
+pub fn queue_tracked<W: Write + ?Sized>(
+    writer: &mut W,
+    command: impl Command,
+    tracking: bool,
+) -> io::Result<&mut W> {
+    crate::metrics::add_command();
+
+    #[cfg(feature = "trace")]
+    trace_command(&command);
+
+    #[cfg(windows)]
+    if !command.is_ansi_code_supported() {
+        // There may be queued commands in this writer, but `execute_winapi` will execute the
+        // command immediately. To prevent commands being executed out of order we flush the
+        // writer now.
+        writer.flush()?;
+        tracking::with_tracking(true, || command.execute_winapi())?;
+        return Ok(writer);
     }
+
+    tracking::with_tracking(tracking, || write_command_ansi(writer, command))?;
+    Ok(writer)
 }
 
 impl<T: Write + ?Sized> ExecutableCommand for T {
@@ -257,11 +512,30 @@ fn write_command_ansi<C: Command>(
     struct Adapter<T> {
         inner: T,
         res: io::Result<()>,
+        #[cfg(all(windows, feature = "legacy-windows"))]
+        synchronous: bool,
     }
 
     impl<T: Write> fmt::Write for Adapter<T> {
         fn write_str(&mut self, s: &str) -> fmt::Result {
-            self.inner.write_all(s.as_bytes()).map_err(|e| {
+            let result = write_all(&mut self.inner, s.as_bytes());
+            if result.is_ok() {
+                tee(s.as_bytes());
+                crate::metrics::add_bytes_written(s.len());
+            }
+
+            // Commands like `PrintStyledContent` execute parts of themselves with WinAPI calls
+            // while they are written, the text written so far has to reach the console first.
+            #[cfg(all(windows, feature = "legacy-windows"))]
+            let result = result.and_then(|()| {
+                if self.synchronous {
+                    self.inner.flush()
+                } else {
+                    Ok(())
+                }
+            });
+
+            result.map_err(|e| {
                 self.res = Err(e);
                 fmt::Error
             })
@@ -271,6 +545,8 @@ fn write_command_ansi<C: Command>(
     let mut adapter = Adapter {
         inner: io,
         res: Ok(()),
+        #[cfg(all(windows, feature = "legacy-windows"))]
+        synchronous: !super::ansi_support::supports_ansi(),
     };
 
     command
@@ -284,6 +560,31 @@ fn write_command_ansi<C: Command>(
         })
 }
 
+/// Logs a command that is about to be queued, annotated with its type.
+#[cfg(feature = "trace")]
+fn trace_command<C: Command>(command: &C) {
+    if !log::log_enabled!(target: "crossterm::command", log::Level::Debug) {
+        return;
+    }
+
+    #[cfg(windows)]
+    if !command.is_ansi_code_supported() {
+        log::debug!(
+            target: "crossterm::command",
+            "{} (WinAPI)",
+            std::any::type_name::<C>()
+        );
+        return;
+    }
+
+    log::debug!(
+        target: "crossterm::command",
+        "{} {:?}",
+        std::any::type_name::<C>(),
+        command.ansi_string()
+    );
+}
+
 /// Executes the ANSI representation of a command, using the given `fmt::Write`.
 pub(crate) fn execute_fmt(f: &mut impl fmt::Write, command: impl Command) -> fmt::Result {
     #[cfg(windows)]
@@ -293,3 +594,48 @@ pub(crate) fn execute_fmt(f: &mut impl fmt::Write, command: impl Command) -> fmt
 
     command.write_ansi(f)
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        cursor::MoveTo,
+        style::{Attribute, Print, SetAttribute},
+        Command, QueueableCommand,
+    };
+
+    fn ansi(command: impl Command) -> String {
+        let mut ansi = String::new();
+        command.write_ansi(&mut ansi).unwrap();
+        ansi
+    }
+
+    #[test]
+    fn test_composite_commands() {
+        assert_eq!(ansi((MoveTo(0, 0), Print("foo"))), "\x1B[1;1Hfoo");
+        assert_eq!(ansi([Print("a"), Print("b")]), "ab");
+        assert_eq!(ansi(&[Print("a"), Print("b")][..]), "ab");
+        assert_eq!(ansi(vec![(MoveTo(1, 1), Print("x"))]), "\x1B[2;2Hx");
+        assert_eq!(ansi(Vec::<Print<&str>>::new()), "");
+    }
+
+    #[test]
+    fn test_then() {
+        let command = SetAttribute(Attribute::Bold)
+            .then(Print("bold"))
+            .then(SetAttribute(Attribute::Reset));
+
+        let mut writer = Vec::new();
+        writer.queue(command).unwrap();
+        assert_eq!(writer, b"\x1B[1mbold\x1B[0m");
+    }
+
+    #[test]
+    fn test_render() {
+        assert_eq!(MoveTo(2, 3).ansi_string(), "\x1B[4;3H");
+        assert_eq!(crate::render!(), b"");
+        assert_eq!(
+            crate::render!(Print("foo"), [MoveTo(0, 0)], SetAttribute(Attribute::Bold),),
+            b"foo\x1B[1;1H\x1B[1m"
+        );
+    }
+}
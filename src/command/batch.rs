@@ -0,0 +1,150 @@
+use std::io::{self, Write};
+
+use super::{Command, CommandBuffer};
+
+/// Starts a batch of commands which is written to `writer` when the returned guard is dropped.
+///
+/// See [`QueuedWriter`](struct.QueuedWriter.html) for more info.
+///
+/// # Examples
+///
+/// ```no_run
+/// use std::io;
+/// use crossterm::{cursor, queue, style::Print};
+///
+/// fn main() -> io::Result<()> {
+///     let mut stdout = io::stdout();
+///
+///     let mut batch = crossterm::batch(&mut stdout);
+///     queue!(batch, cursor::MoveTo(0, 0), Print("Hello"))?;
+///     queue!(batch, cursor::MoveTo(0, 1), Print("World"))?;
+///
+///     // Written and flushed here, `finish` reports errors that dropping the guard can't.
+///     batch.finish()
+/// }
+/// ```
+pub fn batch<W: Write + ?Sized>(writer: &mut W) -> QueuedWriter<'_, W> {
+    QueuedWriter {
+        writer,
+        buffer: CommandBuffer::new(),
+    }
+}
+
+/// A guard which collects queued commands and writes and flushes them all at once when dropped.
+///
+/// It is returned by [`batch`](fn.batch.html). Commands are queued on it with
+/// [`queue!`](macro.queue.html) or [`QueueableCommand`](trait.QueueableCommand.html) like on any
+/// other writer, so a forgotten `flush` can't leave them unwritten.
+///
+/// Errors that occur while writing on drop are lost, call [`finish`](#method.finish) to get them.
+///
+/// # Notes
+///
+/// Explicit flushes, e.g. by [`execute!`](macro.execute.html), are passed through to the
+/// underlying writer right away.
+#[derive(Debug)]
+pub struct QueuedWriter<'a, W: Write + ?Sized> {
+    writer: &'a mut W,
+    buffer: CommandBuffer,
+}
+
+impl<'a, W: Write + ?Sized> QueuedWriter<'a, W> {
+    /// Queues `command`, reserving capacity for it up front.
+    ///
+    /// See [`CommandBuffer::push`](struct.CommandBuffer.html#method.push).
+    pub fn push(&mut self, command: impl Command) -> io::Result<&mut QueuedWriter<'a, W>> {
+        self.buffer.push(command)?;
+        Ok(self)
+    }
+
+    /// Writes and flushes everything queued so far and ends the batch.
+    pub fn finish(mut self) -> io::Result<()> {
+        self.buffer.flush_to(self.writer)
+    }
+
+    /// Discards everything queued so far and ends the batch without writing anything.
+    pub fn discard(mut self) {
+        self.buffer.clear();
+    }
+}
+
+impl<'a, W: Write + ?Sized> Write for QueuedWriter<'a, W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.buffer.write(buf)
+    }
+
+    fn write_all(&mut self, buf: &[u8]) -> io::Result<()> {
+        self.buffer.write_all(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.buffer.flush_to(self.writer)
+    }
+}
+
+impl<'a, W: Write + ?Sized> Drop for QueuedWriter<'a, W> {
+    fn drop(&mut self) {
+        if !self.buffer.is_empty() {
+            let _ = self.buffer.flush_to(self.writer);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{self, Write};
+
+    use crate::{queue, style::Print};
+
+    use super::batch;
+
+    #[derive(Default)]
+    struct CountingWrite {
+        buffer: Vec<u8>,
+        writes: usize,
+        flushes: usize,
+    }
+
+    impl Write for CountingWrite {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.buffer.extend_from_slice(buf);
+            self.writes += 1;
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            self.flushes += 1;
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_batch_flushes_once_on_drop() {
+        let mut writer = CountingWrite::default();
+        {
+            let mut batch = batch(&mut writer);
+            queue!(batch, Print("foo"), Print("bar")).unwrap();
+            queue!(batch, Print("baz")).unwrap();
+        }
+
+        assert_eq!(writer.buffer, b"foobarbaz");
+        assert_eq!(writer.writes, 1);
+        assert_eq!(writer.flushes, 1);
+    }
+
+    #[test]
+    fn test_batch_finish_and_discard() {
+        let mut writer = CountingWrite::default();
+
+        let mut first = batch(&mut writer);
+        queue!(first, Print("foo")).unwrap();
+        first.finish().unwrap();
+
+        let mut second = batch(&mut writer);
+        queue!(second, Print("bar")).unwrap();
+        second.discard();
+
+        assert_eq!(writer.buffer, b"foo");
+        assert_eq!(writer.flushes, 1);
+    }
+}
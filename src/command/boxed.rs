@@ -0,0 +1,154 @@
+use std::fmt;
+#[cfg(windows)]
+use std::io;
+
+use super::Command;
+
+/// A boxed command which can be stored and executed later.
+///
+/// It is returned by [`Command::boxed`](trait.Command.html#method.boxed).
+pub type BoxedCommand = Box<dyn DynCommand + Send>;
+
+/// An object-safe version of [`Command`](trait.Command.html).
+///
+/// `Command` can't be used as a trait object because [`write_ansi`](trait.Command.html#tymethod.write_ansi)
+/// is generic. Every command implements `DynCommand` though, and `dyn DynCommand` as well as
+/// `Box<dyn DynCommand>` implement `Command` again, so commands of different types can be stored
+/// together and executed like any other command.
+///
+/// # Examples
+///
+/// ```no_run
+/// use std::io;
+/// use crossterm::{cursor, style::Print, terminal, BoxedCommand, Command, ExecutableCommand};
+///
+/// fn main() -> io::Result<()> {
+///     let commands: Vec<BoxedCommand> = vec![
+///         terminal::Clear(terminal::ClearType::All).boxed(),
+///         cursor::MoveTo(0, 0).boxed(),
+///         Print("Hello").boxed(),
+///     ];
+///
+///     io::stdout().execute(commands)?;
+///     Ok(())
+/// }
+/// ```
+pub trait DynCommand {
+    /// Writes the ANSI representation of this command, see
+    /// [`Command::write_ansi`](trait.Command.html#tymethod.write_ansi).
+    fn write_ansi_dyn(&self, f: &mut dyn fmt::Write) -> fmt::Result;
+
+    /// Executes this command with WinAPI calls, see
+    /// [`Command::execute_winapi`](trait.Command.html#tymethod.execute_winapi).
+    #[cfg(windows)]
+    fn execute_winapi_dyn(&self) -> io::Result<()>;
+
+    /// Returns whether the ANSI representation of this command is supported, see
+    /// [`Command::is_ansi_code_supported`](trait.Command.html#method.is_ansi_code_supported).
+    #[cfg(windows)]
+    fn is_ansi_code_supported_dyn(&self) -> bool;
+
+    /// Returns an estimate of the number of bytes the ANSI representation takes, see
+    /// [`Command::size_hint`](trait.Command.html#method.size_hint).
+    fn size_hint_dyn(&self) -> usize;
+}
+
+impl<T: Command> DynCommand for T {
+    fn write_ansi_dyn(&self, mut f: &mut dyn fmt::Write) -> fmt::Result {
+        self.write_ansi(&mut f)
+    }
+
+    #[cfg(windows)]
+    fn execute_winapi_dyn(&self) -> io::Result<()> {
+        self.execute_winapi()
+    }
+
+    #[cfg(windows)]
+    fn is_ansi_code_supported_dyn(&self) -> bool {
+        self.is_ansi_code_supported()
+    }
+
+    fn size_hint_dyn(&self) -> usize {
+        self.size_hint()
+    }
+}
+
+macro_rules! impl_command_for_dyn {
+    ($($ty:ty),+) => {$(
+        impl Command for $ty {
+            fn write_ansi(&self, f: &mut impl fmt::Write) -> fmt::Result {
+                self.write_ansi_dyn(f)
+            }
+
+            #[cfg(windows)]
+            fn execute_winapi(&self) -> io::Result<()> {
+                self.execute_winapi_dyn()
+            }
+
+            #[cfg(windows)]
+            fn is_ansi_code_supported(&self) -> bool {
+                self.is_ansi_code_supported_dyn()
+            }
+
+            fn size_hint(&self) -> usize {
+                self.size_hint_dyn()
+            }
+        }
+    )+};
+}
+
+impl_command_for_dyn!(
+    dyn DynCommand,
+    dyn DynCommand + Send,
+    dyn DynCommand + Send + Sync
+);
+
+impl<T: Command + ?Sized> Command for Box<T> {
+    fn write_ansi(&self, f: &mut impl fmt::Write) -> fmt::Result {
+        (**self).write_ansi(f)
+    }
+
+    #[cfg(windows)]
+    fn execute_winapi(&self) -> io::Result<()> {
+        (**self).execute_winapi()
+    }
+
+    #[cfg(windows)]
+    fn is_ansi_code_supported(&self) -> bool {
+        (**self).is_ansi_code_supported()
+    }
+
+    fn size_hint(&self) -> usize {
+        (**self).size_hint()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        cursor::MoveTo,
+        style::{Print, ResetColor},
+        Command, QueueableCommand,
+    };
+
+    use super::{BoxedCommand, DynCommand};
+
+    #[test]
+    fn test_heterogeneous_commands() {
+        let commands: Vec<BoxedCommand> = vec![
+            MoveTo(0, 0).boxed(),
+            Print("foo").boxed(),
+            Box::new(ResetColor),
+        ];
+
+        let mut writer = Vec::new();
+        writer.queue(&commands).unwrap();
+        writer.queue(&*commands[1]).unwrap();
+        assert_eq!(writer, b"\x1B[1;1Hfoo\x1B[0mfoo");
+
+        let command: &dyn DynCommand = &Print("bar");
+        let mut ansi = String::new();
+        command.write_ansi(&mut ansi).unwrap();
+        assert_eq!(ansi, "bar");
+    }
+}
@@ -0,0 +1,168 @@
+use std::io::{self, Write};
+
+use super::{Command, QueueableCommand};
+
+/// A reusable in-memory buffer to queue commands into.
+///
+/// Commands are queued with [`queue!`](macro.queue.html) or
+/// [`QueueableCommand`](trait.QueueableCommand.html) just like with any other writer, but end up
+/// in an internal `Vec<u8>`. [`flush_to`](#method.flush_to) writes everything with a single
+/// `write_all` call and clears the buffer while keeping its allocation, so the same buffer can be
+/// used for every frame of a render loop without locking `stdout` or making a syscall per command.
+///
+/// # Examples
+///
+/// ```no_run
+/// use std::io::{self, Write};
+/// use crossterm::{cursor, queue, style::Print, CommandBuffer};
+///
+/// fn main() -> io::Result<()> {
+///     let mut buffer = CommandBuffer::with_capacity(4096);
+///
+///     for frame in 0..60 {
+///         queue!(buffer, cursor::MoveTo(0, 0), Print(format!("frame {}", frame)))?;
+///         buffer.flush_to(&mut io::stdout())?;
+///     }
+///
+///     Ok(())
+/// }
+/// ```
+///
+/// # Notes
+///
+/// Commands that are executed with WinAPI calls on old Windows versions are executed immediately
+/// when queued, not when the buffer is flushed. The state crossterm tracks about the terminal,
+/// e.g. whether the alternate screen is active, isn't changed by the commands queued into the
+/// buffer, nor when it's flushed to stdout, see [`ProcessTerminal`](trait.ProcessTerminal.html).
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct CommandBuffer {
+    buffer: Vec<u8>,
+}
+
+impl CommandBuffer {
+    /// Creates an empty buffer.
+    pub fn new() -> CommandBuffer {
+        CommandBuffer::default()
+    }
+
+    /// Creates an empty buffer which can hold at least `capacity` bytes without reallocating.
+    pub fn with_capacity(capacity: usize) -> CommandBuffer {
+        CommandBuffer {
+            buffer: Vec::with_capacity(capacity),
+        }
+    }
+
+    /// Returns the bytes queued so far.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.buffer
+    }
+
+    /// Returns the number of bytes queued so far.
+    pub fn len(&self) -> usize {
+        self.buffer.len()
+    }
+
+    /// Returns `true` if nothing is queued.
+    pub fn is_empty(&self) -> bool {
+        self.buffer.is_empty()
+    }
+
+    /// Returns the number of bytes the buffer can hold without reallocating.
+    pub fn capacity(&self) -> usize {
+        self.buffer.capacity()
+    }
+
+    /// Reserves capacity for at least `additional` more bytes.
+    pub fn reserve(&mut self, additional: usize) {
+        self.buffer.reserve(additional);
+    }
+
+    /// Queues `command`, reserving capacity for it up front.
+    ///
+    /// This is the same as [`queue`](trait.QueueableCommand.html#tymethod.queue), except that the
+    /// buffer grows by the command's [`size_hint`](trait.Command.html#method.size_hint) at once
+    /// instead of reallocating while the command is written.
+    pub fn push(&mut self, command: impl Command) -> io::Result<&mut CommandBuffer> {
+        self.buffer.reserve(command.size_hint());
+        self.queue(command)
+    }
+
+    /// Discards everything queued so far, keeping the allocation.
+    pub fn clear(&mut self) {
+        self.buffer.clear();
+    }
+
+    /// Writes everything queued so far to `writer` with a single `write_all` call and flushes it.
+    ///
+    /// The buffer is cleared afterwards, even if writing failed, but keeps its allocation.
+    pub fn flush_to(&mut self, writer: &mut (impl Write + ?Sized)) -> io::Result<()> {
+        let result = writer.write_all(&self.buffer).and_then(|_| writer.flush());
+        self.buffer.clear();
+        result
+    }
+}
+
+impl Write for CommandBuffer {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.buffer.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn write_all(&mut self, buf: &[u8]) -> io::Result<()> {
+        self.buffer.extend_from_slice(buf);
+        Ok(())
+    }
+
+    /// Does nothing, use [`flush_to`](#method.flush_to) to write the buffer somewhere.
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl AsRef<[u8]> for CommandBuffer {
+    fn as_ref(&self) -> &[u8] {
+        &self.buffer
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{cursor::MoveTo, queue, style::Print, Command, QueueableCommand};
+
+    use super::CommandBuffer;
+
+    #[test]
+    fn test_queue_into_buffer() {
+        let mut buffer = CommandBuffer::new();
+        queue!(buffer, MoveTo(1, 2), Print("foo")).unwrap();
+        buffer.queue(Print("bar")).unwrap();
+
+        assert_eq!(buffer.as_bytes(), b"\x1B[3;2Hfoobar");
+    }
+
+    #[test]
+    fn test_push_reserves_size_hint() {
+        let commands = [MoveTo(1, 2), MoveTo(3, 4)];
+
+        let mut buffer = CommandBuffer::new();
+        buffer.push(commands).unwrap().push(Print("foo")).unwrap();
+        assert!(buffer.capacity() >= commands.size_hint());
+        assert_eq!(buffer.as_bytes(), b"\x1B[3;2H\x1B[5;4Hfoo");
+    }
+
+    #[test]
+    fn test_flush_to_keeps_allocation() {
+        let mut buffer = CommandBuffer::new();
+        let mut out = Vec::new();
+
+        queue!(buffer, Print("first frame")).unwrap();
+        let capacity = buffer.capacity();
+        buffer.flush_to(&mut out).unwrap();
+        assert!(buffer.is_empty());
+        assert_eq!(buffer.capacity(), capacity);
+
+        queue!(buffer, Print("second")).unwrap();
+        buffer.flush_to(&mut out).unwrap();
+        assert_eq!(out, b"first framesecond");
+    }
+}
@@ -0,0 +1,179 @@
+use std::io::{self, Write};
+
+/// A writer which keeps the bytes a busy inner writer can't take yet and writes them before the
+/// next ones, so writing never waits nor fails because the terminal is slow to read.
+///
+/// This is meant for a non-blocking tty, e.g. one opened with `O_NONBLOCK`, which returns
+/// `WouldBlock` when it can't take more bytes. The kept bytes belong to this writer, and at most
+/// `capacity` of them are kept: once that many are pending, writing returns `WouldBlock` again,
+/// which the [retry policy](enum.RetryPolicy.html) of the commands handles. Interrupted writes
+/// are tried again.
+///
+/// # Examples
+///
+/// ```no_run
+/// use std::io::{self, Write};
+/// use crossterm::{cursor, queue, style::Print, BufferingWriter};
+///
+/// fn main() -> io::Result<()> {
+///     let mut output = BufferingWriter::new(io::stdout(), 64 * 1024);
+///
+///     queue!(output, cursor::MoveTo(0, 0), Print("Hello"))?;
+///     // writes what the terminal takes now, the rest is written with the next frame
+///     output.flush()?;
+///
+///     Ok(())
+/// }
+/// ```
+#[derive(Debug)]
+pub struct BufferingWriter<W: Write> {
+    inner: W,
+    pending: Vec<u8>,
+    capacity: usize,
+}
+
+impl<W: Write> BufferingWriter<W> {
+    /// Creates a writer which keeps at most `capacity` bytes `inner` can't take yet.
+    pub fn new(inner: W, capacity: usize) -> BufferingWriter<W> {
+        BufferingWriter {
+            inner,
+            pending: Vec::new(),
+            capacity: capacity.max(1),
+        }
+    }
+
+    /// Returns the bytes which wait to be written.
+    pub fn pending(&self) -> &[u8] {
+        &self.pending
+    }
+
+    /// Writes as many of the bytes which wait to be written as the inner writer takes now,
+    /// without waiting. Returns `false` if there are still bytes pending.
+    pub fn try_flush_pending(&mut self) -> io::Result<bool> {
+        let mut written = 0;
+        let result = loop {
+            if written == self.pending.len() {
+                break Ok(());
+            }
+            match self.inner.write(&self.pending[written..]) {
+                Ok(0) => break Err(io::ErrorKind::WriteZero.into()),
+                Ok(n) => written += n,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+                Err(e) if e.kind() == io::ErrorKind::WouldBlock => break Ok(()),
+                Err(e) => break Err(e),
+            }
+        };
+        self.pending.drain(..written);
+        result.map(|()| self.pending.is_empty())
+    }
+
+    /// Returns a reference to the inner writer.
+    pub fn get_ref(&self) -> &W {
+        &self.inner
+    }
+
+    /// Returns a mutable reference to the inner writer.
+    pub fn get_mut(&mut self) -> &mut W {
+        &mut self.inner
+    }
+
+    /// Returns the inner writer, dropping the bytes which weren't written.
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+
+    /// Keeps as much of `buf` as there is room for.
+    fn keep(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let kept = buf
+            .len()
+            .min(self.capacity.saturating_sub(self.pending.len()));
+        if kept == 0 {
+            return Err(io::ErrorKind::WouldBlock.into());
+        }
+        self.pending.extend_from_slice(&buf[..kept]);
+        Ok(kept)
+    }
+}
+
+impl<W: Write> Write for BufferingWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        // the bytes must be written in order, so new ones are kept behind the pending ones
+        if !self.try_flush_pending()? {
+            return self.keep(buf);
+        }
+        loop {
+            match self.inner.write(buf) {
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+                Err(e) if e.kind() == io::ErrorKind::WouldBlock => return self.keep(buf),
+                result => return result,
+            }
+        }
+    }
+
+    /// Writes the bytes which wait to be written as far as the inner writer takes them now and
+    /// flushes it, the rest is written with the next write or flush.
+    fn flush(&mut self) -> io::Result<()> {
+        self.try_flush_pending()?;
+        match self.inner.flush() {
+            Err(e) if e.kind() == io::ErrorKind::WouldBlock => Ok(()),
+            result => result,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{self, Write};
+
+    use super::BufferingWriter;
+
+    // A writer which is busy while `busy` is set.
+    #[derive(Default)]
+    struct Busy {
+        written: Vec<u8>,
+        busy: bool,
+    }
+
+    impl Write for Busy {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            if self.busy {
+                return Err(io::ErrorKind::WouldBlock.into());
+            }
+            self.written.extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_buffering_writer() {
+        let mut output = BufferingWriter::new(Busy::default(), 6);
+        output.write_all(b"abc").unwrap();
+        assert_eq!(output.get_ref().written, b"abc");
+
+        // busy, so the bytes are kept and written before the next ones
+        output.get_mut().busy = true;
+        output.write_all(b"def").unwrap();
+        assert_eq!(output.write(b"ghij").unwrap(), 3);
+        assert_eq!(output.pending(), b"defghi");
+        // full
+        let e = output.write(b"j").unwrap_err();
+        assert_eq!(e.kind(), io::ErrorKind::WouldBlock);
+
+        output.get_mut().busy = false;
+        output.write_all(b"j").unwrap();
+        assert_eq!(output.get_ref().written, b"abcdefghij");
+        assert!(output.pending().is_empty());
+
+        output.get_mut().busy = true;
+        output.write_all(b"k").unwrap();
+        output.flush().unwrap();
+        assert!(!output.try_flush_pending().unwrap());
+        output.get_mut().busy = false;
+        assert!(output.try_flush_pending().unwrap());
+        assert_eq!(output.into_inner().written, b"abcdefghijk");
+    }
+}
@@ -0,0 +1,49 @@
+//! Support for [`queue!`](../macro.queue.html) and [`execute!`](../macro.execute.html) to lock
+//! `stdout` once per invocation instead of once per command.
+//!
+//! The macros call `Locker(writer).lock_writer()`. If the writer is `Stdout`, method resolution
+//! picks [`LockStdout`], which takes the lock. For a [`ColoredWriter`] it picks [`LockColored`],
+//! which applies the writer's color override. For every other writer it falls back to
+//! [`PassThrough`], which returns the writer itself.
+
+use std::io::{Stdout, StdoutLock, Write};
+
+use crate::style::{ColorScope, ColoredWriter};
+
+/// Wraps the writer given to the macros.
+pub struct Locker<'a, W: ?Sized>(pub &'a mut W);
+
+/// Locks `stdout` for the duration of a macro invocation.
+pub trait LockStdout<'a> {
+    fn lock_writer(self) -> StdoutLock<'a>;
+}
+
+impl<'a> LockStdout<'a> for Locker<'a, Stdout> {
+    fn lock_writer(self) -> StdoutLock<'a> {
+        let stdout: &'a Stdout = self.0;
+        stdout.lock()
+    }
+}
+
+/// Shows or hides colors as a [`ColoredWriter`] is configured for the duration of a macro
+/// invocation.
+pub trait LockColored<'a, W> {
+    fn lock_writer(self) -> ColorScope<'a, W>;
+}
+
+impl<'a, W: Write> LockColored<'a, W> for Locker<'a, ColoredWriter<W>> {
+    fn lock_writer(self) -> ColorScope<'a, W> {
+        self.0.scope()
+    }
+}
+
+/// Uses any other writer as is.
+pub trait PassThrough<W: ?Sized> {
+    fn lock_writer(&mut self) -> &mut W;
+}
+
+impl<'a, W: ?Sized> PassThrough<W> for Locker<'a, W> {
+    fn lock_writer(&mut self) -> &mut W {
+        self.0
+    }
+}
@@ -0,0 +1,337 @@
+use std::{
+    io::{self, Write},
+    sync::Arc,
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+use parking_lot::{Condvar, Mutex, MutexGuard};
+
+use super::{write::write_all_with, RetryPolicy, CHUNK_SIZE};
+
+// How many bytes `NonBlockingWriter::new` queues at most.
+const DEFAULT_CAPACITY: usize = 1024 * 1024;
+
+/// A writer which never waits for the terminal: the bytes written to it are queued, and written
+/// to the inner writer by a background thread.
+///
+/// Writing to a terminal blocks while the terminal doesn't read, e.g. after Ctrl-S or over a slow
+/// SSH connection, which freezes an application that renders on its main thread. With
+/// `NonBlockingWriter`, commands are queued as with any other writer, and `flush` hands the queue
+/// to the background thread without waiting for it to be written. Use
+/// [`flush_pending`](#method.flush_pending) to wait until everything is written, e.g. before
+/// leaving the alternate screen.
+///
+/// At most `capacity` bytes are queued, a megabyte with [`new`](#method.new). Once the queue is
+/// full, writing returns `WouldBlock` until the background thread caught up, which the
+/// [retry policy](enum.RetryPolicy.html) of the commands handles. The background thread waits
+/// for a busy inner writer, regardless of the retry policy.
+///
+/// Errors of the inner writer are returned by the next call which writes or flushes.
+///
+/// # Examples
+///
+/// ```no_run
+/// use std::io::{self, Write};
+/// use crossterm::{cursor, queue, style::Print, NonBlockingWriter};
+///
+/// fn main() -> io::Result<()> {
+///     let mut output = NonBlockingWriter::new(io::stdout());
+///
+///     for frame in 0..600 {
+///         queue!(output, cursor::MoveTo(0, 0), Print(format!("frame {}", frame)))?;
+///         // returns immediately, even if the terminal is stalled
+///         output.flush()?;
+///     }
+///
+///     output.flush_pending()
+/// }
+/// ```
+///
+/// # Notes
+///
+/// Bytes which are still queued when the writer is dropped are written by the background thread
+/// as long as the process runs, call [`flush_pending`](#method.flush_pending) or
+/// [`into_inner`](#method.into_inner) to make sure they're written.
+#[derive(Debug)]
+pub struct NonBlockingWriter<W: Write + Send + 'static> {
+    shared: Arc<Shared>,
+    thread: Option<JoinHandle<W>>,
+    capacity: usize,
+}
+
+#[derive(Debug, Default)]
+struct Shared {
+    state: Mutex<State>,
+    changed: Condvar,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    queue: Vec<u8>,
+    // the number of bytes the thread is writing
+    writing: usize,
+    flush_requested: bool,
+    closed: bool,
+    error: Option<io::Error>,
+}
+
+impl State {
+    fn is_done(&self) -> bool {
+        self.queue.is_empty() && self.writing == 0 && !self.flush_requested
+    }
+}
+
+impl<W: Write + Send + 'static> NonBlockingWriter<W> {
+    /// Creates a writer which writes to `inner` on a background thread and queues at most a
+    /// megabyte.
+    pub fn new(inner: W) -> NonBlockingWriter<W> {
+        NonBlockingWriter::with_capacity(inner, DEFAULT_CAPACITY)
+    }
+
+    /// Creates a writer which writes to `inner` on a background thread and queues at most
+    /// `capacity` bytes.
+    pub fn with_capacity(inner: W, capacity: usize) -> NonBlockingWriter<W> {
+        let shared = Arc::new(Shared::default());
+        let thread = {
+            let shared = shared.clone();
+            thread::Builder::new()
+                .name("crossterm-output".to_string())
+                .spawn(move || drain(inner, &shared))
+                .expect("failed to spawn the output thread")
+        };
+
+        NonBlockingWriter {
+            shared,
+            thread: Some(thread),
+            capacity: capacity.max(1),
+        }
+    }
+
+    /// Returns the number of bytes which are queued or being written.
+    pub fn pending(&self) -> usize {
+        let state = self.shared.state.lock();
+        state.queue.len() + state.writing
+    }
+
+    /// Waits until everything written so far is written to the inner writer, and the inner
+    /// writer is flushed.
+    pub fn flush_pending(&mut self) -> io::Result<()> {
+        let mut state = self.request_flush()?;
+        while !state.is_done() && state.error.is_none() {
+            self.shared.changed.wait(&mut state);
+        }
+        take_error(&mut state)
+    }
+
+    /// Waits at most `timeout` until everything written so far is written, see
+    /// [`flush_pending`](#method.flush_pending). Returns `false` if there are still bytes
+    /// pending.
+    pub fn flush_pending_timeout(&mut self, timeout: Duration) -> io::Result<bool> {
+        let deadline = Instant::now() + timeout;
+        let mut state = self.request_flush()?;
+        while !state.is_done() && state.error.is_none() {
+            if self
+                .shared
+                .changed
+                .wait_until(&mut state, deadline)
+                .timed_out()
+            {
+                break;
+            }
+        }
+        take_error(&mut state)?;
+        Ok(state.is_done())
+    }
+
+    /// Writes everything which is pending, stops the background thread and returns the inner
+    /// writer.
+    pub fn into_inner(mut self) -> io::Result<W> {
+        self.flush_pending()?;
+        self.close();
+        let thread = self
+            .thread
+            .take()
+            .expect("the output thread was already joined");
+        thread
+            .join()
+            .map_err(|_| io::Error::new(io::ErrorKind::Other, "the output thread panicked"))
+    }
+
+    fn request_flush(&self) -> io::Result<MutexGuard<'_, State>> {
+        let mut state = self.shared.state.lock();
+        take_error(&mut state)?;
+        state.flush_requested = true;
+        self.shared.changed.notify_all();
+        Ok(state)
+    }
+
+    fn close(&self) {
+        self.shared.state.lock().closed = true;
+        self.shared.changed.notify_all();
+    }
+}
+
+fn take_error(state: &mut State) -> io::Result<()> {
+    match state.error.take() {
+        Some(e) => Err(e),
+        None => Ok(()),
+    }
+}
+
+/// Writes the queue whenever a flush is requested, until the writer is closed.
+fn drain<W: Write>(mut inner: W, shared: &Shared) -> W {
+    let mut bytes = Vec::new();
+    let mut state = shared.state.lock();
+    loop {
+        while !state.flush_requested && !state.closed {
+            shared.changed.wait(&mut state);
+        }
+        if state.closed && state.queue.is_empty() {
+            return inner;
+        }
+
+        std::mem::swap(&mut state.queue, &mut bytes);
+        state.writing = bytes.len();
+        state.flush_requested = false;
+        let result = MutexGuard::unlocked(&mut state, || {
+            write_all_with(&mut inner, &bytes, RetryPolicy::default()).and_then(|_| inner.flush())
+        });
+        bytes.clear();
+
+        state.writing = 0;
+        if let Err(e) = result {
+            state.error.get_or_insert(e);
+        }
+        shared.changed.notify_all();
+    }
+}
+
+impl<W: Write + Send + 'static> Write for NonBlockingWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let mut state = self.shared.state.lock();
+        take_error(&mut state)?;
+        let free = self
+            .capacity
+            .saturating_sub(state.queue.len() + state.writing);
+        if free == 0 && !buf.is_empty() {
+            return Err(io::ErrorKind::WouldBlock.into());
+        }
+        let buf = &buf[..buf.len().min(free)];
+        state.queue.extend_from_slice(buf);
+        // start writing large queues before they're flushed
+        if state.queue.len() >= CHUNK_SIZE {
+            state.flush_requested = true;
+            self.shared.changed.notify_all();
+        }
+        Ok(buf.len())
+    }
+
+    /// Hands everything written so far to the background thread, without waiting for it to be
+    /// written.
+    fn flush(&mut self) -> io::Result<()> {
+        self.request_flush().map(drop)
+    }
+}
+
+impl<W: Write + Send + 'static> Drop for NonBlockingWriter<W> {
+    fn drop(&mut self) {
+        // the thread isn't joined, the terminal may be stalled
+        self.close();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        io::{self, Write},
+        sync::Arc,
+        time::Duration,
+    };
+
+    use parking_lot::Mutex;
+
+    use crate::{queue, style::Print};
+
+    use super::NonBlockingWriter;
+
+    #[derive(Clone, Default)]
+    struct Terminal(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for Terminal {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_stalled_terminal_doesnt_block() {
+        let terminal = Terminal::default();
+        let mut output = NonBlockingWriter::new(terminal.clone());
+
+        let stalled = terminal.0.lock();
+        queue!(output, Print("foo")).unwrap();
+        output.flush().unwrap();
+        queue!(output, Print("bar")).unwrap();
+        output.flush().unwrap();
+        assert!(!output
+            .flush_pending_timeout(Duration::from_millis(10))
+            .unwrap());
+        assert_eq!(output.pending(), 6);
+        drop(stalled);
+
+        output.flush_pending().unwrap();
+        assert_eq!(output.pending(), 0);
+        assert_eq!(*terminal.0.lock(), b"foobar");
+
+        queue!(output, Print("baz")).unwrap();
+        let terminal = output.into_inner().unwrap();
+        assert_eq!(*terminal.0.lock(), b"foobarbaz");
+    }
+
+    #[test]
+    fn test_full_queue_would_block() {
+        let terminal = Terminal::default();
+        let mut output = NonBlockingWriter::with_capacity(terminal.clone(), 4);
+
+        let stalled = terminal.0.lock();
+        assert_eq!(output.write(b"foobar").unwrap(), 4);
+        output.flush().unwrap();
+        let e = output.write(b"bar").unwrap_err();
+        assert_eq!(e.kind(), io::ErrorKind::WouldBlock);
+        assert_eq!(output.pending(), 4);
+        drop(stalled);
+
+        output.flush_pending().unwrap();
+        output.write_all(b"bar").unwrap();
+        output.flush_pending().unwrap();
+        assert_eq!(*terminal.0.lock(), b"foobbar");
+    }
+
+    #[test]
+    fn test_errors_are_returned_later() {
+        struct Broken;
+
+        impl Write for Broken {
+            fn write(&mut self, _: &[u8]) -> io::Result<usize> {
+                Err(io::ErrorKind::BrokenPipe.into())
+            }
+
+            fn flush(&mut self) -> io::Result<()> {
+                Ok(())
+            }
+        }
+
+        let mut output = NonBlockingWriter::new(Broken);
+        output.write_all(b"foo").unwrap();
+        output.flush().unwrap();
+        let e = output.flush_pending().unwrap_err();
+        assert_eq!(e.kind(), io::ErrorKind::BrokenPipe);
+        output.flush_pending().unwrap();
+    }
+}
@@ -0,0 +1,428 @@
+#[cfg(windows)]
+use std::io;
+use std::{cell::Cell, fmt};
+
+use super::Command;
+use crate::capabilities::{Multiplexer, MultiplexerPolicy};
+
+thread_local! {
+    /// A `TmuxPassthrough` is written, so the commands inside aren't wrapped once more.
+    static IN_PASSTHROUGH: Cell<bool> = Cell::new(false);
+}
+
+/// The longest string GNU screen passes on, longer ones are split.
+const SCREEN_CHUNK_LEN: usize = 76;
+
+/// Wraps `command` in the passthrough sequence of tmux, see
+/// [`TmuxPassthrough`](struct.TmuxPassthrough.html).
+pub fn tmux_passthrough<C: Command>(command: C) -> TmuxPassthrough<C> {
+    TmuxPassthrough(command)
+}
+
+/// Wraps `command` in the passthrough sequence of GNU screen, see
+/// [`ScreenPassthrough`](struct.ScreenPassthrough.html).
+pub fn screen_passthrough<C: Command>(command: C) -> ScreenPassthrough<C> {
+    ScreenPassthrough(command)
+}
+
+/// A command that writes another command in the passthrough sequence of tmux,
+/// `DCS tmux ; ... ST`, so it reaches the terminal tmux runs in instead of tmux itself.
+///
+/// This is meant for sequences tmux doesn't understand or handle itself, e.g. the clipboard
+/// with [`CopyToClipboard`](clipboard/struct.CopyToClipboard.html) or the graphics protocols.
+/// The `ESC`s of the command are doubled, as tmux requires, and tmux 3.3 and later only pass
+/// the sequence on with `set -g allow-passthrough on`. Outside of tmux, terminals ignore it.
+///
+/// Everything the command writes ends up in the outer terminal as is, at the cursor position
+/// of that terminal, which tmux doesn't know about.
+///
+/// # Examples
+///
+/// ```no_run
+/// use std::io;
+/// use crossterm::{clipboard::CopyToClipboard, execute, tmux_passthrough};
+///
+/// fn main() -> io::Result<()> {
+///     execute!(
+///         io::stdout(),
+///         tmux_passthrough(CopyToClipboard::to_clipboard_from("foo"))
+///     )
+/// }
+/// ```
+///
+/// # Notes
+///
+/// Commands must be executed/queued for execution otherwise they do nothing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TmuxPassthrough<C>(pub C);
+
+/// A command that writes another command in the passthrough sequence of GNU screen,
+/// `DCS ... ST`, so it reaches the terminal screen runs in instead of screen itself.
+///
+/// Screen passes on only short strings, so the command is split into several sequences of at
+/// most 76 bytes, and it ends the string at the first `ST`. The `ST`s of the command are
+/// therefore written as `BEL`, which terminals only take as the end of an `OSC` sequence, e.g.
+/// of [`CopyToClipboard`](clipboard/struct.CopyToClipboard.html) or
+/// [`SetTitle`](terminal/struct.SetTitle.html). The graphics protocols besides the inline
+/// images of iTerm2 don't survive it.
+///
+/// # Notes
+///
+/// Commands must be executed/queued for execution otherwise they do nothing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ScreenPassthrough<C>(pub C);
+
+/// Runs `write` with the commands it writes marked as inside a passthrough sequence, the
+/// previous mark is restored afterwards, also when `write` panics.
+fn in_passthrough<T>(write: impl FnOnce() -> T) -> T {
+    struct Restore(bool);
+
+    impl Drop for Restore {
+        fn drop(&mut self) {
+            IN_PASSTHROUGH.with(|inside| inside.set(self.0));
+        }
+    }
+
+    let _restore = Restore(IN_PASSTHROUGH.with(|inside| inside.replace(true)));
+    write()
+}
+
+/// How a [`PassthroughWriter`] escapes what's written.
+#[derive(Clone, Copy)]
+enum Envelope {
+    /// Written as is.
+    None,
+    /// The `ESC`s are doubled.
+    Tmux,
+    /// `ST` is written as `BEL` and the sequence is split in chunks.
+    Screen,
+}
+
+/// The writer a sequence inside a multiplexer is written to, which escapes it for the
+/// passthrough sequence, if any.
+pub(crate) struct PassthroughWriter<'a, W> {
+    inner: &'a mut W,
+    envelope: Envelope,
+    /// The length of the current chunk of the screen passthrough sequence.
+    chunk_len: usize,
+    /// The last character was an `ESC` which isn't written yet, as it may start an `ST`.
+    escape: bool,
+}
+
+impl<'a, W: fmt::Write> PassthroughWriter<'a, W> {
+    fn new(inner: &'a mut W, envelope: Envelope) -> Self {
+        PassthroughWriter {
+            inner,
+            envelope,
+            chunk_len: 0,
+            escape: false,
+        }
+    }
+
+    /// Writes a character of the screen passthrough sequence, in a new chunk if it's full.
+    fn write_chunked(&mut self, c: char) -> fmt::Result {
+        if self.chunk_len + c.len_utf8() > SCREEN_CHUNK_LEN {
+            self.inner.write_str("\x1B\\\x1BP")?;
+            self.chunk_len = 0;
+        }
+        self.chunk_len += c.len_utf8();
+        self.inner.write_char(c)
+    }
+
+    /// Ends the passthrough sequence and starts another one, so the escape sequences written
+    /// before and after reach the terminal separately, e.g. the chunks of a kitty image, which
+    /// the multiplexer may not pass on as one.
+    pub(crate) fn next_envelope(&mut self) -> fmt::Result {
+        match self.envelope {
+            Envelope::None => Ok(()),
+            Envelope::Tmux => self.inner.write_str("\x1B\\\x1BPtmux;"),
+            Envelope::Screen => {
+                self.flush_escape()?;
+                self.chunk_len = 0;
+                self.inner.write_str("\x1B\\\x1BP")
+            }
+        }
+    }
+
+    /// Writes the `ESC` which is held back, if any.
+    fn flush_escape(&mut self) -> fmt::Result {
+        if self.escape {
+            self.escape = false;
+            self.write_chunked('\x1B')?;
+        }
+        Ok(())
+    }
+}
+
+impl<W: fmt::Write> fmt::Write for PassthroughWriter<'_, W> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        match self.envelope {
+            Envelope::None => self.inner.write_str(s),
+            Envelope::Tmux => {
+                for (index, part) in s.split('\x1B').enumerate() {
+                    if index > 0 {
+                        self.inner.write_str("\x1B\x1B")?;
+                    }
+                    self.inner.write_str(part)?;
+                }
+                Ok(())
+            }
+            Envelope::Screen => {
+                for c in s.chars() {
+                    if self.escape && c == '\\' {
+                        self.escape = false;
+                        self.write_chunked('\x07')?;
+                    } else {
+                        self.flush_escape()?;
+                        if c == '\x1B' {
+                            self.escape = true;
+                        } else {
+                            self.write_chunked(c)?;
+                        }
+                    }
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Writes what `write` writes in the passthrough sequence of tmux.
+fn write_tmux_passthrough<W: fmt::Write>(
+    f: &mut W,
+    write: impl FnOnce(&mut PassthroughWriter<'_, W>) -> fmt::Result,
+) -> fmt::Result {
+    f.write_str("\x1BPtmux;")?;
+    in_passthrough(|| write(&mut PassthroughWriter::new(f, Envelope::Tmux)))?;
+    f.write_str("\x1B\\")
+}
+
+/// Writes what `write` writes in the passthrough sequence of GNU screen.
+fn write_screen_passthrough<W: fmt::Write>(
+    f: &mut W,
+    write: impl FnOnce(&mut PassthroughWriter<'_, W>) -> fmt::Result,
+) -> fmt::Result {
+    f.write_str("\x1BP")?;
+    in_passthrough(|| {
+        let mut writer = PassthroughWriter::new(f, Envelope::Screen);
+        write(&mut writer).and_then(|_| writer.flush_escape())
+    })?;
+    f.write_str("\x1B\\")
+}
+
+/// Writes a sequence the multiplexer would handle or drop itself, e.g. the clipboard, as the
+/// [`MultiplexerPolicy`] of the installed capabilities says.
+pub(crate) fn write_multiplexed<W: fmt::Write>(
+    f: &mut W,
+    write: impl FnOnce(&mut PassthroughWriter<'_, W>) -> fmt::Result,
+) -> fmt::Result {
+    let (multiplexer, policy) =
+        crate::capabilities::query(|c| (c.multiplexer, c.multiplexer_policy));
+    write_multiplexed_with(f, multiplexer, policy, write)
+}
+
+/// Like [`write_multiplexed`], with the multiplexer and policy to use.
+pub(crate) fn write_multiplexed_with<W: fmt::Write>(
+    f: &mut W,
+    multiplexer: Option<Multiplexer>,
+    policy: MultiplexerPolicy,
+    write: impl FnOnce(&mut PassthroughWriter<'_, W>) -> fmt::Result,
+) -> fmt::Result {
+    let wrapped = IN_PASSTHROUGH.with(Cell::get);
+
+    match (multiplexer, policy) {
+        (Some(_), MultiplexerPolicy::Suppress) if !wrapped => {
+            crate::metrics::add_command_suppressed();
+            Ok(())
+        }
+        (Some(Multiplexer::Tmux), MultiplexerPolicy::Passthrough) if !wrapped => {
+            crate::metrics::add_command_passed_through();
+            write_tmux_passthrough(f, write)
+        }
+        (Some(Multiplexer::Screen), MultiplexerPolicy::Passthrough) if !wrapped => {
+            crate::metrics::add_command_passed_through();
+            write_screen_passthrough(f, write)
+        }
+        _ => write(&mut PassthroughWriter::new(f, Envelope::None)),
+    }
+}
+
+impl<C: Command> Command for TmuxPassthrough<C> {
+    fn write_ansi(&self, f: &mut impl fmt::Write) -> fmt::Result {
+        write_tmux_passthrough(f, |f| self.0.write_ansi(f))
+    }
+
+    #[cfg(windows)]
+    fn execute_winapi(&self) -> io::Result<()> {
+        self.0.execute_winapi()
+    }
+
+    #[cfg(windows)]
+    fn is_ansi_code_supported(&self) -> bool {
+        self.0.is_ansi_code_supported()
+    }
+
+    fn size_hint(&self) -> usize {
+        self.0.size_hint() + 9
+    }
+}
+
+impl<C: Command> Command for ScreenPassthrough<C> {
+    fn write_ansi(&self, f: &mut impl fmt::Write) -> fmt::Result {
+        write_screen_passthrough(f, |f| self.0.write_ansi(f))
+    }
+
+    #[cfg(windows)]
+    fn execute_winapi(&self) -> io::Result<()> {
+        self.0.execute_winapi()
+    }
+
+    #[cfg(windows)]
+    fn is_ansi_code_supported(&self) -> bool {
+        self.0.is_ansi_code_supported()
+    }
+
+    fn size_hint(&self) -> usize {
+        let len = self.0.size_hint();
+        len + len / SCREEN_CHUNK_LEN * 4 + 4
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fmt::Write;
+
+    use crate::{
+        capabilities::{Multiplexer, MultiplexerPolicy},
+        style::Print,
+        Command,
+    };
+
+    use super::{
+        screen_passthrough, tmux_passthrough, write_multiplexed_with, write_tmux_passthrough,
+    };
+
+    #[test]
+    fn test_tmux_passthrough_doubles_escapes() {
+        assert_eq!(
+            tmux_passthrough(Print("\x1B]52;c;Zm9v\x1B\\")).ansi_string(),
+            "\x1BPtmux;\x1B\x1B]52;c;Zm9v\x1B\x1B\\\x1B\\"
+        );
+        assert_eq!(
+            tmux_passthrough(Print("foo")).ansi_string(),
+            "\x1BPtmux;foo\x1B\\"
+        );
+    }
+
+    #[test]
+    fn test_screen_passthrough() {
+        assert_eq!(
+            screen_passthrough(Print("\x1B]52;c;Zm9v\x1B\\")).ansi_string(),
+            "\x1BP\x1B]52;c;Zm9v\x07\x1B\\"
+        );
+        assert_eq!(
+            screen_passthrough(Print("\x1B]0;foo\x1B")).ansi_string(),
+            "\x1BP\x1B]0;foo\x1B\x1B\\"
+        );
+
+        let title = "a".repeat(100);
+        assert_eq!(
+            screen_passthrough(Print(format!("\x1B]0;{}\x07", title))).ansi_string(),
+            format!(
+                "\x1BP\x1B]0;{}\x1B\\\x1BP{}\x07\x1B\\",
+                &title[..72],
+                &title[72..]
+            )
+        );
+    }
+
+    #[test]
+    fn test_multiplexer_policy() {
+        let write = |multiplexer, policy| {
+            let mut sequence = String::new();
+            write_multiplexed_with(&mut sequence, multiplexer, policy, |f| {
+                f.write_str("\x1B]52;c;\x1B\\")
+            })
+            .unwrap();
+            sequence
+        };
+
+        assert_eq!(write(None, MultiplexerPolicy::Suppress), "\x1B]52;c;\x1B\\");
+        assert_eq!(
+            write(Some(Multiplexer::Tmux), MultiplexerPolicy::Passthrough),
+            "\x1BPtmux;\x1B\x1B]52;c;\x1B\x1B\\\x1B\\"
+        );
+        assert_eq!(
+            write(Some(Multiplexer::Tmux), MultiplexerPolicy::Suppress),
+            ""
+        );
+        assert_eq!(
+            write(Some(Multiplexer::Zellij), MultiplexerPolicy::Passthrough),
+            "\x1B]52;c;\x1B\\"
+        );
+        assert_eq!(
+            write(Some(Multiplexer::Screen), MultiplexerPolicy::Passthrough),
+            "\x1BP\x1B]52;c;\x07\x1B\\"
+        );
+        assert_eq!(
+            write(Some(Multiplexer::Tmux), MultiplexerPolicy::Direct),
+            "\x1B]52;c;\x1B\\"
+        );
+
+        // wrapped explicitly, the sequence isn't wrapped twice
+        let mut sequence = String::new();
+        write_tmux_passthrough(&mut sequence, |f| {
+            write_multiplexed_with(
+                f,
+                Some(Multiplexer::Tmux),
+                MultiplexerPolicy::Passthrough,
+                |f| f.write_str("\x1B]2;foo\x07"),
+            )
+        })
+        .unwrap();
+        assert_eq!(sequence, "\x1BPtmux;\x1B\x1B]2;foo\x07\x1B\\");
+
+        // nor after a nested passthrough sequence ended
+        let mut sequence = String::new();
+        write_tmux_passthrough(&mut sequence, |f| {
+            write_tmux_passthrough(f, |f| f.write_str("a"))?;
+            write_multiplexed_with(
+                f,
+                Some(Multiplexer::Tmux),
+                MultiplexerPolicy::Passthrough,
+                |f| f.write_str("b"),
+            )
+        })
+        .unwrap();
+        assert_eq!(sequence, "\x1BPtmux;\x1B\x1BPtmux;a\x1B\x1B\\b\x1B\\");
+    }
+
+    #[test]
+    fn test_next_envelope() {
+        let write = |multiplexer| {
+            let mut sequence = String::new();
+            write_multiplexed_with(
+                &mut sequence,
+                Some(multiplexer),
+                MultiplexerPolicy::Passthrough,
+                |f| {
+                    f.write_str("\x1B_Ga\x1B\\")?;
+                    f.next_envelope()?;
+                    f.write_str("\x1B_Gb\x1B\\")
+                },
+            )
+            .unwrap();
+            sequence
+        };
+
+        assert_eq!(
+            write(Multiplexer::Tmux),
+            "\x1BPtmux;\x1B\x1B_Ga\x1B\x1B\\\x1B\\\x1BPtmux;\x1B\x1B_Gb\x1B\x1B\\\x1B\\"
+        );
+        assert_eq!(
+            write(Multiplexer::Screen),
+            "\x1BP\x1B_Ga\x07\x1B\\\x1BP\x1B_Gb\x07\x1B\\"
+        );
+        assert_eq!(write(Multiplexer::Zellij), "\x1B_Ga\x1B\\\x1B_Gb\x1B\\");
+    }
+}
@@ -0,0 +1,289 @@
+use std::{
+    io::{self, Write},
+    thread,
+    time::{Duration, Instant},
+};
+
+use super::write_all;
+
+/// A writer which limits how many bytes per second are written to a slow link, e.g. a serial
+/// line or a congested SSH connection, by dropping frames.
+///
+/// Everything written between two flushes is a frame. A flushed frame is written right away if
+/// the frames before it have been paid for at the configured rate, otherwise it's kept until they
+/// have, and is replaced by the next frame which is flushed meanwhile. So the terminal shows the
+/// latest frame as soon as the link allows instead of falling behind with an ever growing
+/// backlog.
+///
+/// As frames may be dropped, every frame should draw everything it changes since the last frame
+/// which was written. Applications which only draw the differences to the previous frame can
+/// check [`dropped_frames`](#method.dropped_frames) and redraw everything after a drop. The modes
+/// a dropped frame changes, e.g. the alternate screen, the cursor visibility and style, the mouse
+/// capture and the keyboard enhancement flags, aren't lost though: their sequences are written
+/// with the next frame.
+///
+/// # Examples
+///
+/// ```no_run
+/// use std::{io::{self, Write}, thread, time::Duration};
+/// use crossterm::{cursor, queue, style::Print, RateLimitedWriter};
+///
+/// fn main() -> io::Result<()> {
+///     // about 9600 baud
+///     let mut output = RateLimitedWriter::new(io::stdout(), 960);
+///
+///     for tick in 0..100 {
+///         queue!(output, cursor::MoveTo(0, 0), Print(format!("tick {}", tick)))?;
+///         output.flush()?;
+///         thread::sleep(Duration::from_millis(10));
+///     }
+///
+///     output.flush_pending()
+/// }
+/// ```
+#[derive(Debug)]
+pub struct RateLimitedWriter<W: Write> {
+    inner: W,
+    bytes_per_second: u64,
+    max_frame_size: Option<usize>,
+    frame: Vec<u8>,
+    pending: Option<Vec<u8>>,
+    // the bytes written which aren't paid for yet
+    debt: f64,
+    paid_at: Instant,
+    dropped_frames: u64,
+}
+
+impl<W: Write> RateLimitedWriter<W> {
+    /// Creates a writer which writes at most `bytes_per_second` bytes per second on average to
+    /// `inner`.
+    pub fn new(inner: W, bytes_per_second: u64) -> RateLimitedWriter<W> {
+        RateLimitedWriter {
+            inner,
+            bytes_per_second: bytes_per_second.max(1),
+            max_frame_size: None,
+            frame: Vec::new(),
+            pending: None,
+            debt: 0.0,
+            paid_at: Instant::now(),
+            dropped_frames: 0,
+        }
+    }
+
+    /// Drops every frame larger than `max_frame_size` bytes, which would hold the link up for
+    /// too long.
+    pub fn with_max_frame_size(mut self, max_frame_size: usize) -> RateLimitedWriter<W> {
+        self.max_frame_size = Some(max_frame_size);
+        self
+    }
+
+    /// Returns the number of frames which were dropped so far.
+    pub fn dropped_frames(&self) -> u64 {
+        self.dropped_frames
+    }
+
+    /// Returns whether a flushed frame waits to be written.
+    pub fn has_pending(&self) -> bool {
+        self.pending.is_some()
+    }
+
+    /// Writes the frame which waits to be written if the link allows it now, without waiting.
+    /// Returns `false` if the frame still waits.
+    ///
+    /// Call this regularly if frames aren't flushed regularly, so that the last frame doesn't
+    /// wait for the next one.
+    pub fn try_flush_pending(&mut self) -> io::Result<bool> {
+        self.write_pending_at(Instant::now())
+    }
+
+    /// Waits until the frame which waits to be written, if any, can be written and writes it.
+    pub fn flush_pending(&mut self) -> io::Result<()> {
+        while !self.try_flush_pending()? {
+            thread::sleep(Duration::from_secs_f64(
+                self.debt / self.bytes_per_second as f64,
+            ));
+        }
+        Ok(())
+    }
+
+    /// Returns a reference to the inner writer.
+    pub fn get_ref(&self) -> &W {
+        &self.inner
+    }
+
+    /// Returns a mutable reference to the inner writer.
+    pub fn get_mut(&mut self) -> &mut W {
+        &mut self.inner
+    }
+
+    /// Returns the inner writer, dropping the frames which weren't written.
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+
+    fn flush_at(&mut self, now: Instant) -> io::Result<()> {
+        if self.frame.is_empty() && self.pending.is_none() {
+            return self.inner.flush();
+        }
+
+        let frame = std::mem::take(&mut self.frame);
+        let too_large = self
+            .max_frame_size
+            .map_or(false, |max_frame_size| frame.len() > max_frame_size);
+        // a frame which is too large, or the frame it supersedes, is dropped, apart from its modes
+        if too_large {
+            self.dropped_frames += 1;
+            let modes = mode_sequences(&frame);
+            if !modes.is_empty() {
+                self.pending.get_or_insert_with(Vec::new).extend(modes);
+            }
+        } else if !frame.is_empty() {
+            if let Some(superseded) = self.pending.take() {
+                self.dropped_frames += 1;
+                let mut modes = mode_sequences(&superseded);
+                modes.extend(frame);
+                self.pending = Some(modes);
+            } else {
+                self.pending = Some(frame);
+            }
+        }
+        self.write_pending_at(now).map(drop)
+    }
+
+    fn write_pending_at(&mut self, now: Instant) -> io::Result<bool> {
+        let paid = now.saturating_duration_since(self.paid_at).as_secs_f64()
+            * self.bytes_per_second as f64;
+        self.debt = (self.debt - paid).max(0.0);
+        self.paid_at = now;
+
+        if self.pending.is_none() {
+            return Ok(true);
+        }
+        // less than a byte is rounding
+        if self.debt >= 1.0 {
+            return Ok(false);
+        }
+        if let Some(frame) = self.pending.take() {
+            self.debt = frame.len() as f64;
+            write_all(&mut self.inner, &frame)?;
+            self.inner.flush()?;
+        }
+        Ok(true)
+    }
+}
+
+/// Returns the control sequences of `frame` which change a mode of the terminal, which the
+/// following frames don't repeat.
+fn mode_sequences(frame: &[u8]) -> Vec<u8> {
+    let mut modes = Vec::new();
+    let mut rest = frame;
+    while let Some(start) = rest.windows(2).position(|bytes| bytes == b"\x1B[") {
+        // CSI, the parameter and intermediate bytes and the final byte
+        let end = match rest[start + 2..]
+            .iter()
+            .position(|byte| (0x40..=0x7E).contains(byte))
+        {
+            Some(end) => start + 2 + end + 1,
+            None => break,
+        };
+        if is_mode_sequence(&rest[start..end]) {
+            modes.extend_from_slice(&rest[start..end]);
+        }
+        rest = &rest[end..];
+    }
+    modes
+}
+
+fn is_mode_sequence(sequence: &[u8]) -> bool {
+    let params = &sequence[2..sequence.len() - 1];
+    match sequence[sequence.len() - 1] {
+        // SM and RM, DECSET and DECRST, e.g. the alternate screen or the mouse capture
+        b'h' | b'l' => true,
+        // the keyboard enhancement flags
+        b'u' => matches!(params.first(), Some(b'>' | b'<' | b'=')),
+        // the cursor style (DECSCUSR)
+        b'q' => params.ends_with(b" "),
+        _ => false,
+    }
+}
+
+impl<W: Write> Write for RateLimitedWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.frame.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    /// Ends the frame, which is written if the link allows it, see
+    /// [`RateLimitedWriter`](struct.RateLimitedWriter.html).
+    fn flush(&mut self) -> io::Result<()> {
+        self.flush_at(Instant::now())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        io::Write,
+        time::{Duration, Instant},
+    };
+
+    use super::RateLimitedWriter;
+
+    #[test]
+    fn test_frames_are_dropped_on_slow_links() {
+        let start = Instant::now();
+        let at = |millis| start + Duration::from_millis(millis);
+
+        let mut output = RateLimitedWriter::new(Vec::new(), 1000).with_max_frame_size(50);
+        output.paid_at = start;
+
+        output.write_all(b"frame 1").unwrap();
+        output.flush_at(at(0)).unwrap();
+        // 7 bytes take 7 milliseconds
+        output.write_all(b"frame 2").unwrap();
+        output.flush_at(at(2)).unwrap();
+        output.write_all(b"frame 3").unwrap();
+        output.flush_at(at(4)).unwrap();
+        assert!(output.has_pending());
+        assert!(!output.write_pending_at(at(6)).unwrap());
+        assert!(output.write_pending_at(at(7)).unwrap());
+
+        output.write_all(&[b'x'; 51]).unwrap();
+        output.flush_at(at(100)).unwrap();
+        output.write_all(b"frame 4").unwrap();
+        output.flush_at(at(100)).unwrap();
+
+        assert_eq!(output.dropped_frames(), 2);
+        assert_eq!(output.get_ref(), b"frame 1frame 3frame 4");
+    }
+
+    #[test]
+    fn test_modes_of_dropped_frames_are_kept() {
+        let start = Instant::now();
+        let at = |millis| start + Duration::from_millis(millis);
+
+        let mut output = RateLimitedWriter::new(Vec::new(), 1000).with_max_frame_size(40);
+        output.paid_at = start;
+
+        output.write_all(b"frame 1").unwrap();
+        output.flush_at(at(0)).unwrap();
+        // the alternate screen, the hidden cursor, a bar cursor and the pushed keyboard flags
+        output
+            .write_all(b"\x1B[?1049h\x1B[1;1H\x1B[?25l2\x1B[6 q\x1B[>1u")
+            .unwrap();
+        output.flush_at(at(1)).unwrap();
+        output.write_all(b"frame 3").unwrap();
+        output.flush_at(at(2)).unwrap();
+        // too large, but shows the cursor
+        output.write_all(&[b'x'; 41]).unwrap();
+        output.write_all(b"\x1B[?25h").unwrap();
+        output.flush_at(at(3)).unwrap();
+        output.flush_pending().unwrap();
+
+        assert_eq!(output.dropped_frames(), 2);
+        assert_eq!(
+            output.get_ref(),
+            b"frame 1\x1B[?1049h\x1B[?25l\x1B[6 q\x1B[>1uframe 3\x1B[?25h"
+        );
+    }
+}
@@ -0,0 +1,131 @@
+use std::io::{self, Write};
+
+use super::write_all;
+
+/// The sequences of `BeginSynchronizedUpdate` and `EndSynchronizedUpdate`.
+const BEGIN: &[u8] = b"\x1B[?2026h";
+const END: &[u8] = b"\x1B[?2026l";
+
+/// A writer which wraps every flush in
+/// [`BeginSynchronizedUpdate`](terminal/struct.BeginSynchronizedUpdate.html) and
+/// [`EndSynchronizedUpdate`](terminal/struct.EndSynchronizedUpdate.html), so the terminal shows
+/// each frame at once instead of tearing while it's drawn.
+///
+/// Everything written between two flushes is kept and written in one go when flushed, inside
+/// a synchronized update if the terminal supports it. Whether it does is taken from the
+/// installed [`Capabilities`](capabilities/struct.Capabilities.html#structfield.synchronized_output)
+/// when the writer is created, and can be detected with
+/// [`terminal::supports_synchronized_output`](terminal/fn.supports_synchronized_output.html).
+///
+/// # Examples
+///
+/// ```no_run
+/// use std::io::{self, Write};
+/// use crossterm::{cursor, queue, style::Print, SynchronizedWriter};
+///
+/// fn main() -> io::Result<()> {
+///     let mut output = SynchronizedWriter::new(io::stdout());
+///
+///     for frame in 0..600 {
+///         queue!(output, cursor::MoveTo(0, 0), Print(format!("frame {}", frame)))?;
+///         // written as one synchronized update
+///         output.flush()?;
+///     }
+///     Ok(())
+/// }
+/// ```
+#[derive(Debug)]
+pub struct SynchronizedWriter<W: Write> {
+    inner: W,
+    frame: Vec<u8>,
+    enabled: bool,
+}
+
+impl<W: Write> SynchronizedWriter<W> {
+    /// Creates a writer which synchronizes the updates of `inner` if the installed capabilities
+    /// say the terminal supports it.
+    pub fn new(inner: W) -> SynchronizedWriter<W> {
+        let enabled = crate::capabilities::query(|c| c.synchronized_output);
+        SynchronizedWriter::with_enabled(inner, enabled)
+    }
+
+    /// Creates a writer which synchronizes the updates of `inner` if `enabled` is set,
+    /// regardless of the capabilities.
+    pub fn with_enabled(inner: W, enabled: bool) -> SynchronizedWriter<W> {
+        SynchronizedWriter {
+            inner,
+            frame: Vec::new(),
+            enabled,
+        }
+    }
+
+    /// Returns whether the flushes are wrapped in synchronized updates.
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// Sets whether the flushes are wrapped in synchronized updates.
+    pub fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+    }
+
+    /// Returns a reference to the inner writer.
+    pub fn get_ref(&self) -> &W {
+        &self.inner
+    }
+
+    /// Returns a mutable reference to the inner writer.
+    pub fn get_mut(&mut self) -> &mut W {
+        &mut self.inner
+    }
+
+    /// Returns the inner writer, dropping what wasn't flushed.
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+}
+
+impl<W: Write> Write for SynchronizedWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.frame.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    /// Writes the frame, inside a synchronized update if enabled, and flushes the inner writer.
+    fn flush(&mut self) -> io::Result<()> {
+        if !self.frame.is_empty() {
+            if self.enabled {
+                self.frame.splice(0..0, BEGIN.iter().copied());
+                self.frame.extend_from_slice(END);
+            }
+            let result = write_all(&mut self.inner, &self.frame);
+            self.frame.clear();
+            result?;
+        }
+        self.inner.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use super::SynchronizedWriter;
+
+    #[test]
+    fn test_flushes_are_synchronized() {
+        let mut output = SynchronizedWriter::with_enabled(Vec::new(), true);
+        output.write_all(b"foo").unwrap();
+        output.write_all(b"bar").unwrap();
+        assert!(output.get_ref().is_empty());
+        output.flush().unwrap();
+        // nothing to synchronize
+        output.flush().unwrap();
+        assert_eq!(output.get_ref(), b"\x1B[?2026hfoobar\x1B[?2026l");
+
+        output.set_enabled(false);
+        output.write_all(b"baz").unwrap();
+        output.flush().unwrap();
+        assert_eq!(output.into_inner(), b"\x1B[?2026hfoobar\x1B[?2026lbaz");
+    }
+}
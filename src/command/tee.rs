@@ -0,0 +1,169 @@
+use std::{
+    cell::Cell,
+    io::Write,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+};
+
+use parking_lot::Mutex;
+
+/// The writer set with [`set_tee`], taken out once it's replaced.
+type Tee = Arc<Mutex<Option<Box<dyn Write + Send>>>>;
+
+// Only held to clone the writer out of it, which is written to without holding this lock.
+static TEE: Mutex<Option<Tee>> = parking_lot::const_mutex(None);
+// Whether `TEE` is set, to skip locking it for every command while there is none.
+static TEE_SET: AtomicBool = AtomicBool::new(false);
+
+thread_local! {
+    /// The writer is written to, so the commands it writes itself aren't mirrored to it again.
+    static IN_TEE: Cell<bool> = Cell::new(false);
+}
+
+/// Mirrors every command written from now on to `writer`, e.g. a log file of the session.
+///
+/// The ANSI codes of the commands queued to any writer, including
+/// [`RemoteTerminal`](terminal/struct.RemoteTerminal.html)s, are written to `writer` as well.
+/// Bytes written to the writers directly aren't mirrored, neither are commands which are executed
+/// with WinAPI calls on old Windows versions. Errors of `writer` are ignored, so that a failing
+/// log doesn't break the application.
+///
+/// Replaces the writer set before, which is flushed and returned.
+///
+/// # Examples
+///
+/// ```no_run
+/// use std::{fs::File, io};
+/// use crossterm::{execute, style::Print};
+///
+/// fn main() -> io::Result<()> {
+///     crossterm::set_tee(File::create("session.log")?);
+///
+///     execute!(io::stdout(), Print("Hello"))?;
+///
+///     crossterm::remove_tee();
+///     Ok(())
+/// }
+/// ```
+pub fn set_tee(writer: impl Write + Send + 'static) -> Option<Box<dyn Write + Send>> {
+    replace(Some(Box::new(writer)))
+}
+
+/// Stops mirroring commands, and returns the writer set with [`set_tee`](fn.set_tee.html)
+/// after flushing it.
+pub fn remove_tee() -> Option<Box<dyn Write + Send>> {
+    replace(None)
+}
+
+fn replace(writer: Option<Box<dyn Write + Send>>) -> Option<Box<dyn Write + Send>> {
+    let previous = {
+        let mut tee = TEE.lock();
+        TEE_SET.store(writer.is_some(), Ordering::SeqCst);
+        std::mem::replace(
+            &mut *tee,
+            writer.map(|writer| Arc::new(Mutex::new(Some(writer)))),
+        )
+    };
+    // waits for the writes which are still in progress
+    let mut previous = previous?.lock().take()?;
+    let _ = previous.flush();
+    Some(previous)
+}
+
+/// Writes `bytes` to the writer set with [`set_tee`], if any.
+pub(crate) fn tee(bytes: &[u8]) {
+    if !TEE_SET.load(Ordering::Relaxed) || IN_TEE.with(Cell::get) {
+        return;
+    }
+    let tee = match TEE.lock().clone() {
+        Some(tee) => tee,
+        None => return,
+    };
+
+    IN_TEE.with(|in_tee| in_tee.set(true));
+    if let Some(tee) = tee.lock().as_mut() {
+        let _ = tee.write_all(bytes);
+    }
+    IN_TEE.with(|in_tee| in_tee.set(false));
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        io::{self, Write},
+        sync::Arc,
+    };
+
+    use parking_lot::Mutex;
+
+    use crate::{queue, style::Print};
+
+    use super::{remove_tee, set_tee};
+
+    #[derive(Clone, Default)]
+    struct Shared(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for Shared {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    #[serial_test::serial]
+    fn test_tee_mirrors_commands() {
+        let log = Shared::default();
+        set_tee(log.clone());
+
+        let mut output = Vec::new();
+        queue!(output, Print("tee-test-1"), Print("tee-test-2")).unwrap();
+        output.write_all(b"tee-test-direct").unwrap();
+        assert!(remove_tee().is_some());
+        queue!(output, Print("tee-test-3")).unwrap();
+
+        // other tests might write commands at the same time
+        let log = String::from_utf8(log.0.lock().clone()).unwrap();
+        assert!(log.contains("tee-test-1"));
+        assert!(log.contains("tee-test-2"));
+        assert!(!log.contains("tee-test-direct"));
+        assert!(!log.contains("tee-test-3"));
+    }
+
+    /// Writes a command itself, like a log which is a terminal.
+    struct Reentrant(Shared);
+
+    impl Write for Reentrant {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            queue!(self.0, Print("tee-test-nested"))?;
+            self.0.write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    #[serial_test::serial]
+    fn test_tee_writing_commands_itself() {
+        let log = Shared::default();
+        set_tee(Reentrant(log.clone()));
+
+        queue!(Vec::new(), Print("tee-test-4")).unwrap();
+        set_tee(log.clone());
+        queue!(Vec::new(), Print("tee-test-5")).unwrap();
+        assert!(remove_tee().is_some());
+
+        let log = String::from_utf8(log.0.lock().clone()).unwrap();
+        assert!(log.contains("tee-test-nestedtee-test-4"));
+        assert!(log.contains("tee-test-5"));
+        // other tests might write commands at the same time, but never nested twice
+        assert!(!log.contains("tee-test-nestedtee-test-nested"));
+    }
+}
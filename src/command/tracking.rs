@@ -0,0 +1,160 @@
+use std::{
+    cell::Cell,
+    io::{BufWriter, LineWriter, Stderr, StderrLock, Stdout, StdoutLock, Write},
+};
+
+use super::{
+    BufferingWriter, NonBlockingWriter, QueuedWriter, RateLimitedWriter, SynchronizedWriter,
+};
+use crate::style::{ColorScope, ColoredWriter, SgrBatchWriter};
+
+thread_local! {
+    /// The command which is written is executed on the terminal of the process, see
+    /// [`is_tracking`].
+    static TRACKING: Cell<bool> = Cell::new(false);
+}
+
+/// Returns whether the command which is written is executed on the terminal of the process, so
+/// it updates the state crossterm tracks about it, e.g. whether the alternate screen is active.
+///
+/// Commands which are only rendered, with `ansi_string`, `Display` or into a `CommandBuffer`, or
+/// which are written for another terminal leave the tracked state alone, but still read it.
+pub(crate) fn is_tracking() -> bool {
+    TRACKING.with(Cell::get)
+}
+
+/// Runs `update` if the command which is written is executed on the terminal of the process.
+pub(crate) fn tracked(update: impl FnOnce()) {
+    if is_tracking() {
+        update();
+    }
+}
+
+/// Runs `f` with the tracking set to `tracking`, the previous setting is restored afterwards,
+/// also when `f` panics.
+pub(crate) fn with_tracking<T>(tracking: bool, f: impl FnOnce() -> T) -> T {
+    struct Restore(bool);
+
+    impl Drop for Restore {
+        fn drop(&mut self) {
+            TRACKING.with(|cell| cell.set(self.0));
+        }
+    }
+
+    let _restore = Restore(TRACKING.with(|cell| cell.replace(tracking)));
+    f()
+}
+
+/// A writer which writes to the terminal of the process: stdout or stderr, also locked, borrowed,
+/// boxed, or wrapped in a `BufWriter`, a `LineWriter` or one of the writers of crossterm, e.g. a
+/// [`SynchronizedWriter`](struct.SynchronizedWriter.html).
+///
+/// Commands queued with [`queue!`](macro.queue.html) or [`execute!`](macro.execute.html) on such
+/// a writer update the state crossterm tracks about the terminal, e.g.
+/// [`is_alternate_screen_active`](terminal/fn.is_alternate_screen_active.html). Commands queued on
+/// any other writer, e.g. a `Vec<u8>`, a [`CommandBuffer`](struct.CommandBuffer.html) or a
+/// `Box<dyn Write>`, and commands queued with the
+/// [`QueueableCommand`](trait.QueueableCommand.html) and
+/// [`ExecutableCommand`](trait.ExecutableCommand.html) methods, which can't tell the writers
+/// apart, are written without changing it.
+///
+/// Generic code can require this trait, or use `dyn ProcessTerminal` instead of `dyn Write`, to
+/// keep the tracking. It is sealed, other writers can't implement it.
+pub trait ProcessTerminal: Write + sealed::Sealed {}
+
+pub(crate) mod sealed {
+    pub trait Sealed {}
+}
+
+macro_rules! impl_process_terminal {
+    ($(impl [$($generics:tt)*] $ty:ty;)*) => {$(
+        impl<$($generics)*> sealed::Sealed for $ty {}
+        impl<$($generics)*> ProcessTerminal for $ty {}
+    )*};
+}
+
+impl_process_terminal! {
+    impl [] Stdout;
+    impl [] Stderr;
+    impl ['a] StdoutLock<'a>;
+    impl ['a] StderrLock<'a>;
+    impl [W: ProcessTerminal + ?Sized] &mut W;
+    impl [W: ProcessTerminal + ?Sized] Box<W>;
+    impl [W: ProcessTerminal] BufWriter<W>;
+    impl [W: ProcessTerminal] LineWriter<W>;
+    impl ['a, W: ProcessTerminal + ?Sized] QueuedWriter<'a, W>;
+    impl [W: ProcessTerminal] BufferingWriter<W>;
+    impl [W: ProcessTerminal + Send + 'static] NonBlockingWriter<W>;
+    impl [W: ProcessTerminal] RateLimitedWriter<W>;
+    impl [W: ProcessTerminal] SynchronizedWriter<W>;
+    impl [W: ProcessTerminal] SgrBatchWriter<W>;
+    impl [W: ProcessTerminal] ColoredWriter<W>;
+    impl ['a, W: ProcessTerminal] ColorScope<'a, W>;
+}
+
+/// Wraps the writer given to the macros to find out whether it's a [`ProcessTerminal`].
+///
+/// Method resolution picks [`ProbeProcessTerminal`] if the writer implements it, and falls back
+/// to [`ProbeOtherWriter`] for every other writer.
+#[doc(hidden)]
+pub struct Probe<'a, W: ?Sized>(pub &'a W);
+
+#[doc(hidden)]
+pub trait ProbeProcessTerminal {
+    fn is_process_terminal(&self) -> bool {
+        true
+    }
+}
+
+impl<W: ProcessTerminal + ?Sized> ProbeProcessTerminal for Probe<'_, W> {}
+
+#[doc(hidden)]
+pub trait ProbeOtherWriter {
+    fn is_process_terminal(&self) -> bool {
+        false
+    }
+}
+
+impl<W: ?Sized> ProbeOtherWriter for &Probe<'_, W> {}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{self, BufWriter, Write};
+
+    use super::{
+        is_tracking, with_tracking, Probe, ProbeOtherWriter, ProbeProcessTerminal, ProcessTerminal,
+    };
+    use crate::SynchronizedWriter;
+
+    #[test]
+    fn test_process_terminals_are_recognized() {
+        macro_rules! probe {
+            ($writer:expr) => {
+                (&Probe(&$writer)).is_process_terminal()
+            };
+        }
+
+        let stdout = io::stdout();
+        assert!(probe!(io::stdout()));
+        assert!(probe!(io::stderr().lock()));
+        assert!(probe!(&mut stdout.lock()));
+        assert!(probe!(BufWriter::new(io::stdout())));
+        assert!(probe!(SynchronizedWriter::new(io::stdout())));
+        assert!(probe!(Box::new(io::stdout()) as Box<dyn ProcessTerminal>));
+        assert!(!probe!(Vec::<u8>::new()));
+        assert!(!probe!(io::sink()));
+        assert!(!probe!(Box::new(io::stdout()) as Box<dyn Write>));
+        assert!(!probe!(SynchronizedWriter::new(Vec::<u8>::new())));
+    }
+
+    #[test]
+    fn test_with_tracking() {
+        assert!(!is_tracking());
+        with_tracking(true, || {
+            assert!(is_tracking());
+            with_tracking(false, || assert!(!is_tracking()));
+            assert!(is_tracking());
+        });
+        assert!(!is_tracking());
+    }
+}
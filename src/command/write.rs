@@ -0,0 +1,267 @@
+use std::{
+    cell::Cell,
+    io::{self, Write},
+    thread,
+    time::Duration,
+};
+
+use parking_lot::RwLock;
+
+/// The size of the chunks [`write_large`] and [`PrintLarge`](style/struct.PrintLarge.html) write.
+pub(crate) const CHUNK_SIZE: usize = 16 * 1024;
+
+// How long to wait first for a writer which can't take more bytes yet.
+const MIN_BACKOFF: Duration = Duration::from_millis(1);
+
+/// What commands do when the writer they're written to is non-blocking and can't take more
+/// bytes yet (`EAGAIN`/`WouldBlock`), see [`set_retry_policy`](fn.set_retry_policy.html).
+///
+/// Until a policy is set, only [`PrintLarge`](style/struct.PrintLarge.html) and [`write_large`]
+/// wait for the writer with the default policy, other commands return the error like
+/// `Write::write_all`. Interrupted writes (`EINTR`) are always tried again. Write to a
+/// [`BufferingWriter`](struct.BufferingWriter.html) to keep the bytes a busy writer can't take
+/// instead of waiting for it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RetryPolicy {
+    /// Try again until the bytes are written, waiting between the tries: first a millisecond,
+    /// then twice as long as before, but at most `max_backoff`. The default, with a
+    /// `max_backoff` of 16 milliseconds.
+    Retry {
+        /// The longest time to wait between two tries.
+        max_backoff: Duration,
+    },
+    /// Return the error, as `Write::write_all` does for `WouldBlock`. Part of the command may
+    /// have been written already.
+    Error,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy::Retry {
+            max_backoff: Duration::from_millis(16),
+        }
+    }
+}
+
+// `None` until a policy is set.
+static RETRY_POLICY: RwLock<Option<RetryPolicy>> = parking_lot::const_rwlock(None);
+
+thread_local! {
+    /// A large payload is written, see [`writing_large`].
+    static WRITING_LARGE: Cell<bool> = Cell::new(false);
+}
+
+/// Sets what all commands do from now on when the writer they're written to is busy.
+///
+/// Full-screen applications on slow PTYs, e.g. with the tty set to non-blocking, can otherwise
+/// fail in the middle of a frame.
+///
+/// # Examples
+///
+/// ```no_run
+/// use std::time::Duration;
+/// use crossterm::RetryPolicy;
+///
+/// crossterm::set_retry_policy(RetryPolicy::Retry {
+///     max_backoff: Duration::from_millis(50),
+/// });
+/// ```
+pub fn set_retry_policy(policy: RetryPolicy) {
+    *RETRY_POLICY.write() = Some(policy);
+}
+
+/// Returns the policy which was set, or the default one
+/// [`PrintLarge`](style/struct.PrintLarge.html) and [`write_large`] follow until one is set.
+pub fn retry_policy() -> RetryPolicy {
+    RETRY_POLICY.read().unwrap_or_default()
+}
+
+/// Runs `write` with the commands it writes treated as a large payload, which follows the
+/// default [`RetryPolicy`] until one is set. The previous setting is restored afterwards, also
+/// when `write` panics.
+pub(crate) fn writing_large<T>(write: impl FnOnce() -> T) -> T {
+    struct Restore(bool);
+
+    impl Drop for Restore {
+        fn drop(&mut self) {
+            WRITING_LARGE.with(|large| large.set(self.0));
+        }
+    }
+
+    let _restore = Restore(WRITING_LARGE.with(|large| large.replace(true)));
+    write()
+}
+
+/// Writes all of `bytes` to `writer`, handling interrupted and busy writers according to the
+/// [`RetryPolicy`] which was set. Busy writers fail like with `Write::write_all` until one is
+/// set, except while a large payload is written.
+pub(crate) fn write_all(writer: &mut (impl Write + ?Sized), bytes: &[u8]) -> io::Result<()> {
+    let policy = RETRY_POLICY.read().unwrap_or_else(|| {
+        if WRITING_LARGE.with(Cell::get) {
+            RetryPolicy::default()
+        } else {
+            RetryPolicy::Error
+        }
+    });
+    write_all_with(writer, bytes, policy)
+}
+
+pub(crate) fn write_all_with(
+    writer: &mut (impl Write + ?Sized),
+    bytes: &[u8],
+    policy: RetryPolicy,
+) -> io::Result<()> {
+    let mut written = 0;
+    let mut backoff = MIN_BACKOFF;
+    while written < bytes.len() {
+        match writer.write(&bytes[written..]) {
+            Ok(0) => {
+                return Err(io::Error::new(
+                    io::ErrorKind::WriteZero,
+                    "failed to write whole buffer",
+                ))
+            }
+            Ok(n) => {
+                written += n;
+                backoff = MIN_BACKOFF;
+            }
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+            Err(e) if e.kind() == io::ErrorKind::WouldBlock => match policy {
+                RetryPolicy::Retry { max_backoff } => {
+                    thread::sleep(backoff.min(max_backoff));
+                    backoff = (backoff * 2).min(max_backoff);
+                }
+                RetryPolicy::Error => return Err(e),
+            },
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(())
+}
+
+/// Writes a large payload, e.g. image data or a long log, to `writer` in chunks and flushes it.
+///
+/// Partial writes are continued, and when `writer` is non-blocking, e.g. a tty opened with
+/// `O_NONBLOCK`, and can't take more bytes yet, this waits for it instead of failing with
+/// `WouldBlock` after part of the payload was written, unless configured otherwise with
+/// [`set_retry_policy`](fn.set_retry_policy.html). Use
+/// [`PrintLarge`](style/struct.PrintLarge.html) to queue a large payload together with other
+/// commands.
+///
+/// # Examples
+///
+/// ```no_run
+/// use std::{fs, io};
+///
+/// fn main() -> io::Result<()> {
+///     let log = fs::read("build.log")?;
+///     crossterm::write_large(&mut io::stdout(), &log)
+/// }
+/// ```
+pub fn write_large(writer: &mut (impl Write + ?Sized), bytes: &[u8]) -> io::Result<()> {
+    for chunk in bytes.chunks(CHUNK_SIZE) {
+        write_all_with(writer, chunk, retry_policy())?;
+    }
+    let mut backoff = MIN_BACKOFF;
+    loop {
+        match (writer.flush(), retry_policy()) {
+            (Err(e), _) if e.kind() == io::ErrorKind::Interrupted => {}
+            (Err(e), RetryPolicy::Retry { max_backoff })
+                if e.kind() == io::ErrorKind::WouldBlock =>
+            {
+                thread::sleep(backoff.min(max_backoff));
+                backoff = (backoff * 2).min(max_backoff);
+            }
+            (result, _) => return result,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{self, Write};
+
+    use crate::{
+        queue,
+        style::{Print, PrintLarge},
+    };
+
+    use super::{write_all_with, write_large, RetryPolicy, CHUNK_SIZE};
+
+    // A writer which takes a few bytes at a time and is busy every other call.
+    #[derive(Default)]
+    struct Slow {
+        written: Vec<u8>,
+        calls: usize,
+        largest: usize,
+    }
+
+    impl Write for Slow {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.calls += 1;
+            self.largest = self.largest.max(buf.len());
+            match self.calls % 3 {
+                0 => Err(io::ErrorKind::WouldBlock.into()),
+                1 => Err(io::ErrorKind::Interrupted.into()),
+                _ => {
+                    let written = buf.len().min(4096);
+                    self.written.extend_from_slice(&buf[..written]);
+                    Ok(written)
+                }
+            }
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_write_large_continues_partial_writes() {
+        let payload: Vec<u8> = (0..CHUNK_SIZE * 2 + 100).map(|i| i as u8).collect();
+
+        let mut slow = Slow::default();
+        write_large(&mut slow, &payload).unwrap();
+        assert_eq!(slow.written, payload);
+        assert_eq!(slow.largest, CHUNK_SIZE);
+    }
+
+    #[test]
+    fn test_print_large_writes_chunks() {
+        let text = "ä".repeat(CHUNK_SIZE);
+
+        let mut slow = Slow::default();
+        queue!(slow, PrintLarge(&text)).unwrap();
+        assert_eq!(slow.written, text.as_bytes());
+        assert!(slow.largest <= CHUNK_SIZE);
+    }
+
+    #[test]
+    fn test_commands_return_would_block_by_default() {
+        let mut slow = Slow {
+            calls: 2,
+            ..Slow::default()
+        };
+        let e = queue!(slow, Print("abc")).unwrap_err();
+        assert_eq!(e.kind(), io::ErrorKind::WouldBlock);
+        // the large payload waits for the writer
+        queue!(slow, PrintLarge("abc")).unwrap();
+        assert_eq!(slow.written, b"abc");
+    }
+
+    #[test]
+    fn test_retry_policies() {
+        let mut slow = Slow {
+            calls: 2,
+            ..Slow::default()
+        };
+        let e = write_all_with(&mut slow, b"abc", RetryPolicy::Error).unwrap_err();
+        assert_eq!(e.kind(), io::ErrorKind::WouldBlock);
+        // interrupted, which is always tried again
+        write_all_with(&mut slow, b"abc", RetryPolicy::Error).unwrap();
+        assert_eq!(slow.written, b"abc");
+
+        write_all_with(&mut slow, b"def", RetryPolicy::default()).unwrap();
+        assert_eq!(slow.written, b"abcdef");
+    }
+}
@@ -42,9 +42,14 @@
 //!
 //! For manual execution control check out [crossterm::queue](../macro.queue.html).
 
-use std::fmt;
+use std::{
+    fmt, io,
+    sync::atomic::{AtomicBool, AtomicU8, AtomicUsize, Ordering},
+};
 
-use crate::{csi, impl_display, Command};
+use parking_lot::Mutex;
+
+use crate::{csi, execute, impl_display, Command};
 
 pub(crate) mod sys;
 
@@ -61,9 +66,14 @@ pub struct MoveTo(pub u16, pub u16);
 
 impl Command for MoveTo {
     fn write_ansi(&self, f: &mut impl fmt::Write) -> fmt::Result {
+        crate::write_terminfo!(f, "cup", self.1, self.0);
         write!(f, csi!("{};{}H"), self.1 + 1, self.0 + 1)
     }
 
+    fn size_hint(&self) -> usize {
+        14
+    }
+
     #[cfg(windows)]
     fn execute_winapi(&self) -> std::io::Result<()> {
         sys::move_to(self.0, self.1)
@@ -86,6 +96,10 @@ impl Command for MoveToNextLine {
         Ok(())
     }
 
+    fn size_hint(&self) -> usize {
+        8
+    }
+
     #[cfg(windows)]
     fn execute_winapi(&self) -> std::io::Result<()> {
         if self.0 != 0 {
@@ -111,6 +125,10 @@ impl Command for MoveToPreviousLine {
         Ok(())
     }
 
+    fn size_hint(&self) -> usize {
+        8
+    }
+
     #[cfg(windows)]
     fn execute_winapi(&self) -> std::io::Result<()> {
         if self.0 != 0 {
@@ -130,10 +148,15 @@ pub struct MoveToColumn(pub u16);
 
 impl Command for MoveToColumn {
     fn write_ansi(&self, f: &mut impl fmt::Write) -> fmt::Result {
+        crate::write_terminfo!(f, "hpa", self.0);
         write!(f, csi!("{}G"), self.0 + 1)?;
         Ok(())
     }
 
+    fn size_hint(&self) -> usize {
+        8
+    }
+
     #[cfg(windows)]
     fn execute_winapi(&self) -> std::io::Result<()> {
         sys::move_to_column(self.0)
@@ -150,10 +173,15 @@ pub struct MoveToRow(pub u16);
 
 impl Command for MoveToRow {
     fn write_ansi(&self, f: &mut impl fmt::Write) -> fmt::Result {
+        crate::write_terminfo!(f, "vpa", self.0);
         write!(f, csi!("{}d"), self.0 + 1)?;
         Ok(())
     }
 
+    fn size_hint(&self) -> usize {
+        8
+    }
+
     #[cfg(windows)]
     fn execute_winapi(&self) -> std::io::Result<()> {
         sys::move_to_row(self.0)
@@ -171,10 +199,15 @@ pub struct MoveUp(pub u16);
 
 impl Command for MoveUp {
     fn write_ansi(&self, f: &mut impl fmt::Write) -> fmt::Result {
+        crate::write_terminfo!(f, "cuu", self.0);
         write!(f, csi!("{}A"), self.0)?;
         Ok(())
     }
 
+    fn size_hint(&self) -> usize {
+        8
+    }
+
     #[cfg(windows)]
     fn execute_winapi(&self) -> std::io::Result<()> {
         sys::move_up(self.0)
@@ -192,10 +225,15 @@ pub struct MoveRight(pub u16);
 
 impl Command for MoveRight {
     fn write_ansi(&self, f: &mut impl fmt::Write) -> fmt::Result {
+        crate::write_terminfo!(f, "cuf", self.0);
         write!(f, csi!("{}C"), self.0)?;
         Ok(())
     }
 
+    fn size_hint(&self) -> usize {
+        8
+    }
+
     #[cfg(windows)]
     fn execute_winapi(&self) -> std::io::Result<()> {
         sys::move_right(self.0)
@@ -213,10 +251,15 @@ pub struct MoveDown(pub u16);
 
 impl Command for MoveDown {
     fn write_ansi(&self, f: &mut impl fmt::Write) -> fmt::Result {
+        crate::write_terminfo!(f, "cud", self.0);
         write!(f, csi!("{}B"), self.0)?;
         Ok(())
     }
 
+    fn size_hint(&self) -> usize {
+        8
+    }
+
     #[cfg(windows)]
     fn execute_winapi(&self) -> std::io::Result<()> {
         sys::move_down(self.0)
@@ -234,10 +277,15 @@ pub struct MoveLeft(pub u16);
 
 impl Command for MoveLeft {
     fn write_ansi(&self, f: &mut impl fmt::Write) -> fmt::Result {
+        crate::write_terminfo!(f, "cub", self.0);
         write!(f, csi!("{}D"), self.0)?;
         Ok(())
     }
 
+    fn size_hint(&self) -> usize {
+        8
+    }
+
     #[cfg(windows)]
     fn execute_winapi(&self) -> std::io::Result<()> {
         sys::move_left(self.0)
@@ -257,6 +305,7 @@ pub struct SavePosition;
 
 impl Command for SavePosition {
     fn write_ansi(&self, f: &mut impl fmt::Write) -> fmt::Result {
+        crate::write_terminfo!(f, "sc");
         f.write_str("\x1B7")
     }
 
@@ -279,6 +328,7 @@ pub struct RestorePosition;
 
 impl Command for RestorePosition {
     fn write_ansi(&self, f: &mut impl fmt::Write) -> fmt::Result {
+        crate::write_terminfo!(f, "rc");
         f.write_str("\x1B8")
     }
 
@@ -298,12 +348,20 @@ pub struct Hide;
 
 impl Command for Hide {
     fn write_ansi(&self, f: &mut impl fmt::Write) -> fmt::Result {
+        crate::command::tracked(|| CURSOR_HIDDEN.store(true, Ordering::Relaxed));
+        crate::write_terminfo!(f, "civis");
         f.write_str(csi!("?25l"))
     }
 
+    fn size_hint(&self) -> usize {
+        6
+    }
+
     #[cfg(windows)]
     fn execute_winapi(&self) -> std::io::Result<()> {
-        sys::show_cursor(false)
+        sys::show_cursor(false)?;
+        CURSOR_HIDDEN.store(true, Ordering::Relaxed);
+        Ok(())
     }
 }
 
@@ -317,17 +375,41 @@ pub struct Show;
 
 impl Command for Show {
     fn write_ansi(&self, f: &mut impl fmt::Write) -> fmt::Result {
+        crate::command::tracked(|| CURSOR_HIDDEN.store(false, Ordering::Relaxed));
+        crate::write_terminfo!(f, "cnorm");
         f.write_str(csi!("?25h"))
     }
 
+    fn size_hint(&self) -> usize {
+        6
+    }
+
     #[cfg(windows)]
     fn execute_winapi(&self) -> std::io::Result<()> {
-        sys::show_cursor(true)
+        sys::show_cursor(true)?;
+        CURSOR_HIDDEN.store(false, Ordering::Relaxed);
+        Ok(())
     }
 }
 
+/// Whether the cursor is hidden with [`Hide`].
+static CURSOR_HIDDEN: AtomicBool = AtomicBool::new(false);
+
+/// Tells whether the cursor is hidden.
+///
+/// This is tracked from the [`Hide`] and [`Show`] commands queued on a
+/// [`ProcessTerminal`](crate::ProcessTerminal), the terminal isn't asked.
+pub fn is_cursor_hidden() -> bool {
+    CURSOR_HIDDEN.load(Ordering::Relaxed)
+}
+
 /// A command that enables blinking of the terminal cursor.
 ///
+/// The blinking is independent of the shape: the shape set with [`SetCursorStyle`] before this
+/// command blinks, whichever style is set. A later `SetCursorStyle` sets the blinking of its
+/// style. See [`cursor_blinking`] and [`query_cursor_blinking`] to restore the user's setting
+/// later.
+///
 /// # Notes
 ///
 /// - Some Unix terminals (ex: GNOME and Konsole) as well as Windows versions lower than Windows 10 do not support this functionality.
@@ -337,7 +419,12 @@ impl Command for Show {
 pub struct EnableBlinking;
 impl Command for EnableBlinking {
     fn write_ansi(&self, f: &mut impl fmt::Write) -> fmt::Result {
-        f.write_str(csi!("?12h"))
+        if crate::accessibility::screen_reader_mode() {
+            return Ok(());
+        }
+        crate::command::tracked(|| BLINKING.store(encode_blinking(Some(true)), Ordering::Relaxed));
+        f.write_str(csi!("?12h"))?;
+        write_current_style(f, Some(true))
     }
     #[cfg(windows)]
     fn execute_winapi(&self) -> std::io::Result<()> {
@@ -345,7 +432,7 @@ impl Command for EnableBlinking {
     }
 }
 
-/// A command that disables blinking of the terminal cursor.
+/// A command that disables blinking of the terminal cursor, see [`EnableBlinking`].
 ///
 /// # Notes
 ///
@@ -356,7 +443,9 @@ impl Command for EnableBlinking {
 pub struct DisableBlinking;
 impl Command for DisableBlinking {
     fn write_ansi(&self, f: &mut impl fmt::Write) -> fmt::Result {
-        f.write_str(csi!("?12l"))
+        crate::command::tracked(|| BLINKING.store(encode_blinking(Some(false)), Ordering::Relaxed));
+        f.write_str(csi!("?12l"))?;
+        write_current_style(f, Some(false))
     }
     #[cfg(windows)]
     fn execute_winapi(&self) -> std::io::Result<()> {
@@ -370,7 +459,8 @@ impl Command for DisableBlinking {
 /// # Note
 ///
 /// - Commands must be executed/queued for execution otherwise they do nothing.
-#[derive(Clone, Copy)]
+/// - [`CursorStyleGuard`] restores the previous style when it's dropped.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum SetCursorStyle {
     /// Default cursor shape configured by the user.
     DefaultUserShape,
@@ -390,6 +480,22 @@ pub enum SetCursorStyle {
 
 impl Command for SetCursorStyle {
     fn write_ansi(&self, f: &mut impl fmt::Write) -> fmt::Result {
+        // the user's default shape is what the terminal starts with
+        let style = Some(*self).filter(|&style| style != SetCursorStyle::DefaultUserShape);
+        crate::command::tracked(|| *CURSOR_STYLE.lock() = style);
+        // an explicit style brings its own blinking
+        self.with_blinking(None).write_sequence(f)
+    }
+
+    #[cfg(windows)]
+    fn execute_winapi(&self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+impl SetCursorStyle {
+    /// Writes the style as it is.
+    fn write_sequence(self, f: &mut impl fmt::Write) -> fmt::Result {
         match self {
             SetCursorStyle::DefaultUserShape => f.write_str("\x1b[0 q"),
             SetCursorStyle::BlinkingBlock => f.write_str("\x1b[1 q"),
@@ -401,12 +507,209 @@ impl Command for SetCursorStyle {
         }
     }
 
+    /// Returns the style with the blinking set with [`EnableBlinking`] or [`DisableBlinking`],
+    /// if any, which is never enabled in the screen reader mode.
+    fn with_blinking(self, blinking: Option<bool>) -> SetCursorStyle {
+        if crate::accessibility::screen_reader_mode() {
+            return self.steady();
+        }
+        match blinking {
+            Some(true) => self.blinking(),
+            Some(false) => self.steady(),
+            None => self,
+        }
+    }
+
+    /// Returns the blinking counterpart of a non blinking style.
+    fn blinking(self) -> SetCursorStyle {
+        match self {
+            SetCursorStyle::SteadyBlock => SetCursorStyle::BlinkingBlock,
+            SetCursorStyle::SteadyUnderScore => SetCursorStyle::BlinkingUnderScore,
+            SetCursorStyle::SteadyBar => SetCursorStyle::BlinkingBar,
+            style => style,
+        }
+    }
+
+    /// Returns the non blinking counterpart of a blinking style.
+    fn steady(self) -> SetCursorStyle {
+        match self {
+            SetCursorStyle::BlinkingBlock => SetCursorStyle::SteadyBlock,
+            SetCursorStyle::BlinkingUnderScore => SetCursorStyle::SteadyUnderScore,
+            SetCursorStyle::BlinkingBar => SetCursorStyle::SteadyBar,
+            style => style,
+        }
+    }
+}
+
+/// The style set with the last [`SetCursorStyle`], if any other than the user's default shape.
+static CURSOR_STYLE: Mutex<Option<SetCursorStyle>> = parking_lot::const_mutex(None);
+
+pub(crate) fn cursor_style() -> Option<SetCursorStyle> {
+    *CURSOR_STYLE.lock()
+}
+
+/// Writes the style set with the last [`SetCursorStyle`] again, for a changed `blinking`. The
+/// user's default shape has its own blinking, which isn't touched.
+fn write_current_style(f: &mut impl fmt::Write, blinking: Option<bool>) -> fmt::Result {
+    match cursor_style() {
+        Some(SetCursorStyle::DefaultUserShape) | None => Ok(()),
+        Some(style) => style.with_blinking(blinking).write_sequence(f),
+    }
+}
+
+/// The blinking set with [`EnableBlinking`] and [`DisableBlinking`], see [`encode_blinking`].
+static BLINKING: AtomicU8 = AtomicU8::new(0);
+/// The blinking of the terminal before crossterm changed it, see [`query_cursor_blinking`].
+static ORIGINAL_BLINKING: AtomicU8 = AtomicU8::new(0);
+
+/// Encodes an optional blinking in an atomic, which signal handlers can read.
+fn encode_blinking(blinking: Option<bool>) -> u8 {
+    match blinking {
+        None => 0,
+        Some(false) => 1,
+        Some(true) => 2,
+    }
+}
+
+fn decode_blinking(blinking: u8) -> Option<bool> {
+    match blinking {
+        1 => Some(false),
+        2 => Some(true),
+        _ => None,
+    }
+}
+
+/// Returns whether the cursor blinks as set with [`EnableBlinking`] or [`DisableBlinking`],
+/// `None` if crossterm didn't change the terminal's setting.
+pub fn cursor_blinking() -> Option<bool> {
+    decode_blinking(BLINKING.load(Ordering::Relaxed))
+}
+
+/// Returns the terminal's blinking setting as reported by [`query_cursor_blinking`] before
+/// crossterm changed it.
+pub(crate) fn original_cursor_blinking() -> Option<bool> {
+    decode_blinking(ORIGINAL_BLINKING.load(Ordering::Relaxed))
+}
+
+/// Writes the terminal's original blinking setting, if it's known, and forgets the one set with
+/// [`EnableBlinking`] or [`DisableBlinking`].
+pub(crate) fn write_original_blinking(f: &mut impl fmt::Write) -> fmt::Result {
+    crate::command::tracked(|| BLINKING.store(0, Ordering::Relaxed));
+    match original_cursor_blinking() {
+        Some(true) => f.write_str(csi!("?12h"))?,
+        Some(false) => f.write_str(csi!("?12l"))?,
+        None => {}
+    }
+    write_current_style(f, None)
+}
+
+/// Asks the terminal whether the cursor blinks, `None` if it doesn't tell.
+///
+/// The terminal is asked for the setting of the mode with DECRQM. The answer before the first
+/// [`EnableBlinking`] or [`DisableBlinking`] is the user's setting, which
+/// `terminal::restore_on_exit` of the `restore-on-exit` feature and
+/// [`terminal::restore`](../terminal/fn.restore.html) restore, so ask early.
+///
+/// On unix systems, this function will block and possibly time out while
+/// [`crossterm::event::read`](crate::event::read) or [`crossterm::event::poll`](crate::event::poll) are being called.
+///
+/// This always returns `Ok(None)` on Windows.
+#[cfg(feature = "events")]
+pub fn query_cursor_blinking() -> io::Result<Option<bool>> {
+    #[cfg(unix)]
+    {
+        let blinking = match crate::terminal::sys::query_private_mode(12)? {
+            Some(1) | Some(3) => Some(true),
+            Some(2) | Some(4) => Some(false),
+            _ => None,
+        };
+        if cursor_blinking().is_none() {
+            ORIGINAL_BLINKING.store(encode_blinking(blinking), Ordering::Relaxed);
+        }
+        Ok(blinking)
+    }
+
     #[cfg(windows)]
-    fn execute_winapi(&self) -> std::io::Result<()> {
-        Ok(())
+    {
+        Ok(None)
+    }
+}
+
+/// The styles of the live [`CursorStyleGuard`]s by their id, from the oldest to the newest.
+static GUARDED_STYLES: Mutex<Vec<(usize, SetCursorStyle)>> = parking_lot::const_mutex(Vec::new());
+
+/// The id of the next [`CursorStyleGuard`].
+static NEXT_GUARD: AtomicUsize = AtomicUsize::new(0);
+
+/// Sets the cursor style and restores the previous one when it's dropped, including when a panic
+/// unwinds the stack.
+///
+/// The previous style is the one of the enclosing guard, or the user's default shape. Guards can
+/// be dropped in any order: the cursor always has the style of the newest guard which is still
+/// alive. The commands are written to stdout.
+///
+/// # Examples
+///
+/// ```no_run
+/// use std::io;
+/// use crossterm::cursor::{CursorStyleGuard, SetCursorStyle};
+///
+/// fn main() -> io::Result<()> {
+///     let _guard = CursorStyleGuard::new(SetCursorStyle::SteadyBar)?;
+///     // edit some text
+///     Ok(())
+/// }
+/// ```
+#[derive(Debug)]
+#[must_use = "the previous cursor style is restored when the guard is dropped"]
+pub struct CursorStyleGuard {
+    id: usize,
+}
+
+impl CursorStyleGuard {
+    /// Sets the cursor style to `style`.
+    pub fn new(style: SetCursorStyle) -> io::Result<CursorStyleGuard> {
+        let mut guarded = GUARDED_STYLES.lock();
+        execute!(io::stdout(), style)?;
+        let id = NEXT_GUARD.fetch_add(1, Ordering::Relaxed);
+        guarded.push((id, style));
+        Ok(CursorStyleGuard { id })
     }
 }
 
+impl Drop for CursorStyleGuard {
+    fn drop(&mut self) {
+        let mut guarded = GUARDED_STYLES.lock();
+        let index = match guarded.iter().position(|(id, _)| *id == self.id) {
+            Some(index) => index,
+            None => return,
+        };
+        guarded.remove(index);
+        // a newer guard keeps its style
+        if index == guarded.len() {
+            let style = guarded
+                .last()
+                .map_or(SetCursorStyle::DefaultUserShape, |(_, style)| *style);
+            let _ = execute!(io::stdout(), style);
+        }
+    }
+}
+
+/// Runs `f` with the cursor style set to `style`, see [`CursorStyleGuard`].
+///
+/// # Examples
+///
+/// ```no_run
+/// use crossterm::cursor::{self, SetCursorStyle};
+///
+/// let answer = cursor::with_style(SetCursorStyle::BlinkingUnderScore, || 42)?;
+/// # std::io::Result::Ok(())
+/// ```
+pub fn with_style<T>(style: SetCursorStyle, f: impl FnOnce() -> T) -> io::Result<T> {
+    let _guard = CursorStyleGuard::new(style)?;
+    Ok(f())
+}
+
 impl_display!(for MoveTo);
 impl_display!(for MoveToColumn);
 impl_display!(for MoveToRow);
@@ -429,12 +732,78 @@ impl_display!(for SetCursorStyle);
 mod tests {
     use std::io::{self, stdout};
 
-    use crate::execute;
+    use crate::{execute, Command};
 
     use super::{
-        sys::position, MoveDown, MoveLeft, MoveRight, MoveTo, MoveUp, RestorePosition, SavePosition,
+        sys::position, write_original_blinking, CursorStyleGuard, EnableBlinking, MoveDown,
+        MoveLeft, MoveRight, MoveTo, MoveUp, RestorePosition, SavePosition, SetCursorStyle,
+        CURSOR_STYLE, GUARDED_STYLES,
     };
 
+    #[test]
+    fn test_cursor_style_with_blinking() {
+        assert_eq!(
+            SetCursorStyle::SteadyBar.with_blinking(Some(true)),
+            SetCursorStyle::BlinkingBar
+        );
+        assert_eq!(
+            SetCursorStyle::BlinkingBlock.with_blinking(Some(false)),
+            SetCursorStyle::SteadyBlock
+        );
+        assert_eq!(
+            SetCursorStyle::BlinkingUnderScore.with_blinking(None),
+            SetCursorStyle::BlinkingUnderScore
+        );
+        assert_eq!(
+            SetCursorStyle::DefaultUserShape.with_blinking(Some(true)),
+            SetCursorStyle::DefaultUserShape
+        );
+    }
+
+    #[test]
+    #[serial_test::serial]
+    fn test_cursor_style_keeps_its_blinking() {
+        let mut written = String::new();
+        crate::command::with_tracking(true, || {
+            EnableBlinking.write_ansi(&mut written).unwrap();
+            written.clear();
+            SetCursorStyle::SteadyBar.write_ansi(&mut written).unwrap();
+
+            write_original_blinking(&mut String::new()).unwrap();
+            *CURSOR_STYLE.lock() = None;
+        });
+        assert_eq!(written, "\x1b[6 q");
+    }
+
+    #[test]
+    #[serial_test::serial]
+    fn test_cursor_style_guards_dropped_out_of_order() {
+        let guarded = || {
+            GUARDED_STYLES
+                .lock()
+                .iter()
+                .map(|(_, style)| *style)
+                .collect::<Vec<_>>()
+        };
+
+        let outer = CursorStyleGuard::new(SetCursorStyle::SteadyBar).unwrap();
+        let inner = CursorStyleGuard::new(SetCursorStyle::BlinkingBlock).unwrap();
+        drop(outer);
+        // the newer guard keeps its style
+        assert_eq!(guarded(), [SetCursorStyle::BlinkingBlock]);
+        assert_eq!(*CURSOR_STYLE.lock(), Some(SetCursorStyle::BlinkingBlock));
+        drop(inner);
+        assert!(guarded().is_empty());
+        assert_eq!(*CURSOR_STYLE.lock(), None);
+
+        let outer = CursorStyleGuard::new(SetCursorStyle::SteadyBar).unwrap();
+        let inner = CursorStyleGuard::new(SetCursorStyle::BlinkingBlock).unwrap();
+        drop(inner);
+        assert_eq!(*CURSOR_STYLE.lock(), Some(SetCursorStyle::SteadyBar));
+        drop(outer);
+        assert_eq!(*CURSOR_STYLE.lock(), None);
+    }
+
     // Test is disabled, because it's failing on Travis
     #[test]
     #[ignore]
@@ -0,0 +1,127 @@
+use std::{
+    error,
+    fmt::{self, Display},
+    io,
+};
+
+/// The reason a crossterm operation failed.
+///
+/// No function returns an `Error` directly: they return `io::Result`, so they compose with the
+/// other I/O code of an application, and the errors crossterm raises itself wrap an `Error`, e.g.
+/// the timeouts of the terminal queries and the capabilities the platform lacks. Convert the
+/// `io::Error` with `Error::from` to tell them apart without matching messages, errors of the
+/// operating system become [`Error::Io`](#variant.Io):
+///
+/// ```no_run
+/// use crossterm::{terminal, Error};
+///
+/// match terminal::cell_size().map_err(Error::from) {
+///     Ok(size) => println!("{:?}", size),
+///     Err(Error::UnsupportedCapability(_)) => println!("the terminal doesn't report pixels"),
+///     Err(Error::Timeout(_)) => println!("the terminal didn't answer"),
+///     Err(e) => println!("{}", e),
+/// }
+/// ```
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum Error {
+    /// The terminal or platform doesn't support the operation.
+    UnsupportedCapability(&'static str),
+    /// The stream isn't a terminal.
+    NotATty,
+    /// Data, e.g. a reply of the terminal, couldn't be parsed.
+    ParseError(&'static str),
+    /// The terminal didn't reply in time.
+    Timeout(&'static str),
+    /// Any other I/O error.
+    Io(io::Error),
+}
+
+impl Error {
+    /// Returns the `io::ErrorKind` of the error as an `io::Error`.
+    pub fn kind(&self) -> io::ErrorKind {
+        match self {
+            Error::UnsupportedCapability(_) => io::ErrorKind::Unsupported,
+            Error::NotATty => io::ErrorKind::InvalidInput,
+            Error::ParseError(_) => io::ErrorKind::InvalidData,
+            Error::Timeout(_) => io::ErrorKind::TimedOut,
+            Error::Io(e) => e.kind(),
+        }
+    }
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::UnsupportedCapability(message)
+            | Error::ParseError(message)
+            | Error::Timeout(message) => f.write_str(message),
+            Error::NotATty => f.write_str("The stream is not a terminal."),
+            Error::Io(e) => e.fmt(f),
+        }
+    }
+}
+
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            Error::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    /// Unwraps the `Error` crossterm wrapped in `e`, and tells `ENOTTY` apart on UNIX.
+    fn from(e: io::Error) -> Self {
+        if e.get_ref().map_or(false, |inner| inner.is::<Error>()) {
+            if let Some(inner) = e.into_inner() {
+                if let Ok(error) = inner.downcast::<Error>() {
+                    return *error;
+                }
+            }
+            unreachable!("the inner error was checked to be an `Error`");
+        }
+
+        #[cfg(unix)]
+        if e.raw_os_error() == Some(libc::ENOTTY) {
+            return Error::NotATty;
+        }
+
+        Error::Io(e)
+    }
+}
+
+impl From<Error> for io::Error {
+    fn from(e: Error) -> Self {
+        match e {
+            Error::Io(e) => e,
+            e => io::Error::new(e.kind(), e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io;
+
+    use super::Error;
+
+    #[test]
+    fn test_errors_survive_io_errors() {
+        let e = io::Error::from(Error::Timeout("The terminal didn't reply."));
+        assert_eq!(e.kind(), io::ErrorKind::TimedOut);
+        assert_eq!(e.to_string(), "The terminal didn't reply.");
+        assert!(matches!(Error::from(e), Error::Timeout(_)));
+
+        let e = io::Error::from(Error::Io(io::ErrorKind::BrokenPipe.into()));
+        assert_eq!(e.kind(), io::ErrorKind::BrokenPipe);
+        assert!(matches!(Error::from(e), Error::Io(_)));
+
+        #[cfg(unix)]
+        assert!(matches!(
+            Error::from(io::Error::from_raw_os_error(libc::ENOTTY)),
+            Error::NotATty
+        ));
+    }
+}
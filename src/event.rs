@@ -39,8 +39,12 @@
 //!             Event::Key(event) => println!("{:?}", event),
 //!             Event::Mouse(event) => println!("{:?}", event),
 //!             #[cfg(feature = "bracketed-paste")]
-//!             Event::Paste(data) => println!("{:?}", data),
+//!             Event::Paste(paste) => println!("{:?}", paste.content),
 //!             Event::Resize(width, height) => println!("New size {}x{}", width, height),
+//!             Event::ColorReport { slot, color } => println!("{:?} is {:?}", slot, color),
+//!             Event::Tick(id) => println!("{:?} is due", id),
+//!             Event::NotificationActivated(id) => println!("{} was clicked", id),
+//!             Event::NotificationClosed(id) => println!("{} was closed", id),
 //!         }
 //!     }
 //!     Ok(())
@@ -66,8 +70,12 @@
 //!                 Event::Key(event) => println!("{:?}", event),
 //!                 Event::Mouse(event) => println!("{:?}", event),
 //!                 #[cfg(feature = "bracketed-paste")]
-//!                 Event::Paste(data) => println!("Pasted {:?}", data),
+//!                 Event::Paste(paste) => println!("Pasted {:?}", paste.content),
 //!                 Event::Resize(width, height) => println!("New size {}x{}", width, height),
+//!                 Event::ColorReport { slot, color } => println!("{:?} is {:?}", slot, color),
+//!                 Event::Tick(id) => println!("{:?} is due", id),
+//!                 Event::NotificationActivated(id) => println!("{} was clicked", id),
+//!                 Event::NotificationClosed(id) => println!("{} was closed", id),
 //!             }
 //!         } else {
 //!             // Timeout expired and no `Event` is available
@@ -80,25 +88,41 @@
 //! Check the [examples](https://github.com/crossterm-rs/crossterm/tree/master/examples) folder for more of
 //! them (`event-*`).
 
+pub mod clock;
 pub(crate) mod filter;
+mod hooks;
+mod keymap;
+pub mod parse;
 pub(crate) mod read;
+mod replay;
+mod script;
 pub(crate) mod source;
 #[cfg(feature = "event-stream")]
 pub(crate) mod stream;
 pub(crate) mod sys;
 pub(crate) mod timeout;
+pub(crate) mod timer;
 
+pub use hooks::{
+    add_dcs_handler, add_osc_handler, remove_dcs_handler, remove_osc_handler, ControlString,
+};
+pub use keymap::{add_key_sequence, remove_key_sequence};
+pub use replay::{replay, Macro, MacroRecorder};
+pub use script::{play_script, stop_script, Script};
 #[cfg(feature = "event-stream")]
 pub use stream::EventStream;
+pub use timer::{add_interval, add_timer, remove_timer, TimerId};
 
 use crate::event::{
     filter::{EventFilter, Filter},
     read::InternalEventReader,
     timeout::PollTimeout,
 };
-use crate::{csi, Command};
+use crate::{capabilities::Capabilities, csi, style::Color, Command};
 use parking_lot::{MappedMutexGuard, Mutex, MutexGuard};
+use std::cmp;
 use std::fmt::{self, Display};
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::time::Duration;
 
 use bitflags::bitflags;
@@ -108,14 +132,32 @@ use std::hash::{Hash, Hasher};
 /// This needs to be static because there can be one event reader.
 static INTERNAL_EVENT_READER: Mutex<Option<InternalEventReader>> = parking_lot::const_mutex(None);
 
-pub(crate) fn lock_internal_event_reader() -> MappedMutexGuard<'static, InternalEventReader> {
+/// The reader of the script passed to [`play_script`], which replaces the terminal for the
+/// events, but not for the responses to queries.
+static SCRIPT_READER: Mutex<Option<InternalEventReader>> = parking_lot::const_mutex(None);
+
+/// Locks the reader `F` reads from, the one of a playing script for the events.
+pub(crate) fn lock_internal_event_reader<F: Filter>(
+) -> MappedMutexGuard<'static, InternalEventReader> {
+    if F::EVENTS {
+        if let Ok(script) = MutexGuard::try_map(SCRIPT_READER.lock(), Option::as_mut) {
+            return script;
+        }
+    }
     MutexGuard::map(INTERNAL_EVENT_READER.lock(), |reader| {
         reader.get_or_insert_with(InternalEventReader::default)
     })
 }
-fn try_lock_internal_event_reader_for(
+fn try_lock_internal_event_reader_for<F: Filter>(
     duration: Duration,
 ) -> Option<MappedMutexGuard<'static, InternalEventReader>> {
+    if F::EVENTS {
+        if let Ok(script) =
+            MutexGuard::try_map(SCRIPT_READER.try_lock_for(duration)?, Option::as_mut)
+        {
+            return Some(script);
+        }
+    }
     Some(MutexGuard::map(
         INTERNAL_EVENT_READER.try_lock_for(duration)?,
         |reader| reader.get_or_insert_with(InternalEventReader::default),
@@ -209,11 +251,34 @@ pub fn poll(timeout: Duration) -> std::io::Result<bool> {
 pub fn read() -> std::io::Result<Event> {
     match read_internal(&EventFilter)? {
         InternalEvent::Event(event) => Ok(event),
-        #[cfg(unix)]
         _ => unreachable!(),
     }
 }
 
+/// Enables the virtual terminal input mode of the Windows console.
+///
+/// In this mode the console reports input as VT sequences, which are parsed by the same parser
+/// as on UNIX. This gives access to everything the console can report this way, e.g. SGR mouse
+/// events, focus events and bracketed paste, and makes
+/// [`EnableMouseCapture`](struct.EnableMouseCapture.html) enable the mouse with VT sequences.
+///
+/// # Notes
+///
+/// This is only available on Windows 10 and later.
+#[cfg(windows)]
+pub fn enable_virtual_terminal_input() -> std::io::Result<()> {
+    sys::windows::set_vt_input(true)
+}
+
+/// Disables the virtual terminal input mode of the Windows console, input is read as console
+/// input records again.
+///
+/// See [`enable_virtual_terminal_input`](fn.enable_virtual_terminal_input.html).
+#[cfg(windows)]
+pub fn disable_virtual_terminal_input() -> std::io::Result<()> {
+    sys::windows::set_vt_input(false)
+}
+
 /// Polls to check if there are any `InternalEvent`s that can be read within the given duration.
 pub(crate) fn poll_internal<F>(timeout: Option<Duration>, filter: &F) -> std::io::Result<bool>
 where
@@ -221,13 +286,13 @@ where
 {
     let (mut reader, timeout) = if let Some(timeout) = timeout {
         let poll_timeout = PollTimeout::new(Some(timeout));
-        if let Some(reader) = try_lock_internal_event_reader_for(timeout) {
+        if let Some(reader) = try_lock_internal_event_reader_for::<F>(timeout) {
             (reader, poll_timeout.leftover())
         } else {
             return Ok(false);
         }
     } else {
-        (lock_internal_event_reader(), None)
+        (lock_internal_event_reader::<F>(), None)
     };
     reader.poll(timeout, filter)
 }
@@ -237,7 +302,7 @@ pub(crate) fn read_internal<F>(filter: &F) -> std::io::Result<InternalEvent>
 where
     F: Filter,
 {
-    let mut reader = lock_internal_event_reader();
+    let mut reader = lock_internal_event_reader::<F>();
     reader.read(filter)
 }
 
@@ -273,35 +338,219 @@ bitflags! {
 /// A command that enables mouse event capturing.
 ///
 /// Mouse events can be captured with [read](./fn.read.html)/[poll](./fn.poll.html).
+///
+/// The encodings the [`Capabilities`](../capabilities/struct.Capabilities.html) allow are all
+/// enabled, and the terminal uses the best one it understands. Use
+/// [`EnableMouseCaptureWith`](struct.EnableMouseCaptureWith.html) to enable a single one.
 #[cfg(feature = "events")]
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct EnableMouseCapture;
 
+#[cfg(feature = "events")]
+impl EnableMouseCapture {
+    /// Returns a command which reports button presses, releases and the scroll wheel only
+    /// (`?1000`).
+    pub fn buttons_only() -> EnableMouseCaptureWith {
+        EnableMouseCaptureWith(MouseCaptureOptions::new().with_tracking(MouseTracking::Buttons))
+    }
+
+    /// Returns a command which reports the motion while a button is pressed as well (`?1002`).
+    pub fn with_drag() -> EnableMouseCaptureWith {
+        EnableMouseCaptureWith(MouseCaptureOptions::new().with_tracking(MouseTracking::Drag))
+    }
+
+    /// Returns a command which reports all motion (`?1003`), like this command.
+    pub fn any_motion() -> EnableMouseCaptureWith {
+        EnableMouseCaptureWith(MouseCaptureOptions::new().with_tracking(MouseTracking::AnyMotion))
+    }
+}
+
 #[cfg(feature = "events")]
 impl Command for EnableMouseCapture {
     fn write_ansi(&self, f: &mut impl fmt::Write) -> fmt::Result {
-        f.write_str(concat!(
-            // Normal tracking: Send mouse X & Y on button press and release
-            csi!("?1000h"),
-            // Button-event tracking: Report button motion events (dragging)
-            csi!("?1002h"),
-            // Any-event tracking: Report all motion events
-            csi!("?1003h"),
-            // RXVT mouse mode: Allows mouse coordinates of >223
-            csi!("?1015h"),
-            // SGR mouse mode: Allows mouse coordinates of >223, preferred over RXVT mode
-            csi!("?1006h"),
-        ))
+        EnableMouseCaptureWith(MouseCaptureOptions::default()).write_ansi(f)
     }
 
     #[cfg(windows)]
     fn execute_winapi(&self) -> std::io::Result<()> {
-        sys::windows::enable_mouse_capture()
+        sys::windows::enable_mouse_capture()?;
+        MOUSE_CAPTURE.store(true, Ordering::Relaxed);
+        Ok(())
     }
 
     #[cfg(windows)]
     fn is_ansi_code_supported(&self) -> bool {
-        false
+        // Mouse events are reported as VT sequences as well in virtual terminal input mode.
+        sys::windows::is_vt_input_enabled()
+    }
+}
+
+/// How the terminal encodes mouse events, see
+/// [`EnableMouseCaptureWith`](struct.EnableMouseCaptureWith.html).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MouseEncoding {
+    /// The original encoding, with one byte per coordinate, which can't report columns or rows
+    /// beyond 223.
+    X10,
+    /// The coordinates are encoded as UTF-8 characters (`?1005`), up to 2015.
+    Utf8,
+    /// The SGR encoding (`?1006`), which is the most widely supported one without limits.
+    Sgr,
+    /// The urxvt encoding (`?1015`), without limits.
+    Urxvt,
+}
+
+impl MouseEncoding {
+    /// Returns the DEC private mode which enables the encoding, `None` for the original one.
+    fn mode(self) -> Option<u16> {
+        match self {
+            MouseEncoding::X10 => None,
+            MouseEncoding::Utf8 => Some(1005),
+            MouseEncoding::Sgr => Some(1006),
+            MouseEncoding::Urxvt => Some(1015),
+        }
+    }
+}
+
+/// Which mouse events the terminal reports, see
+/// [`EnableMouseCaptureWith`](struct.EnableMouseCaptureWith.html).
+///
+/// Reporting less saves the application from handling, and possibly repainting for, events it
+/// ignores anyway.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum MouseTracking {
+    /// Button presses, releases and the scroll wheel (`?1000`).
+    Buttons,
+    /// The motion while a button is pressed as well (`?1002`).
+    Drag,
+    /// All motion as well (`?1003`), the default.
+    AnyMotion,
+}
+
+impl MouseTracking {
+    /// Returns the DEC private modes which enable the tracking, in the order they are enabled.
+    fn modes(self) -> &'static [u16] {
+        match self {
+            MouseTracking::Buttons => &[1000],
+            MouseTracking::Drag => &[1000, 1002],
+            MouseTracking::AnyMotion => &[1000, 1002, 1003],
+        }
+    }
+}
+
+impl Default for MouseTracking {
+    fn default() -> Self {
+        MouseTracking::AnyMotion
+    }
+}
+
+/// How [`EnableMouseCaptureWith`](struct.EnableMouseCaptureWith.html) captures the mouse.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct MouseCaptureOptions {
+    /// The encoding, `None` to enable the ones the
+    /// [`Capabilities`](../capabilities/struct.Capabilities.html) allow, like
+    /// [`EnableMouseCapture`](struct.EnableMouseCapture.html).
+    pub encoding: Option<MouseEncoding>,
+    /// Which events are reported.
+    pub tracking: MouseTracking,
+}
+
+impl MouseCaptureOptions {
+    /// Creates options which enable the encodings the capabilities allow and report all events.
+    pub fn new() -> MouseCaptureOptions {
+        MouseCaptureOptions::default()
+    }
+
+    /// Enables only `encoding`.
+    pub fn with_encoding(mut self, encoding: MouseEncoding) -> MouseCaptureOptions {
+        self.encoding = Some(encoding);
+        self
+    }
+
+    /// Reports the events of `tracking` only.
+    pub fn with_tracking(mut self, tracking: MouseTracking) -> MouseCaptureOptions {
+        self.tracking = tracking;
+        self
+    }
+
+    /// Returns the encodings to enable, in the order they are enabled.
+    fn encodings(
+        self,
+        capabilities: &Capabilities,
+    ) -> impl DoubleEndedIterator<Item = MouseEncoding> {
+        let (urxvt, sgr) = (capabilities.urxvt_mouse, capabilities.sgr_mouse);
+        // The terminal uses the last one it understands, so the preferred one comes last.
+        [
+            MouseEncoding::Utf8,
+            MouseEncoding::Urxvt,
+            MouseEncoding::Sgr,
+        ]
+        .into_iter()
+        .filter(move |&encoding| match self.encoding {
+            Some(chosen) => chosen == encoding,
+            None => {
+                (encoding == MouseEncoding::Urxvt && urxvt)
+                    || (encoding == MouseEncoding::Sgr && sgr)
+            }
+        })
+    }
+
+    /// Returns the encoding the terminal uses if it understands all of them.
+    fn chosen_encoding(self, capabilities: &Capabilities) -> MouseEncoding {
+        self.encodings(capabilities)
+            .last()
+            .unwrap_or(MouseEncoding::X10)
+    }
+}
+
+/// A command that enables mouse event capturing with options, e.g. a single encoding for
+/// terminals which misbehave if several are requested.
+///
+/// Mouse events can be captured with [read](./fn.read.html)/[poll](./fn.poll.html). The encoding
+/// in use is returned by [`mouse_encoding`](fn.mouse_encoding.html).
+///
+/// # Examples
+///
+/// ```no_run
+/// use std::io;
+/// use crossterm::{
+///     event::{EnableMouseCaptureWith, MouseCaptureOptions, MouseEncoding},
+///     execute,
+/// };
+///
+/// fn main() -> io::Result<()> {
+///     let options = MouseCaptureOptions::new().with_encoding(MouseEncoding::Sgr);
+///     execute!(io::stdout(), EnableMouseCaptureWith(options))
+/// }
+/// ```
+///
+/// # Notes
+///
+/// Commands must be executed/queued for execution otherwise they do nothing.
+#[cfg(feature = "events")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EnableMouseCaptureWith(pub MouseCaptureOptions);
+
+#[cfg(feature = "events")]
+impl Command for EnableMouseCaptureWith {
+    fn write_ansi(&self, f: &mut impl fmt::Write) -> fmt::Result {
+        // the capabilities are cloned, so their lock isn't held while a stalled terminal is written
+        let capabilities = crate::capabilities::current();
+        crate::command::tracked(|| {
+            MOUSE_CAPTURE.store(true, Ordering::Relaxed);
+            *MOUSE_CAPTURE_OPTIONS.lock() = Some((self.0, capabilities.clone()));
+        });
+        write_enable_mouse_capture(f, self.0, &capabilities)
+    }
+
+    #[cfg(windows)]
+    fn execute_winapi(&self) -> std::io::Result<()> {
+        EnableMouseCapture.execute_winapi()
+    }
+
+    #[cfg(windows)]
+    fn is_ansi_code_supported(&self) -> bool {
+        EnableMouseCapture.is_ansi_code_supported()
     }
 }
 
@@ -313,19 +562,122 @@ pub struct DisableMouseCapture;
 
 impl Command for DisableMouseCapture {
     fn write_ansi(&self, f: &mut impl fmt::Write) -> fmt::Result {
-        f.write_str(concat!(
-            // The inverse commands of EnableMouseCapture, in reverse order.
-            csi!("?1006l"),
-            csi!("?1015l"),
-            csi!("?1003l"),
-            csi!("?1002l"),
-            csi!("?1000l"),
-        ))
+        // the encodings which were enabled, even if the capabilities changed since, or those of
+        // the capabilities, if the mouse was captured by other means or for another terminal
+        let captured = if crate::capabilities::is_other_terminal() {
+            None
+        } else {
+            MOUSE_CAPTURE_OPTIONS.lock().clone()
+        };
+        crate::command::tracked(|| {
+            MOUSE_CAPTURE.store(false, Ordering::Relaxed);
+            *MOUSE_CAPTURE_OPTIONS.lock() = None;
+        });
+        match captured {
+            Some((options, capabilities)) => write_disable_mouse_capture(f, options, &capabilities),
+            None => write_disable_mouse_capture(
+                f,
+                MouseCaptureOptions::default(),
+                &crate::capabilities::current(),
+            ),
+        }
+    }
+
+    #[cfg(windows)]
+    fn execute_winapi(&self) -> std::io::Result<()> {
+        sys::windows::disable_mouse_capture()?;
+        MOUSE_CAPTURE.store(false, Ordering::Relaxed);
+        Ok(())
+    }
+
+    #[cfg(windows)]
+    fn is_ansi_code_supported(&self) -> bool {
+        sys::windows::is_vt_input_enabled()
+    }
+}
+
+/// Whether the mouse is captured with [`EnableMouseCapture`].
+static MOUSE_CAPTURE: AtomicBool = AtomicBool::new(false);
+/// The options of the last [`EnableMouseCaptureWith`] and the capabilities which chose its
+/// encodings, while the mouse is captured.
+static MOUSE_CAPTURE_OPTIONS: Mutex<Option<(MouseCaptureOptions, Capabilities)>> =
+    parking_lot::const_mutex(None);
+
+/// Returns the options the mouse is captured with, for [`terminal::restore`](crate::terminal::restore).
+pub(crate) fn mouse_capture_options() -> Option<MouseCaptureOptions> {
+    MOUSE_CAPTURE_OPTIONS
+        .lock()
+        .as_ref()
+        .map(|(options, _)| *options)
+}
+
+/// Returns the encoding of mouse events, `None` if the mouse isn't captured.
+///
+/// If several encodings are enabled, this is the one the terminal uses if it understands all of
+/// them, see [`EnableMouseCapture`]. This is tracked from the commands queued on a
+/// [`ProcessTerminal`](crate::ProcessTerminal), the terminal isn't asked.
+pub fn mouse_encoding() -> Option<MouseEncoding> {
+    let (options, capabilities) = MOUSE_CAPTURE_OPTIONS.lock().clone()?;
+    Some(options.chosen_encoding(&capabilities))
+}
+
+/// Tells whether the mouse is captured.
+///
+/// This is tracked from the [`EnableMouseCapture`] and [`DisableMouseCapture`] commands
+/// queued on a [`ProcessTerminal`](crate::ProcessTerminal), the terminal isn't asked.
+pub fn is_mouse_capture_enabled() -> bool {
+    MOUSE_CAPTURE.load(Ordering::Relaxed)
+}
+
+/// A command that enables the QuickEdit mode of the Windows console.
+///
+/// In QuickEdit mode the mouse selects text in the console window instead of being reported to
+/// the application. [`EnableMouseCapture`](struct.EnableMouseCapture.html) turns it off while the
+/// mouse is captured and [`DisableMouseCapture`](struct.DisableMouseCapture.html) restores the
+/// original console mode, use this to turn it on explicitly.
+///
+/// # Notes
+///
+/// This is only supported on Windows, it does nothing on other platforms.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EnableQuickEdit;
+
+impl Command for EnableQuickEdit {
+    fn write_ansi(&self, _f: &mut impl fmt::Write) -> fmt::Result {
+        Ok(())
+    }
+
+    #[cfg(windows)]
+    fn execute_winapi(&self) -> std::io::Result<()> {
+        sys::windows::set_quick_edit(true)
+    }
+
+    #[cfg(windows)]
+    fn is_ansi_code_supported(&self) -> bool {
+        false
+    }
+}
+
+/// A command that disables the QuickEdit mode of the Windows console.
+///
+/// Disable it before capturing the mouse if the console mode might be changed in between, so the
+/// console doesn't swallow mouse input to select text. See
+/// [`EnableQuickEdit`](struct.EnableQuickEdit.html).
+///
+/// # Notes
+///
+/// This is only supported on Windows, it does nothing on other platforms.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DisableQuickEdit;
+
+impl Command for DisableQuickEdit {
+    fn write_ansi(&self, _f: &mut impl fmt::Write) -> fmt::Result {
+        Ok(())
     }
 
     #[cfg(windows)]
     fn execute_winapi(&self) -> std::io::Result<()> {
-        sys::windows::disable_mouse_capture()
+        sys::windows::set_quick_edit(false)
     }
 
     #[cfg(windows)]
@@ -334,6 +686,46 @@ impl Command for DisableMouseCapture {
     }
 }
 
+fn write_enable_mouse_capture(
+    f: &mut impl fmt::Write,
+    options: MouseCaptureOptions,
+    capabilities: &Capabilities,
+) -> fmt::Result {
+    // Normal tracking: Send mouse X & Y on button press and release
+    // Button-event tracking: Report button motion events (dragging)
+    // Any-event tracking: Report all motion events
+    for mode in options.tracking.modes() {
+        write!(f, csi!("?{}h"), mode)?;
+    }
+    // UTF-8, RXVT and SGR mouse modes: Allow mouse coordinates of >223
+    for mode in options
+        .encodings(capabilities)
+        .filter_map(MouseEncoding::mode)
+    {
+        write!(f, csi!("?{}h"), mode)?;
+    }
+    Ok(())
+}
+
+fn write_disable_mouse_capture(
+    f: &mut impl fmt::Write,
+    options: MouseCaptureOptions,
+    capabilities: &Capabilities,
+) -> fmt::Result {
+    // The inverse commands of EnableMouseCapture, in reverse order.
+    for mode in options
+        .encodings(capabilities)
+        .rev()
+        .filter_map(MouseEncoding::mode)
+    {
+        write!(f, csi!("?{}l"), mode)?;
+    }
+    for mode in options.tracking.modes().iter().rev() {
+        write!(f, csi!("?{}l"), mode)?;
+    }
+    Ok(())
+}
+
 /// A command that enables focus event emission.
 ///
 /// It should be paired with [`DisableFocusChange`] at the end of execution.
@@ -370,6 +762,91 @@ impl Command for DisableFocusChange {
     }
 }
 
+/// The shape of the mouse pointer, see [`SetPointerShape`].
+///
+/// The shapes are named like the CSS cursors.
+#[derive(Debug, PartialOrd, PartialEq, Eq, Clone, Copy, Hash)]
+pub enum PointerShape {
+    /// The default pointer, usually an arrow.
+    Default,
+    /// A text beam, e.g. over editable text.
+    Text,
+    /// A hand, e.g. over links and buttons.
+    Pointer,
+    /// A crosshair.
+    Crosshair,
+    /// A busy indicator.
+    Wait,
+    /// A busy indicator, while the application can still be used.
+    Progress,
+    /// A question mark.
+    Help,
+    /// The action isn't allowed.
+    NotAllowed,
+    /// Something can be moved.
+    Move,
+    /// Something can be grabbed.
+    Grab,
+    /// Something is grabbed.
+    Grabbing,
+    /// A column can be resized horizontally.
+    ColResize,
+    /// A row can be resized vertically.
+    RowResize,
+    /// Something can be zoomed in.
+    ZoomIn,
+    /// Something can be zoomed out.
+    ZoomOut,
+}
+
+impl PointerShape {
+    fn name(self) -> &'static str {
+        match self {
+            PointerShape::Default => "default",
+            PointerShape::Text => "text",
+            PointerShape::Pointer => "pointer",
+            PointerShape::Crosshair => "crosshair",
+            PointerShape::Wait => "wait",
+            PointerShape::Progress => "progress",
+            PointerShape::Help => "help",
+            PointerShape::NotAllowed => "not-allowed",
+            PointerShape::Move => "move",
+            PointerShape::Grab => "grab",
+            PointerShape::Grabbing => "grabbing",
+            PointerShape::ColResize => "col-resize",
+            PointerShape::RowResize => "row-resize",
+            PointerShape::ZoomIn => "zoom-in",
+            PointerShape::ZoomOut => "zoom-out",
+        }
+    }
+}
+
+/// A command that sets the shape of the mouse pointer while it's over the terminal, with
+/// `OSC 22`.
+///
+/// Supported by kitty, WezTerm and foot among others, other terminals ignore it. Set
+/// [`PointerShape::Default`] before exiting.
+///
+/// # Notes
+///
+/// Commands must be executed/queued for execution otherwise they do nothing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SetPointerShape(pub PointerShape);
+
+impl Command for SetPointerShape {
+    fn write_ansi(&self, f: &mut impl fmt::Write) -> fmt::Result {
+        write!(f, "\x1B]22;{}\x1B\\", self.0.name())
+    }
+
+    #[cfg(windows)]
+    fn execute_winapi(&self) -> std::io::Result<()> {
+        Err(crate::Error::UnsupportedCapability(
+            "Pointer shapes are not supported by the legacy Windows API.",
+        )
+        .into())
+    }
+}
+
 /// A command that enables [bracketed paste mode](https://en.wikipedia.org/wiki/Bracketed-paste).
 ///
 /// It should be paired with [`DisableBracketedPaste`] at the end of execution.
@@ -383,15 +860,16 @@ pub struct EnableBracketedPaste;
 #[cfg(feature = "bracketed-paste")]
 impl Command for EnableBracketedPaste {
     fn write_ansi(&self, f: &mut impl fmt::Write) -> fmt::Result {
+        crate::command::tracked(|| BRACKETED_PASTE.store(true, Ordering::Relaxed));
         f.write_str(csi!("?2004h"))
     }
 
     #[cfg(windows)]
     fn execute_winapi(&self) -> std::io::Result<()> {
-        Err(std::io::Error::new(
-            std::io::ErrorKind::Unsupported,
+        Err(crate::Error::UnsupportedCapability(
             "Bracketed paste not implemented in the legacy Windows API.",
-        ))
+        )
+        .into())
     }
 }
 
@@ -403,6 +881,7 @@ pub struct DisableBracketedPaste;
 #[cfg(feature = "bracketed-paste")]
 impl Command for DisableBracketedPaste {
     fn write_ansi(&self, f: &mut impl fmt::Write) -> fmt::Result {
+        crate::command::tracked(|| BRACKETED_PASTE.store(false, Ordering::Relaxed));
         f.write_str(csi!("?2004l"))
     }
 
@@ -412,6 +891,19 @@ impl Command for DisableBracketedPaste {
     }
 }
 
+/// Whether bracketed paste is enabled with [`EnableBracketedPaste`].
+#[cfg(feature = "bracketed-paste")]
+static BRACKETED_PASTE: AtomicBool = AtomicBool::new(false);
+
+#[cfg(feature = "bracketed-paste")]
+/// Tells whether bracketed paste is enabled.
+///
+/// This is tracked from the [`EnableBracketedPaste`] and [`DisableBracketedPaste`] commands
+/// queued on a [`ProcessTerminal`](crate::ProcessTerminal), the terminal isn't asked.
+pub fn is_bracketed_paste_enabled() -> bool {
+    BRACKETED_PASTE.load(Ordering::Relaxed)
+}
+
 /// A command that enables the [kitty keyboard protocol](https://sw.kovidgoyal.net/kitty/keyboard-protocol/), which adds extra information to keyboard events and removes ambiguity for modifier keys.
 ///
 /// It should be paired with [`PopKeyboardEnhancementFlags`] at the end of execution.
@@ -454,17 +946,16 @@ pub struct PushKeyboardEnhancementFlags(pub KeyboardEnhancementFlags);
 
 impl Command for PushKeyboardEnhancementFlags {
     fn write_ansi(&self, f: &mut impl fmt::Write) -> fmt::Result {
+        crate::command::tracked(|| KEYBOARD_FLAGS.lock().current_mut().push(self.0));
         write!(f, "{}{}u", csi!(">"), self.0.bits())
     }
 
     #[cfg(windows)]
     fn execute_winapi(&self) -> std::io::Result<()> {
-        use std::io;
-
-        Err(io::Error::new(
-            io::ErrorKind::Unsupported,
+        Err(crate::Error::UnsupportedCapability(
             "Keyboard progressive enhancement not implemented for the legacy Windows API.",
-        ))
+        )
+        .into())
     }
 
     #[cfg(windows)]
@@ -473,6 +964,136 @@ impl Command for PushKeyboardEnhancementFlags {
     }
 }
 
+/// The keyboard enhancement flags pushed on the main and the alternate screen.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub(crate) struct KeyboardFlagStacks {
+    main: Vec<KeyboardEnhancementFlags>,
+    alternate: Vec<KeyboardEnhancementFlags>,
+    alternate_active: bool,
+}
+
+impl KeyboardFlagStacks {
+    const fn new() -> KeyboardFlagStacks {
+        KeyboardFlagStacks {
+            main: Vec::new(),
+            alternate: Vec::new(),
+            alternate_active: false,
+        }
+    }
+
+    fn current_mut(&mut self) -> &mut Vec<KeyboardEnhancementFlags> {
+        if self.alternate_active {
+            &mut self.alternate
+        } else {
+            &mut self.main
+        }
+    }
+
+    fn current(&self) -> KeyboardEnhancementFlags {
+        let stack = if self.alternate_active {
+            &self.alternate
+        } else {
+            &self.main
+        };
+        stack
+            .last()
+            .copied()
+            .unwrap_or_else(KeyboardEnhancementFlags::empty)
+    }
+
+    /// Switches the screen and sets the flags of the new screen, which terminals without separate
+    /// stacks per screen don't do themselves.
+    fn switch_screen(&mut self, f: &mut impl fmt::Write, alternate: bool) -> fmt::Result {
+        if self.alternate_active == alternate {
+            return Ok(());
+        }
+        self.alternate_active = alternate;
+
+        if self.main.is_empty() && self.alternate.is_empty() {
+            return Ok(());
+        }
+        // ESC [ = flags ; 1 u      Set the flags of the current stack entry.
+        write!(f, "{}{};1u", csi!("="), self.current().bits())
+    }
+
+    /// Replaces the stacks with `saved`, which is on the same screen, and writes the pops and
+    /// pushes which turn the stack of the current screen into the saved one.
+    fn restore(&mut self, f: &mut impl fmt::Write, saved: &KeyboardFlagStacks) -> fmt::Result {
+        let saved_current = if saved.alternate_active {
+            &saved.alternate
+        } else {
+            &saved.main
+        };
+        let current = self.current_mut();
+        if current != saved_current {
+            if !current.is_empty() {
+                // ESC [ < n u      Pop n entries of the stack.
+                write!(f, "{}{}u", csi!("<"), current.len())?;
+            }
+            for flags in saved_current {
+                write!(f, "{}{}u", csi!(">"), flags.bits())?;
+            }
+        }
+        *self = saved.clone();
+        Ok(())
+    }
+}
+
+static KEYBOARD_FLAGS: Mutex<KeyboardFlagStacks> =
+    parking_lot::const_mutex(KeyboardFlagStacks::new());
+
+/// Returns the keyboard enhancement flags in effect on the current screen.
+///
+/// The flags aren't queried from the terminal, they are tracked from the
+/// [`PushKeyboardEnhancementFlags`] and [`PopKeyboardEnhancementFlags`] commands queued on a
+/// [`ProcessTerminal`](crate::ProcessTerminal), commands written elsewhere leave them alone. The main and
+/// the alternate screen have their own stack of flags, like in kitty.
+/// [`EnterAlternateScreen`](crate::terminal::EnterAlternateScreen) and
+/// [`LeaveAlternateScreen`](crate::terminal::LeaveAlternateScreen) re-apply the flags of the screen
+/// they switch to.
+pub fn current_keyboard_enhancement_flags() -> KeyboardEnhancementFlags {
+    KEYBOARD_FLAGS.lock().current()
+}
+
+/// Returns the keyboard enhancement flags pushed on both screens.
+pub(crate) fn save_keyboard_flags() -> KeyboardFlagStacks {
+    KEYBOARD_FLAGS.lock().clone()
+}
+
+/// Restores the flags returned by [`save_keyboard_flags`], once the screen they were saved on is
+/// the current one again.
+pub(crate) fn restore_keyboard_flags(
+    f: &mut impl fmt::Write,
+    saved: &KeyboardFlagStacks,
+) -> fmt::Result {
+    if crate::capabilities::is_other_terminal() {
+        return KeyboardFlagStacks::new().restore(f, saved);
+    }
+    let mut flags = KEYBOARD_FLAGS.lock();
+    if crate::command::is_tracking() {
+        flags.restore(f, saved)
+    } else {
+        flags.clone().restore(f, saved)
+    }
+}
+
+/// Tracks a switch between the main and the alternate screen, see
+/// [`current_keyboard_enhancement_flags`].
+pub(crate) fn switch_keyboard_flags_screen(
+    f: &mut impl fmt::Write,
+    alternate: bool,
+) -> fmt::Result {
+    if crate::capabilities::is_other_terminal() {
+        return KeyboardFlagStacks::new().switch_screen(f, alternate);
+    }
+    let mut flags = KEYBOARD_FLAGS.lock();
+    if crate::command::is_tracking() {
+        flags.switch_screen(f, alternate)
+    } else {
+        flags.clone().switch_screen(f, alternate)
+    }
+}
+
 /// A command that disables extra kinds of keyboard events.
 ///
 /// Specifically, it pops one level of keyboard enhancement flags.
@@ -483,17 +1104,18 @@ pub struct PopKeyboardEnhancementFlags;
 
 impl Command for PopKeyboardEnhancementFlags {
     fn write_ansi(&self, f: &mut impl fmt::Write) -> fmt::Result {
+        crate::command::tracked(|| {
+            KEYBOARD_FLAGS.lock().current_mut().pop();
+        });
         f.write_str(csi!("<1u"))
     }
 
     #[cfg(windows)]
     fn execute_winapi(&self) -> std::io::Result<()> {
-        use std::io;
-
-        Err(io::Error::new(
-            io::ErrorKind::Unsupported,
+        Err(crate::Error::UnsupportedCapability(
             "Keyboard progressive enhancement not implemented for the legacy Windows API.",
-        ))
+        )
+        .into())
     }
 
     #[cfg(windows)]
@@ -502,6 +1124,156 @@ impl Command for PopKeyboardEnhancementFlags {
     }
 }
 
+/// A command that asks the terminal for one of its colors, which it reports with an
+/// [`Event::ColorReport`].
+///
+/// Terminals which don't support the query don't answer.
+///
+/// This is not supported in older Windows terminals without
+/// [virtual terminal sequences](https://docs.microsoft.com/en-us/windows/console/console-virtual-terminal-sequences).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct QueryColor(pub ColorSlot);
+
+impl Command for QueryColor {
+    fn write_ansi(&self, f: &mut impl fmt::Write) -> fmt::Result {
+        match self.0 {
+            ColorSlot::Foreground => f.write_str("\x1B]10;?\x1B\\"),
+            ColorSlot::Background => f.write_str("\x1B]11;?\x1B\\"),
+            ColorSlot::Cursor => f.write_str("\x1B]12;?\x1B\\"),
+            ColorSlot::Palette(index) => write!(f, "\x1B]4;{};?\x1B\\", index),
+        }
+    }
+
+    #[cfg(windows)]
+    fn execute_winapi(&self) -> std::io::Result<()> {
+        Err(crate::Error::UnsupportedCapability(
+            "Color queries are not supported by the legacy Windows API.",
+        )
+        .into())
+    }
+}
+
+/// Which color of the terminal an [`Event::ColorReport`] is about.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, PartialOrd, PartialEq, Eq, Clone, Copy, Hash)]
+pub enum ColorSlot {
+    /// The default foreground color (`OSC 10`).
+    Foreground,
+    /// The default background color (`OSC 11`).
+    Background,
+    /// The cursor color (`OSC 12`).
+    Cursor,
+    /// A color of the 256 color palette (`OSC 4`).
+    Palette(u8),
+}
+
+/// A string that was pasted into the terminal, see [`Event::Paste`].
+///
+/// It dereferences to the pasted text.
+#[cfg(feature = "bracketed-paste")]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, PartialOrd, PartialEq, Eq, Clone, Hash)]
+pub struct Paste {
+    /// The pasted text, possibly truncated.
+    pub content: String,
+    /// How the paste was recognized.
+    pub kind: PasteKind,
+    /// The length of the pasted text in bytes as it was received, before it was truncated.
+    pub len: usize,
+    /// The text was longer than the [`limit`](PasteOptions::limit) and was truncated.
+    pub truncated: bool,
+}
+
+#[cfg(feature = "bracketed-paste")]
+impl Paste {
+    /// Creates a paste of `content`, which wasn't truncated.
+    pub fn new(content: impl Into<String>, kind: PasteKind) -> Paste {
+        let content = content.into();
+        Paste {
+            len: content.len(),
+            content,
+            kind,
+            truncated: false,
+        }
+    }
+}
+
+#[cfg(feature = "bracketed-paste")]
+impl std::ops::Deref for Paste {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.content
+    }
+}
+
+#[cfg(feature = "bracketed-paste")]
+impl Display for Paste {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.content)
+    }
+}
+
+#[cfg(feature = "bracketed-paste")]
+impl From<Paste> for String {
+    fn from(paste: Paste) -> String {
+        paste.content
+    }
+}
+
+/// How a [`Paste`] was recognized.
+#[cfg(feature = "bracketed-paste")]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, PartialOrd, PartialEq, Eq, Clone, Copy, Hash)]
+pub enum PasteKind {
+    /// The terminal marked the paste, see [`EnableBracketedPaste`].
+    Bracketed,
+    /// Plain text arrived faster than anyone types, see [`PasteOptions::infer`].
+    Inferred,
+}
+
+/// How pastes are read, see [`set_paste_options`].
+#[cfg(feature = "bracketed-paste")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct PasteOptions {
+    /// The number of bytes of a paste which are kept, the rest is dropped and the paste is marked
+    /// as [`truncated`](Paste::truncated). No limit if `None`.
+    pub limit: Option<usize>,
+    /// Report a burst of plain text, which arrives with a single read, as a paste even if the
+    /// terminal doesn't support bracketed paste. Single keys are never reported as pastes.
+    pub infer: bool,
+}
+
+#[cfg(feature = "bracketed-paste")]
+static PASTE_OPTIONS: Mutex<PasteOptions> = parking_lot::const_mutex(PasteOptions {
+    limit: None,
+    infer: false,
+});
+
+/// Sets how [`read`] and [`EventStream`](struct.EventStream.html) read pastes.
+///
+/// # Examples
+///
+/// ```no_run
+/// use crossterm::event::{self, PasteOptions};
+///
+/// // keep at most 1 MiB of a paste
+/// event::set_paste_options(PasteOptions {
+///     limit: Some(1 << 20),
+///     ..PasteOptions::default()
+/// });
+/// ```
+#[cfg(feature = "bracketed-paste")]
+pub fn set_paste_options(options: PasteOptions) {
+    *PASTE_OPTIONS.lock() = options;
+}
+
+/// Returns the options set with [`set_paste_options`].
+#[cfg(feature = "bracketed-paste")]
+pub(crate) fn paste_options() -> PasteOptions {
+    *PASTE_OPTIONS.lock()
+}
+
 /// Represents an event.
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[cfg_attr(not(feature = "bracketed-paste"), derive(Copy))]
@@ -516,12 +1288,29 @@ pub enum Event {
     /// A single mouse event with additional pressed modifiers.
     Mouse(MouseEvent),
     /// A string that was pasted into the terminal. Only emitted if bracketed paste has been
-    /// enabled.
+    /// enabled, or if pastes are inferred, see [`PasteOptions`].
     #[cfg(feature = "bracketed-paste")]
-    Paste(String),
+    Paste(Paste),
     /// An resize event with new dimensions after resize (columns, rows).
     /// **Note** that resize events can occur in batches.
     Resize(u16, u16),
+    /// A color the terminal reported, usually in response to a [`QueryColor`].
+    ///
+    /// The color is always a [`Color::Rgb`].
+    ColorReport {
+        /// Which color was reported.
+        slot: ColorSlot,
+        /// The reported color.
+        color: Color,
+    },
+    /// A timer added with [`add_interval`] or [`add_timer`] is due.
+    Tick(TimerId),
+    /// The user clicked the [`Notification`](crate::notification::Notification) with the
+    /// identifier, which asked for the report.
+    NotificationActivated(u32),
+    /// The [`Notification`](crate::notification::Notification) with the identifier, which asked
+    /// for the report, was closed.
+    NotificationClosed(u32),
 }
 
 /// Represents a mouse event.
@@ -674,9 +1463,13 @@ pub enum KeyEventKind {
 bitflags! {
     /// Represents extra state about the key event.
     ///
-    /// **Note:** This state can only be read if
+    /// **Note:** On Unix, this state can only be read if
     /// [`KeyboardEnhancementFlags::DISAMBIGUATE_ESCAPE_CODES`] has been enabled with
-    /// [`PushKeyboardEnhancementFlags`].
+    /// [`PushKeyboardEnhancementFlags`]. The terminal doesn't report `CAPS_LOCK` and `NUM_LOCK`
+    /// for keys which type text then, unless
+    /// [`KeyboardEnhancementFlags::REPORT_ALL_KEYS_AS_ESCAPE_CODES`] is enabled as well. Other
+    /// terminals don't report the state at all. On Windows, `CAPS_LOCK` and `NUM_LOCK` are
+    /// always set.
     #[derive(Debug, PartialOrd, PartialEq, Eq, Clone, Copy, Hash)]
     #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize), serde(transparent))]
     pub struct KeyEventState: u8 {
@@ -689,14 +1482,93 @@ bitflags! {
         /// Num Lock was enabled for this key event.
         ///
         /// **Note:** this is set for the initial press of Num Lock itself.
-        const NUM_LOCK = 0b0000_1000;
+        const NUM_LOCK = 0b0001_0000;
         const NONE = 0b0000_0000;
     }
 }
 
+bitflags! {
+    /// Represents which side of the keyboard the held modifiers are on.
+    ///
+    /// **Note:** The sides can only be read if
+    /// [`KeyboardEnhancementFlags::REPORT_ALL_KEYS_AS_ESCAPE_CODES`] has been enabled with
+    /// [`PushKeyboardEnhancementFlags`], as the terminal reports the modifier keys themselves
+    /// then, or on Windows, which reports the sides of Ctrl and Alt.
+    #[derive(Debug, PartialOrd, PartialEq, Eq, Clone, Copy, Hash)]
+    #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize), serde(transparent))]
+    pub struct ExtendedModifiers: u8 {
+        const LEFT_SHIFT = 0b0000_0001;
+        const RIGHT_SHIFT = 0b0000_0010;
+        const LEFT_CONTROL = 0b0000_0100;
+        const RIGHT_CONTROL = 0b0000_1000;
+        const LEFT_ALT = 0b0001_0000;
+        const RIGHT_ALT = 0b0010_0000;
+        const LEFT_SUPER = 0b0100_0000;
+        const RIGHT_SUPER = 0b1000_0000;
+    }
+}
+
+impl ExtendedModifiers {
+    /// Returns the side of the modifier key, if it's a Shift, Control, Alt or Super key.
+    pub fn from_modifier_key(code: ModifierKeyCode) -> Option<ExtendedModifiers> {
+        match code {
+            ModifierKeyCode::LeftShift => Some(ExtendedModifiers::LEFT_SHIFT),
+            ModifierKeyCode::RightShift => Some(ExtendedModifiers::RIGHT_SHIFT),
+            ModifierKeyCode::LeftControl => Some(ExtendedModifiers::LEFT_CONTROL),
+            ModifierKeyCode::RightControl => Some(ExtendedModifiers::RIGHT_CONTROL),
+            ModifierKeyCode::LeftAlt => Some(ExtendedModifiers::LEFT_ALT),
+            ModifierKeyCode::RightAlt => Some(ExtendedModifiers::RIGHT_ALT),
+            ModifierKeyCode::LeftSuper => Some(ExtendedModifiers::LEFT_SUPER),
+            ModifierKeyCode::RightSuper => Some(ExtendedModifiers::RIGHT_SUPER),
+            _ => None,
+        }
+    }
+
+    /// Returns both sides of each of the modifiers.
+    pub(crate) fn sides_of(modifiers: KeyModifiers) -> ExtendedModifiers {
+        let mut sides = ExtendedModifiers::empty();
+        for (modifier, both) in [
+            (
+                KeyModifiers::SHIFT,
+                ExtendedModifiers::LEFT_SHIFT | ExtendedModifiers::RIGHT_SHIFT,
+            ),
+            (
+                KeyModifiers::CONTROL,
+                ExtendedModifiers::LEFT_CONTROL | ExtendedModifiers::RIGHT_CONTROL,
+            ),
+            (
+                KeyModifiers::ALT,
+                ExtendedModifiers::LEFT_ALT | ExtendedModifiers::RIGHT_ALT,
+            ),
+            (
+                KeyModifiers::SUPER,
+                ExtendedModifiers::LEFT_SUPER | ExtendedModifiers::RIGHT_SUPER,
+            ),
+        ] {
+            if modifiers.contains(modifier) {
+                sides |= both;
+            }
+        }
+        sides
+    }
+}
+
+/// Represents the physical key of a key event, regardless of the keyboard layout, e.g. to bind
+/// the keys at the place of `W`, `A`, `S` and `D` on AZERTY and Dvorak layouts too.
+#[derive(Debug, PartialOrd, PartialEq, Eq, Clone, Copy, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum PhysicalKey {
+    /// The key at the same place on the standard PC-101 US layout, which the kitty protocol
+    /// reports if [`KeyboardEnhancementFlags::REPORT_ALTERNATE_KEYS`] has been enabled with
+    /// [`PushKeyboardEnhancementFlags`] and the key differs from it.
+    BaseLayout(KeyCode),
+    /// The scan code the keyboard sends for the key, which the Windows console reports.
+    ScanCode(u16),
+}
+
 /// Represents a key event.
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
-#[derive(Debug, PartialOrd, Clone, Copy)]
+#[derive(Debug, Clone, Copy)]
 pub struct KeyEvent {
     /// The key itself.
     pub code: KeyCode,
@@ -705,14 +1577,32 @@ pub struct KeyEvent {
     /// Kind of event.
     ///
     /// Only set if:
-    /// - Unix: [`KeyboardEnhancementFlags::REPORT_EVENT_TYPES`] has been enabled with [`PushKeyboardEnhancementFlags`].
+    /// - Unix: [`KeyboardEnhancementFlags::REPORT_EVENT_TYPES`] has been enabled with
+    ///   [`PushKeyboardEnhancementFlags`].
     /// - Windows: always
     pub kind: KeyEventKind,
     /// Keyboard state.
     ///
-    /// Only set if [`KeyboardEnhancementFlags::DISAMBIGUATE_ESCAPE_CODES`] has been enabled with
-    /// [`PushKeyboardEnhancementFlags`].
+    /// Only set if:
+    /// - Unix: [`KeyboardEnhancementFlags::DISAMBIGUATE_ESCAPE_CODES`] has been enabled with
+    ///   [`PushKeyboardEnhancementFlags`], see [`KeyEventState`].
+    /// - Windows: always, except for `KEYPAD`
+    ///
+    /// `CAPS_LOCK` and `NUM_LOCK` aren't compared, nor hashed, so an event read while Caps Lock is
+    /// on still equals the one created with [`KeyEvent::new`].
     pub state: KeyEventState,
+    /// Which side of the keyboard the held Shift, Control, Alt and Super keys are on.
+    ///
+    /// Only set if the terminal reports it, see [`ExtendedModifiers`]. It's not compared, nor
+    /// hashed, so an event still equals the one created with [`KeyEvent::new`].
+    #[cfg_attr(feature = "serde", serde(default = "ExtendedModifiers::empty"))]
+    pub extended_modifiers: ExtendedModifiers,
+    /// The physical key, regardless of the keyboard layout.
+    ///
+    /// Only set if the terminal reports it, see [`PhysicalKey`]. It's not compared, nor hashed,
+    /// like `extended_modifiers`.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub physical_key: Option<PhysicalKey>,
 }
 
 impl KeyEvent {
@@ -722,6 +1612,8 @@ impl KeyEvent {
             modifiers,
             kind: KeyEventKind::Press,
             state: KeyEventState::empty(),
+            extended_modifiers: ExtendedModifiers::empty(),
+            physical_key: None,
         }
     }
 
@@ -735,6 +1627,8 @@ impl KeyEvent {
             modifiers,
             kind,
             state: KeyEventState::empty(),
+            extended_modifiers: ExtendedModifiers::empty(),
+            physical_key: None,
         }
     }
 
@@ -749,9 +1643,27 @@ impl KeyEvent {
             modifiers,
             kind,
             state,
+            extended_modifiers: ExtendedModifiers::empty(),
+            physical_key: None,
         }
     }
 
+    /// Sets which side of the keyboard the held modifiers are on.
+    pub const fn with_extended_modifiers(
+        mut self,
+        extended_modifiers: ExtendedModifiers,
+    ) -> KeyEvent {
+        self.extended_modifiers = extended_modifiers;
+        self
+    }
+
+    // modifies the KeyEvent, so that it's compared without the lock state
+    fn normalize(mut self) -> KeyEvent {
+        self.state
+            .remove(KeyEventState::CAPS_LOCK | KeyEventState::NUM_LOCK);
+        self.normalize_case()
+    }
+
     // modifies the KeyEvent,
     // so that KeyModifiers::SHIFT is present iff
     // an uppercase char is present.
@@ -777,6 +1689,8 @@ impl From<KeyCode> for KeyEvent {
             modifiers: KeyModifiers::empty(),
             kind: KeyEventKind::Press,
             state: KeyEventState::empty(),
+            extended_modifiers: ExtendedModifiers::empty(),
+            physical_key: None,
         }
     }
 }
@@ -788,13 +1702,15 @@ impl PartialEq for KeyEvent {
             modifiers: lhs_modifiers,
             kind: lhs_kind,
             state: lhs_state,
-        } = self.normalize_case();
+            ..
+        } = self.normalize();
         let KeyEvent {
             code: rhs_code,
             modifiers: rhs_modifiers,
             kind: rhs_kind,
             state: rhs_state,
-        } = other.normalize_case();
+            ..
+        } = other.normalize();
         (lhs_code == rhs_code)
             && (lhs_modifiers == rhs_modifiers)
             && (lhs_kind == rhs_kind)
@@ -804,6 +1720,19 @@ impl PartialEq for KeyEvent {
 
 impl Eq for KeyEvent {}
 
+impl PartialOrd for KeyEvent {
+    fn partial_cmp(&self, other: &KeyEvent) -> Option<cmp::Ordering> {
+        let lhs = self.normalize();
+        let rhs = other.normalize();
+        (lhs.code, lhs.modifiers, lhs.kind, lhs.state).partial_cmp(&(
+            rhs.code,
+            rhs.modifiers,
+            rhs.kind,
+            rhs.state,
+        ))
+    }
+}
+
 impl Hash for KeyEvent {
     fn hash<H: Hasher>(&self, hash_state: &mut H) {
         let KeyEvent {
@@ -811,7 +1740,8 @@ impl Hash for KeyEvent {
             modifiers,
             kind,
             state,
-        } = self.normalize_case();
+            ..
+        } = self.normalize();
         code.hash(hash_state);
         modifiers.hash(hash_state);
         kind.hash(hash_state);
@@ -920,7 +1850,6 @@ impl Display for ModifierKeyCode {
     ///
     /// On other platforms, the super key is referred to as "Super".
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        #[cfg(target_os = "macos")]
         match self {
             ModifierKeyCode::LeftShift => write!(f, "Left Shift"),
             ModifierKeyCode::LeftHyper => write!(f, "Left Hyper"),
@@ -1138,14 +2067,25 @@ pub(crate) enum InternalEvent {
     /// An event.
     Event(Event),
     /// A cursor position (`col`, `row`).
-    #[cfg(unix)]
     CursorPosition(u16, u16),
     /// The progressive keyboard enhancement flags enabled by the terminal.
-    #[cfg(unix)]
     KeyboardEnhancementFlags(KeyboardEnhancementFlags),
     /// Attributes and architectural class of the terminal.
-    #[cfg(unix)]
-    PrimaryDeviceAttributes,
+    PrimaryDeviceAttributes(Vec<u16>),
+    /// The type, version and further values which identify the terminal.
+    SecondaryDeviceAttributes(Vec<u16>),
+    /// The size of a cell in pixels (`width`, `height`).
+    CellSize(u16, u16),
+    /// The response to a DECRQSS request, `None` if the terminal didn't understand it.
+    SettingReport(Option<String>),
+    /// The name and version of the terminal, the response to XTVERSION.
+    TerminalVersion(String),
+    /// The response to a DECRQM request, a DEC private mode and its setting: `0` if the mode
+    /// isn't recognized, `1` if it's set, `2` if it's reset, `3` if it's permanently set and `4`
+    /// if it's permanently reset.
+    ModeReport(u16, u8),
+    /// An `OSC` or `DCS` sequence with a handler, which is called instead of reporting it.
+    ControlString(ControlString),
 }
 
 #[cfg(test)]
@@ -1287,4 +2227,217 @@ mod tests {
         assert_eq!(format!("{}", Modifier(RightAlt)), "Right Alt");
         assert_eq!(format!("{}", Modifier(RightSuper)), "Right Super");
     }
+
+    #[test]
+    fn test_keyboard_flag_stacks() {
+        let mut stacks = KeyboardFlagStacks::new();
+        let mut output = String::new();
+        stacks.switch_screen(&mut output, true).unwrap();
+        stacks.switch_screen(&mut output, false).unwrap();
+        assert_eq!(output, "", "nothing to re-apply without flags");
+
+        let flags = KeyboardEnhancementFlags::DISAMBIGUATE_ESCAPE_CODES
+            | KeyboardEnhancementFlags::REPORT_EVENT_TYPES;
+        stacks.current_mut().push(flags);
+        assert_eq!(stacks.current(), flags);
+
+        stacks.switch_screen(&mut output, true).unwrap();
+        assert_eq!(stacks.current(), KeyboardEnhancementFlags::empty());
+        stacks
+            .current_mut()
+            .push(KeyboardEnhancementFlags::DISAMBIGUATE_ESCAPE_CODES);
+        stacks.switch_screen(&mut output, true).unwrap();
+        stacks.switch_screen(&mut output, false).unwrap();
+        assert_eq!(stacks.current(), flags);
+        assert_eq!(output, "\x1B[=0;1u\x1B[=3;1u");
+
+        stacks.current_mut().pop();
+        assert_eq!(stacks.current(), KeyboardEnhancementFlags::empty());
+    }
+
+    #[test]
+    #[serial_test::serial]
+    fn test_mouse_capture_is_tracked() {
+        use crate::{command::with_tracking, Command};
+
+        // only rendered, not executed on the terminal
+        EnableMouseCapture.ansi_string();
+        assert!(!is_mouse_capture_enabled());
+
+        with_tracking(true, || EnableMouseCapture.ansi_string());
+        assert!(is_mouse_capture_enabled());
+        with_tracking(true, || DisableMouseCapture.ansi_string());
+        assert!(!is_mouse_capture_enabled());
+    }
+
+    #[test]
+    #[serial_test::serial]
+    fn test_mouse_capture_is_disabled_with_the_enabled_encodings() {
+        use crate::{command::with_tracking, Command};
+
+        with_tracking(true, || EnableMouseCapture.ansi_string());
+        let (_, capabilities) = MOUSE_CAPTURE_OPTIONS.lock().clone().unwrap();
+        assert_eq!(capabilities, crate::capabilities::current());
+
+        // the capabilities changed after the mouse was captured
+        MOUSE_CAPTURE_OPTIONS.lock().as_mut().unwrap().1 = Capabilities {
+            urxvt_mouse: false,
+            ..Capabilities::default()
+        };
+        assert_eq!(mouse_encoding(), Some(MouseEncoding::Sgr));
+        let disable = with_tracking(true, || DisableMouseCapture.ansi_string());
+        assert_eq!(disable, "\x1B[?1006l\x1B[?1003l\x1B[?1002l\x1B[?1000l");
+        assert_eq!(mouse_encoding(), None);
+    }
+
+    #[test]
+    fn test_keyboard_flag_stacks_restore() {
+        let mut stacks = KeyboardFlagStacks::new();
+        stacks
+            .current_mut()
+            .push(KeyboardEnhancementFlags::DISAMBIGUATE_ESCAPE_CODES);
+        let saved = stacks.clone();
+
+        let mut output = String::new();
+        stacks.restore(&mut output, &saved).unwrap();
+        assert_eq!(output, "", "nothing changed");
+
+        stacks
+            .current_mut()
+            .push(KeyboardEnhancementFlags::REPORT_EVENT_TYPES);
+        stacks.restore(&mut output, &saved).unwrap();
+        assert_eq!(output, "\x1B[<2u\x1B[>1u");
+        assert_eq!(stacks, saved);
+    }
+
+    #[test]
+    fn test_set_pointer_shape() {
+        assert_eq!(
+            SetPointerShape(PointerShape::Pointer).ansi_string(),
+            "\x1B]22;pointer\x1B\\"
+        );
+        assert_eq!(
+            SetPointerShape(PointerShape::NotAllowed).ansi_string(),
+            "\x1B]22;not-allowed\x1B\\"
+        );
+    }
+
+    #[test]
+    fn test_mouse_capture_encodings() {
+        let options = MouseCaptureOptions::default();
+        let mut all = String::new();
+        write_enable_mouse_capture(&mut all, options, &Capabilities::default()).unwrap();
+        assert_eq!(
+            all,
+            "\x1B[?1000h\x1B[?1002h\x1B[?1003h\x1B[?1015h\x1B[?1006h"
+        );
+
+        let sgr_only = Capabilities {
+            urxvt_mouse: false,
+            ..Capabilities::default()
+        };
+        let mut enable = String::new();
+        write_enable_mouse_capture(&mut enable, options, &sgr_only).unwrap();
+        assert_eq!(enable, "\x1B[?1000h\x1B[?1002h\x1B[?1003h\x1B[?1006h");
+        let mut disable = String::new();
+        write_disable_mouse_capture(&mut disable, options, &sgr_only).unwrap();
+        assert_eq!(disable, "\x1B[?1006l\x1B[?1003l\x1B[?1002l\x1B[?1000l");
+        assert_eq!(
+            options.chosen_encoding(&Capabilities::default()),
+            MouseEncoding::Sgr
+        );
+    }
+
+    #[test]
+    fn test_mouse_capture_with_a_single_encoding() {
+        let utf8 = MouseCaptureOptions::new().with_encoding(MouseEncoding::Utf8);
+        let mut enable = String::new();
+        write_enable_mouse_capture(&mut enable, utf8, &Capabilities::default()).unwrap();
+        assert_eq!(enable, "\x1B[?1000h\x1B[?1002h\x1B[?1003h\x1B[?1005h");
+        assert_eq!(
+            utf8.chosen_encoding(&Capabilities::default()),
+            MouseEncoding::Utf8
+        );
+
+        let x10 = MouseCaptureOptions::new().with_encoding(MouseEncoding::X10);
+        let mut disable = String::new();
+        write_disable_mouse_capture(&mut disable, x10, &Capabilities::default()).unwrap();
+        assert_eq!(disable, "\x1B[?1003l\x1B[?1002l\x1B[?1000l");
+    }
+
+    #[test]
+    fn test_mouse_capture_tracking() {
+        let capabilities = Capabilities {
+            urxvt_mouse: false,
+            ..Capabilities::default()
+        };
+
+        let mut buttons = String::new();
+        write_enable_mouse_capture(
+            &mut buttons,
+            EnableMouseCapture::buttons_only().0,
+            &capabilities,
+        )
+        .unwrap();
+        assert_eq!(buttons, "\x1B[?1000h\x1B[?1006h");
+
+        let mut drag = String::new();
+        write_disable_mouse_capture(&mut drag, EnableMouseCapture::with_drag().0, &capabilities)
+            .unwrap();
+        assert_eq!(drag, "\x1B[?1006l\x1B[?1002l\x1B[?1000l");
+    }
+
+    #[test]
+    fn test_extended_modifiers() {
+        assert_eq!(
+            ExtendedModifiers::from_modifier_key(RightControl),
+            Some(ExtendedModifiers::RIGHT_CONTROL)
+        );
+        assert_eq!(ExtendedModifiers::from_modifier_key(LeftHyper), None);
+        assert_eq!(
+            ExtendedModifiers::sides_of(KeyModifiers::SHIFT | KeyModifiers::HYPER),
+            ExtendedModifiers::LEFT_SHIFT | ExtendedModifiers::RIGHT_SHIFT
+        );
+
+        // the sides don't change the equality
+        let key = KeyEvent::new(KeyCode::Char('a'), KeyModifiers::ALT);
+        assert_eq!(
+            key.with_extended_modifiers(ExtendedModifiers::RIGHT_ALT),
+            key
+        );
+        assert_eq!(
+            key.with_extended_modifiers(ExtendedModifiers::RIGHT_ALT)
+                .partial_cmp(&key),
+            Some(std::cmp::Ordering::Equal)
+        );
+    }
+
+    #[test]
+    fn test_lock_state_equality() {
+        let key = KeyEvent::new(KeyCode::Char('a'), KeyModifiers::NONE);
+        let locked = KeyEvent::new_with_kind_and_state(
+            KeyCode::Char('a'),
+            KeyModifiers::NONE,
+            KeyEventKind::Press,
+            KeyEventState::CAPS_LOCK | KeyEventState::NUM_LOCK,
+        );
+        assert_eq!(locked, key);
+
+        let hash = |key: KeyEvent| {
+            let mut hasher = DefaultHasher::new();
+            key.hash(&mut hasher);
+            hasher.finish()
+        };
+        assert_eq!(hash(locked), hash(key));
+        assert_eq!(locked.partial_cmp(&key), Some(std::cmp::Ordering::Equal));
+
+        // the keypad is still told apart
+        let keypad = KeyEvent::new_with_kind_and_state(
+            KeyCode::Char('a'),
+            KeyModifiers::NONE,
+            KeyEventKind::Press,
+            KeyEventState::KEYPAD | KeyEventState::NUM_LOCK,
+        );
+        assert_ne!(keypad, key);
+    }
 }
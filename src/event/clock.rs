@@ -0,0 +1,115 @@
+//! # Clock
+//!
+//! The `clock` module provides the time source the event polling machinery measures timeouts
+//! with. By default it's the system clock, tests can install a [`ManualClock`](struct.ManualClock.html)
+//! with [`set_clock`](fn.set_clock.html) to control exactly when a timeout of
+//! [`poll`](../fn.poll.html) elapses.
+//!
+//! ```no_run
+//! use std::time::Duration;
+//! use crossterm::event::clock::{self, ManualClock};
+//!
+//! let clock = ManualClock::new();
+//! clock::set_clock(clock.clone());
+//!
+//! // Another thread advances the clock, which elapses the timeout of pending polls.
+//! clock.advance(Duration::from_millis(500));
+//!
+//! clock::reset_clock();
+//! ```
+//!
+//! The clock only decides when a timeout has elapsed. While a clock is installed, the reader
+//! waits for input in real time for a few milliseconds at a time and checks the clock in between,
+//! so a poll returns soon after the clock was advanced past its timeout. With a stopped
+//! `ManualClock`, a poll with a timeout waits until an event is read or the clock is advanced.
+
+use std::{
+    fmt,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use parking_lot::{Mutex, RwLock};
+
+/// A source of the current time.
+pub trait Clock: Send + Sync {
+    /// Returns the current time.
+    fn now(&self) -> Instant;
+}
+
+/// The system clock, which is used by default.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+}
+
+/// A clock which only advances when told so.
+///
+/// Clones share the same time, so one clone can be installed with
+/// [`set_clock`](fn.set_clock.html) while the other one is advanced.
+#[derive(Clone)]
+pub struct ManualClock {
+    now: Arc<Mutex<Instant>>,
+}
+
+impl ManualClock {
+    /// Creates a clock which is stopped at the current time.
+    pub fn new() -> ManualClock {
+        ManualClock {
+            now: Arc::new(Mutex::new(Instant::now())),
+        }
+    }
+
+    /// Advances the clock by `duration`.
+    pub fn advance(&self, duration: Duration) {
+        *self.now.lock() += duration;
+    }
+}
+
+impl Default for ManualClock {
+    fn default() -> Self {
+        ManualClock::new()
+    }
+}
+
+impl fmt::Debug for ManualClock {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ManualClock")
+            .field("now", &*self.now.lock())
+            .finish()
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> Instant {
+        *self.now.lock()
+    }
+}
+
+static CLOCK: RwLock<Option<Arc<dyn Clock>>> = parking_lot::const_rwlock(None);
+
+/// Installs the clock poll timeouts are measured with from now on.
+///
+/// Polls that are already waiting keep using the previous clock.
+pub fn set_clock(clock: impl Clock + 'static) {
+    *CLOCK.write() = Some(Arc::new(clock));
+}
+
+/// Installs the [`SystemClock`](struct.SystemClock.html) again.
+pub fn reset_clock() {
+    *CLOCK.write() = None;
+}
+
+/// Returns the installed clock, `None` means the system clock.
+pub(crate) fn current() -> Option<Arc<dyn Clock>> {
+    CLOCK.read().clone()
+}
+
+/// Returns the current time according to the installed clock.
+pub(crate) fn now() -> Instant {
+    current().map_or_else(Instant::now, |clock| clock.now())
+}
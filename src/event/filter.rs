@@ -1,7 +1,19 @@
+//! Filters for the internal events.
+//!
+//! The filters of the queries a terminal may not understand let the `PrimaryDeviceAttributes`
+//! response through as well. Every terminal answers the primary device attributes query which
+//! follows such a query, so if its response arrives first, the terminal didn't understand the
+//! query.
+
 use crate::event::InternalEvent;
 
 /// Interface for filtering an `InternalEvent`.
 pub(crate) trait Filter: Send + Sync + 'static {
+    /// Whether the filter is the one of [`poll`](../fn.poll.html) and [`read`](../fn.read.html),
+    /// which read a playing script instead of the terminal, as well as the replayed events and
+    /// the ticks of the timers.
+    const EVENTS: bool = false;
+
     /// Returns whether the given event fulfills the filter.
     fn eval(&self, event: &InternalEvent) -> bool;
 }
@@ -24,13 +36,9 @@ pub(crate) struct KeyboardEnhancementFlagsFilter;
 #[cfg(unix)]
 impl Filter for KeyboardEnhancementFlagsFilter {
     fn eval(&self, event: &InternalEvent) -> bool {
-        // This filter checks for either a KeyboardEnhancementFlags response or
-        // a PrimaryDeviceAttributes response. If we receive the PrimaryDeviceAttributes
-        // response but not KeyboardEnhancementFlags, the terminal does not support
-        // progressive keyboard enhancement.
         matches!(
             *event,
-            InternalEvent::KeyboardEnhancementFlags(_) | InternalEvent::PrimaryDeviceAttributes
+            InternalEvent::KeyboardEnhancementFlags(_) | InternalEvent::PrimaryDeviceAttributes(_)
         )
     }
 }
@@ -42,7 +50,77 @@ pub(crate) struct PrimaryDeviceAttributesFilter;
 #[cfg(unix)]
 impl Filter for PrimaryDeviceAttributesFilter {
     fn eval(&self, event: &InternalEvent) -> bool {
-        matches!(*event, InternalEvent::PrimaryDeviceAttributes)
+        matches!(*event, InternalEvent::PrimaryDeviceAttributes(_))
+    }
+}
+
+#[cfg(unix)]
+#[derive(Debug, Clone)]
+pub(crate) struct SecondaryDeviceAttributesFilter;
+
+#[cfg(unix)]
+impl Filter for SecondaryDeviceAttributesFilter {
+    fn eval(&self, event: &InternalEvent) -> bool {
+        matches!(
+            *event,
+            InternalEvent::SecondaryDeviceAttributes(_) | InternalEvent::PrimaryDeviceAttributes(_)
+        )
+    }
+}
+
+#[cfg(unix)]
+#[derive(Debug, Clone)]
+pub(crate) struct CellSizeFilter;
+
+#[cfg(unix)]
+impl Filter for CellSizeFilter {
+    fn eval(&self, event: &InternalEvent) -> bool {
+        matches!(
+            *event,
+            InternalEvent::CellSize(_, _) | InternalEvent::PrimaryDeviceAttributes(_)
+        )
+    }
+}
+
+#[cfg(unix)]
+#[derive(Debug, Clone)]
+pub(crate) struct SettingReportFilter;
+
+#[cfg(unix)]
+impl Filter for SettingReportFilter {
+    fn eval(&self, event: &InternalEvent) -> bool {
+        matches!(
+            *event,
+            InternalEvent::SettingReport(_) | InternalEvent::PrimaryDeviceAttributes(_)
+        )
+    }
+}
+
+#[cfg(unix)]
+#[derive(Debug, Clone)]
+pub(crate) struct TerminalVersionFilter;
+
+#[cfg(unix)]
+impl Filter for TerminalVersionFilter {
+    fn eval(&self, event: &InternalEvent) -> bool {
+        matches!(
+            *event,
+            InternalEvent::TerminalVersion(_) | InternalEvent::PrimaryDeviceAttributes(_)
+        )
+    }
+}
+
+#[cfg(unix)]
+#[derive(Debug, Clone)]
+pub(crate) struct ModeReportFilter;
+
+#[cfg(unix)]
+impl Filter for ModeReportFilter {
+    fn eval(&self, event: &InternalEvent) -> bool {
+        matches!(
+            *event,
+            InternalEvent::ModeReport(_, _) | InternalEvent::PrimaryDeviceAttributes(_)
+        )
     }
 }
 
@@ -50,6 +128,8 @@ impl Filter for PrimaryDeviceAttributesFilter {
 pub(crate) struct EventFilter;
 
 impl Filter for EventFilter {
+    const EVENTS: bool = true;
+
     #[cfg(unix)]
     fn eval(&self, event: &InternalEvent) -> bool {
         matches!(*event, InternalEvent::Event(_))
@@ -74,7 +154,7 @@ impl Filter for InternalEventFilter {
 #[cfg(unix)]
 mod tests {
     use super::{
-        super::Event, CursorPositionFilter, EventFilter, Filter, InternalEvent,
+        super::Event, CellSizeFilter, CursorPositionFilter, EventFilter, Filter, InternalEvent,
         InternalEventFilter, KeyboardEnhancementFlagsFilter, PrimaryDeviceAttributesFilter,
     };
 
@@ -92,13 +172,22 @@ mod tests {
                 crate::event::KeyboardEnhancementFlags::DISAMBIGUATE_ESCAPE_CODES
             ))
         );
-        assert!(KeyboardEnhancementFlagsFilter.eval(&InternalEvent::PrimaryDeviceAttributes));
+        assert!(
+            KeyboardEnhancementFlagsFilter.eval(&InternalEvent::PrimaryDeviceAttributes(vec![]))
+        );
     }
 
     #[test]
     fn test_primary_device_attributes_filter_filters_primary_device_attributes() {
         assert!(!PrimaryDeviceAttributesFilter.eval(&InternalEvent::Event(Event::Resize(10, 10))));
-        assert!(PrimaryDeviceAttributesFilter.eval(&InternalEvent::PrimaryDeviceAttributes));
+        assert!(PrimaryDeviceAttributesFilter.eval(&InternalEvent::PrimaryDeviceAttributes(vec![])));
+    }
+
+    #[test]
+    fn test_cell_size_filter_filters_cell_size() {
+        assert!(!CellSizeFilter.eval(&InternalEvent::Event(Event::Resize(10, 10))));
+        assert!(CellSizeFilter.eval(&InternalEvent::CellSize(8, 16)));
+        assert!(CellSizeFilter.eval(&InternalEvent::PrimaryDeviceAttributes(vec![])));
     }
 
     #[test]
@@ -0,0 +1,286 @@
+use std::{fmt, sync::Arc};
+
+use parking_lot::RwLock;
+
+/// An `OSC` or `DCS` sequence the terminal sent, which is passed to the handler added with
+/// [`add_osc_handler`](fn.add_osc_handler.html) or [`add_dcs_handler`](fn.add_dcs_handler.html).
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ControlString {
+    /// An operating system command, `OSC number ; payload ST`.
+    Osc {
+        /// The number of the command.
+        number: u16,
+        /// The bytes after the `;`, empty if there's none.
+        payload: Vec<u8>,
+    },
+    /// A device control string, `DCS prefix payload ST`.
+    Dcs {
+        /// The prefix of the handler.
+        prefix: Vec<u8>,
+        /// The bytes after the prefix.
+        payload: Vec<u8>,
+    },
+}
+
+pub(crate) type Handler = Arc<dyn Fn(ControlString) + Send + Sync>;
+
+/// Which sequences a handler is added for.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Introducer {
+    Osc(u16),
+    Dcs(Vec<u8>),
+}
+
+/// The handlers of the `OSC` and `DCS` sequences, the global ones or the ones of a
+/// [`Parser`](parse/struct.Parser.html).
+#[derive(Clone, Default)]
+pub(crate) struct Handlers(Vec<(Introducer, Handler)>);
+
+impl Handlers {
+    /// Creates an empty set of handlers.
+    pub(crate) const fn new() -> Handlers {
+        Handlers(Vec::new())
+    }
+
+    fn add(&mut self, introducer: Introducer, handler: Handler) {
+        self.0.retain(|(added, _)| *added != introducer);
+        self.0.push((introducer, handler));
+    }
+
+    pub(crate) fn add_osc(&mut self, number: u16, handler: Handler) {
+        self.add(Introducer::Osc(number), handler);
+    }
+
+    pub(crate) fn add_dcs(&mut self, prefix: Vec<u8>, handler: Handler) {
+        self.add(Introducer::Dcs(prefix), handler);
+    }
+
+    fn remove(&mut self, introducer: Introducer) -> bool {
+        let len = self.0.len();
+        self.0.retain(|(added, _)| *added != introducer);
+        self.0.len() != len
+    }
+
+    /// Returns whether a handler was added for the `OSC number` sequences.
+    pub(crate) fn has_osc(&self, number: u16) -> bool {
+        self.0
+            .iter()
+            .any(|(introducer, _)| *introducer == Introducer::Osc(number))
+    }
+
+    /// Returns whether a handler was added for an `OSC` number which starts with the digits.
+    pub(crate) fn has_osc_starting_with(&self, digits: &[u8]) -> bool {
+        self.0.iter().any(|(introducer, _)| match introducer {
+            Introducer::Osc(number) => number.to_string().as_bytes().starts_with(digits),
+            Introducer::Dcs(_) => false,
+        })
+    }
+
+    /// Matches the bytes after `ESC P` with the prefixes of the `DCS` handlers.
+    pub(crate) fn match_dcs_prefix(&self, bytes: &[u8]) -> Option<PrefixMatch> {
+        let mut partial = false;
+        for (introducer, _) in self.0.iter() {
+            if let Introducer::Dcs(prefix) = introducer {
+                if bytes.starts_with(prefix) {
+                    return Some(PrefixMatch::Complete(prefix.len()));
+                }
+                partial |= prefix.starts_with(bytes);
+            }
+        }
+        if partial {
+            Some(PrefixMatch::Partial)
+        } else {
+            None
+        }
+    }
+
+    /// Returns the handler of the sequence.
+    pub(crate) fn find(&self, sequence: &ControlString) -> Option<Handler> {
+        self.0
+            .iter()
+            .find(|(introducer, _)| match (introducer, sequence) {
+                (Introducer::Osc(number), ControlString::Osc { number: sent, .. }) => {
+                    number == sent
+                }
+                (Introducer::Dcs(prefix), ControlString::Dcs { prefix: sent, .. }) => {
+                    prefix == sent
+                }
+                _ => false,
+            })
+            .map(|(_, handler)| handler.clone())
+    }
+}
+
+impl fmt::Debug for Handlers {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list()
+            .entries(self.0.iter().map(|(introducer, _)| introducer))
+            .finish()
+    }
+}
+
+/// The handlers added with [`add_osc_handler`] and [`add_dcs_handler`].
+static HANDLERS: RwLock<Handlers> = parking_lot::const_rwlock(Handlers::new());
+
+/// Calls `handler` with the `OSC number` sequences the terminal sends, e.g. the responses to the
+/// custom sequences of iTerm2, instead of discarding them. A handler which was added for the
+/// number before is replaced.
+///
+/// The handler is called while the input is parsed, on the thread which reads the events, so it
+/// should return quickly and must not read events itself. A channel is a good way to pass the
+/// sequences on.
+///
+/// The handlers take precedence over the sequences crossterm understands itself, so a handler
+/// for `OSC 10` takes the [`Event::ColorReport`](enum.Event.html#variant.ColorReport)s of
+/// [`QueryColor`](struct.QueryColor.html). Sequences longer than 256 bytes are discarded.
+///
+/// A [`Parser`](parse/struct.Parser.html) has its own handlers, see
+/// [`Parser::add_osc_handler`](parse/struct.Parser.html#method.add_osc_handler).
+///
+/// # Examples
+///
+/// ```no_run
+/// use std::sync::mpsc;
+/// use crossterm::event::{self, ControlString};
+///
+/// let (sender, receiver) = mpsc::channel();
+/// event::add_osc_handler(1337, move |sequence: ControlString| {
+///     let _ = sender.send(sequence);
+/// });
+/// ```
+pub fn add_osc_handler(number: u16, handler: impl Fn(ControlString) + Send + Sync + 'static) {
+    HANDLERS.write().add_osc(number, Arc::new(handler));
+}
+
+/// Removes the handler added with [`add_osc_handler`](fn.add_osc_handler.html), returns whether
+/// there was one.
+pub fn remove_osc_handler(number: u16) -> bool {
+    HANDLERS.write().remove(Introducer::Osc(number))
+}
+
+/// Calls `handler` with the `DCS` sequences which start with `prefix`, the bytes after `ESC P`,
+/// instead of discarding them. A handler which was added for the prefix before is replaced.
+///
+/// The handler is called like the ones of [`add_osc_handler`](fn.add_osc_handler.html), and
+/// takes precedence over the `DCS 0 $ r` and `DCS 1 $ r` responses crossterm understands itself
+/// if the prefix matches them.
+///
+/// # Examples
+///
+/// ```no_run
+/// use crossterm::event::{self, ControlString};
+///
+/// // the response to XTVERSION
+/// event::add_dcs_handler(">|", |sequence: ControlString| {
+///     if let ControlString::Dcs { payload, .. } = sequence {
+///         eprintln!("{}", String::from_utf8_lossy(&payload));
+///     }
+/// });
+/// ```
+pub fn add_dcs_handler(
+    prefix: impl Into<Vec<u8>>,
+    handler: impl Fn(ControlString) + Send + Sync + 'static,
+) {
+    HANDLERS.write().add_dcs(prefix.into(), Arc::new(handler));
+}
+
+/// Removes the handler added with [`add_dcs_handler`](fn.add_dcs_handler.html), returns whether
+/// there was one.
+pub fn remove_dcs_handler(prefix: &[u8]) -> bool {
+    HANDLERS.write().remove(Introducer::Dcs(prefix.to_vec()))
+}
+
+/// Calls `f` with the global handlers.
+pub(crate) fn with_global<T>(f: impl FnOnce(&Handlers) -> T) -> T {
+    f(&HANDLERS.read())
+}
+
+/// How the bytes after `ESC P` match the prefixes of the handlers.
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) enum PrefixMatch {
+    /// The bytes start with a prefix of this length.
+    Complete(usize),
+    /// The bytes are the start of a prefix.
+    Partial,
+}
+
+/// Passes the sequence to its handler, if there's still one.
+pub(crate) fn dispatch(handlers: Option<&Handlers>, sequence: ControlString) {
+    let handler = match handlers {
+        Some(handlers) => handlers.find(&sequence),
+        None => HANDLERS.read().find(&sequence),
+    };
+    // called without the lock, so the handler can add and remove handlers
+    if let Some(handler) = handler {
+        handler(sequence);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use crate::event::{parse::Parser, Event, KeyCode};
+
+    use super::{
+        add_dcs_handler, add_osc_handler, remove_dcs_handler, remove_osc_handler, ControlString,
+    };
+
+    #[test]
+    fn test_control_string_handlers() {
+        let received = Arc::new(Mutex::new(Vec::new()));
+        let osc = received.clone();
+        add_osc_handler(7331, move |sequence| osc.lock().unwrap().push(sequence));
+        let dcs = received.clone();
+        add_dcs_handler(">|", move |sequence| dcs.lock().unwrap().push(sequence));
+
+        let mut parser = Parser::for_terminal();
+        parser.advance(b"\x1B]7331;foo\x07a\x1BP>", false);
+        parser.advance(b"|bar\x1B\\\x1B]7331\x1B\\", false);
+        assert_eq!(parser.next(), Some(Event::Key(KeyCode::Char('a').into())));
+        assert_eq!(parser.next(), None);
+        assert_eq!(
+            *received.lock().unwrap(),
+            vec![
+                ControlString::Osc {
+                    number: 7331,
+                    payload: b"foo".to_vec()
+                },
+                ControlString::Dcs {
+                    prefix: b">|".to_vec(),
+                    payload: b"bar".to_vec()
+                },
+                ControlString::Osc {
+                    number: 7331,
+                    payload: Vec::new()
+                },
+            ]
+        );
+
+        assert!(remove_osc_handler(7331));
+        assert!(remove_dcs_handler(b">|"));
+        assert!(!remove_osc_handler(7331));
+    }
+
+    #[test]
+    fn test_parser_control_string_handlers() {
+        let received = Arc::new(Mutex::new(Vec::new()));
+        add_osc_handler(7332, |_| panic!("the global handler was called"));
+
+        // a parser only calls its own handlers
+        let mut parser = Parser::new();
+        let osc = received.clone();
+        parser.add_osc_handler(7332, move |sequence| osc.lock().unwrap().push(sequence));
+        parser.advance(b"\x1B]7332;foo\x1B\\", false);
+        assert_eq!(parser.next(), None);
+        assert_eq!(
+            *received.lock().unwrap(),
+            vec![ControlString::Osc {
+                number: 7332,
+                payload: b"foo".to_vec()
+            }]
+        );
+
+        assert!(remove_osc_handler(7332));
+    }
+}
@@ -0,0 +1,136 @@
+use parking_lot::RwLock;
+
+use super::KeyEvent;
+
+/// Sequences and the keys they are reported as, the global ones or the ones of a
+/// [`Parser`](parse/struct.Parser.html).
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Keymap(Vec<(Vec<u8>, KeyEvent)>);
+
+impl Keymap {
+    /// Creates an empty keymap.
+    pub(crate) const fn new() -> Keymap {
+        Keymap(Vec::new())
+    }
+
+    pub(crate) fn add(&mut self, sequence: Vec<u8>, key: KeyEvent) {
+        self.0.retain(|(added, _)| *added != sequence);
+        self.0.push((sequence, key));
+    }
+
+    fn remove(&mut self, sequence: &[u8]) -> bool {
+        let len = self.0.len();
+        self.0.retain(|(added, _)| added != sequence);
+        self.0.len() != len
+    }
+
+    /// Looks up the input in the sequences.
+    pub(crate) fn lookup(&self, input: &[u8]) -> Option<KeymapMatch> {
+        let mut partial = false;
+        for (sequence, key) in self.0.iter() {
+            if sequence == input {
+                return Some(KeymapMatch::Key(*key));
+            }
+            partial |= sequence.starts_with(input);
+        }
+        if partial {
+            Some(KeymapMatch::Partial)
+        } else {
+            None
+        }
+    }
+}
+
+/// The sequences added with [`add_key_sequence`](fn.add_key_sequence.html).
+static KEYMAP: RwLock<Keymap> = parking_lot::const_rwlock(Keymap::new());
+
+/// Reports `key` when the terminal sends `sequence`, e.g. a private sequence of an odd terminal
+/// or of a keyboard with extra keys.
+///
+/// The sequences are consulted when crossterm can't parse the input, before it's discarded, so
+/// they can't replace the known keys. A sequence is reported as soon as it's complete, even if
+/// the parser waits for more of an escape sequence. A sequence which was added before is
+/// replaced.
+///
+/// This applies to the events crossterm reads from escape sequences on Unix, but not to the key
+/// events of the Windows console. A [`Parser`](parse/struct.Parser.html) has its own sequences,
+/// see [`Parser::add_key_sequence`](parse/struct.Parser.html#method.add_key_sequence).
+///
+/// # Examples
+///
+/// ```no_run
+/// use crossterm::event::{self, KeyCode, KeyModifiers, KeyEvent};
+///
+/// // a terminal which sends `CSI 200 z` for a macro key
+/// event::add_key_sequence(b"\x1B[200z", KeyEvent::new(KeyCode::F(13), KeyModifiers::NONE));
+/// ```
+pub fn add_key_sequence(sequence: impl Into<Vec<u8>>, key: KeyEvent) {
+    KEYMAP.write().add(sequence.into(), key);
+}
+
+/// Removes a sequence added with [`add_key_sequence`](fn.add_key_sequence.html), returns whether
+/// it was added.
+pub fn remove_key_sequence(sequence: &[u8]) -> bool {
+    KEYMAP.write().remove(sequence)
+}
+
+/// How the input matches the added sequences.
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) enum KeymapMatch {
+    /// The input is a complete sequence.
+    Key(KeyEvent),
+    /// The input is the start of a sequence.
+    Partial,
+}
+
+/// Looks up the input in the sequences added with
+/// [`add_key_sequence`](fn.add_key_sequence.html).
+pub(crate) fn lookup(input: &[u8]) -> Option<KeymapMatch> {
+    KEYMAP.read().lookup(input)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::event::{parse::Parser, Event, KeyCode, KeyEvent, KeyModifiers};
+
+    use super::{add_key_sequence, remove_key_sequence};
+
+    #[test]
+    fn test_key_sequences() {
+        let key = KeyEvent::new(KeyCode::F(13), KeyModifiers::SHIFT);
+        add_key_sequence(b"\x1B[201z".to_vec(), key);
+
+        let mut parser = Parser::for_terminal();
+        parser.advance(b"\x1B[20", false);
+        parser.advance(b"1za", false);
+        assert_eq!(parser.next(), Some(Event::Key(key)));
+        assert_eq!(parser.next(), Some(Event::Key(KeyCode::Char('a').into())));
+
+        // the known keys are kept
+        add_key_sequence(b"\x1B[A".to_vec(), key);
+        parser.advance(b"\x1B[A", false);
+        assert_eq!(parser.next(), Some(Event::Key(KeyCode::Up.into())));
+
+        assert!(remove_key_sequence(b"\x1B[201z"));
+        assert!(remove_key_sequence(b"\x1B[A"));
+        assert!(!remove_key_sequence(b"\x1B[201z"));
+
+        parser.advance(b"\x1B[201z", false);
+        assert_eq!(parser.next(), None);
+    }
+
+    #[test]
+    fn test_parser_key_sequences() {
+        let key = KeyEvent::new(KeyCode::F(14), KeyModifiers::NONE);
+        add_key_sequence(b"\x1B[202z".to_vec(), key);
+
+        // a parser only consults its own sequences
+        let mut parser = Parser::new();
+        parser.add_key_sequence(b"\x1B[203z".to_vec(), key);
+        parser.advance(b"\x1B[202z\x1B[203z", false);
+        assert_eq!(parser.next(), Some(Event::Key(key)));
+        assert_eq!(parser.next(), None);
+
+        assert!(remove_key_sequence(b"\x1B[202z"));
+    }
+}
@@ -0,0 +1,3110 @@
+//! # Parse
+//!
+//! The `parse` module exposes the parser crossterm uses to turn the bytes a terminal sends into
+//! [`Event`](../enum.Event.html)s.
+//!
+//! The [`Parser`](struct.Parser.html) doesn't need a terminal: bytes are pushed in as they arrive,
+//! from a tty, a socket or anything else, and events are pulled out. This makes it useful for
+//! terminal multiplexers and SSH servers which receive the input of a remote terminal.
+//!
+//! ```
+//! use crossterm::event::{parse::Parser, Event, KeyCode};
+//!
+//! let mut parser = Parser::new();
+//! parser.advance(b"a\x1B[A", false);
+//!
+//! assert_eq!(parser.next(), Some(Event::Key(KeyCode::Char('a').into())));
+//! assert_eq!(parser.next(), Some(Event::Key(KeyCode::Up.into())));
+//! assert_eq!(parser.next(), None);
+//! ```
+//!
+//! [`parse`](fn.parse.html) parses a complete byte sequence at once and also returns the
+//! sequences that couldn't be parsed, which makes it a convenient entry point for fuzzers.
+//!
+//! ```
+//! use crossterm::event::{parse, Event, KeyCode};
+//!
+//! let (events, errors) = parse::parse(b"\x1B[Aa\x1B[999;x");
+//!
+//! assert_eq!(events, vec![Event::Key(KeyCode::Up.into()), Event::Key(KeyCode::Char('a').into())]);
+//! assert_eq!(errors[0].sequence(), b"\x1B[999;x");
+//! ```
+
+use std::{collections::VecDeque, error::Error, fmt, io};
+
+use crate::event::{
+    hooks::{self, ControlString, Handlers, PrefixMatch},
+    keymap::{self, Keymap, KeymapMatch},
+};
+use crate::event::{
+    ColorSlot, Event, ExtendedModifiers, InternalEvent, KeyCode, KeyEvent, KeyEventKind,
+    KeyEventState, KeyModifiers, KeyboardEnhancementFlags, MediaKeyCode, ModifierKeyCode,
+    MouseButton, MouseEncoding, MouseEvent, MouseEventKind, PhysicalKey,
+};
+#[cfg(feature = "bracketed-paste")]
+use crate::event::{PasteKind, PasteOptions};
+use crate::style::Color;
+
+// Event parsing
+//
+// This code (& previous one) are kind of ugly. We have to think about this,
+// because it's really not maintainable, no tests, etc.
+//
+// Every fn returns Result<Option<InputEvent>>
+//
+// Ok(None) -> wait for more bytes
+// Err(_) -> failed to parse event, clear the buffer
+// Ok(Some(event)) -> we have event, clear the buffer
+//
+
+// Parse errors are frequent while garbage or unknown sequences are received and are discarded by
+// the `Parser`, so use an error that doesn't allocate.
+fn could_not_parse_event_error() -> io::Error {
+    io::ErrorKind::Other.into()
+}
+
+/// The size of the buffer holding an incomplete sequence between two `Parser::advance` calls.
+///
+/// Sequences that don't fit are discarded. Bracketed paste content is collected separately and
+/// only limited by the [`PasteOptions`](../struct.PasteOptions.html).
+const SEQUENCE_BUFFER_SIZE: usize = 256;
+
+/// The minimal length of plain text received at once to be inferred as a paste.
+#[cfg(feature = "bracketed-paste")]
+const INFERRED_PASTE_MIN_LEN: usize = 16;
+
+#[cfg(feature = "bracketed-paste")]
+const PASTE_START: &[u8] = b"\x1B[200~";
+#[cfg(feature = "bracketed-paste")]
+const PASTE_END: &[u8] = b"\x1B[201~";
+
+/// The content of a bracketed paste which is being received.
+#[cfg(feature = "bracketed-paste")]
+#[derive(Debug)]
+struct PendingPaste {
+    // the first `limit` bytes, followed by the last bytes to detect the end
+    content: Vec<u8>,
+    len: usize,
+    limit: Option<usize>,
+}
+
+#[cfg(feature = "bracketed-paste")]
+impl PendingPaste {
+    fn push(&mut self, byte: u8) {
+        self.content.push(byte);
+        self.len += 1;
+        if let Some(limit) = self.limit {
+            if self.content.len() > limit + PASTE_END.len() {
+                self.content.remove(limit);
+            }
+        }
+    }
+
+    /// Returns the paste if it's complete.
+    fn complete(&self) -> Option<crate::event::Paste> {
+        if !self.content.ends_with(PASTE_END) {
+            return None;
+        }
+        let content = &self.content[..self.content.len() - PASTE_END.len()];
+        let len = self.len - PASTE_END.len();
+        Some(paste_from_bytes(
+            content,
+            PasteKind::Bracketed,
+            len,
+            content.len() < len,
+        ))
+    }
+}
+
+/// Creates a paste, a truncated one is cut at the last complete character.
+#[cfg(feature = "bracketed-paste")]
+fn paste_from_bytes(
+    mut content: &[u8],
+    kind: PasteKind,
+    len: usize,
+    truncated: bool,
+) -> crate::event::Paste {
+    if truncated {
+        if let Err(error) = std::str::from_utf8(content) {
+            if error.error_len().is_none() {
+                content = &content[..error.valid_up_to()];
+            }
+        }
+    }
+    crate::event::Paste {
+        content: String::from_utf8_lossy(content).into_owned(),
+        kind,
+        len,
+        truncated,
+    }
+}
+
+/// Returns whether `buffer` is plain text that's too long to be typed at once.
+#[cfg(feature = "bracketed-paste")]
+fn is_inferred_paste(buffer: &[u8]) -> bool {
+    buffer.len() >= INFERRED_PASTE_MIN_LEN
+        && buffer
+            .iter()
+            .all(|&byte| matches!(byte, b'\r' | b'\n' | b'\t') || (byte >= 0x20 && byte != 0x7F))
+        && std::str::from_utf8(buffer).is_ok()
+}
+
+/// A byte sequence the [`Parser`](struct.Parser.html) couldn't parse and discarded.
+#[derive(Debug, PartialEq, Eq, Clone, Hash)]
+pub struct ParseError {
+    sequence: Vec<u8>,
+}
+
+impl ParseError {
+    /// Returns the discarded bytes.
+    pub fn sequence(&self) -> &[u8] {
+        &self.sequence
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "could not parse an event from {:?}",
+            String::from_utf8_lossy(&self.sequence)
+        )
+    }
+}
+
+impl Error for ParseError {}
+
+/// Parses `bytes` as a complete input sequence.
+///
+/// Returns the parsed events and the sequences that couldn't be parsed, in order. A trailing
+/// incomplete sequence is reported as an error as well. Responses to queries are skipped, like
+/// the [`Parser`](struct.Parser.html) does.
+///
+/// This never panics or blocks for any input, fuzzers can feed it arbitrary bytes.
+pub fn parse(bytes: &[u8]) -> (Vec<Event>, Vec<ParseError>) {
+    let mut parser = Parser::new();
+    parser.collect_errors(true);
+    parser.advance(bytes, false);
+    parser.finish();
+
+    let errors = parser.take_errors();
+    (parser.collect(), errors)
+}
+
+/// A streaming parser for terminal input.
+///
+/// Bytes are pushed in with [`advance`](#method.advance) and the parsed events are pulled out
+/// through the [`Iterator`](#impl-Iterator) implementation. Sequences may be split across several
+/// `advance` calls, incomplete ones are kept until the rest arrives.
+///
+/// Responses to queries (cursor position, keyboard enhancement flags, device attributes) are
+/// consumed silently. Sequences that can't be parsed are discarded, they can be collected with
+/// [`collect_errors`](#method.collect_errors).
+///
+/// `\n` is reported as Ctrl+J, like crossterm does in raw mode, unless
+/// [`set_newline_as_enter`](#method.set_newline_as_enter) says otherwise. Likewise the parser
+/// doesn't follow the mouse capture, the key sequences or the handlers of the terminal crossterm
+/// reads from, see [`set_utf8_mouse`](#method.set_utf8_mouse),
+/// [`add_key_sequence`](#method.add_key_sequence) and [`add_osc_handler`](#method.add_osc_handler).
+//
+// Complete sequences are parsed straight from the slice passed to `advance`, only the incomplete
+// sequence at the end of it is copied into a fixed-size buffer until the next `advance` call.
+#[derive(Debug)]
+pub struct Parser {
+    sequence: [u8; SEQUENCE_BUFFER_SIZE],
+    sequence_len: usize,
+    #[cfg(feature = "bracketed-paste")]
+    paste: Option<PendingPaste>,
+    // If `None`, the global options are used.
+    #[cfg(feature = "bracketed-paste")]
+    paste_options: Option<PasteOptions>,
+    settings: Settings,
+    /// The sides of the modifier keys the terminal reported as held.
+    held_modifier_sides: ExtendedModifiers,
+    internal_events: VecDeque<InternalEvent>,
+    /// The discarded sequences, if they are collected.
+    errors: Option<Vec<ParseError>>,
+    /// Whether the discarded sequences are counted in the metrics, only for the terminal of the
+    /// process.
+    counts_errors: bool,
+}
+
+impl Default for Parser {
+    fn default() -> Self {
+        Parser::new()
+    }
+}
+
+impl Parser {
+    /// Creates a new parser.
+    pub fn new() -> Parser {
+        Parser {
+            sequence: [0; SEQUENCE_BUFFER_SIZE],
+            sequence_len: 0,
+            #[cfg(feature = "bracketed-paste")]
+            paste: None,
+            #[cfg(feature = "bracketed-paste")]
+            paste_options: Some(PasteOptions::default()),
+            settings: Settings {
+                newline_is_enter: Some(false),
+                utf8_mouse: Some(false),
+                keymap: Some(Keymap::new()),
+                handlers: Some(Handlers::new()),
+            },
+            held_modifier_sides: ExtendedModifiers::empty(),
+            // TTY_BUFFER_SIZE is 1_024 bytes. How many ANSI escape sequences can
+            // fit? What is an average sequence length? Let's guess here
+            // and say that the average ANSI escape sequence length is 8 bytes. Thus
+            // the buffer size should be 1024/8=128 to avoid additional allocations
+            // when processing large amounts of data.
+            //
+            // There's no need to make it bigger, because when you look at the `try_read`
+            // method implementation, all events are consumed before the next TTY_BUFFER
+            // is processed -> events pushed.
+            internal_events: VecDeque::with_capacity(128),
+            errors: None,
+            counts_errors: false,
+        }
+    }
+
+    /// Creates a parser for the internal event sources, which follows the raw mode of the
+    /// terminal and the global paste options, mouse capture, key sequences and handlers.
+    pub(crate) fn for_terminal() -> Parser {
+        Parser {
+            #[cfg(feature = "bracketed-paste")]
+            paste_options: None,
+            settings: Settings::default(),
+            ..Parser::new()
+        }
+    }
+
+    /// Sets how pastes are read, see [`PasteOptions`](../struct.PasteOptions.html).
+    ///
+    /// By default pastes aren't limited or inferred.
+    #[cfg(feature = "bracketed-paste")]
+    pub fn set_paste_options(&mut self, options: PasteOptions) {
+        self.paste_options = Some(options);
+    }
+
+    #[cfg(feature = "bracketed-paste")]
+    fn paste_options(&self) -> PasteOptions {
+        self.paste_options
+            .unwrap_or_else(crate::event::paste_options)
+    }
+
+    /// Sets whether `\n` is reported as the Enter key instead of Ctrl+J.
+    ///
+    /// Terminals send `\r` for Enter in raw mode, but `\n` if the terminal converts it for us.
+    pub fn set_newline_as_enter(&mut self, enabled: bool) {
+        self.settings.newline_is_enter = Some(enabled);
+    }
+
+    /// Sets whether the coordinates of the normal mouse encoding are UTF-8 encoded, as the
+    /// terminal sends them after enabling
+    /// [`MouseEncoding::Utf8`](../enum.MouseEncoding.html#variant.Utf8). Disabled by default.
+    pub fn set_utf8_mouse(&mut self, enabled: bool) {
+        self.settings.utf8_mouse = Some(enabled);
+    }
+
+    /// Reports `key` when the terminal sends `sequence`, like
+    /// [`add_key_sequence`](../fn.add_key_sequence.html) does for the events crossterm reads. A
+    /// parser doesn't consult the sequences added with that.
+    pub fn add_key_sequence(&mut self, sequence: impl Into<Vec<u8>>, key: KeyEvent) {
+        self.settings
+            .keymap
+            .get_or_insert_with(Keymap::new)
+            .add(sequence.into(), key);
+    }
+
+    /// Calls `handler` with the `OSC number` sequences, like
+    /// [`add_osc_handler`](../fn.add_osc_handler.html) does for the events crossterm reads. A
+    /// parser doesn't call the handlers added with that.
+    pub fn add_osc_handler(
+        &mut self,
+        number: u16,
+        handler: impl Fn(ControlString) + Send + Sync + 'static,
+    ) {
+        self.settings
+            .handlers
+            .get_or_insert_with(Handlers::new)
+            .add_osc(number, std::sync::Arc::new(handler));
+    }
+
+    /// Calls `handler` with the `DCS` sequences which start with `prefix`, like
+    /// [`add_dcs_handler`](../fn.add_dcs_handler.html) does for the events crossterm reads. A
+    /// parser doesn't call the handlers added with that.
+    pub fn add_dcs_handler(
+        &mut self,
+        prefix: impl Into<Vec<u8>>,
+        handler: impl Fn(ControlString) + Send + Sync + 'static,
+    ) {
+        self.settings
+            .handlers
+            .get_or_insert_with(Handlers::new)
+            .add_dcs(prefix.into(), std::sync::Arc::new(handler));
+    }
+
+    /// Sets whether discarded sequences are collected, to be retrieved with
+    /// [`take_errors`](#method.take_errors). Disabled by default.
+    pub fn collect_errors(&mut self, enabled: bool) {
+        self.errors = if enabled { Some(Vec::new()) } else { None };
+    }
+
+    /// Returns the sequences discarded since the last call, if they are collected.
+    pub fn take_errors(&mut self) -> Vec<ParseError> {
+        self.errors.as_mut().map(std::mem::take).unwrap_or_default()
+    }
+
+    /// Signals the end of the input.
+    ///
+    /// A pending `ESC` is reported as the Escape key, any other incomplete sequence or unterminated
+    /// paste is discarded.
+    pub fn finish(&mut self) {
+        if self.sequence_len > 0 {
+            let mut sequence = [0; SEQUENCE_BUFFER_SIZE];
+            let len = self.sequence_len;
+            sequence[..len].copy_from_slice(&self.sequence[..len]);
+            self.sequence_len = 0;
+
+            self.advance(&sequence[..len], false);
+            if self.sequence_len > 0 {
+                let len = self.sequence_len;
+                self.sequence_len = 0;
+                self.record_error(&sequence[..len]);
+            }
+        }
+
+        #[cfg(feature = "bracketed-paste")]
+        if let Some(mut paste) = self.paste.take() {
+            let mut sequence = PASTE_START.to_vec();
+            sequence.append(&mut paste.content);
+            self.record_error(&sequence);
+        }
+    }
+
+    /// Makes the parser count the sequences it discards in the
+    /// [metrics](../fn.metrics.html).
+    pub(crate) fn count_errors(&mut self) {
+        self.counts_errors = true;
+    }
+
+    /// Counts a discarded sequence if errors are counted, and records it if they're collected.
+    fn record_error(&mut self, sequence: &[u8]) {
+        if self.counts_errors {
+            crate::metrics::add_parse_error();
+        }
+        if let Some(errors) = &mut self.errors {
+            errors.push(ParseError {
+                sequence: sequence.to_vec(),
+            });
+        }
+    }
+
+    /// Pushes bytes received from the terminal into the parser.
+    ///
+    /// `more` says whether more bytes are known to follow immediately. It resolves whether a
+    /// trailing `ESC` is the Escape key (`false`) or the start of an escape sequence (`true`).
+    pub fn advance(&mut self, buffer: &[u8], more: bool) {
+        #[cfg(feature = "bracketed-paste")]
+        if self.paste.is_none() && self.sequence_len == 0 {
+            let options = self.paste_options();
+            if options.infer && is_inferred_paste(buffer) {
+                let kept = options
+                    .limit
+                    .map_or(buffer.len(), |limit| limit.min(buffer.len()));
+                let paste = paste_from_bytes(
+                    &buffer[..kept],
+                    PasteKind::Inferred,
+                    buffer.len(),
+                    kept < buffer.len(),
+                );
+                self.internal_events
+                    .push_back(InternalEvent::Event(Event::Paste(paste)));
+                return;
+            }
+        }
+
+        self.advance_bytes(buffer, more);
+    }
+
+    /// Pushes the bytes into the parser, without inferring a paste.
+    fn advance_bytes(&mut self, buffer: &[u8], more: bool) {
+        // Start of the sequence in `buffer` which is parsed in place, if there's no incomplete
+        // sequence left from a previous call.
+        let mut start = 0;
+
+        for (idx, byte) in buffer.iter().enumerate() {
+            let more = idx + 1 < buffer.len() || more;
+
+            #[cfg(feature = "bracketed-paste")]
+            if let Some(paste) = &mut self.paste {
+                paste.push(*byte);
+                if let Some(paste) = paste.complete() {
+                    let event = InternalEvent::Event(Event::Paste(paste));
+                    self.internal_events.push_back(event);
+                    self.paste = None;
+                }
+                start = idx + 1;
+                continue;
+            }
+
+            let too_long = self.sequence_len >= SEQUENCE_BUFFER_SIZE;
+            let result = if self.sequence_len == 0 {
+                parse_event_with(&buffer[start..=idx], more, &self.settings)
+            } else if !too_long {
+                self.sequence[self.sequence_len] = *byte;
+                self.sequence_len += 1;
+                parse_event_with(&self.sequence[..self.sequence_len], more, &self.settings)
+            } else {
+                // The sequence is too long to be a valid one, throw it away.
+                Err(could_not_parse_event_error())
+            };
+
+            match result {
+                Ok(Some(InternalEvent::ControlString(sequence))) => {
+                    hooks::dispatch(self.settings.handlers.as_ref(), sequence);
+                    self.sequence_len = 0;
+                    start = idx + 1;
+                }
+                Ok(Some(ie)) => {
+                    self.push_event(ie);
+                    self.sequence_len = 0;
+                    start = idx + 1;
+                }
+                Ok(None) => {
+                    // Event can't be parsed, because we don't have enough bytes for
+                    // the current sequence. Keep the sequence and process next bytes.
+                    #[cfg(feature = "bracketed-paste")]
+                    if self.pending(buffer, start, idx) == PASTE_START {
+                        // The paste content can be arbitrarily long, collect it separately
+                        // instead of parsing the whole sequence again for every byte.
+                        self.paste = Some(PendingPaste {
+                            content: Vec::new(),
+                            len: 0,
+                            limit: self.paste_options().limit,
+                        });
+                        self.sequence_len = 0;
+                        start = idx + 1;
+                    }
+                }
+                Err(_) if !too_long && self.is_alt_introducer(buffer, start, idx) => {
+                    // Alt+] or Alt+Shift+P followed by other keys instead of an OSC or DCS, report
+                    // the keys rather than discarding them.
+                    let pending = self.pending(buffer, start, idx).to_vec();
+                    self.sequence_len = 0;
+                    start = idx + 1;
+                    if let Ok(Some(ie)) = parse_event_with(&pending[..2], false, &self.settings) {
+                        self.push_event(ie);
+                    }
+                    self.advance_bytes(&pending[2..], more);
+                }
+                Err(_) => {
+                    // Event can't be parsed (not enough parameters, parameter is not a number, ...).
+                    // Clear the sequence and continue with another one.
+                    let sequence = match self.errors {
+                        Some(_) => self.pending(buffer, start, idx).to_vec(),
+                        None => Vec::new(),
+                    };
+                    self.record_error(&sequence);
+                    self.sequence_len = 0;
+                    start = idx + 1;
+                }
+            }
+        }
+
+        // Keep the incomplete sequence for the next call.
+        if self.sequence_len == 0 && start < buffer.len() {
+            let incomplete = &buffer[start..];
+            if incomplete.len() <= SEQUENCE_BUFFER_SIZE {
+                self.sequence[..incomplete.len()].copy_from_slice(incomplete);
+                self.sequence_len = incomplete.len();
+            } else {
+                self.record_error(incomplete);
+            }
+        }
+    }
+
+    /// Queues a parsed event, with the sides of the held modifiers if it's a key.
+    fn push_event(&mut self, mut ie: InternalEvent) {
+        if let InternalEvent::Event(Event::Key(key_event)) = &mut ie {
+            key_event.extended_modifiers =
+                track_held_modifier_sides(&mut self.held_modifier_sides, key_event);
+        }
+        self.internal_events.push_back(ie);
+    }
+
+    /// Returns whether the incomplete sequence ending at `idx` starts like an OSC or DCS.
+    fn is_alt_introducer(&self, buffer: &[u8], start: usize, idx: usize) -> bool {
+        let pending = self.pending(buffer, start, idx);
+        pending.len() > 2 && pending[0] == b'\x1B' && matches!(pending[1], b']' | b'P')
+    }
+
+    /// Returns the incomplete sequence ending at `idx`.
+    fn pending<'a>(&'a self, buffer: &'a [u8], start: usize, idx: usize) -> &'a [u8] {
+        if self.sequence_len == 0 {
+            &buffer[start..=idx]
+        } else {
+            &self.sequence[..self.sequence_len]
+        }
+    }
+
+    /// Returns the next parsed event, including the internal ones.
+    pub(crate) fn next_internal(&mut self) -> Option<InternalEvent> {
+        self.internal_events.pop_front()
+    }
+}
+
+impl Iterator for Parser {
+    type Item = Event;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some(internal_event) = self.internal_events.pop_front() {
+            if let InternalEvent::Event(event) = internal_event {
+                return Some(event);
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+pub(crate) fn parse_event(
+    buffer: &[u8],
+    input_available: bool,
+) -> io::Result<Option<InternalEvent>> {
+    let settings = Settings {
+        keymap: Some(Keymap::new()),
+        handlers: Some(Handlers::new()),
+        ..Settings::default()
+    };
+    parse_event_with(buffer, input_available, &settings)
+}
+
+/// The settings of a [`Parser`] the parse functions need. `None` follows the global state, the
+/// raw mode of the terminal, the mouse capture, and the added key sequences and handlers.
+#[derive(Debug, Clone, Default)]
+struct Settings {
+    newline_is_enter: Option<bool>,
+    utf8_mouse: Option<bool>,
+    keymap: Option<Keymap>,
+    handlers: Option<Handlers>,
+}
+
+impl Settings {
+    /// Returns whether `\n` is the Enter key instead of Ctrl+J.
+    fn newline_is_enter(&self) -> bool {
+        self.newline_is_enter
+            .unwrap_or_else(terminal_newline_is_enter)
+    }
+
+    /// Returns whether the coordinates of the normal mouse encoding are UTF-8 encoded.
+    fn utf8_mouse(&self) -> bool {
+        self.utf8_mouse.unwrap_or_else(|| {
+            crate::event::mouse_capture_options().map_or(false, |options| {
+                options.encoding == Some(MouseEncoding::Utf8)
+            })
+        })
+    }
+
+    fn lookup_key(&self, input: &[u8]) -> Option<KeymapMatch> {
+        match &self.keymap {
+            Some(keymap) => keymap.lookup(input),
+            None => keymap::lookup(input),
+        }
+    }
+
+    fn with_handlers<T>(&self, f: impl FnOnce(&Handlers) -> T) -> T {
+        match &self.handlers {
+            Some(handlers) => f(handlers),
+            None => hooks::with_global(f),
+        }
+    }
+}
+
+/// Returns whether `\n` is the Enter key, which is only the case if the terminal isn't in raw mode.
+fn terminal_newline_is_enter() -> bool {
+    !crate::terminal::is_raw_mode_enabled().unwrap_or(false)
+}
+
+/// Parses an event with the settings of a parser.
+fn parse_event_with(
+    buffer: &[u8],
+    input_available: bool,
+    settings: &Settings,
+) -> io::Result<Option<InternalEvent>> {
+    let result = parse_known_event(buffer, input_available, settings);
+    if let Ok(Some(_)) = result {
+        return result;
+    }
+
+    // The added key sequences are consulted before the sequence is discarded or continued.
+    match settings.lookup_key(buffer) {
+        Some(KeymapMatch::Key(key)) => Ok(Some(InternalEvent::Event(Event::Key(key)))),
+        Some(KeymapMatch::Partial) => Ok(None),
+        None => result,
+    }
+}
+
+/// Parses an event crossterm knows, see [`parse_event_with`].
+fn parse_known_event(
+    buffer: &[u8],
+    input_available: bool,
+    settings: &Settings,
+) -> io::Result<Option<InternalEvent>> {
+    if buffer.is_empty() {
+        return Ok(None);
+    }
+
+    match buffer[0] {
+        b'\x1B' => {
+            if buffer.len() == 1 {
+                if input_available {
+                    // Possible Esc sequence
+                    Ok(None)
+                } else {
+                    Ok(Some(InternalEvent::Event(Event::Key(KeyCode::Esc.into()))))
+                }
+            } else {
+                match buffer[1] {
+                    b'O' => {
+                        if buffer.len() == 2 {
+                            Ok(None)
+                        } else {
+                            match buffer[2] {
+                                b'D' => {
+                                    Ok(Some(InternalEvent::Event(Event::Key(KeyCode::Left.into()))))
+                                }
+                                b'C' => Ok(Some(InternalEvent::Event(Event::Key(
+                                    KeyCode::Right.into(),
+                                )))),
+                                b'A' => {
+                                    Ok(Some(InternalEvent::Event(Event::Key(KeyCode::Up.into()))))
+                                }
+                                b'B' => {
+                                    Ok(Some(InternalEvent::Event(Event::Key(KeyCode::Down.into()))))
+                                }
+                                b'H' => {
+                                    Ok(Some(InternalEvent::Event(Event::Key(KeyCode::Home.into()))))
+                                }
+                                b'F' => {
+                                    Ok(Some(InternalEvent::Event(Event::Key(KeyCode::End.into()))))
+                                }
+                                // F1-F4
+                                val @ b'P'..=b'S' => Ok(Some(InternalEvent::Event(Event::Key(
+                                    KeyCode::F(1 + val - b'P').into(),
+                                )))),
+                                // rxvt writes the arrow keys with Control with lowercase letters
+                                val @ b'a'..=b'd' => Ok(Some(InternalEvent::Event(Event::Key(
+                                    KeyEvent::new(rxvt_arrow_key_code(val), KeyModifiers::CONTROL),
+                                )))),
+                                b'1'..=b'9' => parse_ss3_modifier_key_code(buffer),
+                                _ => Err(could_not_parse_event_error()),
+                            }
+                        }
+                    }
+                    b'[' => parse_csi(buffer, settings),
+                    // Wait for the next byte to tell an OSC response from Alt+]
+                    b']' if buffer.len() == 2 && input_available => Ok(None),
+                    b']' if buffer.len() > 2 && buffer[2].is_ascii_digit() => {
+                        parse_osc(buffer, settings)
+                    }
+                    // Likewise a DCS response from Alt+Shift+P
+                    b'P' if buffer.len() == 2 && input_available => Ok(None),
+                    b'P' if buffer.len() > 2
+                        && (matches!(buffer[2], b'0' | b'1' | b'>')
+                            || settings.with_handlers(|handlers| {
+                                handlers.match_dcs_prefix(&buffer[2..]).is_some()
+                            })) =>
+                    {
+                        parse_dcs(buffer, settings)
+                    }
+                    b'\x1B' => Ok(Some(InternalEvent::Event(Event::Key(KeyCode::Esc.into())))),
+                    _ => parse_event_with(&buffer[1..], input_available, settings).map(
+                        |event_option| {
+                            event_option.map(|event| {
+                                if let InternalEvent::Event(Event::Key(key_event)) = event {
+                                    let mut alt_key_event = key_event;
+                                    alt_key_event.modifiers |= KeyModifiers::ALT;
+                                    InternalEvent::Event(Event::Key(alt_key_event))
+                                } else {
+                                    event
+                                }
+                            })
+                        },
+                    ),
+                }
+            }
+        }
+        b'\r' => Ok(Some(InternalEvent::Event(Event::Key(
+            KeyCode::Enter.into(),
+        )))),
+        // Issue #371: \n = 0xA, which is also the keycode for Ctrl+J. The only reason we get
+        // newlines as input is because the terminal converts \r into \n for us. When we
+        // enter raw mode, we disable that, so \n no longer has any meaning - it's better to
+        // use Ctrl+J. Waiting to handle it here means it gets picked up later
+        b'\n' if settings.newline_is_enter() => Ok(Some(InternalEvent::Event(Event::Key(
+            KeyCode::Enter.into(),
+        )))),
+        b'\t' => Ok(Some(InternalEvent::Event(Event::Key(KeyCode::Tab.into())))),
+        b'\x7F' => Ok(Some(InternalEvent::Event(Event::Key(
+            KeyCode::Backspace.into(),
+        )))),
+        c @ b'\x01'..=b'\x1A' => Ok(Some(InternalEvent::Event(Event::Key(KeyEvent::new(
+            KeyCode::Char((c - 0x1 + b'a') as char),
+            KeyModifiers::CONTROL,
+        ))))),
+        c @ b'\x1C'..=b'\x1F' => Ok(Some(InternalEvent::Event(Event::Key(KeyEvent::new(
+            KeyCode::Char((c - 0x1C + b'4') as char),
+            KeyModifiers::CONTROL,
+        ))))),
+        b'\0' => Ok(Some(InternalEvent::Event(Event::Key(KeyEvent::new(
+            KeyCode::Char(' '),
+            KeyModifiers::CONTROL,
+        ))))),
+        _ => parse_utf8_char(buffer).map(|maybe_char| {
+            maybe_char
+                .map(KeyCode::Char)
+                .map(char_code_to_event)
+                .map(Event::Key)
+                .map(InternalEvent::Event)
+        }),
+    }
+}
+
+// converts KeyCode to KeyEvent (adds shift modifier in case of uppercase characters)
+fn char_code_to_event(code: KeyCode) -> KeyEvent {
+    let modifiers = match code {
+        KeyCode::Char(c) if c.is_uppercase() => KeyModifiers::SHIFT,
+        _ => KeyModifiers::empty(),
+    };
+    KeyEvent::new(code, modifiers)
+}
+
+/// Returns the arrow key of the lowercase letter rxvt writes for it with a modifier.
+fn rxvt_arrow_key_code(letter: u8) -> KeyCode {
+    match letter {
+        b'a' => KeyCode::Up,
+        b'b' => KeyCode::Down,
+        b'c' => KeyCode::Right,
+        _ => KeyCode::Left,
+    }
+}
+
+pub(crate) fn parse_ss3_modifier_key_code(buffer: &[u8]) -> io::Result<Option<InternalEvent>> {
+    // Older xterms and some of their emulators write the modifiers between SS3 and the key:
+    // ESC O modifiers P    Shift+F1 for modifiers 2
+    assert!(buffer.starts_with(b"\x1BO")); // SS3
+
+    if buffer.len() == 3 {
+        return Ok(None);
+    }
+
+    let modifiers = parse_modifiers(buffer[2] - b'0');
+    let keycode = match buffer[3] {
+        b'A' => KeyCode::Up,
+        b'B' => KeyCode::Down,
+        b'C' => KeyCode::Right,
+        b'D' => KeyCode::Left,
+        b'F' => KeyCode::End,
+        b'H' => KeyCode::Home,
+        val @ b'P'..=b'S' => KeyCode::F(1 + val - b'P'),
+        _ => return Err(could_not_parse_event_error()),
+    };
+
+    Ok(Some(InternalEvent::Event(Event::Key(KeyEvent::new(
+        keycode, modifiers,
+    )))))
+}
+
+fn parse_csi(buffer: &[u8], settings: &Settings) -> io::Result<Option<InternalEvent>> {
+    assert!(buffer.starts_with(&[b'\x1B', b'['])); // ESC [
+
+    if buffer.len() == 2 {
+        return Ok(None);
+    }
+
+    let input_event = match buffer[2] {
+        b'[' => {
+            if buffer.len() == 3 {
+                None
+            } else {
+                match buffer[3] {
+                    // NOTE (@imdaveho): cannot find when this occurs;
+                    // having another '[' after ESC[ not a likely scenario
+                    val @ b'A'..=b'E' => Some(Event::Key(KeyCode::F(1 + val - b'A').into())),
+                    _ => return Err(could_not_parse_event_error()),
+                }
+            }
+        }
+        b'D' => Some(Event::Key(KeyCode::Left.into())),
+        b'C' => Some(Event::Key(KeyCode::Right.into())),
+        b'A' => Some(Event::Key(KeyCode::Up.into())),
+        b'B' => Some(Event::Key(KeyCode::Down.into())),
+        b'H' => Some(Event::Key(KeyCode::Home.into())),
+        b'F' => Some(Event::Key(KeyCode::End.into())),
+        // rxvt writes the arrow keys with Shift with lowercase letters
+        val @ b'a'..=b'd' => Some(Event::Key(KeyEvent::new(
+            rxvt_arrow_key_code(val),
+            KeyModifiers::SHIFT,
+        ))),
+        b'Z' => Some(Event::Key(KeyEvent::new_with_kind(
+            KeyCode::BackTab,
+            KeyModifiers::SHIFT,
+            KeyEventKind::Press,
+        ))),
+        b'M' => return parse_csi_normal_mouse(buffer, settings.utf8_mouse()),
+        b'<' => return parse_csi_sgr_mouse(buffer),
+        b'I' => Some(Event::FocusGained),
+        b'O' => Some(Event::FocusLost),
+        b';' => return parse_csi_modifier_key_code(buffer),
+        // P, Q, and S for compatibility with Kitty keyboard protocol,
+        // as the 1 in 'CSI 1 P' etc. must be omitted if there are no
+        // modifiers pressed:
+        // https://sw.kovidgoyal.net/kitty/keyboard-protocol/#legacy-functional-keys
+        b'P' => Some(Event::Key(KeyCode::F(1).into())),
+        b'Q' => Some(Event::Key(KeyCode::F(2).into())),
+        b'S' => Some(Event::Key(KeyCode::F(4).into())),
+        b'?' => match buffer[buffer.len() - 1] {
+            b'u' => return parse_csi_keyboard_enhancement_flags(buffer),
+            b'c' => return parse_csi_primary_device_attributes(buffer),
+            b'y' => return parse_csi_mode_report(buffer),
+            _ => None,
+        },
+        b'>' => match buffer[buffer.len() - 1] {
+            b'c' => return parse_csi_secondary_device_attributes(buffer),
+            _ => None,
+        },
+        b'0'..=b'9' => {
+            // Numbered escape code.
+            if buffer.len() == 3 {
+                None
+            } else {
+                // The final byte of a CSI sequence can be in the range 64-126, so
+                // let's keep reading anything else.
+                let last_byte = buffer[buffer.len() - 1];
+                if is_rxvt_shifted_key_code(buffer) {
+                    return parse_csi_rxvt_special_key_code(buffer);
+                } else if !(64..=126).contains(&last_byte) {
+                    None
+                } else {
+                    #[cfg(feature = "bracketed-paste")]
+                    if buffer.starts_with(b"\x1B[200~") {
+                        return parse_csi_bracketed_paste(buffer);
+                    }
+                    match last_byte {
+                        b'M' => return parse_csi_rxvt_mouse(buffer),
+                        b'~' => return parse_csi_special_key_code(buffer),
+                        b'^' | b'@' => return parse_csi_rxvt_special_key_code(buffer),
+                        b'u' => {
+                            return parse_csi_u_encoded_key_code(
+                                buffer,
+                                settings.newline_is_enter(),
+                            )
+                        }
+                        b'R' => return parse_csi_cursor_position(buffer),
+                        b't' => return parse_csi_window_report(buffer),
+                        _ => return parse_csi_modifier_key_code(buffer),
+                    }
+                }
+            }
+        }
+        _ => return Err(could_not_parse_event_error()),
+    };
+
+    Ok(input_event.map(InternalEvent::Event))
+}
+
+/// Parses an operating system command, of which only the color and notification reports are
+/// understood.
+fn parse_osc(buffer: &[u8], settings: &Settings) -> io::Result<Option<InternalEvent>> {
+    assert!(buffer.starts_with(b"\x1B]")); // ESC ]
+
+    if !settings.with_handlers(|handlers| is_osc_start(&buffer[2..], handlers))
+        || contains_stray_control(&buffer[2..])
+    {
+        return Err(could_not_parse_event_error());
+    }
+
+    // The response ends with BEL or ST (ESC \\)
+    let content = if let Some(content) = buffer.strip_suffix(b"\x07") {
+        content
+    } else if let Some(content) = buffer.strip_suffix(b"\x1B\\") {
+        content
+    } else {
+        return Ok(None);
+    };
+
+    // ESC ] number ; payload ST            a sequence with a handler
+    let (number, payload) = match content[2..].iter().position(|&byte| byte == b';') {
+        Some(end) => (&content[2..2 + end], &content[3 + end..]),
+        None => (&content[2..], &[][..]),
+    };
+    if let Some(number) = std::str::from_utf8(number)
+        .ok()
+        .and_then(|number| number.parse::<u16>().ok())
+        .filter(|&number| settings.with_handlers(|handlers| handlers.has_osc(number)))
+    {
+        return Ok(Some(InternalEvent::ControlString(ControlString::Osc {
+            number,
+            payload: payload.to_vec(),
+        })));
+    }
+
+    let s = std::str::from_utf8(&content[2..]).map_err(|_| could_not_parse_event_error())?;
+    let mut split = s.split(';');
+
+    // ESC ] 99 ; i=id ; [button] ST          a notification was activated
+    // ESC ] 99 ; i=id:p=close ; ST           a notification was closed
+    if s.starts_with("99;") {
+        split.next();
+        return parse_notification_report(split.next().unwrap_or_default()).map(Some);
+    }
+
+    // ESC ] 10 ; rgb:rrrr/gggg/bbbb ST
+    // ESC ] 4 ; index ; rgb:rrrr/gggg/bbbb ST
+    let slot = match split.next() {
+        Some("10") => ColorSlot::Foreground,
+        Some("11") => ColorSlot::Background,
+        Some("12") => ColorSlot::Cursor,
+        Some("4") => ColorSlot::Palette(next_parsed::<u8>(&mut split)?),
+        _ => return Err(could_not_parse_event_error()),
+    };
+    let color = split
+        .next()
+        .and_then(parse_rgb_spec)
+        .ok_or_else(could_not_parse_event_error)?;
+
+    Ok(Some(InternalEvent::Event(Event::ColorReport {
+        slot,
+        color,
+    })))
+}
+
+/// The numbers of the `OSC` reports crossterm understands itself.
+const OSC_NUMBERS: [&str; 5] = ["4", "10", "11", "12", "99"];
+
+/// Returns whether the bytes after `ESC ]` can be the start of an `OSC` which is understood or has
+/// a handler, rather than keys typed after Alt+].
+fn is_osc_start(bytes: &[u8], handlers: &Handlers) -> bool {
+    let digits = bytes
+        .iter()
+        .take_while(|byte| byte.is_ascii_digit())
+        .count();
+    let number = &bytes[..digits];
+    match bytes.get(digits) {
+        None => {
+            OSC_NUMBERS
+                .iter()
+                .any(|known| known.as_bytes().starts_with(number))
+                || handlers.has_osc_starting_with(number)
+        }
+        Some(b';' | b'\x07' | b'\x1B') => {
+            OSC_NUMBERS.iter().any(|known| known.as_bytes() == number)
+                || std::str::from_utf8(number)
+                    .ok()
+                    .and_then(|number| number.parse::<u16>().ok())
+                    .map_or(false, |number| handlers.has_osc(number))
+        }
+        Some(_) => false,
+    }
+}
+
+/// Returns whether the bytes of an `OSC` or `DCS` contain a control other than the terminating
+/// `BEL` or `ST`, which means they are keys typed after Alt+] or Alt+Shift+P.
+fn contains_stray_control(bytes: &[u8]) -> bool {
+    bytes.iter().enumerate().any(|(idx, &byte)| match byte {
+        b'\x1B' => bytes.get(idx + 1).map_or(false, |&next| next != b'\\'),
+        b'\x07' => idx + 1 < bytes.len(),
+        _ => byte < 0x20 || byte == 0x7F,
+    })
+}
+
+/// Parses the metadata of an `OSC 99` notification report, e.g. `i=1:p=close`.
+fn parse_notification_report(metadata: &str) -> io::Result<InternalEvent> {
+    let mut id = None;
+    let mut closed = false;
+    for (key, value) in metadata.split(':').filter_map(|pair| pair.split_once('=')) {
+        match key {
+            "i" => id = value.parse::<u32>().ok(),
+            "p" => closed = value == "close",
+            _ => {}
+        }
+    }
+
+    let id = id.ok_or_else(could_not_parse_event_error)?;
+    Ok(InternalEvent::Event(if closed {
+        Event::NotificationClosed(id)
+    } else {
+        Event::NotificationActivated(id)
+    }))
+}
+
+/// Parses a device control string, of which only the DECRQSS and XTVERSION responses and the
+/// ones with a handler are understood.
+fn parse_dcs(buffer: &[u8], settings: &Settings) -> io::Result<Option<InternalEvent>> {
+    assert!(buffer.starts_with(b"\x1BP")); // ESC P
+
+    // ESC P prefix payload ST  a sequence with a handler
+    match settings.with_handlers(|handlers| handlers.match_dcs_prefix(&buffer[2..])) {
+        Some(PrefixMatch::Complete(len)) => {
+            return Ok(match buffer.strip_suffix(b"\x1B\\") {
+                Some(content) if content.len() >= 2 + len => {
+                    Some(InternalEvent::ControlString(ControlString::Dcs {
+                        prefix: content[2..2 + len].to_vec(),
+                        payload: content[2 + len..].to_vec(),
+                    }))
+                }
+                _ => None,
+            });
+        }
+        Some(PrefixMatch::Partial) => return Ok(None),
+        None => {}
+    }
+
+    // ESC P > | Pt ST          the name and version of the terminal (XTVERSION)
+    if buffer.len() > 2 && buffer[2] == b'>' {
+        if buffer.len() > 3 && buffer[3] != b'|' || contains_stray_control(&buffer[3..]) {
+            return Err(could_not_parse_event_error());
+        }
+        let content = match buffer.strip_suffix(b"\x1B\\") {
+            Some(content) if content.len() >= 4 => &content[4..],
+            _ => return Ok(None),
+        };
+        let version = std::str::from_utf8(content).map_err(|_| could_not_parse_event_error())?;
+        return Ok(Some(InternalEvent::TerminalVersion(version.to_string())));
+    }
+
+    // ESC P 1 $ r Pt ST        a valid request, Pt is the setting
+    // ESC P 0 $ r ST           an invalid request
+    if buffer.len() > 3 && buffer[3] != b'$'
+        || buffer.len() > 4 && buffer[4] != b'r'
+        || contains_stray_control(&buffer[3..])
+    {
+        return Err(could_not_parse_event_error());
+    }
+    let content = match buffer.strip_suffix(b"\x1B\\") {
+        Some(content) if content.len() >= 5 => &content[5..],
+        _ => return Ok(None),
+    };
+
+    let setting = match buffer[2] {
+        b'1' => Some(
+            std::str::from_utf8(content)
+                .map_err(|_| could_not_parse_event_error())?
+                .to_string(),
+        ),
+        _ => None,
+    };
+    Ok(Some(InternalEvent::SettingReport(setting)))
+}
+
+/// Parses an X11 color specification like `rgb:ffff/8000/0000` with 1 to 4 hex digits per
+/// component. The alpha component of `rgba:` is ignored.
+fn parse_rgb_spec(spec: &str) -> Option<Color> {
+    let components = spec
+        .strip_prefix("rgb:")
+        .or_else(|| spec.strip_prefix("rgba:"))?;
+
+    let mut values = components.split('/').map(|component| {
+        if component.is_empty() || component.len() > 4 {
+            return None;
+        }
+        let value = u32::from_str_radix(component, 16).ok()?;
+        let max = (1 << (4 * component.len())) - 1;
+        Some(((value * 255 + max / 2) / max) as u8)
+    });
+
+    Some(Color::Rgb {
+        r: values.next()??,
+        g: values.next()??,
+        b: values.next()??,
+    })
+}
+
+pub(crate) fn next_parsed<T>(iter: &mut dyn Iterator<Item = &str>) -> io::Result<T>
+where
+    T: std::str::FromStr,
+{
+    iter.next()
+        .ok_or_else(could_not_parse_event_error)?
+        .parse::<T>()
+        .map_err(|_| could_not_parse_event_error())
+}
+
+fn modifier_and_kind_parsed(iter: &mut dyn Iterator<Item = &str>) -> io::Result<(u8, u8)> {
+    let mut sub_split = iter
+        .next()
+        .ok_or_else(could_not_parse_event_error)?
+        .split(':');
+
+    let modifier_mask = next_parsed::<u8>(&mut sub_split)?;
+
+    if let Ok(kind_code) = next_parsed::<u8>(&mut sub_split) {
+        Ok((modifier_mask, kind_code))
+    } else {
+        Ok((modifier_mask, 1))
+    }
+}
+
+pub(crate) fn parse_csi_cursor_position(buffer: &[u8]) -> io::Result<Option<InternalEvent>> {
+    // ESC [ Cy ; Cx R
+    //   Cy - cursor row number (starting from 1)
+    //   Cx - cursor column number (starting from 1)
+    assert!(buffer.starts_with(&[b'\x1B', b'['])); // ESC [
+    assert!(buffer.ends_with(&[b'R']));
+
+    let s = std::str::from_utf8(&buffer[2..buffer.len() - 1])
+        .map_err(|_| could_not_parse_event_error())?;
+
+    let mut split = s.split(';');
+
+    let y = next_parsed::<u16>(&mut split)? - 1;
+    let x = next_parsed::<u16>(&mut split)? - 1;
+
+    Ok(Some(InternalEvent::CursorPosition(x, y)))
+}
+
+fn parse_csi_keyboard_enhancement_flags(buffer: &[u8]) -> io::Result<Option<InternalEvent>> {
+    // ESC [ ? flags u
+    assert!(buffer.starts_with(&[b'\x1B', b'[', b'?'])); // ESC [ ?
+    assert!(buffer.ends_with(&[b'u']));
+
+    if buffer.len() < 5 {
+        return Ok(None);
+    }
+
+    let bits = buffer[3];
+    let mut flags = KeyboardEnhancementFlags::empty();
+
+    if bits & 1 != 0 {
+        flags |= KeyboardEnhancementFlags::DISAMBIGUATE_ESCAPE_CODES;
+    }
+    if bits & 2 != 0 {
+        flags |= KeyboardEnhancementFlags::REPORT_EVENT_TYPES;
+    }
+    if bits & 4 != 0 {
+        flags |= KeyboardEnhancementFlags::REPORT_ALTERNATE_KEYS;
+    }
+    if bits & 8 != 0 {
+        flags |= KeyboardEnhancementFlags::REPORT_ALL_KEYS_AS_ESCAPE_CODES;
+    }
+    // *Note*: this is not yet supported by crossterm.
+    // if bits & 16 != 0 {
+    //     flags |= KeyboardEnhancementFlags::REPORT_ASSOCIATED_TEXT;
+    // }
+
+    Ok(Some(InternalEvent::KeyboardEnhancementFlags(flags)))
+}
+
+fn parse_csi_primary_device_attributes(buffer: &[u8]) -> io::Result<Option<InternalEvent>> {
+    // ESC [ ? 64 ; attr1 ; attr2 ; ... ; attrn ; c
+    assert!(buffer.starts_with(&[b'\x1B', b'[', b'?']));
+    assert!(buffer.ends_with(&[b'c']));
+
+    // The first value is the architectural class, the others are the attributes, e.g. 4 for sixel
+    // graphics. See <https://vt100.net/docs/vt510-rm/DA1.html>
+    let s = std::str::from_utf8(&buffer[3..buffer.len() - 1])
+        .map_err(|_| could_not_parse_event_error())?;
+    let attributes = s
+        .split(';')
+        .filter_map(|value| value.parse().ok())
+        .collect();
+
+    Ok(Some(InternalEvent::PrimaryDeviceAttributes(attributes)))
+}
+
+fn parse_csi_secondary_device_attributes(buffer: &[u8]) -> io::Result<Option<InternalEvent>> {
+    // ESC [ > type ; version ; rom c
+    assert!(buffer.starts_with(b"\x1B[>"));
+    assert!(buffer.ends_with(b"c"));
+
+    // The first value identifies the terminal, e.g. 84 (`T`) for tmux and 83 (`S`) for GNU
+    // screen. See <https://vt100.net/docs/vt510-rm/DA2.html>
+    let s = std::str::from_utf8(&buffer[3..buffer.len() - 1])
+        .map_err(|_| could_not_parse_event_error())?;
+    let attributes = s
+        .split(';')
+        .filter_map(|value| value.parse().ok())
+        .collect();
+
+    Ok(Some(InternalEvent::SecondaryDeviceAttributes(attributes)))
+}
+
+fn parse_csi_mode_report(buffer: &[u8]) -> io::Result<Option<InternalEvent>> {
+    // ESC [ ? mode ; setting $ y
+    assert!(buffer.starts_with(b"\x1B[?"));
+    assert!(buffer.ends_with(b"y"));
+
+    let s = std::str::from_utf8(&buffer[3..buffer.len() - 1])
+        .map_err(|_| could_not_parse_event_error())?
+        .strip_suffix('$')
+        .ok_or_else(could_not_parse_event_error)?;
+    let mut split = s.split(';');
+    let mode = next_parsed::<u16>(&mut split)?;
+    let setting = next_parsed::<u8>(&mut split)?;
+
+    Ok(Some(InternalEvent::ModeReport(mode, setting)))
+}
+
+fn parse_csi_window_report(buffer: &[u8]) -> io::Result<Option<InternalEvent>> {
+    // ESC [ 6 ; height ; width t
+    assert!(buffer.starts_with(b"\x1B[")); // ESC [
+    assert!(buffer.ends_with(b"t"));
+
+    let s = std::str::from_utf8(&buffer[2..buffer.len() - 1])
+        .map_err(|_| could_not_parse_event_error())?;
+    let mut split = s.split(';');
+
+    // Only the cell size is requested, other reports are parsed as keys like before.
+    if split.next() != Some("6") {
+        return parse_csi_modifier_key_code(buffer);
+    }
+    let height = next_parsed::<u16>(&mut split)?;
+    let width = next_parsed::<u16>(&mut split)?;
+
+    Ok(Some(InternalEvent::CellSize(width, height)))
+}
+
+fn parse_modifiers(mask: u8) -> KeyModifiers {
+    let modifier_mask = mask.saturating_sub(1);
+    let mut modifiers = KeyModifiers::empty();
+    if modifier_mask & 1 != 0 {
+        modifiers |= KeyModifiers::SHIFT;
+    }
+    if modifier_mask & 2 != 0 {
+        modifiers |= KeyModifiers::ALT;
+    }
+    if modifier_mask & 4 != 0 {
+        modifiers |= KeyModifiers::CONTROL;
+    }
+    if modifier_mask & 8 != 0 {
+        modifiers |= KeyModifiers::SUPER;
+    }
+    if modifier_mask & 16 != 0 {
+        modifiers |= KeyModifiers::HYPER;
+    }
+    if modifier_mask & 32 != 0 {
+        modifiers |= KeyModifiers::META;
+    }
+    modifiers
+}
+
+fn parse_modifiers_to_state(mask: u8) -> KeyEventState {
+    let modifier_mask = mask.saturating_sub(1);
+    let mut state = KeyEventState::empty();
+    if modifier_mask & 64 != 0 {
+        state |= KeyEventState::CAPS_LOCK;
+    }
+    if modifier_mask & 128 != 0 {
+        state |= KeyEventState::NUM_LOCK;
+    }
+    state
+}
+
+fn parse_key_event_kind(kind: u8) -> KeyEventKind {
+    match kind {
+        1 => KeyEventKind::Press,
+        2 => KeyEventKind::Repeat,
+        3 => KeyEventKind::Release,
+        _ => KeyEventKind::Press,
+    }
+}
+
+pub(crate) fn parse_csi_modifier_key_code(buffer: &[u8]) -> io::Result<Option<InternalEvent>> {
+    assert!(buffer.starts_with(&[b'\x1B', b'['])); // ESC [
+                                                   //
+    let s = std::str::from_utf8(&buffer[2..buffer.len() - 1])
+        .map_err(|_| could_not_parse_event_error())?;
+    let mut split = s.split(';');
+
+    split.next();
+
+    let (modifiers, kind, state) =
+        if let Ok((modifier_mask, kind_code)) = modifier_and_kind_parsed(&mut split) {
+            (
+                parse_modifiers(modifier_mask),
+                parse_key_event_kind(kind_code),
+                parse_modifiers_to_state(modifier_mask),
+            )
+        } else if buffer.len() > 3 {
+            (
+                parse_modifiers(
+                    (buffer[buffer.len() - 2] as char)
+                        .to_digit(10)
+                        .ok_or_else(could_not_parse_event_error)? as u8,
+                ),
+                KeyEventKind::Press,
+                KeyEventState::NONE,
+            )
+        } else {
+            (KeyModifiers::NONE, KeyEventKind::Press, KeyEventState::NONE)
+        };
+    let key = buffer[buffer.len() - 1];
+
+    let keycode = match key {
+        b'A' => KeyCode::Up,
+        b'B' => KeyCode::Down,
+        b'C' => KeyCode::Right,
+        b'D' => KeyCode::Left,
+        b'F' => KeyCode::End,
+        b'H' => KeyCode::Home,
+        b'P' => KeyCode::F(1),
+        b'Q' => KeyCode::F(2),
+        b'R' => KeyCode::F(3),
+        b'S' => KeyCode::F(4),
+        _ => return Err(could_not_parse_event_error()),
+    };
+
+    let input_event = Event::Key(KeyEvent::new_with_kind_and_state(
+        keycode, modifiers, kind, state,
+    ));
+
+    Ok(Some(InternalEvent::Event(input_event)))
+}
+
+fn translate_functional_key_code(codepoint: u32) -> Option<(KeyCode, KeyEventState)> {
+    if let Some(keycode) = match codepoint {
+        57399 => Some(KeyCode::Char('0')),
+        57400 => Some(KeyCode::Char('1')),
+        57401 => Some(KeyCode::Char('2')),
+        57402 => Some(KeyCode::Char('3')),
+        57403 => Some(KeyCode::Char('4')),
+        57404 => Some(KeyCode::Char('5')),
+        57405 => Some(KeyCode::Char('6')),
+        57406 => Some(KeyCode::Char('7')),
+        57407 => Some(KeyCode::Char('8')),
+        57408 => Some(KeyCode::Char('9')),
+        57409 => Some(KeyCode::Char('.')),
+        57410 => Some(KeyCode::Char('/')),
+        57411 => Some(KeyCode::Char('*')),
+        57412 => Some(KeyCode::Char('-')),
+        57413 => Some(KeyCode::Char('+')),
+        57414 => Some(KeyCode::Enter),
+        57415 => Some(KeyCode::Char('=')),
+        57416 => Some(KeyCode::Char(',')),
+        57417 => Some(KeyCode::Left),
+        57418 => Some(KeyCode::Right),
+        57419 => Some(KeyCode::Up),
+        57420 => Some(KeyCode::Down),
+        57421 => Some(KeyCode::PageUp),
+        57422 => Some(KeyCode::PageDown),
+        57423 => Some(KeyCode::Home),
+        57424 => Some(KeyCode::End),
+        57425 => Some(KeyCode::Insert),
+        57426 => Some(KeyCode::Delete),
+        57427 => Some(KeyCode::KeypadBegin),
+        _ => None,
+    } {
+        return Some((keycode, KeyEventState::KEYPAD));
+    }
+
+    if let Some(keycode) = match codepoint {
+        57358 => Some(KeyCode::CapsLock),
+        57359 => Some(KeyCode::ScrollLock),
+        57360 => Some(KeyCode::NumLock),
+        57361 => Some(KeyCode::PrintScreen),
+        57362 => Some(KeyCode::Pause),
+        57363 => Some(KeyCode::Menu),
+        57376 => Some(KeyCode::F(13)),
+        57377 => Some(KeyCode::F(14)),
+        57378 => Some(KeyCode::F(15)),
+        57379 => Some(KeyCode::F(16)),
+        57380 => Some(KeyCode::F(17)),
+        57381 => Some(KeyCode::F(18)),
+        57382 => Some(KeyCode::F(19)),
+        57383 => Some(KeyCode::F(20)),
+        57384 => Some(KeyCode::F(21)),
+        57385 => Some(KeyCode::F(22)),
+        57386 => Some(KeyCode::F(23)),
+        57387 => Some(KeyCode::F(24)),
+        57388 => Some(KeyCode::F(25)),
+        57389 => Some(KeyCode::F(26)),
+        57390 => Some(KeyCode::F(27)),
+        57391 => Some(KeyCode::F(28)),
+        57392 => Some(KeyCode::F(29)),
+        57393 => Some(KeyCode::F(30)),
+        57394 => Some(KeyCode::F(31)),
+        57395 => Some(KeyCode::F(32)),
+        57396 => Some(KeyCode::F(33)),
+        57397 => Some(KeyCode::F(34)),
+        57398 => Some(KeyCode::F(35)),
+        57428 => Some(KeyCode::Media(MediaKeyCode::Play)),
+        57429 => Some(KeyCode::Media(MediaKeyCode::Pause)),
+        57430 => Some(KeyCode::Media(MediaKeyCode::PlayPause)),
+        57431 => Some(KeyCode::Media(MediaKeyCode::Reverse)),
+        57432 => Some(KeyCode::Media(MediaKeyCode::Stop)),
+        57433 => Some(KeyCode::Media(MediaKeyCode::FastForward)),
+        57434 => Some(KeyCode::Media(MediaKeyCode::Rewind)),
+        57435 => Some(KeyCode::Media(MediaKeyCode::TrackNext)),
+        57436 => Some(KeyCode::Media(MediaKeyCode::TrackPrevious)),
+        57437 => Some(KeyCode::Media(MediaKeyCode::Record)),
+        57438 => Some(KeyCode::Media(MediaKeyCode::LowerVolume)),
+        57439 => Some(KeyCode::Media(MediaKeyCode::RaiseVolume)),
+        57440 => Some(KeyCode::Media(MediaKeyCode::MuteVolume)),
+        57441 => Some(KeyCode::Modifier(ModifierKeyCode::LeftShift)),
+        57442 => Some(KeyCode::Modifier(ModifierKeyCode::LeftControl)),
+        57443 => Some(KeyCode::Modifier(ModifierKeyCode::LeftAlt)),
+        57444 => Some(KeyCode::Modifier(ModifierKeyCode::LeftSuper)),
+        57445 => Some(KeyCode::Modifier(ModifierKeyCode::LeftHyper)),
+        57446 => Some(KeyCode::Modifier(ModifierKeyCode::LeftMeta)),
+        57447 => Some(KeyCode::Modifier(ModifierKeyCode::RightShift)),
+        57448 => Some(KeyCode::Modifier(ModifierKeyCode::RightControl)),
+        57449 => Some(KeyCode::Modifier(ModifierKeyCode::RightAlt)),
+        57450 => Some(KeyCode::Modifier(ModifierKeyCode::RightSuper)),
+        57451 => Some(KeyCode::Modifier(ModifierKeyCode::RightHyper)),
+        57452 => Some(KeyCode::Modifier(ModifierKeyCode::RightMeta)),
+        57453 => Some(KeyCode::Modifier(ModifierKeyCode::IsoLevel3Shift)),
+        57454 => Some(KeyCode::Modifier(ModifierKeyCode::IsoLevel5Shift)),
+        _ => None,
+    } {
+        return Some((keycode, KeyEventState::empty()));
+    }
+
+    None
+}
+
+/// Remembers the side of a pressed or released modifier key in `held` and returns the sides of
+/// the modifiers of `key_event`.
+///
+/// The kitty protocol only tells the side with the events of the modifier keys themselves, so
+/// the other keys get the sides of the last ones reported as held. The modifiers of the event
+/// drop sides whose release was missed, e.g. while another window had the focus.
+fn track_held_modifier_sides(
+    held: &mut ExtendedModifiers,
+    key_event: &KeyEvent,
+) -> ExtendedModifiers {
+    if let KeyCode::Modifier(code) = key_event.code {
+        if let Some(side) = ExtendedModifiers::from_modifier_key(code) {
+            held.set(side, key_event.kind != KeyEventKind::Release);
+        }
+    }
+    *held &= ExtendedModifiers::sides_of(key_event.modifiers);
+    *held
+}
+
+pub(crate) fn parse_csi_u_encoded_key_code(
+    buffer: &[u8],
+    newline_is_enter: bool,
+) -> io::Result<Option<InternalEvent>> {
+    assert!(buffer.starts_with(&[b'\x1B', b'['])); // ESC [
+    assert!(buffer.ends_with(&[b'u']));
+
+    // This function parses `CSI … u` sequences. These are sequences defined in either
+    // the `CSI u` (a.k.a. "Fix Keyboard Input on Terminals - Please", https://www.leonerd.org.uk/hacks/fixterms/)
+    // or Kitty Keyboard Protocol (https://sw.kovidgoyal.net/kitty/keyboard-protocol/) specifications.
+    // This CSI sequence is a tuple of semicolon-separated numbers.
+    let s = std::str::from_utf8(&buffer[2..buffer.len() - 1])
+        .map_err(|_| could_not_parse_event_error())?;
+    let mut split = s.split(';');
+
+    // In `CSI u`, this is parsed as:
+    //
+    //     CSI codepoint ; modifiers u
+    //     codepoint: ASCII Dec value
+    //
+    // The Kitty Keyboard Protocol extends this with optional components that can be
+    // enabled progressively. The full sequence is parsed as:
+    //
+    //     CSI unicode-key-code:alternate-key-codes ; modifiers:event-type ; text-as-codepoints u
+    let mut codepoints = split
+        .next()
+        .ok_or_else(could_not_parse_event_error)?
+        .split(':');
+
+    let codepoint = codepoints
+        .next()
+        .ok_or_else(could_not_parse_event_error)?
+        .parse::<u32>()
+        .map_err(|_| could_not_parse_event_error())?;
+
+    let (mut modifiers, kind, state_from_modifiers) =
+        if let Ok((modifier_mask, kind_code)) = modifier_and_kind_parsed(&mut split) {
+            (
+                parse_modifiers(modifier_mask),
+                parse_key_event_kind(kind_code),
+                parse_modifiers_to_state(modifier_mask),
+            )
+        } else {
+            (KeyModifiers::NONE, KeyEventKind::Press, KeyEventState::NONE)
+        };
+
+    let (mut keycode, state_from_keycode) = {
+        if let Some((special_key_code, state)) = translate_functional_key_code(codepoint) {
+            (special_key_code, state)
+        } else if let Some(c) = char::from_u32(codepoint) {
+            (
+                match c {
+                    '\x1B' => KeyCode::Esc,
+                    '\r' => KeyCode::Enter,
+                    // Issue #371: \n = 0xA, which is also the keycode for Ctrl+J. The only reason we get
+                    // newlines as input is because the terminal converts \r into \n for us. When we
+                    // enter raw mode, we disable that, so \n no longer has any meaning - it's better to
+                    // use Ctrl+J. Waiting to handle it here means it gets picked up later
+                    '\n' if newline_is_enter => KeyCode::Enter,
+                    '\t' => {
+                        if modifiers.contains(KeyModifiers::SHIFT) {
+                            KeyCode::BackTab
+                        } else {
+                            KeyCode::Tab
+                        }
+                    }
+                    '\x7F' => KeyCode::Backspace,
+                    _ => KeyCode::Char(c),
+                },
+                KeyEventState::empty(),
+            )
+        } else {
+            return Err(could_not_parse_event_error());
+        }
+    };
+
+    if let KeyCode::Modifier(modifier_keycode) = keycode {
+        match modifier_keycode {
+            ModifierKeyCode::LeftAlt | ModifierKeyCode::RightAlt => {
+                modifiers.set(KeyModifiers::ALT, true)
+            }
+            ModifierKeyCode::LeftControl | ModifierKeyCode::RightControl => {
+                modifiers.set(KeyModifiers::CONTROL, true)
+            }
+            ModifierKeyCode::LeftShift | ModifierKeyCode::RightShift => {
+                modifiers.set(KeyModifiers::SHIFT, true)
+            }
+            ModifierKeyCode::LeftSuper | ModifierKeyCode::RightSuper => {
+                modifiers.set(KeyModifiers::SUPER, true)
+            }
+            ModifierKeyCode::LeftHyper | ModifierKeyCode::RightHyper => {
+                modifiers.set(KeyModifiers::HYPER, true)
+            }
+            ModifierKeyCode::LeftMeta | ModifierKeyCode::RightMeta => {
+                modifiers.set(KeyModifiers::META, true)
+            }
+            _ => {}
+        }
+    }
+
+    // When the "report alternate keys" flag is enabled in the Kitty Keyboard Protocol
+    // and the terminal sends a keyboard event containing shift, the sequence will
+    // contain an additional codepoint separated by a ':' character which contains
+    // the shifted character according to the keyboard layout.
+    let shifted_codepoint = codepoints
+        .next()
+        .and_then(|codepoint| codepoint.parse::<u32>().ok());
+    if modifiers.contains(KeyModifiers::SHIFT) {
+        if let Some(shifted_c) = shifted_codepoint.and_then(char::from_u32) {
+            keycode = KeyCode::Char(shifted_c);
+            modifiers.set(KeyModifiers::SHIFT, false);
+        }
+    }
+
+    // The third codepoint is the key at the same place on the standard PC-101 layout, if it
+    // differs from the key, e.g. `w` for `z` on an AZERTY layout. The shifted codepoint may be
+    // left empty before it.
+    let base_layout_key = codepoints
+        .next()
+        .and_then(|codepoint| codepoint.parse::<u32>().ok())
+        .and_then(|codepoint| {
+            translate_functional_key_code(codepoint)
+                .map(|(keycode, _)| keycode)
+                .or_else(|| char::from_u32(codepoint).map(KeyCode::Char))
+        });
+
+    let mut key_event = KeyEvent::new_with_kind_and_state(
+        keycode,
+        modifiers,
+        kind,
+        state_from_keycode | state_from_modifiers,
+    );
+    key_event.physical_key = base_layout_key.map(PhysicalKey::BaseLayout);
+    let input_event = Event::Key(key_event);
+
+    Ok(Some(InternalEvent::Event(input_event)))
+}
+
+pub(crate) fn parse_csi_special_key_code(buffer: &[u8]) -> io::Result<Option<InternalEvent>> {
+    assert!(buffer.starts_with(&[b'\x1B', b'['])); // ESC [
+    assert!(buffer.ends_with(&[b'~']));
+
+    let s = std::str::from_utf8(&buffer[2..buffer.len() - 1])
+        .map_err(|_| could_not_parse_event_error())?;
+    let mut split = s.split(';');
+
+    // This CSI sequence can be a list of semicolon-separated numbers.
+    let first = next_parsed::<u8>(&mut split)?;
+
+    let (modifiers, kind, state) =
+        if let Ok((modifier_mask, kind_code)) = modifier_and_kind_parsed(&mut split) {
+            (
+                parse_modifiers(modifier_mask),
+                parse_key_event_kind(kind_code),
+                parse_modifiers_to_state(modifier_mask),
+            )
+        } else {
+            (KeyModifiers::NONE, KeyEventKind::Press, KeyEventState::NONE)
+        };
+
+    let keycode = special_key_code(first).ok_or_else(could_not_parse_event_error)?;
+
+    let input_event = Event::Key(KeyEvent::new_with_kind_and_state(
+        keycode, modifiers, kind, state,
+    ));
+
+    Ok(Some(InternalEvent::Event(input_event)))
+}
+
+/// Returns the key of a `CSI number ~` sequence.
+///
+/// F13 to F20 are `CSI 25 ~` to `CSI 34 ~`, skipping 27 and 30, which xterm, rxvt and
+/// Terminal.app write for the keys of extended keyboards, or for Shift+F3 to Shift+F10.
+fn special_key_code(number: u8) -> Option<KeyCode> {
+    let keycode = match number {
+        1 | 7 => KeyCode::Home,
+        2 => KeyCode::Insert,
+        3 => KeyCode::Delete,
+        4 | 8 => KeyCode::End,
+        5 => KeyCode::PageUp,
+        6 => KeyCode::PageDown,
+        v @ 11..=15 => KeyCode::F(v - 10),
+        v @ 17..=21 => KeyCode::F(v - 11),
+        v @ 23..=26 => KeyCode::F(v - 12),
+        v @ 28..=29 => KeyCode::F(v - 13),
+        v @ 31..=34 => KeyCode::F(v - 14),
+        _ => return None,
+    };
+    Some(keycode)
+}
+
+/// Returns whether the sequence is an rxvt key with modifiers, `CSI number $`, which can't be
+/// told complete by its final byte, as `$` is an intermediate byte elsewhere.
+fn is_rxvt_shifted_key_code(buffer: &[u8]) -> bool {
+    buffer.len() > 3
+        && buffer.ends_with(b"$")
+        && buffer[2..buffer.len() - 1].iter().all(u8::is_ascii_digit)
+}
+
+pub(crate) fn parse_csi_rxvt_special_key_code(buffer: &[u8]) -> io::Result<Option<InternalEvent>> {
+    // rxvt writes the modifiers of the special keys as the final byte instead of a parameter:
+    // ESC [ number $    with Shift
+    // ESC [ number ^    with Control
+    // ESC [ number @    with Control and Shift
+    //
+    // Shift+F1 to Shift+F10 are F11 to F20, `CSI 23 ~` to `CSI 34 ~`, so Shift+F11 and
+    // Shift+F12, `CSI 23 $` and `CSI 24 $`, are F21 and F22, like its terminfo entry calls them.
+    // The keys with Control keep the modifier.
+    assert!(buffer.starts_with(b"\x1B[")); // ESC [
+
+    let modifiers = match buffer[buffer.len() - 1] {
+        b'$' => KeyModifiers::SHIFT,
+        b'^' => KeyModifiers::CONTROL,
+        b'@' => KeyModifiers::CONTROL | KeyModifiers::SHIFT,
+        _ => return Err(could_not_parse_event_error()),
+    };
+    let number = std::str::from_utf8(&buffer[2..buffer.len() - 1])
+        .map_err(|_| could_not_parse_event_error())?
+        .parse::<u8>()
+        .map_err(|_| could_not_parse_event_error())?;
+    let (keycode, modifiers) = match (number, modifiers) {
+        (23 | 24, KeyModifiers::SHIFT) => (KeyCode::F(number - 2), KeyModifiers::NONE),
+        _ => (
+            special_key_code(number).ok_or_else(could_not_parse_event_error)?,
+            modifiers,
+        ),
+    };
+
+    Ok(Some(InternalEvent::Event(Event::Key(KeyEvent::new(
+        keycode, modifiers,
+    )))))
+}
+
+pub(crate) fn parse_csi_rxvt_mouse(buffer: &[u8]) -> io::Result<Option<InternalEvent>> {
+    // rxvt mouse encoding:
+    // ESC [ Cb ; Cx ; Cy ; M
+
+    assert!(buffer.starts_with(&[b'\x1B', b'['])); // ESC [
+    assert!(buffer.ends_with(&[b'M']));
+
+    let s = std::str::from_utf8(&buffer[2..buffer.len() - 1])
+        .map_err(|_| could_not_parse_event_error())?;
+    let mut split = s.split(';');
+
+    let cb = next_parsed::<u8>(&mut split)?
+        .checked_sub(32)
+        .ok_or_else(could_not_parse_event_error)?;
+    let (kind, modifiers) = parse_cb(cb)?;
+
+    let cx = next_parsed::<u16>(&mut split)? - 1;
+    let cy = next_parsed::<u16>(&mut split)? - 1;
+
+    Ok(Some(InternalEvent::Event(Event::Mouse(MouseEvent {
+        kind,
+        column: cx,
+        row: cy,
+        modifiers,
+    }))))
+}
+
+pub(crate) fn parse_csi_normal_mouse(
+    buffer: &[u8],
+    utf8: bool,
+) -> io::Result<Option<InternalEvent>> {
+    // Normal mouse encoding: ESC [ M CB Cx Cy (6 characters only).
+    // UTF-8 mouse encoding: the same, but CB, Cx and Cy are UTF-8 encoded characters.
+
+    assert!(buffer.starts_with(&[b'\x1B', b'[', b'M'])); // ESC [ M
+
+    let mut values = [0u16; 3];
+    let mut rest = &buffer[3..];
+    for value in &mut values {
+        let len = match rest.first() {
+            None => return Ok(None),
+            Some(byte) if !utf8 || *byte < 0x80 => 1,
+            Some(byte) if byte & 0xE0 == 0xC0 => 2,
+            Some(_) => return Err(could_not_parse_event_error()),
+        };
+        if rest.len() < len {
+            return Ok(None);
+        }
+        *value = match len {
+            1 => u16::from(rest[0]),
+            _ => u16::from(rest[0] & 0x1F) << 6 | u16::from(rest[1] & 0x3F),
+        };
+        rest = &rest[len..];
+    }
+
+    let cb = values[0]
+        .checked_sub(32)
+        .and_then(|cb| u8::try_from(cb).ok())
+        .ok_or_else(could_not_parse_event_error)?;
+    let (kind, modifiers) = parse_cb(cb)?;
+
+    // See http://www.xfree86.org/current/ctlseqs.html#Mouse%20Tracking
+    // The upper left character position on the terminal is denoted as 1,1.
+    // Subtract 1 to keep it synced with cursor
+    let cx = values[1].saturating_sub(32).saturating_sub(1);
+    let cy = values[2].saturating_sub(32).saturating_sub(1);
+
+    Ok(Some(InternalEvent::Event(Event::Mouse(MouseEvent {
+        kind,
+        column: cx,
+        row: cy,
+        modifiers,
+    }))))
+}
+
+pub(crate) fn parse_csi_sgr_mouse(buffer: &[u8]) -> io::Result<Option<InternalEvent>> {
+    // ESC [ < Cb ; Cx ; Cy (;) (M or m)
+
+    assert!(buffer.starts_with(&[b'\x1B', b'[', b'<'])); // ESC [ <
+
+    if !buffer.ends_with(&[b'm']) && !buffer.ends_with(&[b'M']) {
+        return Ok(None);
+    }
+
+    let s = std::str::from_utf8(&buffer[3..buffer.len() - 1])
+        .map_err(|_| could_not_parse_event_error())?;
+    let mut split = s.split(';');
+
+    let cb = next_parsed::<u8>(&mut split)?;
+    let (kind, modifiers) = parse_cb(cb)?;
+
+    // See http://www.xfree86.org/current/ctlseqs.html#Mouse%20Tracking
+    // The upper left character position on the terminal is denoted as 1,1.
+    // Subtract 1 to keep it synced with cursor
+    let cx = next_parsed::<u16>(&mut split)? - 1;
+    let cy = next_parsed::<u16>(&mut split)? - 1;
+
+    // When button 3 in Cb is used to represent mouse release, you can't tell which button was
+    // released. SGR mode solves this by having the sequence end with a lowercase m if it's a
+    // button release and an uppercase M if it's a button press.
+    //
+    // We've already checked that the last character is a lowercase or uppercase M at the start of
+    // this function, so we just need one if.
+    let kind = if buffer.last() == Some(&b'm') {
+        match kind {
+            MouseEventKind::Down(button) => MouseEventKind::Up(button),
+            other => other,
+        }
+    } else {
+        kind
+    };
+
+    Ok(Some(InternalEvent::Event(Event::Mouse(MouseEvent {
+        kind,
+        column: cx,
+        row: cy,
+        modifiers,
+    }))))
+}
+
+/// Cb is the byte of a mouse input that contains the button being used, the key modifiers being
+/// held and whether the mouse is dragging or not.
+///
+/// Bit layout of cb, from low to high:
+///
+/// - button number
+/// - button number
+/// - shift
+/// - meta (alt)
+/// - control
+/// - mouse is dragging
+/// - button number
+/// - button number
+fn parse_cb(cb: u8) -> io::Result<(MouseEventKind, KeyModifiers)> {
+    let button_number = (cb & 0b0000_0011) | ((cb & 0b1100_0000) >> 4);
+    let dragging = cb & 0b0010_0000 == 0b0010_0000;
+
+    let kind = match (button_number, dragging) {
+        (0, false) => MouseEventKind::Down(MouseButton::Left),
+        (1, false) => MouseEventKind::Down(MouseButton::Middle),
+        (2, false) => MouseEventKind::Down(MouseButton::Right),
+        (0, true) => MouseEventKind::Drag(MouseButton::Left),
+        (1, true) => MouseEventKind::Drag(MouseButton::Middle),
+        (2, true) => MouseEventKind::Drag(MouseButton::Right),
+        (3, false) => MouseEventKind::Up(MouseButton::Left),
+        (3, true) | (4, true) | (5, true) => MouseEventKind::Moved,
+        (4, false) => MouseEventKind::ScrollUp,
+        (5, false) => MouseEventKind::ScrollDown,
+        (6, false) => MouseEventKind::ScrollLeft,
+        (7, false) => MouseEventKind::ScrollRight,
+        // We do not support other buttons.
+        _ => return Err(could_not_parse_event_error()),
+    };
+
+    let mut modifiers = KeyModifiers::empty();
+
+    if cb & 0b0000_0100 == 0b0000_0100 {
+        modifiers |= KeyModifiers::SHIFT;
+    }
+    if cb & 0b0000_1000 == 0b0000_1000 {
+        modifiers |= KeyModifiers::ALT;
+    }
+    if cb & 0b0001_0000 == 0b0001_0000 {
+        modifiers |= KeyModifiers::CONTROL;
+    }
+
+    Ok((kind, modifiers))
+}
+
+#[cfg(feature = "bracketed-paste")]
+pub(crate) fn parse_csi_bracketed_paste(buffer: &[u8]) -> io::Result<Option<InternalEvent>> {
+    // ESC [ 2 0 0 ~ pasted text ESC 2 0 1 ~
+    assert!(buffer.starts_with(b"\x1B[200~"));
+
+    if !buffer.ends_with(b"\x1b[201~") {
+        Ok(None)
+    } else {
+        let paste = String::from_utf8_lossy(&buffer[6..buffer.len() - 6]).to_string();
+        Ok(Some(InternalEvent::Event(Event::Paste(
+            crate::event::Paste::new(paste, PasteKind::Bracketed),
+        ))))
+    }
+}
+
+pub(crate) fn parse_utf8_char(buffer: &[u8]) -> io::Result<Option<char>> {
+    match std::str::from_utf8(buffer) {
+        Ok(s) => {
+            let ch = s.chars().next().ok_or_else(could_not_parse_event_error)?;
+
+            Ok(Some(ch))
+        }
+        Err(_) => {
+            // from_utf8 failed, but we have to check if we need more bytes for code point
+            // and if all the bytes we have no are valid
+
+            let required_bytes = match buffer[0] {
+                // https://en.wikipedia.org/wiki/UTF-8#Description
+                (0x00..=0x7F) => 1, // 0xxxxxxx
+                (0xC0..=0xDF) => 2, // 110xxxxx 10xxxxxx
+                (0xE0..=0xEF) => 3, // 1110xxxx 10xxxxxx 10xxxxxx
+                (0xF0..=0xF7) => 4, // 11110xxx 10xxxxxx 10xxxxxx 10xxxxxx
+                (0x80..=0xBF) | (0xF8..=0xFF) => return Err(could_not_parse_event_error()),
+            };
+
+            // More than 1 byte, check them for 10xxxxxx pattern
+            if required_bytes > 1 && buffer.len() > 1 {
+                for byte in &buffer[1..] {
+                    if byte & !0b0011_1111 != 0b1000_0000 {
+                        return Err(could_not_parse_event_error());
+                    }
+                }
+            }
+
+            if buffer.len() < required_bytes {
+                // All bytes looks good so far, but we need more of them
+                Ok(None)
+            } else {
+                Err(could_not_parse_event_error())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::event::{KeyEventState, KeyModifiers, MouseButton, MouseEvent};
+
+    use super::*;
+
+    #[test]
+    fn test_esc_key() {
+        assert_eq!(
+            parse_event(b"\x1B", false).unwrap(),
+            Some(InternalEvent::Event(Event::Key(KeyCode::Esc.into()))),
+        );
+    }
+
+    #[test]
+    fn test_possible_esc_sequence() {
+        assert_eq!(parse_event(b"\x1B", true).unwrap(), None,);
+    }
+
+    #[test]
+    fn test_alt_key() {
+        assert_eq!(
+            parse_event(b"\x1Bc", false).unwrap(),
+            Some(InternalEvent::Event(Event::Key(KeyEvent::new(
+                KeyCode::Char('c'),
+                KeyModifiers::ALT
+            )))),
+        );
+    }
+
+    #[test]
+    fn test_alt_shift() {
+        assert_eq!(
+            parse_event(b"\x1BH", false).unwrap(),
+            Some(InternalEvent::Event(Event::Key(KeyEvent::new(
+                KeyCode::Char('H'),
+                KeyModifiers::ALT | KeyModifiers::SHIFT
+            )))),
+        );
+    }
+
+    #[test]
+    fn test_alt_ctrl() {
+        assert_eq!(
+            parse_event(b"\x1B\x14", false).unwrap(),
+            Some(InternalEvent::Event(Event::Key(KeyEvent::new(
+                KeyCode::Char('t'),
+                KeyModifiers::ALT | KeyModifiers::CONTROL
+            )))),
+        );
+    }
+
+    #[test]
+    fn test_parse_event_subsequent_calls() {
+        // The main purpose of this test is to check if we're passing
+        // correct slice to other parse_ functions.
+
+        // parse_csi_cursor_position
+        assert_eq!(
+            parse_event(b"\x1B[20;10R", false).unwrap(),
+            Some(InternalEvent::CursorPosition(9, 19))
+        );
+
+        // parse_csi
+        assert_eq!(
+            parse_event(b"\x1B[D", false).unwrap(),
+            Some(InternalEvent::Event(Event::Key(KeyCode::Left.into()))),
+        );
+
+        // parse_csi_modifier_key_code
+        assert_eq!(
+            parse_event(b"\x1B[2D", false).unwrap(),
+            Some(InternalEvent::Event(Event::Key(KeyEvent::new(
+                KeyCode::Left,
+                KeyModifiers::SHIFT
+            ))))
+        );
+
+        // parse_csi_special_key_code
+        assert_eq!(
+            parse_event(b"\x1B[3~", false).unwrap(),
+            Some(InternalEvent::Event(Event::Key(KeyCode::Delete.into()))),
+        );
+
+        // parse_csi_bracketed_paste
+        #[cfg(feature = "bracketed-paste")]
+        assert_eq!(
+            parse_event(b"\x1B[200~on and on and on\x1B[201~", false).unwrap(),
+            Some(InternalEvent::Event(Event::Paste(
+                crate::event::Paste::new("on and on and on", PasteKind::Bracketed)
+            ))),
+        );
+
+        // parse_csi_rxvt_mouse
+        assert_eq!(
+            parse_event(b"\x1B[32;30;40;M", false).unwrap(),
+            Some(InternalEvent::Event(Event::Mouse(MouseEvent {
+                kind: MouseEventKind::Down(MouseButton::Left),
+                column: 29,
+                row: 39,
+                modifiers: KeyModifiers::empty(),
+            })))
+        );
+
+        // parse_csi_normal_mouse
+        assert_eq!(
+            parse_event(b"\x1B[M0\x60\x70", false).unwrap(),
+            Some(InternalEvent::Event(Event::Mouse(MouseEvent {
+                kind: MouseEventKind::Down(MouseButton::Left),
+                column: 63,
+                row: 79,
+                modifiers: KeyModifiers::CONTROL,
+            })))
+        );
+
+        // parse_csi_sgr_mouse
+        assert_eq!(
+            parse_event(b"\x1B[<0;20;10;M", false).unwrap(),
+            Some(InternalEvent::Event(Event::Mouse(MouseEvent {
+                kind: MouseEventKind::Down(MouseButton::Left),
+                column: 19,
+                row: 9,
+                modifiers: KeyModifiers::empty(),
+            })))
+        );
+
+        // parse_utf8_char
+        assert_eq!(
+            parse_event("Ž".as_bytes(), false).unwrap(),
+            Some(InternalEvent::Event(Event::Key(KeyEvent::new(
+                KeyCode::Char('Ž'),
+                KeyModifiers::SHIFT
+            )))),
+        );
+    }
+
+    #[test]
+    fn test_parse_event() {
+        assert_eq!(
+            parse_event(b"\t", false).unwrap(),
+            Some(InternalEvent::Event(Event::Key(KeyCode::Tab.into()))),
+        );
+    }
+
+    #[test]
+    fn test_parse_csi_cursor_position() {
+        assert_eq!(
+            parse_csi_cursor_position(b"\x1B[20;10R").unwrap(),
+            Some(InternalEvent::CursorPosition(9, 19))
+        );
+    }
+
+    #[test]
+    fn test_parse_csi_primary_device_attributes() {
+        assert_eq!(
+            parse_csi_primary_device_attributes(b"\x1B[?64;1;4;22c").unwrap(),
+            Some(InternalEvent::PrimaryDeviceAttributes(vec![64, 1, 4, 22]))
+        );
+    }
+
+    #[test]
+    fn test_parse_csi_secondary_device_attributes() {
+        assert_eq!(
+            parse_event(b"\x1B[>84;0;0c", false).unwrap(),
+            Some(InternalEvent::SecondaryDeviceAttributes(vec![84, 0, 0]))
+        );
+        assert_eq!(parse_event(b"\x1B[>84;0", true).unwrap(), None);
+    }
+
+    #[test]
+    fn test_parse_csi_mode_report() {
+        assert_eq!(
+            parse_event(b"\x1B[?2026;2$y", false).unwrap(),
+            Some(InternalEvent::ModeReport(2026, 2))
+        );
+        assert_eq!(parse_event(b"\x1B[?2026;2$", true).unwrap(), None);
+        assert!(parse_event(b"\x1B[?2026;2y", false).is_err());
+    }
+
+    #[test]
+    fn test_parse_csi_window_report() {
+        assert_eq!(
+            parse_event(b"\x1B[6;16;8t", false).unwrap(),
+            Some(InternalEvent::CellSize(8, 16))
+        );
+        assert!(parse_csi_window_report(b"\x1B[6;16t").is_err());
+    }
+
+    #[test]
+    fn test_parse_dcs_terminal_version() {
+        assert_eq!(
+            parse_event(b"\x1BP>|WezTerm 20240203-110809-5046fc22\x1B\\", false).unwrap(),
+            Some(InternalEvent::TerminalVersion(
+                "WezTerm 20240203-110809-5046fc22".to_string()
+            ))
+        );
+        assert_eq!(parse_event(b"\x1BP>|kitty(0.3", true).unwrap(), None);
+        assert!(parse_event(b"\x1BP>x", true).is_err());
+        assert!(parse_event(b"\x1BP>|kitty\r", true).is_err());
+    }
+
+    #[test]
+    fn test_parse_dcs_setting_report() {
+        assert_eq!(
+            parse_event(b"\x1BP1$r0;38:2::1:2:3m\x1B\\", false).unwrap(),
+            Some(InternalEvent::SettingReport(Some(
+                "0;38:2::1:2:3m".to_string()
+            )))
+        );
+        assert_eq!(
+            parse_event(b"\x1BP0$r\x1B\\", false).unwrap(),
+            Some(InternalEvent::SettingReport(None))
+        );
+
+        // incomplete and Alt+Shift+P
+        assert_eq!(parse_event(b"\x1BP1$r0m\x1B", true).unwrap(), None);
+        assert_eq!(parse_event(b"\x1BP", true).unwrap(), None);
+        assert!(parse_event(b"\x1BP1x", true).is_err());
+        assert!(parse_event(b"\x1BP1$r0m\x1Bx", true).is_err());
+        assert_eq!(
+            parse_event(b"\x1BP", false).unwrap(),
+            Some(InternalEvent::Event(Event::Key(KeyEvent::new(
+                KeyCode::Char('P'),
+                KeyModifiers::ALT | KeyModifiers::SHIFT
+            ))))
+        );
+    }
+
+    #[test]
+    fn test_parse_osc_color_report() {
+        assert_eq!(
+            parse_event(b"\x1B]11;rgb:ffff/8080/0000\x1B\\", false).unwrap(),
+            Some(InternalEvent::Event(Event::ColorReport {
+                slot: ColorSlot::Background,
+                color: Color::Rgb {
+                    r: 255,
+                    g: 128,
+                    b: 0
+                },
+            })),
+        );
+        assert_eq!(
+            parse_event(b"\x1B]4;1;rgb:f/0/00\x07", false).unwrap(),
+            Some(InternalEvent::Event(Event::ColorReport {
+                slot: ColorSlot::Palette(1),
+                color: Color::Rgb { r: 255, g: 0, b: 0 },
+            })),
+        );
+
+        assert_eq!(
+            parse_event(b"\x1B]99;i=7;\x1B\\", false).unwrap(),
+            Some(InternalEvent::Event(Event::NotificationActivated(7))),
+        );
+        assert_eq!(
+            parse_event(b"\x1B]99;i=7:p=close;\x07", false).unwrap(),
+            Some(InternalEvent::Event(Event::NotificationClosed(7))),
+        );
+        assert!(parse_event(b"\x1B]99;i=foo;\x1B\\", false).is_err());
+
+        // incomplete, another OSC and Alt+]
+        assert_eq!(parse_event(b"\x1B]10;rgb:0/0/0\x1B", true).unwrap(), None);
+        assert_eq!(parse_event(b"\x1B]", true).unwrap(), None);
+        assert!(parse_event(b"\x1B]52;c;Zm9v\x07", false).is_err());
+        assert_eq!(
+            parse_event(b"\x1B]", false).unwrap(),
+            Some(InternalEvent::Event(Event::Key(KeyEvent::new(
+                KeyCode::Char(']'),
+                KeyModifiers::ALT
+            )))),
+        );
+    }
+
+    #[test]
+    fn test_parse_csi() {
+        assert_eq!(
+            parse_csi(b"\x1B[D", &Settings::default()).unwrap(),
+            Some(InternalEvent::Event(Event::Key(KeyCode::Left.into()))),
+        );
+    }
+
+    #[test]
+    fn test_parse_csi_modifier_key_code() {
+        assert_eq!(
+            parse_csi_modifier_key_code(b"\x1B[2D").unwrap(),
+            Some(InternalEvent::Event(Event::Key(KeyEvent::new(
+                KeyCode::Left,
+                KeyModifiers::SHIFT
+            )))),
+        );
+    }
+
+    #[test]
+    fn test_parse_csi_special_key_code() {
+        assert_eq!(
+            parse_csi_special_key_code(b"\x1B[3~").unwrap(),
+            Some(InternalEvent::Event(Event::Key(KeyCode::Delete.into()))),
+        );
+    }
+
+    #[test]
+    fn test_parse_f13_to_f20() {
+        // xterm, rxvt and Terminal.app
+        let sequences: [&[u8]; 8] = [
+            b"\x1B[25~",
+            b"\x1B[26~",
+            b"\x1B[28~",
+            b"\x1B[29~",
+            b"\x1B[31~",
+            b"\x1B[32~",
+            b"\x1B[33~",
+            b"\x1B[34~",
+        ];
+        for (n, sequence) in (13..=20).zip(sequences) {
+            assert_eq!(
+                parse_event(sequence, false).unwrap(),
+                Some(InternalEvent::Event(Event::Key(KeyCode::F(n).into()))),
+            );
+        }
+        assert_eq!(
+            parse_event(b"\x1B[34;5~", false).unwrap(),
+            Some(InternalEvent::Event(Event::Key(KeyEvent::new(
+                KeyCode::F(20),
+                KeyModifiers::CONTROL
+            )))),
+        );
+        assert!(parse_event(b"\x1B[27~", false).is_err());
+        assert!(parse_event(b"\x1B[30~", false).is_err());
+    }
+
+    #[test]
+    fn test_parse_csi_special_key_code_multiple_values_not_supported() {
+        assert_eq!(
+            parse_csi_special_key_code(b"\x1B[3;2~").unwrap(),
+            Some(InternalEvent::Event(Event::Key(KeyEvent::new(
+                KeyCode::Delete,
+                KeyModifiers::SHIFT
+            )))),
+        );
+    }
+
+    #[test]
+    fn test_parse_rxvt_and_old_xterm_modified_keys() {
+        let key = |code, modifiers| {
+            Some(InternalEvent::Event(Event::Key(KeyEvent::new(
+                code, modifiers,
+            ))))
+        };
+
+        assert_eq!(
+            parse_event(b"\x1B[23$", false).unwrap(),
+            key(KeyCode::F(21), KeyModifiers::NONE)
+        );
+        assert_eq!(
+            parse_event(b"\x1B[24$", false).unwrap(),
+            key(KeyCode::F(22), KeyModifiers::NONE)
+        );
+        assert_eq!(
+            parse_event(b"\x1B[23^", false).unwrap(),
+            key(KeyCode::F(11), KeyModifiers::CONTROL)
+        );
+        assert_eq!(
+            parse_event(b"\x1B[24^", false).unwrap(),
+            key(KeyCode::F(12), KeyModifiers::CONTROL)
+        );
+        assert_eq!(
+            parse_event(b"\x1B[15@", false).unwrap(),
+            key(KeyCode::F(5), KeyModifiers::CONTROL | KeyModifiers::SHIFT)
+        );
+        assert_eq!(
+            parse_event(b"\x1B[5$", false).unwrap(),
+            key(KeyCode::PageUp, KeyModifiers::SHIFT)
+        );
+        assert_eq!(
+            parse_event(b"\x1B[a", false).unwrap(),
+            key(KeyCode::Up, KeyModifiers::SHIFT)
+        );
+        assert_eq!(
+            parse_event(b"\x1BOd", false).unwrap(),
+            key(KeyCode::Left, KeyModifiers::CONTROL)
+        );
+        assert_eq!(parse_event(b"\x1BO2", true).unwrap(), None);
+        assert_eq!(
+            parse_event(b"\x1BO2P", false).unwrap(),
+            key(KeyCode::F(1), KeyModifiers::SHIFT)
+        );
+        assert_eq!(
+            parse_event(b"\x1B[1;5S", false).unwrap(),
+            key(KeyCode::F(4), KeyModifiers::CONTROL)
+        );
+
+        // a mode report still waits for its final byte
+        assert_eq!(parse_event(b"\x1B[4;1$", true).unwrap(), None);
+    }
+
+    #[cfg(feature = "bracketed-paste")]
+    #[test]
+    fn test_parse_csi_bracketed_paste() {
+        //
+        assert_eq!(
+            parse_event(b"\x1B[200~o", false).unwrap(),
+            None,
+            "A partial bracketed paste isn't parsed"
+        );
+        assert_eq!(
+            parse_event(b"\x1B[200~o\x1B[2D", false).unwrap(),
+            None,
+            "A partial bracketed paste containing another escape code isn't parsed"
+        );
+        assert_eq!(
+            parse_event(b"\x1B[200~o\x1B[2D\x1B[201~", false).unwrap(),
+            Some(InternalEvent::Event(Event::Paste(
+                crate::event::Paste::new("o\x1B[2D", PasteKind::Bracketed)
+            )))
+        );
+    }
+
+    #[test]
+    fn test_parse_csi_focus() {
+        assert_eq!(
+            parse_csi(b"\x1B[O", &Settings::default()).unwrap(),
+            Some(InternalEvent::Event(Event::FocusLost))
+        );
+    }
+
+    #[test]
+    fn test_parse_csi_rxvt_mouse() {
+        assert_eq!(
+            parse_csi_rxvt_mouse(b"\x1B[32;30;40;M").unwrap(),
+            Some(InternalEvent::Event(Event::Mouse(MouseEvent {
+                kind: MouseEventKind::Down(MouseButton::Left),
+                column: 29,
+                row: 39,
+                modifiers: KeyModifiers::empty(),
+            })))
+        );
+    }
+
+    #[test]
+    fn test_parse_utf8_mouse() {
+        // column 299 and row 5, with the column encoded in two bytes
+        assert_eq!(
+            parse_csi_normal_mouse(b"\x1B[M \xC5\x8C&", true).unwrap(),
+            Some(InternalEvent::Event(Event::Mouse(MouseEvent {
+                kind: MouseEventKind::Down(MouseButton::Left),
+                column: 299,
+                row: 5,
+                modifiers: KeyModifiers::empty(),
+            })))
+        );
+        assert_eq!(parse_csi_normal_mouse(b"\x1B[M \xC5", true).unwrap(), None);
+
+        let mut parser = Parser::new();
+        parser.set_utf8_mouse(true);
+        parser.advance(b"\x1B[M \xC5\x8C&", false);
+        assert!(matches!(
+            parser.next(),
+            Some(Event::Mouse(MouseEvent { column: 299, .. }))
+        ));
+    }
+
+    #[test]
+    fn test_parse_csi_normal_mouse() {
+        assert_eq!(
+            parse_csi_normal_mouse(b"\x1B[M0\x60\x70", false).unwrap(),
+            Some(InternalEvent::Event(Event::Mouse(MouseEvent {
+                kind: MouseEventKind::Down(MouseButton::Left),
+                column: 63,
+                row: 79,
+                modifiers: KeyModifiers::CONTROL,
+            })))
+        );
+    }
+
+    #[test]
+    fn test_parse_csi_sgr_mouse() {
+        assert_eq!(
+            parse_csi_sgr_mouse(b"\x1B[<0;20;10;M").unwrap(),
+            Some(InternalEvent::Event(Event::Mouse(MouseEvent {
+                kind: MouseEventKind::Down(MouseButton::Left),
+                column: 19,
+                row: 9,
+                modifiers: KeyModifiers::empty(),
+            })))
+        );
+        assert_eq!(
+            parse_csi_sgr_mouse(b"\x1B[<0;20;10M").unwrap(),
+            Some(InternalEvent::Event(Event::Mouse(MouseEvent {
+                kind: MouseEventKind::Down(MouseButton::Left),
+                column: 19,
+                row: 9,
+                modifiers: KeyModifiers::empty(),
+            })))
+        );
+        assert_eq!(
+            parse_csi_sgr_mouse(b"\x1B[<0;20;10;m").unwrap(),
+            Some(InternalEvent::Event(Event::Mouse(MouseEvent {
+                kind: MouseEventKind::Up(MouseButton::Left),
+                column: 19,
+                row: 9,
+                modifiers: KeyModifiers::empty(),
+            })))
+        );
+        assert_eq!(
+            parse_csi_sgr_mouse(b"\x1B[<0;20;10m").unwrap(),
+            Some(InternalEvent::Event(Event::Mouse(MouseEvent {
+                kind: MouseEventKind::Up(MouseButton::Left),
+                column: 19,
+                row: 9,
+                modifiers: KeyModifiers::empty(),
+            })))
+        );
+    }
+
+    #[test]
+    fn test_utf8() {
+        // https://www.php.net/manual/en/reference.pcre.pattern.modifiers.php#54805
+
+        // 'Valid ASCII' => "a",
+        assert_eq!(parse_utf8_char(b"a").unwrap(), Some('a'),);
+
+        // 'Valid 2 Octet Sequence' => "\xc3\xb1",
+        assert_eq!(parse_utf8_char(&[0xC3, 0xB1]).unwrap(), Some('ñ'),);
+
+        // 'Invalid 2 Octet Sequence' => "\xc3\x28",
+        assert!(parse_utf8_char(&[0xC3, 0x28]).is_err());
+
+        // 'Invalid Sequence Identifier' => "\xa0\xa1",
+        assert!(parse_utf8_char(&[0xA0, 0xA1]).is_err());
+
+        // 'Valid 3 Octet Sequence' => "\xe2\x82\xa1",
+        assert_eq!(
+            parse_utf8_char(&[0xE2, 0x81, 0xA1]).unwrap(),
+            Some('\u{2061}'),
+        );
+
+        // 'Invalid 3 Octet Sequence (in 2nd Octet)' => "\xe2\x28\xa1",
+        assert!(parse_utf8_char(&[0xE2, 0x28, 0xA1]).is_err());
+
+        // 'Invalid 3 Octet Sequence (in 3rd Octet)' => "\xe2\x82\x28",
+        assert!(parse_utf8_char(&[0xE2, 0x82, 0x28]).is_err());
+
+        // 'Valid 4 Octet Sequence' => "\xf0\x90\x8c\xbc",
+        assert_eq!(
+            parse_utf8_char(&[0xF0, 0x90, 0x8C, 0xBC]).unwrap(),
+            Some('𐌼'),
+        );
+
+        // 'Invalid 4 Octet Sequence (in 2nd Octet)' => "\xf0\x28\x8c\xbc",
+        assert!(parse_utf8_char(&[0xF0, 0x28, 0x8C, 0xBC]).is_err());
+
+        // 'Invalid 4 Octet Sequence (in 3rd Octet)' => "\xf0\x90\x28\xbc",
+        assert!(parse_utf8_char(&[0xF0, 0x90, 0x28, 0xBC]).is_err());
+
+        // 'Invalid 4 Octet Sequence (in 4th Octet)' => "\xf0\x28\x8c\x28",
+        assert!(parse_utf8_char(&[0xF0, 0x28, 0x8C, 0x28]).is_err());
+    }
+
+    #[test]
+    fn test_parse_char_event_lowercase() {
+        assert_eq!(
+            parse_event(b"c", false).unwrap(),
+            Some(InternalEvent::Event(Event::Key(KeyEvent::new(
+                KeyCode::Char('c'),
+                KeyModifiers::empty()
+            )))),
+        );
+    }
+
+    #[test]
+    fn test_parse_char_event_uppercase() {
+        assert_eq!(
+            parse_event(b"C", false).unwrap(),
+            Some(InternalEvent::Event(Event::Key(KeyEvent::new(
+                KeyCode::Char('C'),
+                KeyModifiers::SHIFT
+            )))),
+        );
+    }
+
+    #[test]
+    fn test_parse_basic_csi_u_encoded_key_code() {
+        assert_eq!(
+            parse_csi_u_encoded_key_code(b"\x1B[97u", false).unwrap(),
+            Some(InternalEvent::Event(Event::Key(KeyEvent::new(
+                KeyCode::Char('a'),
+                KeyModifiers::empty()
+            )))),
+        );
+        assert_eq!(
+            parse_csi_u_encoded_key_code(b"\x1B[97;2u", false).unwrap(),
+            Some(InternalEvent::Event(Event::Key(KeyEvent::new(
+                KeyCode::Char('A'),
+                KeyModifiers::SHIFT
+            )))),
+        );
+        assert_eq!(
+            parse_csi_u_encoded_key_code(b"\x1B[97;7u", false).unwrap(),
+            Some(InternalEvent::Event(Event::Key(KeyEvent::new(
+                KeyCode::Char('a'),
+                KeyModifiers::ALT | KeyModifiers::CONTROL
+            )))),
+        );
+    }
+
+    #[test]
+    fn test_parse_basic_csi_u_encoded_key_code_special_keys() {
+        assert_eq!(
+            parse_csi_u_encoded_key_code(b"\x1B[13u", false).unwrap(),
+            Some(InternalEvent::Event(Event::Key(KeyEvent::new(
+                KeyCode::Enter,
+                KeyModifiers::empty()
+            )))),
+        );
+        assert_eq!(
+            parse_csi_u_encoded_key_code(b"\x1B[27u", false).unwrap(),
+            Some(InternalEvent::Event(Event::Key(KeyEvent::new(
+                KeyCode::Esc,
+                KeyModifiers::empty()
+            )))),
+        );
+        assert_eq!(
+            parse_csi_u_encoded_key_code(b"\x1B[57358u", false).unwrap(),
+            Some(InternalEvent::Event(Event::Key(KeyEvent::new(
+                KeyCode::CapsLock,
+                KeyModifiers::empty()
+            )))),
+        );
+        assert_eq!(
+            parse_csi_u_encoded_key_code(b"\x1B[57376u", false).unwrap(),
+            Some(InternalEvent::Event(Event::Key(KeyEvent::new(
+                KeyCode::F(13),
+                KeyModifiers::empty()
+            )))),
+        );
+        assert_eq!(
+            parse_csi_u_encoded_key_code(b"\x1B[57428u", false).unwrap(),
+            Some(InternalEvent::Event(Event::Key(KeyEvent::new(
+                KeyCode::Media(MediaKeyCode::Play),
+                KeyModifiers::empty()
+            )))),
+        );
+        assert_eq!(
+            parse_csi_u_encoded_key_code(b"\x1B[57441u", false).unwrap(),
+            Some(InternalEvent::Event(Event::Key(KeyEvent::new(
+                KeyCode::Modifier(ModifierKeyCode::LeftShift),
+                KeyModifiers::SHIFT,
+            )))),
+        );
+    }
+
+    #[test]
+    fn test_parse_csi_u_encoded_keypad_code() {
+        assert_eq!(
+            parse_csi_u_encoded_key_code(b"\x1B[57399u", false).unwrap(),
+            Some(InternalEvent::Event(Event::Key(
+                KeyEvent::new_with_kind_and_state(
+                    KeyCode::Char('0'),
+                    KeyModifiers::empty(),
+                    KeyEventKind::Press,
+                    KeyEventState::KEYPAD,
+                )
+            ))),
+        );
+        assert_eq!(
+            parse_csi_u_encoded_key_code(b"\x1B[57419u", false).unwrap(),
+            Some(InternalEvent::Event(Event::Key(
+                KeyEvent::new_with_kind_and_state(
+                    KeyCode::Up,
+                    KeyModifiers::empty(),
+                    KeyEventKind::Press,
+                    KeyEventState::KEYPAD,
+                )
+            ))),
+        );
+    }
+
+    #[test]
+    fn test_parse_csi_u_encoded_key_code_with_types() {
+        assert_eq!(
+            parse_csi_u_encoded_key_code(b"\x1B[97;1u", false).unwrap(),
+            Some(InternalEvent::Event(Event::Key(KeyEvent::new_with_kind(
+                KeyCode::Char('a'),
+                KeyModifiers::empty(),
+                KeyEventKind::Press,
+            )))),
+        );
+        assert_eq!(
+            parse_csi_u_encoded_key_code(b"\x1B[97;1:1u", false).unwrap(),
+            Some(InternalEvent::Event(Event::Key(KeyEvent::new_with_kind(
+                KeyCode::Char('a'),
+                KeyModifiers::empty(),
+                KeyEventKind::Press,
+            )))),
+        );
+        assert_eq!(
+            parse_csi_u_encoded_key_code(b"\x1B[97;5:1u", false).unwrap(),
+            Some(InternalEvent::Event(Event::Key(KeyEvent::new_with_kind(
+                KeyCode::Char('a'),
+                KeyModifiers::CONTROL,
+                KeyEventKind::Press,
+            )))),
+        );
+        assert_eq!(
+            parse_csi_u_encoded_key_code(b"\x1B[97;1:2u", false).unwrap(),
+            Some(InternalEvent::Event(Event::Key(KeyEvent::new_with_kind(
+                KeyCode::Char('a'),
+                KeyModifiers::empty(),
+                KeyEventKind::Repeat,
+            )))),
+        );
+        assert_eq!(
+            parse_csi_u_encoded_key_code(b"\x1B[97;1:3u", false).unwrap(),
+            Some(InternalEvent::Event(Event::Key(KeyEvent::new_with_kind(
+                KeyCode::Char('a'),
+                KeyModifiers::empty(),
+                KeyEventKind::Release,
+            )))),
+        );
+    }
+
+    #[test]
+    fn test_parse_csi_u_encoded_key_code_has_modifier_on_modifier_press() {
+        assert_eq!(
+            parse_csi_u_encoded_key_code(b"\x1B[57449u", false).unwrap(),
+            Some(InternalEvent::Event(Event::Key(KeyEvent::new_with_kind(
+                KeyCode::Modifier(ModifierKeyCode::RightAlt),
+                KeyModifiers::ALT,
+                KeyEventKind::Press,
+            )))),
+        );
+        assert_eq!(
+            parse_csi_u_encoded_key_code(b"\x1B[57449;3:3u", false).unwrap(),
+            Some(InternalEvent::Event(Event::Key(KeyEvent::new_with_kind(
+                KeyCode::Modifier(ModifierKeyCode::RightAlt),
+                KeyModifiers::ALT,
+                KeyEventKind::Release,
+            )))),
+        );
+        assert_eq!(
+            parse_csi_u_encoded_key_code(b"\x1B[57450u", false).unwrap(),
+            Some(InternalEvent::Event(Event::Key(KeyEvent::new(
+                KeyCode::Modifier(ModifierKeyCode::RightSuper),
+                KeyModifiers::SUPER,
+            )))),
+        );
+        assert_eq!(
+            parse_csi_u_encoded_key_code(b"\x1B[57451u", false).unwrap(),
+            Some(InternalEvent::Event(Event::Key(KeyEvent::new(
+                KeyCode::Modifier(ModifierKeyCode::RightHyper),
+                KeyModifiers::HYPER,
+            )))),
+        );
+        assert_eq!(
+            parse_csi_u_encoded_key_code(b"\x1B[57452u", false).unwrap(),
+            Some(InternalEvent::Event(Event::Key(KeyEvent::new(
+                KeyCode::Modifier(ModifierKeyCode::RightMeta),
+                KeyModifiers::META,
+            )))),
+        );
+    }
+
+    #[test]
+    fn test_held_modifier_sides() {
+        let sides = |parser: &mut Parser| match parser.next() {
+            Some(Event::Key(key_event)) => key_event.extended_modifiers,
+            event => panic!("not a key event: {:?}", event),
+        };
+
+        let mut parser = Parser::new();
+        let mut other = Parser::new();
+        // Right Alt pressed, then Alt+a
+        parser.advance(b"\x1B[57449;3u\x1B[97;3u", false);
+        assert_eq!(sides(&mut parser), ExtendedModifiers::RIGHT_ALT);
+        assert_eq!(sides(&mut parser), ExtendedModifiers::RIGHT_ALT);
+        // another parser doesn't know about the held key
+        other.advance(b"\x1B[97;3u", false);
+        assert_eq!(sides(&mut other), ExtendedModifiers::empty());
+        // released
+        parser.advance(b"\x1B[57449;3:3u\x1B[97u", false);
+        assert_eq!(sides(&mut parser), ExtendedModifiers::empty());
+        assert_eq!(sides(&mut parser), ExtendedModifiers::empty());
+    }
+
+    #[test]
+    fn test_parse_csi_u_encoded_key_code_with_extra_modifiers() {
+        assert_eq!(
+            parse_csi_u_encoded_key_code(b"\x1B[97;9u", false).unwrap(),
+            Some(InternalEvent::Event(Event::Key(KeyEvent::new(
+                KeyCode::Char('a'),
+                KeyModifiers::SUPER
+            )))),
+        );
+        assert_eq!(
+            parse_csi_u_encoded_key_code(b"\x1B[97;17u", false).unwrap(),
+            Some(InternalEvent::Event(Event::Key(KeyEvent::new(
+                KeyCode::Char('a'),
+                KeyModifiers::HYPER,
+            )))),
+        );
+        assert_eq!(
+            parse_csi_u_encoded_key_code(b"\x1B[97;33u", false).unwrap(),
+            Some(InternalEvent::Event(Event::Key(KeyEvent::new(
+                KeyCode::Char('a'),
+                KeyModifiers::META,
+            )))),
+        );
+    }
+
+    #[test]
+    fn test_parse_csi_u_encoded_key_code_with_extra_state() {
+        assert_eq!(
+            parse_csi_u_encoded_key_code(b"\x1B[97;65u", false).unwrap(),
+            Some(InternalEvent::Event(Event::Key(
+                KeyEvent::new_with_kind_and_state(
+                    KeyCode::Char('a'),
+                    KeyModifiers::empty(),
+                    KeyEventKind::Press,
+                    KeyEventState::CAPS_LOCK,
+                )
+            ))),
+        );
+        assert_eq!(
+            parse_csi_u_encoded_key_code(b"\x1B[49;129u", false).unwrap(),
+            Some(InternalEvent::Event(Event::Key(
+                KeyEvent::new_with_kind_and_state(
+                    KeyCode::Char('1'),
+                    KeyModifiers::empty(),
+                    KeyEventKind::Press,
+                    KeyEventState::NUM_LOCK,
+                )
+            ))),
+        );
+    }
+
+    #[test]
+    fn test_parse_csi_u_encoded_key_code_with_base_layout_key() {
+        // `z` on an AZERTY layout, at the place of `w`
+        let event = parse_csi_u_encoded_key_code(b"\x1B[122::119u", false).unwrap();
+        assert_eq!(
+            event,
+            Some(InternalEvent::Event(Event::Key(KeyEvent::new(
+                KeyCode::Char('z'),
+                KeyModifiers::empty()
+            ))))
+        );
+        match event {
+            Some(InternalEvent::Event(Event::Key(key_event))) => assert_eq!(
+                key_event.physical_key,
+                Some(PhysicalKey::BaseLayout(KeyCode::Char('w')))
+            ),
+            _ => unreachable!(),
+        }
+
+        // `Z` with shift
+        match parse_csi_u_encoded_key_code(b"\x1B[122:90:119;2u", false).unwrap() {
+            Some(InternalEvent::Event(Event::Key(key_event))) => {
+                assert_eq!(key_event.code, KeyCode::Char('Z'));
+                assert_eq!(
+                    key_event.physical_key,
+                    Some(PhysicalKey::BaseLayout(KeyCode::Char('w')))
+                );
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn test_parse_csi_modifier_key_code_with_lock_state() {
+        assert_eq!(
+            parse_csi_modifier_key_code(b"\x1B[1;194A").unwrap(),
+            Some(InternalEvent::Event(Event::Key(
+                KeyEvent::new_with_kind_and_state(
+                    KeyCode::Up,
+                    KeyModifiers::SHIFT,
+                    KeyEventKind::Press,
+                    KeyEventState::CAPS_LOCK | KeyEventState::NUM_LOCK,
+                )
+            ))),
+        );
+    }
+
+    #[test]
+    fn test_parse_csi_u_with_shifted_keycode() {
+        assert_eq!(
+            // A-S-9 is equivalent to A-(
+            parse_event(b"\x1B[57:40;4u", false).unwrap(),
+            Some(InternalEvent::Event(Event::Key(KeyEvent::new(
+                KeyCode::Char('('),
+                KeyModifiers::ALT,
+            )))),
+        );
+        assert_eq!(
+            // A-S-minus is equivalent to A-_
+            parse_event(b"\x1B[45:95;4u", false).unwrap(),
+            Some(InternalEvent::Event(Event::Key(KeyEvent::new(
+                KeyCode::Char('_'),
+                KeyModifiers::ALT,
+            )))),
+        );
+    }
+
+    #[test]
+    fn test_parse_csi_special_key_code_with_types() {
+        assert_eq!(
+            parse_event(b"\x1B[;1:3B", false).unwrap(),
+            Some(InternalEvent::Event(Event::Key(KeyEvent::new_with_kind(
+                KeyCode::Down,
+                KeyModifiers::empty(),
+                KeyEventKind::Release,
+            )))),
+        );
+        assert_eq!(
+            parse_event(b"\x1B[1;1:3B", false).unwrap(),
+            Some(InternalEvent::Event(Event::Key(KeyEvent::new_with_kind(
+                KeyCode::Down,
+                KeyModifiers::empty(),
+                KeyEventKind::Release,
+            )))),
+        );
+    }
+
+    #[test]
+    fn test_parse_csi_numbered_escape_code_with_types() {
+        assert_eq!(
+            parse_event(b"\x1B[5;1:3~", false).unwrap(),
+            Some(InternalEvent::Event(Event::Key(KeyEvent::new_with_kind(
+                KeyCode::PageUp,
+                KeyModifiers::empty(),
+                KeyEventKind::Release,
+            )))),
+        );
+        assert_eq!(
+            parse_event(b"\x1B[6;5:3~", false).unwrap(),
+            Some(InternalEvent::Event(Event::Key(KeyEvent::new_with_kind(
+                KeyCode::PageDown,
+                KeyModifiers::CONTROL,
+                KeyEventKind::Release,
+            )))),
+        );
+    }
+
+    #[test]
+    fn test_parser_sequence_split_across_reads() {
+        let mut parser = Parser::new();
+        parser.advance(b"a\x1B", true);
+        assert_eq!(parser.next(), Some(Event::Key(KeyCode::Char('a').into())));
+        assert_eq!(parser.next(), None);
+
+        parser.advance(b"[", true);
+        parser.advance(b"Ab", false);
+        assert_eq!(parser.next(), Some(Event::Key(KeyCode::Up.into())));
+        assert_eq!(parser.next(), Some(Event::Key(KeyCode::Char('b').into())));
+        assert_eq!(parser.next(), None);
+    }
+
+    #[test]
+    fn test_parser_discards_overlong_sequence() {
+        let mut parser = Parser::new();
+        parser.advance(b"\x1B[1", true);
+        parser.advance(&[b'1'; SEQUENCE_BUFFER_SIZE], true);
+        parser.advance(b"~x", false);
+
+        // The bytes after the discarded part are parsed as regular input.
+        let events: Vec<_> = parser.collect();
+        assert_eq!(events.last(), Some(&Event::Key(KeyCode::Char('x').into())));
+    }
+
+    #[cfg(feature = "bracketed-paste")]
+    #[test]
+    fn test_parser_long_paste_across_reads() {
+        let content = "x".repeat(SEQUENCE_BUFFER_SIZE * 4);
+        let mut parser = Parser::new();
+        parser.advance(b"\x1B[20", true);
+        parser.advance(b"0~", true);
+        parser.advance(content.as_bytes(), true);
+        parser.advance(b"\x1B[2", true);
+        parser.advance(b"01~q", false);
+        assert_eq!(
+            parser.next(),
+            Some(Event::Paste(crate::event::Paste::new(
+                content,
+                PasteKind::Bracketed
+            )))
+        );
+        assert_eq!(parser.next(), Some(Event::Key(KeyCode::Char('q').into())));
+        assert_eq!(parser.next(), None);
+    }
+
+    #[cfg(feature = "bracketed-paste")]
+    #[test]
+    fn test_parser_paste_limit() {
+        let mut parser = Parser::new();
+        parser.set_paste_options(PasteOptions {
+            limit: Some(3),
+            ..PasteOptions::default()
+        });
+        parser.advance(b"\x1B[200~ab\xC3\xA4", true);
+        parser.advance(b"cdef\x1B[20", true);
+        parser.advance(b"1~", false);
+
+        let paste = match parser.next() {
+            Some(Event::Paste(paste)) => paste,
+            event => panic!("expected a paste, got {:?}", event),
+        };
+        // the limit splits the `ä`
+        assert_eq!(paste.content, "ab");
+        assert_eq!(paste.len, 8);
+        assert!(paste.truncated);
+
+        parser.advance(b"\x1B[200~abc\x1B[201~", false);
+        let paste = crate::event::Paste::new("abc", PasteKind::Bracketed);
+        assert_eq!(parser.next(), Some(Event::Paste(paste)));
+    }
+
+    #[cfg(feature = "bracketed-paste")]
+    #[test]
+    fn test_parser_inferred_paste() {
+        let text = "let x = 1;\rlet y = 2;\r";
+        let mut parser = Parser::new();
+        parser.advance(text.as_bytes(), false);
+        assert_eq!(parser.count(), text.len());
+
+        let mut parser = Parser::new();
+        parser.set_paste_options(PasteOptions {
+            infer: true,
+            ..PasteOptions::default()
+        });
+        parser.advance(text.as_bytes(), false);
+        assert_eq!(
+            parser.next(),
+            Some(Event::Paste(crate::event::Paste::new(
+                text,
+                PasteKind::Inferred
+            )))
+        );
+
+        // keys and escape sequences aren't pastes
+        parser.advance(b"a", false);
+        parser.advance(b"\x1B[A\x1B[A\x1B[A\x1B[A\x1B[A\x1B[A", false);
+        assert_eq!(parser.count(), 7);
+    }
+
+    #[test]
+    fn test_parser_reports_keys_after_alt_introducer() {
+        let alt = |c| Event::Key(KeyEvent::new(KeyCode::Char(c), KeyModifiers::ALT));
+        let char = |c| Event::Key(KeyCode::Char(c).into());
+
+        assert!(parse_event(b"\x1B]1", true).unwrap().is_none());
+        assert!(parse_event(b"\x1B]1x", true).is_err());
+        assert!(parse_event(b"\x1B]3", true).is_err());
+        assert!(parse_event(b"\x1B]10;\r", true).is_err());
+
+        let (events, errors) = parse(b"\x1B]1x\x1B]3");
+        assert_eq!(
+            events,
+            vec![alt(']'), char('1'), char('x'), alt(']'), char('3')]
+        );
+        assert!(errors.is_empty());
+
+        let alt_shift_p = Event::Key(KeyEvent::new(
+            KeyCode::Char('P'),
+            KeyModifiers::ALT | KeyModifiers::SHIFT,
+        ));
+        let mut parser = Parser::new();
+        parser.advance(b"\x1BP>", true);
+        parser.advance(b"a\x1BP1", true);
+        parser.advance(b"b\x1B[A", false);
+        assert_eq!(
+            parser.collect::<Vec<_>>(),
+            vec![
+                alt_shift_p.clone(),
+                char('>'),
+                char('a'),
+                alt_shift_p,
+                char('1'),
+                char('b'),
+                Event::Key(KeyCode::Up.into()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parser_skips_responses_and_handles_newline() {
+        let mut parser = Parser::new();
+        parser.advance(b"\x1B[20;10R\n", false);
+        assert_eq!(
+            parser.next(),
+            Some(Event::Key(KeyEvent::new(
+                KeyCode::Char('j'),
+                KeyModifiers::CONTROL
+            )))
+        );
+        assert_eq!(parser.next(), None);
+
+        parser.set_newline_as_enter(true);
+        parser.advance(b"\n\x1B[10u", false);
+        assert_eq!(parser.next(), Some(Event::Key(KeyCode::Enter.into())));
+        assert_eq!(parser.next(), Some(Event::Key(KeyCode::Enter.into())));
+
+        // the default parser is a new one, which doesn't follow the raw mode
+        let mut parser = Parser::default();
+        parser.advance(b"\n\x1B[10u", false);
+        assert_eq!(
+            parser.next(),
+            Some(Event::Key(KeyEvent::new(
+                KeyCode::Char('j'),
+                KeyModifiers::CONTROL
+            )))
+        );
+        assert_eq!(parser.next(), Some(Event::Key(KeyCode::Char('\n').into())));
+    }
+
+    #[test]
+    fn test_parse_collects_errors() {
+        let (events, errors) = parse(b"\x1B[1;xa\x1B[");
+        assert_eq!(events, vec![Event::Key(KeyCode::Char('a').into())]);
+        assert_eq!(
+            errors.iter().map(ParseError::sequence).collect::<Vec<_>>(),
+            vec![&b"\x1B[1;x"[..], b"\x1B["]
+        );
+
+        let (events, errors) = parse(b"a\x1B");
+        assert_eq!(events.last(), Some(&Event::Key(KeyCode::Esc.into())));
+        assert!(errors.is_empty());
+    }
+
+    #[test]
+    fn test_only_terminal_parse_errors_are_counted() {
+        let before = crate::metrics().parse_errors;
+        let mut parser = Parser::new();
+        parser.advance(b"\x1B[1;xa", false);
+        assert_eq!(crate::metrics().parse_errors, before);
+
+        parser.count_errors();
+        parser.advance(b"\x1B[1;xa", false);
+        assert!(crate::metrics().parse_errors > before);
+    }
+
+    #[test]
+    fn test_parse_arbitrary_bytes() {
+        // A tiny xorshift generator, biased towards bytes that occur in escape sequences.
+        let mut state = 0x2545_f491_4f6c_dd1d_u64;
+        let mut next = move || {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state
+        };
+
+        const ALPHABET: &[u8] = b"\x1B[;<M0123456789~uR";
+
+        for _ in 0..5_000 {
+            let len = (next() % 64) as usize;
+            let bytes: Vec<u8> = (0..len)
+                .map(|_| match next() % 4 {
+                    0 => next() as u8,
+                    _ => ALPHABET[next() as usize % ALPHABET.len()],
+                })
+                .collect();
+
+            let _ = parse(&bytes);
+
+            let mut parser = Parser::new();
+            for chunk in bytes.chunks(3) {
+                parser.advance(chunk, true);
+                parser.by_ref().for_each(drop);
+            }
+        }
+    }
+}
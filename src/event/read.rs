@@ -1,38 +1,124 @@
 use std::{collections::vec_deque::VecDeque, io, time::Duration};
 
+#[cfg(feature = "event-stream")]
+use parking_lot::Mutex;
+
 #[cfg(unix)]
 use crate::event::source::unix::UnixInternalEventSource;
 #[cfg(windows)]
-use crate::event::source::windows::WindowsEventSource;
+use crate::event::source::{msys::MsysEventSource, windows::WindowsEventSource};
 #[cfg(feature = "event-stream")]
 use crate::event::sys::Waker;
-use crate::event::{filter::Filter, source::EventSource, timeout::PollTimeout, InternalEvent};
+use crate::event::{
+    filter::Filter, replay, source::EventSource, timeout::PollTimeout, timer, InternalEvent,
+};
+use crate::metrics;
+
+/// Wakes the reader of the terminal of the process, when timers are added or events replayed.
+#[cfg(feature = "event-stream")]
+static GLOBAL_WAKER: Mutex<Option<Waker>> = parking_lot::const_mutex(None);
+
+/// How long the reader of the terminal of the process waits for input at most before it checks
+/// the timers and replayed events again, as it can't be woken without the `event-stream` feature.
+#[cfg(not(feature = "event-stream"))]
+const WAKE_CHECK_INTERVAL: Duration = Duration::from_millis(50);
 
 /// Can be used to read `InternalEvent`s.
 pub(crate) struct InternalEventReader {
     events: VecDeque<InternalEvent>,
     source: Option<Box<dyn EventSource>>,
     skipped_events: Vec<InternalEvent>,
+    /// Whether this is the reader of the terminal of the process, which also reports the ticks
+    /// of the timers and replayed events.
+    global: bool,
+}
+
+/// Wakes the reader of the terminal of the process, so that a pending poll returns `Ok(false)`.
+///
+/// Without the `event-stream` feature, the reader notices the timers and replayed events within
+/// `WAKE_CHECK_INTERVAL` instead.
+pub(crate) fn wake_global_reader() {
+    #[cfg(feature = "event-stream")]
+    if let Some(waker) = GLOBAL_WAKER.lock().as_ref() {
+        let _ = waker.wake();
+    }
+}
+
+/// Makes `reader` the one [`wake_global_reader`] wakes, e.g. the reader of the terminal again
+/// after a script was played.
+pub(crate) fn set_global_reader(reader: Option<&InternalEventReader>) {
+    #[cfg(feature = "event-stream")]
+    {
+        *GLOBAL_WAKER.lock() = reader.and_then(|reader| Some(reader.source.as_ref()?.waker()));
+    }
+    #[cfg(not(feature = "event-stream"))]
+    let _ = reader;
 }
 
 impl Default for InternalEventReader {
     fn default() -> Self {
         #[cfg(windows)]
-        let source = WindowsEventSource::new();
+        let source = if crate::ansi_support::is_msys_pty_input() {
+            MsysEventSource::new().map(|x| Box::new(x) as Box<dyn EventSource>)
+        } else {
+            WindowsEventSource::new().map(|x| Box::new(x) as Box<dyn EventSource>)
+        };
         #[cfg(unix)]
-        let source = UnixInternalEventSource::new();
+        let source = UnixInternalEventSource::new().map(|x| Box::new(x) as Box<dyn EventSource>);
 
-        let source = source.ok().map(|x| Box::new(x) as Box<dyn EventSource>);
+        let mut source = source.ok();
+        if let Some(source) = &mut source {
+            source.count_parse_errors();
+        }
+
+        #[cfg(feature = "event-stream")]
+        if let Some(source) = &source {
+            *GLOBAL_WAKER.lock() = Some(source.waker());
+        }
 
         InternalEventReader {
             source,
             events: VecDeque::with_capacity(32),
             skipped_events: Vec::with_capacity(32),
+            global: true,
         }
     }
 }
 
 impl InternalEventReader {
+    /// Creates a reader of the events of `source`.
+    ///
+    /// Only the `global` reader of the terminal of the process reports the ticks of the timers
+    /// and replayed events, and counts its events and parse errors in the metrics.
+    pub(crate) fn with_source(
+        mut source: Box<dyn EventSource>,
+        global: bool,
+    ) -> InternalEventReader {
+        if global {
+            source.count_parse_errors();
+        }
+        #[cfg(feature = "event-stream")]
+        if global {
+            *GLOBAL_WAKER.lock() = Some(source.waker());
+        }
+
+        InternalEventReader {
+            source: Some(source),
+            events: VecDeque::with_capacity(32),
+            skipped_events: Vec::with_capacity(32),
+            global,
+        }
+    }
+
+    /// Sets whether the source reads `\n` as the Enter key, see
+    /// [`EventSource::set_newline_as_enter`].
+    #[cfg(unix)]
+    pub(crate) fn set_newline_as_enter(&mut self, enabled: bool) {
+        if let Some(source) = &mut self.source {
+            source.set_newline_as_enter(enabled);
+        }
+    }
+
     /// Returns a `Waker` allowing to wake/force the `poll` method to return `Ok(false)`.
     #[cfg(feature = "event-stream")]
     pub(crate) fn waker(&self) -> Waker {
@@ -59,12 +145,46 @@ impl InternalEventReader {
             }
         };
 
-        let poll_timeout = PollTimeout::new(timeout);
+        let poll_timeout = PollTimeout::with_installed_clock(timeout);
 
         loop {
-            let maybe_event = match event_source.try_read(poll_timeout.leftover()) {
+            // replayed events and due timers are reported before waiting for input, which is
+            // only waited for until the next timer is due
+            let reads_ticks = self.global && F::EVENTS;
+            let next_tick = if reads_ticks {
+                match replay::next_replayed() {
+                    Some(event) => Ok(event),
+                    None => timer::next_tick(),
+                }
+            } else {
+                Err(None)
+            };
+            let read = match next_tick {
+                Ok(tick) => Ok(Some(InternalEvent::Event(tick))),
+                Err(until_tick) => {
+                    let wait = poll_timeout.wait_duration(until_tick);
+                    #[cfg(not(feature = "event-stream"))]
+                    let wait = match wait {
+                        Some(wait) if reads_ticks => Some(wait.min(WAKE_CHECK_INTERVAL)),
+                        None if reads_ticks => Some(WAKE_CHECK_INTERVAL),
+                        wait => wait,
+                    };
+                    let read = event_source.try_read(wait);
+                    if let Ok(Some(InternalEvent::Event(_))) = read {
+                        if self.global {
+                            metrics::add_event();
+                        }
+                    }
+                    read
+                }
+            };
+
+            let maybe_event = match read {
                 Ok(None) => None,
                 Ok(Some(event)) => {
+                    #[cfg(feature = "trace")]
+                    log::debug!(target: "crossterm::event", "read {:?}", event);
+
                     if filter.eval(&event) {
                         Some(event)
                     } else {
@@ -83,6 +203,7 @@ impl InternalEventReader {
 
             if poll_timeout.elapsed() || maybe_event.is_some() {
                 self.events.extend(self.skipped_events.drain(..));
+                self.update_queue_len();
 
                 if let Some(event) = maybe_event {
                     self.events.push_front(event);
@@ -94,6 +215,13 @@ impl InternalEventReader {
         }
     }
 
+    /// Reports the number of queued events of the terminal of the process to the metrics.
+    fn update_queue_len(&self) {
+        if self.global {
+            metrics::set_event_queue_len(self.events.len());
+        }
+    }
+
     pub(crate) fn read<F>(&mut self, filter: &F) -> io::Result<InternalEvent>
     where
         F: Filter,
@@ -106,6 +234,7 @@ impl InternalEventReader {
                     while let Some(event) = skipped_events.pop_front() {
                         self.events.push_back(event);
                     }
+                    self.update_queue_len();
 
                     return Ok(event);
                 } else {
@@ -143,6 +272,7 @@ mod tests {
             events: VecDeque::new(),
             source: None,
             skipped_events: Vec::with_capacity(32),
+            global: false,
         };
 
         assert!(reader.poll(None, &InternalEventFilter).is_err());
@@ -160,6 +290,7 @@ mod tests {
             events: vec![InternalEvent::Event(Event::Resize(10, 10))].into(),
             source: None,
             skipped_events: Vec::with_capacity(32),
+            global: false,
         };
 
         assert!(reader.poll(None, &InternalEventFilter).unwrap());
@@ -176,6 +307,7 @@ mod tests {
             .into(),
             source: None,
             skipped_events: Vec::with_capacity(32),
+            global: false,
         };
 
         assert!(reader.poll(None, &CursorPositionFilter).unwrap());
@@ -189,6 +321,7 @@ mod tests {
             events: vec![EVENT].into(),
             source: None,
             skipped_events: Vec::with_capacity(32),
+            global: false,
         };
 
         assert_eq!(reader.read(&InternalEventFilter).unwrap(), EVENT);
@@ -203,6 +336,7 @@ mod tests {
             events: vec![InternalEvent::Event(Event::Resize(10, 10)), CURSOR_EVENT].into(),
             source: None,
             skipped_events: Vec::with_capacity(32),
+            global: false,
         };
 
         assert_eq!(reader.read(&CursorPositionFilter).unwrap(), CURSOR_EVENT);
@@ -218,6 +352,7 @@ mod tests {
             events: vec![SKIPPED_EVENT, CURSOR_EVENT].into(),
             source: None,
             skipped_events: Vec::with_capacity(32),
+            global: false,
         };
 
         assert_eq!(reader.read(&CursorPositionFilter).unwrap(), CURSOR_EVENT);
@@ -232,6 +367,7 @@ mod tests {
             events: VecDeque::new(),
             source: Some(Box::new(source)),
             skipped_events: Vec::with_capacity(32),
+            global: false,
         };
 
         assert!(!reader
@@ -247,6 +383,7 @@ mod tests {
             events: VecDeque::new(),
             source: Some(Box::new(source)),
             skipped_events: Vec::with_capacity(32),
+            global: false,
         };
 
         assert!(reader.poll(None, &InternalEventFilter).unwrap());
@@ -265,6 +402,7 @@ mod tests {
             events: VecDeque::new(),
             source: Some(Box::new(source)),
             skipped_events: Vec::with_capacity(32),
+            global: false,
         };
 
         assert_eq!(reader.read(&InternalEventFilter).unwrap(), EVENT);
@@ -280,6 +418,7 @@ mod tests {
             events: VecDeque::new(),
             source: Some(Box::new(source)),
             skipped_events: Vec::with_capacity(32),
+            global: false,
         };
 
         assert_eq!(reader.read(&InternalEventFilter).unwrap(), EVENT);
@@ -297,6 +436,7 @@ mod tests {
             events: VecDeque::new(),
             source: Some(Box::new(source)),
             skipped_events: Vec::with_capacity(32),
+            global: false,
         };
 
         assert_eq!(reader.read(&InternalEventFilter).unwrap(), EVENT);
@@ -313,6 +453,7 @@ mod tests {
             events: VecDeque::new(),
             source: Some(Box::new(FakeSource::new(&[]))),
             skipped_events: Vec::with_capacity(32),
+            global: false,
         };
 
         assert_eq!(
@@ -330,6 +471,7 @@ mod tests {
             events: VecDeque::new(),
             source: Some(Box::new(FakeSource::new(&[]))),
             skipped_events: Vec::with_capacity(32),
+            global: false,
         };
 
         assert_eq!(
@@ -351,6 +493,7 @@ mod tests {
             events: VecDeque::new(),
             source: Some(Box::new(source)),
             skipped_events: Vec::with_capacity(32),
+            global: false,
         };
 
         assert_eq!(reader.read(&InternalEventFilter).unwrap(), EVENT);
@@ -370,6 +513,7 @@ mod tests {
             events: VecDeque::new(),
             source: Some(Box::new(source)),
             skipped_events: Vec::with_capacity(32),
+            global: false,
         };
 
         assert_eq!(reader.read(&InternalEventFilter).unwrap(), EVENT);
@@ -0,0 +1,174 @@
+use std::collections::VecDeque;
+
+use parking_lot::Mutex;
+
+use super::{read, Event};
+
+/// The events which are read before the input, see [`replay`].
+// `VecDeque::new` isn't const before Rust 1.68
+static REPLAYED: Mutex<Option<VecDeque<Event>>> = parking_lot::const_mutex(None);
+
+/// A recorded sequence of events, which can be [replayed](fn.replay.html).
+///
+/// Macros can be persisted by saving their [`events`](#method.events), e.g. with the `serde`
+/// feature, and restored with [`Macro::new`].
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Default, Clone, PartialEq, Eq, Hash)]
+pub struct Macro {
+    events: Vec<Event>,
+}
+
+impl Macro {
+    /// Creates a macro which replays `events`.
+    pub fn new(events: Vec<Event>) -> Macro {
+        Macro { events }
+    }
+
+    /// Returns the recorded events.
+    pub fn events(&self) -> &[Event] {
+        &self.events
+    }
+
+    /// Returns the number of recorded events.
+    pub fn len(&self) -> usize {
+        self.events.len()
+    }
+
+    /// Returns whether no events were recorded.
+    pub fn is_empty(&self) -> bool {
+        self.events.is_empty()
+    }
+
+    /// Returns the recorded events.
+    pub fn into_events(self) -> Vec<Event> {
+        self.events
+    }
+}
+
+/// Records a [`Macro`] from the events the application reads, like `q` in vim.
+///
+/// The recorder doesn't read events itself, the application passes every event it reads to
+/// [`record`](#method.record), which only keeps them while the recorder is recording. Timer ticks
+/// are never recorded, and at most `limit` events are kept, later ones are dropped.
+///
+/// # Examples
+///
+/// ```no_run
+/// use std::io;
+/// use crossterm::event::{self, Event, KeyCode, MacroRecorder};
+///
+/// fn main() -> io::Result<()> {
+///     let mut recorder = MacroRecorder::new(1_000);
+///     let mut recorded = None;
+///
+///     loop {
+///         let event = event::read()?;
+///         match event {
+///             Event::Key(key) if key.code == KeyCode::Char('q') => {
+///                 if recorder.is_recording() {
+///                     recorded = Some(recorder.stop());
+///                 } else {
+///                     recorder.start();
+///                 }
+///             }
+///             Event::Key(key) if key.code == KeyCode::Char('@') => {
+///                 if let Some(recorded) = &recorded {
+///                     event::replay(recorded);
+///                 }
+///             }
+///             Event::Key(key) if key.code == KeyCode::Esc => return Ok(()),
+///             event => recorder.record(&event),
+///         }
+///     }
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MacroRecorder {
+    events: Vec<Event>,
+    limit: usize,
+    recording: bool,
+}
+
+impl MacroRecorder {
+    /// Creates a recorder which keeps at most `limit` events per macro.
+    pub fn new(limit: usize) -> MacroRecorder {
+        MacroRecorder {
+            events: Vec::new(),
+            limit,
+            recording: false,
+        }
+    }
+
+    /// Starts recording a new macro, the events recorded so far are discarded.
+    pub fn start(&mut self) {
+        self.events.clear();
+        self.recording = true;
+    }
+
+    /// Stops recording and returns the recorded macro.
+    pub fn stop(&mut self) -> Macro {
+        self.recording = false;
+        Macro::new(std::mem::take(&mut self.events))
+    }
+
+    /// Returns whether the recorder is recording.
+    pub fn is_recording(&self) -> bool {
+        self.recording
+    }
+
+    /// Records `event` if the recorder is recording.
+    pub fn record(&mut self, event: &Event) {
+        if self.recording && self.events.len() < self.limit && !matches!(event, Event::Tick(_)) {
+            self.events.push(event.clone());
+        }
+    }
+}
+
+/// Replays the events of `recorded`, they are read before any further input.
+///
+/// With the `event-stream` feature, this wakes a pending [`poll`](fn.poll.html), which returns
+/// `Ok(false)` then. Without it, a pending poll reads the events within 50 milliseconds.
+pub fn replay(recorded: &Macro) {
+    if recorded.is_empty() {
+        return;
+    }
+    REPLAYED
+        .lock()
+        .get_or_insert_with(VecDeque::new)
+        .extend(recorded.events.iter().cloned());
+    read::wake_global_reader();
+}
+
+/// Returns the next replayed event.
+pub(crate) fn next_replayed() -> Option<Event> {
+    REPLAYED.lock().as_mut()?.pop_front()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::event::{self, Event, KeyCode};
+
+    use super::{Macro, MacroRecorder};
+
+    #[test]
+    fn test_macro_recorder() {
+        let key = |c| Event::Key(KeyCode::Char(c).into());
+        let mut recorder = MacroRecorder::new(2);
+        recorder.record(&key('a'));
+        assert!(!recorder.is_recording());
+
+        recorder.start();
+        recorder.record(&key('b'));
+        let timer = event::add_timer(Duration::from_secs(3600));
+        event::remove_timer(timer);
+        recorder.record(&Event::Tick(timer));
+        recorder.record(&key('c'));
+        recorder.record(&key('d'));
+        assert_eq!(recorder.stop(), Macro::new(vec![key('b'), key('c')]));
+
+        recorder.record(&key('e'));
+        assert!(recorder.stop().is_empty());
+    }
+}
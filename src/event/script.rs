@@ -0,0 +1,169 @@
+use std::{io, time::Duration};
+
+use super::{
+    read::{self, InternalEventReader},
+    source::script::ScriptSource,
+    Event, KeyCode, KeyEvent, KeyModifiers, MouseEvent, MouseEventKind, INTERNAL_EVENT_READER,
+    SCRIPT_READER,
+};
+
+/// A list of events with delays, which [`play_script`](fn.play_script.html) plays instead of
+/// reading the terminal, e.g. for end-to-end tests of interactive applications.
+///
+/// # Examples
+///
+/// ```no_run
+/// use std::time::Duration;
+/// use crossterm::event::{self, KeyCode, Script};
+///
+/// #[test]
+/// fn test_quit_with_confirmation() {
+///     event::play_script(
+///         Script::new()
+///             .key(KeyCode::Char('q'))
+///             .wait(Duration::from_millis(100))
+///             .text("y"),
+///     )
+///     .unwrap();
+///
+///     // run the application, which reads the events with `event::read`
+///
+///     event::stop_script();
+/// }
+/// ```
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Script {
+    steps: Vec<(Duration, Event)>,
+    // the delay before the next event
+    delay: Duration,
+}
+
+impl Script {
+    /// Creates an empty script.
+    pub fn new() -> Script {
+        Script::default()
+    }
+
+    /// Plays `event`.
+    pub fn event(mut self, event: Event) -> Script {
+        self.steps.push((self.delay, event));
+        self.delay = Duration::ZERO;
+        self
+    }
+
+    /// Presses the key `code`.
+    pub fn key(self, code: KeyCode) -> Script {
+        self.event(Event::Key(code.into()))
+    }
+
+    /// Presses the key `code` with `modifiers`.
+    pub fn key_with(self, code: KeyCode, modifiers: KeyModifiers) -> Script {
+        self.event(Event::Key(KeyEvent::new(code, modifiers)))
+    }
+
+    /// Types `text`, a key per character.
+    pub fn text(self, text: &str) -> Script {
+        text.chars()
+            .fold(self, |script, c| script.key(KeyCode::Char(c)))
+    }
+
+    /// Plays a mouse event of `kind` at `column` and `row`.
+    pub fn mouse(self, kind: MouseEventKind, column: u16, row: u16) -> Script {
+        self.event(Event::Mouse(MouseEvent {
+            kind,
+            column,
+            row,
+            modifiers: KeyModifiers::NONE,
+        }))
+    }
+
+    /// Resizes the terminal to `columns` and `rows`.
+    ///
+    /// This only plays the event, [`terminal::size`](../terminal/fn.size.html) still returns the
+    /// real size.
+    pub fn resize(self, columns: u16, rows: u16) -> Script {
+        self.event(Event::Resize(columns, rows))
+    }
+
+    /// Waits for `delay` before playing the next event.
+    pub fn wait(mut self, delay: Duration) -> Script {
+        self.delay += delay;
+        self
+    }
+}
+
+/// Plays `script` instead of reading the terminal, until [`stop_script`](fn.stop_script.html) is
+/// called.
+///
+/// The events are read with [`read`](fn.read.html), [`poll`](fn.poll.html) and the
+/// [`EventStream`](struct.EventStream.html), which don't need a terminal then. The delays are
+/// measured with the clock installed with [`clock::set_clock`](clock/fn.set_clock.html). Once all
+/// events were read, reading fails with an error of kind `UnexpectedEof`, so that a test doesn't
+/// hang waiting for input which never comes.
+///
+/// The responses to queries, e.g. [`cursor::position`](../cursor/fn.position.html), are still
+/// read from the terminal. The events which were read from the terminal but not returned yet are
+/// returned once the script was stopped.
+pub fn play_script(script: Script) -> io::Result<()> {
+    let source = ScriptSource::new(script.steps)?;
+    *SCRIPT_READER.lock() = Some(InternalEventReader::with_source(Box::new(source), true));
+    Ok(())
+}
+
+/// Stops playing the script passed to [`play_script`](fn.play_script.html), the terminal is read
+/// again.
+pub fn stop_script() {
+    *SCRIPT_READER.lock() = None;
+    read::set_global_reader(INTERNAL_EVENT_READER.lock().as_ref());
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::event::{
+        self, filter::InternalEventFilter, poll_internal, Event, KeyCode, KeyModifiers,
+        MouseButton, MouseEventKind, SCRIPT_READER,
+    };
+
+    use super::{play_script, stop_script, Script};
+
+    #[test]
+    fn test_script_steps() {
+        let script = Script::new()
+            .wait(Duration::from_millis(5))
+            .text("ab")
+            .wait(Duration::from_millis(1))
+            .wait(Duration::from_millis(2))
+            .key_with(KeyCode::Enter, KeyModifiers::ALT)
+            .mouse(MouseEventKind::Down(MouseButton::Left), 1, 2)
+            .resize(10, 20);
+
+        let delays = script
+            .steps
+            .iter()
+            .map(|(delay, _)| delay.as_millis())
+            .collect::<Vec<_>>();
+        assert_eq!(delays, [5, 0, 3, 0, 0]);
+        assert_eq!(script.steps[1].1, Event::Key(KeyCode::Char('b').into()));
+        assert_eq!(script.steps[4].1, Event::Resize(10, 20));
+    }
+
+    #[test]
+    #[serial_test::serial]
+    fn test_queries_dont_read_the_script() {
+        play_script(Script::new().key(KeyCode::Char('a'))).unwrap();
+
+        // a query reads the terminal, if there's one
+        let _ = poll_internal(Some(Duration::ZERO), &InternalEventFilter);
+        assert_eq!(
+            event::read().unwrap(),
+            Event::Key(KeyCode::Char('a').into())
+        );
+        let error = event::read().unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::UnexpectedEof);
+
+        stop_script();
+        assert!(SCRIPT_READER.lock().is_none());
+    }
+}
@@ -4,6 +4,9 @@ use std::{io, time::Duration};
 use super::sys::Waker;
 use super::InternalEvent;
 
+#[cfg(windows)]
+pub(crate) mod msys;
+pub(crate) mod script;
 #[cfg(unix)]
 pub(crate) mod unix;
 #[cfg(windows)]
@@ -21,6 +24,15 @@ pub(crate) trait EventSource: Sync + Send {
     /// Returns `Ok(None)` if there's no event available and timeout expires.
     fn try_read(&mut self, timeout: Option<Duration>) -> io::Result<Option<InternalEvent>>;
 
+    /// Sets whether `\n` is read as the Enter key, instead of following the raw mode of the
+    /// terminal of the process. Sources which don't read `\n` ignore it.
+    #[cfg(unix)]
+    fn set_newline_as_enter(&mut self, _enabled: bool) {}
+
+    /// Makes the source count the sequences it discards in the metrics, for the reader of the
+    /// terminal of the process. Sources which don't parse input ignore it.
+    fn count_parse_errors(&mut self) {}
+
     /// Returns a `Waker` allowing to wake/force the `try_read` method to return `Ok(None)`.
     #[cfg(feature = "event-stream")]
     fn waker(&self) -> Waker;
@@ -0,0 +1,262 @@
+use std::{
+    io, ptr,
+    sync::Arc,
+    thread,
+    time::{Duration, Instant},
+};
+
+use crossterm_winapi::{Handle, Semaphore};
+use parking_lot::Mutex;
+use winapi::um::fileapi::ReadFile;
+
+#[cfg(feature = "event-stream")]
+use crate::event::sys::Waker;
+use crate::event::{
+    parse::Parser, source::EventSource, timeout::PollTimeout, Event, InternalEvent,
+};
+
+/// How often the size is checked while waiting for input, the pty doesn't tell native programs
+/// about resizes.
+const RESIZE_CHECK_INTERVAL: Duration = Duration::from_millis(500);
+
+/// The bytes read from the pty by the input thread, a pipe can't be waited on.
+struct Input {
+    state: Mutex<InputState>,
+    // released when bytes were read or the input failed
+    available: Semaphore,
+}
+
+#[derive(Default)]
+struct InputState {
+    bytes: Vec<u8>,
+    // the last read filled the buffer, so more bytes are probably on their way
+    more: bool,
+    error: Option<io::Error>,
+}
+
+/// The input of the pty, read by a single thread for the whole process, so that no bytes are
+/// lost when the event source is created again.
+static INPUT: Mutex<Option<Arc<Input>>> = parking_lot::const_mutex(None);
+
+/// Returns the input of the pty, starting the thread which reads it first.
+fn input() -> io::Result<Arc<Input>> {
+    let mut input = INPUT.lock();
+    if let Some(input) = &*input {
+        return Ok(input.clone());
+    }
+
+    let handle = Handle::input_handle()?;
+    let shared = Arc::new(Input {
+        state: Mutex::new(InputState::default()),
+        available: Semaphore::new()?,
+    });
+    {
+        let shared = shared.clone();
+        thread::Builder::new()
+            .name("crossterm-msys-input".to_string())
+            .spawn(move || read_input(handle, &shared))?;
+    }
+    *input = Some(shared.clone());
+    Ok(shared)
+}
+
+/// Reads the pty until it fails or ends, blocking in between.
+fn read_input(handle: Handle, input: &Input) {
+    let mut buffer = [0; 1024];
+    loop {
+        let mut read = 0;
+        let result = unsafe {
+            ReadFile(
+                *handle,
+                buffer.as_mut_ptr().cast(),
+                buffer.len() as u32,
+                &mut read,
+                ptr::null_mut(),
+            )
+        };
+
+        let mut state = input.state.lock();
+        if result == 0 || read == 0 {
+            state.error = Some(if result == 0 {
+                io::Error::last_os_error()
+            } else {
+                io::Error::new(io::ErrorKind::UnexpectedEof, "The input has ended.")
+            });
+        } else {
+            let read = read as usize;
+            state.bytes.extend_from_slice(&buffer[..read]);
+            state.more = read == buffer.len();
+        }
+        let failed = state.error.is_some();
+        drop(state);
+        // fails if it's released already, which is fine
+        let _ = input.available.release();
+        if failed {
+            return;
+        }
+    }
+}
+
+/// Tells when the size of the terminal changed.
+struct SizeWatcher {
+    size: Option<(u16, u16)>,
+    checked_at: Instant,
+}
+
+impl SizeWatcher {
+    fn new(size: Option<(u16, u16)>) -> SizeWatcher {
+        SizeWatcher {
+            size,
+            checked_at: Instant::now(),
+        }
+    }
+
+    /// Returns how long until the size should be checked again.
+    fn next_check(&self) -> Duration {
+        RESIZE_CHECK_INTERVAL.saturating_sub(self.checked_at.elapsed())
+    }
+
+    /// Takes the size which was checked, returns it if it changed.
+    fn update(&mut self, size: Option<(u16, u16)>) -> Option<(u16, u16)> {
+        self.checked_at = Instant::now();
+        match size {
+            Some(size) if self.size != Some(size) => {
+                self.size = Some(size);
+                Some(size)
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Reads the events of a MSYS2 or Cygwin pty, e.g. mintty (Git Bash).
+///
+/// The standard input is a named pipe which carries the bytes the terminal sends, like a tty on
+/// UNIX, so they're parsed like on UNIX. The pipe is read on a thread, which signals a semaphore
+/// the source waits on along with the waker. Resizes are noticed by checking the size regularly
+/// while waiting.
+pub(crate) struct MsysEventSource {
+    input: Arc<Input>,
+    parser: Parser,
+    size: SizeWatcher,
+    #[cfg(feature = "event-stream")]
+    waker: Waker,
+}
+
+impl MsysEventSource {
+    pub(crate) fn new() -> io::Result<MsysEventSource> {
+        Ok(MsysEventSource {
+            input: input()?,
+            parser: Parser::for_terminal(),
+            size: SizeWatcher::new(crate::terminal::sys::check_msys_size().ok()),
+            #[cfg(feature = "event-stream")]
+            waker: Waker::new()?,
+        })
+    }
+
+    /// Waits until bytes were read, `timeout` elapsed, or the waker is woken.
+    fn wait(&self, timeout: Duration) -> io::Result<()> {
+        let millis = timeout.as_millis().min(u32::MAX as u128) as u32;
+
+        #[cfg(feature = "event-stream")]
+        {
+            use winapi::{
+                shared::winerror::WAIT_TIMEOUT,
+                um::{synchapi::WaitForMultipleObjects, winbase::WAIT_OBJECT_0},
+            };
+
+            let semaphore = self.waker.semaphore();
+            let handles = [**self.input.available.handle(), **semaphore.handle()];
+            let result = unsafe {
+                WaitForMultipleObjects(handles.len() as u32, handles.as_ptr(), 0, millis)
+            };
+            if result == WAIT_OBJECT_0 + 1 {
+                let _ = self.waker.reset();
+                return Err(io::Error::new(
+                    io::ErrorKind::Interrupted,
+                    "Poll operation was woken up by `Waker::wake`",
+                ));
+            }
+            if result != WAIT_OBJECT_0 && result != WAIT_TIMEOUT {
+                return Err(io::Error::last_os_error());
+            }
+        }
+        #[cfg(not(feature = "event-stream"))]
+        {
+            use winapi::um::{synchapi::WaitForSingleObject, winbase::WAIT_FAILED};
+
+            let handle = **self.input.available.handle();
+            if unsafe { WaitForSingleObject(handle, millis) } == WAIT_FAILED {
+                return Err(io::Error::last_os_error());
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl EventSource for MsysEventSource {
+    fn try_read(&mut self, timeout: Option<Duration>) -> io::Result<Option<InternalEvent>> {
+        let poll_timeout = PollTimeout::new(timeout);
+
+        loop {
+            if let Some(event) = self.parser.next_internal() {
+                return Ok(Some(event));
+            }
+
+            {
+                let mut state = self.input.state.lock();
+                if !state.bytes.is_empty() {
+                    let more = state.more;
+                    self.parser.advance(&state.bytes, more);
+                    state.bytes.clear();
+                    continue;
+                }
+                if let Some(e) = &state.error {
+                    return Err(io::Error::new(e.kind(), e.to_string()));
+                }
+            }
+
+            if self.size.next_check() == Duration::ZERO {
+                if let Some((columns, rows)) = self
+                    .size
+                    .update(crate::terminal::sys::check_msys_size().ok())
+                {
+                    return Ok(Some(InternalEvent::Event(Event::Resize(columns, rows))));
+                }
+            }
+
+            if poll_timeout.elapsed() {
+                return Ok(None);
+            }
+            let next_check = self.size.next_check();
+            let interval = poll_timeout
+                .leftover()
+                .map_or(next_check, |leftover| leftover.min(next_check));
+            self.wait(interval)?;
+        }
+    }
+
+    fn count_parse_errors(&mut self) {
+        self.parser.count_errors();
+    }
+
+    #[cfg(feature = "event-stream")]
+    fn waker(&self) -> Waker {
+        self.waker.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SizeWatcher;
+
+    #[test]
+    fn test_size_watcher() {
+        let mut size = SizeWatcher::new(Some((80, 24)));
+        assert_eq!(size.update(Some((80, 24))), None);
+        assert_eq!(size.update(None), None);
+        assert_eq!(size.update(Some((100, 30))), Some((100, 30)));
+        assert_eq!(size.update(Some((100, 30))), None);
+    }
+}
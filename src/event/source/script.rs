@@ -0,0 +1,247 @@
+use std::{
+    collections::VecDeque,
+    io,
+    time::{Duration, Instant},
+};
+
+#[cfg(feature = "event-stream")]
+use crate::event::sys::Waker;
+use crate::event::{clock, source::EventSource, Event, InternalEvent};
+
+/// An event source which plays scripted events instead of reading the terminal.
+pub(crate) struct ScriptSource {
+    // the events with the delay after the previous one
+    steps: VecDeque<(Duration, Event)>,
+    // when the previous event was due
+    last: Instant,
+    sleeper: Sleeper,
+}
+
+impl ScriptSource {
+    pub(crate) fn new(steps: Vec<(Duration, Event)>) -> io::Result<ScriptSource> {
+        Ok(ScriptSource {
+            steps: steps.into(),
+            last: clock::now(),
+            sleeper: Sleeper::new()?,
+        })
+    }
+}
+
+impl EventSource for ScriptSource {
+    fn try_read(&mut self, timeout: Option<Duration>) -> io::Result<Option<InternalEvent>> {
+        let due = match self.steps.front() {
+            Some((delay, _)) => self.last + *delay,
+            None => {
+                return Err(io::Error::new(
+                    io::ErrorKind::UnexpectedEof,
+                    "The script has ended.",
+                ))
+            }
+        };
+
+        let wait = due.saturating_duration_since(clock::now());
+        if !wait.is_zero() {
+            match timeout {
+                Some(timeout) if timeout < wait => {
+                    self.sleeper.sleep(timeout)?;
+                    return Ok(None);
+                }
+                _ => self.sleeper.sleep(wait)?,
+            }
+            // an installed clock might not have advanced
+            if clock::now() < due {
+                return Ok(None);
+            }
+        }
+
+        self.last = due;
+        Ok(self
+            .steps
+            .pop_front()
+            .map(|(_, event)| InternalEvent::Event(event)))
+    }
+
+    #[cfg(feature = "event-stream")]
+    fn waker(&self) -> Waker {
+        self.sleeper.waker.clone()
+    }
+}
+
+#[cfg(feature = "event-stream")]
+fn woken() -> io::Error {
+    io::Error::new(
+        io::ErrorKind::Interrupted,
+        "Poll operation was woken up by `Waker::wake`",
+    )
+}
+
+/// Sleeps until the time is up or the waker wakes it.
+#[cfg(not(feature = "event-stream"))]
+struct Sleeper;
+
+#[cfg(not(feature = "event-stream"))]
+impl Sleeper {
+    fn new() -> io::Result<Sleeper> {
+        Ok(Sleeper)
+    }
+
+    fn sleep(&mut self, duration: Duration) -> io::Result<()> {
+        std::thread::sleep(duration);
+        Ok(())
+    }
+}
+
+#[cfg(all(
+    feature = "event-stream",
+    unix,
+    not(any(
+        feature = "use-dev-tty",
+        all(feature = "use-epoll", any(target_os = "linux", target_os = "android"))
+    ))
+))]
+struct Sleeper {
+    poll: mio::Poll,
+    events: mio::Events,
+    waker: Waker,
+}
+
+#[cfg(all(
+    feature = "event-stream",
+    unix,
+    not(any(
+        feature = "use-dev-tty",
+        all(feature = "use-epoll", any(target_os = "linux", target_os = "android"))
+    ))
+))]
+impl Sleeper {
+    fn new() -> io::Result<Sleeper> {
+        let poll = mio::Poll::new()?;
+        let waker = Waker::new(poll.registry(), mio::Token(0))?;
+        Ok(Sleeper {
+            poll,
+            events: mio::Events::with_capacity(1),
+            waker,
+        })
+    }
+
+    fn sleep(&mut self, duration: Duration) -> io::Result<()> {
+        match self.poll.poll(&mut self.events, Some(duration)) {
+            Ok(()) if self.events.is_empty() => Ok(()),
+            Ok(()) => Err(woken()),
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => Ok(()),
+            Err(e) => Err(e),
+        }
+    }
+}
+
+#[cfg(all(
+    feature = "event-stream",
+    unix,
+    any(
+        feature = "use-dev-tty",
+        all(feature = "use-epoll", any(target_os = "linux", target_os = "android"))
+    )
+))]
+struct Sleeper {
+    receiver: std::os::unix::net::UnixStream,
+    waker: Waker,
+}
+
+#[cfg(all(
+    feature = "event-stream",
+    unix,
+    any(
+        feature = "use-dev-tty",
+        all(feature = "use-epoll", any(target_os = "linux", target_os = "android"))
+    )
+))]
+impl Sleeper {
+    fn new() -> io::Result<Sleeper> {
+        let (receiver, sender) = std::os::unix::net::UnixStream::pair()?;
+        Ok(Sleeper {
+            receiver,
+            waker: Waker::new(sender),
+        })
+    }
+
+    fn sleep(&mut self, duration: Duration) -> io::Result<()> {
+        use std::io::Read;
+
+        if duration.is_zero() {
+            return Ok(());
+        }
+        self.receiver.set_read_timeout(Some(duration))?;
+        match self.receiver.read(&mut [0; 32]) {
+            Ok(_) => Err(woken()),
+            Err(e)
+                if matches!(
+                    e.kind(),
+                    io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut
+                ) =>
+            {
+                Ok(())
+            }
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => Ok(()),
+            Err(e) => Err(e),
+        }
+    }
+}
+
+#[cfg(all(feature = "event-stream", windows))]
+struct Sleeper {
+    waker: Waker,
+}
+
+#[cfg(all(feature = "event-stream", windows))]
+impl Sleeper {
+    fn new() -> io::Result<Sleeper> {
+        Ok(Sleeper {
+            waker: Waker::new()?,
+        })
+    }
+
+    fn sleep(&mut self, duration: Duration) -> io::Result<()> {
+        use winapi::um::{synchapi::WaitForSingleObject, winbase::WAIT_OBJECT_0};
+
+        let semaphore = self.waker.semaphore();
+        let millis = duration.as_millis().min(u32::MAX as u128 - 1) as u32;
+        if unsafe { WaitForSingleObject(**semaphore.handle(), millis) } == WAIT_OBJECT_0 {
+            let _ = self.waker.reset();
+            return Err(woken());
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{io, time::Duration};
+
+    use crate::event::{source::EventSource, Event, InternalEvent, KeyCode};
+
+    use super::ScriptSource;
+
+    #[test]
+    fn test_script_source_plays_events_in_time() {
+        let key = |c| Event::Key(KeyCode::Char(c).into());
+        let mut source = ScriptSource::new(vec![
+            (Duration::ZERO, key('a')),
+            (Duration::from_millis(50), key('b')),
+        ])
+        .unwrap();
+
+        assert_eq!(
+            source.try_read(Some(Duration::ZERO)).unwrap(),
+            Some(InternalEvent::Event(key('a')))
+        );
+        assert_eq!(source.try_read(Some(Duration::ZERO)).unwrap(), None);
+        assert_eq!(
+            source.try_read(None).unwrap(),
+            Some(InternalEvent::Event(key('b')))
+        );
+        assert_eq!(
+            source.try_read(None).unwrap_err().kind(),
+            io::ErrorKind::UnexpectedEof
+        );
+    }
+}
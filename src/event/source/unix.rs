@@ -1,11 +1,29 @@
-#[cfg(feature = "use-dev-tty")]
+#[cfg(all(feature = "use-epoll", any(target_os = "linux", target_os = "android")))]
+pub(crate) mod epoll;
+
+#[cfg(all(
+    feature = "use-dev-tty",
+    not(all(feature = "use-epoll", any(target_os = "linux", target_os = "android")))
+))]
 pub(crate) mod tty;
 
-#[cfg(not(feature = "use-dev-tty"))]
+#[cfg(not(any(
+    feature = "use-dev-tty",
+    all(feature = "use-epoll", any(target_os = "linux", target_os = "android"))
+)))]
 pub(crate) mod mio;
 
-#[cfg(feature = "use-dev-tty")]
+#[cfg(all(feature = "use-epoll", any(target_os = "linux", target_os = "android")))]
+pub(crate) use self::epoll::UnixInternalEventSource;
+
+#[cfg(all(
+    feature = "use-dev-tty",
+    not(all(feature = "use-epoll", any(target_os = "linux", target_os = "android")))
+))]
 pub(crate) use self::tty::UnixInternalEventSource;
 
-#[cfg(not(feature = "use-dev-tty"))]
+#[cfg(not(any(
+    feature = "use-dev-tty",
+    all(feature = "use-epoll", any(target_os = "linux", target_os = "android"))
+)))]
 pub(crate) use self::mio::UnixInternalEventSource;
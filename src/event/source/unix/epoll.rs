@@ -0,0 +1,212 @@
+// rustix needs Rust 1.63, so does this event source, regardless of the crate's MSRV.
+#![allow(clippy::incompatible_msrv)]
+
+use std::os::unix::prelude::AsRawFd;
+use std::{io, os::unix::net::UnixStream, time::Duration};
+
+use rustix::event::epoll::{self, CreateFlags, EventData, EventFlags, EventVec};
+use rustix::fd::{BorrowedFd, OwnedFd};
+use signal_hook::low_level::pipe;
+
+#[cfg(feature = "event-stream")]
+use crate::event::sys::Waker;
+use crate::event::{
+    parse::Parser, source::EventSource, timeout::PollTimeout, Event, InternalEvent,
+};
+use crate::terminal::sys::file_descriptor::{tty_fd, FileDesc};
+
+// Tokens to identify file descriptor
+const TTY_TOKEN: u64 = 0;
+const SIGNAL_TOKEN: u64 = 1;
+#[cfg(feature = "event-stream")]
+const WAKE_TOKEN: u64 = 2;
+
+// I (@zrzka) wasn't able to read more than 1_022 bytes when testing
+// reading on macOS/Linux -> we don't need bigger buffer and 1k of bytes
+// is enough.
+const TTY_BUFFER_SIZE: usize = 1_024;
+
+/// The ready events returned by `epoll_wait`.
+///
+/// `EventVec` isn't `Send` and `Sync`, because `EventData` can hold a pointer. We only ever store
+/// tokens in it.
+struct Events(EventVec);
+
+unsafe impl Send for Events {}
+unsafe impl Sync for Events {}
+
+/// Event source which waits for input with `epoll` directly.
+///
+/// Compared to mio, it does one `epoll_wait` per wakeup without any bookkeeping in between and
+/// drains the tty with as few `read` calls as possible, which matters under heavy mouse traffic.
+pub(crate) struct UnixInternalEventSource {
+    epoll: OwnedFd,
+    events: Events,
+    parser: Parser,
+    tty_buffer: [u8; TTY_BUFFER_SIZE],
+    tty: FileDesc,
+    winch_signal_receiver: UnixStream,
+    #[cfg(feature = "event-stream")]
+    wake_receiver: UnixStream,
+    #[cfg(feature = "event-stream")]
+    waker: Waker,
+}
+
+fn nonblocking_unix_pair() -> io::Result<(UnixStream, UnixStream)> {
+    let (receiver, sender) = UnixStream::pair()?;
+    receiver.set_nonblocking(true)?;
+    sender.set_nonblocking(true)?;
+    Ok((receiver, sender))
+}
+
+fn register(epoll: &OwnedFd, fd: &impl AsRawFd, token: u64) -> io::Result<()> {
+    // SAFETY: the registered file descriptors are owned by the event source, which also owns the
+    // epoll instance, so they outlive their registration.
+    let fd = unsafe { BorrowedFd::borrow_raw(fd.as_raw_fd()) };
+    epoll::add(epoll, fd, EventData::new_u64(token), EventFlags::IN)?;
+    Ok(())
+}
+
+impl UnixInternalEventSource {
+    pub fn new() -> io::Result<Self> {
+        UnixInternalEventSource::from_file_descriptor(tty_fd()?)
+    }
+
+    pub(crate) fn from_file_descriptor(input_fd: FileDesc) -> io::Result<Self> {
+        let epoll = epoll::create(CreateFlags::CLOEXEC)?;
+
+        let (winch_signal_receiver, sender) = nonblocking_unix_pair()?;
+        // Unregistering is unnecessary because EventSource is a singleton
+        pipe::register(libc::SIGWINCH, sender)?;
+
+        register(&epoll, &input_fd.raw_fd(), TTY_TOKEN)?;
+        register(&epoll, &winch_signal_receiver, SIGNAL_TOKEN)?;
+
+        #[cfg(feature = "event-stream")]
+        let (wake_receiver, waker) = {
+            let (receiver, sender) = nonblocking_unix_pair()?;
+            register(&epoll, &receiver, WAKE_TOKEN)?;
+            (receiver, Waker::new(sender))
+        };
+
+        Ok(UnixInternalEventSource {
+            epoll,
+            events: Events(EventVec::with_capacity(3)),
+            parser: Parser::for_terminal(),
+            tty_buffer: [0u8; TTY_BUFFER_SIZE],
+            tty: input_fd,
+            winch_signal_receiver,
+            #[cfg(feature = "event-stream")]
+            wake_receiver,
+            #[cfg(feature = "event-stream")]
+            waker,
+        })
+    }
+}
+
+/// Reads from a non-blocking file descriptor, returns 0 if it would block.
+fn read_nonblocking(fd: &FileDesc, buf: &mut [u8]) -> io::Result<usize> {
+    loop {
+        match fd.read(buf) {
+            Ok(x) => return Ok(x),
+            Err(e) => match e.kind() {
+                io::ErrorKind::WouldBlock => return Ok(0),
+                io::ErrorKind::Interrupted => continue,
+                _ => return Err(e),
+            },
+        }
+    }
+}
+
+/// Reads everything that is available from a non-blocking pipe.
+fn drain(receiver: &UnixStream) -> io::Result<()> {
+    let fd = FileDesc::new(receiver.as_raw_fd(), false);
+    while read_nonblocking(&fd, &mut [0; 1024])? != 0 {}
+    Ok(())
+}
+
+impl EventSource for UnixInternalEventSource {
+    fn try_read(&mut self, timeout: Option<Duration>) -> io::Result<Option<InternalEvent>> {
+        if let Some(event) = self.parser.next_internal() {
+            return Ok(Some(event));
+        }
+
+        let timeout = PollTimeout::new(timeout);
+
+        loop {
+            let timeout_ms = timeout.leftover().map_or(-1, |leftover| {
+                // Round up, otherwise we'd spin for timeouts below one millisecond.
+                let millis = leftover.as_nanos().saturating_add(999_999) / 1_000_000;
+                millis.min(i32::MAX as u128) as i32
+            });
+
+            if let Err(e) = epoll::wait(&self.epoll, &mut self.events.0, timeout_ms) {
+                if e == rustix::io::Errno::INTR {
+                    continue;
+                }
+                return Err(e.into());
+            }
+
+            if self.events.0.is_empty() {
+                // No readiness events = timeout
+                return Ok(None);
+            }
+
+            for token in self.events.0.iter().map(|event| event.data.u64()) {
+                match token {
+                    TTY_TOKEN => loop {
+                        let read_count = read_nonblocking(&self.tty, &mut self.tty_buffer)?;
+                        if read_count > 0 {
+                            self.parser.advance(
+                                &self.tty_buffer[..read_count],
+                                read_count == TTY_BUFFER_SIZE,
+                            );
+                        }
+
+                        if let Some(event) = self.parser.next_internal() {
+                            return Ok(Some(event));
+                        }
+
+                        if read_count == 0 {
+                            break;
+                        }
+                    },
+                    SIGNAL_TOKEN => {
+                        drain(&self.winch_signal_receiver)?;
+                        let new_size = crate::terminal::sys::size_of(self.tty.raw_fd())?;
+                        return Ok(Some(InternalEvent::Event(Event::Resize(
+                            new_size.0, new_size.1,
+                        ))));
+                    }
+                    #[cfg(feature = "event-stream")]
+                    WAKE_TOKEN => {
+                        drain(&self.wake_receiver)?;
+                        return Err(std::io::Error::new(
+                            std::io::ErrorKind::Interrupted,
+                            "Poll operation was woken up by `Waker::wake`",
+                        ));
+                    }
+                    _ => unreachable!("Synchronize epoll registration & token handling"),
+                }
+            }
+
+            // Processing above can take some time, check if timeout expired
+            if timeout.elapsed() {
+                return Ok(None);
+            }
+        }
+    }
+
+    fn set_newline_as_enter(&mut self, enabled: bool) {
+        self.parser.set_newline_as_enter(enabled);
+    }
+
+    fn count_parse_errors(&mut self) {
+        self.parser.count_errors();
+    }
+
+    #[cfg(feature = "event-stream")]
+    fn waker(&self) -> Waker {
+        self.waker.clone()
+    }
+}
@@ -1,5 +1,5 @@
 use std::os::unix::prelude::AsRawFd;
-use std::{collections::VecDeque, io, os::unix::net::UnixStream, time::Duration};
+use std::{io, os::unix::net::UnixStream, time::Duration};
 
 use signal_hook::low_level::pipe;
 
@@ -9,7 +9,7 @@ use filedescriptor::{poll, pollfd, POLLIN};
 
 #[cfg(feature = "event-stream")]
 use crate::event::sys::Waker;
-use crate::event::{source::EventSource, sys::unix::parse::parse_event, InternalEvent};
+use crate::event::{parse::Parser, source::EventSource, InternalEvent};
 use crate::terminal::sys::file_descriptor::{tty_fd, FileDesc};
 
 /// Holds a prototypical Waker and a receiver we can wait on when doing select().
@@ -58,7 +58,7 @@ impl UnixInternalEventSource {
 
     pub(crate) fn from_file_descriptor(input_fd: FileDesc) -> io::Result<Self> {
         Ok(UnixInternalEventSource {
-            parser: Parser::default(),
+            parser: Parser::for_terminal(),
             tty_buffer: [0u8; TTY_BUFFER_SIZE],
             tty: input_fd,
             winch_signal_receiver: {
@@ -80,7 +80,7 @@ impl UnixInternalEventSource {
 /// only fills the given buffer and does not read beyond that.
 fn read_complete(fd: &FileDesc, buf: &mut [u8]) -> io::Result<usize> {
     loop {
-        match fd.read(buf, buf.len()) {
+        match fd.read(buf) {
             Ok(x) => return Ok(x),
             Err(e) => match e.kind() {
                 io::ErrorKind::WouldBlock => return Ok(0),
@@ -118,7 +118,7 @@ impl EventSource for UnixInternalEventSource {
 
         while timeout.leftover().map_or(true, |t| !t.is_zero()) {
             // check if there are buffered events from the last read
-            if let Some(event) = self.parser.next() {
+            if let Some(event) = self.parser.next_internal() {
                 return Ok(Some(event));
             }
             match poll(&mut fds, timeout.leftover()) {
@@ -147,7 +147,7 @@ impl EventSource for UnixInternalEventSource {
                         );
                     }
 
-                    if let Some(event) = self.parser.next() {
+                    if let Some(event) = self.parser.next_internal() {
                         return Ok(Some(event));
                     }
 
@@ -167,7 +167,7 @@ impl EventSource for UnixInternalEventSource {
                 // not a really long time from the absolute time point of view, but
                 // it's a really long time from the mio, async-std/tokio executor, ...
                 // point of view.
-                let new_size = crate::terminal::size()?;
+                let new_size = crate::terminal::sys::size_of(self.tty.raw_fd())?;
                 return Ok(Some(InternalEvent::Event(Event::Resize(
                     new_size.0, new_size.1,
                 ))));
@@ -188,78 +188,16 @@ impl EventSource for UnixInternalEventSource {
         Ok(None)
     }
 
-    #[cfg(feature = "event-stream")]
-    fn waker(&self) -> Waker {
-        self.wake_pipe.waker.clone()
+    fn set_newline_as_enter(&mut self, enabled: bool) {
+        self.parser.set_newline_as_enter(enabled);
     }
-}
-
-//
-// Following `Parser` structure exists for two reasons:
-//
-//  * mimic anes Parser interface
-//  * move the advancing, parsing, ... stuff out of the `try_read` method
-//
-#[derive(Debug)]
-struct Parser {
-    buffer: Vec<u8>,
-    internal_events: VecDeque<InternalEvent>,
-}
-
-impl Default for Parser {
-    fn default() -> Self {
-        Parser {
-            // This buffer is used for -> 1 <- ANSI escape sequence. Are we
-            // aware of any ANSI escape sequence that is bigger? Can we make
-            // it smaller?
-            //
-            // Probably not worth spending more time on this as "there's a plan"
-            // to use the anes crate parser.
-            buffer: Vec::with_capacity(256),
-            // TTY_BUFFER_SIZE is 1_024 bytes. How many ANSI escape sequences can
-            // fit? What is an average sequence length? Let's guess here
-            // and say that the average ANSI escape sequence length is 8 bytes. Thus
-            // the buffer size should be 1024/8=128 to avoid additional allocations
-            // when processing large amounts of data.
-            //
-            // There's no need to make it bigger, because when you look at the `try_read`
-            // method implementation, all events are consumed before the next TTY_BUFFER
-            // is processed -> events pushed.
-            internal_events: VecDeque::with_capacity(128),
-        }
-    }
-}
-
-impl Parser {
-    fn advance(&mut self, buffer: &[u8], more: bool) {
-        for (idx, byte) in buffer.iter().enumerate() {
-            let more = idx + 1 < buffer.len() || more;
 
-            self.buffer.push(*byte);
-
-            match parse_event(&self.buffer, more) {
-                Ok(Some(ie)) => {
-                    self.internal_events.push_back(ie);
-                    self.buffer.clear();
-                }
-                Ok(None) => {
-                    // Event can't be parsed, because we don't have enough bytes for
-                    // the current sequence. Keep the buffer and process next bytes.
-                }
-                Err(_) => {
-                    // Event can't be parsed (not enough parameters, parameter is not a number, ...).
-                    // Clear the buffer and continue with another sequence.
-                    self.buffer.clear();
-                }
-            }
-        }
+    fn count_parse_errors(&mut self) {
+        self.parser.count_errors();
     }
-}
-
-impl Iterator for Parser {
-    type Item = InternalEvent;
 
-    fn next(&mut self) -> Option<Self::Item> {
-        self.internal_events.pop_front()
+    #[cfg(feature = "event-stream")]
+    fn waker(&self) -> Waker {
+        self.wake_pipe.waker.clone()
     }
 }
@@ -3,7 +3,12 @@ use std::time::Duration;
 use crossterm_winapi::{Console, Handle, InputRecord};
 
 use crate::event::{
-    sys::windows::{parse::MouseButtonsPressed, poll::WinApiPoll},
+    parse::Parser,
+    sys::windows::{
+        is_vt_input_enabled,
+        parse::{handle_vt_key_event, MouseButtonsPressed, SurrogateBuffer},
+        poll::WinApiPoll,
+    },
     Event,
 };
 
@@ -19,8 +24,10 @@ use crate::event::{
 pub(crate) struct WindowsEventSource {
     console: Console,
     poll: WinApiPoll,
-    surrogate_buffer: Option<u16>,
+    surrogate_buffer: SurrogateBuffer,
     mouse_buttons_pressed: MouseButtonsPressed,
+    /// Parses the input in virtual terminal input mode.
+    parser: Parser,
 }
 
 impl WindowsEventSource {
@@ -34,21 +41,42 @@ impl WindowsEventSource {
             #[cfg(feature = "event-stream")]
             poll: WinApiPoll::new()?,
 
-            surrogate_buffer: None,
+            surrogate_buffer: SurrogateBuffer::default(),
             mouse_buttons_pressed: MouseButtonsPressed::default(),
+            parser: Parser::for_terminal(),
         })
     }
 }
 
 impl EventSource for WindowsEventSource {
     fn try_read(&mut self, timeout: Option<Duration>) -> std::io::Result<Option<InternalEvent>> {
+        if let Some(event) = self.parser.next_internal() {
+            return Ok(Some(event));
+        }
+
         let poll_timeout = PollTimeout::new(timeout);
+        let vt_input = is_vt_input_enabled();
 
         loop {
             if let Some(event_ready) = self.poll.poll(poll_timeout.leftover())? {
                 let number = self.console.number_of_console_input_events()?;
                 if event_ready && number != 0 {
                     let event = match self.console.read_single_input_event()? {
+                        InputRecord::KeyEvent(record) if vt_input => {
+                            // The key records carry the VT sequences, parse them like on UNIX.
+                            if let Some(ch) =
+                                handle_vt_key_event(&record, &mut self.surrogate_buffer)
+                            {
+                                let more = self.console.number_of_console_input_events()? != 0;
+                                self.parser
+                                    .advance(ch.encode_utf8(&mut [0; 4]).as_bytes(), more);
+                            }
+
+                            if let Some(event) = self.parser.next_internal() {
+                                return Ok(Some(event));
+                            }
+                            None
+                        }
                         InputRecord::KeyEvent(record) => {
                             handle_key_event(record, &mut self.surrogate_buffer)
                         }
@@ -93,6 +121,10 @@ impl EventSource for WindowsEventSource {
         }
     }
 
+    fn count_parse_errors(&mut self) {
+        self.parser.count_errors();
+    }
+
     #[cfg(feature = "event-stream")]
     fn waker(&self) -> Waker {
         self.poll.waker()
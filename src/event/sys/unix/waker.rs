@@ -1,11 +1,23 @@
-#[cfg(feature = "use-dev-tty")]
+#[cfg(any(
+    feature = "use-dev-tty",
+    all(feature = "use-epoll", any(target_os = "linux", target_os = "android"))
+))]
 pub(crate) mod tty;
 
-#[cfg(not(feature = "use-dev-tty"))]
+#[cfg(not(any(
+    feature = "use-dev-tty",
+    all(feature = "use-epoll", any(target_os = "linux", target_os = "android"))
+)))]
 pub(crate) mod mio;
 
-#[cfg(feature = "use-dev-tty")]
+#[cfg(any(
+    feature = "use-dev-tty",
+    all(feature = "use-epoll", any(target_os = "linux", target_os = "android"))
+))]
 pub(crate) use self::tty::Waker;
 
-#[cfg(not(feature = "use-dev-tty"))]
+#[cfg(not(any(
+    feature = "use-dev-tty",
+    all(feature = "use-epoll", any(target_os = "linux", target_os = "android"))
+)))]
 pub(crate) use self::mio::Waker;
@@ -5,6 +5,9 @@ use std::io;
 use std::sync::atomic::{AtomicU64, Ordering};
 
 use crossterm_winapi::{ConsoleMode, Handle};
+use winapi::um::wincon::{
+    ENABLE_EXTENDED_FLAGS, ENABLE_QUICK_EDIT_MODE, ENABLE_VIRTUAL_TERMINAL_INPUT,
+};
 
 pub(crate) mod parse;
 pub(crate) mod poll;
@@ -35,8 +38,9 @@ fn original_console_mode() -> std::io::Result<u32> {
 
 pub(crate) fn enable_mouse_capture() -> std::io::Result<()> {
     let mode = ConsoleMode::from(Handle::current_in_handle()?);
-    init_original_console_mode(mode.mode()?);
-    mode.set_mode(ENABLE_MOUSE_MODE)?;
+    let current_mode = mode.mode()?;
+    init_original_console_mode(current_mode);
+    mode.set_mode(ENABLE_MOUSE_MODE | (current_mode & ENABLE_VIRTUAL_TERMINAL_INPUT))?;
 
     Ok(())
 }
@@ -46,3 +50,33 @@ pub(crate) fn disable_mouse_capture() -> std::io::Result<()> {
     mode.set_mode(original_console_mode()?)?;
     Ok(())
 }
+
+pub(crate) fn set_quick_edit(enabled: bool) -> std::io::Result<()> {
+    let mode = ConsoleMode::from(Handle::current_in_handle()?);
+    // The QuickEdit flag is only applied together with ENABLE_EXTENDED_FLAGS.
+    let current_mode = mode.mode()? | ENABLE_EXTENDED_FLAGS;
+
+    if enabled {
+        mode.set_mode(current_mode | ENABLE_QUICK_EDIT_MODE)
+    } else {
+        mode.set_mode(current_mode & !ENABLE_QUICK_EDIT_MODE)
+    }
+}
+
+/// Returns whether the console reports input as virtual terminal sequences.
+pub(crate) fn is_vt_input_enabled() -> bool {
+    Handle::current_in_handle()
+        .and_then(|handle| ConsoleMode::from(handle).mode())
+        .map_or(false, |mode| mode & ENABLE_VIRTUAL_TERMINAL_INPUT != 0)
+}
+
+pub(crate) fn set_vt_input(enabled: bool) -> std::io::Result<()> {
+    let mode = ConsoleMode::from(Handle::current_in_handle()?);
+    let current_mode = mode.mode()?;
+
+    if enabled {
+        mode.set_mode(current_mode | ENABLE_VIRTUAL_TERMINAL_INPUT)
+    } else {
+        mode.set_mode(current_mode & !ENABLE_VIRTUAL_TERMINAL_INPUT)
+    }
+}
@@ -1,8 +1,8 @@
 use crossterm_winapi::{ControlKeyState, EventFlags, KeyEventRecord, ScreenBuffer};
 use winapi::um::{
     wincon::{
-        CAPSLOCK_ON, LEFT_ALT_PRESSED, LEFT_CTRL_PRESSED, RIGHT_ALT_PRESSED, RIGHT_CTRL_PRESSED,
-        SHIFT_PRESSED,
+        CAPSLOCK_ON, LEFT_ALT_PRESSED, LEFT_CTRL_PRESSED, NUMLOCK_ON, RIGHT_ALT_PRESSED,
+        RIGHT_CTRL_PRESSED, SHIFT_PRESSED,
     },
     winuser::{
         GetForegroundWindow, GetKeyboardLayout, GetWindowThreadProcessId, ToUnicodeEx, VK_BACK,
@@ -13,7 +13,8 @@ use winapi::um::{
 };
 
 use crate::event::{
-    Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers, MouseButton, MouseEvent, MouseEventKind,
+    Event, ExtendedModifiers, KeyCode, KeyEvent, KeyEventKind, KeyEventState, KeyModifiers,
+    MouseButton, MouseEvent, MouseEventKind, PhysicalKey,
 };
 
 #[derive(Default)]
@@ -36,42 +37,100 @@ pub(crate) fn handle_mouse_event(
 
 enum WindowsKeyEvent {
     KeyEvent(KeyEvent),
-    Surrogate(u16),
+    Surrogate(u16, KeyEventKind),
+}
+
+/// Assembles characters outside of the Basic Multilingual Plane (e.g. emoji or characters typed
+/// with an IME), which arrive as two key event records, one per UTF-16 surrogate.
+///
+/// Presses and releases are assembled separately, because the console may interleave them.
+#[derive(Debug, Default)]
+pub(crate) struct SurrogateBuffer {
+    press: Option<u16>,
+    release: Option<u16>,
+}
+
+impl SurrogateBuffer {
+    /// Pushes a surrogate, returns the character once both halves arrived.
+    ///
+    /// A low surrogate without a preceding high surrogate is discarded, a high surrogate replaces
+    /// a pending one.
+    fn push(&mut self, surrogate: u16, kind: KeyEventKind) -> Option<char> {
+        let pending = match kind {
+            KeyEventKind::Release => &mut self.release,
+            KeyEventKind::Press | KeyEventKind::Repeat => &mut self.press,
+        };
+
+        if (0xD800..=0xDBFF).contains(&surrogate) {
+            *pending = Some(surrogate);
+            return None;
+        }
+
+        let high = pending.take()?;
+        std::char::decode_utf16([high, surrogate]).next()?.ok()
+    }
+
+    /// Discards the pending halves, e.g. because another key event arrived in between.
+    fn clear(&mut self) {
+        self.press = None;
+        self.release = None;
+    }
 }
 
 pub(crate) fn handle_key_event(
     key_event: KeyEventRecord,
-    surrogate_buffer: &mut Option<u16>,
+    surrogate_buffer: &mut SurrogateBuffer,
 ) -> Option<Event> {
     let windows_key_event = parse_key_event_record(&key_event)?;
+    let sides = ExtendedModifiers::from(&key_event.control_key_state);
+    let lock_state = KeyEventState::from(&key_event.control_key_state);
+    // characters injected by an IME don't come from a key and have no scan code
+    let physical_key = match key_event.virtual_scan_code {
+        0 => None,
+        scan_code => Some(PhysicalKey::ScanCode(scan_code)),
+    };
     match windows_key_event {
         WindowsKeyEvent::KeyEvent(key_event) => {
             // Discard any buffered surrogate value if another valid key event comes before the
             // next surrogate value.
-            *surrogate_buffer = None;
+            surrogate_buffer.clear();
+            // AltGr characters drop the Ctrl and Alt modifiers, and so their sides
+            let sides = sides & ExtendedModifiers::sides_of(key_event.modifiers);
+            let mut key_event = key_event.with_extended_modifiers(sides);
+            key_event.state |= lock_state;
+            key_event.physical_key = physical_key;
             Some(Event::Key(key_event))
         }
-        WindowsKeyEvent::Surrogate(new_surrogate) => {
-            let ch = handle_surrogate(surrogate_buffer, new_surrogate)?;
+        WindowsKeyEvent::Surrogate(new_surrogate, kind) => {
+            let ch = surrogate_buffer.push(new_surrogate, kind)?;
             let modifiers = KeyModifiers::from(&key_event.control_key_state);
-            let key_event = KeyEvent::new(KeyCode::Char(ch), modifiers);
+            let mut key_event =
+                KeyEvent::new_with_kind_and_state(KeyCode::Char(ch), modifiers, kind, lock_state)
+                    .with_extended_modifiers(sides);
+            key_event.physical_key = physical_key;
             Some(Event::Key(key_event))
         }
     }
 }
 
-fn handle_surrogate(surrogate_buffer: &mut Option<u16>, new_surrogate: u16) -> Option<char> {
-    match *surrogate_buffer {
-        Some(buffered_surrogate) => {
-            *surrogate_buffer = None;
-            std::char::decode_utf16([buffered_surrogate, new_surrogate])
-                .next()
-                .unwrap()
-                .ok()
-        }
-        None => {
-            *surrogate_buffer = Some(new_surrogate);
-            None
+/// Returns the character a key event record carries in virtual terminal input mode.
+///
+/// The console reports the input as a stream of characters, each in the key down record of a key
+/// event. Characters outside of the Basic Multilingual Plane arrive as two records, one per
+/// surrogate.
+pub(crate) fn handle_vt_key_event(
+    key_event: &KeyEventRecord,
+    surrogate_buffer: &mut SurrogateBuffer,
+) -> Option<char> {
+    if !key_event.key_down || key_event.u_char == 0 {
+        return None;
+    }
+
+    match key_event.u_char {
+        surrogate @ 0xD800..=0xDFFF => surrogate_buffer.push(surrogate, KeyEventKind::Press),
+        unicode_scalar_value => {
+            surrogate_buffer.clear();
+            std::char::from_u32(unicode_scalar_value as u32)
         }
     }
 }
@@ -98,6 +157,39 @@ impl From<&ControlKeyState> for KeyModifiers {
     }
 }
 
+impl From<&ControlKeyState> for ExtendedModifiers {
+    fn from(state: &ControlKeyState) -> Self {
+        // The console tells the sides of Ctrl and Alt, but not of Shift.
+        let mut sides = ExtendedModifiers::empty();
+        sides.set(
+            ExtendedModifiers::LEFT_CONTROL,
+            state.has_state(LEFT_CTRL_PRESSED),
+        );
+        sides.set(
+            ExtendedModifiers::RIGHT_CONTROL,
+            state.has_state(RIGHT_CTRL_PRESSED),
+        );
+        sides.set(
+            ExtendedModifiers::LEFT_ALT,
+            state.has_state(LEFT_ALT_PRESSED),
+        );
+        sides.set(
+            ExtendedModifiers::RIGHT_ALT,
+            state.has_state(RIGHT_ALT_PRESSED),
+        );
+        sides
+    }
+}
+
+impl From<&ControlKeyState> for KeyEventState {
+    fn from(state: &ControlKeyState) -> Self {
+        let mut lock_state = KeyEventState::empty();
+        lock_state.set(KeyEventState::CAPS_LOCK, state.has_state(CAPSLOCK_ON));
+        lock_state.set(KeyEventState::NUM_LOCK, state.has_state(NUMLOCK_ON));
+        lock_state
+    }
+}
+
 enum CharCase {
     LowerCase,
     UpperCase,
@@ -204,6 +296,11 @@ fn get_char_for_key(key_event: &KeyEventRecord) -> Option<char> {
 fn parse_key_event_record(key_event: &KeyEventRecord) -> Option<WindowsKeyEvent> {
     let modifiers = KeyModifiers::from(&key_event.control_key_state);
     let virtual_key_code = key_event.virtual_key_code as i32;
+    let kind = if key_event.key_down {
+        KeyEventKind::Press
+    } else {
+        KeyEventKind::Release
+    };
 
     // We normally ignore all key release events, but we will make an exception for an Alt key
     // release if it carries a u_char value, as this indicates an Alt code.
@@ -212,7 +309,7 @@ fn parse_key_event_record(key_event: &KeyEventRecord) -> Option<WindowsKeyEvent>
         let utf16 = key_event.u_char;
         match utf16 {
             surrogate @ 0xD800..=0xDFFF => {
-                return Some(WindowsKeyEvent::Surrogate(surrogate));
+                return Some(WindowsKeyEvent::Surrogate(surrogate, kind));
             }
             unicode_scalar_value => {
                 // Unwrap is safe: We tested for surrogate values above and those are the only
@@ -220,11 +317,6 @@ fn parse_key_event_record(key_event: &KeyEventRecord) -> Option<WindowsKeyEvent>
                 // values.
                 let ch = std::char::from_u32(unicode_scalar_value as u32).unwrap();
                 let key_code = KeyCode::Char(ch);
-                let kind = if key_event.key_down {
-                    KeyEventKind::Press
-                } else {
-                    KeyEventKind::Release
-                };
                 let key_event = KeyEvent::new_with_kind(key_code, modifiers, kind);
                 return Some(WindowsKeyEvent::KeyEvent(key_event));
             }
@@ -269,7 +361,7 @@ fn parse_key_event_record(key_event: &KeyEventRecord) -> Option<WindowsKeyEvent>
                     get_char_for_key(key_event).map(KeyCode::Char)
                 }
                 surrogate @ 0xD800..=0xDFFF => {
-                    return Some(WindowsKeyEvent::Surrogate(surrogate));
+                    return Some(WindowsKeyEvent::Surrogate(surrogate, kind));
                 }
                 unicode_scalar_value => {
                     // Unwrap is safe: We tested for surrogate values above and those are the only
@@ -283,11 +375,6 @@ fn parse_key_event_record(key_event: &KeyEventRecord) -> Option<WindowsKeyEvent>
     };
 
     if let Some(key_code) = parse_result {
-        let kind = if key_event.key_down {
-            KeyEventKind::Press
-        } else {
-            KeyEventKind::Release
-        };
         let key_event = KeyEvent::new_with_kind(key_code, modifiers, kind);
         return Some(WindowsKeyEvent::KeyEvent(key_event));
     }
@@ -376,3 +463,34 @@ fn parse_mouse_event_record(
         modifiers,
     }))
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::event::KeyEventKind;
+
+    use super::SurrogateBuffer;
+
+    #[test]
+    fn test_surrogate_pairs() {
+        let mut buffer = SurrogateBuffer::default();
+
+        // U+1F600, with the release of the high surrogate in between.
+        assert_eq!(buffer.push(0xD83D, KeyEventKind::Press), None);
+        assert_eq!(buffer.push(0xD83D, KeyEventKind::Release), None);
+        assert_eq!(buffer.push(0xDE00, KeyEventKind::Press), Some('\u{1F600}'));
+        assert_eq!(
+            buffer.push(0xDE00, KeyEventKind::Release),
+            Some('\u{1F600}')
+        );
+
+        // A lone low surrogate is discarded, a new high surrogate replaces a pending one.
+        assert_eq!(buffer.push(0xDE00, KeyEventKind::Press), None);
+        assert_eq!(buffer.push(0xD800, KeyEventKind::Press), None);
+        assert_eq!(buffer.push(0xD83D, KeyEventKind::Press), None);
+        assert_eq!(buffer.push(0xDE00, KeyEventKind::Press), Some('\u{1F600}'));
+
+        assert_eq!(buffer.push(0xD83D, KeyEventKind::Press), None);
+        buffer.clear();
+        assert_eq!(buffer.push(0xDE00, KeyEventKind::Press), None);
+    }
+}
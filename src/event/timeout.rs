@@ -1,18 +1,53 @@
-use std::time::{Duration, Instant};
+use std::{
+    fmt,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use super::clock::{self, Clock};
+
+/// How long a reader waits for input at most before it checks the installed clock again, which
+/// can't wake it when it's advanced.
+const CLOCK_CHECK_INTERVAL: Duration = Duration::from_millis(10);
 
 /// Keeps track of the elapsed time since the moment the polling started.
-#[derive(Debug, Clone)]
+#[derive(Clone)]
 pub struct PollTimeout {
     timeout: Option<Duration>,
     start: Instant,
+    /// The clock installed when the polling started, `None` is the system clock.
+    clock: Option<Arc<dyn Clock>>,
 }
 
 impl PollTimeout {
-    /// Constructs a new `PollTimeout` with the given optional `Duration`.
+    /// Constructs a new `PollTimeout` with the given optional `Duration`, measured in real time.
+    ///
+    /// The event sources use it, as they wait for input in real time.
     pub fn new(timeout: Option<Duration>) -> PollTimeout {
+        PollTimeout::with_clock(timeout, None)
+    }
+
+    /// Constructs a new `PollTimeout` measured with the clock installed with
+    /// [`clock::set_clock`](../clock/fn.set_clock.html).
+    pub fn with_installed_clock(timeout: Option<Duration>) -> PollTimeout {
+        PollTimeout::with_clock(timeout, clock::current())
+    }
+
+    fn with_clock(timeout: Option<Duration>, clock: Option<Arc<dyn Clock>>) -> PollTimeout {
+        let start = clock
+            .as_ref()
+            .map_or_else(Instant::now, |clock| clock.now());
         PollTimeout {
             timeout,
-            start: Instant::now(),
+            start,
+            clock,
+        }
+    }
+
+    fn elapsed_duration(&self) -> Duration {
+        match &self.clock {
+            Some(clock) => clock.now().saturating_duration_since(self.start),
+            None => self.start.elapsed(),
         }
     }
 
@@ -21,14 +56,30 @@ impl PollTimeout {
     /// It always returns `false` if the initial timeout was set to `None`.
     pub fn elapsed(&self) -> bool {
         self.timeout
-            .map(|timeout| self.start.elapsed() >= timeout)
+            .map(|timeout| self.elapsed_duration() >= timeout)
             .unwrap_or(false)
     }
 
+    /// Returns how long to wait for input in real time until `until` or the leftover, whichever
+    /// is shorter. With an installed clock, this is at most a few milliseconds, so that advancing
+    /// the clock is noticed.
+    pub fn wait_duration(&self, until: Option<Duration>) -> Option<Duration> {
+        let wait = match (self.leftover(), until) {
+            (Some(leftover), Some(until)) => Some(leftover.min(until)),
+            (leftover, until) => leftover.or(until),
+        };
+        match self.clock {
+            Some(_) => {
+                Some(wait.map_or(CLOCK_CHECK_INTERVAL, |wait| wait.min(CLOCK_CHECK_INTERVAL)))
+            }
+            None => wait,
+        }
+    }
+
     /// Returns the timeout leftover (initial timeout duration - elapsed duration).
     pub fn leftover(&self) -> Option<Duration> {
         self.timeout.map(|timeout| {
-            let elapsed = self.start.elapsed();
+            let elapsed = self.elapsed_duration();
 
             if elapsed >= timeout {
                 Duration::from_secs(0)
@@ -39,11 +90,24 @@ impl PollTimeout {
     }
 }
 
+impl fmt::Debug for PollTimeout {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PollTimeout")
+            .field("timeout", &self.timeout)
+            .field("start", &self.start)
+            .finish()
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use std::time::{Duration, Instant};
+    use std::{
+        sync::Arc,
+        time::{Duration, Instant},
+    };
 
-    use super::PollTimeout;
+    use super::{PollTimeout, CLOCK_CHECK_INTERVAL};
+    use crate::event::clock::ManualClock;
 
     #[test]
     pub fn test_timeout_without_duration_does_not_have_leftover() {
@@ -64,6 +128,7 @@ mod tests {
         let timeout = PollTimeout {
             timeout: Some(Duration::from_millis(TIMEOUT_MILLIS)),
             start: Instant::now() - Duration::from_millis(2 * TIMEOUT_MILLIS),
+            clock: None,
         };
 
         assert!(timeout.elapsed());
@@ -76,6 +141,7 @@ mod tests {
         let timeout = PollTimeout {
             timeout: Some(Duration::from_millis(TIMEOUT_MILLIS)),
             start: Instant::now() - Duration::from_millis(2 * TIMEOUT_MILLIS),
+            clock: None,
         };
 
         assert!(timeout.elapsed());
@@ -89,4 +155,41 @@ mod tests {
         assert!(!timeout.elapsed());
         assert!(timeout.leftover().unwrap() > Duration::from_secs(0));
     }
+
+    #[test]
+    pub fn test_timeout_with_manual_clock() {
+        let clock = ManualClock::new();
+        let timeout = PollTimeout::with_clock(
+            Some(Duration::from_millis(100)),
+            Some(Arc::new(clock.clone())),
+        );
+
+        assert_eq!(timeout.leftover(), Some(Duration::from_millis(100)));
+        clock.advance(Duration::from_millis(60));
+        assert_eq!(timeout.leftover(), Some(Duration::from_millis(40)));
+        assert!(!timeout.elapsed());
+
+        clock.advance(Duration::from_millis(40));
+        assert!(timeout.elapsed());
+        assert_eq!(timeout.leftover(), Some(Duration::from_millis(0)));
+    }
+
+    #[test]
+    pub fn test_wait_duration() {
+        let timeout = PollTimeout::new(Some(Duration::from_millis(100)));
+        assert!(timeout.wait_duration(None).unwrap() > CLOCK_CHECK_INTERVAL);
+        assert_eq!(
+            timeout.wait_duration(Some(Duration::from_millis(5))),
+            Some(Duration::from_millis(5))
+        );
+        assert_eq!(PollTimeout::new(None).wait_duration(None), None);
+
+        // the installed clock is checked regularly, it doesn't wake the reader
+        let clock = ManualClock::new();
+        let timeout =
+            PollTimeout::with_clock(Some(Duration::from_millis(100)), Some(Arc::new(clock)));
+        assert_eq!(timeout.wait_duration(None), Some(CLOCK_CHECK_INTERVAL));
+        let timeout = PollTimeout::with_clock(None, Some(Arc::new(ManualClock::new())));
+        assert_eq!(timeout.wait_duration(None), Some(CLOCK_CHECK_INTERVAL));
+    }
 }
@@ -0,0 +1,290 @@
+use std::time::{Duration, Instant};
+
+use parking_lot::Mutex;
+
+use super::{clock, read, Event};
+
+/// Identifies a timer added with [`add_interval`](fn.add_interval.html) or
+/// [`add_timer`](fn.add_timer.html), it's reported by [`Event::Tick`](enum.Event.html#variant.Tick).
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, PartialOrd, Ord, PartialEq, Eq, Clone, Copy, Hash)]
+pub struct TimerId(u64);
+
+#[derive(Debug)]
+struct Timer {
+    id: TimerId,
+    deadline: Instant,
+    // `None` for one-shot timers
+    interval: Option<Duration>,
+}
+
+/// The timers which are reported by the event reader.
+#[derive(Debug)]
+pub(crate) struct Timers {
+    next_id: u64,
+    timers: Vec<Timer>,
+}
+
+static TIMERS: Mutex<Timers> = parking_lot::const_mutex(Timers::new());
+
+impl Timers {
+    const fn new() -> Timers {
+        Timers {
+            next_id: 0,
+            timers: Vec::new(),
+        }
+    }
+
+    fn add(&mut self, deadline: Instant, interval: Option<Duration>) -> TimerId {
+        let id = TimerId(self.next_id);
+        self.next_id += 1;
+        self.timers.push(Timer {
+            id,
+            deadline,
+            interval,
+        });
+        id
+    }
+
+    fn remove(&mut self, id: TimerId) -> bool {
+        let len = self.timers.len();
+        self.timers.retain(|timer| timer.id != id);
+        self.timers.len() != len
+    }
+
+    /// Returns the tick of the timer which is due the longest, or how long it takes until the
+    /// next timer is due, `None` if there are no timers.
+    fn next_tick(&mut self, now: Instant) -> Result<Event, Option<Duration>> {
+        let index = match self
+            .timers
+            .iter()
+            .enumerate()
+            .min_by_key(|(_, timer)| timer.deadline)
+        {
+            Some((index, timer)) if timer.deadline <= now => index,
+            Some((_, timer)) => return Err(Some(timer.deadline - now)),
+            None => return Err(None),
+        };
+
+        let timer = &mut self.timers[index];
+        let id = timer.id;
+        match timer.interval {
+            Some(interval) => {
+                // ticks which were missed, e.g. while nobody polled, are reported only once
+                timer.deadline += interval;
+                if timer.deadline <= now {
+                    timer.deadline = now + interval;
+                }
+            }
+            None => {
+                self.timers.remove(index);
+            }
+        }
+        Ok(Event::Tick(id))
+    }
+}
+
+fn add(delay: Duration, interval: Option<Duration>) -> TimerId {
+    let id = TIMERS.lock().add(clock::now() + delay, interval);
+    // a pending poll picks the timer up
+    read::wake_global_reader();
+    id
+}
+
+/// Returns the next due tick, or how long it takes until the next timer is due.
+pub(crate) fn next_tick() -> Result<Event, Option<Duration>> {
+    TIMERS.lock().next_tick(clock::now())
+}
+
+/// Adds a timer which reports an [`Event::Tick`](enum.Event.html#variant.Tick) every `interval`.
+///
+/// The ticks are read like any other event with [`read`](fn.read.html) or the
+/// [`EventStream`](struct.EventStream.html), interleaved with the input. If a tick is due while
+/// the previous one wasn't read yet, only one tick is reported.
+///
+/// The time is measured with the clock installed with [`clock::set_clock`](clock/fn.set_clock.html).
+/// In the [screen reader mode](../accessibility/index.html), intervals shorter than
+/// [`MIN_REPAINT_INTERVAL`](../accessibility/constant.MIN_REPAINT_INTERVAL.html) are stretched
+/// to it.
+///
+/// # Notes
+///
+/// With the `event-stream` feature, adding a timer wakes a pending [`poll`](fn.poll.html), which
+/// returns `Ok(false)` then. Without it, a pending poll picks the timer up within 50
+/// milliseconds.
+///
+/// # Examples
+///
+/// ```no_run
+/// use std::{io, time::Duration};
+/// use crossterm::event::{self, Event};
+///
+/// fn main() -> io::Result<()> {
+///     let animation = event::add_interval(Duration::from_millis(100));
+///
+///     loop {
+///         match event::read()? {
+///             Event::Tick(id) if id == animation => { /* draw the next frame */ }
+///             Event::Key(_) => break,
+///             _ => {}
+///         }
+///     }
+///
+///     event::remove_timer(animation);
+///     Ok(())
+/// }
+/// ```
+pub fn add_interval(interval: Duration) -> TimerId {
+    let interval = if crate::accessibility::screen_reader_mode() {
+        interval.max(crate::accessibility::MIN_REPAINT_INTERVAL)
+    } else {
+        interval
+    };
+    add(interval, Some(interval))
+}
+
+/// Adds a timer which reports a single [`Event::Tick`](enum.Event.html#variant.Tick) after
+/// `delay`, and is removed then.
+///
+/// See [`add_interval`](fn.add_interval.html).
+pub fn add_timer(delay: Duration) -> TimerId {
+    add(delay, None)
+}
+
+/// Removes a timer, ticks which were already read from it stay queued.
+///
+/// Returns `false` if there is no such timer, e.g. because a one-shot timer already ticked.
+pub fn remove_timer(id: TimerId) -> bool {
+    TIMERS.lock().remove(id)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        thread,
+        time::{Duration, Instant},
+    };
+
+    use crate::event::{
+        self,
+        clock::{self, ManualClock},
+        KeyCode, Script,
+    };
+
+    use super::{add_interval, remove_timer, Event, Timers, TIMERS};
+
+    /// A script which plays a key long after the tests ended.
+    fn idle_script() -> Script {
+        Script::new()
+            .wait(Duration::from_secs(7200))
+            .key(KeyCode::Char('a'))
+    }
+
+    #[test]
+    fn test_one_shot_timer_ticks_once() {
+        let start = Instant::now();
+        let at = |millis| start + Duration::from_millis(millis);
+        let mut timers = Timers::new();
+        assert_eq!(timers.next_tick(at(0)).unwrap_err(), None);
+
+        let id = timers.add(at(10), None);
+        assert_eq!(
+            timers.next_tick(at(4)).unwrap_err(),
+            Some(Duration::from_millis(6))
+        );
+        assert_eq!(timers.next_tick(at(12)).unwrap(), Event::Tick(id));
+        assert_eq!(timers.next_tick(at(12)).unwrap_err(), None);
+        assert!(!timers.remove(id));
+    }
+
+    #[test]
+    fn test_interval_ticks_in_order() {
+        let start = Instant::now();
+        let at = |millis| start + Duration::from_millis(millis);
+        let mut timers = Timers::new();
+        let slow = timers.add(at(10), Some(Duration::from_millis(10)));
+        let fast = timers.add(at(3), Some(Duration::from_millis(3)));
+
+        assert_eq!(timers.next_tick(at(3)).unwrap(), Event::Tick(fast));
+        assert_eq!(timers.next_tick(at(10)).unwrap(), Event::Tick(fast));
+        assert_eq!(timers.next_tick(at(10)).unwrap(), Event::Tick(slow));
+        // the missed tick at 9 is coalesced with the one at 6
+        assert_eq!(
+            timers.next_tick(at(10)).unwrap_err(),
+            Some(Duration::from_millis(3))
+        );
+
+        assert!(timers.remove(fast));
+        assert_eq!(
+            timers.next_tick(at(10)).unwrap_err(),
+            Some(Duration::from_millis(10))
+        );
+    }
+
+    #[test]
+    #[serial_test::serial]
+    fn test_timer_ticks_when_the_installed_clock_advances() {
+        let manual = ManualClock::new();
+        clock::set_clock(manual.clone());
+        event::play_script(idle_script()).unwrap();
+
+        let id = event::add_timer(Duration::from_secs(3600));
+        let advance = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(30));
+            manual.advance(Duration::from_secs(3600));
+        });
+        assert_eq!(event::read().unwrap(), Event::Tick(id));
+        advance.join().unwrap();
+
+        event::stop_script();
+        clock::reset_clock();
+    }
+
+    #[test]
+    #[serial_test::serial]
+    fn test_timer_added_while_reading_ticks() {
+        event::play_script(idle_script()).unwrap();
+
+        let add = thread::spawn(|| {
+            thread::sleep(Duration::from_millis(30));
+            event::add_timer(Duration::ZERO)
+        });
+        let tick = event::read().unwrap();
+        assert_eq!(tick, Event::Tick(add.join().unwrap()));
+
+        event::stop_script();
+    }
+
+    #[test]
+    #[serial_test::serial]
+    fn test_screen_reader_mode_stretches_short_intervals() {
+        let interval = |id| {
+            TIMERS
+                .lock()
+                .timers
+                .iter()
+                .find(|timer| timer.id == id)
+                .and_then(|timer| timer.interval)
+        };
+
+        let (slow, fast) = crate::accessibility::with_screen_reader_mode(true, || {
+            (
+                add_interval(Duration::from_secs(5)),
+                add_interval(Duration::from_millis(10)),
+            )
+        });
+        assert_eq!(interval(slow), Some(Duration::from_secs(5)));
+        assert_eq!(
+            interval(fast),
+            Some(crate::accessibility::MIN_REPAINT_INTERVAL)
+        );
+        remove_timer(slow);
+        remove_timer(fast);
+
+        let fast = crate::accessibility::with_screen_reader_mode(false, || {
+            add_interval(Duration::from_millis(10))
+        });
+        assert_eq!(interval(fast), Some(Duration::from_millis(10)));
+        remove_timer(fast);
+    }
+}
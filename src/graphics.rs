@@ -0,0 +1,116 @@
+//! # Graphics
+//!
+//! The `graphics` module provides commands to display images in terminals which support one
+//! of the graphics protocols.
+//!
+//! The easiest way is [`PrintImage`](struct.PrintImage.html), which picks the
+//! [`best_protocol`](fn.best_protocol.html) according to the installed
+//! [`Capabilities`](../capabilities/struct.Capabilities.html), falling back to colored blocks:
+//!
+//! ```no_run
+//! use std::io::{self, Write};
+//! use crossterm::{capabilities, execute, graphics::PrintImage};
+//!
+//! fn main() -> io::Result<()> {
+//!     capabilities::set(capabilities::from_env().capabilities());
+//!
+//!     let pixels = vec![255; 16 * 16 * 4];
+//!     execute!(io::stdout(), PrintImage::new(&pixels, 16, 16))
+//! }
+//! ```
+//!
+//! The protocols can also be used directly with the commands below.
+//!
+//! ## Kitty graphics protocol
+//!
+//! The [kitty graphics protocol](https://sw.kovidgoyal.net/kitty/graphics-protocol/) first
+//! transmits an image to the terminal, which stores it under an id. The image can then be
+//! placed, possibly several times, until it's deleted.
+//!
+//! ```no_run
+//! use std::io::{self, Write};
+//! use crossterm::{
+//!     execute,
+//!     graphics::{DeleteImages, ImageFormat, Medium, PlaceImage, Placement, TransmitImage},
+//! };
+//!
+//! fn main() -> io::Result<()> {
+//!     let pixels = vec![255; 16 * 16 * 4];
+//!     let format = ImageFormat::Rgba { width: 16, height: 16 };
+//!
+//!     execute!(
+//!         io::stdout(),
+//!         TransmitImage::new(1, format, Medium::Direct(&pixels)),
+//!         PlaceImage::new(1, Placement { columns: Some(4), ..Placement::default() }),
+//!         DeleteImages::Image { id: 1, free: true },
+//!     )
+//! }
+//! ```
+//!
+//! The commands ask the terminal not to answer, so no responses end up in the input.
+//!
+//! Images can be animated by adding frames with [`TransmitFrame`](struct.TransmitFrame.html) and
+//! starting the playback with [`ControlAnimation`](struct.ControlAnimation.html).
+//!
+//! [`ImageManager`](struct.ImageManager.html) does the bookkeeping on top of these commands: it
+//! assigns the ids, moves and restacks placements, and deletes its images when it's dropped.
+//!
+//! ## Sixel graphics
+//!
+//! [`PrintSixel`](struct.PrintSixel.html) prints RGBA pixels as sixel graphics, which xterm, mlterm
+//! and WezTerm among others understand. As few terminals support them, the command only writes
+//! something after sixel support was detected and installed in the capabilities:
+//!
+//! ```no_run
+//! use std::io::{self, Write};
+//! use crossterm::{capabilities, execute, graphics::PrintSixel, terminal};
+//!
+//! fn main() -> io::Result<()> {
+//!     capabilities::set(capabilities::Capabilities {
+//!         sixel: terminal::supports_sixel()?,
+//!         ..capabilities::current()
+//!     });
+//!
+//!     let pixels = vec![255; 16 * 16 * 4];
+//!     execute!(io::stdout(), PrintSixel::new(&pixels, 16, 16))
+//! }
+//! ```
+//!
+//! ## iTerm2 inline images
+//!
+//! [`InlineImage`](struct.InlineImage.html) prints an image file with the inline image protocol
+//! of iTerm2, which is also understood by WezTerm and mintty:
+//!
+//! ```no_run
+//! use std::io::{self, Write};
+//! use crossterm::{
+//!     execute,
+//!     graphics::{Dimension, InlineImage},
+//! };
+//!
+//! fn main() -> io::Result<()> {
+//!     let png = std::fs::read("logo.png")?;
+//!     execute!(
+//!         io::stdout(),
+//!         InlineImage::new(&png).size(Dimension::Cells(20), Dimension::Auto)
+//!     )
+//! }
+//! ```
+
+pub use self::{
+    image::{best_protocol, ImageProtocol, PrintImage},
+    iterm::{Dimension, InlineImage},
+    kitty::{
+        AnimationState, ControlAnimation, DeleteImages, ImageFormat, Medium, PlaceImage, Placement,
+        TransmitFrame, TransmitImage,
+    },
+    manager::{ImageId, ImageManager, PlacementId},
+    sixel::PrintSixel,
+};
+
+mod image;
+mod iterm;
+mod kitty;
+mod manager;
+mod png;
+mod sixel;
@@ -0,0 +1,196 @@
+use std::fmt;
+
+use crate::{
+    capabilities,
+    command::write_multiplexed,
+    style::{Color, ResetColor, SetBackgroundColor, SetForegroundColor},
+    Command,
+};
+
+use super::{kitty, png, sixel, InlineImage};
+
+/// Pixels with a smaller alpha value are left transparent by [`ImageProtocol::Blocks`].
+const ALPHA_THRESHOLD: u8 = 128;
+
+/// A way to display images in a terminal, see [`best_protocol`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ImageProtocol {
+    /// The kitty graphics protocol.
+    Kitty,
+    /// The inline image protocol of iTerm2.
+    ITerm2,
+    /// Sixel graphics.
+    Sixel,
+    /// Colored half blocks (`▀`), one cell for two pixels on top of each other, which works
+    /// wherever truecolor does.
+    Blocks,
+}
+
+/// Returns the best protocol the terminal supports according to the
+/// [`Capabilities`](../capabilities/struct.Capabilities.html).
+///
+/// The kitty graphics protocol is preferred over the iTerm2 protocol, which is preferred over
+/// sixel graphics. If none of them is supported, the image is drawn with colored blocks.
+pub fn best_protocol() -> ImageProtocol {
+    capabilities::query(|capabilities| {
+        if capabilities.kitty_graphics {
+            ImageProtocol::Kitty
+        } else if capabilities.inline_images {
+            ImageProtocol::ITerm2
+        } else if capabilities.sixel {
+            ImageProtocol::Sixel
+        } else {
+            ImageProtocol::Blocks
+        }
+    })
+}
+
+/// A command that prints an image at the cursor position with the best protocol the terminal
+/// supports, see [`best_protocol`].
+///
+/// The pixels are RGBA, row by row. The image is printed in its original size, which with
+/// [`ImageProtocol::Blocks`] means a column per pixel, so large images should be scaled down
+/// first.
+///
+/// # Notes
+///
+/// Commands must be executed/queued for execution otherwise they do nothing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct PrintImage<'a> {
+    /// The RGBA pixels, `width * height * 4` bytes.
+    pub pixels: &'a [u8],
+    /// The width in pixels.
+    pub width: u32,
+    /// The height in pixels.
+    pub height: u32,
+    /// The protocol to use instead of the best one.
+    pub protocol: Option<ImageProtocol>,
+}
+
+impl<'a> PrintImage<'a> {
+    /// Creates a command which prints `width` x `height` RGBA `pixels`.
+    pub fn new(pixels: &'a [u8], width: u32, height: u32) -> PrintImage<'a> {
+        PrintImage {
+            pixels,
+            width,
+            height,
+            protocol: None,
+        }
+    }
+
+    /// Uses `protocol` instead of the best one.
+    pub fn protocol(mut self, protocol: ImageProtocol) -> PrintImage<'a> {
+        self.protocol = Some(protocol);
+        self
+    }
+}
+
+impl Command for PrintImage<'_> {
+    fn write_ansi(&self, f: &mut impl fmt::Write) -> fmt::Result {
+        match self.protocol.unwrap_or_else(best_protocol) {
+            ImageProtocol::Kitty => write_multiplexed(f, |f| {
+                kitty::write_rgba(f, self.pixels, self.width, self.height)
+            }),
+            ImageProtocol::ITerm2 => {
+                let png = png::encode_rgba(self.pixels, self.width, self.height);
+                InlineImage::new(&png).write_ansi(f)
+            }
+            ImageProtocol::Sixel => write_multiplexed(f, |f| {
+                sixel::write_sixel(f, self.pixels, self.width as usize, self.height as usize)
+            }),
+            ImageProtocol::Blocks => {
+                write_blocks(f, self.pixels, self.width as usize, self.height as usize)
+            }
+        }
+    }
+
+    fn size_hint(&self) -> usize {
+        self.pixels.len() / 3 * 4 + 64
+    }
+
+    #[cfg(windows)]
+    fn execute_winapi(&self) -> std::io::Result<()> {
+        Err(crate::Error::UnsupportedCapability(
+            "Printing images is not supported by the legacy Windows API.",
+        )
+        .into())
+    }
+}
+
+fn write_blocks(
+    f: &mut impl fmt::Write,
+    pixels: &[u8],
+    width: usize,
+    height: usize,
+) -> fmt::Result {
+    let color = |x: usize, y: usize| {
+        let start = (y * width + x) * 4;
+        match pixels.get(start..start + 4) {
+            Some(&[r, g, b, a]) if a >= ALPHA_THRESHOLD => Color::Rgb { r, g, b },
+            _ => Color::Reset,
+        }
+    };
+
+    for y in (0..height).step_by(2) {
+        if y > 0 {
+            // back to the first column, one line down, scrolling if necessary
+            write!(f, "\x1B[{}D\x1BD", width)?;
+        }
+        for x in 0..width {
+            let top = color(x, y);
+            let bottom = if y + 1 < height {
+                color(x, y + 1)
+            } else {
+                Color::Reset
+            };
+            SetForegroundColor(top).write_ansi(f)?;
+            SetBackgroundColor(bottom).write_ansi(f)?;
+            f.write_char(if top == Color::Reset { ' ' } else { '▀' })?;
+        }
+        ResetColor.write_ansi(f)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Command;
+
+    use super::{ImageProtocol, PrintImage};
+
+    #[test]
+    fn test_print_image() {
+        let pixels = [
+            [255, 0, 0, 255],
+            [0, 0, 255, 255],
+            [0, 0, 0, 0],
+            [0, 255, 0, 255],
+        ]
+        .concat();
+        let image = PrintImage::new(&pixels, 2, 2);
+
+        assert_eq!(
+            image.protocol(ImageProtocol::Blocks).ansi_string(),
+            "\x1B[38;2;255;0;0m\x1B[49m▀\x1B[38;2;0;0;255m\x1B[48;2;0;255;0m▀\x1B[0m"
+        );
+        assert!(image
+            .protocol(ImageProtocol::Kitty)
+            .ansi_string()
+            .starts_with("\x1B_Ga=T,q=2,f=32,s=2,v=2;/wAA"));
+        assert!(image
+            .protocol(ImageProtocol::ITerm2)
+            .ansi_string()
+            .starts_with("\x1B]1337;File=inline=1;size="));
+        assert!(image
+            .protocol(ImageProtocol::Sixel)
+            .ansi_string()
+            .starts_with("\x1BP0;1;0q\"1;1;2;2"));
+
+        // a second row of cells
+        let image = PrintImage::new(&pixels, 1, 3).protocol(ImageProtocol::Blocks);
+        assert_eq!(
+            image.ansi_string(),
+            "\x1B[38;2;255;0;0m\x1B[48;2;0;0;255m▀\x1B[0m\x1B[1D\x1BD\x1B[39m\x1B[49m \x1B[0m"
+        );
+    }
+}
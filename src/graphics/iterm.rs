@@ -0,0 +1,144 @@
+use std::fmt;
+
+use crate::{base64, command::write_multiplexed, Command};
+
+/// The width or height of an image printed with [`InlineImage`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Dimension {
+    /// The size of the image itself, scaled down to fit the window if necessary.
+    Auto,
+    /// A number of cells.
+    Cells(u16),
+    /// A number of pixels.
+    Pixels(u32),
+    /// A percentage of the window's width or height.
+    Percent(u8),
+}
+
+impl Default for Dimension {
+    fn default() -> Self {
+        Dimension::Auto
+    }
+}
+
+impl fmt::Display for Dimension {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Dimension::Auto => f.write_str("auto"),
+            Dimension::Cells(cells) => write!(f, "{}", cells),
+            Dimension::Pixels(pixels) => write!(f, "{}px", pixels),
+            Dimension::Percent(percent) => write!(f, "{}%", percent),
+        }
+    }
+}
+
+/// A command that prints an image at the cursor position with the inline image protocol of
+/// iTerm2, which WezTerm and mintty understand as well.
+///
+/// The data is an image file in any format macOS can read, e.g. PNG, JPEG or GIF. The cursor is
+/// moved behind the image.
+///
+/// # Notes
+///
+/// Commands must be executed/queued for execution otherwise they do nothing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct InlineImage<'a> {
+    /// The content of the image file.
+    pub data: &'a [u8],
+    /// The file name, which the terminal may show.
+    pub name: Option<&'a str>,
+    /// The width the image is scaled to.
+    pub width: Dimension,
+    /// The height the image is scaled to.
+    pub height: Dimension,
+    /// Keep the aspect ratio if both `width` and `height` are given, filling the remaining
+    /// space with the background.
+    pub preserve_aspect_ratio: bool,
+}
+
+impl<'a> InlineImage<'a> {
+    /// Creates a command which prints the image file `data` in its own size.
+    pub fn new(data: &'a [u8]) -> InlineImage<'a> {
+        InlineImage {
+            data,
+            name: None,
+            width: Dimension::Auto,
+            height: Dimension::Auto,
+            preserve_aspect_ratio: true,
+        }
+    }
+
+    /// Scales the image to `width` and `height`.
+    pub fn size(mut self, width: Dimension, height: Dimension) -> InlineImage<'a> {
+        self.width = width;
+        self.height = height;
+        self
+    }
+}
+
+impl Command for InlineImage<'_> {
+    fn write_ansi(&self, f: &mut impl fmt::Write) -> fmt::Result {
+        use fmt::Write;
+
+        write_multiplexed(f, |f| {
+            write!(f, "\x1B]1337;File=inline=1;size={}", self.data.len())?;
+            if let Some(name) = self.name {
+                write!(f, ";name={}", base64::encode(name.as_bytes()))?;
+            }
+            if self.width != Dimension::Auto {
+                write!(f, ";width={}", self.width)?;
+            }
+            if self.height != Dimension::Auto {
+                write!(f, ";height={}", self.height)?;
+            }
+            if !self.preserve_aspect_ratio {
+                f.write_str(";preserveAspectRatio=0")?;
+            }
+            write!(f, ":{}\x07", base64::encode(self.data))
+        })
+    }
+
+    fn size_hint(&self) -> usize {
+        (self.data.len() + 2) / 3 * 4 + 64
+    }
+
+    #[cfg(windows)]
+    fn execute_winapi(&self) -> std::io::Result<()> {
+        Err(crate::Error::UnsupportedCapability(
+            "Inline images are not supported by the legacy Windows API.",
+        )
+        .into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Command;
+
+    use super::{Dimension, InlineImage};
+
+    #[test]
+    fn test_inline_image() {
+        assert_eq!(
+            InlineImage::new(b"foo").ansi_string(),
+            "\x1B]1337;File=inline=1;size=3:Zm9v\x07"
+        );
+
+        let image = InlineImage {
+            name: Some("a.png"),
+            preserve_aspect_ratio: false,
+            ..InlineImage::new(b"foobar").size(Dimension::Cells(10), Dimension::Percent(50))
+        };
+        assert_eq!(
+            image.ansi_string(),
+            "\x1B]1337;File=inline=1;size=6;name=YS5wbmc=;width=10;height=50%;\
+             preserveAspectRatio=0:Zm9vYmFy\x07"
+        );
+
+        let image = InlineImage::new(b"").size(Dimension::Pixels(64), Dimension::Auto);
+        assert_eq!(
+            image.ansi_string(),
+            "\x1B]1337;File=inline=1;size=0;width=64px:\x07"
+        );
+    }
+}
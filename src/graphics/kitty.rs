@@ -0,0 +1,696 @@
+use std::{borrow::Cow, fmt, path::Path};
+
+use crate::{
+    base64,
+    command::{write_multiplexed, PassthroughWriter},
+    Command,
+};
+
+/// The size of the base64 encoded payload of one escape sequence.
+const CHUNK_SIZE: usize = 4096;
+
+/// The pixel format of an image transmitted with [`TransmitImage`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ImageFormat {
+    /// 24 bit RGB pixels, row by row.
+    Rgb { width: u32, height: u32 },
+    /// 32 bit RGBA pixels, row by row.
+    Rgba { width: u32, height: u32 },
+    /// A PNG file, which contains its own size.
+    Png,
+}
+
+/// How the image data of [`TransmitImage`] gets to the terminal.
+///
+/// Only [`Direct`](Medium::Direct) works if the terminal runs on another machine, e.g. over SSH.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Medium<'a> {
+    /// The data is sent within the escape sequences, split into chunks.
+    Direct(&'a [u8]),
+    /// The terminal reads the data from a file.
+    File(&'a Path),
+    /// The terminal reads the data from a temporary file and deletes it afterwards.
+    ///
+    /// The path has to be in a temporary directory and contain `tty-graphics-protocol`.
+    TempFile(&'a Path),
+    /// The terminal reads the data from a POSIX shared memory object, or a named file mapping on
+    /// Windows, and unlinks it afterwards.
+    SharedMemory(&'a str),
+}
+
+/// Where and how an image is placed, see [`PlaceImage`].
+///
+/// The default places the image at the cursor position in its original size.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Placement {
+    /// The id of the placement, which replaces an earlier placement of the image with the same
+    /// id. If `None`, every placement creates a new one.
+    pub placement_id: Option<u32>,
+    /// The number of columns the image is scaled to.
+    pub columns: Option<u16>,
+    /// The number of rows the image is scaled to.
+    pub rows: Option<u16>,
+    /// The horizontal offset in pixels within the first cell.
+    pub x_offset: u16,
+    /// The vertical offset in pixels within the first cell.
+    pub y_offset: u16,
+    /// The stacking order, negative values are drawn below the text.
+    pub z_index: i32,
+    /// Leave the cursor where it is instead of moving it behind the image.
+    pub keep_cursor: bool,
+}
+
+impl Placement {
+    fn write_keys(&self, f: &mut impl fmt::Write) -> fmt::Result {
+        if let Some(placement_id) = self.placement_id {
+            write!(f, ",p={}", placement_id)?;
+        }
+        if let Some(columns) = self.columns {
+            write!(f, ",c={}", columns)?;
+        }
+        if let Some(rows) = self.rows {
+            write!(f, ",r={}", rows)?;
+        }
+        if self.x_offset != 0 {
+            write!(f, ",X={}", self.x_offset)?;
+        }
+        if self.y_offset != 0 {
+            write!(f, ",Y={}", self.y_offset)?;
+        }
+        if self.z_index != 0 {
+            write!(f, ",z={}", self.z_index)?;
+        }
+        if self.keep_cursor {
+            f.write_str(",C=1")?;
+        }
+        Ok(())
+    }
+}
+
+/// A command that transmits an image to the terminal with the kitty graphics protocol.
+///
+/// The terminal stores the image under `id` until it's deleted with [`DeleteImages`]. If
+/// `display` is set, the image is placed right away.
+///
+/// # Notes
+///
+/// * The `id` must not be `0`.
+/// * Commands must be executed/queued for execution otherwise they do nothing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TransmitImage<'a> {
+    /// The id the terminal stores the image under.
+    pub id: u32,
+    /// The pixel format of the data.
+    pub format: ImageFormat,
+    /// Where the data comes from.
+    pub medium: Medium<'a>,
+    /// The data is compressed with zlib (RFC 1950).
+    pub compressed: bool,
+    /// Places the image right away.
+    pub display: Option<Placement>,
+}
+
+impl<'a> TransmitImage<'a> {
+    /// Creates a command which transmits an image without placing it.
+    pub fn new(id: u32, format: ImageFormat, medium: Medium<'a>) -> TransmitImage<'a> {
+        TransmitImage {
+            id,
+            format,
+            medium,
+            compressed: false,
+            display: None,
+        }
+    }
+
+    /// Places the image right away.
+    pub fn display(mut self, placement: Placement) -> TransmitImage<'a> {
+        self.display = Some(placement);
+        self
+    }
+}
+
+impl Command for TransmitImage<'_> {
+    fn write_ansi(&self, f: &mut impl fmt::Write) -> fmt::Result {
+        write_multiplexed(f, |f| {
+            let mut control = String::new();
+            let action = if self.display.is_some() { 'T' } else { 't' };
+            write_format(&mut control, action, self.id, self.format)?;
+
+            let payload = write_medium(&mut control, self.medium, self.compressed);
+            if let Some(placement) = &self.display {
+                placement.write_keys(&mut control)?;
+            }
+            write_chunked(f, &control, &payload)
+        })
+    }
+
+    fn size_hint(&self) -> usize {
+        match self.medium {
+            Medium::Direct(data) => (data.len() + 2) / 3 * 4 + 64,
+            _ => 128,
+        }
+    }
+
+    #[cfg(windows)]
+    fn execute_winapi(&self) -> std::io::Result<()> {
+        Err(unsupported())
+    }
+}
+
+/// A command that places an image transmitted with [`TransmitImage`] at the cursor position.
+///
+/// # Notes
+///
+/// Commands must be executed/queued for execution otherwise they do nothing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct PlaceImage {
+    /// The id of the image.
+    pub id: u32,
+    /// Where and how the image is placed.
+    pub placement: Placement,
+}
+
+impl PlaceImage {
+    /// Creates a command which places the image `id`.
+    pub fn new(id: u32, placement: Placement) -> PlaceImage {
+        PlaceImage { id, placement }
+    }
+}
+
+impl Command for PlaceImage {
+    fn write_ansi(&self, f: &mut impl fmt::Write) -> fmt::Result {
+        use fmt::Write;
+
+        write_multiplexed(f, |f| {
+            write!(f, "\x1B_Ga=p,i={},q=2", self.id)?;
+            self.placement.write_keys(f)?;
+            f.write_str("\x1B\\")
+        })
+    }
+
+    #[cfg(windows)]
+    fn execute_winapi(&self) -> std::io::Result<()> {
+        Err(unsupported())
+    }
+}
+
+/// A command that deletes images placed with the kitty graphics protocol.
+///
+/// If `free` is set, the image data is deleted as well, otherwise only the placements are removed
+/// from the screen and the image can be placed again.
+///
+/// # Notes
+///
+/// Commands must be executed/queued for execution otherwise they do nothing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DeleteImages {
+    /// All placements on the screen.
+    All { free: bool },
+    /// All placements of the image `id`.
+    Image { id: u32, free: bool },
+    /// The placement `placement_id` of the image `id`.
+    Placement {
+        id: u32,
+        placement_id: u32,
+        free: bool,
+    },
+    /// All placements which intersect the cursor position.
+    AtCursor { free: bool },
+    /// All placements with the z-index `z_index`.
+    ZIndex { z_index: i32, free: bool },
+}
+
+impl Command for DeleteImages {
+    fn write_ansi(&self, f: &mut impl fmt::Write) -> fmt::Result {
+        use fmt::Write;
+
+        write_multiplexed(f, |f| {
+            let target = |free: bool, target: char| {
+                if free {
+                    target.to_ascii_uppercase()
+                } else {
+                    target
+                }
+            };
+
+            f.write_str("\x1B_Ga=d,q=2,d=")?;
+            match *self {
+                DeleteImages::All { free } => write!(f, "{}", target(free, 'a'))?,
+                DeleteImages::Image { id, free } => write!(f, "{},i={}", target(free, 'i'), id)?,
+                DeleteImages::Placement {
+                    id,
+                    placement_id,
+                    free,
+                } => write!(f, "{},i={},p={}", target(free, 'i'), id, placement_id)?,
+                DeleteImages::AtCursor { free } => write!(f, "{}", target(free, 'c'))?,
+                DeleteImages::ZIndex { z_index, free } => {
+                    write!(f, "{},z={}", target(free, 'z'), z_index)?
+                }
+            }
+            f.write_str("\x1B\\")
+        })
+    }
+
+    #[cfg(windows)]
+    fn execute_winapi(&self) -> std::io::Result<()> {
+        Err(unsupported())
+    }
+}
+
+/// A command that adds a frame to an image transmitted with [`TransmitImage`], or edits one of
+/// its frames, which turns the image into an animation.
+///
+/// The transmitted image is the first frame. The data of a frame may cover only a part of it,
+/// the rest is taken from `base_frame` or is transparent.
+///
+/// # Notes
+///
+/// Commands must be executed/queued for execution otherwise they do nothing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TransmitFrame<'a> {
+    /// The id of the image.
+    pub id: u32,
+    /// The pixel format of the data.
+    pub format: ImageFormat,
+    /// Where the data comes from.
+    pub medium: Medium<'a>,
+    /// The data is compressed with zlib (RFC 1950).
+    pub compressed: bool,
+    /// The number of the frame to edit, starting at `1`. If `None`, a new frame is added.
+    pub frame: Option<u32>,
+    /// The number of the frame whose pixels fill the parts the data doesn't cover.
+    pub base_frame: Option<u32>,
+    /// The horizontal offset in pixels of the data within the frame.
+    pub x_offset: u32,
+    /// The vertical offset in pixels of the data within the frame.
+    pub y_offset: u32,
+    /// How long the frame is shown in milliseconds, a negative value skips it. If `None`, the
+    /// terminal's default is used.
+    pub gap: Option<i32>,
+    /// Replace the pixels below the data instead of alpha blending the data onto them.
+    pub overwrite: bool,
+}
+
+impl<'a> TransmitFrame<'a> {
+    /// Creates a command which adds a frame to the image `id`.
+    pub fn new(id: u32, format: ImageFormat, medium: Medium<'a>) -> TransmitFrame<'a> {
+        TransmitFrame {
+            id,
+            format,
+            medium,
+            compressed: false,
+            frame: None,
+            base_frame: None,
+            x_offset: 0,
+            y_offset: 0,
+            gap: None,
+            overwrite: false,
+        }
+    }
+
+    /// Shows the frame for `gap` milliseconds.
+    pub fn gap(mut self, gap: i32) -> TransmitFrame<'a> {
+        self.gap = Some(gap);
+        self
+    }
+}
+
+impl Command for TransmitFrame<'_> {
+    fn write_ansi(&self, f: &mut impl fmt::Write) -> fmt::Result {
+        write_multiplexed(f, |f| {
+            use fmt::Write;
+
+            let mut control = String::new();
+            write_format(&mut control, 'f', self.id, self.format)?;
+            let payload = write_medium(&mut control, self.medium, self.compressed);
+
+            if let Some(frame) = self.frame {
+                write!(control, ",r={}", frame)?;
+            }
+            if let Some(base_frame) = self.base_frame {
+                write!(control, ",c={}", base_frame)?;
+            }
+            if self.x_offset != 0 {
+                write!(control, ",x={}", self.x_offset)?;
+            }
+            if self.y_offset != 0 {
+                write!(control, ",y={}", self.y_offset)?;
+            }
+            if let Some(gap) = self.gap {
+                write!(control, ",z={}", gap)?;
+            }
+            if self.overwrite {
+                control.push_str(",X=1");
+            }
+            write_chunked(f, &control, &payload)
+        })
+    }
+
+    fn size_hint(&self) -> usize {
+        match self.medium {
+            Medium::Direct(data) => (data.len() + 2) / 3 * 4 + 64,
+            _ => 128,
+        }
+    }
+
+    #[cfg(windows)]
+    fn execute_winapi(&self) -> std::io::Result<()> {
+        Err(unsupported())
+    }
+}
+
+/// Whether the frames of an animation are played, see [`ControlAnimation`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AnimationState {
+    /// The animation stays at the current frame.
+    Stopped,
+    /// The animation is played, but waits for more frames at the last one instead of looping.
+    Loading,
+    /// The animation is played in a loop.
+    Running,
+}
+
+/// A command that controls the playback of an animation, see [`TransmitFrame`].
+///
+/// # Notes
+///
+/// Commands must be executed/queued for execution otherwise they do nothing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ControlAnimation {
+    /// The id of the image.
+    pub id: u32,
+    /// Starts or stops the animation.
+    pub state: Option<AnimationState>,
+    /// Shows the frame with this number, starting at `1`.
+    pub current_frame: Option<u32>,
+    /// The frame whose gap is changed.
+    pub frame: Option<u32>,
+    /// How long `frame` is shown in milliseconds, a negative value skips it.
+    pub gap: Option<i32>,
+    /// How often the animation is played, `0` loops forever.
+    pub loops: Option<u32>,
+}
+
+impl ControlAnimation {
+    /// Creates a command which sets the state of the animation of the image `id`.
+    pub fn new(id: u32, state: AnimationState) -> ControlAnimation {
+        ControlAnimation {
+            id,
+            state: Some(state),
+            current_frame: None,
+            frame: None,
+            gap: None,
+            loops: None,
+        }
+    }
+}
+
+impl Command for ControlAnimation {
+    fn write_ansi(&self, f: &mut impl fmt::Write) -> fmt::Result {
+        use fmt::Write;
+
+        write_multiplexed(f, |f| {
+            write!(f, "\x1B_Ga=a,i={},q=2", self.id)?;
+            if let Some(state) = self.state {
+                let state = match state {
+                    AnimationState::Stopped => 1,
+                    AnimationState::Loading => 2,
+                    AnimationState::Running => 3,
+                };
+                write!(f, ",s={}", state)?;
+            }
+            if let Some(current_frame) = self.current_frame {
+                write!(f, ",c={}", current_frame)?;
+            }
+            if let Some(frame) = self.frame {
+                write!(f, ",r={}", frame)?;
+            }
+            if let Some(gap) = self.gap {
+                write!(f, ",z={}", gap)?;
+            }
+            if let Some(loops) = self.loops {
+                // 1 loops forever, n plays the animation n - 1 times
+                write!(f, ",v={}", loops.saturating_add(1))?;
+            }
+            f.write_str("\x1B\\")
+        })
+    }
+
+    #[cfg(windows)]
+    fn execute_winapi(&self) -> std::io::Result<()> {
+        Err(unsupported())
+    }
+}
+
+/// Transmits and places `width` x `height` RGBA `pixels` at the cursor position without an id,
+/// so other images aren't replaced.
+pub(super) fn write_rgba<W: fmt::Write>(
+    f: &mut PassthroughWriter<'_, W>,
+    pixels: &[u8],
+    width: u32,
+    height: u32,
+) -> fmt::Result {
+    let control = format!("a=T,q=2,f=32,s={},v={}", width, height);
+    write_chunked(f, &control, pixels)
+}
+
+fn write_format(control: &mut String, action: char, id: u32, format: ImageFormat) -> fmt::Result {
+    use fmt::Write;
+
+    write!(control, "a={},i={},q=2", action, id)?;
+    match format {
+        ImageFormat::Rgb { width, height } => write!(control, ",f=24,s={},v={}", width, height),
+        ImageFormat::Rgba { width, height } => write!(control, ",f=32,s={},v={}", width, height),
+        ImageFormat::Png => control.write_str(",f=100"),
+    }
+}
+
+/// Adds the keys for `medium` to `control` and returns the payload.
+fn write_medium<'a>(control: &mut String, medium: Medium<'a>, compressed: bool) -> Cow<'a, [u8]> {
+    let path = |file: &'a Path| match file.to_string_lossy() {
+        Cow::Borrowed(path) => Cow::Borrowed(path.as_bytes()),
+        Cow::Owned(path) => Cow::Owned(path.into_bytes()),
+    };
+
+    let payload = match medium {
+        Medium::Direct(data) => Cow::Borrowed(data),
+        Medium::File(file) => {
+            control.push_str(",t=f");
+            path(file)
+        }
+        Medium::TempFile(file) => {
+            control.push_str(",t=t");
+            path(file)
+        }
+        Medium::SharedMemory(name) => {
+            control.push_str(",t=s");
+            Cow::Borrowed(name.as_bytes())
+        }
+    };
+
+    if compressed {
+        control.push_str(",o=z");
+    }
+    payload
+}
+
+/// Writes `payload` base64 encoded, split into several escape sequences if it's too long. Inside
+/// a multiplexer, every sequence gets its own passthrough sequence.
+fn write_chunked<W: fmt::Write>(
+    f: &mut PassthroughWriter<'_, W>,
+    control: &str,
+    payload: &[u8],
+) -> fmt::Result {
+    use fmt::Write;
+
+    let encoded = base64::encode(payload);
+    let mut chunks = encoded.as_bytes().chunks(CHUNK_SIZE).peekable();
+
+    let first = chunks.next().unwrap_or_default();
+    let chunked = chunks.peek().is_some();
+    f.write_str("\x1B_G")?;
+    f.write_str(control)?;
+    if chunked {
+        f.write_str(",m=1")?;
+    }
+    write_payload(f, first)?;
+
+    while let Some(chunk) = chunks.next() {
+        let more = if chunks.peek().is_some() { 1 } else { 0 };
+        f.next_envelope()?;
+        write!(f, "\x1B_Gm={}", more)?;
+        write_payload(f, chunk)?;
+    }
+
+    Ok(())
+}
+
+fn write_payload(f: &mut impl fmt::Write, chunk: &[u8]) -> fmt::Result {
+    f.write_char(';')?;
+    // base64 is ASCII
+    f.write_str(std::str::from_utf8(chunk).unwrap_or_default())?;
+    f.write_str("\x1B\\")
+}
+
+#[cfg(windows)]
+fn unsupported() -> std::io::Error {
+    crate::Error::UnsupportedCapability(
+        "The kitty graphics protocol is not supported by the legacy Windows API.",
+    )
+    .into()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use crate::{
+        capabilities::{Multiplexer, MultiplexerPolicy},
+        command::write_multiplexed_with,
+        Command,
+    };
+
+    use super::{
+        write_chunked, AnimationState, ControlAnimation, DeleteImages, ImageFormat, Medium,
+        PlaceImage, Placement, TransmitFrame, TransmitImage,
+    };
+
+    #[test]
+    fn test_transmit_image() {
+        let format = ImageFormat::Rgb {
+            width: 1,
+            height: 1,
+        };
+        assert_eq!(
+            TransmitImage::new(1, format, Medium::Direct(&[255, 0, 0])).ansi_string(),
+            "\x1B_Ga=t,i=1,q=2,f=24,s=1,v=1;/wAA\x1B\\"
+        );
+
+        let command = TransmitImage::new(2, ImageFormat::Png, Medium::File(Path::new("/a.png")))
+            .display(Placement {
+                columns: Some(10),
+                keep_cursor: true,
+                ..Placement::default()
+            });
+        assert_eq!(
+            command.ansi_string(),
+            "\x1B_Ga=T,i=2,q=2,f=100,t=f,c=10,C=1;L2EucG5n\x1B\\"
+        );
+
+        let command = TransmitImage::new(3, ImageFormat::Png, Medium::SharedMemory("shm"));
+        assert_eq!(
+            command.ansi_string(),
+            "\x1B_Ga=t,i=3,q=2,f=100,t=s;c2ht\x1B\\"
+        );
+    }
+
+    #[test]
+    fn test_transmit_image_chunked() {
+        // 3072 bytes are exactly one chunk of 4096 base64 characters
+        let data = vec![0; 3072 * 2 + 3];
+        let ansi = TransmitImage::new(1, ImageFormat::Png, Medium::Direct(&data)).ansi_string();
+
+        let sequences: Vec<_> = ansi.split("\x1B\\").filter(|s| !s.is_empty()).collect();
+        assert_eq!(sequences.len(), 3);
+        assert!(sequences[0].starts_with("\x1B_Ga=t,i=1,q=2,f=100,m=1;AAAA"));
+        assert!(sequences[1].starts_with("\x1B_Gm=1;AAAA"));
+        assert_eq!(sequences[2], "\x1B_Gm=0;AAAA");
+        assert_eq!(
+            sequences[0].len(),
+            sequences[0].find(';').unwrap() + 1 + 4096
+        );
+    }
+
+    #[test]
+    fn test_transmit_image_chunked_in_tmux() {
+        let data = vec![0; 3072 + 3];
+        let mut ansi = String::new();
+        write_multiplexed_with(
+            &mut ansi,
+            Some(Multiplexer::Tmux),
+            MultiplexerPolicy::Passthrough,
+            |f| write_chunked(f, "a=t,i=1", &data),
+        )
+        .unwrap();
+
+        let sequences: Vec<_> = ansi.split("\x1B\x1B\\\x1B\\").collect();
+        assert_eq!(sequences.len(), 3);
+        assert!(sequences[0].starts_with("\x1BPtmux;\x1B\x1B_Ga=t,i=1,m=1;AAAA"));
+        assert_eq!(sequences[1], "\x1BPtmux;\x1B\x1B_Gm=0;AAAA");
+        assert_eq!(sequences[2], "");
+    }
+
+    #[test]
+    fn test_animation() {
+        let format = ImageFormat::Rgba {
+            width: 1,
+            height: 1,
+        };
+        assert_eq!(
+            TransmitFrame::new(1, format, Medium::Direct(&[0, 0, 0, 255]))
+                .gap(100)
+                .ansi_string(),
+            "\x1B_Ga=f,i=1,q=2,f=32,s=1,v=1,z=100;AAAA/w==\x1B\\"
+        );
+
+        let frame = TransmitFrame {
+            frame: Some(2),
+            base_frame: Some(1),
+            x_offset: 4,
+            overwrite: true,
+            ..TransmitFrame::new(1, ImageFormat::Png, Medium::File(Path::new("/a.png")))
+        };
+        assert_eq!(
+            frame.ansi_string(),
+            "\x1B_Ga=f,i=1,q=2,f=100,t=f,r=2,c=1,x=4,X=1;L2EucG5n\x1B\\"
+        );
+
+        assert_eq!(
+            ControlAnimation::new(1, AnimationState::Running).ansi_string(),
+            "\x1B_Ga=a,i=1,q=2,s=3\x1B\\"
+        );
+        let control = ControlAnimation {
+            current_frame: Some(2),
+            loops: Some(0),
+            ..ControlAnimation::new(1, AnimationState::Stopped)
+        };
+        assert_eq!(control.ansi_string(), "\x1B_Ga=a,i=1,q=2,s=1,c=2,v=1\x1B\\");
+    }
+
+    #[test]
+    fn test_place_and_delete() {
+        let placement = Placement {
+            placement_id: Some(7),
+            rows: Some(2),
+            x_offset: 3,
+            z_index: -1,
+            ..Placement::default()
+        };
+        assert_eq!(
+            PlaceImage::new(1, placement).ansi_string(),
+            "\x1B_Ga=p,i=1,q=2,p=7,r=2,X=3,z=-1\x1B\\"
+        );
+
+        assert_eq!(
+            DeleteImages::All { free: false }.ansi_string(),
+            "\x1B_Ga=d,q=2,d=a\x1B\\"
+        );
+        assert_eq!(
+            DeleteImages::Placement {
+                id: 1,
+                placement_id: 7,
+                free: true
+            }
+            .ansi_string(),
+            "\x1B_Ga=d,q=2,d=I,i=1,p=7\x1B\\"
+        );
+        assert_eq!(
+            DeleteImages::ZIndex {
+                z_index: -1,
+                free: false
+            }
+            .ansi_string(),
+            "\x1B_Ga=d,q=2,d=z,z=-1\x1B\\"
+        );
+    }
+}
@@ -0,0 +1,393 @@
+use std::{collections::HashMap, io};
+
+use crate::{
+    cursor::{MoveTo, RestorePosition, SavePosition},
+    queue,
+};
+
+use super::{DeleteImages, ImageFormat, Medium, PlaceImage, Placement, TransmitImage};
+
+/// An image transmitted by an [`ImageManager`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct ImageId(u32);
+
+impl ImageId {
+    /// The id of the image in the kitty graphics protocol.
+    pub fn id(self) -> u32 {
+        self.0
+    }
+}
+
+/// A placement of an image created by an [`ImageManager`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct PlacementId {
+    image: ImageId,
+    id: u32,
+}
+
+impl PlacementId {
+    /// The image which is placed.
+    pub fn image(self) -> ImageId {
+        self.image
+    }
+
+    /// The id of the placement in the kitty graphics protocol.
+    pub fn id(self) -> u32 {
+        self.id
+    }
+}
+
+#[derive(Debug, Default)]
+struct Image {
+    last_placement_id: u32,
+    // the position (column, row) and placement of every placement
+    placements: HashMap<u32, ((u16, u16), Placement)>,
+}
+
+/// Keeps track of the images transmitted with the kitty graphics protocol.
+///
+/// The manager assigns the image and placement ids, remembers where the images are placed so
+/// they can be moved or restacked, and deletes all its images when it's dropped. Images of
+/// others, e.g. other programs, aren't touched.
+///
+/// The commands are written to `W` and flushed after every operation.
+///
+/// # Examples
+///
+/// ```no_run
+/// use std::io;
+/// use crossterm::graphics::{ImageFormat, ImageManager, Medium, Placement};
+///
+/// fn main() -> io::Result<()> {
+///     let pixels = vec![255; 16 * 16 * 4];
+///     let format = ImageFormat::Rgba { width: 16, height: 16 };
+///
+///     let mut images = ImageManager::new(io::stdout());
+///     let image = images.transmit(format, Medium::Direct(&pixels))?;
+///     let placement = images.place(image, (10, 5), Placement::default())?;
+///     images.move_placement(placement, (20, 5))?;
+///     images.set_z_index(placement, -1)?;
+///
+///     // the images are deleted when the manager is dropped
+///     Ok(())
+/// }
+/// ```
+#[derive(Debug)]
+pub struct ImageManager<W: io::Write> {
+    writer: W,
+    next_image_id: u32,
+    images: HashMap<ImageId, Image>,
+}
+
+impl<W: io::Write> ImageManager<W> {
+    /// Creates a manager which writes to `writer`, with image ids starting at `1`.
+    pub fn new(writer: W) -> ImageManager<W> {
+        ImageManager::with_first_id(writer, 1)
+    }
+
+    /// Creates a manager whose image ids start at `first_id`, to avoid ids used elsewhere.
+    ///
+    /// # Notes
+    ///
+    /// The `first_id` must not be `0`.
+    pub fn with_first_id(writer: W, first_id: u32) -> ImageManager<W> {
+        ImageManager {
+            writer,
+            next_image_id: first_id,
+            images: HashMap::new(),
+        }
+    }
+
+    /// Returns the writer.
+    pub fn writer(&self) -> &W {
+        &self.writer
+    }
+
+    /// Returns the writer, e.g. to print text around the images.
+    pub fn writer_mut(&mut self) -> &mut W {
+        &mut self.writer
+    }
+
+    /// Returns the images which haven't been deleted.
+    pub fn images(&self) -> impl Iterator<Item = ImageId> + '_ {
+        self.images.keys().copied()
+    }
+
+    /// Returns the placements of `image`.
+    pub fn placements(&self, image: ImageId) -> impl Iterator<Item = PlacementId> + '_ {
+        self.images.get(&image).into_iter().flat_map(move |state| {
+            state
+                .placements
+                .keys()
+                .map(move |&id| PlacementId { image, id })
+        })
+    }
+
+    /// Transmits an image without placing it.
+    pub fn transmit(&mut self, format: ImageFormat, medium: Medium<'_>) -> io::Result<ImageId> {
+        let images = &self.images;
+        let image = ImageId(free_id(self.next_image_id, |id| {
+            images.contains_key(&ImageId(id))
+        }));
+        queue!(self.writer, TransmitImage::new(image.0, format, medium))?;
+        self.writer.flush()?;
+
+        self.next_image_id = next_id(image.0);
+        self.images.insert(image, Image::default());
+        Ok(image)
+    }
+
+    /// Places `image` with its top left corner at `position` (column, row).
+    ///
+    /// The `placement_id` of `placement` is assigned by the manager and the cursor doesn't move.
+    pub fn place(
+        &mut self,
+        image: ImageId,
+        position: (u16, u16),
+        placement: Placement,
+    ) -> io::Result<PlacementId> {
+        let state = self.images.get_mut(&image).ok_or_else(unknown)?;
+        let placements = &state.placements;
+        state.last_placement_id = free_id(next_id(state.last_placement_id), |id| {
+            placements.contains_key(&id)
+        });
+        let id = PlacementId {
+            image,
+            id: state.last_placement_id,
+        };
+
+        self.write_placement(id, position, placement)?;
+        Ok(id)
+    }
+
+    /// Moves a placement to `position` (column, row).
+    pub fn move_placement(
+        &mut self,
+        placement: PlacementId,
+        position: (u16, u16),
+    ) -> io::Result<()> {
+        let (_, current) = self.placement(placement)?;
+        self.write_placement(placement, position, current)
+    }
+
+    /// Changes the stacking order of a placement, negative values are drawn below the text.
+    pub fn set_z_index(&mut self, placement: PlacementId, z_index: i32) -> io::Result<()> {
+        let (position, current) = self.placement(placement)?;
+        self.write_placement(placement, position, Placement { z_index, ..current })
+    }
+
+    /// Removes a placement from the screen, the image can still be placed again.
+    pub fn delete_placement(&mut self, placement: PlacementId) -> io::Result<()> {
+        self.placement(placement)?;
+        queue!(
+            self.writer,
+            DeleteImages::Placement {
+                id: placement.image.0,
+                placement_id: placement.id,
+                free: false,
+            }
+        )?;
+        self.writer.flush()?;
+
+        if let Some(state) = self.images.get_mut(&placement.image) {
+            state.placements.remove(&placement.id);
+        }
+        Ok(())
+    }
+
+    /// Removes all placements of `image` and deletes its data in the terminal.
+    pub fn delete_image(&mut self, image: ImageId) -> io::Result<()> {
+        if !self.images.contains_key(&image) {
+            return Err(unknown());
+        }
+        queue!(
+            self.writer,
+            DeleteImages::Image {
+                id: image.0,
+                free: true,
+            }
+        )?;
+        self.writer.flush()?;
+
+        self.images.remove(&image);
+        Ok(())
+    }
+
+    /// Deletes all images of the manager.
+    pub fn clear(&mut self) -> io::Result<()> {
+        for image in self.images.keys() {
+            queue!(
+                self.writer,
+                DeleteImages::Image {
+                    id: image.0,
+                    free: true,
+                }
+            )?;
+        }
+        self.writer.flush()?;
+
+        self.images.clear();
+        Ok(())
+    }
+
+    fn placement(&self, placement: PlacementId) -> io::Result<((u16, u16), Placement)> {
+        self.images
+            .get(&placement.image)
+            .and_then(|state| state.placements.get(&placement.id))
+            .copied()
+            .ok_or_else(unknown)
+    }
+
+    fn write_placement(
+        &mut self,
+        id: PlacementId,
+        position: (u16, u16),
+        placement: Placement,
+    ) -> io::Result<()> {
+        let placement = Placement {
+            placement_id: Some(id.id),
+            keep_cursor: true,
+            ..placement
+        };
+
+        // a placement with the same id replaces the previous one
+        queue!(
+            self.writer,
+            SavePosition,
+            MoveTo(position.0, position.1),
+            PlaceImage::new(id.image.0, placement),
+            RestorePosition
+        )?;
+        self.writer.flush()?;
+
+        if let Some(state) = self.images.get_mut(&id.image) {
+            state.placements.insert(id.id, (position, placement));
+        }
+        Ok(())
+    }
+}
+
+impl<W: io::Write> Drop for ImageManager<W> {
+    fn drop(&mut self) {
+        let _ = self.clear();
+    }
+}
+
+/// Returns the id after `id`, which wraps around to `1`, as `0` isn't a valid id.
+fn next_id(id: u32) -> u32 {
+    id.checked_add(1).unwrap_or(1)
+}
+
+/// Returns `id`, or the first id after it which isn't in use, after the ids wrapped around.
+fn free_id(mut id: u32, in_use: impl Fn(u32) -> bool) -> u32 {
+    while in_use(id) {
+        id = next_id(id);
+    }
+    id
+}
+
+fn unknown() -> io::Error {
+    io::Error::new(
+        io::ErrorKind::NotFound,
+        "The image or placement doesn't belong to the image manager or was deleted.",
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::graphics::{ImageFormat, Medium, Placement};
+
+    use super::ImageManager;
+
+    fn take(images: &mut ImageManager<Vec<u8>>) -> String {
+        String::from_utf8(std::mem::take(images.writer_mut())).unwrap()
+    }
+
+    #[test]
+    fn test_image_manager() {
+        let mut images = ImageManager::new(Vec::new());
+        let image = images
+            .transmit(ImageFormat::Png, Medium::Direct(b"foo"))
+            .unwrap();
+        assert_eq!(image.id(), 1);
+        assert_eq!(take(&mut images), "\x1B_Ga=t,i=1,q=2,f=100;Zm9v\x1B\\");
+
+        let placement = images.place(image, (2, 3), Placement::default()).unwrap();
+        assert_eq!(placement.id(), 1);
+        assert_eq!(
+            take(&mut images),
+            "\x1B7\x1B[4;3H\x1B_Ga=p,i=1,q=2,p=1,C=1\x1B\\\x1B8"
+        );
+
+        images.set_z_index(placement, -1).unwrap();
+        assert_eq!(
+            take(&mut images),
+            "\x1B7\x1B[4;3H\x1B_Ga=p,i=1,q=2,p=1,z=-1,C=1\x1B\\\x1B8"
+        );
+
+        images.move_placement(placement, (0, 0)).unwrap();
+        assert_eq!(
+            take(&mut images),
+            "\x1B7\x1B[1;1H\x1B_Ga=p,i=1,q=2,p=1,z=-1,C=1\x1B\\\x1B8"
+        );
+
+        images.delete_placement(placement).unwrap();
+        assert_eq!(take(&mut images), "\x1B_Ga=d,q=2,d=i,i=1,p=1\x1B\\");
+        assert!(images.move_placement(placement, (0, 0)).is_err());
+        assert_eq!(images.placements(image).count(), 0);
+
+        images.delete_image(image).unwrap();
+        assert_eq!(take(&mut images), "\x1B_Ga=d,q=2,d=I,i=1\x1B\\");
+        assert!(images.place(image, (0, 0), Placement::default()).is_err());
+    }
+
+    #[test]
+    fn test_image_manager_cleans_up_on_drop() {
+        let mut output = Vec::new();
+        {
+            let mut images = ImageManager::with_first_id(&mut output, 7);
+            let image = images
+                .transmit(ImageFormat::Png, Medium::Direct(b""))
+                .unwrap();
+            images.place(image, (0, 0), Placement::default()).unwrap();
+        }
+
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.ends_with("\x1B_Ga=d,q=2,d=I,i=7\x1B\\"));
+    }
+
+    #[test]
+    fn test_image_manager_skips_live_ids_after_wraparound() {
+        let mut images = ImageManager::with_first_id(Vec::new(), u32::MAX);
+        let transmit = |images: &mut ImageManager<Vec<u8>>| {
+            images
+                .transmit(ImageFormat::Png, Medium::Direct(b""))
+                .unwrap()
+        };
+
+        let last = transmit(&mut images);
+        assert_eq!(last.id(), u32::MAX);
+        let first = transmit(&mut images);
+        let second = transmit(&mut images);
+        assert_eq!((first.id(), second.id()), (1, 2));
+
+        images.delete_image(first).unwrap();
+        images.next_image_id = u32::MAX;
+        // the live images keep their ids
+        assert_eq!(transmit(&mut images).id(), 1);
+        assert_eq!(transmit(&mut images).id(), 3);
+
+        let placement = images.place(last, (0, 0), Placement::default()).unwrap();
+        images.images.get_mut(&last).unwrap().last_placement_id = u32::MAX - 1;
+        let wrapped = images.place(last, (0, 0), Placement::default()).unwrap();
+        assert_eq!(wrapped.id(), u32::MAX);
+        assert_eq!(placement.id(), 1);
+        assert_eq!(
+            images
+                .place(last, (0, 0), Placement::default())
+                .unwrap()
+                .id(),
+            2
+        );
+    }
+}
@@ -0,0 +1,114 @@
+//! A minimal PNG encoder for protocols which expect an image file, using uncompressed deflate
+//! blocks.
+
+const SIGNATURE: &[u8] = b"\x89PNG\r\n\x1A\n";
+
+/// The largest length of an uncompressed deflate block.
+const MAX_BLOCK: usize = 0xFFFF;
+
+/// Encodes `width` x `height` RGBA `pixels` as a PNG file.
+pub(super) fn encode_rgba(pixels: &[u8], width: u32, height: u32) -> Vec<u8> {
+    let stride = width as usize * 4;
+
+    // every row starts with the filter type, 0 for none
+    let mut raw = Vec::with_capacity((stride + 1) * height as usize);
+    for row in 0..height as usize {
+        raw.push(0);
+        let start = (row * stride).min(pixels.len());
+        let end = (start + stride).min(pixels.len());
+        raw.extend_from_slice(&pixels[start..end]);
+        raw.resize(raw.len() + stride - (end - start), 0);
+    }
+
+    let mut header = Vec::with_capacity(13);
+    header.extend_from_slice(&width.to_be_bytes());
+    header.extend_from_slice(&height.to_be_bytes());
+    // 8 bits per channel, RGBA, deflate, adaptive filtering, no interlace
+    header.extend_from_slice(&[8, 6, 0, 0, 0]);
+
+    let mut png = SIGNATURE.to_vec();
+    write_chunk(&mut png, b"IHDR", &header);
+    write_chunk(&mut png, b"IDAT", &zlib_stored(&raw));
+    write_chunk(&mut png, b"IEND", &[]);
+    png
+}
+
+fn write_chunk(png: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    png.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    png.extend_from_slice(kind);
+    png.extend_from_slice(data);
+    let crc = crc32(kind.iter().chain(data));
+    png.extend_from_slice(&crc.to_be_bytes());
+}
+
+/// Wraps `data` into a zlib stream of uncompressed deflate blocks.
+fn zlib_stored(data: &[u8]) -> Vec<u8> {
+    let mut zlib = Vec::with_capacity(data.len() + data.len() / MAX_BLOCK * 5 + 11);
+    // deflate with a 32K window, no dictionary
+    zlib.extend_from_slice(&[0x78, 0x01]);
+
+    let mut blocks = data.chunks(MAX_BLOCK).peekable();
+    if blocks.peek().is_none() {
+        zlib.extend_from_slice(&[1, 0, 0, 0xFF, 0xFF]);
+    }
+    while let Some(block) = blocks.next() {
+        let last = blocks.peek().is_none();
+        let len = block.len() as u16;
+        zlib.push(last as u8);
+        zlib.extend_from_slice(&len.to_le_bytes());
+        zlib.extend_from_slice(&(!len).to_le_bytes());
+        zlib.extend_from_slice(block);
+    }
+
+    zlib.extend_from_slice(&adler32(data).to_be_bytes());
+    zlib
+}
+
+fn crc32<'a>(bytes: impl IntoIterator<Item = &'a u8>) -> u32 {
+    let mut crc = !0u32;
+    for &byte in bytes {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 {
+                0xEDB8_8320 ^ (crc >> 1)
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
+
+fn adler32(bytes: &[u8]) -> u32 {
+    let (mut a, mut b) = (1u32, 0u32);
+    for &byte in bytes {
+        a = (a + byte as u32) % 65521;
+        b = (b + a) % 65521;
+    }
+    b << 16 | a
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{adler32, crc32, encode_rgba};
+
+    #[test]
+    fn test_encode_rgba() {
+        assert_eq!(crc32(b"IEND"), 0xAE42_6082);
+        assert_eq!(adler32(b"Wikipedia"), 0x11E6_0398);
+
+        let png = encode_rgba(&[255, 0, 0, 255], 1, 1);
+        assert!(png.starts_with(b"\x89PNG\r\n\x1A\n\0\0\0\x0DIHDR\0\0\0\x01\0\0\0\x01\x08\x06"));
+        assert!(png.ends_with(b"\0\0\0\0IEND\xAE\x42\x60\x82"));
+
+        // the filter byte and the pixel in a single stored block
+        let idat = &png[33..];
+        assert_eq!(&idat[..8], b"\0\0\0\x10IDAT");
+        assert_eq!(
+            &idat[8..15],
+            &[0x78, 0x01, 1, 5, 0, 0xFA, 0xFF],
+            "zlib header and stored block"
+        );
+        assert_eq!(&idat[15..20], &[0, 255, 0, 0, 255]);
+    }
+}
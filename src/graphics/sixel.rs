@@ -0,0 +1,256 @@
+use std::{collections::HashMap, fmt};
+
+use crate::{command::write_multiplexed, Command};
+
+/// The number of palette registers terminals usually provide.
+const MAX_COLORS: usize = 256;
+
+/// Pixels with a smaller alpha value are left transparent.
+const ALPHA_THRESHOLD: u8 = 128;
+
+/// A command that prints an image as sixel graphics at the cursor position.
+///
+/// The pixels are RGBA, row by row. Images with more than 256 colors are quantized to a fixed
+/// palette, pixels with an alpha value below 128 are left transparent.
+///
+/// Does nothing if the [`Capabilities`](../capabilities/struct.Capabilities.html) say the
+/// terminal doesn't support sixel graphics, which is the default.
+///
+/// # Notes
+///
+/// Commands must be executed/queued for execution otherwise they do nothing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct PrintSixel<'a> {
+    /// The RGBA pixels, `width * height * 4` bytes.
+    pub pixels: &'a [u8],
+    /// The width in pixels.
+    pub width: u32,
+    /// The height in pixels.
+    pub height: u32,
+}
+
+impl<'a> PrintSixel<'a> {
+    /// Creates a command which prints `width` x `height` RGBA `pixels`.
+    pub fn new(pixels: &'a [u8], width: u32, height: u32) -> PrintSixel<'a> {
+        PrintSixel {
+            pixels,
+            width,
+            height,
+        }
+    }
+}
+
+impl Command for PrintSixel<'_> {
+    fn write_ansi(&self, f: &mut impl fmt::Write) -> fmt::Result {
+        if !crate::capabilities::query(|capabilities| capabilities.sixel) {
+            return Ok(());
+        }
+        write_multiplexed(f, |f| {
+            write_sixel(f, self.pixels, self.width as usize, self.height as usize)
+        })
+    }
+
+    fn size_hint(&self) -> usize {
+        // a sixel character covers six pixels, the palette and color switches add some more
+        (self.width as usize * self.height as usize) / 3
+    }
+
+    #[cfg(windows)]
+    fn execute_winapi(&self) -> std::io::Result<()> {
+        Err(crate::Error::UnsupportedCapability(
+            "Sixel graphics are not supported by the legacy Windows API.",
+        )
+        .into())
+    }
+}
+
+/// Maps the pixels to palette registers.
+struct Quantized {
+    palette: Vec<[u8; 3]>,
+    // the register of every pixel, `None` for transparent ones
+    pixels: Vec<Option<u16>>,
+}
+
+fn quantize(pixels: &[u8], count: usize) -> Quantized {
+    let pixels = pixels.chunks_exact(4).take(count);
+
+    // Use the exact colors if they fit into the palette, otherwise a 6x7x6 color cube.
+    let exact = pixels
+        .clone()
+        .filter(|pixel| pixel[3] >= ALPHA_THRESHOLD)
+        .try_fold(HashMap::new(), |mut colors, pixel| {
+            colors.insert([pixel[0], pixel[1], pixel[2]], ());
+            if colors.len() > MAX_COLORS {
+                None
+            } else {
+                Some(colors)
+            }
+        })
+        .is_some();
+    let reduce = |pixel: &[u8]| {
+        let level = |value: u8, levels: u32| {
+            let level = (value as u32 * (levels - 1) + 127) / 255;
+            (level * 255 / (levels - 1)) as u8
+        };
+        if exact {
+            [pixel[0], pixel[1], pixel[2]]
+        } else {
+            [level(pixel[0], 6), level(pixel[1], 7), level(pixel[2], 6)]
+        }
+    };
+
+    let mut palette = Vec::new();
+    let mut registers = HashMap::new();
+    let pixels = pixels
+        .map(|pixel| {
+            if pixel[3] < ALPHA_THRESHOLD {
+                return None;
+            }
+            let color = reduce(pixel);
+            Some(*registers.entry(color).or_insert_with(|| {
+                palette.push(color);
+                palette.len() as u16 - 1
+            }))
+        })
+        .collect();
+
+    Quantized { palette, pixels }
+}
+
+pub(super) fn write_sixel(
+    f: &mut impl fmt::Write,
+    pixels: &[u8],
+    width: usize,
+    height: usize,
+) -> fmt::Result {
+    let quantized = quantize(pixels, width * height);
+    let register = |x: usize, y: usize| quantized.pixels.get(y * width + x).copied().flatten();
+
+    // P2 = 1: pixels without a color stay transparent
+    write!(f, "\x1BP0;1;0q\"1;1;{};{}", width, height)?;
+    for (i, [r, g, b]) in quantized.palette.iter().enumerate() {
+        let percent = |value: u8| (value as u32 * 100 + 127) / 255;
+        write!(
+            f,
+            "#{};2;{};{};{}",
+            i,
+            percent(*r),
+            percent(*g),
+            percent(*b)
+        )?;
+    }
+
+    let mut row = Vec::with_capacity(width);
+    for band in (0..height).step_by(6) {
+        if band > 0 {
+            f.write_char('-')?;
+        }
+
+        let mut registers: Vec<u16> = (band..height.min(band + 6))
+            .flat_map(|y| (0..width).filter_map(move |x| register(x, y)))
+            .collect();
+        registers.sort_unstable();
+        registers.dedup();
+
+        for (i, &current) in registers.iter().enumerate() {
+            if i > 0 {
+                // back to the start of the band
+                f.write_char('$')?;
+            }
+
+            row.clear();
+            row.extend((0..width).map(|x| {
+                (0..6)
+                    .filter(|dy| band + dy < height && register(x, band + dy) == Some(current))
+                    .fold(0u8, |bits, dy| bits | 1 << dy)
+            }));
+            while row.last() == Some(&0) {
+                row.pop();
+            }
+
+            write!(f, "#{}", current)?;
+            write_run_length(f, &row)?;
+        }
+    }
+
+    f.write_str("\x1B\\")
+}
+
+fn write_run_length(f: &mut impl fmt::Write, row: &[u8]) -> fmt::Result {
+    let mut i = 0;
+    while i < row.len() {
+        let bits = row[i];
+        let count = row[i..].iter().take_while(|&&b| b == bits).count();
+        let sixel = (b'?' + bits) as char;
+        if count > 3 {
+            write!(f, "!{}{}", count, sixel)?;
+        } else {
+            for _ in 0..count {
+                f.write_char(sixel)?;
+            }
+        }
+        i += count;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Command;
+
+    use super::{quantize, write_sixel, PrintSixel};
+
+    fn sixel(pixels: &[u8], width: usize, height: usize) -> String {
+        let mut ansi = String::new();
+        write_sixel(&mut ansi, pixels, width, height).unwrap();
+        ansi
+    }
+
+    #[test]
+    fn test_sixel_encoding() {
+        let red = [255, 0, 0, 255];
+        let blue = [0, 0, 255, 255];
+        let clear = [0, 0, 0, 0];
+
+        assert_eq!(
+            sixel(&[red, blue].concat(), 2, 1),
+            "\x1BP0;1;0q\"1;1;2;1#0;2;100;0;0#1;2;0;0;100#0@$#1?@\x1B\\"
+        );
+
+        // a 5x7 image: two bands, the second one with a single row
+        let mut pixels = Vec::new();
+        for y in 0..7 {
+            for x in 0..5 {
+                pixels.extend(if y == 6 || x == 4 { clear } else { red });
+            }
+        }
+        assert_eq!(
+            sixel(&pixels, 5, 7),
+            "\x1BP0;1;0q\"1;1;5;7#0;2;100;0;0#0!4~-\x1B\\"
+        );
+
+        pixels[6 * 5 * 4..6 * 5 * 4 + 4].copy_from_slice(&blue);
+        assert_eq!(
+            sixel(&pixels, 5, 7),
+            "\x1BP0;1;0q\"1;1;5;7#0;2;100;0;0#1;2;0;0;100#0!4~-#1@\x1B\\"
+        );
+    }
+
+    #[test]
+    fn test_quantize() {
+        let pixels: Vec<u8> = (0..300u32)
+            .flat_map(|i| [(i % 256) as u8, (i / 256) as u8 * 200, 0, 255])
+            .collect();
+        let quantized = quantize(&pixels, 300);
+
+        assert!(quantized.palette.len() <= 6 * 7 * 6);
+        assert!(quantized.pixels.iter().all(|register| register.is_some()));
+        assert_eq!(quantized.palette[0], [0, 0, 0]);
+    }
+
+    #[test]
+    fn test_print_sixel_needs_capability() {
+        let pixels = [255; 4];
+        assert_eq!(PrintSixel::new(&pixels, 1, 1).ansi_string(), "");
+    }
+}
@@ -33,6 +33,8 @@
 //!
 //! ### Supported Commands
 //!
+//! - Module [`clipboard`](clipboard/index.html)
+//!   - Clipboard - [`CopyToClipboard`](clipboard/struct.CopyToClipboard.html)
 //! - Module [`cursor`](cursor/index.html)
 //!   - Visibility - [`Show`](cursor/struct.Show.html), [`Hide`](cursor/struct.Hide.html)
 //!   - Appearance - [`EnableBlinking`](cursor/struct.EnableBlinking.html),
@@ -49,7 +51,25 @@
 //!     [`PushKeyboardEnhancementFlags`](event/struct.PushKeyboardEnhancementFlags.html),
 //!     [`PopKeyboardEnhancementFlags`](event/struct.PopKeyboardEnhancementFlags.html)
 //!   - Mouse events - [`EnableMouseCapture`](event/struct.EnableMouseCapture.html),
-//!     [`DisableMouseCapture`](event/struct.DisableMouseCapture.html)
+//!     [`EnableMouseCaptureWith`](event/struct.EnableMouseCaptureWith.html),
+//!     [`DisableMouseCapture`](event/struct.DisableMouseCapture.html),
+//!     [`EnableQuickEdit`](event/struct.EnableQuickEdit.html),
+//!     [`DisableQuickEdit`](event/struct.DisableQuickEdit.html),
+//!     [`SetPointerShape`](event/struct.SetPointerShape.html)
+//!   - Color reports - [`QueryColor`](event/struct.QueryColor.html)
+//! - Module [`graphics`](graphics/index.html)
+//!   - Any protocol - [`PrintImage`](graphics/struct.PrintImage.html)
+//!   - Kitty graphics protocol - [`TransmitImage`](graphics/struct.TransmitImage.html),
+//!     [`PlaceImage`](graphics/struct.PlaceImage.html),
+//!     [`DeleteImages`](graphics/enum.DeleteImages.html),
+//!     [`TransmitFrame`](graphics/struct.TransmitFrame.html),
+//!     [`ControlAnimation`](graphics/struct.ControlAnimation.html)
+//!   - Sixel graphics - [`PrintSixel`](graphics/struct.PrintSixel.html)
+//!   - iTerm2 inline images - [`InlineImage`](graphics/struct.InlineImage.html)
+//! - Module [`notification`](notification/index.html)
+//!   - Notifications - [`Notify`](notification/struct.Notify.html),
+//!     [`Notification`](notification/struct.Notification.html),
+//!     [`CloseNotification`](notification/struct.CloseNotification.html)
 //! - Module [`style`](style/index.html)
 //!   - Colors - [`SetForegroundColor`](style/struct.SetForegroundColor.html),
 //!     [`SetBackgroundColor`](style/struct.SetBackgroundColor.html),
@@ -58,12 +78,21 @@
 //!     [`PrintStyledContent`](style/struct.PrintStyledContent.html)
 //! - Module [`terminal`](terminal/index.html)
 //!   - Scrolling - [`ScrollUp`](terminal/struct.ScrollUp.html),
-//!     [`ScrollDown`](terminal/struct.ScrollDown.html)
+//!     [`ScrollDown`](terminal/struct.ScrollDown.html),
+//!     [`SetScrollRegion`](terminal/struct.SetScrollRegion.html),
+//!     [`ResetScrollRegion`](terminal/struct.ResetScrollRegion.html)
+//!   - Margins - [`EnableLeftRightMargins`](terminal/struct.EnableLeftRightMargins.html),
+//!     [`DisableLeftRightMargins`](terminal/struct.DisableLeftRightMargins.html),
+//!     [`SetLeftRightMargins`](terminal/struct.SetLeftRightMargins.html),
+//!     [`EnableOriginMode`](terminal/struct.EnableOriginMode.html),
+//!     [`DisableOriginMode`](terminal/struct.DisableOriginMode.html)
 //!   - Miscellaneous - [`Clear`](terminal/struct.Clear.html),
 //!     [`SetSize`](terminal/struct.SetSize.html),
 //!     [`SetTitle`](terminal/struct.SetTitle.html),
 //!     [`DisableLineWrap`](terminal/struct.DisableLineWrap.html),
-//!     [`EnableLineWrap`](terminal/struct.EnableLineWrap.html)
+//!     [`EnableLineWrap`](terminal/struct.EnableLineWrap.html),
+//!     [`DisableAutoRepeat`](terminal/struct.DisableAutoRepeat.html),
+//!     [`EnableAutoRepeat`](terminal/struct.EnableAutoRepeat.html)
 //!   - Alternate screen - [`EnterAlternateScreen`](terminal/struct.EnterAlternateScreen.html),
 //!     [`LeaveAlternateScreen`](terminal/struct.LeaveAlternateScreen.html)
 //!
@@ -229,26 +258,66 @@
 //! [stderr]: https://doc.rust-lang.org/std/io/fn.stderr.html
 //! [flush]: https://doc.rust-lang.org/std/io/trait.Write.html#tymethod.flush
 
-pub use crate::command::{Command, ExecutableCommand, QueueableCommand, SynchronizedUpdate};
+pub use crate::command::{
+    batch, remove_tee, retry_policy, screen_passthrough, set_retry_policy, set_tee,
+    tmux_passthrough, write_large, BoxedCommand, BufferingWriter, Command, CommandBuffer,
+    DynCommand, ExecutableCommand, NonBlockingWriter, ProcessTerminal, QueueableCommand,
+    QueuedWriter, RateLimitedWriter, RetryPolicy, ScreenPassthrough, SynchronizedUpdate,
+    SynchronizedWriter, TmuxPassthrough,
+};
+pub use crate::error::Error;
+pub use crate::metrics::{metrics, reset_metrics, Metrics};
 
+/// A module to make applications usable with screen readers.
+pub mod accessibility;
+/// A module to draw a grid of cells by only printing the changed cells.
+#[cfg(feature = "buffer")]
+pub mod buffer;
+/// A module to describe which optional features the terminal supports.
+pub mod capabilities;
+/// A module to copy text to the clipboard.
+pub mod clipboard;
 /// A module to work with the terminal cursor
 pub mod cursor;
 /// A module to read events.
 #[cfg(feature = "events")]
 pub mod event;
+/// A module to display images.
+pub mod graphics;
+/// A module to show desktop notifications.
+pub mod notification;
+/// A module to run processes under a pseudo-terminal for testing.
+#[cfg(all(unix, feature = "pty"))]
+pub mod pty;
 /// A module to apply attributes and colors on your text.
 pub mod style;
 /// A module to work with the terminal.
 pub mod terminal;
 
+/// A module to consult the terminfo database for terminals which aren't xterm compatible.
+#[cfg(feature = "terminfo")]
+pub mod terminfo;
+
 /// A module to query if the current instance is a tty.
 pub mod tty;
 
 #[cfg(windows)]
-/// A module that exposes one function to check if the current terminal supports ANSI sequences.
+/// A module to check if the current terminal supports ANSI sequences and which console host it runs in.
 pub mod ansi_support;
+mod base64;
 mod command;
+mod error;
 pub(crate) mod macros;
+mod metrics;
+
+/// Implementation details of the macros, not part of the public API.
+#[doc(hidden)]
+pub mod __private {
+    pub use crate::command::{
+        queue_tracked, LockColored, LockStdout, Locker, PassThrough, Probe, ProbeOtherWriter,
+        ProbeProcessTerminal,
+    };
+}
 
 #[cfg(all(windows, not(feature = "windows")))]
 compile_error!("Compiling on Windows with \"windows\" feature disabled. Feature \"windows\" should only be disabled when project will never be compiled on Windows.");
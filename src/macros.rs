@@ -5,6 +5,19 @@ macro_rules! csi {
     ($( $l:expr ),*) => { concat!("\x1B[", $( $l ),*) };
 }
 
+/// Writes the terminfo capability `$name` and returns from the calling `write_ansi`, if the
+/// `terminfo` feature is enabled and the current entry has the capability.
+#[macro_export]
+#[doc(hidden)]
+macro_rules! write_terminfo {
+    ($f:expr, $name:expr $(, $param:expr)*) => {
+        #[cfg(feature = "terminfo")]
+        if let Some(result) = $crate::terminfo::write($f, $name, &[$($param as i32),*]) {
+            return result;
+        }
+    };
+}
+
 /// Queues one or more command(s) for further execution.
 ///
 /// Queued commands must be flushed to the underlying device to be executed.
@@ -54,15 +67,32 @@ macro_rules! csi {
 /// Therefore, there is no difference between [execute](macro.execute.html)
 /// and [queue](macro.queue.html) for those old Windows versions.
 ///
+/// Commands queued on a [`ProcessTerminal`](trait.ProcessTerminal.html), i.e. stdout or stderr,
+/// update the state crossterm tracks about the terminal, e.g.
+/// [`is_alternate_screen_active`](terminal/fn.is_alternate_screen_active.html).
+///
 #[macro_export]
 macro_rules! queue {
     ($writer:expr $(, $command:expr)* $(,)?) => {{
-        use ::std::io::Write;
-
-        // This allows the macro to take both mut impl Write and &mut impl Write.
-        Ok($writer.by_ref())
-            $(.and_then(|writer| $crate::QueueableCommand::queue(writer, $command)))*
-            .map(|_| ())
+        #[allow(unused_imports)]
+        use {
+            ::std::io::Write,
+            $crate::__private::{
+                LockColored, LockStdout, PassThrough, ProbeOtherWriter, ProbeProcessTerminal,
+            },
+        };
+
+        // This allows the macro to take both mut impl Write and &mut impl Write. `stdout` is
+        // locked once for all commands.
+        match $crate::__private::Locker($writer.by_ref()).lock_writer() {
+            mut writer => {
+                // Only commands queued on the terminal of the process update its tracked state.
+                let tracking = (&$crate::__private::Probe(&writer)).is_process_terminal();
+                Ok(&mut writer)
+                    $(.and_then(|writer| $crate::__private::queue_tracked(writer, $command, tracking)))*
+                    .map(|_| ())
+            }
+        }
     }}
 }
 
@@ -108,13 +138,44 @@ macro_rules! queue {
 #[macro_export]
 macro_rules! execute {
     ($writer:expr $(, $command:expr)* $(,)? ) => {{
-        use ::std::io::Write;
+        #[allow(unused_imports)]
+        use {
+            ::std::io::Write,
+            $crate::__private::{LockColored, LockStdout, PassThrough},
+        };
+
+        // Queue each command, then flush, while holding the `stdout` lock
+        match $crate::__private::Locker($writer.by_ref()).lock_writer() {
+            mut writer => $crate::queue!(writer $(, $command)*)
+                .and_then(|()| ::std::io::Write::flush(&mut writer)),
+        }
+    }}
+}
 
-        // Queue each command, then flush
-        $crate::queue!($writer $(, $command)*)
-            .and_then(|()| {
-                ::std::io::Write::flush($writer.by_ref())
-            })
+/// Renders one or more command(s) to a `Vec<u8>` holding their ANSI escape codes.
+///
+/// No writer is involved, the bytes can be sent over a network socket, stored in a custom
+/// framebuffer or written to the terminal later.
+///
+/// # Examples
+///
+/// ```rust
+/// use crossterm::{cursor::MoveTo, render, style::Print};
+///
+/// let bytes = render!(MoveTo(0, 0), Print("foo"));
+/// assert_eq!(bytes, b"\x1B[1;1Hfoo");
+/// ```
+///
+/// # Notes
+///
+/// The ANSI codes are rendered on every platform, commands are never executed with WinAPI calls.
+#[macro_export]
+macro_rules! render {
+    ($($command:expr),* $(,)?) => {{
+        #[allow(unused_mut)]
+        let mut ansi = ::std::string::String::new();
+        $(ansi.push_str(&$crate::Command::ansi_string(&$command));)*
+        ansi.into_bytes()
     }}
 }
 
@@ -231,6 +292,31 @@ mod tests {
             assert_eq!(&result.buffer, "cmdcmd");
             assert!(result.flushed);
         }
+
+        #[test]
+        fn test_generic_writer() {
+            fn queue_generic(writer: &mut impl std::io::Write) -> std::io::Result<()> {
+                queue!(writer, FakeCommand)
+            }
+
+            let mut result = FakeWrite::default();
+            queue_generic(&mut result).unwrap();
+            execute!(&mut result, FakeCommand).unwrap();
+            assert_eq!(&result.buffer, "cmdcmd");
+            assert!(result.flushed);
+        }
+    }
+
+    #[test]
+    fn test_stdout_is_locked_once() {
+        #[allow(unused_imports)]
+        use crate::__private::{LockColored, LockStdout, Locker, PassThrough};
+
+        let mut stdout = io::stdout();
+        let _lock: io::StdoutLock<'_> = Locker(&mut stdout).lock_writer();
+
+        let mut stderr = io::stderr();
+        let _writer: &mut io::Stderr = Locker(&mut stderr).lock_writer();
     }
 
     #[cfg(windows)]
@@ -0,0 +1,137 @@
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+
+static BYTES_WRITTEN: AtomicU64 = AtomicU64::new(0);
+static COMMANDS_EXECUTED: AtomicU64 = AtomicU64::new(0);
+static EVENTS_READ: AtomicU64 = AtomicU64::new(0);
+static PARSE_ERRORS: AtomicU64 = AtomicU64::new(0);
+static COMMANDS_PASSED_THROUGH: AtomicU64 = AtomicU64::new(0);
+static COMMANDS_SUPPRESSED: AtomicU64 = AtomicU64::new(0);
+static EVENT_QUEUE_LEN: AtomicUsize = AtomicUsize::new(0);
+
+/// Counters of the terminal I/O of the process, see [`metrics`](fn.metrics.html).
+///
+/// The event metrics cover the terminal of the process, which [`read`](event/fn.read.html)
+/// reads: the events of [terminal handles](terminal/struct.Terminal.html) and of
+/// [`Parser`](event/struct.Parser.html)s created by the application aren't counted.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub struct Metrics {
+    /// The number of bytes commands wrote, to any writer.
+    pub bytes_written: u64,
+    /// The number of commands which were queued or executed.
+    pub commands_executed: u64,
+    /// The number of events read from the terminal by [`read`](event/fn.read.html) and
+    /// [`poll`](event/fn.poll.html), or by an [`EventStream`](event/struct.EventStream.html),
+    /// without the replies to the queries of crossterm, e.g. of
+    /// [`cursor::position`](cursor/fn.position.html).
+    pub events_read: u64,
+    /// The number of input sequences of the terminal which couldn't be parsed and were
+    /// discarded.
+    pub parse_errors: u64,
+    /// The number of commands which were wrapped in the passthrough sequence of the multiplexer,
+    /// see [`MultiplexerPolicy`](capabilities/enum.MultiplexerPolicy.html).
+    pub commands_passed_through: u64,
+    /// The number of commands which weren't written inside the multiplexer, see
+    /// [`MultiplexerPolicy`](capabilities/enum.MultiplexerPolicy.html).
+    pub commands_suppressed: u64,
+    /// The number of events which were read from the terminal, but not yet returned by
+    /// [`read`](event/fn.read.html), e.g. because they didn't match a filter.
+    pub event_queue_len: usize,
+}
+
+/// Returns the counters of the terminal I/O since the start of the process, or the last
+/// [`reset_metrics`](fn.reset_metrics.html).
+///
+/// The counters are cheap to update, so they're always kept. Long-running applications can
+/// sample them regularly to monitor the health of the terminal connection, e.g. a growing
+/// `event_queue_len` or a rising `parse_errors` count.
+///
+/// # Examples
+///
+/// ```no_run
+/// use std::io;
+/// use crossterm::{execute, style::Print};
+///
+/// fn main() -> io::Result<()> {
+///     execute!(io::stdout(), Print("Hello"))?;
+///
+///     let metrics = crossterm::metrics();
+///     eprintln!("{} bytes, {} commands", metrics.bytes_written, metrics.commands_executed);
+///     Ok(())
+/// }
+/// ```
+pub fn metrics() -> Metrics {
+    Metrics {
+        bytes_written: BYTES_WRITTEN.load(Ordering::Relaxed),
+        commands_executed: COMMANDS_EXECUTED.load(Ordering::Relaxed),
+        events_read: EVENTS_READ.load(Ordering::Relaxed),
+        parse_errors: PARSE_ERRORS.load(Ordering::Relaxed),
+        commands_passed_through: COMMANDS_PASSED_THROUGH.load(Ordering::Relaxed),
+        commands_suppressed: COMMANDS_SUPPRESSED.load(Ordering::Relaxed),
+        event_queue_len: EVENT_QUEUE_LEN.load(Ordering::Relaxed),
+    }
+}
+
+/// Sets the counters returned by [`metrics`](fn.metrics.html) back to zero, except
+/// `event_queue_len` which isn't a counter.
+pub fn reset_metrics() {
+    BYTES_WRITTEN.store(0, Ordering::Relaxed);
+    COMMANDS_EXECUTED.store(0, Ordering::Relaxed);
+    EVENTS_READ.store(0, Ordering::Relaxed);
+    PARSE_ERRORS.store(0, Ordering::Relaxed);
+    COMMANDS_PASSED_THROUGH.store(0, Ordering::Relaxed);
+    COMMANDS_SUPPRESSED.store(0, Ordering::Relaxed);
+}
+
+pub(crate) fn add_bytes_written(bytes: usize) {
+    BYTES_WRITTEN.fetch_add(bytes as u64, Ordering::Relaxed);
+}
+
+pub(crate) fn add_command() {
+    COMMANDS_EXECUTED.fetch_add(1, Ordering::Relaxed);
+}
+
+#[cfg(feature = "events")]
+pub(crate) fn add_event() {
+    EVENTS_READ.fetch_add(1, Ordering::Relaxed);
+}
+
+pub(crate) fn add_command_passed_through() {
+    COMMANDS_PASSED_THROUGH.fetch_add(1, Ordering::Relaxed);
+}
+
+pub(crate) fn add_command_suppressed() {
+    COMMANDS_SUPPRESSED.fetch_add(1, Ordering::Relaxed);
+}
+
+#[cfg(feature = "events")]
+pub(crate) fn add_parse_error() {
+    PARSE_ERRORS.fetch_add(1, Ordering::Relaxed);
+}
+
+#[cfg(feature = "events")]
+pub(crate) fn set_event_queue_len(len: usize) {
+    EVENT_QUEUE_LEN.store(len, Ordering::Relaxed);
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{style::Print, QueueableCommand};
+
+    use super::metrics;
+
+    #[test]
+    fn test_commands_are_counted() {
+        let before = metrics();
+        Vec::new()
+            .queue(Print("foo"))
+            .unwrap()
+            .queue(Print(""))
+            .unwrap();
+        let after = metrics();
+
+        // other tests write commands at the same time
+        assert!(after.commands_executed >= before.commands_executed + 2);
+        assert!(after.bytes_written >= before.bytes_written + 3);
+    }
+}
@@ -0,0 +1,242 @@
+//! # Notification
+//!
+//! The `notification` module provides commands to show desktop notifications through the
+//! terminal.
+//!
+//! [`Notify`](struct.Notify.html) writes the basic `OSC 9` notification, which only has a
+//! text and is understood by iTerm2, WezTerm, Windows Terminal and others.
+//! [`Notification`](struct.Notification.html) writes the `OSC 99` protocol of kitty, which
+//! adds a title, a body, an urgency and an identifier to close the notification again with
+//! [`CloseNotification`](struct.CloseNotification.html). With the `events` feature, the
+//! terminal can report when the user activates or closes it, with
+//! [`Event::NotificationActivated`](../event/enum.Event.html#variant.NotificationActivated) and
+//! [`Event::NotificationClosed`](../event/enum.Event.html#variant.NotificationClosed).
+//!
+//! ```no_run
+//! use std::io;
+//! use crossterm::{
+//!     execute,
+//!     notification::{Notification, Urgency},
+//! };
+//!
+//! fn main() -> io::Result<()> {
+//!     let notification = Notification::new("Build finished")
+//!         .with_body("3 warnings")
+//!         .with_id(1)
+//!         .with_urgency(Urgency::Low)
+//!         .with_activation_report();
+//!
+//!     execute!(io::stdout(), notification)
+//! }
+//! ```
+//!
+//! Terminals which don't understand the sequences ignore them.
+
+use std::fmt;
+
+use crate::{base64, Command};
+
+/// A command that shows a notification with `text` with `OSC 9`.
+///
+/// # Notes
+///
+/// Commands must be executed/queued for execution otherwise they do nothing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Notify<T: fmt::Display>(pub T);
+
+impl<T: fmt::Display> Command for Notify<T> {
+    fn write_ansi(&self, f: &mut impl fmt::Write) -> fmt::Result {
+        write!(f, "\x1B]9;{}\x1B\\", self.0)
+    }
+
+    #[cfg(windows)]
+    fn execute_winapi(&self) -> std::io::Result<()> {
+        Err(crate::Error::UnsupportedCapability(
+            "Notifications are not supported by the legacy Windows API.",
+        )
+        .into())
+    }
+}
+
+/// How urgent a [`Notification`](struct.Notification.html) is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Urgency {
+    /// The notification may be shown less prominently.
+    Low,
+    /// The default urgency.
+    Normal,
+    /// The notification should be shown even if the user doesn't want to be disturbed.
+    Critical,
+}
+
+impl Default for Urgency {
+    fn default() -> Self {
+        Urgency::Normal
+    }
+}
+
+/// A command that shows a notification with kitty's `OSC 99` protocol.
+///
+/// # Notes
+///
+/// Commands must be executed/queued for execution otherwise they do nothing.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Notification {
+    /// The identifier, to close the notification and to tell which one an event is about.
+    pub id: Option<u32>,
+    /// The title.
+    pub title: String,
+    /// The body, shown below the title.
+    pub body: Option<String>,
+    /// How urgent the notification is.
+    pub urgency: Urgency,
+    /// The terminal reports an
+    /// [`Event::NotificationActivated`](../event/enum.Event.html#variant.NotificationActivated)
+    /// when the user clicks the notification, which needs an `id`.
+    pub report_activation: bool,
+    /// The terminal reports an
+    /// [`Event::NotificationClosed`](../event/enum.Event.html#variant.NotificationClosed) when
+    /// the notification is closed, which needs an `id`.
+    pub report_close: bool,
+}
+
+impl Notification {
+    /// Creates a notification with a title and the default urgency.
+    pub fn new(title: impl Into<String>) -> Notification {
+        Notification {
+            id: None,
+            title: title.into(),
+            body: None,
+            urgency: Urgency::default(),
+            report_activation: false,
+            report_close: false,
+        }
+    }
+
+    /// Sets the identifier.
+    pub fn with_id(mut self, id: u32) -> Notification {
+        self.id = Some(id);
+        self
+    }
+
+    /// Sets the body.
+    pub fn with_body(mut self, body: impl Into<String>) -> Notification {
+        self.body = Some(body.into());
+        self
+    }
+
+    /// Sets the urgency.
+    pub fn with_urgency(mut self, urgency: Urgency) -> Notification {
+        self.urgency = urgency;
+        self
+    }
+
+    /// Asks the terminal to report when the user clicks the notification.
+    pub fn with_activation_report(mut self) -> Notification {
+        self.report_activation = true;
+        self
+    }
+
+    /// Asks the terminal to report when the notification is closed.
+    pub fn with_close_report(mut self) -> Notification {
+        self.report_close = true;
+        self
+    }
+}
+
+impl Command for Notification {
+    fn write_ansi(&self, f: &mut impl fmt::Write) -> fmt::Result {
+        // The payloads are base64 encoded (`e=1`), so they can contain any character. The title
+        // isn't the last chunk (`d=0`) if a body follows.
+        f.write_str("\x1B]99;")?;
+        if let Some(id) = self.id {
+            write!(f, "i={}:", id)?;
+        }
+        if self.body.is_some() {
+            f.write_str("d=0:")?;
+        }
+        match self.urgency {
+            Urgency::Low => f.write_str("u=0:")?,
+            Urgency::Normal => {}
+            Urgency::Critical => f.write_str("u=2:")?,
+        }
+        if self.report_activation {
+            f.write_str("a=focus,report:")?;
+        }
+        if self.report_close {
+            f.write_str("c=1:")?;
+        }
+        write!(f, "e=1;{}\x1B\\", base64::encode(self.title.as_bytes()))?;
+
+        if let Some(body) = &self.body {
+            f.write_str("\x1B]99;")?;
+            if let Some(id) = self.id {
+                write!(f, "i={}:", id)?;
+            }
+            write!(f, "p=body:e=1;{}\x1B\\", base64::encode(body.as_bytes()))?;
+        }
+        Ok(())
+    }
+
+    #[cfg(windows)]
+    fn execute_winapi(&self) -> std::io::Result<()> {
+        Err(crate::Error::UnsupportedCapability(
+            "Notifications are not supported by the legacy Windows API.",
+        )
+        .into())
+    }
+}
+
+/// A command that closes the [`Notification`](struct.Notification.html) with the identifier.
+///
+/// # Notes
+///
+/// Commands must be executed/queued for execution otherwise they do nothing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct CloseNotification(pub u32);
+
+impl Command for CloseNotification {
+    fn write_ansi(&self, f: &mut impl fmt::Write) -> fmt::Result {
+        write!(f, "\x1B]99;i={}:p=close;\x1B\\", self.0)
+    }
+
+    #[cfg(windows)]
+    fn execute_winapi(&self) -> std::io::Result<()> {
+        Err(crate::Error::UnsupportedCapability(
+            "Notifications are not supported by the legacy Windows API.",
+        )
+        .into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Command;
+
+    use super::{CloseNotification, Notification, Notify, Urgency};
+
+    #[test]
+    fn test_notifications() {
+        assert_eq!(Notify("done").ansi_string(), "\x1B]9;done\x1B\\");
+
+        assert_eq!(
+            Notification::new("foo").ansi_string(),
+            "\x1B]99;e=1;Zm9v\x1B\\"
+        );
+        assert_eq!(
+            Notification::new("foo")
+                .with_id(7)
+                .with_body("foobar")
+                .with_urgency(Urgency::Critical)
+                .with_activation_report()
+                .with_close_report()
+                .ansi_string(),
+            "\x1B]99;i=7:d=0:u=2:a=focus,report:c=1:e=1;Zm9v\x1B\\\
+             \x1B]99;i=7:p=body:e=1;Zm9vYmFy\x1B\\"
+        );
+        assert_eq!(
+            CloseNotification(7).ansi_string(),
+            "\x1B]99;i=7:p=close;\x1B\\"
+        );
+    }
+}
@@ -0,0 +1,406 @@
+//! # Pseudo-terminal
+//!
+//! The `pty` module runs a process under a pseudo-terminal, so applications built with crossterm
+//! can be tested against a real tty, also in CI where the tests themselves don't run in a
+//! terminal. It's only available on Unix with the `pty` feature.
+//!
+//! [`run_in_pty`](fn.run_in_pty.html) runs the current test again under a pseudo-terminal.
+//! The child half of the test talks to the terminal through stdin and stdout like any other
+//! application, while the parent half reads what it wrote and types input.
+//!
+//! ```no_run
+//! use std::{io::Write, time::Duration};
+//! use crossterm::{pty, terminal};
+//!
+//! #[test]
+//! fn test_size() {
+//!     pty::run_in_pty(
+//!         "tests::test_size",
+//!         || {
+//!             let (columns, rows) = terminal::size().unwrap();
+//!             println!("size {}x{}", columns, rows);
+//!         },
+//!         |pty| {
+//!             pty.read_until(b"size 80x24", Duration::from_secs(10)).unwrap();
+//!         },
+//!     )
+//!     .unwrap();
+//! }
+//! ```
+//!
+//! Other programs can be run with [`PtyChild::spawn`](struct.PtyChild.html#method.spawn).
+
+use std::{
+    env,
+    ffi::CStr,
+    fs::{File, OpenOptions},
+    io::{self, Read, Write},
+    os::unix::{
+        fs::OpenOptionsExt,
+        io::{AsRawFd, FromRawFd},
+        process::CommandExt,
+    },
+    process::{self, Child, ExitStatus, Stdio},
+    time::{Duration, Instant},
+};
+
+use libc::winsize;
+use parking_lot::Mutex;
+
+/// The environment variable which tells the current test binary it runs as the child.
+pub(crate) const CHILD_ENV: &str = "CROSSTERM_PTY_CHILD";
+
+/// The size of a newly opened pseudo-terminal `(columns, rows)`.
+pub const DEFAULT_SIZE: (u16, u16) = (80, 24);
+
+// `ptsname` returns a static buffer
+static PTSNAME: Mutex<()> = parking_lot::const_mutex(());
+
+/// Returns whether the current process is the child of [`run_in_pty`](fn.run_in_pty.html) or
+/// [`PtyChild::spawn_test`](struct.PtyChild.html#method.spawn_test).
+pub fn is_child() -> bool {
+    env::var_os(CHILD_ENV).is_some()
+}
+
+/// Runs the test `test_name` of the current test binary under a pseudo-terminal.
+///
+/// In the parent, the test binary is started again for the test `test_name`, which has to be
+/// the full name as printed by the test harness, e.g. `module::tests::test_name`. Then `parent`
+/// is called with the pseudo-terminal and afterwards this waits for the child to finish.
+///
+/// In the child, `child` is called instead, with stdin, stdout and stderr connected to the
+/// pseudo-terminal.
+///
+/// # Errors
+///
+/// Returns an error if the pseudo-terminal can't be opened or if the child test failed.
+pub fn run_in_pty<C, P>(test_name: &str, child: C, parent: P) -> io::Result<()>
+where
+    C: FnOnce(),
+    P: FnOnce(&mut PtyChild),
+{
+    if is_child() {
+        child();
+        return Ok(());
+    }
+
+    let mut pty = PtyChild::spawn_test(test_name)?;
+    parent(&mut pty);
+
+    let status = pty.wait()?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(io::Error::new(
+            io::ErrorKind::Other,
+            format!("child test {} failed: {}", test_name, status),
+        ))
+    }
+}
+
+/// A child process running under a pseudo-terminal.
+///
+/// Reading returns what the child wrote to the terminal, writing types input.
+#[derive(Debug)]
+pub struct PtyChild {
+    master: File,
+    child: Child,
+    // what `read_until` read behind the needle
+    pending: Vec<u8>,
+}
+
+impl PtyChild {
+    /// Spawns `command` under a new pseudo-terminal of [`DEFAULT_SIZE`](constant.DEFAULT_SIZE.html).
+    ///
+    /// The pseudo-terminal becomes the controlling terminal as well as stdin, stdout and stderr
+    /// of the child.
+    pub fn spawn(mut command: process::Command) -> io::Result<PtyChild> {
+        let (master, slave) = open_pty()?;
+
+        command
+            .stdin(Stdio::from(slave.try_clone()?))
+            .stdout(Stdio::from(slave.try_clone()?))
+            .stderr(Stdio::from(slave));
+
+        // Safety: only async-signal-safe functions are called between fork and exec.
+        unsafe {
+            command.pre_exec(|| {
+                if libc::setsid() == -1 {
+                    return Err(io::Error::last_os_error());
+                }
+                #[allow(clippy::useless_conversion)]
+                if libc::ioctl(0, libc::TIOCSCTTY.into(), 0) == -1 {
+                    return Err(io::Error::last_os_error());
+                }
+                Ok(())
+            });
+        }
+
+        let child = command.spawn()?;
+        Ok(PtyChild {
+            master,
+            child,
+            pending: Vec::new(),
+        })
+    }
+
+    /// Spawns the test `test_name` of the current test binary under a new pseudo-terminal,
+    /// see [`run_in_pty`](fn.run_in_pty.html).
+    pub fn spawn_test(test_name: &str) -> io::Result<PtyChild> {
+        let mut command = process::Command::new(env::current_exe()?);
+        command
+            .args([test_name, "--exact", "--nocapture", "--test-threads=1"])
+            .env(CHILD_ENV, test_name);
+        PtyChild::spawn(command)
+    }
+
+    /// Returns the process id of the child.
+    pub fn id(&self) -> u32 {
+        self.child.id()
+    }
+
+    /// Resizes the pseudo-terminal, which sends `SIGWINCH` to the child.
+    pub fn resize(&self, columns: u16, rows: u16) -> io::Result<()> {
+        set_size(&self.master, columns, rows)
+    }
+
+    /// Reads the output of the child until it contains `needle` and returns everything up to
+    /// the end of it. What was read behind the needle is returned by the next read.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error of kind `TimedOut` if `needle` wasn't written within `timeout`, or of
+    /// kind `UnexpectedEof` if the child closed the terminal before. The error message contains
+    /// the output read so far.
+    pub fn read_until(&mut self, needle: &[u8], timeout: Duration) -> io::Result<Vec<u8>> {
+        let deadline = Instant::now() + timeout;
+        let mut output = std::mem::take(&mut self.pending);
+        let mut buffer = [0u8; 1024];
+
+        loop {
+            if let Some(end) = find_end(&output, needle) {
+                self.pending = output.split_off(end);
+                return Ok(output);
+            }
+
+            let left = deadline.saturating_duration_since(Instant::now());
+            if left.is_zero() || !self.poll_read(left)? {
+                return Err(read_error(io::ErrorKind::TimedOut, &output));
+            }
+
+            match self.master.read(&mut buffer) {
+                Ok(0) => return Err(read_error(io::ErrorKind::UnexpectedEof, &output)),
+                Ok(count) => output.extend_from_slice(&buffer[..count]),
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+                // Linux reports EIO once the child closed its side
+                Err(e) if e.raw_os_error() == Some(libc::EIO) => {
+                    return Err(read_error(io::ErrorKind::UnexpectedEof, &output))
+                }
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    /// Waits for the child to exit.
+    ///
+    /// The child blocks if it writes more than the pseudo-terminal buffers while nobody reads,
+    /// so read its output first.
+    pub fn wait(&mut self) -> io::Result<ExitStatus> {
+        self.child.wait()
+    }
+
+    /// Kills the child and waits for it to exit.
+    pub fn kill(&mut self) -> io::Result<ExitStatus> {
+        if let Some(status) = self.child.try_wait()? {
+            return Ok(status);
+        }
+        self.child.kill()?;
+        self.child.wait()
+    }
+
+    fn poll_read(&self, timeout: Duration) -> io::Result<bool> {
+        let mut fd = libc::pollfd {
+            fd: self.master.as_raw_fd(),
+            events: libc::POLLIN,
+            revents: 0,
+        };
+        let timeout = timeout.as_millis().min(i32::MAX as u128) as i32;
+
+        match unsafe { libc::poll(&mut fd, 1, timeout) } {
+            -1 => {
+                let e = io::Error::last_os_error();
+                if e.kind() == io::ErrorKind::Interrupted {
+                    Ok(true)
+                } else {
+                    Err(e)
+                }
+            }
+            0 => Ok(false),
+            _ => Ok(true),
+        }
+    }
+}
+
+impl Read for PtyChild {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.pending.is_empty() {
+            return self.master.read(buf);
+        }
+        let count = buf.len().min(self.pending.len());
+        buf[..count].copy_from_slice(&self.pending[..count]);
+        self.pending.drain(..count);
+        Ok(count)
+    }
+}
+
+impl Write for PtyChild {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.master.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.master.flush()
+    }
+}
+
+impl Drop for PtyChild {
+    fn drop(&mut self) {
+        let _ = self.kill();
+    }
+}
+
+/// Opens a new pseudo-terminal and returns its master and slave side.
+pub(crate) fn open_pty() -> io::Result<(File, File)> {
+    let fd = unsafe { libc::posix_openpt(libc::O_RDWR | libc::O_NOCTTY | libc::O_CLOEXEC) };
+    if fd == -1 {
+        return Err(io::Error::last_os_error());
+    }
+    let master = unsafe { File::from_raw_fd(fd) };
+
+    if unsafe { libc::grantpt(fd) } == -1 || unsafe { libc::unlockpt(fd) } == -1 {
+        return Err(io::Error::last_os_error());
+    }
+
+    let path = {
+        let _guard = PTSNAME.lock();
+        let name = unsafe { libc::ptsname(fd) };
+        if name.is_null() {
+            return Err(io::Error::last_os_error());
+        }
+        unsafe { CStr::from_ptr(name) }
+            .to_string_lossy()
+            .into_owned()
+    };
+
+    let slave = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .custom_flags(libc::O_NOCTTY)
+        .open(path)?;
+
+    set_size(&master, DEFAULT_SIZE.0, DEFAULT_SIZE.1)?;
+
+    Ok((master, slave))
+}
+
+#[allow(clippy::useless_conversion)]
+fn set_size(master: &File, columns: u16, rows: u16) -> io::Result<()> {
+    let size = winsize {
+        ws_row: rows,
+        ws_col: columns,
+        ws_xpixel: 0,
+        ws_ypixel: 0,
+    };
+
+    if unsafe { libc::ioctl(master.as_raw_fd(), libc::TIOCSWINSZ.into(), &size) } == -1 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+/// Returns the index behind the first occurrence of `needle` in `haystack`.
+fn find_end(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    if needle.is_empty() {
+        return Some(0);
+    }
+    haystack
+        .windows(needle.len())
+        .position(|window| window == needle)
+        .map(|start| start + needle.len())
+}
+
+fn read_error(kind: io::ErrorKind, output: &[u8]) -> io::Error {
+    io::Error::new(
+        kind,
+        format!(
+            "pseudo-terminal output so far: {:?}",
+            String::from_utf8_lossy(output)
+        ),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        io::{Read, Write},
+        process,
+        time::Duration,
+    };
+
+    use crate::terminal;
+
+    use super::{open_pty, run_in_pty, PtyChild};
+
+    const TIMEOUT: Duration = Duration::from_secs(10);
+
+    #[test]
+    fn test_read_until_keeps_the_rest() {
+        // Pseudo-terminals might not be available in the sandbox
+        if open_pty().is_err() {
+            return;
+        }
+
+        let mut command = process::Command::new("sh");
+        command.args(["-c", "printf 'one two three'"]);
+        let mut pty = PtyChild::spawn(command).unwrap();
+
+        // everything is likely read at once
+        assert_eq!(pty.read_until(b"one", TIMEOUT).unwrap(), b"one");
+        assert_eq!(pty.read_until(b"two", TIMEOUT).unwrap(), b" two");
+        let mut rest = [0; 3];
+        pty.read_exact(&mut rest).unwrap();
+        assert_eq!(&rest, b" th");
+        assert_eq!(pty.read_until(b"ee", TIMEOUT).unwrap(), b"ree");
+    }
+
+    #[test]
+    fn test_run_in_pty() {
+        // Pseudo-terminals might not be available in the sandbox
+        if open_pty().is_err() {
+            return;
+        }
+
+        run_in_pty(
+            "pty::tests::test_run_in_pty",
+            || {
+                let (columns, rows) = terminal::size().unwrap();
+                println!("size {}x{}", columns, rows);
+
+                terminal::enable_raw_mode().unwrap();
+                println!("ready");
+
+                let mut byte = [0u8];
+                std::io::stdin().read_exact(&mut byte).unwrap();
+                terminal::disable_raw_mode().unwrap();
+                println!("got {}", byte[0] as char);
+            },
+            |pty| {
+                pty.read_until(b"size 80x24", TIMEOUT).unwrap();
+                pty.read_until(b"ready", TIMEOUT).unwrap();
+                pty.write_all(b"x").unwrap();
+                pty.read_until(b"got x", TIMEOUT).unwrap();
+            },
+        )
+        .unwrap();
+    }
+}
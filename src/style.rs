@@ -111,28 +111,46 @@
 //! );
 //! ```
 
-use std::{
-    env,
-    fmt::{self, Display},
-};
+use std::fmt::{self, Display};
 
 use crate::command::execute_fmt;
 use crate::{csi, impl_display, Command};
 
+#[doc(hidden)]
+pub use self::colored_writer::ColorScope;
+pub(crate) use self::types::set_color_override;
 pub use self::{
+    attribute_fallbacks::{attribute_fallbacks, set_attribute_fallbacks, AttributeFallbacks},
     attributes::Attributes,
+    color_level::{color_level, supports_color, ColorLevel, Stream},
+    colored_writer::ColoredWriter,
     content_style::ContentStyle,
+    hyperlink::{hyperlink_fallback, set_hyperlink_fallback, HyperlinkFallback, PrintHyperlink},
+    items::{items, Items},
+    plain::NoStyle,
+    sgr_batch::SgrBatchWriter,
     styled_content::StyledContent,
+    styled_line::StyledLine,
     stylize::Stylize,
-    types::{Attribute, Color, Colored, Colors},
+    types::{Attribute, Color, Colored, Colors, UnderlineStyle},
+    width::{char_width, display_width},
 };
 
+mod attribute_fallbacks;
 mod attributes;
+mod color_level;
+mod colored_writer;
 mod content_style;
+mod hyperlink;
+mod items;
+mod plain;
+mod sgr_batch;
 mod styled_content;
+mod styled_line;
 mod stylize;
 mod sys;
 mod types;
+mod width;
 
 /// Creates a `StyledContent`.
 ///
@@ -161,9 +179,11 @@ pub fn style<D: Display>(val: D) -> StyledContent<D> {
 ///
 /// This does not always provide a good result.
 pub fn available_color_count() -> u16 {
-    env::var("TERM")
-        .map(|x| if x.contains("256color") { 256 } else { 8 })
-        .unwrap_or(8)
+    if crate::capabilities::from_env().ansi256 {
+        256
+    } else {
+        8
+    }
 }
 
 /// Forces colored output on or off globally, overriding NO_COLOR.
@@ -193,11 +213,25 @@ pub struct SetForegroundColor(pub Color);
 
 impl Command for SetForegroundColor {
     fn write_ansi(&self, f: &mut impl fmt::Write) -> fmt::Result {
+        if crate::accessibility::screen_reader_mode() {
+            return Ok(());
+        }
+        #[cfg(feature = "terminfo")]
+        if let Some(result) = crate::terminfo::write_color(f, "setaf", self.0) {
+            return result;
+        }
         write!(f, csi!("{}m"), Colored::ForegroundColor(self.0))
     }
 
+    fn size_hint(&self) -> usize {
+        19
+    }
+
     #[cfg(windows)]
     fn execute_winapi(&self) -> std::io::Result<()> {
+        if crate::accessibility::screen_reader_mode() {
+            return Ok(());
+        }
         sys::windows::set_foreground_color(self.0)
     }
 }
@@ -217,11 +251,25 @@ pub struct SetBackgroundColor(pub Color);
 
 impl Command for SetBackgroundColor {
     fn write_ansi(&self, f: &mut impl fmt::Write) -> fmt::Result {
+        if crate::accessibility::screen_reader_mode() {
+            return Ok(());
+        }
+        #[cfg(feature = "terminfo")]
+        if let Some(result) = crate::terminfo::write_color(f, "setab", self.0) {
+            return result;
+        }
         write!(f, csi!("{}m"), Colored::BackgroundColor(self.0))
     }
 
+    fn size_hint(&self) -> usize {
+        19
+    }
+
     #[cfg(windows)]
     fn execute_winapi(&self) -> std::io::Result<()> {
+        if crate::accessibility::screen_reader_mode() {
+            return Ok(());
+        }
         sys::windows::set_background_color(self.0)
     }
 }
@@ -230,6 +278,9 @@ impl Command for SetBackgroundColor {
 ///
 /// See [`Color`](enum.Color.html) for more info.
 ///
+/// Does nothing if the [`Capabilities`](../capabilities/struct.Capabilities.html) say the terminal
+/// doesn't support underline colors.
+///
 /// [`SetColors`](struct.SetColors.html) can also be used to set both the foreground and background
 /// color with one command.
 ///
@@ -241,15 +292,24 @@ pub struct SetUnderlineColor(pub Color);
 
 impl Command for SetUnderlineColor {
     fn write_ansi(&self, f: &mut impl fmt::Write) -> fmt::Result {
+        if !crate::capabilities::query(|capabilities| capabilities.underline_color)
+            || crate::accessibility::screen_reader_mode()
+        {
+            return Ok(());
+        }
         write!(f, csi!("{}m"), Colored::UnderlineColor(self.0))
     }
 
+    fn size_hint(&self) -> usize {
+        19
+    }
+
     #[cfg(windows)]
     fn execute_winapi(&self) -> std::io::Result<()> {
-        Err(std::io::Error::new(
-            std::io::ErrorKind::Other,
-            "SetUnderlineColor not supported by winapi.",
-        ))
+        Err(
+            crate::Error::UnsupportedCapability("SetUnderlineColor not supported by winapi.")
+                .into(),
+        )
     }
 }
 
@@ -279,6 +339,9 @@ pub struct SetColors(pub Colors);
 
 impl Command for SetColors {
     fn write_ansi(&self, f: &mut impl fmt::Write) -> fmt::Result {
+        if crate::accessibility::screen_reader_mode() {
+            return Ok(());
+        }
         // Writing both foreground and background colors in one command resulted in about 20% more
         // FPS (20 to 24 fps) on a fullscreen (171x51) app that writes every cell with a different
         // foreground and background color, compared to separately using the SetForegroundColor and
@@ -299,8 +362,15 @@ impl Command for SetColors {
         }
     }
 
+    fn size_hint(&self) -> usize {
+        36
+    }
+
     #[cfg(windows)]
     fn execute_winapi(&self) -> std::io::Result<()> {
+        if crate::accessibility::screen_reader_mode() {
+            return Ok(());
+        }
         if let Some(color) = self.0.foreground {
             sys::windows::set_foreground_color(color)?;
         }
@@ -323,7 +393,38 @@ pub struct SetAttribute(pub Attribute);
 
 impl Command for SetAttribute {
     fn write_ansi(&self, f: &mut impl fmt::Write) -> fmt::Result {
-        write!(f, csi!("{}m"), self.0.sgr())
+        attribute_fallbacks::write_attribute(f, self.0)
+    }
+
+    fn size_hint(&self) -> usize {
+        6
+    }
+
+    #[cfg(windows)]
+    fn execute_winapi(&self) -> std::io::Result<()> {
+        // attributes are not supported by WinAPI.
+        Ok(())
+    }
+}
+
+/// A command that sets the style of the underline, replacing the previous one.
+///
+/// See [`UnderlineStyle`](enum.UnderlineStyle.html) for more info. Turn the underline off with
+/// [`Attribute::NoUnderline`](enum.Attribute.html#variant.NoUnderline).
+///
+/// # Notes
+///
+/// Commands must be executed/queued for execution otherwise they do nothing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SetUnderlineStyle(pub UnderlineStyle);
+
+impl Command for SetUnderlineStyle {
+    fn write_ansi(&self, f: &mut impl fmt::Write) -> fmt::Result {
+        attribute_fallbacks::write_attribute(f, self.0.attribute())
+    }
+
+    fn size_hint(&self) -> usize {
+        6
     }
 
     #[cfg(windows)]
@@ -382,6 +483,9 @@ impl Command for SetStyle {
         if !self.0.attributes.is_empty() {
             execute_fmt(f, SetAttributes(self.0.attributes)).map_err(|_| fmt::Error)?;
         }
+        if let Some(underline) = self.0.underline {
+            execute_fmt(f, SetUnderlineStyle(underline)).map_err(|_| fmt::Error)?;
+        }
 
         Ok(())
     }
@@ -432,6 +536,10 @@ impl<D: Display> Command for PrintStyledContent<D> {
             execute_fmt(f, SetAttributes(style.attributes)).map_err(|_| fmt::Error)?;
             reset = true;
         }
+        if let Some(underline) = style.underline {
+            execute_fmt(f, SetUnderlineStyle(underline)).map_err(|_| fmt::Error)?;
+            reset = true;
+        }
 
         write!(f, "{}", self.0.content())?;
 
@@ -472,6 +580,10 @@ impl Command for ResetColor {
         f.write_str(csi!("0m"))
     }
 
+    fn size_hint(&self) -> usize {
+        4
+    }
+
     #[cfg(windows)]
     fn execute_winapi(&self) -> std::io::Result<()> {
         sys::windows::reset()
@@ -506,10 +618,56 @@ impl<T: Display> Display for Print<T> {
     }
 }
 
+/// A command that prints a large text, e.g. encoded image data or a long log, in chunks.
+///
+/// Unlike [`Print`](struct.Print.html), the text is handed to the writer in chunks of a few
+/// kilobytes, which keeps large payloads out of the writer's buffer. Like every command, partial
+/// writes are continued, and unlike other commands, a non-blocking writer which returns
+/// `WouldBlock` is waited for by default instead of failing halfway through the text, see
+/// [`set_retry_policy`](../fn.set_retry_policy.html). See also
+/// [`write_large`](../fn.write_large.html) for payloads which aren't text.
+///
+/// # Notes
+///
+/// Commands must be executed/queued for execution otherwise they do nothing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PrintLarge<T: AsRef<str>>(pub T);
+
+impl<T: AsRef<str>> Command for PrintLarge<T> {
+    fn write_ansi(&self, f: &mut impl fmt::Write) -> fmt::Result {
+        let mut text = self.0.as_ref();
+        while !text.is_empty() {
+            let mut end = text.len().min(crate::command::CHUNK_SIZE);
+            while !text.is_char_boundary(end) {
+                end -= 1;
+            }
+            let (chunk, rest) = text.split_at(end);
+            crate::command::writing_large(|| f.write_str(chunk))?;
+            text = rest;
+        }
+        Ok(())
+    }
+
+    fn size_hint(&self) -> usize {
+        self.0.as_ref().len()
+    }
+
+    #[cfg(windows)]
+    fn execute_winapi(&self) -> std::io::Result<()> {
+        panic!("tried to execute PrintLarge command using WinAPI, use ANSI instead");
+    }
+
+    #[cfg(windows)]
+    fn is_ansi_code_supported(&self) -> bool {
+        true
+    }
+}
+
 impl_display!(for SetForegroundColor);
 impl_display!(for SetBackgroundColor);
 impl_display!(for SetColors);
 impl_display!(for SetAttribute);
+impl_display!(for SetUnderlineStyle);
 impl_display!(for PrintStyledContent<String>);
 impl_display!(for PrintStyledContent<&'static str>);
 impl_display!(for ResetColor);
@@ -0,0 +1,252 @@
+use std::fmt;
+
+use parking_lot::RwLock;
+
+use crate::{capabilities, csi};
+
+use super::{Attribute, Attributes};
+
+/// What [`SetAttribute`](struct.SetAttribute.html) writes instead of an attribute the terminal
+/// doesn't support, according to the
+/// [`attributes`](../capabilities/struct.Capabilities.html#structfield.attributes) of the
+/// installed capabilities.
+///
+/// An attribute without a fallback is dropped. If the fallback isn't supported either, its own
+/// fallback is tried. Turning an unsupported attribute off, e.g. with `NoItalic`, turns its
+/// fallback off instead, which turns off the fallback if it was set by other means as well.
+///
+/// The [`Default`](#impl-Default) table makes themes look reasonable on basic terminals:
+///
+/// | Attribute | Fallback |
+/// | :-- | :-- |
+/// | `Italic` | `Underlined` |
+/// | `DoubleUnderlined`, `Undercurled`, `Underdotted`, `Underdashed` | `Underlined` |
+/// | `Fraktur` | `Italic` |
+/// | `RapidBlink` | `SlowBlink` |
+/// | `Dim`, `SlowBlink`, `Hidden`, `CrossedOut`, `Framed`, `Encircled`, `OverLined` | none |
+///
+/// # Examples
+///
+/// ```no_run
+/// use crossterm::style::{self, Attribute, AttributeFallbacks};
+///
+/// // show italic text in reverse video on terminals without italics
+/// style::set_attribute_fallbacks(
+///     AttributeFallbacks::default().with(Attribute::Italic, Attribute::Reverse),
+/// );
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct AttributeFallbacks {
+    fallbacks: Vec<(Attribute, Attribute)>,
+}
+
+impl AttributeFallbacks {
+    /// Creates a table without fallbacks, unsupported attributes are dropped.
+    pub fn new() -> AttributeFallbacks {
+        AttributeFallbacks {
+            fallbacks: Vec::new(),
+        }
+    }
+
+    /// Writes `fallback` instead of `attribute`.
+    pub fn with(mut self, attribute: Attribute, fallback: Attribute) -> AttributeFallbacks {
+        self = self.without(attribute);
+        self.fallbacks.push((attribute, fallback));
+        self
+    }
+
+    /// Drops `attribute` if it isn't supported.
+    pub fn without(mut self, attribute: Attribute) -> AttributeFallbacks {
+        self.fallbacks.retain(|(from, _)| *from != attribute);
+        self
+    }
+
+    /// Returns the fallback of `attribute`.
+    pub fn get(&self, attribute: Attribute) -> Option<Attribute> {
+        self.fallbacks
+            .iter()
+            .find(|(from, _)| *from == attribute)
+            .map(|(_, to)| *to)
+    }
+
+    /// Returns the attribute to write for `attribute` if the terminal supports `supported`,
+    /// `None` if nothing should be written.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossterm::style::{Attribute, AttributeFallbacks, Attributes};
+    ///
+    /// let fallbacks = AttributeFallbacks::default();
+    /// let basic = Attributes::from(&[Attribute::Bold, Attribute::Underlined][..]);
+    ///
+    /// assert_eq!(fallbacks.resolve(Attribute::Bold, basic), Some(Attribute::Bold));
+    /// assert_eq!(fallbacks.resolve(Attribute::Undercurled, basic), Some(Attribute::Underlined));
+    /// assert_eq!(fallbacks.resolve(Attribute::NoItalic, basic), Some(Attribute::NoUnderline));
+    /// assert_eq!(fallbacks.resolve(Attribute::CrossedOut, basic), None);
+    /// ```
+    pub fn resolve(&self, attribute: Attribute, supported: Attributes) -> Option<Attribute> {
+        if attribute == Attribute::Reset {
+            return Some(attribute);
+        }
+        if turn_off(attribute).is_some() {
+            return self.resolve_on(attribute, supported);
+        }
+
+        // an attribute which turns others off is written if one of them is supported
+        let turned_off =
+            || Attribute::iterator().filter(move |&on| turn_off(on) == Some(attribute));
+        if turned_off().any(|on| supported.has(on)) {
+            return Some(attribute);
+        }
+        turned_off()
+            .find_map(|on| self.resolve_on(on, supported))
+            .and_then(turn_off)
+    }
+
+    fn resolve_on(&self, mut attribute: Attribute, supported: Attributes) -> Option<Attribute> {
+        // the length of the table bounds the chain, in case it has a cycle
+        for _ in 0..=self.fallbacks.len() {
+            if supported.has(attribute) {
+                return Some(attribute);
+            }
+            attribute = self.get(attribute)?;
+        }
+        None
+    }
+}
+
+impl Default for AttributeFallbacks {
+    fn default() -> Self {
+        AttributeFallbacks::new()
+            .with(Attribute::Italic, Attribute::Underlined)
+            .with(Attribute::DoubleUnderlined, Attribute::Underlined)
+            .with(Attribute::Undercurled, Attribute::Underlined)
+            .with(Attribute::Underdotted, Attribute::Underlined)
+            .with(Attribute::Underdashed, Attribute::Underlined)
+            .with(Attribute::Fraktur, Attribute::Italic)
+            .with(Attribute::RapidBlink, Attribute::SlowBlink)
+    }
+}
+
+/// Returns the attribute which turns `attribute` off, `None` if it doesn't turn anything on.
+fn turn_off(attribute: Attribute) -> Option<Attribute> {
+    let off = match attribute {
+        Attribute::Bold | Attribute::Dim => Attribute::NormalIntensity,
+        Attribute::Italic | Attribute::Fraktur => Attribute::NoItalic,
+        Attribute::Underlined
+        | Attribute::DoubleUnderlined
+        | Attribute::Undercurled
+        | Attribute::Underdotted
+        | Attribute::Underdashed => Attribute::NoUnderline,
+        Attribute::SlowBlink | Attribute::RapidBlink => Attribute::NoBlink,
+        Attribute::Reverse => Attribute::NoReverse,
+        Attribute::Hidden => Attribute::NoHidden,
+        Attribute::CrossedOut => Attribute::NotCrossedOut,
+        Attribute::Framed | Attribute::Encircled => Attribute::NotFramedOrEncircled,
+        Attribute::OverLined => Attribute::NotOverLined,
+        _ => return None,
+    };
+    Some(off)
+}
+
+static ATTRIBUTE_FALLBACKS: RwLock<Option<AttributeFallbacks>> = parking_lot::const_rwlock(None);
+
+/// Installs the fallbacks [`SetAttribute`](struct.SetAttribute.html) consults from now on.
+pub fn set_attribute_fallbacks(fallbacks: AttributeFallbacks) {
+    *ATTRIBUTE_FALLBACKS.write() = Some(fallbacks);
+}
+
+/// Returns the fallbacks [`SetAttribute`](struct.SetAttribute.html) currently consults, the
+/// [default](struct.AttributeFallbacks.html#impl-Default) ones if none were installed.
+pub fn attribute_fallbacks() -> AttributeFallbacks {
+    ATTRIBUTE_FALLBACKS.read().clone().unwrap_or_default()
+}
+
+/// Writes `attribute`, or its fallback if the terminal doesn't support it.
+pub(crate) fn write_attribute(f: &mut impl fmt::Write, attribute: Attribute) -> fmt::Result {
+    if crate::accessibility::screen_reader_mode()
+        && matches!(attribute, Attribute::SlowBlink | Attribute::RapidBlink)
+    {
+        return Ok(());
+    }
+
+    let (supported, sgr21_no_bold) =
+        capabilities::query(|capabilities| (capabilities.attributes, capabilities.sgr21_no_bold));
+    let attribute = if attribute == Attribute::NoBold && !sgr21_no_bold {
+        Attribute::NormalIntensity
+    } else {
+        attribute
+    };
+    let attribute = if supported == Attributes::all() {
+        attribute
+    } else {
+        let resolved = match &*ATTRIBUTE_FALLBACKS.read() {
+            Some(fallbacks) => fallbacks.resolve(attribute, supported),
+            None => AttributeFallbacks::default().resolve(attribute, supported),
+        };
+        match resolved {
+            Some(attribute) => attribute,
+            None => return Ok(()),
+        }
+    };
+
+    #[cfg(feature = "terminfo")]
+    if let Some(result) = crate::terminfo::write_attribute(f, attribute) {
+        return result;
+    }
+    write!(f, csi!("{}m"), attribute.sgr())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::style::{Attribute, Attributes};
+
+    use super::{write_attribute, AttributeFallbacks};
+
+    #[test]
+    fn test_no_bold_is_written_as_normal_intensity() {
+        let mut output = String::new();
+        write_attribute(&mut output, Attribute::NoBold).unwrap();
+        assert_eq!(output, "\x1B[22m");
+    }
+
+    #[test]
+    fn test_resolve_follows_fallbacks() {
+        let supported = Attributes::from(&[Attribute::Reverse, Attribute::Underlined][..]);
+        let fallbacks = AttributeFallbacks::default().with(Attribute::Italic, Attribute::Reverse);
+
+        assert_eq!(
+            fallbacks.resolve(Attribute::Fraktur, supported),
+            Some(Attribute::Reverse)
+        );
+        assert_eq!(
+            fallbacks.resolve(Attribute::NoItalic, supported),
+            Some(Attribute::NoReverse)
+        );
+        assert_eq!(
+            fallbacks.resolve(Attribute::NoUnderline, supported),
+            Some(Attribute::NoUnderline)
+        );
+        assert_eq!(fallbacks.resolve(Attribute::Bold, supported), None);
+        assert_eq!(
+            fallbacks.resolve(Attribute::NormalIntensity, supported),
+            None
+        );
+        assert_eq!(
+            fallbacks.resolve(Attribute::Reset, Attributes::none()),
+            Some(Attribute::Reset)
+        );
+    }
+
+    #[test]
+    fn test_resolve_stops_on_cycles() {
+        let fallbacks = AttributeFallbacks::new()
+            .with(Attribute::Italic, Attribute::Fraktur)
+            .with(Attribute::Fraktur, Attribute::Italic);
+        assert_eq!(
+            fallbacks.resolve(Attribute::Italic, Attributes::none()),
+            None
+        );
+    }
+}
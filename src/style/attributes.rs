@@ -3,7 +3,7 @@ use std::ops::{BitAnd, BitOr, BitXor};
 use crate::style::Attribute;
 
 /// a bitset for all possible attributes
-#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct Attributes(u32);
 
 impl From<Attribute> for Attributes {
@@ -68,6 +68,11 @@ impl Attributes {
         Self(0)
     }
 
+    /// Returns the bitset with every attribute set.
+    pub fn all() -> Self {
+        Attribute::iterator().fold(Self::none(), Self::with)
+    }
+
     /// Returns a copy of the bitset with the given attribute set.
     /// If it's already set, this returns the bitset unmodified.
     #[inline(always)]
@@ -140,8 +145,11 @@ mod tests {
 
     #[test]
     fn test_attributes_const() {
-      const ATTRIBUTES: Attributes = Attributes::none().with(Attribute::Bold).with(Attribute::Italic).without(Attribute::Bold);
-      assert!(!ATTRIBUTES.has(Attribute::Bold));
-      assert!(ATTRIBUTES.has(Attribute::Italic));
+        const ATTRIBUTES: Attributes = Attributes::none()
+            .with(Attribute::Bold)
+            .with(Attribute::Italic)
+            .without(Attribute::Bold);
+        assert!(!ATTRIBUTES.has(Attribute::Bold));
+        assert!(ATTRIBUTES.has(Attribute::Italic));
     }
 }
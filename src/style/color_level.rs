@@ -0,0 +1,370 @@
+use std::{
+    io,
+    sync::atomic::{AtomicU8, Ordering},
+};
+
+use crate::{capabilities::Environment, tty::IsTty};
+
+use super::{Color, Colored};
+
+/// How many colors a stream can show, see [`color_level`](fn.color_level.html).
+///
+/// The levels are ordered, `ColorLevel::None < ColorLevel::Ansi16 < ColorLevel::Ansi256 <
+/// ColorLevel::TrueColor`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ColorLevel {
+    /// No colors, the stream isn't a terminal or the user asked for no colors.
+    None,
+    /// The 16 base colors.
+    Ansi16,
+    /// The 256 colors of [`Color::AnsiValue`](enum.Color.html#variant.AnsiValue).
+    Ansi256,
+    /// 24 bit [`Color::Rgb`](enum.Color.html#variant.Rgb) colors.
+    TrueColor,
+}
+
+impl ColorLevel {
+    /// Returns the level the environment variables suggest for a stream.
+    ///
+    /// Streams which aren't terminals, and dumb terminals, have no colors unless
+    /// `CLICOLOR_FORCE` or `FORCE_COLOR` is set. `NO_COLOR` isn't consulted here, see
+    /// [`color_level`](fn.color_level.html).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossterm::{capabilities::Environment, style::ColorLevel};
+    ///
+    /// let env = Environment::from_vars(|name| match name {
+    ///     "TERM" => Some("xterm-256color".to_string()),
+    ///     _ => None,
+    /// });
+    ///
+    /// assert_eq!(ColorLevel::from_env(&env, true), ColorLevel::Ansi256);
+    /// assert_eq!(ColorLevel::from_env(&env, false), ColorLevel::None);
+    /// ```
+    pub fn from_env(env: &Environment, is_tty: bool) -> ColorLevel {
+        if (!is_tty || env.dumb) && !env.force_color {
+            ColorLevel::None
+        } else if env.truecolor {
+            ColorLevel::TrueColor
+        } else if env.ansi256 {
+            ColorLevel::Ansi256
+        } else {
+            ColorLevel::Ansi16
+        }
+    }
+
+    fn from_u8(value: u8) -> Option<ColorLevel> {
+        match value {
+            0 => Some(ColorLevel::None),
+            1 => Some(ColorLevel::Ansi16),
+            2 => Some(ColorLevel::Ansi256),
+            3 => Some(ColorLevel::TrueColor),
+            _ => None,
+        }
+    }
+}
+
+/// A standard stream of the process.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Stream {
+    /// The standard output.
+    Stdout,
+    /// The standard error.
+    Stderr,
+}
+
+impl Stream {
+    // MSYS2 and Cygwin ptys count, they show colors like any other terminal
+    fn is_tty(self) -> bool {
+        let kind = match self {
+            Stream::Stdout => io::stdout().tty_kind(),
+            Stream::Stderr => io::stderr().tty_kind(),
+        };
+        kind.is_terminal()
+    }
+}
+
+// The detected level per stream, `UNKNOWN` until it is detected.
+const UNKNOWN: u8 = u8::MAX;
+static LEVELS: [AtomicU8; 2] = [AtomicU8::new(UNKNOWN), AtomicU8::new(UNKNOWN)];
+
+/// Returns how many colors `stream` can show.
+///
+/// The level is detected once per stream, from whether the stream is a terminal, the environment
+/// variables (see [`ColorLevel::from_env`](enum.ColorLevel.html#method.from_env)) and, with the
+/// `terminfo` feature, the `colors` and `RGB` capabilities of the terminfo entry in use. On
+/// Windows, consoles which support ANSI codes have 24 bit colors. If capabilities were
+/// installed, their [`truecolor`](../capabilities/struct.Capabilities.html#structfield.truecolor)
+/// and [`ansi256`](../capabilities/struct.Capabilities.html#structfield.ansi256) decide how many
+/// colors the stream has.
+///
+/// It's `ColorLevel::None` while colors are disabled with `NO_COLOR` or
+/// [`force_color_output`](fn.force_color_output.html).
+///
+/// # Examples
+///
+/// ```no_run
+/// use crossterm::style::{self, Color, ContentStyle, Stream, Stylize};
+///
+/// let orange = Color::Rgb { r: 255, g: 135, b: 0 };
+/// let style = ContentStyle::new().with(orange).bold();
+///
+/// // an approximation of orange, or no color at all if stdout is piped
+/// let style = style.downgrade(style::color_level(Stream::Stdout));
+/// println!("{}", style.apply("warning"));
+/// ```
+pub fn color_level(stream: Stream) -> ColorLevel {
+    if Colored::ansi_color_disabled_memoized() {
+        return ColorLevel::None;
+    }
+
+    let cached = &LEVELS[stream as usize];
+    if let Some(level) = ColorLevel::from_u8(cached.load(Ordering::Relaxed)) {
+        return level;
+    }
+    let level = detect(stream);
+    cached.store(level as u8, Ordering::Relaxed);
+    level
+}
+
+/// Returns whether `stream` can show colors, see [`color_level`](fn.color_level.html).
+pub fn supports_color(stream: Stream) -> bool {
+    color_level(stream) > ColorLevel::None
+}
+
+fn detect(stream: Stream) -> ColorLevel {
+    let env = crate::capabilities::from_env();
+    #[allow(unused_mut)]
+    let mut level = ColorLevel::from_env(&env, stream.is_tty());
+    if level == ColorLevel::None {
+        return level;
+    }
+
+    // installed capabilities, e.g. from `detect_truecolor`, know better than the environment
+    if let Some((truecolor, ansi256)) =
+        crate::capabilities::query_installed(|c| (c.truecolor, c.ansi256))
+    {
+        return if truecolor {
+            ColorLevel::TrueColor
+        } else if ansi256 {
+            level.min(ColorLevel::Ansi256)
+        } else {
+            ColorLevel::Ansi16
+        };
+    }
+
+    #[cfg(feature = "terminfo")]
+    if let Some(info) = crate::terminfo::current() {
+        if info.flag("RGB") || info.flag("Tc") {
+            level = ColorLevel::TrueColor;
+        } else if info.number("colors").unwrap_or(0) >= 256 {
+            level = level.max(ColorLevel::Ansi256);
+        }
+    }
+
+    #[cfg(windows)]
+    if env.term.is_none() {
+        level = if crate::ansi_support::supports_ansi() {
+            ColorLevel::TrueColor
+        } else {
+            ColorLevel::Ansi16
+        };
+    }
+
+    level
+}
+
+// The base colors in the order of their ANSI values.
+const BASE_COLORS: [Color; 16] = [
+    Color::Black,
+    Color::DarkRed,
+    Color::DarkGreen,
+    Color::DarkYellow,
+    Color::DarkBlue,
+    Color::DarkMagenta,
+    Color::DarkCyan,
+    Color::Grey,
+    Color::DarkGrey,
+    Color::Red,
+    Color::Green,
+    Color::Yellow,
+    Color::Blue,
+    Color::Magenta,
+    Color::Cyan,
+    Color::White,
+];
+
+// The RGB values xterm uses for the base colors.
+const BASE_RGB: [(u8, u8, u8); 16] = [
+    (0, 0, 0),
+    (205, 0, 0),
+    (0, 205, 0),
+    (205, 205, 0),
+    (0, 0, 238),
+    (205, 0, 205),
+    (0, 205, 205),
+    (229, 229, 229),
+    (127, 127, 127),
+    (255, 0, 0),
+    (0, 255, 0),
+    (255, 255, 0),
+    (92, 92, 255),
+    (255, 0, 255),
+    (0, 255, 255),
+    (255, 255, 255),
+];
+
+const CUBE_STEPS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+
+fn distance((r1, g1, b1): (u8, u8, u8), (r2, g2, b2): (u8, u8, u8)) -> u32 {
+    let d = |a: u8, b: u8| (a as i32 - b as i32).pow(2) as u32;
+    d(r1, r2) + d(g1, g2) + d(b1, b2)
+}
+
+/// Returns the RGB value of an ANSI value.
+fn ansi_rgb(value: u8) -> (u8, u8, u8) {
+    match value {
+        0..=15 => BASE_RGB[value as usize],
+        16..=231 => {
+            let value = value - 16;
+            (
+                CUBE_STEPS[(value / 36) as usize],
+                CUBE_STEPS[(value / 6 % 6) as usize],
+                CUBE_STEPS[(value % 6) as usize],
+            )
+        }
+        _ => {
+            let grey = 8 + (value - 232) * 10;
+            (grey, grey, grey)
+        }
+    }
+}
+
+/// Returns the ANSI value of the 256 colors closest to `rgb`.
+fn nearest_ansi256(rgb: (u8, u8, u8)) -> u8 {
+    let step = |v: u8| match v {
+        0..=47 => 0,
+        48..=114 => 1,
+        _ => (v - 35) / 40,
+    };
+    let (r, g, b) = rgb;
+    let cube = 16 + 36 * step(r) + 6 * step(g) + step(b);
+
+    let average = (r as u32 + g as u32 + b as u32) / 3;
+    let grey = 232 + (average.saturating_sub(3) / 10).min(23) as u8;
+
+    if distance(ansi_rgb(grey), rgb) < distance(ansi_rgb(cube), rgb) {
+        grey
+    } else {
+        cube
+    }
+}
+
+/// Returns the index of a base color in the order of their ANSI values, `None` for other colors.
+pub(crate) fn base_index(color: Color) -> Option<u8> {
+    BASE_COLORS
+        .iter()
+        .position(|&base| base == color)
+        .map(|index| index as u8)
+}
+
+/// Returns the base color closest to `rgb`.
+fn nearest_base(rgb: (u8, u8, u8)) -> Color {
+    let index = (0..16)
+        .min_by_key(|&index| distance(BASE_RGB[index], rgb))
+        .unwrap_or_default();
+    BASE_COLORS[index]
+}
+
+impl Color {
+    /// Returns the color closest to this one a stream of `level` can show, `None` for
+    /// `ColorLevel::None`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossterm::style::{Color, ColorLevel};
+    ///
+    /// let orange = Color::Rgb { r: 255, g: 135, b: 0 };
+    ///
+    /// assert_eq!(orange.downgrade(ColorLevel::TrueColor), Some(orange));
+    /// assert_eq!(orange.downgrade(ColorLevel::Ansi256), Some(Color::AnsiValue(208)));
+    /// assert_eq!(orange.downgrade(ColorLevel::Ansi16), Some(Color::DarkYellow));
+    /// assert_eq!(orange.downgrade(ColorLevel::None), None);
+    /// ```
+    pub fn downgrade(self, level: ColorLevel) -> Option<Color> {
+        let color = match (self, level) {
+            (_, ColorLevel::None) => return None,
+            (Color::Rgb { r, g, b }, ColorLevel::Ansi256) => {
+                Color::AnsiValue(nearest_ansi256((r, g, b)))
+            }
+            (Color::Rgb { r, g, b }, ColorLevel::Ansi16) => nearest_base((r, g, b)),
+            (Color::AnsiValue(value), ColorLevel::Ansi16) if value < 16 => {
+                BASE_COLORS[value as usize]
+            }
+            (Color::AnsiValue(value), ColorLevel::Ansi16) => nearest_base(ansi_rgb(value)),
+            (color, _) => color,
+        };
+        Some(color)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::capabilities::Environment;
+
+    use super::{nearest_ansi256, Color, ColorLevel};
+
+    fn from_vars(vars: &[(&str, &str)]) -> Environment {
+        Environment::from_vars(|name| {
+            vars.iter()
+                .find(|(key, _)| *key == name)
+                .map(|(_, value)| value.to_string())
+        })
+    }
+
+    #[test]
+    fn test_color_level_from_env() {
+        let env = from_vars(&[("TERM", "xterm"), ("COLORTERM", "truecolor")]);
+        assert_eq!(ColorLevel::from_env(&env, true), ColorLevel::TrueColor);
+        assert_eq!(ColorLevel::from_env(&env, false), ColorLevel::None);
+
+        let env = from_vars(&[("TERM", "xterm")]);
+        assert_eq!(ColorLevel::from_env(&env, true), ColorLevel::Ansi16);
+
+        let env = from_vars(&[("TERM", "dumb")]);
+        assert_eq!(ColorLevel::from_env(&env, true), ColorLevel::None);
+
+        let env = from_vars(&[("TERM", "xterm-256color"), ("CLICOLOR_FORCE", "1")]);
+        assert_eq!(ColorLevel::from_env(&env, false), ColorLevel::Ansi256);
+    }
+
+    #[test]
+    fn test_nearest_ansi256() {
+        assert_eq!(nearest_ansi256((0, 0, 0)), 16);
+        assert_eq!(nearest_ansi256((255, 255, 255)), 231);
+        assert_eq!(nearest_ansi256((95, 135, 175)), 67);
+        assert_eq!(nearest_ansi256((128, 128, 128)), 244);
+    }
+
+    #[test]
+    fn test_downgrade() {
+        let rgb = Color::Rgb { r: 250, g: 5, b: 5 };
+        assert_eq!(rgb.downgrade(ColorLevel::Ansi16), Some(Color::Red));
+        assert_eq!(
+            Color::AnsiValue(4).downgrade(ColorLevel::Ansi16),
+            Some(Color::DarkBlue)
+        );
+        assert_eq!(
+            Color::AnsiValue(232).downgrade(ColorLevel::Ansi16),
+            Some(Color::Black)
+        );
+        assert_eq!(
+            Color::Blue.downgrade(ColorLevel::Ansi256),
+            Some(Color::Blue)
+        );
+        assert_eq!(Color::Reset.downgrade(ColorLevel::None), None);
+    }
+}
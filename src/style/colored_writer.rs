@@ -0,0 +1,164 @@
+use std::io::{self, Write};
+
+use crate::{Command, QueueableCommand};
+
+use super::set_color_override;
+
+/// A writer which shows or hides colors regardless of `NO_COLOR` and
+/// [`force_color_output`](fn.force_color_output.html), e.g. to keep colors on `stderr` while
+/// `stdout` is piped.
+///
+/// The override applies to the commands queued with [`queue!`](../macro.queue.html),
+/// [`execute!`](../macro.execute.html) or the [`queue`](#method.queue) and
+/// [`execute`](#method.execute) methods of the writer. Bytes written to it directly pass
+/// through unchanged.
+///
+/// # Examples
+///
+/// ```no_run
+/// use std::io;
+/// use crossterm::{
+///     execute,
+///     style::{ColoredWriter, Print, ResetColor, SetForegroundColor, Color},
+/// };
+///
+/// fn main() -> io::Result<()> {
+///     let mut stderr = ColoredWriter::always(io::stderr());
+///
+///     execute!(
+///         stderr,
+///         SetForegroundColor(Color::Red),
+///         Print("error: "),
+///         ResetColor,
+///         Print("file not found\n")
+///     )
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ColoredWriter<W> {
+    inner: W,
+    enabled: bool,
+}
+
+impl<W: Write> ColoredWriter<W> {
+    /// Wraps `inner`, colors are shown if `enabled`.
+    pub fn new(inner: W, enabled: bool) -> ColoredWriter<W> {
+        ColoredWriter { inner, enabled }
+    }
+
+    /// Wraps `inner`, colors are always shown.
+    pub fn always(inner: W) -> ColoredWriter<W> {
+        ColoredWriter::new(inner, true)
+    }
+
+    /// Wraps `inner`, colors are never shown.
+    pub fn never(inner: W) -> ColoredWriter<W> {
+        ColoredWriter::new(inner, false)
+    }
+
+    /// Returns whether colors are shown.
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// Sets whether colors are shown.
+    pub fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+    }
+
+    /// Queues `command`, with colors shown or hidden as configured.
+    ///
+    /// See [`QueueableCommand::queue`](../trait.QueueableCommand.html#tymethod.queue).
+    pub fn queue(&mut self, command: impl Command) -> io::Result<&mut Self> {
+        self.scope().queue(command)?;
+        Ok(self)
+    }
+
+    /// Executes `command`, with colors shown or hidden as configured.
+    ///
+    /// See [`ExecutableCommand::execute`](../trait.ExecutableCommand.html#tymethod.execute).
+    pub fn execute(&mut self, command: impl Command) -> io::Result<&mut Self> {
+        self.queue(command)?;
+        self.inner.flush()?;
+        Ok(self)
+    }
+
+    /// Returns a reference to the wrapped writer.
+    pub fn get_ref(&self) -> &W {
+        &self.inner
+    }
+
+    /// Returns a mutable reference to the wrapped writer.
+    pub fn get_mut(&mut self) -> &mut W {
+        &mut self.inner
+    }
+
+    /// Returns the wrapped writer.
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+
+    /// Returns the wrapped writer, which writes commands with the override until it is dropped.
+    pub(crate) fn scope(&mut self) -> ColorScope<'_, W> {
+        ColorScope {
+            previous: set_color_override(Some(self.enabled)),
+            writer: &mut self.inner,
+        }
+    }
+}
+
+impl<W: Write> Write for ColoredWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.inner.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// The writer [`queue!`](../macro.queue.html) and [`execute!`](../macro.execute.html) write to
+/// for a [`ColoredWriter`], see there.
+#[doc(hidden)]
+pub struct ColorScope<'a, W> {
+    writer: &'a mut W,
+    previous: Option<bool>,
+}
+
+impl<W: Write> Write for ColorScope<'_, W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.writer.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+}
+
+impl<W> Drop for ColorScope<'_, W> {
+    fn drop(&mut self) {
+        set_color_override(self.previous);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        execute, queue,
+        style::{Color, Print, SetForegroundColor},
+    };
+
+    use super::ColoredWriter;
+
+    #[test]
+    fn test_colored_writer_overrides_colors() {
+        let mut never = ColoredWriter::never(Vec::new());
+        queue!(never, SetForegroundColor(Color::Red), Print("a")).unwrap();
+        never.queue(SetForegroundColor(Color::Red)).unwrap();
+        assert_eq!(never.get_ref().as_slice(), b"\x1b[ma\x1b[m");
+
+        let mut always = ColoredWriter::always(Vec::new());
+        execute!(always, SetForegroundColor(Color::Red)).unwrap();
+        assert_eq!(always.into_inner(), b"\x1b[38;5;9m");
+    }
+}
@@ -2,10 +2,10 @@
 
 use std::fmt::Display;
 
-use crate::style::{Attributes, Color, StyledContent};
+use crate::style::{Attributes, Color, ColorLevel, StyledContent, UnderlineStyle};
 
 /// The style that can be put on content.
-#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Hash)]
 pub struct ContentStyle {
     /// The foreground color.
     pub foreground_color: Option<Color>,
@@ -15,6 +15,10 @@ pub struct ContentStyle {
     pub underline_color: Option<Color>,
     /// List of attributes.
     pub attributes: Attributes,
+    /// The underline style, which is written after the attributes. Set it with
+    /// [`Stylize::underline_style`](trait.Stylize.html#method.underline_style) to replace the
+    /// underline attributes as well.
+    pub underline: Option<UnderlineStyle>,
 }
 
 impl ContentStyle {
@@ -29,6 +33,24 @@ impl ContentStyle {
     pub fn new() -> ContentStyle {
         ContentStyle::default()
     }
+
+    /// Replaces the colors with the closest ones a stream of `level` can show, see
+    /// [`Color::downgrade`](enum.Color.html#method.downgrade). The attributes are kept.
+    pub fn downgrade(self, level: ColorLevel) -> ContentStyle {
+        ContentStyle {
+            foreground_color: self
+                .foreground_color
+                .and_then(|color| color.downgrade(level)),
+            background_color: self
+                .background_color
+                .and_then(|color| color.downgrade(level)),
+            underline_color: self
+                .underline_color
+                .and_then(|color| color.downgrade(level)),
+            attributes: self.attributes,
+            underline: self.underline,
+        }
+    }
 }
 
 impl AsRef<ContentStyle> for ContentStyle {
@@ -0,0 +1,130 @@
+use std::{
+    fmt::{self, Display},
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+use crate::{capabilities, Command};
+
+/// What [`PrintHyperlink`](struct.PrintHyperlink.html) prints on terminals which don't support
+/// hyperlinks, see [`set_hyperlink_fallback`](fn.set_hyperlink_fallback.html).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum HyperlinkFallback {
+    /// Only the text.
+    Text,
+    /// The text followed by the URL in parentheses, the default.
+    TextAndUrl,
+}
+
+impl Default for HyperlinkFallback {
+    fn default() -> Self {
+        HyperlinkFallback::TextAndUrl
+    }
+}
+
+// Whether the fallback prints the URL.
+static SHOW_URL: AtomicBool = AtomicBool::new(true);
+
+/// Sets what [`PrintHyperlink`](struct.PrintHyperlink.html) prints on terminals which don't
+/// support hyperlinks.
+pub fn set_hyperlink_fallback(fallback: HyperlinkFallback) {
+    SHOW_URL.store(fallback == HyperlinkFallback::TextAndUrl, Ordering::Relaxed);
+}
+
+/// Returns what [`PrintHyperlink`](struct.PrintHyperlink.html) prints on terminals which don't
+/// support hyperlinks.
+pub fn hyperlink_fallback() -> HyperlinkFallback {
+    if SHOW_URL.load(Ordering::Relaxed) {
+        HyperlinkFallback::TextAndUrl
+    } else {
+        HyperlinkFallback::Text
+    }
+}
+
+/// A command that prints `text` as a hyperlink to `url` (`OSC 8`).
+///
+/// If the terminal doesn't support hyperlinks according to the
+/// [`hyperlinks`](../capabilities/struct.Capabilities.html#structfield.hyperlinks) capability,
+/// the text is printed as configured with
+/// [`set_hyperlink_fallback`](fn.set_hyperlink_fallback.html), so hyperlinks can be used
+/// unconditionally.
+///
+/// # Examples
+///
+/// ```no_run
+/// use std::io;
+/// use crossterm::{execute, style::PrintHyperlink};
+///
+/// fn main() -> io::Result<()> {
+///     execute!(
+///         io::stdout(),
+///         PrintHyperlink::new("https://github.com/crossterm-rs/crossterm", "crossterm")
+///     )
+/// }
+/// ```
+///
+/// # Notes
+///
+/// Commands must be executed/queued for execution otherwise they do nothing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct PrintHyperlink<U: Display, T: Display> {
+    /// The URL the hyperlink points to.
+    pub url: U,
+    /// The text which is shown.
+    pub text: T,
+}
+
+impl<U: Display, T: Display> PrintHyperlink<U, T> {
+    /// Creates a hyperlink showing `text` which points to `url`.
+    pub fn new(url: U, text: T) -> PrintHyperlink<U, T> {
+        PrintHyperlink { url, text }
+    }
+
+    fn write(&self, f: &mut impl fmt::Write, hyperlinks: bool, show_url: bool) -> fmt::Result {
+        if hyperlinks {
+            write!(f, "\x1B]8;;{}\x1B\\{}\x1B]8;;\x1B\\", self.url, self.text)
+        } else if show_url {
+            write!(f, "{} ({})", self.text, self.url)
+        } else {
+            write!(f, "{}", self.text)
+        }
+    }
+}
+
+impl<U: Display, T: Display> Command for PrintHyperlink<U, T> {
+    fn write_ansi(&self, f: &mut impl fmt::Write) -> fmt::Result {
+        let hyperlinks = capabilities::query(|capabilities| capabilities.hyperlinks);
+        self.write(f, hyperlinks, SHOW_URL.load(Ordering::Relaxed))
+    }
+
+    #[cfg(windows)]
+    fn execute_winapi(&self) -> std::io::Result<()> {
+        panic!("tried to execute PrintHyperlink command using WinAPI, use ANSI instead");
+    }
+
+    #[cfg(windows)]
+    fn is_ansi_code_supported(&self) -> bool {
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::PrintHyperlink;
+
+    #[test]
+    fn test_print_hyperlink() {
+        let link = PrintHyperlink::new("https://example.com", "example");
+        let written = |hyperlinks, show_url| {
+            let mut s = String::new();
+            link.write(&mut s, hyperlinks, show_url).unwrap();
+            s
+        };
+
+        assert_eq!(
+            written(true, true),
+            "\x1B]8;;https://example.com\x1B\\example\x1B]8;;\x1B\\"
+        );
+        assert_eq!(written(false, true), "example (https://example.com)");
+        assert_eq!(written(false, false), "example");
+    }
+}
@@ -0,0 +1,108 @@
+use std::fmt::{self, Display, Formatter};
+
+use super::{style, StyledContent, Stylize};
+
+/// Displays the items of an iterator one after another, see [`items`](fn.items.html).
+///
+/// Styling `Items` styles all of them at once: the style is written before the first item and
+/// reset after the last one, instead of around every item.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Items<I, S = &'static str> {
+    items: I,
+    separator: S,
+}
+
+impl<I, S> Items<I, S>
+where
+    I: IntoIterator + Clone,
+    I::Item: Display,
+    S: Display,
+{
+    /// Writes `separator` between the items.
+    pub fn separated_by<T: Display>(self, separator: T) -> Items<I, T> {
+        Items {
+            items: self.items,
+            separator,
+        }
+    }
+}
+
+impl<I, S> Display for Items<I, S>
+where
+    I: IntoIterator + Clone,
+    I::Item: Display,
+    S: Display,
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        for (index, item) in self.items.clone().into_iter().enumerate() {
+            if index > 0 {
+                self.separator.fmt(f)?;
+            }
+            item.fmt(f)?;
+        }
+        Ok(())
+    }
+}
+
+impl<I, S> Stylize for Items<I, S>
+where
+    I: IntoIterator + Clone,
+    I::Item: Display,
+    S: Display,
+{
+    type Styled = StyledContent<Self>;
+
+    fn stylize(self) -> Self::Styled {
+        style(self)
+    }
+}
+
+/// Creates [`Items`](struct.Items.html) displaying `items` one after another.
+///
+/// `items` is iterated every time it's displayed, so it has to be cheap to clone, e.g. a
+/// reference to a collection or a lazy iterator. Styled with [`Stylize`](trait.Stylize.html),
+/// the items can be printed with a single [`PrintStyledContent`](struct.PrintStyledContent.html)
+/// which sets the style only once.
+///
+/// # Examples
+///
+/// ```
+/// use crossterm::style::{items, Stylize};
+///
+/// let files = vec!["Cargo.toml", "README.md", "src"];
+///
+/// assert_eq!(items(&files).separated_by(", ").to_string(), "Cargo.toml, README.md, src");
+/// assert_eq!(
+///     items(&files).separated_by(' ').green().to_string(),
+///     "\x1B[38;5;10mCargo.toml README.md src\x1B[39m"
+/// );
+/// ```
+pub fn items<I>(items: I) -> Items<I>
+where
+    I: IntoIterator + Clone,
+    I::Item: Display,
+{
+    Items {
+        items,
+        separator: "",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::style::{Attribute, Stylize};
+
+    use super::items;
+
+    #[test]
+    fn test_items_set_style_once() {
+        let styled = items(1..4).separated_by('-').attribute(Attribute::Bold);
+        assert_eq!(styled.to_string(), "\x1B[1m1-2-3\x1B[0m");
+        // the items can be displayed again
+        assert_eq!(styled.to_string(), "\x1B[1m1-2-3\x1B[0m");
+
+        let empty: [&str; 0] = [];
+        assert_eq!(items(&empty).to_string(), "");
+        assert_eq!(items(&["a", "b"]).red().to_plain(), "ab");
+    }
+}
@@ -0,0 +1,125 @@
+use std::fmt::{self, Display, Formatter};
+
+/// Displays `D` without escape sequences, e.g. to write the same value to a log file and the
+/// terminal.
+///
+/// The colors and attributes of [`StyledContent`](struct.StyledContent.html) are dropped, as well
+/// as any other CSI, OSC and DCS sequence the content contains, e.g. from nested styled content or
+/// [`PrintHyperlink`](struct.PrintHyperlink.html).
+///
+/// # Examples
+///
+/// ```
+/// use crossterm::style::{NoStyle, Stylize};
+///
+/// let styled = "error".red().bold();
+///
+/// assert_eq!(NoStyle(&styled).to_string(), "error");
+/// assert_eq!(NoStyle(format!("{}: file not found", styled)).to_string(), "error: file not found");
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct NoStyle<D: Display>(pub D);
+
+impl<D: Display> Display for NoStyle<D> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        use std::fmt::Write;
+
+        let mut plain = PlainWriter {
+            inner: f,
+            escapes: EscapeFilter::default(),
+        };
+        write!(plain, "{}", self.0)
+    }
+}
+
+struct PlainWriter<'a, 'b> {
+    inner: &'a mut Formatter<'b>,
+    escapes: EscapeFilter,
+}
+
+impl fmt::Write for PlainWriter<'_, '_> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        // write the visible runs at once instead of char by char
+        let mut start = None;
+        for (index, c) in s.char_indices() {
+            match (self.escapes.is_visible(c), start) {
+                (true, None) => start = Some(index),
+                (false, Some(from)) => {
+                    self.inner.write_str(&s[from..index])?;
+                    start = None;
+                }
+                _ => {}
+            }
+        }
+        match start {
+            Some(from) => self.inner.write_str(&s[from..]),
+            None => Ok(()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum State {
+    Text,
+    // after ESC
+    Escape,
+    // in a CSI sequence, until the final byte
+    Csi,
+    // in an OSC, DCS or other string, until BEL or ST
+    String,
+    // after ESC in a string, which is the start of ST
+    StringEscape,
+}
+
+impl Default for State {
+    fn default() -> Self {
+        State::Text
+    }
+}
+
+/// Tells the text from the escape sequences in a stream of characters.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct EscapeFilter {
+    state: State,
+}
+
+impl EscapeFilter {
+    /// Returns whether `c` is text, `false` if it's part of an escape sequence.
+    pub(crate) fn is_visible(&mut self, c: char) -> bool {
+        let (state, visible) = match (self.state, c) {
+            (State::Text, '\x1B') => (State::Escape, false),
+            (State::Text, _) => (State::Text, true),
+            (State::Escape, '[') => (State::Csi, false),
+            (State::Escape, ']' | 'P' | 'X' | '^' | '_') => (State::String, false),
+            // a two character sequence like `ESC 7`, intermediate bytes are skipped
+            (State::Escape, '\x20'..='\x2F') => (State::Escape, false),
+            (State::Escape, _) => (State::Text, false),
+            (State::Csi, '\x40'..='\x7E') => (State::Text, false),
+            (State::Csi, _) => (State::Csi, false),
+            (State::String, '\x07') => (State::Text, false),
+            (State::String | State::StringEscape, '\x1B') => (State::StringEscape, false),
+            (State::StringEscape, '\\') => (State::Text, false),
+            (State::String | State::StringEscape, _) => (State::String, false),
+        };
+        self.state = state;
+        visible
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::style::{Color, Stylize};
+
+    use super::NoStyle;
+
+    #[test]
+    fn test_no_style_strips_escapes() {
+        let text = format!(
+            "a{}b\x1B7c\x1B(Bd\x1B]8;;https://e.com\x1B\\link\x1B]8;;\x1B\\",
+            "x".with(Color::Rgb { r: 1, g: 2, b: 3 }).underlined(),
+        );
+        assert_eq!(NoStyle(&text).to_string(), "axbcdlink");
+        assert_eq!(NoStyle("\x1B]0;title\x07plain").to_string(), "plain");
+        assert_eq!(NoStyle("no escapes").to_string(), "no escapes");
+    }
+}
@@ -0,0 +1,307 @@
+use std::io::{self, Write};
+
+/// The maximum number of parameters merged into one SGR sequence.
+///
+/// Some terminals (e.g. the Linux console) ignore parameters beyond the 16th.
+const MAX_PARAMETERS: usize = 16;
+
+/// The maximum length of a CSI sequence that is inspected, longer ones are passed through as is.
+const MAX_SEQUENCE_LEN: usize = 64;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum State {
+    Ground,
+    Escape,
+    Csi,
+}
+
+/// A writer adapter that merges consecutive SGR sequences into a single one.
+///
+/// Commands like [`SetForegroundColor`](struct.SetForegroundColor.html),
+/// [`SetBackgroundColor`](struct.SetBackgroundColor.html) and
+/// [`SetAttribute`](struct.SetAttribute.html) each write their own `CSI … m` sequence. When they
+/// are queued back to back, this writer combines them into one `CSI …;…;… m` sequence, which
+/// considerably reduces the output size of style-heavy frames.
+///
+/// Everything else written to it is passed through unchanged.
+///
+/// # Examples
+///
+/// ```no_run
+/// use std::io::{self, Write};
+/// use crossterm::{
+///     queue,
+///     style::{Attribute, Color, Print, SetAttribute, SetBackgroundColor, SetForegroundColor, SgrBatchWriter},
+/// };
+///
+/// fn main() -> io::Result<()> {
+///     let mut stdout = SgrBatchWriter::new(io::stdout());
+///
+///     // Writes `ESC[38;5;9;48;5;12;1mStyled text` instead of three separate sequences.
+///     queue!(
+///         stdout,
+///         SetForegroundColor(Color::Red),
+///         SetBackgroundColor(Color::Blue),
+///         SetAttribute(Attribute::Bold),
+///         Print("Styled text")
+///     )?;
+///
+///     stdout.flush()
+/// }
+/// ```
+///
+/// # Notes
+///
+/// The last SGR sequence is held back until something else is written or the writer is flushed,
+/// so don't forget to [`flush`](#method.flush).
+#[derive(Debug)]
+pub struct SgrBatchWriter<W: Write> {
+    inner: W,
+    state: State,
+    /// The parameter bytes of the CSI sequence that is currently being read.
+    sequence: Vec<u8>,
+    /// The merged parameters of the SGR sequences that are held back.
+    pending: Vec<u8>,
+    pending_parameters: usize,
+}
+
+impl<W: Write> SgrBatchWriter<W> {
+    /// Creates a new batching writer around `inner`.
+    pub fn new(inner: W) -> SgrBatchWriter<W> {
+        SgrBatchWriter {
+            inner,
+            state: State::Ground,
+            sequence: Vec::with_capacity(MAX_SEQUENCE_LEN),
+            pending: Vec::with_capacity(MAX_SEQUENCE_LEN),
+            pending_parameters: 0,
+        }
+    }
+
+    /// Returns a reference to the underlying writer.
+    pub fn get_ref(&self) -> &W {
+        &self.inner
+    }
+
+    /// Returns a mutable reference to the underlying writer.
+    ///
+    /// Writing directly to the underlying writer may reorder output relative to SGR sequences that
+    /// are held back.
+    pub fn get_mut(&mut self) -> &mut W {
+        &mut self.inner
+    }
+
+    /// Writes everything that is held back and returns the underlying writer.
+    pub fn into_inner(mut self) -> io::Result<W> {
+        self.write_held_back()?;
+        Ok(self.inner)
+    }
+
+    /// Writes the pending SGR sequence, if any.
+    fn write_pending(&mut self) -> io::Result<()> {
+        if self.pending_parameters == 0 {
+            return Ok(());
+        }
+
+        self.inner.write_all(b"\x1B[")?;
+        self.inner.write_all(&self.pending)?;
+        self.inner.write_all(b"m")?;
+        self.pending.clear();
+        self.pending_parameters = 0;
+        Ok(())
+    }
+
+    /// Writes the pending SGR sequence and the partially read escape sequence, if any.
+    fn write_held_back(&mut self) -> io::Result<()> {
+        self.write_pending()?;
+        match self.state {
+            State::Ground => {}
+            State::Escape => self.inner.write_all(b"\x1B")?,
+            State::Csi => {
+                self.inner.write_all(b"\x1B[")?;
+                self.inner.write_all(&self.sequence)?;
+            }
+        }
+        self.state = State::Ground;
+        self.sequence.clear();
+        Ok(())
+    }
+
+    /// Handles a complete CSI sequence, `self.sequence` holds its parameter bytes.
+    fn finish_csi(&mut self, final_byte: u8) -> io::Result<()> {
+        let is_sgr = final_byte == b'm'
+            && self
+                .sequence
+                .iter()
+                .all(|byte| byte.is_ascii_digit() || *byte == b';' || *byte == b':');
+
+        if is_sgr {
+            // `CSI m` is the same as `CSI 0 m`, the parameter must be explicit when merging.
+            if self.sequence.is_empty() {
+                self.sequence.push(b'0');
+            }
+
+            let parameters = self.sequence.iter().filter(|byte| **byte == b';').count() + 1;
+            if self.pending_parameters + parameters > MAX_PARAMETERS {
+                self.write_pending()?;
+            }
+
+            if self.pending_parameters > 0 {
+                self.pending.push(b';');
+            }
+            self.pending.extend_from_slice(&self.sequence);
+            self.pending_parameters += parameters;
+        } else {
+            self.write_pending()?;
+            self.inner.write_all(b"\x1B[")?;
+            self.inner.write_all(&self.sequence)?;
+            self.inner.write_all(&[final_byte])?;
+        }
+
+        self.sequence.clear();
+        Ok(())
+    }
+}
+
+impl<W: Write> Write for SgrBatchWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let mut index = 0;
+
+        while index < buf.len() {
+            match self.state {
+                State::Ground => {
+                    let end = buf[index..]
+                        .iter()
+                        .position(|byte| *byte == b'\x1B')
+                        .map_or(buf.len(), |position| index + position);
+
+                    if end > index {
+                        self.write_pending()?;
+                        self.inner.write_all(&buf[index..end])?;
+                    }
+                    if end < buf.len() {
+                        self.state = State::Escape;
+                    }
+                    index = end + 1;
+                    continue;
+                }
+                State::Escape => {
+                    if buf[index] == b'[' {
+                        self.state = State::Csi;
+                    } else if buf[index] == b'\x1B' {
+                        self.write_pending()?;
+                        self.inner.write_all(b"\x1B")?;
+                    } else {
+                        self.write_pending()?;
+                        self.inner.write_all(&[b'\x1B', buf[index]])?;
+                        self.state = State::Ground;
+                    }
+                }
+                State::Csi => {
+                    let byte = buf[index];
+                    if (0x40..=0x7E).contains(&byte) {
+                        self.state = State::Ground;
+                        self.finish_csi(byte)?;
+                    } else if self.sequence.len() >= MAX_SEQUENCE_LEN {
+                        self.sequence.push(byte);
+                        self.write_held_back()?;
+                    } else {
+                        self.sequence.push(byte);
+                    }
+                }
+            }
+            index += 1;
+        }
+
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.write_held_back()?;
+        self.inner.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use crate::{
+        cursor::MoveTo,
+        queue,
+        style::{Attribute, Color, Print, SetAttribute, SetBackgroundColor, SetForegroundColor},
+    };
+
+    use super::SgrBatchWriter;
+
+    fn output(writer: SgrBatchWriter<Vec<u8>>) -> String {
+        String::from_utf8(writer.into_inner().unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_merge_consecutive_sgr() {
+        let mut writer = SgrBatchWriter::new(Vec::new());
+        queue!(
+            writer,
+            SetForegroundColor(Color::Red),
+            SetBackgroundColor(Color::Rgb { r: 1, g: 2, b: 3 }),
+            SetAttribute(Attribute::Bold),
+            Print("text"),
+            SetAttribute(Attribute::Reset)
+        )
+        .unwrap();
+
+        assert_eq!(output(writer), "\x1B[38;5;9;48;2;1;2;3;1mtext\x1B[0m");
+    }
+
+    #[test]
+    fn test_other_sequences_pass_through() {
+        let mut writer = SgrBatchWriter::new(Vec::new());
+        queue!(
+            writer,
+            SetAttribute(Attribute::Italic),
+            MoveTo(0, 0),
+            SetAttribute(Attribute::Bold)
+        )
+        .unwrap();
+        writer.write_all(b"\x1B[?25l\x1B[>4;1m\x1B7").unwrap();
+
+        assert_eq!(
+            output(writer),
+            "\x1B[3m\x1B[1;1H\x1B[1m\x1B[?25l\x1B[>4;1m\x1B7"
+        );
+    }
+
+    #[test]
+    fn test_split_writes_and_empty_parameters() {
+        let mut writer = SgrBatchWriter::new(Vec::new());
+        writer.write_all(b"\x1B").unwrap();
+        writer.write_all(b"[m\x1B[").unwrap();
+        writer.write_all(b"4").unwrap();
+        writer.write_all(b"m").unwrap();
+        writer.flush().unwrap();
+        writer.write_all(b"\x1B[9").unwrap();
+
+        assert_eq!(output(writer), "\x1B[0;4m\x1B[9");
+    }
+
+    #[test]
+    fn test_parameter_limit() {
+        let mut writer = SgrBatchWriter::new(Vec::new());
+        for _ in 0..3 {
+            queue!(writer, SetForegroundColor(Color::Rgb { r: 1, g: 2, b: 3 })).unwrap();
+        }
+        queue!(writer, SetAttribute(Attribute::Bold)).unwrap();
+
+        assert_eq!(output(writer), "\x1B[38;2;1;2;3;38;2;1;2;3;38;2;1;2;3;1m");
+
+        let mut writer = SgrBatchWriter::new(Vec::new());
+        for _ in 0..4 {
+            queue!(writer, SetForegroundColor(Color::Rgb { r: 1, g: 2, b: 3 })).unwrap();
+        }
+
+        assert_eq!(
+            output(writer),
+            "\x1B[38;2;1;2;3;38;2;1;2;3;38;2;1;2;3m\x1B[38;2;1;2;3m"
+        );
+    }
+}
@@ -2,7 +2,11 @@
 
 use std::fmt::{self, Display, Formatter};
 
-use super::{ContentStyle, PrintStyledContent};
+use super::{
+    display_width,
+    width::{self, split_padding},
+    ContentStyle, NoStyle, PrintStyledContent,
+};
 
 /// The style with the content to be styled.
 ///
@@ -39,6 +43,12 @@ impl<D: Display> StyledContent<D> {
         &self.content
     }
 
+    /// Returns a mutable reference to the content.
+    #[inline]
+    pub(crate) fn content_mut(&mut self) -> &mut D {
+        &mut self.content
+    }
+
     /// Returns the style.
     #[inline]
     pub fn style(&self) -> &ContentStyle {
@@ -51,6 +61,60 @@ impl<D: Display> StyledContent<D> {
     pub fn style_mut(&mut self) -> &mut ContentStyle {
         &mut self.style
     }
+
+    /// Puts `fill` left of the content, so that it's right-aligned in `width` cells.
+    ///
+    /// The fill has the style of the content, e.g. its background color. The width of the
+    /// content is measured with [`display_width`](fn.display_width.html), content which is
+    /// wider than `width` is returned as is.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossterm::style::Stylize;
+    ///
+    /// let status = "ready".on_blue();
+    ///
+    /// assert_eq!(status.pad_left(8, '.').to_plain(), "...ready");
+    /// assert_eq!(status.pad_right(8, ' ').to_plain(), "ready   ");
+    /// assert_eq!(status.center(8, '-').to_plain(), "-ready--");
+    /// ```
+    pub fn pad_left(&self, width: usize, fill: char) -> StyledContent<String> {
+        self.padded(width, fill, |missing| missing)
+    }
+
+    /// Puts `fill` right of the content, so that it's left-aligned in `width` cells, see
+    /// [`pad_left`](#method.pad_left).
+    pub fn pad_right(&self, width: usize, fill: char) -> StyledContent<String> {
+        self.padded(width, fill, |_| 0)
+    }
+
+    /// Puts `fill` on both sides of the content, so that it's centered in `width` cells, see
+    /// [`pad_left`](#method.pad_left). If the fill can't be split evenly, the right side gets
+    /// more.
+    pub fn center(&self, width: usize, fill: char) -> StyledContent<String> {
+        self.padded(width, fill, |missing| missing / 2)
+    }
+
+    fn padded(
+        &self,
+        width: usize,
+        fill: char,
+        left: impl FnOnce(usize) -> usize,
+    ) -> StyledContent<String> {
+        let content = self.content.to_string();
+        let (left, right) = split_padding(display_width(&content), width, left);
+        StyledContent::new(
+            self.style,
+            width::fill(fill, left) + &content + &width::fill(fill, right),
+        )
+    }
+
+    /// Returns the content without the style and any escape sequences it contains, see
+    /// [`NoStyle`](struct.NoStyle.html).
+    pub fn to_plain(&self) -> String {
+        NoStyle(&self.content).to_string()
+    }
 }
 
 impl<D: Display> AsRef<ContentStyle> for StyledContent<D> {
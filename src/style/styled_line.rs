@@ -0,0 +1,291 @@
+use std::fmt::{self, Display, Formatter};
+
+use super::{
+    width::{self, split_padding, WidthCounter},
+    ContentStyle, StyledContent,
+};
+
+/// A line of text made of differently styled spans.
+///
+/// # Examples
+///
+/// ```
+/// use crossterm::style::{Stylize, StyledLine};
+///
+/// let mut line = StyledLine::new();
+/// line.push("error:".red().bold());
+/// line.push(" the file ".stylize());
+/// line.push("config.toml".underlined());
+/// line.push(" was not found".stylize());
+///
+/// let lines = line.wrap(20);
+/// assert_eq!(lines.len(), 3);
+/// assert_eq!(lines[0].to_plain(), "error: the file");
+/// assert_eq!(lines[1].to_plain(), "config.toml was not");
+/// assert_eq!(lines[2].to_plain(), "found");
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct StyledLine {
+    spans: Vec<StyledContent<String>>,
+}
+
+impl StyledLine {
+    /// Creates an empty line.
+    pub fn new() -> StyledLine {
+        StyledLine::default()
+    }
+
+    /// Appends `span` to the line.
+    pub fn push<D: Display>(&mut self, span: StyledContent<D>) {
+        self.push_str(*span.style(), &span.content().to_string());
+    }
+
+    fn push_str(&mut self, style: ContentStyle, text: &str) {
+        if text.is_empty() {
+            return;
+        }
+        match self.spans.last_mut() {
+            // the text continues the previous span
+            Some(last) if *last.style() == style => last.content_mut().push_str(text),
+            _ => self.spans.push(StyledContent::new(style, text.to_string())),
+        }
+    }
+
+    /// Returns the spans of the line.
+    pub fn spans(&self) -> &[StyledContent<String>] {
+        &self.spans
+    }
+
+    /// Returns whether the line has no text.
+    pub fn is_empty(&self) -> bool {
+        self.spans.is_empty()
+    }
+
+    /// Returns the number of cells the line takes, see
+    /// [`display_width`](fn.display_width.html).
+    pub fn width(&self) -> usize {
+        let mut counter = WidthCounter::default();
+        self.chars().map(|(_, c)| counter.width(c)).sum()
+    }
+
+    /// Returns the text of the line without styles.
+    pub fn to_plain(&self) -> String {
+        self.spans.iter().map(StyledContent::to_plain).collect()
+    }
+
+    /// Puts `fill` left of the line, so that it's right-aligned in `width` cells.
+    ///
+    /// The fill has no style. A line which is wider than `width` is returned as is.
+    pub fn pad_left(self, width: usize, fill: char) -> StyledLine {
+        self.padded(width, fill, |missing| missing)
+    }
+
+    /// Puts `fill` right of the line, so that it's left-aligned in `width` cells, see
+    /// [`pad_left`](#method.pad_left).
+    pub fn pad_right(self, width: usize, fill: char) -> StyledLine {
+        self.padded(width, fill, |_| 0)
+    }
+
+    /// Puts `fill` on both sides of the line, so that it's centered in `width` cells, see
+    /// [`pad_left`](#method.pad_left). If the fill can't be split evenly, the right side gets
+    /// more.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use crossterm::style::{Stylize, StyledLine};
+    ///
+    /// let title: StyledLine = vec!["crossterm".bold(), " 0.27".dim()].into_iter().collect();
+    ///
+    /// assert_eq!(title.center(20, '=').to_plain(), "===crossterm 0.27===");
+    /// ```
+    pub fn center(self, width: usize, fill: char) -> StyledLine {
+        self.padded(width, fill, |missing| missing / 2)
+    }
+
+    fn padded(self, width: usize, fill: char, left: impl FnOnce(usize) -> usize) -> StyledLine {
+        let (left, right) = split_padding(self.width(), width, left);
+        let mut line = StyledLine::new();
+        line.push_str(ContentStyle::new(), &width::fill(fill, left));
+        for span in &self.spans {
+            line.push_str(*span.style(), span.content());
+        }
+        line.push_str(ContentStyle::new(), &width::fill(fill, right));
+        line
+    }
+
+    /// Splits the line into lines at most `width` cells wide.
+    ///
+    /// The line is broken at whitespace, which is dropped at the breaks, and at line feeds.
+    /// Words longer than `width` are broken wherever they have to. Every line starts with the
+    /// style of its first span, so a style which spans a break is set again on the next line.
+    ///
+    /// A `width` of `0` is treated as `1`.
+    pub fn wrap(&self, width: usize) -> Vec<StyledLine> {
+        let width = width.max(1);
+        let mut wrapper = Wrapper {
+            width,
+            lines: Vec::new(),
+            line: StyledLine::new(),
+            line_width: 0,
+            space: Vec::new(),
+            word: Vec::new(),
+            word_width: 0,
+        };
+
+        let mut counter = WidthCounter::default();
+        for (style, c) in self.chars() {
+            if c == '\n' {
+                wrapper.end_word();
+                wrapper.break_line();
+                wrapper.space.clear();
+            } else if c.is_whitespace() {
+                wrapper.end_word();
+                wrapper.space.push((style, c, counter.width(c)));
+            } else {
+                let width = counter.width(c);
+                wrapper.word.push((style, c, width));
+                wrapper.word_width += width;
+            }
+        }
+        wrapper.end_word();
+        wrapper.lines.push(wrapper.line);
+        wrapper.lines
+    }
+
+    fn chars(&self) -> impl Iterator<Item = (ContentStyle, char)> + '_ {
+        self.spans
+            .iter()
+            .flat_map(|span| span.content().chars().map(move |c| (*span.style(), c)))
+    }
+}
+
+// A character with its style and width.
+type Cell = (ContentStyle, char, usize);
+
+struct Wrapper {
+    width: usize,
+    lines: Vec<StyledLine>,
+    line: StyledLine,
+    line_width: usize,
+    // the whitespace after the last word
+    space: Vec<Cell>,
+    // the word which is being read
+    word: Vec<Cell>,
+    word_width: usize,
+}
+
+impl Wrapper {
+    fn break_line(&mut self) {
+        self.lines.push(std::mem::take(&mut self.line));
+        self.line_width = 0;
+    }
+
+    fn push(&mut self, cells: &[Cell]) {
+        for &(style, c, width) in cells {
+            let mut buffer = [0; 4];
+            self.line.push_str(style, c.encode_utf8(&mut buffer));
+            self.line_width += width;
+        }
+    }
+
+    fn end_word(&mut self) {
+        if self.word.is_empty() {
+            return;
+        }
+        let word = std::mem::take(&mut self.word);
+        let word_width = std::mem::replace(&mut self.word_width, 0);
+        let space = std::mem::take(&mut self.space);
+        let space_width: usize = space.iter().map(|(_, _, width)| width).sum();
+
+        if self.line_width + space_width + word_width <= self.width {
+            self.push(&space);
+            self.push(&word);
+            return;
+        }
+        if self.line_width > 0 {
+            self.break_line();
+        }
+        if word_width <= self.width {
+            self.push(&word);
+            return;
+        }
+
+        // the word doesn't fit on a line of its own
+        for cell in word {
+            if self.line_width + cell.2 > self.width && self.line_width > 0 {
+                self.break_line();
+            }
+            self.push(&[cell]);
+        }
+    }
+}
+
+impl<D: Display> From<StyledContent<D>> for StyledLine {
+    fn from(span: StyledContent<D>) -> Self {
+        let mut line = StyledLine::new();
+        line.push(span);
+        line
+    }
+}
+
+impl<D: Display> FromIterator<StyledContent<D>> for StyledLine {
+    fn from_iter<I: IntoIterator<Item = StyledContent<D>>>(spans: I) -> Self {
+        let mut line = StyledLine::new();
+        for span in spans {
+            line.push(span);
+        }
+        line
+    }
+}
+
+impl Display for StyledLine {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        for span in &self.spans {
+            span.fmt(f)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::style::{Color, ContentStyle, Stylize};
+
+    use super::StyledLine;
+
+    fn plain(lines: &[StyledLine]) -> Vec<String> {
+        lines.iter().map(StyledLine::to_plain).collect()
+    }
+
+    #[test]
+    fn test_wrap_carries_styles() {
+        let line: StyledLine = vec!["one ".red(), "two three".blue()].into_iter().collect();
+        let lines = line.wrap(7);
+        assert_eq!(plain(&lines), ["one two", "three"]);
+
+        let red = ContentStyle::new().with(Color::Red);
+        let blue = ContentStyle::new().with(Color::Blue);
+        assert_eq!(lines[0].spans().len(), 2);
+        assert_eq!(*lines[0].spans()[0].style(), red);
+        assert_eq!(lines[0].spans()[0].content(), "one ");
+        assert_eq!(lines[1].spans().len(), 1);
+        assert_eq!(*lines[1].spans()[0].style(), blue);
+    }
+
+    #[test]
+    fn test_wrap_breaks_long_words_and_line_feeds() {
+        let line = StyledLine::from("abcdefg hi\n\njk  l".stylize());
+        assert_eq!(
+            plain(&line.wrap(3)),
+            ["abc", "def", "g", "hi", "", "jk", "l"]
+        );
+        assert_eq!(plain(&line.wrap(0))[..3], ["a", "b", "c"]);
+
+        let wide = StyledLine::from("日本語".stylize());
+        assert_eq!(wide.clone().pad_left(7, '-').to_plain(), "-日本語");
+        assert_eq!(wide.clone().pad_right(9, '・').to_plain(), "日本語・ ");
+        assert_eq!(plain(&wide.wrap(5)), ["日本", "語"]);
+        assert_eq!(plain(&StyledLine::new().wrap(5)), [""]);
+    }
+}
@@ -1,6 +1,6 @@
 use std::fmt::Display;
 
-use super::{style, Attribute, Color, ContentStyle, StyledContent};
+use super::{style, Attribute, Color, ContentStyle, StyledContent, UnderlineStyle};
 
 macro_rules! stylize_method {
     ($method_name:ident Attribute::$attribute:ident) => {
@@ -95,10 +95,26 @@ pub trait Stylize: Sized {
         styled
     }
 
-    /// Styles the content with the attribute.
+    /// Sets the underline style, which replaces the previous one and the underline attributes.
+    fn underline_style(self, underline: UnderlineStyle) -> Self::Styled {
+        let mut styled = self.stylize();
+        let style = styled.as_mut();
+        for other in UnderlineStyle::ALL {
+            style.attributes.unset(other.attribute());
+        }
+        style.underline = Some(underline);
+        styled
+    }
+
+    /// Styles the content with the attribute. An underline attribute, or
+    /// [`NoUnderline`](Attribute::NoUnderline), replaces the underline style.
     fn attribute(self, attr: Attribute) -> Self::Styled {
         let mut styled = self.stylize();
-        styled.as_mut().attributes.set(attr);
+        let style = styled.as_mut();
+        if attr == Attribute::NoUnderline || UnderlineStyle::from_attribute(attr).is_some() {
+            style.underline = None;
+        }
+        style.attributes.set(attr);
         styled
     }
 
@@ -170,7 +186,7 @@ use calculated_docs;
 
 #[cfg(test)]
 mod tests {
-    use super::super::{Attribute, Color, ContentStyle, Stylize};
+    use super::super::{Attribute, Color, ContentStyle, Stylize, UnderlineStyle};
 
     #[test]
     fn set_fg_bg_add_attr() {
@@ -197,4 +213,39 @@ mod tests {
         assert!(style.attributes.has(Attribute::Bold));
         assert!(style.attributes.has(Attribute::NoItalic));
     }
+
+    #[test]
+    fn set_underline_style() {
+        let style = ContentStyle::new()
+            .underlined()
+            .underline_style(UnderlineStyle::Curly)
+            .underline_style(UnderlineStyle::Dotted);
+
+        assert_eq!(style.underline, Some(UnderlineStyle::Dotted));
+        assert!(!style.attributes.has(Attribute::Underlined));
+    }
+
+    #[test]
+    fn underline_attribute_replaces_underline_style() {
+        let style = ContentStyle::new()
+            .underline_style(UnderlineStyle::Curly)
+            .underlined();
+        assert_eq!(style.underline, None);
+        assert!(style.attributes.has(Attribute::Underlined));
+
+        let style = ContentStyle::new()
+            .underline_style(UnderlineStyle::Dashed)
+            .attribute(Attribute::DoubleUnderlined);
+        assert_eq!(style.underline, None);
+
+        let style = ContentStyle::new()
+            .underline_style(UnderlineStyle::Dashed)
+            .attribute(Attribute::NoUnderline);
+        assert_eq!(style.underline, None);
+
+        let style = ContentStyle::new()
+            .underline_style(UnderlineStyle::Dashed)
+            .bold();
+        assert_eq!(style.underline, Some(UnderlineStyle::Dashed));
+    }
 }
@@ -1,6 +1,11 @@
-pub use self::{attribute::Attribute, color::Color, colored::Colored, colors::Colors};
+pub(crate) use self::colored::set_color_override;
+pub use self::{
+    attribute::Attribute, color::Color, colored::Colored, colors::Colors,
+    underline_style::UnderlineStyle,
+};
 
 mod attribute;
 mod color;
 mod colored;
 mod colors;
+mod underline_style;
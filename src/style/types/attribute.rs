@@ -125,7 +125,12 @@ Attribute! {
     ///
     /// Mostly used for [mathematical alphanumeric symbols](https://en.wikipedia.org/wiki/Mathematical_Alphanumeric_Symbols).
     Fraktur = 20,
-    /// Turns off the `Bold` attribute. - Inconsistent - Prefer to use NormalIntensity
+    /// Turns off the `Bold` attribute.
+    ///
+    /// Most terminals take `SGR 21` for a double underline, so this is written as
+    /// `NormalIntensity`, which turns `Dim` off as well, unless the
+    /// [`Capabilities`](../capabilities/struct.Capabilities.html#structfield.sgr21_no_bold) say
+    /// the terminal takes it for turning bold off.
     NoBold = 21,
     /// Switches the text back to normal intensity (no bold, italic).
     NormalIntensity = 22,
@@ -1,11 +1,12 @@
 use parking_lot::Once;
+use std::cell::Cell;
 use std::fmt::{self, Formatter};
 use std::sync::atomic::{AtomicBool, Ordering};
 
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 
-use crate::style::{parse_next_u8, Color};
+use crate::style::{parse_next_u8, Color, ColorLevel};
 
 /// Represents a foreground or background color.
 ///
@@ -26,6 +27,17 @@ pub enum Colored {
 static ANSI_COLOR_DISABLED: AtomicBool = AtomicBool::new(false);
 static INITIALIZER: Once = Once::new();
 
+thread_local! {
+    // Overrides `ANSI_COLOR_DISABLED` while commands are written to a `ColoredWriter`.
+    static COLOR_OVERRIDE: Cell<Option<bool>> = Cell::new(None);
+}
+
+/// Makes colors enabled or disabled on this thread regardless of the global setting, `None`
+/// removes the override. Returns the previous override.
+pub(crate) fn set_color_override(enabled: Option<bool>) -> Option<bool> {
+    COLOR_OVERRIDE.with(|cell| cell.replace(enabled))
+}
+
 impl Colored {
     /// Parse an ANSI foreground or background color.
     /// This is the string that would appear within an `ESC [ <str> m` escape sequence, as found in
@@ -97,14 +109,20 @@ impl fmt::Display for Colored {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         let color;
 
-        if Self::ansi_color_disabled_memoized() {
+        let disabled = COLOR_OVERRIDE
+            .with(Cell::get)
+            .map_or_else(Self::ansi_color_disabled_memoized, |enabled| !enabled);
+        if disabled || crate::accessibility::screen_reader_mode() {
             return Ok(());
         }
 
+        let (ansi256, bright) = crate::capabilities::query(|c| (c.ansi256, c.bright_colors));
         match *self {
             Colored::ForegroundColor(new_color) => {
                 if new_color == Color::Reset {
                     return f.write_str("39");
+                } else if !ansi256 {
+                    return write_base_color(f, 30, new_color, bright);
                 } else {
                     f.write_str("38;")?;
                     color = new_color;
@@ -113,6 +131,8 @@ impl fmt::Display for Colored {
             Colored::BackgroundColor(new_color) => {
                 if new_color == Color::Reset {
                     return f.write_str("49");
+                } else if !ansi256 {
+                    return write_base_color(f, 40, new_color, bright);
                 } else {
                     f.write_str("48;")?;
                     color = new_color;
@@ -145,15 +165,38 @@ impl fmt::Display for Colored {
             Color::DarkCyan => f.write_str("5;6"),
             Color::White => f.write_str("5;15"),
             Color::Grey => f.write_str("5;7"),
-            Color::Rgb { r, g, b } => write!(f, "2;{r};{g};{b}"),
+            Color::Rgb { r, g, b } if crate::capabilities::query(|c| c.truecolor) => {
+                write!(f, "2;{r};{g};{b}")
+            }
+            Color::Rgb { .. } => match color.downgrade(ColorLevel::Ansi256) {
+                Some(Color::AnsiValue(val)) => write!(f, "5;{val}"),
+                _ => Ok(()),
+            },
             Color::AnsiValue(val) => write!(f, "5;{val}"),
             _ => Ok(()),
         }
     }
 }
 
+/// Writes the closest base color with the original 8 color SGR parameters, starting at `first`,
+/// and their `bright` variants 60 above, or the dark ones instead.
+fn write_base_color(f: &mut Formatter<'_>, first: u8, color: Color, bright: bool) -> fmt::Result {
+    let index = color
+        .downgrade(ColorLevel::Ansi16)
+        .and_then(crate::style::color_level::base_index);
+    match index {
+        Some(index) if index < 8 => write!(f, "{}", first + index),
+        Some(index) if bright => write!(f, "{}", first + 60 + index - 8),
+        Some(index) => write!(f, "{}", first + index - 8),
+        None => Ok(()),
+    }
+}
+
 #[cfg(test)]
 mod tests {
+    use std::fmt;
+
+    use super::write_base_color;
     use crate::style::{Color, Colored};
 
     fn check_format_color(colored: Colored, expected: &str) {
@@ -317,4 +360,29 @@ mod tests {
         std::env::remove_var("NO_COLOR");
         assert!(!Colored::ansi_color_disabled());
     }
+
+    #[test]
+    fn test_format_base_color() {
+        struct Base(u8, Color, bool);
+
+        impl fmt::Display for Base {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                write_base_color(f, self.0, self.1, self.2)
+            }
+        }
+
+        assert_eq!(Base(30, Color::DarkRed, true).to_string(), "31");
+        assert_eq!(Base(40, Color::Grey, true).to_string(), "47");
+        assert_eq!(Base(30, Color::Red, true).to_string(), "91");
+        assert_eq!(Base(40, Color::AnsiValue(15), true).to_string(), "107");
+        assert_eq!(
+            Base(30, Color::Rgb { r: 0, g: 0, b: 0 }, true).to_string(),
+            "30"
+        );
+
+        // a VT100 doesn't know the bright colors
+        assert_eq!(Base(30, Color::DarkRed, false).to_string(), "31");
+        assert_eq!(Base(30, Color::Red, false).to_string(), "31");
+        assert_eq!(Base(40, Color::AnsiValue(15), false).to_string(), "47");
+    }
 }
@@ -0,0 +1,64 @@
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use super::Attribute;
+
+/// The style of an underline.
+///
+/// Unlike the underline attributes, e.g. [`Attribute::Undercurled`], of which several can be set
+/// in [`Attributes`](struct.Attributes.html), there is only one underline style, see
+/// [`ContentStyle::underline`](struct.ContentStyle.html#structfield.underline) and
+/// [`SetUnderlineStyle`](struct.SetUnderlineStyle.html).
+///
+/// Terminals without the styles show a single underline, see
+/// [`AttributeFallbacks`](struct.AttributeFallbacks.html).
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Ord, PartialOrd, Hash)]
+pub enum UnderlineStyle {
+    /// A single line, see [`Attribute::Underlined`].
+    Single,
+    /// A double line, see [`Attribute::DoubleUnderlined`].
+    Double,
+    /// A curly line, see [`Attribute::Undercurled`].
+    Curly,
+    /// A dotted line, see [`Attribute::Underdotted`].
+    Dotted,
+    /// A dashed line, see [`Attribute::Underdashed`].
+    Dashed,
+}
+
+impl UnderlineStyle {
+    /// The underline styles.
+    pub const ALL: [UnderlineStyle; 5] = [
+        UnderlineStyle::Single,
+        UnderlineStyle::Double,
+        UnderlineStyle::Curly,
+        UnderlineStyle::Dotted,
+        UnderlineStyle::Dashed,
+    ];
+
+    /// Returns the attribute which sets the style.
+    pub fn attribute(self) -> Attribute {
+        match self {
+            UnderlineStyle::Single => Attribute::Underlined,
+            UnderlineStyle::Double => Attribute::DoubleUnderlined,
+            UnderlineStyle::Curly => Attribute::Undercurled,
+            UnderlineStyle::Dotted => Attribute::Underdotted,
+            UnderlineStyle::Dashed => Attribute::Underdashed,
+        }
+    }
+
+    /// Returns the style an underline attribute sets, `None` for other attributes.
+    pub fn from_attribute(attribute: Attribute) -> Option<UnderlineStyle> {
+        UnderlineStyle::ALL
+            .iter()
+            .copied()
+            .find(|style| style.attribute() == attribute)
+    }
+}
+
+impl From<UnderlineStyle> for Attribute {
+    fn from(style: UnderlineStyle) -> Self {
+        style.attribute()
+    }
+}
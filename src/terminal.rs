@@ -83,7 +83,10 @@
 //!
 //! For manual execution control check out [crossterm::queue](../macro.queue.html).
 
-use std::{fmt, io};
+use std::{
+    fmt, io,
+    sync::atomic::{AtomicBool, Ordering},
+};
 
 #[cfg(windows)]
 use crossterm_winapi::{ConsoleMode, Handle, ScreenBuffer};
@@ -94,13 +97,94 @@ use winapi::um::wincon::ENABLE_WRAP_AT_EOL_OUTPUT;
 
 #[doc(no_inline)]
 use crate::Command;
-use crate::{csi, impl_display};
-
+use crate::{
+    capabilities::{Multiplexer, MultiplexerPolicy},
+    command::write_multiplexed_with,
+    csi, impl_display,
+};
+
+#[cfg(feature = "restore-on-exit")]
+mod exit;
+#[cfg(unix)]
+mod handle;
+mod remote;
+mod snapshot;
 pub(crate) mod sys;
 
+#[cfg(feature = "restore-on-exit")]
+pub use exit::restore_on_exit;
+#[cfg(unix)]
+pub use handle::Terminal;
+pub use remote::RemoteTerminal;
+pub use snapshot::{restore, snapshot, TerminalSnapshot};
 #[cfg(feature = "events")]
 pub use sys::supports_keyboard_enhancement;
 
+/// Queries whether the terminal supports sixel graphics.
+///
+/// The terminal reports sixel support in its primary device attributes (DA1). The result can be
+/// installed in the [`Capabilities`](../capabilities/struct.Capabilities.html), which
+/// [`PrintSixel`](../graphics/struct.PrintSixel.html) consults.
+///
+/// On unix systems, this function will block and possibly time out while
+/// [`crossterm::event::read`](crate::event::read) or [`crossterm::event::poll`](crate::event::poll) are being called.
+///
+/// This always returns `Ok(false)` on Windows.
+#[cfg(feature = "events")]
+pub fn supports_sixel() -> io::Result<bool> {
+    #[cfg(unix)]
+    {
+        Ok(sys::primary_device_attributes()?.contains(&4))
+    }
+
+    #[cfg(windows)]
+    {
+        Ok(false)
+    }
+}
+
+/// Queries whether the terminal supports synchronized output (DEC private mode 2026), see
+/// [`BeginSynchronizedUpdate`](struct.BeginSynchronizedUpdate.html).
+///
+/// The terminal is asked for the setting of the mode with DECRQM. The result can be installed in
+/// the [`Capabilities`](../capabilities/struct.Capabilities.html#structfield.synchronized_output),
+/// which [`SynchronizedWriter`](../struct.SynchronizedWriter.html) consults.
+///
+/// On unix systems, this function will block and possibly time out while
+/// [`crossterm::event::read`](crate::event::read) or [`crossterm::event::poll`](crate::event::poll) are being called.
+///
+/// This always returns `Ok(false)` on Windows.
+///
+/// # Examples
+///
+/// ```no_run
+/// use std::io;
+/// use crossterm::{capabilities, terminal};
+///
+/// fn main() -> io::Result<()> {
+///     let mut capabilities = capabilities::from_env().capabilities();
+///     capabilities.synchronized_output = terminal::supports_synchronized_output()?;
+///     capabilities::set(capabilities);
+///     Ok(())
+/// }
+/// ```
+#[cfg(feature = "events")]
+pub fn supports_synchronized_output() -> io::Result<bool> {
+    #[cfg(unix)]
+    {
+        // the mode isn't recognized (0) or permanently reset (4)
+        Ok(matches!(
+            sys::query_private_mode(2026)?,
+            Some(1) | Some(2) | Some(3)
+        ))
+    }
+
+    #[cfg(windows)]
+    {
+        Ok(false)
+    }
+}
+
 /// Tells whether the raw mode is enabled.
 ///
 /// Please have a look at the [raw mode](./index.html#raw-mode) section.
@@ -130,6 +214,154 @@ pub fn disable_raw_mode() -> io::Result<()> {
     sys::disable_raw_mode()
 }
 
+/// Returns a [`RawModeBuilder`] to enable a raw mode which keeps some of the terminal's
+/// processing enabled.
+///
+/// # Examples
+///
+/// ```no_run
+/// use std::io;
+/// use crossterm::terminal;
+///
+/// fn main() -> io::Result<()> {
+///     // Ctrl-C still interrupts the process.
+///     terminal::raw_mode_builder().keep_signals(true).enable()?;
+///
+///     terminal::disable_raw_mode()
+/// }
+/// ```
+pub fn raw_mode_builder() -> RawModeBuilder {
+    RawModeBuilder::default()
+}
+
+/// Builds a partial raw mode, see [`raw_mode_builder`].
+///
+/// By default nothing is kept, which is the same as [`enable_raw_mode`]. The raw mode is
+/// disabled with [`disable_raw_mode`] as usual.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct RawModeBuilder {
+    pub(crate) keep_signals: bool,
+    pub(crate) keep_output_processing: bool,
+    pub(crate) keep_flow_control: bool,
+}
+
+impl RawModeBuilder {
+    /// Keeps the signal generating characters, so Ctrl-C still interrupts the process.
+    ///
+    /// * Unix: keeps `ISIG`.
+    /// * Windows: keeps `ENABLE_PROCESSED_INPUT`.
+    pub fn keep_signals(mut self, keep: bool) -> RawModeBuilder {
+        self.keep_signals = keep;
+        self
+    }
+
+    /// Keeps the output processing, so `\n` still moves the cursor to the start of the next line.
+    ///
+    /// * Unix: keeps `OPOST`.
+    /// * Windows: does nothing, raw mode doesn't change the output mode.
+    pub fn keep_output_processing(mut self, keep: bool) -> RawModeBuilder {
+        self.keep_output_processing = keep;
+        self
+    }
+
+    /// Keeps the software flow control, so Ctrl-S and Ctrl-Q still pause and resume the output.
+    ///
+    /// * Unix: keeps `IXON`.
+    /// * Windows: does nothing, the console has no flow control.
+    pub fn keep_flow_control(mut self, keep: bool) -> RawModeBuilder {
+        self.keep_flow_control = keep;
+        self
+    }
+
+    /// Enables the raw mode.
+    ///
+    /// Like [`enable_raw_mode`], this does nothing if the raw mode is already enabled.
+    pub fn enable(self) -> io::Result<()> {
+        sys::enable_raw_mode_with(self)
+    }
+}
+
+/// Disables the software flow control, so Ctrl-S and Ctrl-Q are read as input instead of
+/// pausing and resuming the output.
+///
+/// Unlike the raw mode, this leaves everything else, like the line editing, untouched.
+///
+/// * Unix: clears `IXON` and `IXOFF`.
+/// * Windows: does nothing, the console has no flow control.
+pub fn disable_flow_control() -> io::Result<()> {
+    #[cfg(unix)]
+    {
+        sys::set_flow_control(false)
+    }
+
+    #[cfg(windows)]
+    {
+        Ok(())
+    }
+}
+
+/// Enables the software flow control again, see [`disable_flow_control`].
+///
+/// * Unix: sets `IXON`, and `IXOFF` if it was set before [`disable_flow_control`] cleared it.
+/// * Windows: does nothing, the console has no flow control.
+pub fn enable_flow_control() -> io::Result<()> {
+    #[cfg(unix)]
+    {
+        sys::set_flow_control(true)
+    }
+
+    #[cfg(windows)]
+    {
+        Ok(())
+    }
+}
+
+/// The terminal settings returned by [`save_settings`].
+///
+/// These are the termios attributes on Unix and the console modes on Windows.
+#[derive(Clone)]
+pub struct TerminalSettings(sys::Settings);
+
+impl std::fmt::Debug for TerminalSettings {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TerminalSettings").finish_non_exhaustive()
+    }
+}
+
+/// Saves the current terminal settings.
+///
+/// Unlike [`disable_raw_mode`], which only undoes [`enable_raw_mode`], [`restore_settings`]
+/// restores the exact state at the time of this call. This is useful when the terminal is
+/// temporarily handed to a child process.
+///
+/// # Examples
+///
+/// ```no_run
+/// use std::{io, process};
+/// use crossterm::terminal;
+///
+/// fn main() -> io::Result<()> {
+///     terminal::enable_raw_mode()?;
+///
+///     let saved = terminal::save_settings()?;
+///     terminal::disable_raw_mode()?;
+///     process::Command::new("vi").status()?;
+///     terminal::restore_settings(&saved)?;
+///
+///     terminal::disable_raw_mode()
+/// }
+/// ```
+pub fn save_settings() -> io::Result<TerminalSettings> {
+    sys::save_settings().map(TerminalSettings)
+}
+
+/// Restores terminal settings returned by [`save_settings`].
+///
+/// This includes whether the raw mode is enabled.
+pub fn restore_settings(saved: &TerminalSettings) -> io::Result<()> {
+    sys::restore_settings(&saved.0)
+}
+
 /// Returns the terminal size `(columns, rows)`.
 ///
 /// The top left cell is represented `(1, 1)`.
@@ -154,12 +386,117 @@ pub fn window_size() -> io::Result<WindowSize> {
     sys::window_size()
 }
 
+/// Returns the size of a cell `(width, height)` in pixels, e.g. to convert the size of an image
+/// to cells.
+///
+/// On Unix the terminal is asked with `CSI 16 t`. If it doesn't answer, the size is computed from
+/// the [`window_size`](fn.window_size.html), which not all terminals report. On Windows this is
+/// the size of the [`console_font`](fn.console_font.html).
+///
+/// On unix systems, this function will block and possibly time out while
+/// [`crossterm::event::read`](crate::event::read) or [`crossterm::event::poll`](crate::event::poll) are being called.
+pub fn cell_size() -> io::Result<(u16, u16)> {
+    sys::cell_size()
+}
+
+/// A cell of the Windows console screen buffer.
+#[cfg(windows)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ScreenBufferCell {
+    /// The UTF-16 code unit displayed in the cell.
+    ///
+    /// Characters outside of the Basic Multilingual Plane occupy two cells, one with each half
+    /// of the surrogate pair.
+    pub character: u16,
+    /// The character attributes (colors, `COMMON_LVB_*` flags) of the cell.
+    pub attributes: u16,
+}
+
+/// Reads a rectangle of cells from the console screen buffer.
+///
+/// The rectangle starts at `(column, row)` of the screen buffer, not the visible window, and is
+/// `width` cells wide and `height` cells high. The cells are returned row by row. Cells outside of
+/// the screen buffer are returned as [`ScreenBufferCell::default`](struct.ScreenBufferCell.html).
+///
+/// The cells can be written back with [`write_screen_buffer`](fn.write_screen_buffer.html), e.g.
+/// to restore what an overlay covered.
+///
+/// # Notes
+///
+/// This is only available on Windows, there is no equivalent ANSI sequence.
+#[cfg(windows)]
+pub fn read_screen_buffer(
+    column: u16,
+    row: u16,
+    width: u16,
+    height: u16,
+) -> io::Result<Vec<ScreenBufferCell>> {
+    sys::read_screen_buffer(column, row, width, height)
+}
+
+/// Writes a rectangle of cells to the console screen buffer.
+///
+/// The rectangle starts at `(column, row)` of the screen buffer and is `width` cells wide, `cells`
+/// holds its rows one after another. Cells outside of the screen buffer are ignored.
+///
+/// # Notes
+///
+/// This is only available on Windows, there is no equivalent ANSI sequence.
+#[cfg(windows)]
+pub fn write_screen_buffer(
+    column: u16,
+    row: u16,
+    width: u16,
+    cells: &[ScreenBufferCell],
+) -> io::Result<()> {
+    sys::write_screen_buffer(column, row, width, cells)
+}
+
+/// The font of the Windows console.
+#[cfg(windows)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ConsoleFont {
+    /// The name of the typeface, e.g. `Consolas`, at most 31 UTF-16 code units long.
+    pub face_name: String,
+    /// The width of a character cell in pixels, `0` lets the console pick it for the height.
+    pub width: u16,
+    /// The height of a character cell in pixels.
+    pub height: u16,
+    /// The weight of the font, between 100 and 1000, e.g. 400 for normal and 700 for bold.
+    pub weight: u32,
+    /// The font pitch and family, see the `TMPF_*` and `FF_*` constants of the Windows API.
+    pub family: u32,
+}
+
+/// Returns the font of the Windows console.
+///
+/// # Notes
+///
+/// This is only available on Windows, in a classic console window. Pseudo consoles (e.g. Windows
+/// Terminal) and MSYS2 ptys don't have a font the application can control, an error of kind
+/// [`Unsupported`](std::io::ErrorKind::Unsupported) is returned for them.
+#[cfg(windows)]
+pub fn console_font() -> io::Result<ConsoleFont> {
+    sys::console_font()
+}
+
+/// Sets the font of the Windows console, e.g. to enlarge the text.
+///
+/// # Notes
+///
+/// See [`console_font`](fn.console_font.html).
+#[cfg(windows)]
+pub fn set_console_font(font: &ConsoleFont) -> io::Result<()> {
+    sys::set_console_font(font)
+}
+
 /// Disables line wrapping.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct DisableLineWrap;
 
 impl Command for DisableLineWrap {
     fn write_ansi(&self, f: &mut impl fmt::Write) -> fmt::Result {
+        crate::write_terminfo!(f, "rmam");
         f.write_str(csi!("?7l"))
     }
 
@@ -179,6 +516,7 @@ pub struct EnableLineWrap;
 
 impl Command for EnableLineWrap {
     fn write_ansi(&self, f: &mut impl fmt::Write) -> fmt::Result {
+        crate::write_terminfo!(f, "smam");
         f.write_str(csi!("?7h"))
     }
 
@@ -192,12 +530,91 @@ impl Command for EnableLineWrap {
     }
 }
 
+/// A command that disables the auto-repeat of held keys (DECARM).
+///
+/// Games which track held keys with press and release events, see
+/// [`KeyboardEnhancementFlags::REPORT_EVENT_TYPES`](../event/struct.KeyboardEnhancementFlags.html#associatedconstant.REPORT_EVENT_TYPES),
+/// don't want the repeated presses. Terminals which don't support the mode ignore it.
+///
+/// # Notes
+///
+/// * Commands must be executed/queued for execution otherwise they do nothing.
+/// * The mode affects all programs in the terminal, so enable it again with
+///   [`EnableAutoRepeat`](struct.EnableAutoRepeat.html) before exiting.
+///   `restore_on_exit` of the `restore-on-exit` feature does so as well.
+/// * Windows: does nothing with the legacy Windows API.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DisableAutoRepeat;
+
+impl Command for DisableAutoRepeat {
+    fn write_ansi(&self, f: &mut impl fmt::Write) -> fmt::Result {
+        crate::command::tracked(|| AUTO_REPEAT_DISABLED.store(true, Ordering::Relaxed));
+        f.write_str(csi!("?8l"))
+    }
+
+    #[cfg(windows)]
+    fn execute_winapi(&self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// A command that enables the auto-repeat of held keys (DECARM) again, see
+/// [`DisableAutoRepeat`](struct.DisableAutoRepeat.html).
+///
+/// # Notes
+///
+/// * Commands must be executed/queued for execution otherwise they do nothing.
+/// * Windows: does nothing with the legacy Windows API.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EnableAutoRepeat;
+
+impl Command for EnableAutoRepeat {
+    fn write_ansi(&self, f: &mut impl fmt::Write) -> fmt::Result {
+        crate::command::tracked(|| AUTO_REPEAT_DISABLED.store(false, Ordering::Relaxed));
+        f.write_str(csi!("?8h"))
+    }
+
+    #[cfg(windows)]
+    fn execute_winapi(&self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Whether the auto-repeat was disabled with [`DisableAutoRepeat`].
+static AUTO_REPEAT_DISABLED: AtomicBool = AtomicBool::new(false);
+
+/// Tells whether the auto-repeat of held keys is disabled.
+///
+/// This is tracked from the [`DisableAutoRepeat`] and [`EnableAutoRepeat`] commands queued on a
+/// [`ProcessTerminal`](crate::ProcessTerminal), the terminal isn't asked.
+pub fn is_auto_repeat_disabled() -> bool {
+    AUTO_REPEAT_DISABLED.load(Ordering::Relaxed)
+}
+
+/// Whether the alternate screen was entered with [`EnterAlternateScreen`] and not left yet.
+static ALTERNATE_SCREEN_ACTIVE: AtomicBool = AtomicBool::new(false);
+
+/// Tells whether the alternate screen is active.
+///
+/// This is tracked from the [`EnterAlternateScreen`] and [`LeaveAlternateScreen`] commands
+/// queued on a [`ProcessTerminal`](crate::ProcessTerminal), the terminal isn't asked.
+pub fn is_alternate_screen_active() -> bool {
+    ALTERNATE_SCREEN_ACTIVE.load(Ordering::Relaxed)
+}
+
+pub(crate) fn set_alternate_screen_active(active: bool) {
+    ALTERNATE_SCREEN_ACTIVE.store(active, Ordering::Relaxed);
+}
+
 /// A command that switches to alternate screen.
 ///
 /// # Notes
 ///
 /// * Commands must be executed/queued for execution otherwise they do nothing.
 /// * Use [LeaveAlternateScreen](./struct.LeaveAlternateScreen.html) command to leave the entered alternate screen.
+/// * Does nothing if the installed
+///   [capabilities](../capabilities/struct.Capabilities.html#structfield.alternate_screen) have
+///   no alternate screen.
 ///
 /// # Examples
 ///
@@ -219,13 +636,29 @@ pub struct EnterAlternateScreen;
 
 impl Command for EnterAlternateScreen {
     fn write_ansi(&self, f: &mut impl fmt::Write) -> fmt::Result {
-        f.write_str(csi!("?1049h"))
+        fn write_smcup(f: &mut impl fmt::Write) -> fmt::Result {
+            crate::write_terminfo!(f, "smcup");
+            f.write_str(csi!("?1049h"))
+        }
+
+        if !crate::capabilities::query(|c| c.alternate_screen) {
+            return Ok(());
+        }
+        write_smcup(f)?;
+        crate::command::tracked(|| set_alternate_screen_active(true));
+        #[cfg(feature = "events")]
+        crate::event::switch_keyboard_flags_screen(f, true)?;
+        Ok(())
     }
 
     #[cfg(windows)]
     fn execute_winapi(&self) -> io::Result<()> {
+        if !crate::capabilities::query(|c| c.alternate_screen) {
+            return Ok(());
+        }
         let alternate_screen = ScreenBuffer::create()?;
         alternate_screen.show()?;
+        set_alternate_screen_active(true);
         Ok(())
     }
 }
@@ -236,6 +669,9 @@ impl Command for EnterAlternateScreen {
 ///
 /// * Commands must be executed/queued for execution otherwise they do nothing.
 /// * Use [EnterAlternateScreen](./struct.EnterAlternateScreen.html) to enter the alternate screen.
+/// * Does nothing if the installed
+///   [capabilities](../capabilities/struct.Capabilities.html#structfield.alternate_screen) have
+///   no alternate screen.
 ///
 /// # Examples
 ///
@@ -257,13 +693,29 @@ pub struct LeaveAlternateScreen;
 
 impl Command for LeaveAlternateScreen {
     fn write_ansi(&self, f: &mut impl fmt::Write) -> fmt::Result {
-        f.write_str(csi!("?1049l"))
+        fn write_rmcup(f: &mut impl fmt::Write) -> fmt::Result {
+            crate::write_terminfo!(f, "rmcup");
+            f.write_str(csi!("?1049l"))
+        }
+
+        if !crate::capabilities::query(|c| c.alternate_screen) {
+            return Ok(());
+        }
+        write_rmcup(f)?;
+        crate::command::tracked(|| set_alternate_screen_active(false));
+        #[cfg(feature = "events")]
+        crate::event::switch_keyboard_flags_screen(f, false)?;
+        Ok(())
     }
 
     #[cfg(windows)]
     fn execute_winapi(&self) -> io::Result<()> {
+        if !crate::capabilities::query(|c| c.alternate_screen) {
+            return Ok(());
+        }
         let screen_buffer = ScreenBuffer::from(Handle::current_out_handle()?);
         screen_buffer.show()?;
+        set_alternate_screen_active(false);
         Ok(())
     }
 }
@@ -297,6 +749,7 @@ pub struct ScrollUp(pub u16);
 impl Command for ScrollUp {
     fn write_ansi(&self, f: &mut impl fmt::Write) -> fmt::Result {
         if self.0 != 0 {
+            crate::write_terminfo!(f, "indn", self.0);
             write!(f, csi!("{}S"), self.0)?;
         }
         Ok(())
@@ -319,6 +772,7 @@ pub struct ScrollDown(pub u16);
 impl Command for ScrollDown {
     fn write_ansi(&self, f: &mut impl fmt::Write) -> fmt::Result {
         if self.0 != 0 {
+            crate::write_terminfo!(f, "rin", self.0);
             write!(f, csi!("{}T"), self.0)?;
         }
         Ok(())
@@ -330,6 +784,178 @@ impl Command for ScrollDown {
     }
 }
 
+/// A command that limits scrolling to the rows from `top` to `bottom`, both included and starting
+/// at `0`.
+///
+/// [`ScrollUp`](struct.ScrollUp.html), [`ScrollDown`](struct.ScrollDown.html) and new lines at
+/// the bottom only move the rows of the region. The cursor is moved to the top left corner.
+///
+/// # Notes
+///
+/// Commands must be executed/queued for execution otherwise they do nothing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SetScrollRegion(pub u16, pub u16);
+
+impl Command for SetScrollRegion {
+    fn write_ansi(&self, f: &mut impl fmt::Write) -> fmt::Result {
+        crate::write_terminfo!(f, "csr", self.0, self.1);
+        write!(f, csi!("{};{}r"), self.0 + 1, self.1 + 1)
+    }
+
+    #[cfg(windows)]
+    fn execute_winapi(&self) -> io::Result<()> {
+        Err(crate::Error::UnsupportedCapability(
+            "Scroll regions are not supported by the legacy Windows API.",
+        )
+        .into())
+    }
+}
+
+/// A command that makes the whole screen the scroll region again, see
+/// [`SetScrollRegion`](struct.SetScrollRegion.html).
+///
+/// The cursor is moved to the top left corner.
+///
+/// # Notes
+///
+/// Commands must be executed/queued for execution otherwise they do nothing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ResetScrollRegion;
+
+impl Command for ResetScrollRegion {
+    fn write_ansi(&self, f: &mut impl fmt::Write) -> fmt::Result {
+        f.write_str(csi!("r"))
+    }
+
+    #[cfg(windows)]
+    fn execute_winapi(&self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// A command that allows left and right margins (DECLRMM), which are set with
+/// [`SetLeftRightMargins`](struct.SetLeftRightMargins.html).
+///
+/// # Notes
+///
+/// Commands must be executed/queued for execution otherwise they do nothing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EnableLeftRightMargins;
+
+impl Command for EnableLeftRightMargins {
+    fn write_ansi(&self, f: &mut impl fmt::Write) -> fmt::Result {
+        f.write_str(csi!("?69h"))
+    }
+
+    #[cfg(windows)]
+    fn execute_winapi(&self) -> io::Result<()> {
+        Err(crate::Error::UnsupportedCapability(
+            "Margins are not supported by the legacy Windows API.",
+        )
+        .into())
+    }
+}
+
+/// A command that disallows left and right margins, which resets them to the whole width of the
+/// screen, see [`EnableLeftRightMargins`](struct.EnableLeftRightMargins.html).
+///
+/// # Notes
+///
+/// Commands must be executed/queued for execution otherwise they do nothing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DisableLeftRightMargins;
+
+impl Command for DisableLeftRightMargins {
+    fn write_ansi(&self, f: &mut impl fmt::Write) -> fmt::Result {
+        f.write_str(csi!("?69l"))
+    }
+
+    #[cfg(windows)]
+    fn execute_winapi(&self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// A command that limits scrolling and printing to the columns from `left` to `right`, both
+/// included and starting at `0` (DECSLRM).
+///
+/// Together with a [`SetScrollRegion`](struct.SetScrollRegion.html), the scroll region is a
+/// rectangle, so a panel scrolls with [`ScrollUp`](struct.ScrollUp.html) without repainting it.
+/// Text wraps at the right margin. The cursor is moved to the top left corner.
+///
+/// # Notes
+///
+/// * Commands must be executed/queued for execution otherwise they do nothing.
+/// * The margins must be allowed with
+///   [`EnableLeftRightMargins`](struct.EnableLeftRightMargins.html) first, otherwise terminals
+///   take the sequence for saving the cursor position.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SetLeftRightMargins(pub u16, pub u16);
+
+impl Command for SetLeftRightMargins {
+    fn write_ansi(&self, f: &mut impl fmt::Write) -> fmt::Result {
+        write!(f, csi!("{};{}s"), self.0 + 1, self.1 + 1)
+    }
+
+    #[cfg(windows)]
+    fn execute_winapi(&self) -> io::Result<()> {
+        Err(crate::Error::UnsupportedCapability(
+            "Margins are not supported by the legacy Windows API.",
+        )
+        .into())
+    }
+}
+
+/// A command that makes cursor positions relative to the margins and keeps the cursor within them
+/// (DECOM).
+///
+/// [`MoveTo(0, 0)`](../cursor/struct.MoveTo.html) moves the cursor to the top left corner of the
+/// [`SetScrollRegion`](struct.SetScrollRegion.html) and
+/// [`SetLeftRightMargins`](struct.SetLeftRightMargins.html) rectangle, so a panel is drawn with
+/// the same positions wherever it is. The cursor is moved to that corner.
+///
+/// # Notes
+///
+/// Commands must be executed/queued for execution otherwise they do nothing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EnableOriginMode;
+
+impl Command for EnableOriginMode {
+    fn write_ansi(&self, f: &mut impl fmt::Write) -> fmt::Result {
+        f.write_str(csi!("?6h"))
+    }
+
+    #[cfg(windows)]
+    fn execute_winapi(&self) -> io::Result<()> {
+        Err(crate::Error::UnsupportedCapability(
+            "The origin mode is not supported by the legacy Windows API.",
+        )
+        .into())
+    }
+}
+
+/// A command that makes cursor positions relative to the screen again, see
+/// [`EnableOriginMode`](struct.EnableOriginMode.html).
+///
+/// The cursor is moved to the top left corner.
+///
+/// # Notes
+///
+/// Commands must be executed/queued for execution otherwise they do nothing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DisableOriginMode;
+
+impl Command for DisableOriginMode {
+    fn write_ansi(&self, f: &mut impl fmt::Write) -> fmt::Result {
+        f.write_str(csi!("?6l"))
+    }
+
+    #[cfg(windows)]
+    fn execute_winapi(&self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
 /// A command that clears the terminal screen buffer.
 ///
 /// See the [`ClearType`](enum.ClearType.html) enum.
@@ -342,6 +968,15 @@ pub struct Clear(pub ClearType);
 
 impl Command for Clear {
     fn write_ansi(&self, f: &mut impl fmt::Write) -> fmt::Result {
+        match self.0 {
+            ClearType::FromCursorDown => {
+                crate::write_terminfo!(f, "ed");
+            }
+            ClearType::UntilNewLine => {
+                crate::write_terminfo!(f, "el");
+            }
+            _ => {}
+        }
         f.write_str(match self.0 {
             ClearType::All => csi!("2J"),
             ClearType::Purge => csi!("3J"),
@@ -379,6 +1014,14 @@ impl Command for SetSize {
 
 /// A command that sets the terminal title
 ///
+/// Inside tmux or GNU screen, see
+/// [`Capabilities::multiplexer`](../capabilities/struct.Capabilities.html#structfield.multiplexer),
+/// the title of the multiplexer's window is set as well, with `ESC k title ESC \`. The title of
+/// the terminal the multiplexer runs in is set as the
+/// [`MultiplexerPolicy`](../capabilities/enum.MultiplexerPolicy.html) says, by default with the
+/// passthrough sequence of the multiplexer. tmux only renames the window if its
+/// `allow-rename` option is on.
+///
 /// # Notes
 ///
 /// Commands must be executed/queued for execution otherwise they do nothing.
@@ -387,7 +1030,9 @@ pub struct SetTitle<T>(pub T);
 
 impl<T: fmt::Display> Command for SetTitle<T> {
     fn write_ansi(&self, f: &mut impl fmt::Write) -> fmt::Result {
-        write!(f, "\x1B]0;{}\x07", &self.0)
+        let (multiplexer, policy) =
+            crate::capabilities::query(|c| (c.multiplexer, c.multiplexer_policy));
+        write_title(f, &self.0, multiplexer, policy)
     }
 
     #[cfg(windows)]
@@ -396,6 +1041,23 @@ impl<T: fmt::Display> Command for SetTitle<T> {
     }
 }
 
+/// Writes the sequences which set the title inside `multiplexer`, if any.
+fn write_title<W: fmt::Write>(
+    f: &mut W,
+    title: &impl fmt::Display,
+    multiplexer: Option<Multiplexer>,
+    policy: MultiplexerPolicy,
+) -> fmt::Result {
+    use fmt::Write;
+
+    if let Some(Multiplexer::Tmux) | Some(Multiplexer::Screen) = multiplexer {
+        write!(f, "\x1Bk{}\x1B\\", title)?;
+    }
+    write_multiplexed_with(f, multiplexer, policy, |f| {
+        write!(f, "\x1B]0;{}\x07", title)
+    })
+}
+
 /// A command that instructs the terminal emulator to begin a synchronized frame.
 ///
 /// # Notes
@@ -504,6 +1166,13 @@ impl Command for EndSynchronizedUpdate {
 
 impl_display!(for ScrollUp);
 impl_display!(for ScrollDown);
+impl_display!(for SetScrollRegion);
+impl_display!(for ResetScrollRegion);
+impl_display!(for EnableLeftRightMargins);
+impl_display!(for DisableLeftRightMargins);
+impl_display!(for SetLeftRightMargins);
+impl_display!(for EnableOriginMode);
+impl_display!(for DisableOriginMode);
 impl_display!(for SetSize);
 impl_display!(for Clear);
 
@@ -511,7 +1180,7 @@ impl_display!(for Clear);
 mod tests {
     use std::{io::stdout, thread, time};
 
-    use crate::execute;
+    use crate::{command::with_tracking, execute};
 
     use super::*;
 
@@ -537,6 +1206,51 @@ mod tests {
         assert_eq!((width, height), size().unwrap());
     }
 
+    #[test]
+    fn test_set_title_in_multiplexer() {
+        let title = |multiplexer, policy| {
+            let mut sequence = String::new();
+            write_title(&mut sequence, &"foo", multiplexer, policy).unwrap();
+            sequence
+        };
+
+        assert_eq!(
+            title(None, MultiplexerPolicy::Passthrough),
+            "\x1B]0;foo\x07"
+        );
+        assert_eq!(
+            title(Some(Multiplexer::Tmux), MultiplexerPolicy::Passthrough),
+            "\x1Bkfoo\x1B\\\x1BPtmux;\x1B\x1B]0;foo\x07\x1B\\"
+        );
+        assert_eq!(
+            title(Some(Multiplexer::Screen), MultiplexerPolicy::Passthrough),
+            "\x1Bkfoo\x1B\\\x1BP\x1B]0;foo\x07\x1B\\"
+        );
+        assert_eq!(
+            title(Some(Multiplexer::Screen), MultiplexerPolicy::Suppress),
+            "\x1Bkfoo\x1B\\"
+        );
+        assert_eq!(
+            title(Some(Multiplexer::Zellij), MultiplexerPolicy::Passthrough),
+            "\x1B]0;foo\x07"
+        );
+    }
+
+    #[test]
+    #[serial_test::serial]
+    fn test_auto_repeat() {
+        assert_eq!(DisableAutoRepeat.ansi_string(), "\x1B[?8l");
+        assert!(!is_auto_repeat_disabled());
+
+        with_tracking(true, || DisableAutoRepeat.ansi_string());
+        assert!(is_auto_repeat_disabled());
+        assert_eq!(
+            with_tracking(true, || EnableAutoRepeat.ansi_string()),
+            "\x1B[?8h"
+        );
+        assert!(!is_auto_repeat_disabled());
+    }
+
     #[test]
     fn test_raw_mode() {
         // check we start from normal mode (may fail on some test harnesses)
@@ -565,4 +1279,19 @@ mod tests {
         // check we're back to normal mode
         assert!(!is_raw_mode_enabled().unwrap());
     }
+
+    #[test]
+    fn test_scroll_region() {
+        assert_eq!(SetScrollRegion(2, 9).ansi_string(), "\x1B[3;10r");
+        assert_eq!(ResetScrollRegion.ansi_string(), "\x1B[r");
+    }
+
+    #[test]
+    fn test_margins() {
+        assert_eq!(EnableLeftRightMargins.ansi_string(), "\x1B[?69h");
+        assert_eq!(SetLeftRightMargins(4, 39).ansi_string(), "\x1B[5;40s");
+        assert_eq!(DisableLeftRightMargins.ansi_string(), "\x1B[?69l");
+        assert_eq!(EnableOriginMode.ansi_string(), "\x1B[?6h");
+        assert_eq!(DisableOriginMode.ansi_string(), "\x1B[?6l");
+    }
 }
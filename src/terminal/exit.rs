@@ -0,0 +1,314 @@
+//! Restores the terminal when the process exits, see [`restore_on_exit`].
+
+use std::io;
+
+use parking_lot::Once;
+
+/// Leaves the alternate screen.
+const LEAVE_ALTERNATE_SCREEN: &[u8] = b"\x1B[?1049l";
+/// Shows the cursor.
+const SHOW_CURSOR: &[u8] = b"\x1B[?25h";
+/// Enables the auto-repeat of held keys.
+const ENABLE_AUTO_REPEAT: &[u8] = b"\x1B[?8h";
+/// Makes the cursor blink.
+const ENABLE_BLINKING: &[u8] = b"\x1B[?12h";
+/// Makes the cursor steady.
+const DISABLE_BLINKING: &[u8] = b"\x1B[?12l";
+
+static INSTALL: Once = Once::new();
+
+/// Restores the terminal when the process exits, as a safety net for cleanup paths an
+/// application forgets, e.g. a `std::process::exit` deep in the code.
+///
+/// The raw mode is disabled, the alternate screen entered with
+/// [`EnterAlternateScreen`](struct.EnterAlternateScreen.html) is left, the auto-repeat disabled
+/// with [`DisableAutoRepeat`](struct.DisableAutoRepeat.html) is enabled, the cursor blinking
+/// changed with [`EnableBlinking`](../cursor/struct.EnableBlinking.html) or
+/// [`DisableBlinking`](../cursor/struct.DisableBlinking.html) is restored and the cursor hidden
+/// with [`Hide`](../cursor/struct.Hide.html) is shown again when the process exits normally. The
+/// sequences are written to the terminal, not to stdout if it's redirected: on Unix to
+/// `/dev/tty`, on Windows nothing is written then. The original blinking is only known if it was
+/// asked for with [`cursor::query_cursor_blinking`](../cursor/fn.query_cursor_blinking.html)
+/// before, this doesn't ask the terminal. Only the first call installs the handlers, the later
+/// ones do nothing.
+///
+/// On Unix, `SIGTERM` and `SIGINT` are handled as well: the raw mode is disabled and the
+/// sequences are written with async-signal-safe calls only, as far as that's possible, and then
+/// the default action of the signal runs, which terminates the process. So don't call this if
+/// the application handles these signals itself. On Windows, the console control events like
+/// Ctrl+C and closing the window restore the terminal before the default handler runs.
+///
+/// # Examples
+///
+/// ```no_run
+/// use std::io;
+/// use crossterm::{execute, terminal};
+///
+/// fn main() -> io::Result<()> {
+///     terminal::restore_on_exit()?;
+///     terminal::enable_raw_mode()?;
+///     execute!(io::stdout(), terminal::EnterAlternateScreen)?;
+///
+///     // the terminal is restored even without cleanup
+///     std::process::exit(1)
+/// }
+/// ```
+pub fn restore_on_exit() -> io::Result<()> {
+    let mut result = Ok(());
+    INSTALL.call_once(|| result = sys::install());
+    result
+}
+
+/// Returns the sequence which restores the original cursor blinking, if it was changed and is
+/// known.
+fn original_blinking() -> Option<&'static [u8]> {
+    crate::cursor::cursor_blinking()?;
+    match crate::cursor::original_cursor_blinking()? {
+        true => Some(ENABLE_BLINKING),
+        false => Some(DISABLE_BLINKING),
+    }
+}
+
+/// Writes the sequences which undo the tracked changes with `write`, which may be called from a
+/// signal handler.
+fn write_restore_sequences(mut write: impl FnMut(&[u8])) {
+    if crate::terminal::is_alternate_screen_active() {
+        write(LEAVE_ALTERNATE_SCREEN);
+    }
+    if crate::terminal::is_auto_repeat_disabled() {
+        write(ENABLE_AUTO_REPEAT);
+    }
+    if let Some(blinking) = original_blinking() {
+        write(blinking);
+    }
+    if crate::cursor::is_cursor_hidden() {
+        write(SHOW_CURSOR);
+    }
+}
+
+/// Restores the terminal from ordinary code, e.g. when the process exits.
+fn restore() {
+    let _ = crate::terminal::disable_raw_mode();
+
+    sys::write_to_terminal(|write| write_restore_sequences(write));
+    crate::terminal::set_alternate_screen_active(false);
+}
+
+extern "C" fn restore_at_exit() {
+    restore();
+}
+
+#[cfg(unix)]
+mod sys {
+    use std::{
+        io,
+        os::unix::io::IntoRawFd,
+        sync::atomic::{AtomicI32, Ordering},
+    };
+
+    use libc::{SIGINT, SIGTERM, STDOUT_FILENO};
+    use signal_hook::low_level;
+
+    use super::{restore_at_exit, write_restore_sequences};
+
+    /// The terminal which is restored, opened up front, as `open` allocates. `-1` if there is
+    /// none, e.g. stdout is redirected in a process without a controlling terminal.
+    static TTY: AtomicI32 = AtomicI32::new(-1);
+
+    pub(super) fn install() -> io::Result<()> {
+        let tty = std::fs::OpenOptions::new()
+            .read(true)
+            .write(true)
+            .open("/dev/tty");
+        match tty {
+            // never closed, the signal handlers may use it at any time
+            Ok(tty) => TTY.store(tty.into_raw_fd(), Ordering::Relaxed),
+            Err(_) if unsafe { libc::isatty(STDOUT_FILENO) } == 1 => {
+                TTY.store(STDOUT_FILENO, Ordering::Relaxed)
+            }
+            Err(_) => {}
+        }
+
+        for signal in [SIGTERM, SIGINT] {
+            // SAFETY: the handler only calls async-signal-safe functions.
+            unsafe { low_level::register(signal, move || on_signal(signal)) }?;
+        }
+
+        if unsafe { libc::atexit(restore_at_exit) } != 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::Other,
+                "The exit handler could not be registered.",
+            ));
+        }
+        Ok(())
+    }
+
+    /// Calls `f` with a function which writes to the terminal, unless there is none, with
+    /// async-signal-safe calls only.
+    pub(super) fn write_to_terminal(f: impl FnOnce(&mut dyn FnMut(&[u8]))) {
+        let fd = TTY.load(Ordering::Relaxed);
+        if fd < 0 {
+            return;
+        }
+        f(&mut |bytes: &[u8]| {
+            unsafe { libc::write(fd, bytes.as_ptr().cast(), bytes.len()) };
+        });
+    }
+
+    /// Restores the terminal in a signal handler and runs the default action of the signal.
+    fn on_signal(signal: i32) {
+        let fd = TTY.load(Ordering::Relaxed);
+
+        // The raw mode is left alone if the lock is held, waiting for it could deadlock.
+        crate::terminal::sys::restore_prior_raw_mode(if fd < 0 { STDOUT_FILENO } else { fd });
+        write_to_terminal(|write| write_restore_sequences(write));
+
+        let _ = low_level::emulate_default_handler(signal);
+    }
+}
+
+#[cfg(windows)]
+mod sys {
+    use std::io::{self, Write};
+
+    use winapi::{
+        shared::minwindef::{BOOL, DWORD, FALSE},
+        um::consoleapi::SetConsoleCtrlHandler,
+    };
+
+    use super::{restore, restore_at_exit};
+    use crate::tty::IsTty;
+
+    extern "C" {
+        fn atexit(callback: extern "C" fn()) -> i32;
+    }
+
+    pub(super) fn install() -> io::Result<()> {
+        if unsafe { SetConsoleCtrlHandler(Some(on_control_event), 1) } == 0 {
+            return Err(io::Error::last_os_error());
+        }
+        if unsafe { atexit(restore_at_exit) } != 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::Other,
+                "The exit handler could not be registered.",
+            ));
+        }
+        Ok(())
+    }
+
+    /// Calls `f` with a function which writes to stdout, unless it's redirected.
+    pub(super) fn write_to_terminal(f: impl FnOnce(&mut dyn FnMut(&[u8]))) {
+        let stdout = io::stdout();
+        if !stdout.is_tty() {
+            return;
+        }
+        let mut stdout = stdout.lock();
+        f(&mut |bytes: &[u8]| {
+            let _ = stdout.write_all(bytes);
+        });
+        let _ = stdout.flush();
+    }
+
+    /// Restores the terminal on Ctrl+C, Ctrl+Break, closing the window and the like, and lets
+    /// the next handler run, by default the one which terminates the process.
+    unsafe extern "system" fn on_control_event(_: DWORD) -> BOOL {
+        restore();
+        FALSE
+    }
+}
+
+#[cfg(all(test, unix, feature = "pty"))]
+mod tests {
+    use std::{env, io::Write, process, time::Duration};
+
+    use crate::{cursor, execute, pty, terminal};
+
+    use super::{restore_on_exit, LEAVE_ALTERNATE_SCREEN, SHOW_CURSOR};
+
+    const TEST_NAME: &str = "terminal::exit::tests::test_sigterm_restores_the_terminal";
+    const TIMEOUT: Duration = Duration::from_secs(10);
+
+    #[test]
+    fn test_sigterm_restores_the_terminal() {
+        if pty::is_child() {
+            restore_on_exit().unwrap();
+            terminal::enable_raw_mode().unwrap();
+            let mut stdout = std::io::stdout();
+            execute!(stdout, terminal::EnterAlternateScreen).unwrap();
+            write!(stdout, "pid {};", process::id()).unwrap();
+            stdout.flush().unwrap();
+            loop {
+                std::thread::sleep(Duration::from_secs(1));
+            }
+        }
+        // Pseudo-terminals might not be available in the sandbox
+        if pty::open_pty().is_err() {
+            return;
+        }
+
+        // the shell reports the terminal settings after the test child was terminated
+        let mut command = process::Command::new("sh");
+        command
+            .args([
+                "-c",
+                "\"$0\" \"$1\" --exact --nocapture --test-threads=1; stty -a",
+                env::current_exe().unwrap().to_str().unwrap(),
+                TEST_NAME,
+            ])
+            .env(pty::CHILD_ENV, TEST_NAME);
+        let mut pty = pty::PtyChild::spawn(command).unwrap();
+
+        let output = pty.read_until(b";", TIMEOUT).unwrap();
+        let output = String::from_utf8_lossy(&output);
+        let pid: i32 = output[output.find("pid ").unwrap() + 4..output.len() - 1]
+            .parse()
+            .unwrap();
+        assert_eq!(unsafe { libc::kill(pid, libc::SIGTERM) }, 0);
+
+        pty.read_until(LEAVE_ALTERNATE_SCREEN, TIMEOUT).unwrap();
+        let settings = pty.read_until(b"icanon", TIMEOUT).unwrap();
+        assert!(!settings.ends_with(b"-icanon"));
+        let settings = pty.read_until(b"echo", TIMEOUT).unwrap();
+        assert!(!settings.ends_with(b"-echo"));
+        assert!(pty.wait().unwrap().success());
+    }
+
+    #[test]
+    fn test_exit_restores_the_terminal_instead_of_redirected_stdout() {
+        const TEST_NAME: &str =
+            "terminal::exit::tests::test_exit_restores_the_terminal_instead_of_redirected_stdout";
+
+        if pty::is_child() {
+            restore_on_exit().unwrap();
+            execute!(std::io::stdout(), cursor::Hide).unwrap();
+            return;
+        }
+        // Pseudo-terminals might not be available in the sandbox
+        if pty::open_pty().is_err() {
+            return;
+        }
+
+        // stdout is piped through `cat -v`, which shows escape sequences as `^[`
+        let mut command = process::Command::new("sh");
+        command
+            .args([
+                "-c",
+                "\"$0\" \"$1\" --exact --test-threads=1 | cat -v; echo \"sh done;\"",
+                env::current_exe().unwrap().to_str().unwrap(),
+                TEST_NAME,
+            ])
+            .env(pty::CHILD_ENV, TEST_NAME);
+        let mut pty = pty::PtyChild::spawn(command).unwrap();
+
+        let output = pty.read_until(b"sh done;", TIMEOUT).unwrap();
+        let output = String::from_utf8_lossy(&output);
+        assert!(output.contains("^[[?25l"), "{:?}", output);
+        assert!(!output.contains("^[[?25h"), "{:?}", output);
+        assert!(
+            output.contains(std::str::from_utf8(SHOW_CURSOR).unwrap()),
+            "{:?}",
+            output
+        );
+        assert!(pty.wait().unwrap().success());
+    }
+}
@@ -0,0 +1,257 @@
+#[cfg(feature = "events")]
+use std::time::Duration;
+use std::{
+    fmt,
+    fs::{File, OpenOptions},
+    io::{self, Write},
+    os::unix::io::{AsRawFd, RawFd},
+    path::Path,
+};
+
+use libc::termios as Termios;
+
+#[cfg(feature = "events")]
+use crate::event::{
+    filter::EventFilter, read::InternalEventReader, source::unix::UnixInternalEventSource, Event,
+    InternalEvent,
+};
+#[cfg(feature = "events")]
+use crate::terminal::sys::file_descriptor::FileDesc;
+
+use super::{sys, RawModeBuilder, WindowSize};
+
+/// A handle to a terminal other than the one of the process, e.g. a second terminal a
+/// debugger shows its output on while the debugged program uses the first one.
+///
+/// Every handle has its own raw mode, size and event reader, independent of the functions of
+/// this module and the [`event`](../event/index.html) module, which use the terminal of the
+/// process. Commands are written to the handle like to any other writer.
+///
+/// The raw mode is disabled when the handle is dropped.
+///
+/// # Notes
+///
+/// This is only available on UNIX systems. [`Event::Resize`](../event/enum.Event.html#variant.Resize)
+/// is only reported when the process receives `SIGWINCH`, which is sent when the controlling
+/// terminal is resized.
+///
+/// # Examples
+///
+/// ```no_run
+/// use std::io;
+/// use crossterm::{execute, style::Print, terminal::Terminal};
+///
+/// fn main() -> io::Result<()> {
+///     let mut monitor = Terminal::open("/dev/pts/3")?;
+///     monitor.enable_raw_mode()?;
+///
+///     let (columns, rows) = monitor.size()?;
+///     execute!(monitor, Print(format!("{}x{}", columns, rows)))?;
+///     let event = monitor.read()?;
+///
+///     monitor.disable_raw_mode()
+/// }
+/// ```
+pub struct Terminal {
+    #[cfg(feature = "events")]
+    reader: Option<InternalEventReader>,
+    file: File,
+    // the mode before the raw mode, if the raw mode is enabled
+    prior_raw_mode: Option<Termios>,
+}
+
+impl Terminal {
+    /// Opens the terminal device at `path`, e.g. `/dev/pts/3`.
+    pub fn open(path: impl AsRef<Path>) -> io::Result<Terminal> {
+        let file = OpenOptions::new().read(true).write(true).open(path)?;
+        Terminal::from_file(file)
+    }
+
+    /// Creates a handle to the terminal `file` was opened for reading and writing of.
+    ///
+    /// Fails if `file` isn't a terminal.
+    pub fn from_file(file: File) -> io::Result<Terminal> {
+        if unsafe { libc::isatty(file.as_raw_fd()) } != 1 {
+            return Err(crate::Error::NotATty.into());
+        }
+
+        Ok(Terminal {
+            file,
+            prior_raw_mode: None,
+            #[cfg(feature = "events")]
+            reader: None,
+        })
+    }
+
+    /// Tells whether the raw mode of this terminal is enabled.
+    pub fn is_raw_mode_enabled(&self) -> bool {
+        self.prior_raw_mode.is_some()
+    }
+
+    /// Enables the raw mode of this terminal.
+    ///
+    /// See the [raw mode](./index.html#raw-mode) section.
+    pub fn enable_raw_mode(&mut self) -> io::Result<()> {
+        self.enable_raw_mode_with(RawModeBuilder::default())
+    }
+
+    /// Enables a raw mode of this terminal which keeps some of the terminal's processing, see
+    /// [`RawModeBuilder`](struct.RawModeBuilder.html).
+    pub fn enable_raw_mode_with(&mut self, builder: RawModeBuilder) -> io::Result<()> {
+        if self.prior_raw_mode.is_none() {
+            self.prior_raw_mode = Some(sys::make_raw(self.file.as_raw_fd(), builder)?);
+            self.follow_raw_mode();
+        }
+        Ok(())
+    }
+
+    /// Disables the raw mode of this terminal.
+    pub fn disable_raw_mode(&mut self) -> io::Result<()> {
+        if let Some(prior_raw_mode) = &self.prior_raw_mode {
+            sys::set_terminal_attr(self.file.as_raw_fd(), prior_raw_mode)?;
+            self.prior_raw_mode = None;
+            self.follow_raw_mode();
+        }
+        Ok(())
+    }
+
+    /// Reads `\n` as the Enter key if the raw mode of this terminal is disabled, like the event
+    /// reader of the process does for its terminal.
+    fn follow_raw_mode(&mut self) {
+        #[cfg(feature = "events")]
+        if let Some(reader) = &mut self.reader {
+            reader.set_newline_as_enter(self.prior_raw_mode.is_none());
+        }
+    }
+
+    /// Returns the size (columns, rows) of this terminal.
+    pub fn size(&self) -> io::Result<(u16, u16)> {
+        let size = self.window_size()?;
+        Ok((size.columns, size.rows))
+    }
+
+    /// Returns the size of this terminal in cells and pixels, see
+    /// [`window_size`](fn.window_size.html).
+    pub fn window_size(&self) -> io::Result<WindowSize> {
+        sys::window_size_of(self.file.as_raw_fd())
+    }
+
+    /// Checks if there is an event of this terminal available, see
+    /// [`event::poll`](../event/fn.poll.html).
+    #[cfg(feature = "events")]
+    pub fn poll(&mut self, timeout: Duration) -> io::Result<bool> {
+        self.reader()?.poll(Some(timeout), &EventFilter)
+    }
+
+    /// Reads an event of this terminal, see [`event::read`](../event/fn.read.html).
+    #[cfg(feature = "events")]
+    pub fn read(&mut self) -> io::Result<Event> {
+        match self.reader()?.read(&EventFilter)? {
+            InternalEvent::Event(event) => Ok(event),
+            _ => unreachable!(),
+        }
+    }
+
+    #[cfg(feature = "events")]
+    fn reader(&mut self) -> io::Result<&mut InternalEventReader> {
+        if self.reader.is_none() {
+            // the file descriptor is closed by `file`
+            let fd = FileDesc::new(self.file.as_raw_fd(), false);
+            let source = UnixInternalEventSource::from_file_descriptor(fd)?;
+            self.reader = Some(InternalEventReader::with_source(Box::new(source), false));
+            self.follow_raw_mode();
+        }
+        Ok(self.reader.as_mut().unwrap())
+    }
+}
+
+impl Write for Terminal {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.file.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.file.flush()
+    }
+}
+
+impl AsRawFd for Terminal {
+    fn as_raw_fd(&self) -> RawFd {
+        self.file.as_raw_fd()
+    }
+}
+
+impl Drop for Terminal {
+    fn drop(&mut self) {
+        let _ = self.disable_raw_mode();
+    }
+}
+
+impl fmt::Debug for Terminal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Terminal")
+            .field("file", &self.file)
+            .field("raw_mode", &self.is_raw_mode_enabled())
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::File;
+
+    use super::Terminal;
+
+    #[test]
+    fn test_terminal_requires_a_tty() {
+        let file = File::open("/dev/null").unwrap();
+        assert!(Terminal::from_file(file).is_err());
+    }
+
+    #[cfg(all(feature = "pty", feature = "events"))]
+    #[test]
+    fn test_terminal_of_pty() {
+        use std::{io::Write, time::Duration};
+
+        use crate::{
+            event::{Event, KeyCode, KeyEvent, KeyModifiers},
+            pty::{self, DEFAULT_SIZE},
+        };
+
+        // Pseudo-terminals might not be available in the sandbox
+        let (mut master, slave) = match pty::open_pty() {
+            Ok(pty) => pty,
+            Err(_) => return,
+        };
+        let mut terminal = Terminal::from_file(slave).unwrap();
+        assert_eq!(terminal.size().unwrap(), DEFAULT_SIZE);
+
+        terminal.enable_raw_mode().unwrap();
+        assert!(terminal.is_raw_mode_enabled());
+
+        master.write_all(b"a").unwrap();
+        assert!(terminal.poll(Duration::from_secs(10)).unwrap());
+        assert_eq!(
+            terminal.read().unwrap(),
+            Event::Key(KeyEvent::from(KeyCode::Char('a')))
+        );
+
+        // `\n` follows the raw mode of this terminal, not the one of the process
+        master.write_all(b"\n").unwrap();
+        assert!(terminal.poll(Duration::from_secs(10)).unwrap());
+        assert_eq!(
+            terminal.read().unwrap(),
+            Event::Key(KeyEvent::new(KeyCode::Char('j'), KeyModifiers::CONTROL))
+        );
+
+        terminal.disable_raw_mode().unwrap();
+        assert!(!terminal.is_raw_mode_enabled());
+
+        master.write_all(b"\n").unwrap();
+        assert!(terminal.poll(Duration::from_secs(10)).unwrap());
+        assert_eq!(
+            terminal.read().unwrap(),
+            Event::Key(KeyEvent::from(KeyCode::Enter))
+        );
+    }
+}
@@ -0,0 +1,376 @@
+use std::{
+    collections::BTreeMap,
+    io::{self, Read, Write},
+};
+
+#[cfg(feature = "events")]
+use crate::event::{parse::Parser, Event};
+use crate::{
+    capabilities::{self, Capabilities},
+    command, Command,
+};
+
+// The same as the buffer of the event source of the terminal.
+#[cfg(feature = "events")]
+const READ_BUFFER_SIZE: usize = 1_024;
+
+/// A terminal which is only reachable through a reader and a writer, e.g. the channel of an SSH
+/// session or the terminal widget of a GUI.
+///
+/// Commands are always written as ANSI escape codes to the writer, and events are parsed from
+/// what is read from the reader. There is no tty, so the raw mode is the business of the remote
+/// side, and the size has to be supplied by the caller whenever it changes.
+///
+/// The commands are rendered for the [capabilities](#method.set_capabilities) of this terminal,
+/// the [default](../capabilities/struct.Capabilities.html#impl-Default) ones unless others are
+/// set. The installed capabilities, the terminfo entry and the state crossterm tracks about the
+/// terminal of the process, e.g. the cursor blinking or the keyboard enhancement flags, belong to
+/// another terminal and aren't consulted, nor changed.
+///
+/// The terminal keeps track of the raw mode and of the DEC private modes, e.g. the alternate
+/// screen, the commands written by [`queue`](#method.queue) set, so they can be
+/// [restored](#method.restore) at the end of the session. A terminal without a reader, see
+/// [`headless`](#method.headless), only does this and writes the commands, e.g. for recording a
+/// session.
+///
+/// # Notes
+///
+/// Use [`queue`](#method.queue) and [`execute`](#method.execute) of the terminal itself on
+/// Windows. The [`queue!`](../macro.queue.html) and [`execute!`](../macro.execute.html) macros
+/// execute commands with WinAPI calls on the local console if it doesn't support ANSI escape
+/// codes.
+///
+/// # Examples
+///
+/// ```no_run
+/// use std::{io, net::TcpStream};
+/// use crossterm::{cursor::MoveTo, event::Event, style::Print, terminal::RemoteTerminal};
+///
+/// fn main() -> io::Result<()> {
+///     let stream = TcpStream::connect("127.0.0.1:2323")?;
+///     let mut terminal = RemoteTerminal::new(stream.try_clone()?, stream, (80, 24));
+///
+///     let (columns, rows) = terminal.size();
+///     terminal
+///         .queue(MoveTo(columns / 2, rows / 2))?
+///         .execute(Print("Hello"))?;
+///
+///     loop {
+///         if let Event::Key(_) = terminal.read()? {
+///             return Ok(());
+///         }
+///     }
+/// }
+/// ```
+#[derive(Debug)]
+pub struct RemoteTerminal<R, W> {
+    reader: R,
+    writer: W,
+    size: (u16, u16),
+    capabilities: Capabilities,
+    raw_mode: bool,
+    // the DEC private modes the written commands set (`true`) or reset (`false`)
+    private_modes: BTreeMap<u16, bool>,
+    #[cfg(feature = "events")]
+    parser: Parser,
+    // a resize which wasn't read yet
+    #[cfg(feature = "events")]
+    resized: Option<(u16, u16)>,
+}
+
+impl<R: Read, W: Write> RemoteTerminal<R, W> {
+    /// Creates a terminal reading its input from `reader` and writing to `writer`, which has
+    /// `size` (columns, rows).
+    pub fn new(reader: R, writer: W, size: (u16, u16)) -> RemoteTerminal<R, W> {
+        RemoteTerminal {
+            reader,
+            writer,
+            size,
+            capabilities: Capabilities::default(),
+            raw_mode: false,
+            private_modes: BTreeMap::new(),
+            #[cfg(feature = "events")]
+            parser: Parser::new(),
+            #[cfg(feature = "events")]
+            resized: None,
+        }
+    }
+
+    /// Returns the size (columns, rows) last supplied.
+    pub fn size(&self) -> (u16, u16) {
+        self.size
+    }
+
+    /// Updates the size (columns, rows), e.g. when the SSH client sent a window change request.
+    ///
+    /// The next [`read`](#method.read) returns an [`Event::Resize`](../event/enum.Event.html#variant.Resize)
+    /// if the size changed.
+    pub fn set_size(&mut self, columns: u16, rows: u16) {
+        #[cfg(feature = "events")]
+        if (columns, rows) != self.size {
+            self.resized = Some((columns, rows));
+        }
+        self.size = (columns, rows);
+    }
+
+    /// Returns the capabilities the commands are rendered for.
+    pub fn capabilities(&self) -> &Capabilities {
+        &self.capabilities
+    }
+
+    /// Sets the capabilities the commands are rendered for, e.g.
+    /// [`Capabilities::conservative`](../capabilities/struct.Capabilities.html#method.conservative)
+    /// for a serial terminal or the ones its `TERM` suggests.
+    pub fn set_capabilities(&mut self, capabilities: Capabilities) {
+        self.capabilities = capabilities;
+    }
+
+    /// Tells whether the raw mode is enabled.
+    pub fn is_raw_mode_enabled(&self) -> bool {
+        self.raw_mode
+    }
+
+    /// Marks the raw mode as enabled, the remote side is responsible for actually enabling it.
+    pub fn enable_raw_mode(&mut self) {
+        self.raw_mode = true;
+    }
+
+    /// Marks the raw mode as disabled.
+    pub fn disable_raw_mode(&mut self) {
+        self.raw_mode = false;
+    }
+
+    /// Returns whether the commands written so far set (`true`) or reset (`false`) the DEC
+    /// private mode `mode`, e.g. `1049` for the alternate screen, or `None` if they didn't
+    /// change it.
+    pub fn private_mode(&self, mode: u16) -> Option<bool> {
+        self.private_modes.get(&mode).copied()
+    }
+
+    /// Writes the ANSI escape codes of `command` to the writer.
+    pub fn queue(&mut self, command: impl Command) -> io::Result<&mut Self> {
+        let ansi = capabilities::for_other_terminal(&self.capabilities, || command.ansi_string());
+        self.writer.write_all(ansi.as_bytes())?;
+        command::tee(ansi.as_bytes());
+        track_private_modes(&mut self.private_modes, &ansi);
+        Ok(self)
+    }
+
+    /// Writes the ANSI escape codes of `command` to the writer and flushes it.
+    pub fn execute(&mut self, command: impl Command) -> io::Result<&mut Self> {
+        self.queue(command)?;
+        self.writer.flush()?;
+        Ok(self)
+    }
+
+    /// Resets the DEC private modes the written commands changed to their defaults, and disables
+    /// the raw mode.
+    ///
+    /// All modes are off by default, except for the visible cursor (`25`) and the line wrap (`7`).
+    pub fn restore(&mut self) -> io::Result<()> {
+        for (&mode, &set) in &self.private_modes {
+            let default = matches!(mode, 7 | 25);
+            if set != default {
+                let action = if default { 'h' } else { 'l' };
+                write!(self.writer, "\x1B[?{}{}", mode, action)?;
+            }
+        }
+        self.private_modes.clear();
+        self.raw_mode = false;
+        self.writer.flush()
+    }
+
+    /// Reads the next event, blocking until the reader delivers enough input.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error of kind `UnexpectedEof` once the reader reached its end and all events
+    /// were read.
+    #[cfg(feature = "events")]
+    pub fn read(&mut self) -> io::Result<Event> {
+        if let Some((columns, rows)) = self.resized.take() {
+            return Ok(Event::Resize(columns, rows));
+        }
+
+        let mut buffer = [0; READ_BUFFER_SIZE];
+        loop {
+            if let Some(event) = self.parser.next() {
+                return Ok(event);
+            }
+
+            let read = match self.reader.read(&mut buffer) {
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                result => result?,
+            };
+            if read == 0 {
+                self.parser.finish();
+                return self.parser.next().ok_or_else(|| {
+                    io::Error::new(io::ErrorKind::UnexpectedEof, "The input has ended.")
+                });
+            }
+            self.parser
+                .advance(&buffer[..read], read == READ_BUFFER_SIZE);
+        }
+    }
+
+    /// Returns the parser, e.g. to change how pastes are detected.
+    #[cfg(feature = "events")]
+    pub fn parser_mut(&mut self) -> &mut Parser {
+        &mut self.parser
+    }
+
+    /// Returns the reader.
+    pub fn reader_mut(&mut self) -> &mut R {
+        &mut self.reader
+    }
+
+    /// Returns the writer.
+    pub fn writer_mut(&mut self) -> &mut W {
+        &mut self.writer
+    }
+
+    /// Returns the reader and the writer.
+    pub fn into_inner(self) -> (R, W) {
+        (self.reader, self.writer)
+    }
+}
+
+impl<W: Write> RemoteTerminal<io::Empty, W> {
+    /// Creates a terminal without input, which only writes the commands to `writer` and keeps
+    /// track of the modes they set.
+    pub fn headless(writer: W, size: (u16, u16)) -> RemoteTerminal<io::Empty, W> {
+        RemoteTerminal::new(io::empty(), writer, size)
+    }
+}
+
+// Records the DEC private modes `CSI ? Pm h` sets and `CSI ? Pm l` resets.
+fn track_private_modes(modes: &mut BTreeMap<u16, bool>, ansi: &str) {
+    for (index, _) in ansi.match_indices("\x1B[?") {
+        let sequence = &ansi[index + 3..];
+        let end = match sequence.find(|c: char| !c.is_ascii_digit() && c != ';') {
+            Some(end) => end,
+            None => continue,
+        };
+        let set = match sequence.as_bytes()[end] {
+            b'h' => true,
+            b'l' => false,
+            _ => continue,
+        };
+        for mode in sequence[..end]
+            .split(';')
+            .filter_map(|mode| mode.parse().ok())
+        {
+            modes.insert(mode, set);
+        }
+    }
+}
+
+impl<R, W: Write> Write for RemoteTerminal<R, W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.writer.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{self, Write};
+
+    use super::RemoteTerminal;
+    #[cfg(feature = "events")]
+    use crate::event::{Event, KeyCode};
+    use crate::{
+        capabilities::{self, Capabilities},
+        cursor::{Hide, MoveTo},
+        style::Print,
+        terminal::{EnterAlternateScreen, LeaveAlternateScreen},
+    };
+
+    #[test]
+    fn test_remote_terminal_writes_ansi() {
+        let mut terminal = RemoteTerminal::new(io::empty(), Vec::new(), (80, 24));
+        terminal
+            .queue(MoveTo(1, 2))
+            .unwrap()
+            .execute(Print("a"))
+            .unwrap();
+        terminal.write_all(b"b").unwrap();
+        assert_eq!(terminal.into_inner().1, b"\x1B[3;2Hab");
+    }
+
+    #[test]
+    fn test_remote_terminal_capabilities() {
+        let mut terminal = RemoteTerminal::headless(Vec::new(), (80, 24));
+        terminal.set_capabilities(Capabilities::conservative());
+        assert!(!terminal.capabilities().alternate_screen);
+
+        // a serial terminal without an alternate screen
+        terminal.queue(EnterAlternateScreen).unwrap();
+        assert_eq!(terminal.private_mode(1049), None);
+        assert!(terminal.writer_mut().is_empty());
+        // only while the commands are written for it
+        assert!(!capabilities::is_other_terminal());
+    }
+
+    #[cfg(feature = "events")]
+    #[test]
+    fn test_remote_terminal_reads_events() {
+        let input: &[u8] = b"a\x1B[A\x1B";
+        let mut terminal = RemoteTerminal::new(input, io::sink(), (80, 24));
+        assert_eq!(terminal.size(), (80, 24));
+
+        terminal.set_size(80, 24);
+        terminal.set_size(100, 30);
+        assert_eq!(terminal.size(), (100, 30));
+        assert_eq!(terminal.read().unwrap(), Event::Resize(100, 30));
+
+        assert_eq!(
+            terminal.read().unwrap(),
+            Event::Key(KeyCode::Char('a').into())
+        );
+        assert_eq!(terminal.read().unwrap(), Event::Key(KeyCode::Up.into()));
+        assert_eq!(terminal.read().unwrap(), Event::Key(KeyCode::Esc.into()));
+        assert_eq!(
+            terminal.read().unwrap_err().kind(),
+            io::ErrorKind::UnexpectedEof
+        );
+    }
+
+    #[test]
+    fn test_headless_terminal_tracks_modes() {
+        let mut terminal = RemoteTerminal::headless(Vec::new(), (80, 24));
+        terminal.enable_raw_mode();
+        assert!(terminal.is_raw_mode_enabled());
+
+        terminal
+            .queue(EnterAlternateScreen)
+            .unwrap()
+            .queue(LeaveAlternateScreen)
+            .unwrap()
+            .queue(EnterAlternateScreen)
+            .unwrap()
+            .queue(Hide)
+            .unwrap()
+            .queue(Print("\x1B[?1004;2004h\x1B[?5$p"))
+            .unwrap();
+        assert_eq!(terminal.private_mode(1049), Some(true));
+        assert_eq!(terminal.private_mode(25), Some(false));
+        assert_eq!(terminal.private_mode(2004), Some(true));
+        assert_eq!(terminal.private_mode(5), None);
+        // the state of the terminal of the process is left alone
+        assert!(!crate::terminal::is_alternate_screen_active());
+        assert!(!crate::cursor::is_cursor_hidden());
+
+        terminal.writer_mut().clear();
+        terminal.restore().unwrap();
+        assert!(!terminal.is_raw_mode_enabled());
+        assert_eq!(terminal.private_mode(1049), None);
+        assert_eq!(
+            terminal.into_inner().1,
+            b"\x1B[?25h\x1B[?1004l\x1B[?1049l\x1B[?2004l"
+        );
+    }
+}
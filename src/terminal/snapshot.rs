@@ -0,0 +1,250 @@
+//! Captures and restores the terminal state changed by crossterm, see [`snapshot`].
+
+use std::{
+    fmt,
+    io::{self, Write},
+};
+
+use crate::{
+    cursor::{self, SetCursorStyle},
+    queue, Command, ProcessTerminal,
+};
+
+use super::{
+    DisableAutoRepeat, EnableAutoRepeat, EnterAlternateScreen, LeaveAlternateScreen,
+    TerminalSettings,
+};
+
+/// The terminal state returned by [`snapshot`].
+#[derive(Debug, Clone)]
+pub struct TerminalSnapshot {
+    settings: TerminalSettings,
+    tracked: TrackedState,
+}
+
+/// The part of a [`TerminalSnapshot`] crossterm tracks from the commands.
+#[derive(Debug, Clone, PartialEq)]
+struct TrackedState {
+    alternate_screen: bool,
+    auto_repeat_disabled: bool,
+    cursor_hidden: bool,
+    cursor_blinking: Option<bool>,
+    cursor_style: Option<SetCursorStyle>,
+    #[cfg(feature = "events")]
+    mouse_capture: Option<crate::event::MouseCaptureOptions>,
+    #[cfg(feature = "events")]
+    keyboard_flags: crate::event::KeyboardFlagStacks,
+    #[cfg(feature = "bracketed-paste")]
+    bracketed_paste: bool,
+}
+
+/// Captures everything crossterm knows it changed in the terminal, to return to that exact
+/// state later with [`restore`].
+///
+/// This is the terminal settings of [`save_settings`](fn.save_settings.html), including the
+/// raw mode, whether the alternate screen is active, the auto-repeat of held keys, the mouse
+/// capture, bracketed paste, the pushed keyboard enhancement flags and the cursor visibility,
+/// blinking and style. The state is tracked from the commands queued on a
+/// [`ProcessTerminal`](crate::ProcessTerminal), so changes made by other means aren't captured.
+///
+/// # Examples
+///
+/// ```no_run
+/// use std::{io, process};
+/// use crossterm::{cursor, execute, terminal};
+///
+/// fn main() -> io::Result<()> {
+///     let snapshot = terminal::snapshot()?;
+///
+///     terminal::enable_raw_mode()?;
+///     execute!(io::stdout(), terminal::EnterAlternateScreen, cursor::Hide)?;
+///
+///     terminal::restore(&snapshot)
+/// }
+/// ```
+pub fn snapshot() -> io::Result<TerminalSnapshot> {
+    Ok(TerminalSnapshot {
+        settings: super::save_settings()?,
+        tracked: TrackedState::capture(),
+    })
+}
+
+/// Returns the terminal to the state captured with [`snapshot`].
+///
+/// Only what differs from the tracked state is written to stdout, apart from the cursor
+/// visibility, which is always set.
+pub fn restore(snapshot: &TerminalSnapshot) -> io::Result<()> {
+    let mut stdout = io::stdout();
+    snapshot.tracked.restore(&mut stdout)?;
+    stdout.flush()?;
+
+    super::restore_settings(&snapshot.settings)
+}
+
+impl TrackedState {
+    /// Captures the tracked state.
+    fn capture() -> TrackedState {
+        TrackedState {
+            alternate_screen: super::is_alternate_screen_active(),
+            auto_repeat_disabled: super::is_auto_repeat_disabled(),
+            cursor_hidden: cursor::is_cursor_hidden(),
+            cursor_blinking: cursor::cursor_blinking(),
+            cursor_style: cursor::cursor_style(),
+            #[cfg(feature = "events")]
+            mouse_capture: mouse_capture(),
+            #[cfg(feature = "events")]
+            keyboard_flags: crate::event::save_keyboard_flags(),
+            #[cfg(feature = "bracketed-paste")]
+            bracketed_paste: crate::event::is_bracketed_paste_enabled(),
+        }
+    }
+
+    /// Queues the commands which return to this state on `stdout`, which is the terminal of the
+    /// process, so the tracked state follows.
+    fn restore(&self, stdout: &mut impl ProcessTerminal) -> io::Result<()> {
+        // The keyboard enhancement flags are tracked per screen, so the screen is switched first.
+        match (super::is_alternate_screen_active(), self.alternate_screen) {
+            (false, true) => queue!(stdout, EnterAlternateScreen)?,
+            (true, false) => queue!(stdout, LeaveAlternateScreen)?,
+            _ => {}
+        }
+
+        match (super::is_auto_repeat_disabled(), self.auto_repeat_disabled) {
+            (false, true) => queue!(stdout, DisableAutoRepeat)?,
+            (true, false) => queue!(stdout, EnableAutoRepeat)?,
+            _ => {}
+        }
+
+        #[cfg(feature = "events")]
+        {
+            use crate::event::{DisableMouseCapture, EnableMouseCaptureWith};
+
+            queue!(stdout, RestoreKeyboardFlags(&self.keyboard_flags))?;
+            let mouse_capture = mouse_capture();
+            if mouse_capture != self.mouse_capture {
+                // other options are only applied once the current encodings are disabled
+                if mouse_capture.is_some() {
+                    queue!(stdout, DisableMouseCapture)?;
+                }
+                if let Some(options) = self.mouse_capture {
+                    queue!(stdout, EnableMouseCaptureWith(options))?;
+                }
+            }
+        }
+
+        #[cfg(feature = "bracketed-paste")]
+        {
+            use crate::event::{DisableBracketedPaste, EnableBracketedPaste};
+
+            match (
+                crate::event::is_bracketed_paste_enabled(),
+                self.bracketed_paste,
+            ) {
+                (false, true) => queue!(stdout, EnableBracketedPaste)?,
+                (true, false) => queue!(stdout, DisableBracketedPaste)?,
+                _ => {}
+            }
+        }
+
+        if self.cursor_hidden {
+            queue!(stdout, cursor::Hide)?;
+        } else {
+            queue!(stdout, cursor::Show)?;
+        }
+        if cursor::cursor_blinking() != self.cursor_blinking {
+            match self.cursor_blinking {
+                Some(true) => queue!(stdout, cursor::EnableBlinking)?,
+                Some(false) => queue!(stdout, cursor::DisableBlinking)?,
+                None => queue!(stdout, RestoreOriginalBlinking)?,
+            }
+        }
+        if cursor::cursor_style() != self.cursor_style {
+            queue!(
+                stdout,
+                self.cursor_style
+                    .unwrap_or(SetCursorStyle::DefaultUserShape)
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Returns the options the mouse is captured with, the default ones if it was captured without
+/// [`EnableMouseCaptureWith`](../event/struct.EnableMouseCaptureWith.html).
+#[cfg(feature = "events")]
+fn mouse_capture() -> Option<crate::event::MouseCaptureOptions> {
+    crate::event::is_mouse_capture_enabled()
+        .then(|| crate::event::mouse_capture_options().unwrap_or_default())
+}
+
+/// Returns to the terminal's blinking setting before crossterm changed it.
+struct RestoreOriginalBlinking;
+
+impl Command for RestoreOriginalBlinking {
+    fn write_ansi(&self, f: &mut impl fmt::Write) -> fmt::Result {
+        cursor::write_original_blinking(f)
+    }
+
+    #[cfg(windows)]
+    fn execute_winapi(&self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Pops and pushes the keyboard enhancement flags to return to saved ones.
+#[cfg(feature = "events")]
+struct RestoreKeyboardFlags<'a>(&'a crate::event::KeyboardFlagStacks);
+
+#[cfg(feature = "events")]
+impl Command for RestoreKeyboardFlags<'_> {
+    fn write_ansi(&self, f: &mut impl fmt::Write) -> fmt::Result {
+        crate::event::restore_keyboard_flags(f, self.0)
+    }
+
+    #[cfg(windows)]
+    fn execute_winapi(&self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{self, BufWriter};
+
+    use crate::{
+        cursor::{self, SetCursorStyle},
+        queue,
+        terminal::{DisableAutoRepeat, EnterAlternateScreen},
+    };
+
+    use super::TrackedState;
+
+    #[test]
+    #[serial_test::serial]
+    fn test_restore_tracked_state() {
+        let saved = TrackedState::capture();
+
+        // a buffered stdout, which is tracked, but never flushed
+        let mut stdout = BufWriter::with_capacity(1024, io::stdout());
+        queue!(
+            stdout,
+            EnterAlternateScreen,
+            DisableAutoRepeat,
+            cursor::Hide,
+            SetCursorStyle::SteadyBar
+        )
+        .unwrap();
+        #[cfg(feature = "events")]
+        queue!(stdout, crate::event::EnableMouseCapture).unwrap();
+        assert_ne!(TrackedState::capture(), saved);
+
+        let len = stdout.buffer().len();
+        saved.restore(&mut stdout).unwrap();
+        assert_eq!(TrackedState::capture(), saved);
+
+        let (_, written) = stdout.into_parts();
+        let written = String::from_utf8(written.unwrap().split_off(len)).unwrap();
+        assert!(written.starts_with("\x1B[?1049l\x1B[?8h"), "{:?}", written);
+        assert!(written.ends_with("\x1B[?25h\x1b[0 q"), "{:?}", written);
+    }
+}
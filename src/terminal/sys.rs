@@ -1,11 +1,23 @@
 //! This module provides platform related functions.
 
+#[cfg(unix)]
+#[cfg(feature = "restore-on-exit")]
+pub(crate) use self::unix::restore_prior_raw_mode;
 #[cfg(unix)]
 #[cfg(feature = "events")]
 pub use self::unix::supports_keyboard_enhancement;
 #[cfg(unix)]
 pub(crate) use self::unix::{
-    disable_raw_mode, enable_raw_mode, is_raw_mode_enabled, size, window_size,
+    cell_size, disable_raw_mode, enable_raw_mode, enable_raw_mode_with, is_raw_mode_enabled,
+    make_raw, restore_settings, save_settings, set_flow_control, set_terminal_attr, size,
+    window_size, window_size_of, Settings,
+};
+#[cfg(unix)]
+#[cfg(feature = "events")]
+pub(crate) use self::unix::{
+    in_raw_mode, primary_device_attributes, query, query_outer_terminal_version,
+    query_private_mode, query_secondary_device_attributes, query_terminal_version, query_truecolor,
+    size_of,
 };
 #[cfg(windows)]
 #[cfg(feature = "events")]
@@ -14,10 +26,16 @@ pub use self::windows::supports_keyboard_enhancement;
 pub(crate) use self::windows::temp_screen_buffer;
 #[cfg(windows)]
 pub(crate) use self::windows::{
-    clear, disable_raw_mode, enable_raw_mode, is_raw_mode_enabled, scroll_down, scroll_up,
-    set_size, set_window_title, size, window_size,
+    cell_size, clear, console_font, disable_raw_mode, enable_raw_mode, enable_raw_mode_with,
+    is_raw_mode_enabled, read_screen_buffer, restore_settings, save_settings, scroll_down,
+    scroll_up, set_console_font, set_size, set_window_title, size, window_size,
+    write_screen_buffer, Settings,
 };
 
+#[cfg(all(windows, feature = "events"))]
+pub(crate) use self::msys::check_size as check_msys_size;
+#[cfg(windows)]
+mod msys;
 #[cfg(windows)]
 mod windows;
 
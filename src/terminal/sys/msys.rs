@@ -0,0 +1,147 @@
+//! Terminal manipulation for MSYS2 and Cygwin ptys, e.g. in mintty (Git Bash).
+//!
+//! The standard handles are named pipes there, and the terminal modes are kept by the MSYS2 or
+//! Cygwin runtime, which native programs can't call. The `stty` of the MSYS2 or Cygwin
+//! installation can, when it inherits the pty as its standard input.
+
+use std::{
+    io,
+    process::{Command, Stdio},
+    sync::atomic::{AtomicBool, Ordering},
+    time::{Duration, Instant},
+};
+
+use parking_lot::Mutex;
+
+use crate::terminal::RawModeBuilder;
+
+/// The settings before raw mode was enabled, as printed by `stty -g`.
+static ORIGINAL_SETTINGS: Mutex<Option<String>> = parking_lot::const_mutex(None);
+/// Whether raw mode was enabled, which is tracked so that checking it, e.g. for every printed
+/// line, doesn't run `stty`.
+static RAW_MODE: AtomicBool = AtomicBool::new(false);
+/// The size the event source checked last and when, so that [`size`] doesn't run `stty` while
+/// events are read.
+static CHECKED_SIZE: Mutex<Option<((u16, u16), Instant)>> = parking_lot::const_mutex(None);
+/// How long a checked size is used, the event source checks it every 500 milliseconds while it
+/// waits for input.
+const CHECKED_SIZE_MAX_AGE: Duration = Duration::from_secs(1);
+
+/// Runs `stty` on the pty and returns what it printed.
+fn stty(args: &[&str]) -> io::Result<String> {
+    let output = Command::new("stty")
+        .args(args)
+        .stdin(Stdio::inherit())
+        .stderr(Stdio::null())
+        .output()?;
+    if !output.status.success() {
+        return Err(io::Error::new(
+            io::ErrorKind::Other,
+            format!("stty {} failed with {}", args.join(" "), output.status),
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+pub(crate) fn is_raw_mode_enabled() -> io::Result<bool> {
+    Ok(RAW_MODE.load(Ordering::Relaxed))
+}
+
+pub(crate) fn enable_raw_mode_with(builder: RawModeBuilder) -> io::Result<()> {
+    let mut original = ORIGINAL_SETTINGS.lock();
+    if original.is_none() {
+        *original = Some(stty(&["-g"])?);
+    }
+
+    let mut args = vec!["raw", "-echo"];
+    if builder.keep_signals {
+        args.push("isig");
+    }
+    stty(&args)?;
+    RAW_MODE.store(true, Ordering::Relaxed);
+    Ok(())
+}
+
+pub(crate) fn disable_raw_mode() -> io::Result<()> {
+    match ORIGINAL_SETTINGS.lock().take() {
+        Some(original) => stty(&[&original])?,
+        None => stty(&["-raw", "echo"])?,
+    };
+    RAW_MODE.store(false, Ordering::Relaxed);
+    Ok(())
+}
+
+/// Returns the settings printed by `stty -g` and whether raw mode is enabled.
+pub(crate) fn save_settings() -> io::Result<(String, bool)> {
+    Ok((stty(&["-g"])?, RAW_MODE.load(Ordering::Relaxed)))
+}
+
+pub(crate) fn restore_settings(settings: &str, raw_mode: bool) -> io::Result<()> {
+    stty(&[settings])?;
+    RAW_MODE.store(raw_mode, Ordering::Relaxed);
+    Ok(())
+}
+
+pub(crate) fn size() -> io::Result<(u16, u16)> {
+    match checked_size() {
+        Some(size) => Ok(size),
+        None => stty_size(),
+    }
+}
+
+/// Runs `stty size` and keeps the size for [`size`], which the event source calls regularly to
+/// notice resizes.
+#[cfg(feature = "events")]
+pub(crate) fn check_size() -> io::Result<(u16, u16)> {
+    let size = stty_size()?;
+    *CHECKED_SIZE.lock() = Some((size, Instant::now()));
+    Ok(size)
+}
+
+/// Returns the size the event source checked, unless it's too old.
+fn checked_size() -> Option<(u16, u16)> {
+    match *CHECKED_SIZE.lock() {
+        Some((size, checked_at)) if checked_at.elapsed() < CHECKED_SIZE_MAX_AGE => Some(size),
+        _ => None,
+    }
+}
+
+fn stty_size() -> io::Result<(u16, u16)> {
+    parse_size(&stty(&["size"])?).ok_or_else(|| {
+        crate::Error::ParseError("The size printed by stty could not be parsed.").into()
+    })
+}
+
+/// Parses the `rows columns` printed by `stty size` into `(columns, rows)`.
+fn parse_size(size: &str) -> Option<(u16, u16)> {
+    let mut parts = size.split_whitespace().map(str::parse::<u16>);
+    match (parts.next(), parts.next(), parts.next()) {
+        (Some(Ok(rows)), Some(Ok(columns)), None) if rows > 0 && columns > 0 => {
+            Some((columns, rows))
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Instant;
+
+    use super::{checked_size, parse_size, CHECKED_SIZE, CHECKED_SIZE_MAX_AGE};
+
+    #[test]
+    fn test_parse_size() {
+        assert_eq!(parse_size("24 80\n"), Some((80, 24)));
+        assert_eq!(parse_size("0 0"), None);
+        assert_eq!(parse_size("24"), None);
+    }
+
+    #[test]
+    fn test_checked_size() {
+        *CHECKED_SIZE.lock() = Some(((80, 24), Instant::now()));
+        assert_eq!(checked_size(), Some((80, 24)));
+
+        *CHECKED_SIZE.lock() = Some(((80, 24), Instant::now() - CHECKED_SIZE_MAX_AGE));
+        assert_eq!(checked_size(), None);
+    }
+}
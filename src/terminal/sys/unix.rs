@@ -2,11 +2,11 @@
 
 use crate::terminal::{
     sys::file_descriptor::{tty_fd, FileDesc},
-    WindowSize,
+    RawModeBuilder, WindowSize,
 };
 use libc::{
-    cfmakeraw, ioctl, tcgetattr, tcsetattr, termios as Termios, winsize, STDOUT_FILENO, TCSANOW,
-    TIOCGWINSZ,
+    cfmakeraw, ioctl, tcgetattr, tcsetattr, termios as Termios, winsize, ISIG, IXOFF, IXON, OPOST,
+    STDOUT_FILENO, TCSANOW, TIOCGWINSZ,
 };
 use parking_lot::Mutex;
 use std::fs::File;
@@ -15,10 +15,17 @@ use std::os::unix::io::{IntoRawFd, RawFd};
 
 use std::{io, mem, process};
 
+#[cfg(feature = "events")]
+use crate::event::{filter::Filter, InternalEvent};
+
 // Some(Termios) -> we're in the raw mode and this is the previous mode
 // None -> we're not in the raw mode
 static TERMINAL_MODE_PRIOR_RAW_MODE: Mutex<Option<Termios>> = parking_lot::const_mutex(None);
 
+// Some(ixoff) -> the flow control is disabled and `ixoff` is whether `IXOFF` was set before
+// None -> the flow control isn't disabled
+static IXOFF_PRIOR_FLOW_CONTROL: Mutex<Option<bool>> = parking_lot::const_mutex(None);
+
 pub(crate) fn is_raw_mode_enabled() -> bool {
     TERMINAL_MODE_PRIOR_RAW_MODE.lock().is_some()
 }
@@ -34,16 +41,8 @@ impl From<winsize> for WindowSize {
     }
 }
 
-#[allow(clippy::useless_conversion)]
 pub(crate) fn window_size() -> io::Result<WindowSize> {
     // http://rosettacode.org/wiki/Terminal_control/Dimensions#Library:_BSD_libc
-    let mut size = winsize {
-        ws_row: 0,
-        ws_col: 0,
-        ws_xpixel: 0,
-        ws_ypixel: 0,
-    };
-
     let file = File::open("/dev/tty").map(|file| (FileDesc::new(file.into_raw_fd(), true)));
     let fd = if let Ok(file) = &file {
         file.raw_fd()
@@ -52,6 +51,19 @@ pub(crate) fn window_size() -> io::Result<WindowSize> {
         STDOUT_FILENO
     };
 
+    window_size_of(fd)
+}
+
+/// Returns the size of the terminal `fd` refers to.
+#[allow(clippy::useless_conversion)]
+pub(crate) fn window_size_of(fd: RawFd) -> io::Result<WindowSize> {
+    let mut size = winsize {
+        ws_row: 0,
+        ws_col: 0,
+        ws_xpixel: 0,
+        ws_ypixel: 0,
+    };
+
     if wrap_with_result(unsafe { ioctl(fd, TIOCGWINSZ.into(), &mut size) }).is_ok() {
         return Ok(size.into());
     }
@@ -68,7 +80,20 @@ pub(crate) fn size() -> io::Result<(u16, u16)> {
     tput_size().ok_or_else(|| std::io::Error::last_os_error().into())
 }
 
+/// Returns the size of the terminal `fd` refers to, or of the controlling terminal if that fails.
+#[cfg(feature = "events")]
+pub(crate) fn size_of(fd: RawFd) -> io::Result<(u16, u16)> {
+    match window_size_of(fd) {
+        Ok(window_size) => Ok((window_size.columns, window_size.rows)),
+        Err(_) => size(),
+    }
+}
+
 pub(crate) fn enable_raw_mode() -> io::Result<()> {
+    enable_raw_mode_with(RawModeBuilder::default())
+}
+
+pub(crate) fn enable_raw_mode_with(builder: RawModeBuilder) -> io::Result<()> {
     let mut original_mode = TERMINAL_MODE_PRIOR_RAW_MODE.lock();
 
     if original_mode.is_some() {
@@ -76,17 +101,24 @@ pub(crate) fn enable_raw_mode() -> io::Result<()> {
     }
 
     let tty = tty_fd()?;
-    let fd = tty.raw_fd();
+    let original_mode_ios = make_raw(tty.raw_fd(), builder)?;
+
+    // Keep it last - set the original mode only if we were able to switch to the raw mode
+    *original_mode = Some(original_mode_ios);
+
+    Ok(())
+}
+
+/// Switches the terminal `fd` refers to to the raw mode, and returns the mode before.
+pub(crate) fn make_raw(fd: RawFd, builder: RawModeBuilder) -> io::Result<Termios> {
     let mut ios = get_terminal_attr(fd)?;
     let original_mode_ios = ios;
 
     raw_terminal_attr(&mut ios);
+    keep_terminal_attr(&mut ios, &original_mode_ios, builder);
     set_terminal_attr(fd, &ios)?;
 
-    // Keep it last - set the original mode only if we were able to switch to the raw mode
-    *original_mode = Some(original_mode_ios);
-
-    Ok(())
+    Ok(original_mode_ios)
 }
 
 /// Reset the raw mode.
@@ -107,35 +139,70 @@ pub(crate) fn disable_raw_mode() -> io::Result<()> {
     Ok(())
 }
 
-/// Queries the terminal's support for progressive keyboard enhancement.
-///
-/// On unix systems, this function will block and possibly time out while
-/// [`crossterm::event::read`](crate::event::read) or [`crossterm::event::poll`](crate::event::poll) are being called.
-#[cfg(feature = "events")]
-pub fn supports_keyboard_enhancement() -> io::Result<bool> {
-    if is_raw_mode_enabled() {
-        read_supports_keyboard_enhancement_raw()
-    } else {
-        read_supports_keyboard_enhancement_flags()
+/// Switches the terminal `fd` refers to back to the mode before the raw mode, if the raw mode is
+/// enabled, with async-signal-safe calls only.
+#[cfg(feature = "restore-on-exit")]
+pub(crate) fn restore_prior_raw_mode(fd: RawFd) {
+    // The raw mode may be in the middle of being changed, waiting for the lock could deadlock.
+    if let Some(original_mode) = TERMINAL_MODE_PRIOR_RAW_MODE.try_lock() {
+        if let Some(original_mode_ios) = original_mode.as_ref() {
+            unsafe { tcsetattr(fd, TCSANOW, original_mode_ios) };
+        }
     }
 }
 
-#[cfg(feature = "events")]
-fn read_supports_keyboard_enhancement_flags() -> io::Result<bool> {
-    enable_raw_mode()?;
-    let flags = read_supports_keyboard_enhancement_raw();
-    disable_raw_mode()?;
-    flags
+pub(crate) fn set_flow_control(enabled: bool) -> io::Result<()> {
+    let mut prior_ixoff = IXOFF_PRIOR_FLOW_CONTROL.lock();
+    let tty = tty_fd()?;
+    let fd = tty.raw_fd();
+    let mut ios = get_terminal_attr(fd)?;
+    let ixoff = ios.c_iflag & IXOFF != 0;
+    // the input side is only enabled again if it was enabled before
+    flow_control_attr(&mut ios, enabled, prior_ixoff.unwrap_or(false));
+    set_terminal_attr(fd, &ios)?;
+
+    if enabled {
+        *prior_ixoff = None;
+    } else if prior_ixoff.is_none() {
+        *prior_ixoff = Some(ixoff);
+    }
+    Ok(())
 }
 
+#[derive(Clone)]
+pub(crate) struct Settings {
+    termios: Termios,
+    // the mode before the raw mode, if the raw mode was enabled
+    prior_raw_mode: Option<Termios>,
+}
+
+pub(crate) fn save_settings() -> io::Result<Settings> {
+    let original_mode = TERMINAL_MODE_PRIOR_RAW_MODE.lock();
+
+    let tty = tty_fd()?;
+    Ok(Settings {
+        termios: get_terminal_attr(tty.raw_fd())?,
+        prior_raw_mode: *original_mode,
+    })
+}
+
+pub(crate) fn restore_settings(settings: &Settings) -> io::Result<()> {
+    let mut original_mode = TERMINAL_MODE_PRIOR_RAW_MODE.lock();
+
+    let tty = tty_fd()?;
+    set_terminal_attr(tty.raw_fd(), &settings.termios)?;
+    *original_mode = settings.prior_raw_mode;
+
+    Ok(())
+}
+
+/// Queries the terminal's support for progressive keyboard enhancement.
+///
+/// On unix systems, this function will block and possibly time out while
+/// [`crossterm::event::read`](crate::event::read) or [`crossterm::event::poll`](crate::event::poll) are being called.
 #[cfg(feature = "events")]
-fn read_supports_keyboard_enhancement_raw() -> io::Result<bool> {
-    use crate::event::{
-        filter::{KeyboardEnhancementFlagsFilter, PrimaryDeviceAttributesFilter},
-        poll_internal, read_internal, InternalEvent,
-    };
-    use std::io::Write;
-    use std::time::Duration;
+pub fn supports_keyboard_enhancement() -> io::Result<bool> {
+    use crate::event::filter::KeyboardEnhancementFlagsFilter;
 
     // This is the recommended method for testing support for the keyboard enhancement protocol.
     // We send a query for the flags supported by the terminal and then the primary device attributes
@@ -146,44 +213,329 @@ fn read_supports_keyboard_enhancement_raw() -> io::Result<bool> {
 
     // ESC [ ? u        Query progressive keyboard enhancement flags (kitty protocol).
     // ESC [ c          Query primary device attributes.
-    const QUERY: &[u8] = b"\x1B[?u\x1B[c";
+    in_raw_mode(|| {
+        query(
+            b"\x1B[?u\x1B[c",
+            &KeyboardEnhancementFlagsFilter,
+            "The keyboard enhancement status could not be read within a normal duration",
+            |event| match event {
+                InternalEvent::KeyboardEnhancementFlags(_) => Some(true),
+                _ => Some(false),
+            },
+        )
+    })
+}
 
-    let result = File::open("/dev/tty").and_then(|mut file| {
-        file.write_all(QUERY)?;
-        file.flush()
-    });
-    if result.is_err() {
-        let mut stdout = io::stdout();
-        stdout.write_all(QUERY)?;
-        stdout.flush()?;
+/// Queries the primary device attributes (DA1), the architectural class of the terminal followed
+/// by its attributes.
+#[cfg(feature = "events")]
+pub(crate) fn primary_device_attributes() -> io::Result<Vec<u16>> {
+    use crate::event::filter::PrimaryDeviceAttributesFilter;
+
+    // ESC [ c          Query primary device attributes.
+    in_raw_mode(|| {
+        query(
+            b"\x1B[c",
+            &PrimaryDeviceAttributesFilter,
+            "The primary device attributes could not be read within a normal duration",
+            |event| match event {
+                InternalEvent::PrimaryDeviceAttributes(attributes) => Some(attributes),
+                _ => None,
+            },
+        )
+    })
+}
+
+/// Asks the terminal whether it shows 24 bit colors, `None` if it doesn't tell.
+///
+/// A 24 bit foreground color is set and read back with DECRQSS, terminals which approximate
+/// it report another color. The SGR setting is read before and restored afterwards, so the
+/// colors and attributes stay as they were.
+#[cfg(feature = "events")]
+pub(crate) fn query_truecolor() -> io::Result<Option<bool>> {
+    in_raw_mode(|| {
+        // ESC P $ q m ESC \        Request the SGR setting (DECRQSS).
+        // ESC [ c                  Query primary device attributes.
+        let original = match read_sgr_setting(b"\x1BP$qm\x1B\\\x1B[c")? {
+            Some(sgr) if is_sgr_setting(&sgr) => sgr,
+            _ => return Ok(None),
+        };
+
+        // ESC [ 38;2;12;34;56 m    Set a 24 bit foreground color.
+        // ESC P $ q m ESC \        Request the SGR setting (DECRQSS).
+        // ESC [ Pt                 Restore the original SGR setting.
+        // ESC [ c                  Query primary device attributes.
+        let probe = format!(
+            "\x1B[38;2;{};{};{}m\x1BP$qm\x1B\\\x1B[{}\x1B[c",
+            PROBE_COLOR[0], PROBE_COLOR[1], PROBE_COLOR[2], original
+        );
+        let setting = read_sgr_setting(probe.as_bytes())?;
+        Ok(setting.map(|sgr| is_probe_color(&sgr)))
+    })
+}
+
+/// The color set by [`query_truecolor`], which no palette contains.
+#[cfg(feature = "events")]
+const PROBE_COLOR: [&str; 3] = ["12", "34", "56"];
+
+/// Returns whether the SGR setting `sgr` reported by DECRQSS has [`PROBE_COLOR`] as the
+/// foreground, e.g. `0;38:2::12:34:56m` or `38;2;12;34;56m`.
+#[cfg(feature = "events")]
+fn is_probe_color(sgr: &str) -> bool {
+    let sgr = sgr.trim_end_matches('m').replace(':', ";");
+    let mut params = sgr.split(';');
+    while let Some(param) = params.next() {
+        if param != "38" {
+            continue;
+        }
+        if params.next() != Some("2") {
+            return false;
+        }
+        // the color space id between the `:` separators is optional
+        let rgb = params.filter(|param| !param.is_empty()).take(3);
+        return rgb.eq(PROBE_COLOR.iter().copied());
+    }
+    false
+}
+
+/// Returns whether `sgr` is an SGR setting reported by DECRQSS which can be written back, the
+/// parameters followed by `m`.
+#[cfg(feature = "events")]
+fn is_sgr_setting(sgr: &str) -> bool {
+    sgr.strip_suffix('m').map_or(false, |params| {
+        params
+            .bytes()
+            .all(|byte| byte.is_ascii_digit() || byte == b';' || byte == b':')
+    })
+}
+
+/// Writes `query`, which ends with a DECRQSS request for the SGR setting and the primary device
+/// attributes query, and reads the reported setting.
+#[cfg(feature = "events")]
+fn read_sgr_setting(request: &[u8]) -> io::Result<Option<String>> {
+    use crate::event::filter::SettingReportFilter;
+
+    query(
+        request,
+        &SettingReportFilter,
+        "The SGR setting could not be read within a normal duration",
+        |event| match event {
+            InternalEvent::SettingReport(setting) => Some(setting),
+            _ => Some(None),
+        },
+    )
+}
+
+/// Asks the terminal for its name and version with XTVERSION, see
+/// `InternalEvent::TerminalVersion`, `None` if it doesn't understand the request.
+#[cfg(feature = "events")]
+pub(crate) fn query_terminal_version() -> io::Result<Option<String>> {
+    // ESC [ > 0 q          Query the name and version of the terminal (XTVERSION).
+    // ESC [ c              Query primary device attributes.
+    read_terminal_version(b"\x1B[>0q\x1B[c")
+}
+
+/// Asks the terminal `depth` nested tmuxes run in for its name and version, like
+/// `query_terminal_version`, with the passthrough sequence of tmux once per tmux.
+#[cfg(feature = "events")]
+pub(crate) fn query_outer_terminal_version(depth: usize) -> io::Result<Option<String>> {
+    // ESC P tmux ; ... ESC \  The queries of `query_terminal_version` in the passthrough
+    //                         sequence, with the ESCs doubled, for every tmux.
+    read_terminal_version(&tmux_passthrough_request(b"\x1B[>0q\x1B[c", depth))
+}
+
+/// Wraps `request` in the passthrough sequence of tmux `depth` times.
+#[cfg(feature = "events")]
+fn tmux_passthrough_request(request: &[u8], depth: usize) -> Vec<u8> {
+    let mut request = request.to_vec();
+    for _ in 0..depth {
+        let mut wrapped = b"\x1BPtmux;".to_vec();
+        for &byte in &request {
+            if byte == b'\x1B' {
+                wrapped.push(b'\x1B');
+            }
+            wrapped.push(byte);
+        }
+        wrapped.extend_from_slice(b"\x1B\\");
+        request = wrapped;
     }
+    request
+}
+
+#[cfg(feature = "events")]
+fn read_terminal_version(request: &[u8]) -> io::Result<Option<String>> {
+    use crate::event::filter::TerminalVersionFilter;
+
+    in_raw_mode(|| {
+        query(
+            request,
+            &TerminalVersionFilter,
+            "The terminal version could not be read within a normal duration",
+            |event| match event {
+                InternalEvent::TerminalVersion(version) => Some(Some(version)),
+                _ => Some(None),
+            },
+        )
+    })
+}
+
+/// Asks the terminal for its secondary device attributes, see
+/// `InternalEvent::SecondaryDeviceAttributes`, `None` if it doesn't answer them.
+#[cfg(feature = "events")]
+pub(crate) fn query_secondary_device_attributes() -> io::Result<Option<Vec<u16>>> {
+    use crate::event::filter::SecondaryDeviceAttributesFilter;
+
+    // ESC [ > c            Query secondary device attributes.
+    // ESC [ c              Query primary device attributes.
+    in_raw_mode(|| {
+        query(
+            b"\x1B[>c\x1B[c",
+            &SecondaryDeviceAttributesFilter,
+            "The secondary device attributes could not be read within a normal duration",
+            |event| match event {
+                InternalEvent::SecondaryDeviceAttributes(attributes) => Some(Some(attributes)),
+                _ => Some(None),
+            },
+        )
+    })
+}
+
+/// Asks the terminal for the setting of a DEC private mode with DECRQM, see
+/// `InternalEvent::ModeReport`, `None` if it doesn't understand the request.
+#[cfg(feature = "events")]
+pub(crate) fn query_private_mode(mode: u16) -> io::Result<Option<u8>> {
+    use crate::event::filter::ModeReportFilter;
+
+    // ESC [ ? mode $ p     Request the setting of a DEC private mode (DECRQM).
+    // ESC [ c              Query primary device attributes.
+    in_raw_mode(|| {
+        query(
+            format!("\x1B[?{}$p\x1B[c", mode).as_bytes(),
+            &ModeReportFilter,
+            "The mode setting could not be read within a normal duration",
+            |event| match event {
+                InternalEvent::ModeReport(reported, setting) if reported == mode => {
+                    Some(Some(setting))
+                }
+                // the report of another query
+                InternalEvent::ModeReport(_, _) => None,
+                _ => Some(None),
+            },
+        )
+    })
+}
+
+/// Returns the size of a cell `(width, height)` in pixels.
+///
+/// Asks the terminal first, then falls back to dividing the window size by the number of cells.
+pub(crate) fn cell_size() -> io::Result<(u16, u16)> {
+    #[cfg(feature = "events")]
+    {
+        use crate::event::filter::CellSizeFilter;
+
+        // ESC [ 16 t       Report the cell size in pixels.
+        // ESC [ c          Query primary device attributes.
+        let size = in_raw_mode(|| {
+            query(
+                b"\x1B[16t\x1B[c",
+                &CellSizeFilter,
+                "The cell size could not be read within a normal duration",
+                |event| match event {
+                    InternalEvent::CellSize(width, height) => Some(Some((width, height))),
+                    _ => Some(None),
+                },
+            )
+        });
+        if let Ok(Some(size)) = size {
+            return Ok(size);
+        }
+    }
+
+    let window = window_size()?;
+    if window.columns == 0 || window.rows == 0 || window.width == 0 || window.height == 0 {
+        return Err(crate::Error::UnsupportedCapability(
+            "The terminal doesn't report its size in pixels.",
+        )
+        .into());
+    }
+    Ok((window.width / window.columns, window.height / window.rows))
+}
+
+/// Runs `f` in the raw mode, which is enabled for it if it isn't already.
+#[cfg(feature = "events")]
+pub(crate) fn in_raw_mode<T>(f: impl FnOnce() -> io::Result<T>) -> io::Result<T> {
+    if is_raw_mode_enabled() {
+        f()
+    } else {
+        enable_raw_mode()?;
+        let result = f();
+        disable_raw_mode()?;
+        result
+    }
+}
 
+/// Writes `request` to the terminal and waits for the response `filter` lets through, which
+/// `extract` turns into the answer. `extract` returns `None` for a response to wait past, e.g. one
+/// to another request.
+///
+/// Requests which the terminal may not understand are followed by the primary device attributes
+/// query, and their filters let its response through, see `event::filter`. If the answer
+/// arrives first, that response is read as well, so it isn't left for the next query.
+#[cfg(feature = "events")]
+pub(crate) fn query<F, T>(
+    request: &[u8],
+    filter: &F,
+    timeout_message: &'static str,
+    mut extract: impl FnMut(InternalEvent) -> Option<T>,
+) -> io::Result<T>
+where
+    F: Filter,
+{
+    use crate::event::{filter::PrimaryDeviceAttributesFilter, poll_internal, read_internal};
+
+    let timeout = crate::capabilities::query_timeout()?;
+    write_query(request)?;
+
+    let attributes_follow = filter.eval(&InternalEvent::PrimaryDeviceAttributes(Vec::new()));
     loop {
-        match poll_internal(
-            Some(Duration::from_millis(2000)),
-            &KeyboardEnhancementFlagsFilter,
-        ) {
+        match poll_internal(Some(timeout), filter) {
             Ok(true) => {
-                match read_internal(&KeyboardEnhancementFlagsFilter) {
-                    Ok(InternalEvent::KeyboardEnhancementFlags(_current_flags)) => {
+                let event = match read_internal(filter) {
+                    Ok(event) => event,
+                    Err(_) => continue,
+                };
+                let is_attributes = matches!(event, InternalEvent::PrimaryDeviceAttributes(_));
+                if let Some(answer) = extract(event) {
+                    if attributes_follow && !is_attributes {
                         // Flush the PrimaryDeviceAttributes out of the event queue.
                         read_internal(&PrimaryDeviceAttributesFilter).ok();
-                        return Ok(true);
                     }
-                    _ => return Ok(false),
+                    return Ok(answer);
                 }
             }
-            Ok(false) => {
-                return Err(io::Error::new(
-                    io::ErrorKind::Other,
-                    "The keyboard enhancement status could not be read within a normal duration",
-                ));
-            }
+            Ok(false) => return Err(crate::Error::Timeout(timeout_message).into()),
             Err(_) => {}
         }
     }
 }
 
+/// Writes a query to the terminal, preferably to `/dev/tty` in case stdout is redirected.
+#[cfg(feature = "events")]
+fn write_query(query: &[u8]) -> io::Result<()> {
+    use std::io::Write;
+
+    let result = File::open("/dev/tty").and_then(|mut file| {
+        file.write_all(query)?;
+        file.flush()
+    });
+    if result.is_err() {
+        let mut stdout = io::stdout();
+        stdout.write_all(query)?;
+        stdout.flush()?;
+    }
+    Ok(())
+}
+
 /// execute tput with the given argument and parse
 /// the output as a u16.
 ///
@@ -219,6 +571,30 @@ fn raw_terminal_attr(termios: &mut Termios) {
     unsafe { cfmakeraw(termios) }
 }
 
+// Restores the bits the builder asked to keep from the mode before the raw mode
+fn keep_terminal_attr(termios: &mut Termios, original: &Termios, builder: RawModeBuilder) {
+    if builder.keep_signals {
+        termios.c_lflag |= original.c_lflag & ISIG;
+    }
+    if builder.keep_output_processing {
+        termios.c_oflag |= original.c_oflag & OPOST;
+    }
+    if builder.keep_flow_control {
+        termios.c_iflag |= original.c_iflag & IXON;
+    }
+}
+
+fn flow_control_attr(termios: &mut Termios, enabled: bool, ixoff: bool) {
+    if enabled {
+        termios.c_iflag |= IXON;
+        if ixoff {
+            termios.c_iflag |= IXOFF;
+        }
+    } else {
+        termios.c_iflag &= !(IXON | IXOFF);
+    }
+}
+
 fn get_terminal_attr(fd: RawFd) -> io::Result<Termios> {
     unsafe {
         let mut termios = mem::zeroed();
@@ -227,7 +603,7 @@ fn get_terminal_attr(fd: RawFd) -> io::Result<Termios> {
     }
 }
 
-fn set_terminal_attr(fd: RawFd, termios: &Termios) -> io::Result<()> {
+pub(crate) fn set_terminal_attr(fd: RawFd, termios: &Termios) -> io::Result<()> {
     wrap_with_result(unsafe { tcsetattr(fd, TCSANOW, termios) })
 }
 
@@ -238,3 +614,101 @@ fn wrap_with_result(result: i32) -> io::Result<()> {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use libc::{ECHO, ISIG, IXOFF, IXON, OPOST};
+
+    use super::{
+        flow_control_attr, keep_terminal_attr, raw_terminal_attr, RawModeBuilder, Termios,
+    };
+
+    #[cfg(feature = "events")]
+    #[test]
+    fn test_tmux_passthrough_request() {
+        use super::tmux_passthrough_request;
+
+        assert_eq!(tmux_passthrough_request(b"\x1B[c", 0), b"\x1B[c");
+        assert_eq!(
+            tmux_passthrough_request(b"\x1B[c", 1),
+            b"\x1BPtmux;\x1B\x1B[c\x1B\\"
+        );
+        assert_eq!(
+            tmux_passthrough_request(b"\x1B[c", 2),
+            b"\x1BPtmux;\x1B\x1BPtmux;\x1B\x1B\x1B\x1B[c\x1B\x1B\\\x1B\\"
+        );
+    }
+
+    #[test]
+    fn test_keep_terminal_attr() {
+        let mut original: Termios = unsafe { std::mem::zeroed() };
+        original.c_lflag = ISIG;
+        original.c_oflag = OPOST;
+        original.c_iflag = IXON;
+
+        let raw = |builder: RawModeBuilder| {
+            let mut ios = original;
+            raw_terminal_attr(&mut ios);
+            keep_terminal_attr(&mut ios, &original, builder);
+            ios
+        };
+
+        let ios = raw(RawModeBuilder::default());
+        assert_eq!(ios.c_lflag & ISIG, 0);
+        assert_eq!(ios.c_oflag & OPOST, 0);
+        assert_eq!(ios.c_iflag & IXON, 0);
+
+        let ios = raw(RawModeBuilder::default().keep_signals(true));
+        assert_eq!(ios.c_lflag & ISIG, ISIG);
+        assert_eq!(ios.c_oflag & OPOST, 0);
+
+        let ios = raw(RawModeBuilder::default()
+            .keep_output_processing(true)
+            .keep_flow_control(true));
+        assert_eq!(ios.c_lflag & ISIG, 0);
+        assert_eq!(ios.c_oflag & OPOST, OPOST);
+        assert_eq!(ios.c_iflag & IXON, IXON);
+    }
+
+    #[test]
+    fn test_flow_control_attr() {
+        let mut ios: Termios = unsafe { std::mem::zeroed() };
+        ios.c_iflag = IXON | IXOFF;
+        ios.c_lflag = ECHO;
+
+        flow_control_attr(&mut ios, false, false);
+        assert_eq!(ios.c_iflag & (IXON | IXOFF), 0);
+        assert_eq!(ios.c_lflag, ECHO);
+
+        flow_control_attr(&mut ios, true, true);
+        assert_eq!(ios.c_iflag & (IXON | IXOFF), IXON | IXOFF);
+
+        // the input side wasn't enabled before
+        flow_control_attr(&mut ios, false, false);
+        flow_control_attr(&mut ios, true, false);
+        assert_eq!(ios.c_iflag & (IXON | IXOFF), IXON);
+    }
+
+    #[cfg(feature = "events")]
+    #[test]
+    fn test_is_probe_color() {
+        use super::is_probe_color;
+
+        assert!(is_probe_color("0;38:2::12:34:56m"));
+        assert!(is_probe_color("38;2;12;34;56m"));
+        assert!(is_probe_color("0;1;38:2:12:34:56m"));
+        assert!(!is_probe_color("0;38;5;23m"));
+        assert!(!is_probe_color("0m"));
+    }
+
+    #[cfg(feature = "events")]
+    #[test]
+    fn test_is_sgr_setting() {
+        use super::is_sgr_setting;
+
+        assert!(is_sgr_setting("0m"));
+        assert!(is_sgr_setting("0;1;38:2::1:2:3m"));
+        assert!(!is_sgr_setting("0;1"));
+        assert!(!is_sgr_setting("0m\x1B[2J"));
+    }
+}
@@ -3,37 +3,67 @@
 use std::fmt::{self, Write};
 use std::io::{self};
 
-use crossterm_winapi::{Console, ConsoleMode, Coord, Handle, ScreenBuffer, Size};
+use crossterm_winapi::{result, Console, ConsoleMode, Coord, Handle, ScreenBuffer, Size};
 use winapi::{
     shared::minwindef::DWORD,
-    um::wincon::{SetConsoleTitleW, ENABLE_ECHO_INPUT, ENABLE_LINE_INPUT, ENABLE_PROCESSED_INPUT},
+    um::{
+        wincon::{
+            GetCurrentConsoleFontEx, ReadConsoleOutputW, SetConsoleTitleW, SetCurrentConsoleFontEx,
+            WriteConsoleOutputW, CONSOLE_FONT_INFOEX, ENABLE_ECHO_INPUT, ENABLE_LINE_INPUT,
+            ENABLE_PROCESSED_INPUT,
+        },
+        wincontypes::{CHAR_INFO, COORD, SMALL_RECT},
+    },
 };
 
 use crate::{
+    ansi_support::{console_kind, ConsoleKind},
     cursor,
-    terminal::{ClearType, WindowSize},
+    terminal::{ClearType, ConsoleFont, RawModeBuilder, ScreenBufferCell, WindowSize},
 };
 
+use super::msys;
+
 /// bits which can't be set in raw mode
 const NOT_RAW_MODE_MASK: DWORD = ENABLE_LINE_INPUT | ENABLE_ECHO_INPUT | ENABLE_PROCESSED_INPUT;
 
+/// Returns whether the standard input is a MSYS2 or Cygwin pty instead of a console.
+fn is_msys_pty() -> bool {
+    crate::ansi_support::is_msys_pty_input()
+}
+
 pub(crate) fn is_raw_mode_enabled() -> std::io::Result<bool> {
+    if is_msys_pty() {
+        return msys::is_raw_mode_enabled();
+    }
+
     let console_mode = ConsoleMode::from(Handle::current_in_handle()?);
 
     let dw_mode = console_mode.mode()?;
 
     Ok(
-        // check none of the "not raw" bits is set
-        dw_mode & NOT_RAW_MODE_MASK == 0,
+        // check none of the "not raw" bits is set, processed input may be kept by the builder
+        dw_mode & NOT_RAW_MODE_MASK & !ENABLE_PROCESSED_INPUT == 0,
     )
 }
 
 pub(crate) fn enable_raw_mode() -> std::io::Result<()> {
+    enable_raw_mode_with(RawModeBuilder::default())
+}
+
+pub(crate) fn enable_raw_mode_with(builder: RawModeBuilder) -> std::io::Result<()> {
+    if is_msys_pty() {
+        return msys::enable_raw_mode_with(builder);
+    }
+
     let console_mode = ConsoleMode::from(Handle::current_in_handle()?);
 
     let dw_mode = console_mode.mode()?;
 
-    let new_mode = dw_mode & !NOT_RAW_MODE_MASK;
+    let mut new_mode = dw_mode & !NOT_RAW_MODE_MASK;
+    if builder.keep_signals {
+        new_mode |= dw_mode & ENABLE_PROCESSED_INPUT;
+    }
 
     console_mode.set_mode(new_mode)?;
 
@@ -41,6 +71,10 @@ pub(crate) fn enable_raw_mode() -> std::io::Result<()> {
 }
 
 pub(crate) fn disable_raw_mode() -> std::io::Result<()> {
+    if is_msys_pty() {
+        return msys::disable_raw_mode();
+    }
+
     let console_mode = ConsoleMode::from(Handle::current_in_handle()?);
 
     let dw_mode = console_mode.mode()?;
@@ -52,7 +86,47 @@ pub(crate) fn disable_raw_mode() -> std::io::Result<()> {
     Ok(())
 }
 
+#[derive(Clone)]
+pub(crate) enum Settings {
+    Console {
+        input_mode: DWORD,
+        output_mode: DWORD,
+    },
+    /// The settings of a MSYS2 or Cygwin pty, as printed by `stty -g`, and whether raw mode is
+    /// enabled.
+    Msys(String, bool),
+}
+
+pub(crate) fn save_settings() -> std::io::Result<Settings> {
+    if is_msys_pty() {
+        return msys::save_settings()
+            .map(|(settings, raw_mode)| Settings::Msys(settings, raw_mode));
+    }
+
+    Ok(Settings::Console {
+        input_mode: ConsoleMode::from(Handle::current_in_handle()?).mode()?,
+        output_mode: ConsoleMode::from(Handle::current_out_handle()?).mode()?,
+    })
+}
+
+pub(crate) fn restore_settings(settings: &Settings) -> std::io::Result<()> {
+    let (input_mode, output_mode) = match settings {
+        Settings::Console {
+            input_mode,
+            output_mode,
+        } => (*input_mode, *output_mode),
+        Settings::Msys(settings, raw_mode) => return msys::restore_settings(settings, *raw_mode),
+    };
+    ConsoleMode::from(Handle::current_in_handle()?).set_mode(input_mode)?;
+    ConsoleMode::from(Handle::current_out_handle()?).set_mode(output_mode)?;
+    Ok(())
+}
+
 pub(crate) fn size() -> io::Result<(u16, u16)> {
+    if is_msys_pty() {
+        return msys::size();
+    }
+
     let terminal_size = ScreenBuffer::current()?.info()?.terminal_size();
     // windows starts counting at 0, unix at 1, add one to replicated unix behaviour.
     Ok((
@@ -62,10 +136,10 @@ pub(crate) fn size() -> io::Result<(u16, u16)> {
 }
 
 pub(crate) fn window_size() -> io::Result<WindowSize> {
-    Err(io::Error::new(
-        io::ErrorKind::Unsupported,
+    Err(crate::Error::UnsupportedCapability(
         "Window pixel size not implemented for the Windows API.",
-    ))
+    )
+    .into())
 }
 
 /// Queries the terminal's support for progressive keyboard enhancement.
@@ -241,6 +315,185 @@ pub(crate) fn set_window_title(title: impl fmt::Display) -> std::io::Result<()>
     }
 }
 
+/// Returns the region of one row of a screen buffer rectangle.
+fn row_region(column: u16, row: u16, width: u16) -> io::Result<SMALL_RECT> {
+    let invalid = || io::Error::new(io::ErrorKind::InvalidInput, "region is out of bounds");
+    let left = i16::try_from(column).map_err(|_| invalid())?;
+    let right = i16::try_from(u32::from(column) + u32::from(width) - 1).map_err(|_| invalid())?;
+    let top = i16::try_from(row).map_err(|_| invalid())?;
+    Ok(SMALL_RECT {
+        Left: left,
+        Top: top,
+        Right: right,
+        Bottom: top,
+    })
+}
+
+pub(crate) fn read_screen_buffer(
+    column: u16,
+    row: u16,
+    width: u16,
+    height: u16,
+) -> io::Result<Vec<ScreenBufferCell>> {
+    let mut cells = Vec::with_capacity(usize::from(width) * usize::from(height));
+    if width == 0 {
+        return Ok(cells);
+    }
+
+    let handle = Handle::current_out_handle()?;
+    // SAFETY: CHAR_INFO is plain old data.
+    let mut buffer: Vec<CHAR_INFO> = vec![unsafe { std::mem::zeroed() }; usize::from(width)];
+
+    // Read row by row, the buffer `ReadConsoleOutputW` uses is limited to 64 KiB.
+    for y in row..row.saturating_add(height) {
+        for info in buffer.iter_mut() {
+            // SAFETY: see above.
+            *info = unsafe { std::mem::zeroed() };
+        }
+
+        let mut region = row_region(column, y, width)?;
+        result(unsafe {
+            ReadConsoleOutputW(
+                *handle,
+                buffer.as_mut_ptr(),
+                COORD {
+                    X: width as i16,
+                    Y: 1,
+                },
+                COORD { X: 0, Y: 0 },
+                &mut region,
+            )
+        })?;
+
+        cells.extend(buffer.iter().map(|info| ScreenBufferCell {
+            // SAFETY: the wide character APIs always set `UnicodeChar`.
+            character: unsafe { *info.Char.UnicodeChar() },
+            attributes: info.Attributes,
+        }));
+    }
+
+    Ok(cells)
+}
+
+pub(crate) fn write_screen_buffer(
+    column: u16,
+    row: u16,
+    width: u16,
+    cells: &[ScreenBufferCell],
+) -> io::Result<()> {
+    if width == 0 {
+        return Ok(());
+    }
+
+    let handle = Handle::current_out_handle()?;
+
+    for (y, cells) in (row..).zip(cells.chunks(usize::from(width))) {
+        let buffer: Vec<CHAR_INFO> = cells
+            .iter()
+            .map(|cell| {
+                // SAFETY: CHAR_INFO is plain old data.
+                let mut info: CHAR_INFO = unsafe { std::mem::zeroed() };
+                // SAFETY: writing a field of a union of plain old data.
+                unsafe { *info.Char.UnicodeChar_mut() = cell.character };
+                info.Attributes = cell.attributes;
+                info
+            })
+            .collect();
+
+        let mut region = row_region(column, y, buffer.len() as u16)?;
+        result(unsafe {
+            WriteConsoleOutputW(
+                *handle,
+                buffer.as_ptr(),
+                COORD {
+                    X: buffer.len() as i16,
+                    Y: 1,
+                },
+                COORD { X: 0, Y: 0 },
+                &mut region,
+            )
+        })?;
+    }
+
+    Ok(())
+}
+
+/// Returns an error if the console has no font the application can control.
+fn check_console_font_supported() -> io::Result<()> {
+    match console_kind() {
+        ConsoleKind::ConPty | ConsoleKind::MsysPty => Err(crate::Error::UnsupportedCapability(
+            "The console font can only be controlled in a classic console window.",
+        )
+        .into()),
+        ConsoleKind::Classic | ConsoleKind::Unknown => Ok(()),
+    }
+}
+
+fn empty_font_info() -> CONSOLE_FONT_INFOEX {
+    // SAFETY: CONSOLE_FONT_INFOEX is plain old data.
+    let mut info: CONSOLE_FONT_INFOEX = unsafe { std::mem::zeroed() };
+    info.cbSize = std::mem::size_of::<CONSOLE_FONT_INFOEX>() as u32;
+    info
+}
+
+pub(crate) fn console_font() -> io::Result<ConsoleFont> {
+    check_console_font_supported()?;
+
+    let handle = Handle::current_out_handle()?;
+    let mut info = empty_font_info();
+    result(unsafe { GetCurrentConsoleFontEx(*handle, 0, &mut info) })?;
+
+    let face_name_len = info
+        .FaceName
+        .iter()
+        .position(|unit| *unit == 0)
+        .unwrap_or(info.FaceName.len());
+
+    Ok(ConsoleFont {
+        face_name: String::from_utf16_lossy(&info.FaceName[..face_name_len]),
+        width: info.dwFontSize.X as u16,
+        height: info.dwFontSize.Y as u16,
+        weight: info.FontWeight,
+        family: info.FontFamily,
+    })
+}
+
+/// Returns the size of a cell `(width, height)` in pixels, which is the size of the console font.
+pub(crate) fn cell_size() -> io::Result<(u16, u16)> {
+    let font = console_font()?;
+    if font.width == 0 || font.height == 0 {
+        return Err(crate::Error::UnsupportedCapability(
+            "The console doesn't report the size of its font.",
+        )
+        .into());
+    }
+    Ok((font.width, font.height))
+}
+
+pub(crate) fn set_console_font(font: &ConsoleFont) -> io::Result<()> {
+    check_console_font_supported()?;
+
+    let mut info = empty_font_info();
+    let face_name: Vec<u16> = font.face_name.encode_utf16().collect();
+    // The face name must be null terminated.
+    if face_name.len() >= info.FaceName.len() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "The face name of the console font is too long.",
+        ));
+    }
+    info.FaceName[..face_name.len()].copy_from_slice(&face_name);
+    info.dwFontSize = COORD {
+        X: font.width as i16,
+        Y: font.height as i16,
+    };
+    info.FontWeight = font.weight;
+    info.FontFamily = font.family;
+
+    let handle = Handle::current_out_handle()?;
+    result(unsafe { SetCurrentConsoleFontEx(*handle, 0, &mut info) })
+}
+
 fn clear_after_cursor(
     location: Coord,
     buffer_size: Size,
@@ -368,7 +621,11 @@ mod tests {
     use serial_test::serial;
     use winapi::um::wincon::GetConsoleTitleW;
 
-    use super::{scroll_down, scroll_up, set_size, set_window_title, size, temp_screen_buffer};
+    use super::{
+        read_screen_buffer, scroll_down, scroll_up, set_size, set_window_title, size,
+        temp_screen_buffer, write_screen_buffer,
+    };
+    use crate::terminal::ScreenBufferCell;
 
     #[test]
     #[serial]
@@ -468,4 +725,21 @@ mod tests {
         let console_title = OsString::from_wide(&raw[..length]).into_string().unwrap();
         assert_eq!(test_title, &console_title[..]);
     }
+
+    #[test]
+    #[serial]
+    fn test_read_write_screen_buffer_winapi() {
+        let _test_screen = temp_screen_buffer().unwrap();
+
+        let cells: Vec<_> = "abcdef"
+            .encode_utf16()
+            .map(|character| ScreenBufferCell {
+                character,
+                attributes: 0x07,
+            })
+            .collect();
+        write_screen_buffer(1, 2, 3, &cells).unwrap();
+
+        assert_eq!(read_screen_buffer(1, 2, 3, 2).unwrap(), cells);
+    }
 }
@@ -0,0 +1,880 @@
+//! # Terminfo
+//!
+//! Commands write xterm flavored escape sequences, which almost every terminal in use today
+//! understands. With the `terminfo` feature, commands consult the terminfo database instead if
+//! `TERM` names a terminal which isn't known to be xterm compatible, e.g. `vt100`, `linux` or
+//! `screen.xterm-256color`. Capabilities the entry doesn't have still use the xterm sequence.
+//!
+//! The entry is loaded on first use. Applications can install another one, or none to always
+//! write the xterm sequences, with [`set`](fn.set.html).
+//!
+//! ```no_run
+//! use std::io;
+//! use crossterm::terminfo::{self, TermInfo};
+//!
+//! fn main() -> io::Result<()> {
+//!     terminfo::set(Some(TermInfo::from_name("vt100")?));
+//!
+//!     let clear = terminfo::current().and_then(|info| info.expand("clear", &[]));
+//!     println!("{:?}", clear);
+//!     Ok(())
+//! }
+//! ```
+//!
+//! Only the compiled format of the database is read, standard capabilities are available by
+//! the names of the capabilities crossterm uses, extended ones by any name.
+
+use std::{
+    collections::HashMap,
+    env, fmt, fs,
+    io::{self, ErrorKind},
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use parking_lot::RwLock;
+
+use crate::style::{Attribute, Color};
+
+/// The standard boolean capabilities known by name, with their index in the compiled format.
+const BOOLEANS: &[(&str, usize)] = &[("am", 1), ("xenl", 4), ("hs", 9), ("bce", 28)];
+
+/// The standard numeric capabilities known by name, with their index in the compiled format.
+const NUMBERS: &[(&str, usize)] = &[("cols", 0), ("lines", 2), ("colors", 13), ("pairs", 14)];
+
+/// The standard string capabilities known by name, with their index in the compiled format.
+const STRINGS: &[(&str, usize)] = &[
+    ("bel", 1),
+    ("cr", 2),
+    ("csr", 3),
+    ("clear", 5),
+    ("el", 6),
+    ("ed", 7),
+    ("hpa", 8),
+    ("cup", 10),
+    ("cud1", 11),
+    ("home", 12),
+    ("civis", 13),
+    ("cub1", 14),
+    ("cnorm", 16),
+    ("cuf1", 17),
+    ("cuu1", 19),
+    ("cvvis", 20),
+    ("dl1", 22),
+    ("dsl", 23),
+    ("smacs", 25),
+    ("blink", 26),
+    ("bold", 27),
+    ("smcup", 28),
+    ("dim", 30),
+    ("invis", 32),
+    ("rev", 34),
+    ("smso", 35),
+    ("smul", 36),
+    ("ech", 37),
+    ("rmacs", 38),
+    ("sgr0", 39),
+    ("rmcup", 40),
+    ("rmso", 43),
+    ("rmul", 44),
+    ("flash", 45),
+    ("fsl", 47),
+    ("il1", 53),
+    ("rmkx", 88),
+    ("smkx", 89),
+    ("dl", 106),
+    ("cud", 107),
+    ("indn", 109),
+    ("il", 110),
+    ("cub", 111),
+    ("cuf", 112),
+    ("rin", 113),
+    ("cuu", 114),
+    ("rc", 126),
+    ("vpa", 127),
+    ("sc", 128),
+    ("ind", 129),
+    ("ri", 130),
+    ("tsl", 135),
+    ("smam", 151),
+    ("rmam", 152),
+    ("el1", 269),
+    ("op", 297),
+    ("oc", 298),
+    ("setf", 302),
+    ("setb", 303),
+    ("sitm", 311),
+    ("ritm", 321),
+    ("setaf", 359),
+    ("setab", 360),
+];
+
+/// The directories searched for entries after `$TERMINFO`, `~/.terminfo` and `$TERMINFO_DIRS`.
+const DEFAULT_DIRS: &[&str] = &[
+    "/etc/terminfo",
+    "/lib/terminfo",
+    "/usr/share/terminfo",
+    "/usr/lib/terminfo",
+    "/usr/share/lib/terminfo",
+    "/boot/system/data/terminfo",
+];
+
+/// A terminal description from the terminfo database.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TermInfo {
+    names: Vec<String>,
+    booleans: HashMap<String, bool>,
+    numbers: HashMap<String, i32>,
+    strings: HashMap<String, Vec<u8>>,
+}
+
+impl TermInfo {
+    /// Loads the entry for the terminal named by the `TERM` environment variable.
+    pub fn from_env() -> io::Result<TermInfo> {
+        match env::var("TERM") {
+            Ok(term) if !term.is_empty() => TermInfo::from_name(&term),
+            _ => Err(io::Error::new(ErrorKind::NotFound, "TERM is not set")),
+        }
+    }
+
+    /// Loads the entry for the terminal `name` from the terminfo database.
+    pub fn from_name(name: &str) -> io::Result<TermInfo> {
+        if name.is_empty() || name.contains(['/', '\\']) || name.starts_with('.') {
+            return Err(io::Error::new(
+                ErrorKind::InvalidInput,
+                format!("invalid terminal name {:?}", name),
+            ));
+        }
+
+        for dir in search_dirs() {
+            if let Some(path) = entry_path(&dir, name) {
+                return TermInfo::from_path(path);
+            }
+        }
+
+        Err(io::Error::new(
+            ErrorKind::NotFound,
+            format!("no terminfo entry for {:?}", name),
+        ))
+    }
+
+    /// Loads a compiled entry from the file at `path`.
+    pub fn from_path(path: impl AsRef<Path>) -> io::Result<TermInfo> {
+        TermInfo::parse(&fs::read(path)?)
+    }
+
+    /// Parses a compiled entry.
+    pub fn parse(bytes: &[u8]) -> io::Result<TermInfo> {
+        let mut reader = Reader { bytes, position: 0 };
+
+        let number_size = match reader.i16()? {
+            0o432 => 2,
+            0o1036 => 4,
+            _ => return Err(invalid("not a compiled terminfo entry")),
+        };
+        let names_size = reader.count()?;
+        let boolean_count = reader.count()?;
+        let number_count = reader.count()?;
+        let string_count = reader.count()?;
+        let table_size = reader.count()?;
+
+        let names = reader.take(names_size)?;
+        let names = String::from_utf8_lossy(names.split(|&b| b == 0).next().unwrap_or_default())
+            .split('|')
+            .map(str::to_string)
+            .collect();
+
+        let mut info = TermInfo {
+            names,
+            booleans: HashMap::new(),
+            numbers: HashMap::new(),
+            strings: HashMap::new(),
+        };
+
+        let booleans = reader.take(boolean_count)?;
+        reader.align();
+        let numbers = reader.numbers(number_count, number_size)?;
+        let offsets = reader.numbers(string_count, 2)?;
+        let table = reader.take(table_size)?;
+
+        for &(name, index) in BOOLEANS {
+            if booleans.get(index) == Some(&1) {
+                info.booleans.insert(name.to_string(), true);
+            }
+        }
+        for &(name, index) in NUMBERS {
+            if let Some(&number) = numbers.get(index).filter(|&&number| number >= 0) {
+                info.numbers.insert(name.to_string(), number);
+            }
+        }
+        for &(name, index) in STRINGS {
+            if let Some(string) = offsets
+                .get(index)
+                .and_then(|&offset| string_at(table, offset))
+            {
+                info.strings.insert(name.to_string(), string.to_vec());
+            }
+        }
+
+        reader.align();
+        if reader.position < bytes.len() {
+            info.parse_extended(&mut reader, number_size)?;
+        }
+
+        Ok(info)
+    }
+
+    fn parse_extended(&mut self, reader: &mut Reader, number_size: usize) -> io::Result<()> {
+        let boolean_count = reader.count()?;
+        let number_count = reader.count()?;
+        let string_count = reader.count()?;
+        let _item_count = reader.count()?;
+        let table_size = reader.count()?;
+
+        let booleans = reader.take(boolean_count)?;
+        reader.align();
+        let numbers = reader.numbers(number_count, number_size)?;
+        let offsets = reader.numbers(string_count, 2)?;
+        let name_offsets = reader.numbers(boolean_count + number_count + string_count, 2)?;
+        let table = reader.take(table_size)?;
+
+        // The names follow the last string value in the table.
+        let names_start = offsets
+            .iter()
+            .filter_map(|&offset| string_at(table, offset).map(|s| offset as usize + s.len() + 1))
+            .max()
+            .unwrap_or(0);
+        let names = table.get(names_start..).unwrap_or_default();
+        let mut names = name_offsets.iter().map(|&offset| {
+            string_at(names, offset)
+                .map(|name| String::from_utf8_lossy(name).into_owned())
+                .ok_or_else(|| invalid("invalid extended capability name"))
+        });
+
+        for &value in booleans {
+            let name = names
+                .next()
+                .unwrap_or_else(|| Err(invalid("missing name")))?;
+            if value == 1 {
+                self.booleans.insert(name, true);
+            }
+        }
+        for &value in &numbers {
+            let name = names
+                .next()
+                .unwrap_or_else(|| Err(invalid("missing name")))?;
+            if value >= 0 {
+                self.numbers.insert(name, value);
+            }
+        }
+        for &offset in &offsets {
+            let name = names
+                .next()
+                .unwrap_or_else(|| Err(invalid("missing name")))?;
+            if let Some(string) = string_at(table, offset) {
+                self.strings.insert(name, string.to_vec());
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Returns the names of the terminal, the primary name first and the description last.
+    pub fn names(&self) -> &[String] {
+        &self.names
+    }
+
+    /// Returns whether the boolean capability `name` is set.
+    pub fn flag(&self, name: &str) -> bool {
+        self.booleans.get(name).copied().unwrap_or(false)
+    }
+
+    /// Returns the numeric capability `name`.
+    pub fn number(&self, name: &str) -> Option<i32> {
+        self.numbers.get(name).copied()
+    }
+
+    /// Returns the unexpanded string capability `name`.
+    pub fn string(&self, name: &str) -> Option<&[u8]> {
+        self.strings.get(name).map(Vec::as_slice)
+    }
+
+    /// Returns the string capability `name` with `params` substituted.
+    ///
+    /// Padding (`$<5>`) is removed. Returns `None` if the entry doesn't have the capability or if
+    /// it isn't valid UTF-8.
+    pub fn expand(&self, name: &str, params: &[i32]) -> Option<String> {
+        let expanded = expand(self.string(name)?, params);
+        String::from_utf8(expanded).ok()
+    }
+}
+
+static TERMINFO: RwLock<Option<Option<Arc<TermInfo>>>> = parking_lot::const_rwlock(None);
+
+/// Returns the entry commands currently consult.
+///
+/// On first use, this is the entry for `TERM` unless it names an xterm compatible terminal or
+/// has no entry.
+pub fn current() -> Option<Arc<TermInfo>> {
+    if let Some(info) = &*TERMINFO.read() {
+        return info.clone();
+    }
+
+    TERMINFO
+        .write()
+        .get_or_insert_with(|| match env::var("TERM") {
+            Ok(term) if !is_xterm_compatible(&term) => {
+                TermInfo::from_name(&term).ok().map(Arc::new)
+            }
+            _ => None,
+        })
+        .clone()
+}
+
+/// Installs the entry commands consult from now on, `None` makes them write the xterm
+/// sequences.
+pub fn set(info: Option<TermInfo>) {
+    *TERMINFO.write() = Some(info.map(Arc::new));
+}
+
+/// Returns the entry the commands consult, none while they're rendered for another terminal,
+/// which may have another `TERM`.
+fn consulted() -> Option<Arc<TermInfo>> {
+    if crate::capabilities::is_other_terminal() {
+        return None;
+    }
+    current()
+}
+
+/// Writes the expansion of the string capability `name` of the current entry.
+///
+/// Returns `None` if there's no entry in use or it doesn't have the capability, so the caller
+/// writes its xterm sequence instead.
+pub(crate) fn write(f: &mut impl fmt::Write, name: &str, params: &[i32]) -> Option<fmt::Result> {
+    let info = consulted()?;
+    let expanded = info.expand(name, params)?;
+    Some(f.write_str(&expanded))
+}
+
+/// Writes the color `color` with the string capability `name`, `setaf` or `setab`, of the
+/// current entry.
+///
+/// Returns `None` for RGB colors and colors the entry doesn't have.
+pub(crate) fn write_color(
+    f: &mut impl fmt::Write,
+    name: &str,
+    color: Color,
+) -> Option<fmt::Result> {
+    let index = match color {
+        Color::Black => 0,
+        Color::DarkRed => 1,
+        Color::DarkGreen => 2,
+        Color::DarkYellow => 3,
+        Color::DarkBlue => 4,
+        Color::DarkMagenta => 5,
+        Color::DarkCyan => 6,
+        Color::Grey => 7,
+        Color::DarkGrey => 8,
+        Color::Red => 9,
+        Color::Green => 10,
+        Color::Yellow => 11,
+        Color::Blue => 12,
+        Color::Magenta => 13,
+        Color::Cyan => 14,
+        Color::White => 15,
+        Color::AnsiValue(value) => value as i32,
+        Color::Reset | Color::Rgb { .. } => return None,
+    };
+
+    let info = consulted()?;
+    if index >= info.number("colors").unwrap_or(0) {
+        return None;
+    }
+    let expanded = info.expand(name, &[index])?;
+    Some(f.write_str(&expanded))
+}
+
+/// Writes the string capability of the current entry which sets `attribute`.
+pub(crate) fn write_attribute(
+    f: &mut impl fmt::Write,
+    attribute: Attribute,
+) -> Option<fmt::Result> {
+    let name = match attribute {
+        Attribute::Reset => "sgr0",
+        Attribute::Bold => "bold",
+        Attribute::Dim => "dim",
+        Attribute::Italic => "sitm",
+        Attribute::NoItalic => "ritm",
+        Attribute::Underlined => "smul",
+        Attribute::NoUnderline => "rmul",
+        Attribute::SlowBlink => "blink",
+        Attribute::Reverse => "rev",
+        Attribute::Hidden => "invis",
+        _ => return None,
+    };
+    write(f, name, &[])
+}
+
+/// Returns whether `term` names a terminal which understands the xterm sequences crossterm
+/// writes, so there's no reason to consult the database.
+fn is_xterm_compatible(term: &str) -> bool {
+    term.is_empty()
+        || term.starts_with("xterm")
+        || term.starts_with("foot")
+        || matches!(
+            term,
+            "alacritty" | "wezterm" | "contour" | "ghostty" | "rio" | "tmux-256color"
+        )
+}
+
+fn search_dirs() -> Vec<PathBuf> {
+    let mut dirs = Vec::new();
+
+    if let Some(dir) = env::var_os("TERMINFO") {
+        dirs.push(PathBuf::from(dir));
+    }
+    if let Some(home) = env::var_os("HOME") {
+        dirs.push(Path::new(&home).join(".terminfo"));
+    }
+    if let Ok(terminfo_dirs) = env::var("TERMINFO_DIRS") {
+        for dir in terminfo_dirs.split(':') {
+            if dir.is_empty() {
+                dirs.extend(DEFAULT_DIRS.iter().map(PathBuf::from));
+            } else {
+                dirs.push(PathBuf::from(dir));
+            }
+        }
+    }
+    dirs.extend(DEFAULT_DIRS.iter().map(PathBuf::from));
+
+    dirs
+}
+
+fn entry_path(dir: &Path, name: &str) -> Option<PathBuf> {
+    let first = name.chars().next()?;
+
+    // Most systems use the first character, macOS uses its hex code.
+    [first.to_string(), format!("{:x}", first as u32)]
+        .iter()
+        .map(|subdir| dir.join(subdir).join(name))
+        .find(|path| path.is_file())
+}
+
+/// Returns the NUL terminated string at `offset` in `table`, `None` for absent (`-1`) and
+/// cancelled (`-2`) capabilities.
+fn string_at(table: &[u8], offset: i32) -> Option<&[u8]> {
+    let rest = table.get(usize::try_from(offset).ok()?..)?;
+    let end = rest.iter().position(|&b| b == 0)?;
+    Some(&rest[..end])
+}
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(ErrorKind::InvalidData, message.to_string())
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, count: usize) -> io::Result<&'a [u8]> {
+        let bytes = self
+            .bytes
+            .get(self.position..self.position + count)
+            .ok_or_else(|| invalid("truncated terminfo entry"))?;
+        self.position += count;
+        Ok(bytes)
+    }
+
+    fn i16(&mut self) -> io::Result<i16> {
+        let bytes = self.take(2)?;
+        Ok(i16::from_le_bytes([bytes[0], bytes[1]]))
+    }
+
+    fn count(&mut self) -> io::Result<usize> {
+        usize::try_from(self.i16()?).map_err(|_| invalid("negative count"))
+    }
+
+    fn numbers(&mut self, count: usize, size: usize) -> io::Result<Vec<i32>> {
+        let bytes = self.take(count * size)?;
+        Ok(bytes
+            .chunks(size)
+            .map(|chunk| match *chunk {
+                [a, b] => i16::from_le_bytes([a, b]) as i32,
+                [a, b, c, d] => i32::from_le_bytes([a, b, c, d]),
+                _ => unreachable!(),
+            })
+            .collect())
+    }
+
+    // Sections start at even offsets.
+    fn align(&mut self) {
+        if self.position % 2 == 1 {
+            self.position += 1;
+        }
+    }
+}
+
+/// Substitutes `params` in a parameterized string, like `tparm`.
+fn expand(string: &[u8], params: &[i32]) -> Vec<u8> {
+    let mut params: [i32; 9] = {
+        let mut all = [0; 9];
+        for (to, from) in all.iter_mut().zip(params) {
+            *to = *from;
+        }
+        all
+    };
+    let mut output = Vec::new();
+    let mut stack: Vec<i32> = Vec::new();
+    let mut dynamic = [0; 26];
+    let mut fixed = [0; 26];
+
+    let mut i = 0;
+    while i < string.len() {
+        let byte = string[i];
+        i += 1;
+
+        if byte == b'$' && string.get(i) == Some(&b'<') {
+            // Skip padding.
+            match string[i..].iter().position(|&b| b == b'>') {
+                Some(end) => i += end + 1,
+                None => output.push(byte),
+            }
+            continue;
+        }
+        if byte != b'%' {
+            output.push(byte);
+            continue;
+        }
+
+        let op = match string.get(i) {
+            Some(&op) => op,
+            None => break,
+        };
+        i += 1;
+        let mut pop = || stack.pop().unwrap_or(0);
+
+        match op {
+            b'%' => output.push(b'%'),
+            b'c' => output.push(pop() as u8),
+            b'p' => {
+                let index = string.get(i).map_or(0, |&b| b.wrapping_sub(b'1') as usize);
+                i += 1;
+                stack.push(params.get(index).copied().unwrap_or(0));
+            }
+            b'P' | b'g' => {
+                let name = match string.get(i) {
+                    Some(&name) => name,
+                    None => break,
+                };
+                i += 1;
+                let variable = match name {
+                    b'a'..=b'z' => &mut dynamic[(name - b'a') as usize],
+                    b'A'..=b'Z' => &mut fixed[(name - b'A') as usize],
+                    _ => continue,
+                };
+                if op == b'P' {
+                    *variable = pop();
+                } else {
+                    let value = *variable;
+                    stack.push(value);
+                }
+            }
+            b'\'' => {
+                stack.push(string.get(i).copied().unwrap_or(0) as i32);
+                i += 2;
+            }
+            b'{' => {
+                let end = string[i..].iter().position(|&b| b == b'}').unwrap_or(0);
+                let number = std::str::from_utf8(&string[i..i + end])
+                    .ok()
+                    .and_then(|number| number.parse().ok())
+                    .unwrap_or(0);
+                stack.push(number);
+                i += end + 1;
+            }
+            b'l' => {
+                // Strings aren't supported as parameters, so their length is always 0.
+                pop();
+                stack.push(0);
+            }
+            b'+' | b'-' | b'*' | b'/' | b'm' | b'&' | b'|' | b'^' | b'=' | b'>' | b'<' | b'A'
+            | b'O' => {
+                let b = pop();
+                let a = pop();
+                stack.push(match op {
+                    b'+' => a.wrapping_add(b),
+                    b'-' => a.wrapping_sub(b),
+                    b'*' => a.wrapping_mul(b),
+                    b'/' => a.checked_div(b).unwrap_or(0),
+                    b'm' => a.checked_rem(b).unwrap_or(0),
+                    b'&' => a & b,
+                    b'|' => a | b,
+                    b'^' => a ^ b,
+                    b'=' => (a == b) as i32,
+                    b'>' => (a > b) as i32,
+                    b'<' => (a < b) as i32,
+                    b'A' => (a != 0 && b != 0) as i32,
+                    _ => (a != 0 || b != 0) as i32,
+                });
+            }
+            b'!' => {
+                let a = pop();
+                stack.push((a == 0) as i32);
+            }
+            b'~' => {
+                let a = pop();
+                stack.push(!a);
+            }
+            b'i' => {
+                params[0] = params[0].wrapping_add(1);
+                params[1] = params[1].wrapping_add(1);
+            }
+            b'?' | b';' => {}
+            b't' => {
+                if pop() == 0 {
+                    i = skip_branch(string, i, true);
+                }
+            }
+            b'e' => i = skip_branch(string, i, false),
+            _ => {
+                // A printf style conversion like `%d` or `%:-3x`.
+                let start = i - 1;
+                let end = match string[start..]
+                    .iter()
+                    .position(|b| matches!(b, b'd' | b'o' | b'x' | b'X' | b's'))
+                {
+                    Some(end) => start + end,
+                    None => break,
+                };
+                let value = pop();
+                format_number(&mut output, &string[start..end], string[end], value);
+                i = end + 1;
+            }
+        }
+    }
+
+    output
+}
+
+/// Skips to the matching `%e` (if `to_else`) or `%;` and returns the position after it.
+fn skip_branch(string: &[u8], mut i: usize, to_else: bool) -> usize {
+    let mut depth = 0;
+    while i + 1 < string.len() {
+        if string[i] != b'%' {
+            i += 1;
+            continue;
+        }
+        match string[i + 1] {
+            b'?' => depth += 1,
+            b';' if depth == 0 => return i + 2,
+            b';' => depth -= 1,
+            b'e' if depth == 0 && to_else => return i + 2,
+            _ => {}
+        }
+        i += 2;
+    }
+    string.len()
+}
+
+/// The widest field and the most digits a conversion is padded to, the entries aren't trusted.
+const MAX_FIELD_WIDTH: usize = 256;
+
+/// Parses the width or precision of a conversion, at most [`MAX_FIELD_WIDTH`].
+fn field_width(digits: &str) -> usize {
+    digits
+        .bytes()
+        .take_while(u8::is_ascii_digit)
+        .fold(0, |width, digit| {
+            (width * 10 + usize::from(digit - b'0')).min(MAX_FIELD_WIDTH)
+        })
+}
+
+fn format_number(output: &mut Vec<u8>, spec: &[u8], conversion: u8, value: i32) {
+    let spec = std::str::from_utf8(spec).unwrap_or_default();
+    let spec = spec.strip_prefix(':').unwrap_or(spec);
+    let left = spec.contains('-');
+    let plus = spec.contains('+');
+    let space = spec.contains(' ');
+    let alternate = spec.contains('#');
+    let digits = spec.trim_start_matches(['-', '+', ' ', '#']);
+    let zero = digits.starts_with('0');
+    let (width, precision) = match digits.split_once('.') {
+        Some((width, precision)) => (width, Some(field_width(precision))),
+        None => (digits, None),
+    };
+    let width = field_width(width);
+
+    let mut number = match conversion {
+        b'o' => format!("{:o}", value),
+        b'x' => format!("{:x}", value),
+        b'X' => format!("{:X}", value),
+        _ => value.unsigned_abs().to_string(),
+    };
+    if let Some(precision) = precision {
+        if number.len() < precision {
+            number.insert_str(0, &"0".repeat(precision - number.len()));
+        }
+    }
+    let prefix = match conversion {
+        b'd' | b's' if value < 0 => "-",
+        b'd' | b's' if plus => "+",
+        b'd' | b's' if space => " ",
+        b'o' if alternate => "0",
+        b'x' if alternate => "0x",
+        b'X' if alternate => "0X",
+        _ => "",
+    };
+
+    let length = prefix.len() + number.len();
+    let padding = width.saturating_sub(length);
+    let formatted = if left {
+        format!("{}{}{}", prefix, number, " ".repeat(padding))
+    } else if zero && precision.is_none() {
+        format!("{}{}{}", prefix, "0".repeat(padding), number)
+    } else {
+        format!("{}{}{}", " ".repeat(padding), prefix, number)
+    };
+    output.extend_from_slice(formatted.as_bytes());
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        cursor::{Hide, Show},
+        Command,
+    };
+
+    use super::{expand, set, TermInfo};
+
+    /// Compiles an entry in the legacy format, with extended capabilities if any are given.
+    fn compile(strings: &[(usize, &str)], extended: &[(&str, &str)]) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        let push = |bytes: &mut Vec<u8>, value: i16| bytes.extend(value.to_le_bytes());
+
+        let names = b"test|Test terminal\0";
+        let string_count = strings
+            .iter()
+            .map(|&(index, _)| index + 1)
+            .max()
+            .unwrap_or(0);
+        let mut offsets = vec![-1i16; string_count];
+        let mut table = Vec::new();
+        for &(index, value) in strings {
+            offsets[index] = table.len() as i16;
+            table.extend(value.as_bytes());
+            table.push(0);
+        }
+
+        for value in [0o432, names.len() as i16, 2, 1, string_count as i16] {
+            push(&mut bytes, value);
+        }
+        push(&mut bytes, table.len() as i16);
+        bytes.extend(names);
+        bytes.extend([0, 1]);
+        if bytes.len() % 2 == 1 {
+            bytes.push(0);
+        }
+        push(&mut bytes, 80);
+        for offset in offsets {
+            push(&mut bytes, offset);
+        }
+        bytes.extend(&table);
+
+        if !extended.is_empty() {
+            if bytes.len() % 2 == 1 {
+                bytes.push(0);
+            }
+            let mut values = Vec::new();
+            let mut value_offsets = Vec::new();
+            let mut names = Vec::new();
+            let mut name_offsets = Vec::new();
+            for &(name, value) in extended {
+                value_offsets.push(values.len() as i16);
+                values.extend(value.as_bytes());
+                values.push(0);
+                name_offsets.push(names.len() as i16);
+                names.extend(name.as_bytes());
+                names.push(0);
+            }
+            let count = extended.len() as i16;
+            for value in [0, 0, count, count * 2, (values.len() + names.len()) as i16] {
+                push(&mut bytes, value);
+            }
+            for offset in value_offsets.into_iter().chain(name_offsets) {
+                push(&mut bytes, offset);
+            }
+            bytes.extend(values);
+            bytes.extend(names);
+        }
+
+        bytes
+    }
+
+    #[test]
+    fn test_parse() {
+        let bytes = compile(
+            &[(10, "\x1B[%i%p1%d;%p2%dH"), (13, "\x1B[?25l$<5>")],
+            &[("Smulx", "\x1B[4:%p1%dm"), ("Tc", "")],
+        );
+        let info = TermInfo::parse(&bytes).unwrap();
+
+        assert_eq!(info.names(), ["test", "Test terminal"]);
+        assert!(info.flag("am"));
+        assert!(!info.flag("xenl"));
+        assert_eq!(info.number("cols"), Some(80));
+        assert_eq!(info.number("lines"), None);
+        assert_eq!(info.expand("cup", &[4, 9]).as_deref(), Some("\x1B[5;10H"));
+        assert_eq!(info.expand("civis", &[]).as_deref(), Some("\x1B[?25l"));
+        assert_eq!(info.expand("cnorm", &[]), None);
+        assert_eq!(info.expand("Smulx", &[3]).as_deref(), Some("\x1B[4:3m"));
+        assert_eq!(info.string("Tc"), Some(&b""[..]));
+
+        assert!(TermInfo::parse(b"not terminfo").is_err());
+        assert!(TermInfo::parse(&bytes[..20]).is_err());
+    }
+
+    #[test]
+    fn test_expand() {
+        let expand = |string: &str, params: &[i32]| {
+            String::from_utf8(expand(string.as_bytes(), params)).unwrap()
+        };
+
+        // xterm-256color setaf
+        let setaf = "\x1B[%?%p1%{8}%<%t3%p1%d%e%p1%{16}%<%t9%p1%{8}%-%d%e38;5;%p1%d%;m";
+        assert_eq!(expand(setaf, &[1]), "\x1B[31m");
+        assert_eq!(expand(setaf, &[9]), "\x1B[91m");
+        assert_eq!(expand(setaf, &[200]), "\x1B[38;5;200m");
+
+        // linux setaf
+        assert_eq!(expand("\x1B[3%p1%dm", &[2]), "\x1B[32m");
+
+        assert_eq!(expand("%p1%c", &[65]), "A");
+        assert_eq!(expand("%p1%02d|%p1%3d|%p1%:-3d|", &[7]), "07|  7|7  |");
+        assert_eq!(expand("%p1%x %p1%#X", &[255]), "ff 0XFF");
+        assert_eq!(expand("%p1%Pa%ga%ga%+%d", &[21]), "42");
+        assert_eq!(expand("%'a'%c%{10}%p1%*%d", &[3]), "a30");
+        assert_eq!(expand("%?%p1%t1%e%p2%t2%e3%;", &[0, 1]), "2");
+        assert_eq!(expand("100%%", &[]), "100%");
+
+        // an untrusted entry can't overflow or allocate without bound
+        assert_eq!(expand("%i%p1%d", &[i32::MAX]), i32::MIN.to_string());
+        assert_eq!(expand("%p1%99999999999d", &[1]).len(), 256);
+        assert_eq!(expand("%p1%.99999999999d", &[1]).len(), 256);
+    }
+
+    #[test]
+    fn test_commands_consult_terminfo() {
+        // The Linux console also switches the cursor shape
+        let bytes = compile(&[(13, "\x1B[?25l\x1B[?1c")], &[]);
+        set(Some(TermInfo::parse(&bytes).unwrap()));
+
+        assert_eq!(Hide.ansi_string(), "\x1B[?25l\x1B[?1c");
+        // not in the entry
+        assert_eq!(Show.ansi_string(), "\x1B[?25h");
+
+        set(None);
+        assert_eq!(Hide.ansi_string(), "\x1B[?25l");
+    }
+}
@@ -9,7 +9,37 @@ use std::os::unix::io::AsRawFd;
 use std::os::windows::io::AsRawHandle;
 
 #[cfg(windows)]
-use winapi::um::consoleapi::GetConsoleMode;
+use winapi::um::{
+    consoleapi::GetConsoleMode,
+    fileapi::GetFileType,
+    winbase::{FILE_TYPE_CHAR, FILE_TYPE_DISK, FILE_TYPE_PIPE},
+};
+
+/// What a stream is connected to, see [`IsTty::tty_kind`](trait.IsTty.html#method.tty_kind).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TtyKind {
+    /// A terminal: a tty on UNIX, a console on Windows.
+    Terminal,
+    /// A MSYS2 or Cygwin pty, as used by mintty (Git Bash), on Windows. It's a terminal, but
+    /// presents itself as a named pipe, so [`is_tty`](trait.IsTty.html#tymethod.is_tty) returns
+    /// `false` for it.
+    MsysPty,
+    /// A pipe or socket, e.g. the output is piped into another program.
+    Pipe,
+    /// A regular file, e.g. the output is redirected to a file.
+    File,
+    /// The null device.
+    Null,
+    /// Anything else, e.g. a device which isn't a terminal, or the stream couldn't be inspected.
+    Other,
+}
+
+impl TtyKind {
+    /// Returns whether the stream is connected to a terminal, including MSYS2 and Cygwin ptys.
+    pub fn is_terminal(self) -> bool {
+        matches!(self, TtyKind::Terminal | TtyKind::MsysPty)
+    }
+}
 
 /// Adds the `is_tty` method to types that might represent a terminal
 ///
@@ -21,7 +51,30 @@ use winapi::um::consoleapi::GetConsoleMode;
 /// ```
 pub trait IsTty {
     /// Returns true when an instance is a terminal teletype, otherwise false.
+    ///
+    /// MSYS2 and Cygwin ptys aren't terminals according to this method, use
+    /// [`tty_kind`](#method.tty_kind) to recognize them.
     fn is_tty(&self) -> bool;
+
+    /// Returns what the instance is connected to.
+    ///
+    /// ```rust
+    /// use std::io::stdout;
+    /// use crossterm::tty::{IsTty, TtyKind};
+    ///
+    /// match stdout().tty_kind() {
+    ///     TtyKind::Pipe | TtyKind::File => println!("the output is redirected"),
+    ///     kind if kind.is_terminal() => println!("the output is shown in a terminal"),
+    ///     _ => {}
+    /// }
+    /// ```
+    fn tty_kind(&self) -> TtyKind {
+        if self.is_tty() {
+            TtyKind::Terminal
+        } else {
+            TtyKind::Other
+        }
+    }
 }
 
 /// On UNIX, the `isatty()` function returns true if a file
@@ -32,6 +85,31 @@ impl<S: AsRawFd> IsTty for S {
         let fd = self.as_raw_fd();
         unsafe { libc::isatty(fd) == 1 }
     }
+
+    fn tty_kind(&self) -> TtyKind {
+        let fd = self.as_raw_fd();
+        // SAFETY: `stat` is plain old data, and only read if `fstat` filled it.
+        let mut stat: libc::stat = unsafe { std::mem::zeroed() };
+        if unsafe { libc::fstat(fd, &mut stat) } != 0 {
+            return TtyKind::Other;
+        }
+
+        match stat.st_mode & libc::S_IFMT {
+            libc::S_IFIFO | libc::S_IFSOCK => TtyKind::Pipe,
+            libc::S_IFREG => TtyKind::File,
+            libc::S_IFCHR if self.is_tty() => TtyKind::Terminal,
+            libc::S_IFCHR if is_dev_null(&stat) => TtyKind::Null,
+            _ => TtyKind::Other,
+        }
+    }
+}
+
+#[cfg(unix)]
+fn is_dev_null(stat: &libc::stat) -> bool {
+    // SAFETY: as above.
+    let mut null: libc::stat = unsafe { std::mem::zeroed() };
+    let path = b"/dev/null\0";
+    unsafe { libc::stat(path.as_ptr().cast(), &mut null) == 0 && null.st_rdev == stat.st_rdev }
 }
 
 /// On windows, `GetConsoleMode` will return true if we are in a terminal.
@@ -43,4 +121,42 @@ impl<S: AsRawHandle> IsTty for S {
         let ok = unsafe { GetConsoleMode(self.as_raw_handle() as *mut _, &mut mode) };
         ok == 1
     }
+
+    fn tty_kind(&self) -> TtyKind {
+        if self.is_tty() {
+            return TtyKind::Terminal;
+        }
+
+        let handle = self.as_raw_handle() as *mut _;
+        match unsafe { GetFileType(handle) } {
+            FILE_TYPE_PIPE if crate::ansi_support::is_msys_pty_handle(handle) => TtyKind::MsysPty,
+            FILE_TYPE_PIPE => TtyKind::Pipe,
+            FILE_TYPE_DISK => TtyKind::File,
+            // a character device which isn't a console is most likely `NUL`
+            FILE_TYPE_CHAR => TtyKind::Null,
+            _ => TtyKind::Other,
+        }
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use std::fs::File;
+
+    use super::{IsTty, TtyKind};
+
+    #[test]
+    fn test_tty_kind() {
+        assert_eq!(File::open("/dev/null").unwrap().tty_kind(), TtyKind::Null);
+        assert_eq!(File::open("Cargo.toml").unwrap().tty_kind(), TtyKind::File);
+
+        let mut fds = [0; 2];
+        assert_eq!(unsafe { libc::pipe(fds.as_mut_ptr()) }, 0);
+        let (reader, writer) = unsafe {
+            use std::os::unix::io::FromRawFd;
+            (File::from_raw_fd(fds[0]), File::from_raw_fd(fds[1]))
+        };
+        assert_eq!(reader.tty_kind(), TtyKind::Pipe);
+        assert!(!writer.tty_kind().is_terminal());
+    }
 }